@@ -58,15 +58,21 @@ fn new_node(tokio_handle: Handle) -> node_cli::service::NewFullBase {
 		transaction_pool: TransactionPoolOptions {
 			ready: PoolLimit { count: 100_000, total_bytes: 100 * 1024 * 1024 },
 			future: PoolLimit { count: 100_000, total_bytes: 100 * 1024 * 1024 },
+			ready_per_sender: None,
+			future_per_sender: None,
 			reject_future_transactions: false,
 			ban_time: Duration::from_secs(30 * 60),
+			journal: None,
 		},
 		network: network_config,
 		keystore: KeystoreConfig::InMemory,
 		database: DatabaseSource::RocksDb { path: root.join("db"), cache_size: 128 },
 		trie_cache_maximum_size: Some(64 * 1024 * 1024),
+		trie_cache_adaptive: false,
 		state_pruning: Some(PruningMode::ArchiveAll),
 		blocks_pruning: BlocksPruning::KeepAll,
+		justifications_pruning: None,
+		transactions_pruning: None,
 		chain_spec: spec,
 		wasm_method: Default::default(),
 		rpc_addr: None,
@@ -77,6 +83,7 @@ fn new_node(tokio_handle: Handle) -> node_cli::service::NewFullBase {
 		rpc_max_response_size: Default::default(),
 		rpc_id_provider: Default::default(),
 		rpc_max_subs_per_conn: Default::default(),
+		rpc_call_budget: Default::default(),
 		rpc_port: 9944,
 		prometheus_config: None,
 		telemetry_endpoints: None,
@@ -89,11 +96,13 @@ fn new_node(tokio_handle: Handle) -> node_cli::service::NewFullBase {
 		tracing_receiver: Default::default(),
 		max_runtime_instances: 8,
 		runtime_cache_size: 2,
+		runtime_artifact_cache_size: 0,
 		announce_block: true,
 		data_path: base_path.path().into(),
 		base_path,
 		informant_output_format: Default::default(),
 		wasm_runtime_overrides: None,
+		runtime_profiler: None,
 	};
 
 	node_cli::service::new_full_base(config, false, |_, _| ()).expect("Creates node")
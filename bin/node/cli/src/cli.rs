@@ -90,6 +90,9 @@ pub enum Subcommand {
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
+	/// Import a state streamed by `export-state --stream`.
+	ImportState(sc_cli::ImportStateCmd),
+
 	/// Remove the whole chain.
 	PurgeChain(sc_cli::PurgeChainCmd),
 
@@ -98,4 +101,14 @@ pub enum Subcommand {
 
 	/// Db meta columns information.
 	ChainInfo(sc_cli::ChainInfoCmd),
+
+	/// Database statistics and maintenance utilities.
+	#[command(subcommand)]
+	Db(sc_cli::DbSubcommand),
+
+	/// Compile a runtime ahead of its enactment.
+	PrecompileRuntime(sc_cli::PrecompileRuntimeCmd),
+
+	/// Check that the node provides the host functions a runtime imports.
+	CheckRuntime(sc_cli::CheckRuntimeCmd),
 }
@@ -27,6 +27,7 @@ use kitchensink_runtime::{ExistentialDeposit, RuntimeApi};
 use node_executor::ExecutorDispatch;
 use node_primitives::Block;
 use sc_cli::{Result, SubstrateCli};
+use sc_executor::{sp_wasm_interface::ExtendedHostFunctions, NativeExecutionDispatch};
 use sc_service::PartialComponents;
 use sp_keyring::Sr25519Keyring;
 
@@ -196,6 +197,22 @@ pub fn run() -> Result<()> {
 				Ok((cmd.run(client, config.chain_spec), task_manager))
 			})
 		},
+		Some(Subcommand::ImportState(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|mut config| {
+				// A genesis state is imported into an empty database, so the genesis of the chain
+				// spec must not be written first.
+				let backend = if cmd.genesis {
+					sc_service::new_db_backend(config.db_config())?
+				} else {
+					// The state of a later block is imported like after a warp sync, into a
+					// database holding the genesis block of the chain spec but not its state.
+					config.network.sync_mode = sc_service::config::SyncMode::Warp;
+					new_partial(&config)?.backend
+				};
+				cmd.run::<Block>(&backend)
+			})
+		},
 		Some(Subcommand::ImportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -230,5 +247,14 @@ pub fn run() -> Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run::<Block>(&config))
 		},
+		Some(Subcommand::Db(cmd)) => cmd.run::<Block, _>(&cli),
+		Some(Subcommand::PrecompileRuntime(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run::<Block>(&config))
+		},
+		Some(Subcommand::CheckRuntime(cmd)) => cmd.run::<ExtendedHostFunctions<
+			sp_io::SubstrateHostFunctions,
+			<ExecutorDispatch as NativeExecutionDispatch>::ExtendHostFunctions,
+		>>(),
 	}
 }
@@ -105,6 +105,7 @@ pub fn create_extrinsic(
 		pallet_asset_conversion_tx_payment::ChargeAssetTxPayment::<kitchensink_runtime::Runtime>::from(
 			tip, None,
 		),
+		frame_metadata_hash_extension::CheckMetadataHash::new(false),
 	);
 
 	let raw_payload = kitchensink_runtime::SignedPayload::from_raw(
@@ -119,6 +120,7 @@ pub fn create_extrinsic(
 			(),
 			(),
 			(),
+			None,
 		),
 	);
 	let signature = raw_payload.using_encoded(|e| sender.sign(e));
@@ -842,6 +844,8 @@ mod tests {
 				let check_weight = frame_system::CheckWeight::new();
 				let tx_payment =
 					pallet_asset_conversion_tx_payment::ChargeAssetTxPayment::from(0, None);
+				let check_metadata_hash =
+					frame_metadata_hash_extension::CheckMetadataHash::new(false);
 				let extra = (
 					check_non_zero_sender,
 					check_spec_version,
@@ -851,11 +855,22 @@ mod tests {
 					check_nonce,
 					check_weight,
 					tx_payment,
+					check_metadata_hash,
 				);
 				let raw_payload = SignedPayload::from_raw(
 					function,
 					extra,
-					((), spec_version, transaction_version, genesis_hash, genesis_hash, (), (), ()),
+					(
+						(),
+						spec_version,
+						transaction_version,
+						genesis_hash,
+						genesis_hash,
+						(),
+						(),
+						(),
+						None,
+					),
 				);
 				let signature = raw_payload.using_encoded(|payload| signer.sign(payload));
 				let (function, extra, _) = raw_payload.deconstruct();
@@ -18,10 +18,14 @@
 fn main() {
 	#[cfg(feature = "std")]
 	{
-		substrate_wasm_builder::WasmBuilder::new()
+		let builder = substrate_wasm_builder::WasmBuilder::new()
 			.with_current_project()
 			.export_heap_base()
-			.import_memory()
-			.build();
+			.import_memory();
+
+		#[cfg(feature = "metadata-hash")]
+		let builder = builder.enable_metadata_hash("Test", 14);
+
+		builder.build();
 	}
 }
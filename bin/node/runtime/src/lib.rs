@@ -23,6 +23,7 @@
 #![recursion_limit = "1024"]
 
 use codec::{Decode, Encode, MaxEncodedLen};
+use dynamic_params::{RuntimeParameters, RuntimeParametersKey};
 use frame_election_provider_support::{
 	bounds::{ElectionBounds, ElectionBoundsBuilder},
 	onchain, BalancingConfig, ElectionDataProvider, SequentialPhragmen, VoteWeight,
@@ -38,8 +39,9 @@ use frame_support::{
 		fungible::{Balanced, Credit, ItemOf},
 		tokens::{nonfungibles_v2::Inspect, GetSalary, PayFromAccount},
 		AsEnsureOriginWithArg, ConstBool, ConstU128, ConstU16, ConstU32, Contains, Currency,
-		EitherOfDiverse, EqualPrivilegeOnly, Imbalance, InsideBoth, InstanceFilter,
-		KeyOwnerProofSystem, LockIdentifier, Nothing, OnUnbalanced, WithdrawReasons,
+		EitherOfDiverse, EnsureOriginWithArg, EqualPrivilegeOnly, Imbalance, InsideBoth,
+		InstanceFilter, KeyOwnerProofSystem, LockIdentifier, Nothing, OnUnbalanced,
+		WithdrawReasons,
 	},
 	weights::{
 		constants::{
@@ -281,12 +283,13 @@ impl pallet_safe_mode::Config for Runtime {
 }
 
 impl frame_system::Config for Runtime {
-	type BaseCallFilter = InsideBoth<SafeMode, TxPause>;
+	type BaseCallFilter = InsideBoth<SafeMode, InsideBoth<TxPause, MultiBlockMigrations>>;
 	type BlockWeights = RuntimeBlockWeights;
 	type BlockLength = RuntimeBlockLength;
 	type DbWeight = RocksDbWeight;
 	type RuntimeOrigin = RuntimeOrigin;
 	type RuntimeCall = RuntimeCall;
+	type RuntimeTask = RuntimeTask;
 	type Nonce = Nonce;
 	type Hash = Hash;
 	type Hashing = BlakeTwo256;
@@ -448,9 +451,6 @@ impl pallet_glutton::Config for Runtime {
 
 parameter_types! {
 	pub const PreimageMaxSize: u32 = 4096 * 1024;
-	pub const PreimageBaseDeposit: Balance = 1 * DOLLARS;
-	// One cent: $10,000 / MB
-	pub const PreimageByteDeposit: Balance = 1 * CENTS;
 }
 
 impl pallet_preimage::Config for Runtime {
@@ -458,8 +458,57 @@ impl pallet_preimage::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type Currency = Balances;
 	type ManagerOrigin = EnsureRoot<AccountId>;
-	type BaseDeposit = PreimageBaseDeposit;
-	type ByteDeposit = PreimageByteDeposit;
+	type BaseDeposit = dynamic_params::storage::BaseDeposit;
+	type ByteDeposit = dynamic_params::storage::ByteDeposit;
+}
+
+/// Parameters of the runtime which can be changed through `pallet-parameters`.
+#[frame_support::dynamic_params(RuntimeParameters, pallet_parameters::Pallet::<Runtime>)]
+pub mod dynamic_params {
+	use super::*;
+
+	/// Deposits for storing data on chain.
+	#[codec(index = 0)]
+	pub mod storage {
+		/// The base deposit of storing a preimage.
+		#[codec(index = 0)]
+		pub static BaseDeposit: Balance = 1 * DOLLARS;
+
+		/// The deposit per byte of storing a preimage. One cent: $10,000 / MB.
+		#[codec(index = 1)]
+		pub static ByteDeposit: Balance = 1 * CENTS;
+	}
+}
+
+/// The origin which may change each group of dynamic parameters.
+pub struct DynamicParametersManagerOrigin;
+
+impl EnsureOriginWithArg<RuntimeOrigin, RuntimeParametersKey> for DynamicParametersManagerOrigin {
+	type Success = ();
+
+	fn try_origin(
+		origin: RuntimeOrigin,
+		key: &RuntimeParametersKey,
+	) -> Result<Self::Success, RuntimeOrigin> {
+		match key {
+			RuntimeParametersKey::Storage(_) => {
+				frame_system::ensure_root(origin.clone()).map_err(|_| origin)?;
+				Ok(())
+			},
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn try_successful_origin(_key: &RuntimeParametersKey) -> Result<RuntimeOrigin, ()> {
+		Ok(RuntimeOrigin::root())
+	}
+}
+
+impl pallet_parameters::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeParameters = RuntimeParameters;
+	type AdminOrigin = DynamicParametersManagerOrigin;
+	type WeightInfo = pallet_parameters::weights::SubstrateWeight<Runtime>;
 }
 
 parameter_types! {
@@ -1242,6 +1291,33 @@ impl pallet_bounties::Config for Runtime {
 	type ChildBountyManager = ChildBounties;
 }
 
+/// Calls that can be dispatched while multi-block migrations are ongoing or stuck.
+pub struct MultiBlockMigrationsExemptCalls;
+impl Contains<RuntimeCall> for MultiBlockMigrationsExemptCalls {
+	fn contains(call: &RuntimeCall) -> bool {
+		match call {
+			RuntimeCall::System(_) |
+			RuntimeCall::Timestamp(_) |
+			RuntimeCall::MultiBlockMigrations(_) => true,
+			_ => false,
+		}
+	}
+}
+
+parameter_types! {
+	/// Allocate at most half of each block for multi-block migrations.
+	pub MultiBlockMigrationsServiceWeight: Weight = Perbill::from_percent(50) * RuntimeBlockWeights::get().max_block;
+}
+
+impl pallet_migrations::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Migrations = ();
+	type CursorMaxLen = ConstU32<65_536>;
+	type IdentifierMaxLen = ConstU32<256>;
+	type MaxServiceWeight = MultiBlockMigrationsServiceWeight;
+	type ExemptCalls = MultiBlockMigrationsExemptCalls;
+}
+
 parameter_types! {
 	/// Allocate at most 20% of each block for message processing.
 	///
@@ -1373,6 +1449,7 @@ where
 			frame_system::CheckNonce::<Runtime>::from(nonce),
 			frame_system::CheckWeight::<Runtime>::new(),
 			pallet_asset_conversion_tx_payment::ChargeAssetTxPayment::<Runtime>::from(tip, None),
+			frame_metadata_hash_extension::CheckMetadataHash::new(false),
 		);
 		let raw_payload = SignedPayload::new(call, extra)
 			.map_err(|e| {
@@ -2085,6 +2162,8 @@ construct_runtime!(
 		SafeMode: pallet_safe_mode,
 		Statement: pallet_statement,
 		Broker: pallet_broker,
+		MultiBlockMigrations: pallet_migrations,
+		Parameters: pallet_parameters,
 	}
 );
 
@@ -2112,6 +2191,7 @@ pub type SignedExtra = (
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
 	pallet_asset_conversion_tx_payment::ChargeAssetTxPayment<Runtime>,
+	frame_metadata_hash_extension::CheckMetadataHash<Runtime>,
 );
 
 /// Unchecked extrinsic type as expected by this runtime.
@@ -2129,6 +2209,7 @@ pub type Executive = frame_executive::Executive<
 	Runtime,
 	AllPalletsWithSystem,
 	Migrations,
+	MultiBlockMigrations,
 >;
 
 // All migrations executed on runtime upgrade as a nested tuple of types implementing
@@ -2217,6 +2298,7 @@ mod benches {
 		[pallet_whitelist, Whitelist]
 		[pallet_tx_pause, TxPause]
 		[pallet_safe_mode, SafeMode]
+		[pallet_parameters, Parameters]
 	);
 }
 
@@ -2513,6 +2595,21 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_migrations::MigrationsApi<Block> for Runtime {
+		fn status() -> pallet_migrations::MigrationsStatus {
+			MultiBlockMigrations::migrations_status()
+		}
+	}
+
+	impl frame_support::view_functions::runtime_api::RuntimeViewFunction<Block> for Runtime {
+		fn execute_view_function(
+			id: frame_support::view_functions::ViewFunctionId,
+			input: Vec<u8>,
+		) -> Result<Vec<u8>, frame_support::view_functions::ViewFunctionDispatchError> {
+			Runtime::execute_view_function(id, input)
+		}
+	}
+
 	impl pallet_asset_conversion::AssetConversionApi<
 		Block,
 		Balance,
@@ -78,6 +78,7 @@ pub fn signed_extra(nonce: Nonce, extra_fee: Balance) -> SignedExtra {
 		frame_system::CheckNonce::from(nonce),
 		frame_system::CheckWeight::new(),
 		pallet_asset_conversion_tx_payment::ChargeAssetTxPayment::from(extra_fee, None),
+		frame_metadata_hash_extension::CheckMetadataHash::new(false),
 	)
 }
 
@@ -90,8 +91,16 @@ pub fn sign(
 ) -> UncheckedExtrinsic {
 	match xt.signed {
 		Some((signed, extra)) => {
-			let payload =
-				(xt.function, extra.clone(), spec_version, tx_version, genesis_hash, genesis_hash);
+			let payload = (
+				xt.function,
+				extra.clone(),
+				spec_version,
+				tx_version,
+				genesis_hash,
+				genesis_hash,
+				// Signed data of `CheckMetadataHash`.
+				None::<[u8; 32]>,
+			);
 			let key = AccountKeyring::from_account_id(&signed).unwrap();
 			let signature = payload
 				.using_encoded(|b| {
@@ -28,6 +28,8 @@ use std::cell::RefCell;
 use crate::execution_extensions::ExecutionExtensions;
 use sp_api::{HashingFor, ProofRecorder};
 
+pub use sc_executor::CallBudget;
+
 /// Executor Provider
 pub trait ExecutorProvider<Block: BlockT> {
 	/// executor instance
@@ -62,6 +64,22 @@ pub trait CallExecutor<B: BlockT>: RuntimeVersionOf {
 		context: CallContext,
 	) -> Result<Vec<u8>, sp_blockchain::Error>;
 
+	/// Execute a call like [`Self::call`], limiting the resources it may use to `budget`.
+	///
+	/// Returns [`sp_blockchain::Error::CallBudgetExhausted`] if the call was aborted because it
+	/// ran out of its budget. The default implementation ignores the budget.
+	fn call_with_budget(
+		&self,
+		at_hash: B::Hash,
+		method: &str,
+		call_data: &[u8],
+		context: CallContext,
+		budget: CallBudget,
+	) -> Result<Vec<u8>, sp_blockchain::Error> {
+		let _ = budget;
+		self.call(at_hash, method, call_data, context)
+	}
+
 	/// Execute a contextual call on top of state in a block of a given hash.
 	///
 	/// No changes are made.
@@ -238,6 +238,34 @@ pub struct IoInfo {
 	pub state_writes_nodes: u64,
 }
 
+/// Statistics of one of the trie caches.
+///
+/// The counters are accumulated since the cache was created.
+#[derive(Default, Clone, Debug)]
+pub struct TrieCacheUsage {
+	/// Lookups served by a per-block local cache.
+	pub local_hits: u64,
+	/// Lookups served by the shared cache.
+	pub shared_hits: u64,
+	/// Lookups that had to go to the database.
+	pub misses: u64,
+	/// Items evicted from the shared cache.
+	pub evictions: u64,
+	/// Memory used by the shared cache.
+	pub size: MemorySize,
+	/// Memory the shared cache is allowed to use.
+	pub budget: MemorySize,
+}
+
+/// Statistics of the trie cache of the backend.
+#[derive(Default, Clone, Debug)]
+pub struct TrieCacheInfo {
+	/// Statistics of the trie node cache.
+	pub node_cache: TrieCacheUsage,
+	/// Statistics of the trie value cache.
+	pub value_cache: TrieCacheUsage,
+}
+
 /// Usage statistics for running client instance.
 ///
 /// Returning backend determines the scope of these stats,
@@ -249,6 +277,8 @@ pub struct UsageInfo {
 	pub memory: MemoryInfo,
 	/// I/O statistics.
 	pub io: IoInfo,
+	/// Trie cache statistics, `None` if the trie cache is disabled.
+	pub trie_cache: Option<TrieCacheInfo>,
 }
 
 impl fmt::Display for UsageInfo {
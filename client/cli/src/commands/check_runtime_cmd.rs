@@ -0,0 +1,68 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::error;
+use clap::Parser;
+use sc_executor::host_functions::HostFunctionCatalog;
+use sp_wasm_interface::HostFunctions;
+use std::path::PathBuf;
+
+/// The `check-runtime` command used to check that the node provides the host functions a runtime
+/// imports.
+///
+/// Listing every missing host function with the versions the node provides instead gives a clearer
+/// picture than the error of a failing runtime upgrade.
+#[derive(Debug, Clone, Parser)]
+pub struct CheckRuntimeCmd {
+	/// Path to the runtime Wasm blob, compressed or not.
+	#[arg(value_name = "PATH")]
+	pub runtime: PathBuf,
+
+	/// List all host functions the node provides, with their versions and signatures.
+	#[arg(long)]
+	pub list: bool,
+}
+
+impl CheckRuntimeCmd {
+	/// Run the check-runtime command against the host functions `H` of the node.
+	pub fn run<H: HostFunctions>(&self) -> error::Result<()> {
+		let code = std::fs::read(&self.runtime)?;
+		let catalog = HostFunctionCatalog::new::<H>();
+
+		if self.list {
+			println!("The node provides the following host functions:");
+			for function in catalog.functions() {
+				println!("  {}", function);
+			}
+		}
+
+		let report = catalog
+			.check_code(&code)
+			.map_err(|e| error::Error::Input(format!("Invalid runtime: {}", e)))?;
+		print!("{}", report);
+
+		if report.is_compatible() {
+			Ok(())
+		} else {
+			Err(error::Error::Input(format!(
+				"{} is incompatible with the host functions of the node",
+				self.runtime.display(),
+			)))
+		}
+	}
+}
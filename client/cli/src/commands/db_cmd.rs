@@ -0,0 +1,327 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Database inspection and maintenance commands.
+
+use crate::{
+	error,
+	params::{BlockNumberOrHash, DatabaseParams, PruningParams, SharedParams},
+	CliConfiguration, SubstrateCli,
+};
+use clap::Parser;
+use sc_client_api::{backend::Backend as BackendT, blockchain::HeaderBackend};
+use sc_client_db::{
+	maintenance::{compact_database, database_stats, migrate_database},
+	state_migration::{state_migration_report, MigrationLimits, MigrationWeights},
+};
+use sc_service::DatabaseSource;
+use sp_runtime::traits::{Block as BlockT, HashingFor, Header as HeaderT};
+use std::{fmt::Debug, path::PathBuf, str::FromStr};
+
+/// Database utilities for the cli.
+#[derive(Debug, clap::Subcommand)]
+pub enum DbSubcommand {
+	/// Print per column key counts, sizes and reference count distributions of the database.
+	Stats(DbStatsCmd),
+
+	/// Trigger a manual compaction of the database.
+	Compact(DbCompactCmd),
+
+	/// Copy a RocksDB database into a new ParityDb database, or a ParityDb database into a new
+	/// RocksDB database.
+	Migrate(DbMigrateCmd),
+
+	/// Compare the state of a block under the V0 and the V1 trie layout.
+	StateMigration(DbStateMigrationCmd),
+}
+
+impl DbSubcommand {
+	/// Run the db subcommands
+	pub fn run<B: BlockT, C: SubstrateCli>(&self, cli: &C) -> error::Result<()>
+	where
+		<B::Hash as FromStr>::Err: Debug,
+		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
+	{
+		match self {
+			DbSubcommand::Stats(cmd) => {
+				let runner = cli.create_runner(cmd)?;
+				runner.sync_run(|config| cmd.run(config.database))
+			},
+			DbSubcommand::Compact(cmd) => {
+				let runner = cli.create_runner(cmd)?;
+				runner.sync_run(|config| cmd.run(config.database))
+			},
+			DbSubcommand::Migrate(cmd) => {
+				let runner = cli.create_runner(cmd)?;
+				runner.sync_run(|config| cmd.run::<B>(config.database))
+			},
+			DbSubcommand::StateMigration(cmd) => {
+				let runner = cli.create_runner(cmd)?;
+				runner.sync_run(|config| cmd.run::<B>(&config))
+			},
+		}
+	}
+}
+
+/// The `db stats` command used to print statistics of the database columns.
+#[derive(Debug, Clone, Parser)]
+pub struct DbStatsCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl DbStatsCmd {
+	/// Run the stats command
+	pub fn run(&self, database_config: DatabaseSource) -> error::Result<()> {
+		let stats = database_stats(&database_config)?;
+
+		println!("Backend: {}", stats.backend);
+		println!(
+			"{:>3} {:<16} {:>12} {:>16} {:>16}  {}",
+			"col", "name", "keys", "key bytes", "value bytes", "ref counts"
+		);
+		for col in &stats.columns {
+			println!(
+				"{:>3} {:<16} {:>12} {:>16} {:>16}  {}",
+				col.column,
+				col.name(),
+				col.keys,
+				col.key_bytes,
+				col.value_bytes,
+				col.ref_counts.as_ref().map_or_else(|| "-".into(), ToString::to_string),
+			);
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for DbStatsCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// The `db compact` command used to compact the database.
+#[derive(Debug, Clone, Parser)]
+pub struct DbCompactCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl DbCompactCmd {
+	/// Run the compact command
+	pub fn run(&self, database_config: DatabaseSource) -> error::Result<()> {
+		compact_database(&database_config)?;
+		println!("Database compacted.");
+		Ok(())
+	}
+}
+
+impl CliConfiguration for DbCompactCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// The `db migrate` command used to copy a database into a new database that uses the other
+/// backend.
+#[derive(Debug, Clone, Parser)]
+pub struct DbMigrateCmd {
+	/// Path of the database to create.
+	///
+	/// Defaults to the location the node uses for the other backend with the same base path. An
+	/// interrupted migration is resumed when started again with the same target.
+	#[arg(long, value_name = "PATH")]
+	pub target_path: Option<PathBuf>,
+
+	/// Key of an auxiliary entry to copy when migrating a ParityDb database, either as `0x`
+	/// prefixed hex or as UTF-8 text. Can be given multiple times.
+	///
+	/// ParityDb does not store the keys of the auxiliary entries, so only the listed ones are
+	/// migrated. Entries of a RocksDB database are always migrated.
+	#[arg(long = "aux-key", value_name = "KEY", value_parser = parse_aux_key)]
+	pub aux_keys: Vec<Vec<u8>>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl DbMigrateCmd {
+	/// Run the migrate command
+	pub fn run<B: BlockT>(&self, database_config: DatabaseSource) -> error::Result<()> {
+		// `<base>/db/full` is stored as `<base>/paritydb/full` and the other way around.
+		let sibling = |path: &PathBuf, dir| {
+			path.parent()
+				.and_then(|db_dir| Some(db_dir.parent()?.join(dir).join(path.file_name()?)))
+				.ok_or_else(|| error::Error::Input("Cannot derive the target path".into()))
+		};
+		let target = match (&self.target_path, &database_config) {
+			(Some(path), _) => path.clone(),
+			// The same resolution as the one used by the node to open the database.
+			(None, DatabaseSource::Auto { paritydb_path, rocksdb_path, .. }) =>
+				if rocksdb_path.join("CURRENT").exists() {
+					paritydb_path.clone()
+				} else {
+					rocksdb_path.clone()
+				},
+			#[cfg(feature = "rocksdb")]
+			(None, DatabaseSource::RocksDb { path, .. }) => sibling(path, "paritydb")?,
+			(None, DatabaseSource::ParityDb { path }) => sibling(path, "db")?,
+			(None, _) => return Err(error::Error::Input("Missing `--target-path`".into())),
+		};
+
+		println!("Migrating {} database to {:?}", database_config, target);
+		migrate_database::<B>(&database_config, &target, &self.aux_keys)?;
+		println!("Database migrated and verified.");
+		Ok(())
+	}
+}
+
+/// Parse an auxiliary key given as `0x` prefixed hex or as UTF-8 text.
+fn parse_aux_key(key: &str) -> Result<Vec<u8>, String> {
+	if key.starts_with("0x") {
+		array_bytes::hex2bytes(key).map_err(|e| format!("Invalid hex key `{}`: {:?}", key, e))
+	} else {
+		Ok(key.as_bytes().to_vec())
+	}
+}
+
+impl CliConfiguration for DbMigrateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// The `db state-migration` command used to analyse the migration of the state to the V1 trie
+/// layout.
+///
+/// Nothing is written to the database.
+#[derive(Debug, Clone, Parser)]
+pub struct DbStateMigrationCmd {
+	/// Block hash or number of the state to analyse. Defaults to the best block.
+	#[arg(value_name = "HASH or NUMBER")]
+	pub input: Option<BlockNumberOrHash>,
+
+	/// Predict the automatic migration of `pallet-state-trie-migration` with at most this many
+	/// keys per block.
+	#[arg(long, value_name = "COUNT", requires = "size_limit")]
+	pub item_limit: Option<u32>,
+
+	/// Predict the automatic migration of `pallet-state-trie-migration` with at most this many
+	/// value bytes per block.
+	#[arg(long, value_name = "BYTES", requires = "item_limit")]
+	pub size_limit: Option<u32>,
+
+	/// Weight of a database read of the runtime, in picoseconds.
+	#[arg(long, value_name = "PICOSECONDS", requires = "item_limit")]
+	pub db_read_weight: Option<u64>,
+
+	/// Weight of a database write of the runtime, in picoseconds.
+	#[arg(long, value_name = "PICOSECONDS", requires = "item_limit")]
+	pub db_write_weight: Option<u64>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl DbStateMigrationCmd {
+	/// Run the state-migration command
+	pub fn run<B: BlockT>(&self, config: &sc_service::Configuration) -> error::Result<()>
+	where
+		<B::Hash as FromStr>::Err: Debug,
+		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
+	{
+		let db_config = sc_client_db::DatabaseSettings {
+			trie_cache_maximum_size: config.trie_cache_maximum_size,
+			trie_cache_adaptive: config.trie_cache_adaptive,
+			state_pruning: config.state_pruning.clone(),
+			source: config.database.clone(),
+			blocks_pruning: config.blocks_pruning,
+			justifications_pruning: config.justifications_pruning,
+			transactions_pruning: config.transactions_pruning,
+		};
+		let backend = sc_service::new_db_backend::<B>(db_config)?;
+		let hash = match self.input.as_ref().map(|b| b.parse::<B>()).transpose()? {
+			Some(id) => backend.blockchain().expect_block_hash_from_id(&id)?,
+			None => backend.blockchain().info().best_hash,
+		};
+
+		let dry_run = self.item_limit.zip(self.size_limit).map(|(item, size)| {
+			let mut weights = MigrationWeights::default();
+			weights.db_read = self.db_read_weight.unwrap_or(weights.db_read);
+			weights.db_write = self.db_write_weight.unwrap_or(weights.db_write);
+			(MigrationLimits { item, size }, weights)
+		});
+
+		println!("Analysing the state of block {:?}", hash);
+		let state = backend.state_at(hash)?;
+		let report = state_migration_report::<HashingFor<B>, _>(&state, dry_run)?;
+		print!("{}", report);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for DbStateMigrationCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
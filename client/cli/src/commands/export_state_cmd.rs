@@ -23,9 +23,10 @@ use crate::{
 };
 use clap::Parser;
 use log::info;
-use sc_client_api::{HeaderBackend, StorageProvider, UsageProvider};
+use sc_client_api::{HeaderBackend, ProofProvider, StorageProvider, UsageProvider};
+use sc_service::chain_ops::{StateExportConfig, StateStreamFormat};
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
-use std::{fmt::Debug, io::Write, str::FromStr, sync::Arc};
+use std::{fmt::Debug, fs, io, io::Write, path::PathBuf, str::FromStr, sync::Arc};
 
 /// The `export-state` command used to export the state of a given block into
 /// a chain spec.
@@ -35,6 +36,21 @@ pub struct ExportStateCmd {
 	#[arg(value_name = "HASH or NUMBER")]
 	pub input: Option<BlockNumberOrHash>,
 
+	/// Stream the state into the given file instead of printing a chain spec.
+	///
+	/// The state is written in chunks, with checkpoints proving the exported pairs against the
+	/// state root, and can be imported with `import-state`. Use this for large states.
+	#[arg(long, value_name = "PATH")]
+	pub stream: Option<PathBuf>,
+
+	/// Write the stream as JSON lines instead of binary records.
+	#[arg(long, requires = "stream")]
+	pub jsonl: bool,
+
+	/// The number of key/value pairs after which a checkpoint is written to the stream.
+	#[arg(long, value_name = "COUNT", default_value_t = 100_000)]
+	pub checkpoint_interval: u64,
+
 	#[allow(missing_docs)]
 	#[clap(flatten)]
 	pub shared_params: SharedParams,
@@ -57,7 +73,7 @@ impl ExportStateCmd {
 	) -> error::Result<()>
 	where
 		B: BlockT,
-		C: UsageProvider<B> + StorageProvider<B, BA> + HeaderBackend<B>,
+		C: UsageProvider<B> + StorageProvider<B, BA> + ProofProvider<B> + HeaderBackend<B>,
 		BA: sc_client_api::backend::Backend<B>,
 		<B::Hash as FromStr>::Err: Debug,
 		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
@@ -68,6 +84,23 @@ impl ExportStateCmd {
 			Some(id) => client.expect_block_hash_from_id(&id)?,
 			None => client.usage_info().chain.best_hash,
 		};
+
+		if let Some(path) = &self.stream {
+			let config = StateExportConfig {
+				format: if self.jsonl {
+					StateStreamFormat::Jsonl
+				} else {
+					StateStreamFormat::Binary
+				},
+				checkpoint_interval: self.checkpoint_interval,
+				..Default::default()
+			};
+			let output = io::BufWriter::new(fs::File::create(path)?);
+			let pairs = sc_service::chain_ops::export_state_stream(&*client, hash, output, config)?;
+			info!("Exported {} key/value pairs to {}", pairs, path.display());
+			return Ok(())
+		}
+
 		let raw_state = sc_service::chain_ops::export_raw_state(client, hash)?;
 		input_spec.set_storage(raw_state);
 
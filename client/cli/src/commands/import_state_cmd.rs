@@ -0,0 +1,84 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	error,
+	params::{DatabaseParams, PruningParams, SharedParams},
+	CliConfiguration,
+};
+use clap::Parser;
+use log::info;
+use sc_client_db::Backend;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use std::{fs, io, path::PathBuf};
+
+/// The `import-state` command used to import a state streamed by `export-state --stream`.
+///
+/// Every chunk of the state is verified against the checkpoints of the stream and written to the
+/// database in batches, so the state doesn't need to fit into memory.
+#[derive(Debug, Clone, Parser)]
+pub struct ImportStateCmd {
+	/// Path to the state stream.
+	#[arg(value_name = "PATH")]
+	pub input: PathBuf,
+
+	/// Import the state as the genesis state of a new chain, into an empty database.
+	///
+	/// Otherwise the exported block is imported as the new finalized block of its chain, into a
+	/// database without any state.
+	#[arg(long)]
+	pub genesis: bool,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl ImportStateCmd {
+	/// Run the import-state command
+	pub fn run<B: BlockT>(&self, backend: &Backend<B>) -> error::Result<()> {
+		info!("Importing state from {}...", self.input.display());
+		let input = io::BufReader::new(fs::File::open(&self.input)?);
+		let header = sc_service::chain_ops::import_state_stream(backend, input, self.genesis)?;
+		info!("Imported the state of block #{} ({:?})", header.number(), header.hash());
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ImportStateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
@@ -0,0 +1,93 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	error,
+	params::{ImportParams, SharedParams},
+	CliConfiguration,
+};
+use clap::Parser;
+use sc_service::Configuration;
+use sp_runtime::traits::Block as BlockT;
+use std::{fmt::Debug, path::PathBuf};
+
+/// The `precompile-runtime` command used to compile a runtime ahead of its enactment.
+///
+/// The compiled runtime is stored in the executor cache of the node, which loads it instead of
+/// compiling the runtime once it is enacted.
+#[derive(Debug, Clone, Parser)]
+pub struct PrecompileRuntimeCmd {
+	/// Path to the runtime Wasm blob, exactly as it is going to be stored on chain.
+	#[arg(value_name = "PATH")]
+	pub runtime: PathBuf,
+
+	/// The number of 64KB heap pages the runtime is going to be executed with.
+	///
+	/// Must be set to the on chain `:heappages` value if the chain sets one.
+	#[arg(long, value_name = "COUNT")]
+	pub heap_pages: Option<u64>,
+
+	/// Compile the runtime with call metering.
+	///
+	/// The node only uses such runtimes for the runtime calls of the RPC it limits, i.e. if it
+	/// is started with `--rpc-call-max-duration` or `--rpc-call-max-fuel`. Run the command with
+	/// and without this flag to compile the runtime for both uses.
+	#[arg(long)]
+	pub call_metering: bool,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl PrecompileRuntimeCmd {
+	/// Run the precompile-runtime command
+	pub fn run<B: BlockT>(&self, config: &Configuration) -> error::Result<()> {
+		let code = std::fs::read(&self.runtime)?;
+
+		let start = std::time::Instant::now();
+		let path = sc_service::chain_ops::precompile_runtime::<B>(
+			config,
+			&code,
+			self.heap_pages,
+			self.call_metering,
+		)?;
+		println!(
+			"Compiled {} into {} in {} ms.",
+			self.runtime.display(),
+			path.display(),
+			start.elapsed().as_millis(),
+		);
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for PrecompileRuntimeCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
@@ -30,11 +30,14 @@ use crate::{
 use clap::Parser;
 use regex::Regex;
 use sc_service::{
-	config::{BasePath, PrometheusConfig, TransactionPoolOptions},
+	config::{BasePath, CallBudget, PrometheusConfig, TransactionPoolOptions},
 	ChainSpec, Role,
 };
 use sc_telemetry::TelemetryEndpoints;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::{
+	net::{IpAddr, Ipv4Addr, SocketAddr},
+	time::Duration,
+};
 
 /// The `run` command used to run a node.
 #[derive(Debug, Clone, Parser)]
@@ -91,6 +94,22 @@ pub struct RunCmd {
 	#[arg(long, default_value_t = RPC_DEFAULT_MAX_SUBS_PER_CONN)]
 	pub rpc_max_subscriptions_per_connection: u32,
 
+	/// Abort the runtime calls of `state_call` and `chainHead_unstable_call` that take longer
+	/// than the given number of milliseconds.
+	///
+	/// Setting a limit keeps a second copy of the runtime compiled with call metering, which
+	/// executes these calls slightly slower.
+	#[arg(long, value_name = "MS")]
+	pub rpc_call_max_duration: Option<u64>,
+
+	/// Abort the runtime calls of `state_call` and `chainHead_unstable_call` that consume more
+	/// than the given amount of fuel, roughly the number of executed wasm instructions.
+	///
+	/// Setting a limit keeps a second copy of the runtime compiled with call metering, which
+	/// executes these calls slightly slower.
+	#[arg(long, value_name = "FUEL")]
+	pub rpc_call_max_fuel: Option<u64>,
+
 	/// Specify JSON-RPC server TCP port.
 	#[arg(long, value_name = "PORT")]
 	pub rpc_port: Option<u16>,
@@ -365,6 +384,13 @@ impl CliConfiguration for RunCmd {
 		Ok(self.rpc_max_subscriptions_per_connection)
 	}
 
+	fn rpc_call_budget(&self) -> Result<CallBudget> {
+		Ok(CallBudget {
+			max_duration: self.rpc_call_max_duration.map(Duration::from_millis),
+			max_fuel: self.rpc_call_max_fuel,
+		})
+	}
+
 	fn transaction_pool(&self, is_dev: bool) -> Result<TransactionPoolOptions> {
 		Ok(self.pool_config.transaction_pool(is_dev))
 	}
@@ -377,6 +403,10 @@ impl CliConfiguration for RunCmd {
 		Ok(self.runtime_params.runtime_cache_size)
 	}
 
+	fn runtime_artifact_cache_size(&self) -> Result<u64> {
+		Ok(self.runtime_params.runtime_artifact_cache_size.saturating_mul(1024 * 1024))
+	}
+
 	fn base_path(&self) -> Result<Option<BasePath>> {
 		Ok(if self.tmp {
 			Some(BasePath::new_temp_dir()?)
@@ -26,14 +26,14 @@ use log::warn;
 use names::{Generator, Name};
 use sc_service::{
 	config::{
-		BasePath, Configuration, DatabaseSource, KeystoreConfig, NetworkConfiguration,
+		BasePath, CallBudget, Configuration, DatabaseSource, KeystoreConfig, NetworkConfiguration,
 		NodeKeyConfig, OffchainWorkerConfig, PrometheusConfig, PruningMode, Role, RpcMethods,
-		TelemetryEndpoints, TransactionPoolOptions, WasmExecutionMethod,
+		TelemetryEndpoints, TransactionPoolOptions, WasmExecutionMethod, WasmtimeProfiler,
 	},
 	BlocksPruning, ChainSpec, TracingReceiver,
 };
 use sc_tracing::logging::LoggerBuilder;
-use std::{net::SocketAddr, path::PathBuf};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
 
 /// The maximum number of characters for a node name.
 pub(crate) const NODE_NAME_MAX_LENGTH: usize = 64;
@@ -241,6 +241,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(self.import_params().map(|x| x.trie_cache_maximum_size()).unwrap_or_default())
 	}
 
+	/// Whether the trie cache adapts its split between nodes and values to the workload.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise its `false`.
+	fn trie_cache_adaptive(&self) -> Result<bool> {
+		Ok(self.import_params().map_or(false, |x| x.trie_cache_adaptive))
+	}
+
 	/// Get the state pruning mode.
 	///
 	/// By default this is retrieved from `PruningMode` if it is available. Otherwise its
@@ -261,6 +268,22 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			.unwrap_or_else(|| Ok(BlocksPruning::KeepFinalized))
 	}
 
+	/// Get the justifications pruning mode.
+	///
+	/// By default this is retrieved from `justifications_pruning` if it is available. Otherwise
+	/// justifications are pruned together with the block bodies.
+	fn justifications_pruning(&self) -> Result<Option<BlocksPruning>> {
+		self.pruning_params().map(|x| x.justifications_pruning()).unwrap_or(Ok(None))
+	}
+
+	/// Get the indexed transactions pruning mode.
+	///
+	/// By default this is retrieved from `transactions_pruning` if it is available. Otherwise
+	/// indexed transactions are pruned together with the block bodies.
+	fn transactions_pruning(&self) -> Result<Option<BlocksPruning>> {
+		self.pruning_params().map(|x| x.transactions_pruning()).unwrap_or(Ok(None))
+	}
+
 	/// Get the chain ID (string).
 	///
 	/// By default this is retrieved from `SharedParams`.
@@ -290,6 +313,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		self.import_params().map(|x| x.wasm_runtime_overrides()).unwrap_or_default()
 	}
 
+	/// Get the profiler the runtimes should be instrumented for.
+	///
+	/// By default this is `None`.
+	fn runtime_profiler(&self) -> Option<Arc<WasmtimeProfiler>> {
+		None
+	}
+
 	/// Get the RPC address.
 	fn rpc_addr(&self, _default_listen_port: u16) -> Result<Option<SocketAddr>> {
 		Ok(None)
@@ -330,6 +360,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(RPC_DEFAULT_MAX_SUBS_PER_CONN)
 	}
 
+	/// Get the limits of the runtime calls made by the RPC.
+	///
+	/// By default the calls are unlimited.
+	fn rpc_call_budget(&self) -> Result<CallBudget> {
+		Ok(Default::default())
+	}
+
 	/// Get the prometheus configuration (`None` if disabled)
 	///
 	/// By default this is `None`.
@@ -428,6 +465,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(2)
 	}
 
+	/// Get the maximum size in bytes of the compiled runtimes cached on disk.
+	///
+	/// By default this is 1 GiB.
+	fn runtime_artifact_cache_size(&self) -> Result<u64> {
+		Ok(1024 * 1024 * 1024)
+	}
+
 	/// Activate or not the automatic announcing of blocks after import
 	///
 	/// By default this is `false`.
@@ -468,6 +512,7 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		let keystore = self.keystore_config(&config_dir)?;
 		let telemetry_endpoints = self.telemetry_endpoints(&chain_spec)?;
 		let runtime_cache_size = self.runtime_cache_size()?;
+		let runtime_artifact_cache_size = self.runtime_artifact_cache_size()?;
 
 		Ok(Configuration {
 			impl_name: C::impl_name(),
@@ -488,10 +533,14 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			database: self.database_config(&config_dir, database_cache_size, database)?,
 			data_path: config_dir,
 			trie_cache_maximum_size: self.trie_cache_maximum_size()?,
+			trie_cache_adaptive: self.trie_cache_adaptive()?,
 			state_pruning: self.state_pruning()?,
 			blocks_pruning: self.blocks_pruning()?,
+			justifications_pruning: self.justifications_pruning()?,
+			transactions_pruning: self.transactions_pruning()?,
 			wasm_method: self.wasm_method()?,
 			wasm_runtime_overrides: self.wasm_runtime_overrides(),
+			runtime_profiler: self.runtime_profiler(),
 			rpc_addr: self.rpc_addr(DCV::rpc_listen_port())?,
 			rpc_methods: self.rpc_methods()?,
 			rpc_max_connections: self.rpc_max_connections()?,
@@ -500,6 +549,7 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			rpc_max_response_size: self.rpc_max_response_size()?,
 			rpc_id_provider: None,
 			rpc_max_subs_per_conn: self.rpc_max_subscriptions_per_connection()?,
+			rpc_call_budget: self.rpc_call_budget()?,
 			rpc_port: DCV::rpc_listen_port(),
 			prometheus_config: self
 				.prometheus_config(DCV::prometheus_listen_port(), &chain_spec)?,
@@ -518,6 +568,7 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			base_path,
 			informant_output_format: Default::default(),
 			runtime_cache_size,
+			runtime_artifact_cache_size,
 		})
 	}
 
@@ -78,6 +78,14 @@ pub struct ImportParams {
 	#[arg(long, value_name = "Bytes", default_value_t = 67108864)]
 	pub trie_cache_size: usize,
 
+	/// Split the trie cache size between trie nodes and values based on the observed workload.
+	///
+	/// By default 80% of the cache is used for trie nodes. With this flag the split is
+	/// adjusted over time towards the cache that misses more. The hit, miss and eviction
+	/// counters of both caches are exported as Prometheus metrics either way.
+	#[arg(long)]
+	pub trie_cache_adaptive: bool,
+
 	/// DEPRECATED
 	/// Switch to `--trie-cache-size`.
 	#[arg(long)]
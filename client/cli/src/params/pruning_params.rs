@@ -18,7 +18,9 @@
 
 use crate::error;
 use clap::Args;
+use sc_client_db::Constraints;
 use sc_service::{BlocksPruning, PruningMode};
+use std::time::Duration;
 
 /// Parameters to define the pruning mode
 #[derive(Debug, Clone, Args)]
@@ -33,10 +35,24 @@ pub struct PruningParams {
 	///  - archive: Keep the state of all blocks.
 	///  - 'archive-canonical' Keep only the state of finalized blocks.
 	///  - number Keep the state of the last number of finalized blocks.
+	///  - age, e.g. '7d' or '12h': Keep the state of the finalized blocks produced within the
+	///  given time of the last finalized block. Supported units are s, m, h, d and w.
+	///  - size, e.g. '200GiB': Keep the state of the last finalized blocks whose inserted state
+	///  fits into the given size. Supported units are B, KiB, MiB, GiB and TiB.
+	/// A number, an age and a size can be combined with commas, e.g. '1000,7d,200GiB', in
+	/// which case state is pruned as soon as any of them is exceeded.
 	/// [default: 256]
 	#[arg(alias = "pruning", long, value_name = "PRUNING_MODE")]
 	pub state_pruning: Option<DatabasePruningMode>,
 
+	/// Specify the hex encoded storage key of the block timestamp, used to enforce an age
+	/// given to `--state-pruning`.
+	/// The value stored under the key must be the SCALE encoded timestamp of the block in
+	/// milliseconds.
+	/// [default: the key of FRAME's `Timestamp::Now`]
+	#[arg(long, value_name = "KEY")]
+	pub state_pruning_timestamp_key: Option<String>,
+
 	/// Specify the blocks pruning mode.
 	/// This mode specifies when the block's body (including justifications)
 	/// should be pruned (ie, removed) from the database.
@@ -52,25 +68,82 @@ pub struct PruningParams {
 		default_value = "archive-canonical"
 	)]
 	pub blocks_pruning: DatabasePruningMode,
+
+	/// Specify the justifications pruning mode.
+	/// This mode specifies when the block's justifications should be pruned (ie, removed) from
+	/// the database, independently of the block's body.
+	/// Possible values are the same as for `--blocks-pruning`.
+	/// [default: same as `--blocks-pruning`]
+	#[arg(long, value_name = "PRUNING_MODE")]
+	pub justifications_pruning: Option<DatabasePruningMode>,
+
+	/// Specify the indexed transactions pruning mode.
+	/// This mode specifies when the block's indexed transactions should be pruned (ie, removed)
+	/// from the database. Indexed transactions are never kept for longer than the block's body,
+	/// and the body of a block can not be retrieved anymore once its indexed transactions are
+	/// pruned.
+	/// Possible values are the same as for `--blocks-pruning`.
+	/// [default: same as `--blocks-pruning`]
+	#[arg(long, value_name = "PRUNING_MODE")]
+	pub transactions_pruning: Option<DatabasePruningMode>,
 }
 
 impl PruningParams {
 	/// Get the pruning value from the parameters
 	pub fn state_pruning(&self) -> error::Result<Option<PruningMode>> {
-		Ok(self.state_pruning.map(|v| v.into()))
+		let state_pruning = self.state_pruning.map(|v| v.into());
+		match (state_pruning, &self.state_pruning_timestamp_key) {
+			(state_pruning, None) => Ok(state_pruning),
+			(Some(PruningMode::Constrained(mut constraints)), Some(key))
+				if constraints.max_age.is_some() =>
+			{
+				let key = array_bytes::hex2bytes(key).map_err(|_| {
+					error::Error::Input(format!("Invalid state pruning timestamp key: '{key}'"))
+				})?;
+				constraints.timestamp_key = Some(key);
+				Ok(Some(PruningMode::Constrained(constraints)))
+			},
+			(_, Some(_)) => Err(error::Error::Input(
+				"`--state-pruning-timestamp-key` requires an age given to `--state-pruning`".into(),
+			)),
+		}
 	}
 
 	/// Get the block pruning value from the parameters
 	pub fn blocks_pruning(&self) -> error::Result<BlocksPruning> {
-		Ok(self.blocks_pruning.into())
+		block_data_pruning(self.blocks_pruning)
+	}
+
+	/// Get the justifications pruning value from the parameters
+	pub fn justifications_pruning(&self) -> error::Result<Option<BlocksPruning>> {
+		self.justifications_pruning.map(block_data_pruning).transpose()
+	}
+
+	/// Get the indexed transactions pruning value from the parameters
+	pub fn transactions_pruning(&self) -> error::Result<Option<BlocksPruning>> {
+		self.transactions_pruning.map(block_data_pruning).transpose()
 	}
 }
 
+/// Convert the pruning mode of some block data, which only supports block constraints.
+fn block_data_pruning(mode: DatabasePruningMode) -> error::Result<BlocksPruning> {
+	if let DatabasePruningMode::Constrained { max_age, max_bytes, .. } = mode {
+		if max_age.is_some() || max_bytes.is_some() {
+			return Err(error::Error::Input(
+				"Age and size constraints are only supported by `--state-pruning`".into(),
+			))
+		}
+	}
+
+	Ok(mode.into())
+}
+
 /// Specifies the pruning mode of the database.
 ///
-/// This specifies when the block's data (either state via `--state-pruning`
-/// or body via `--blocks-pruning`) should be pruned (ie, removed) from
-/// the database.
+/// This specifies when the block's data (either state via `--state-pruning`,
+/// body via `--blocks-pruning`, justifications via `--justifications-pruning` or
+/// indexed transactions via `--transactions-pruning`) should be pruned (ie, removed)
+/// from the database.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DatabasePruningMode {
 	/// Keep the data of all blocks.
@@ -79,6 +152,17 @@ pub enum DatabasePruningMode {
 	ArchiveCanonical,
 	/// Keep the data of the last number of finalized blocks.
 	Custom(u32),
+	/// Keep the data of the last finalized blocks within the given constraints.
+	///
+	/// Age and size constraints are only supported for the state.
+	Constrained {
+		/// Maximum number of finalized blocks.
+		max_blocks: Option<u32>,
+		/// Maximum age of the oldest finalized block relative to the newest one.
+		max_age: Option<Duration>,
+		/// Maximum size of the state inserted by the finalized blocks.
+		max_bytes: Option<u64>,
+	},
 }
 
 impl std::str::FromStr for DatabasePruningMode {
@@ -88,12 +172,70 @@ impl std::str::FromStr for DatabasePruningMode {
 		match input {
 			"archive" => Ok(Self::Archive),
 			"archive-canonical" => Ok(Self::ArchiveCanonical),
-			bc => bc
+			bc if bc.bytes().all(|b| b.is_ascii_digit()) => bc
 				.parse()
 				.map_err(|_| "Invalid pruning mode specified".to_string())
 				.map(Self::Custom),
+			constraints => parse_constraints(constraints),
+		}
+	}
+}
+
+/// Parse a comma separated list of block, age and size constraints.
+fn parse_constraints(input: &str) -> Result<DatabasePruningMode, String> {
+	let mut max_blocks = None;
+	let mut max_age = None;
+	let mut max_bytes = None;
+
+	for constraint in input.split(',').map(str::trim) {
+		let split = constraint.find(|c: char| !c.is_ascii_digit()).unwrap_or(constraint.len());
+		let (value, unit) = constraint.split_at(split);
+		let value: u64 = value
+			.parse()
+			.map_err(|_| format!("Invalid pruning constraint specified: '{constraint}'"))?;
+
+		let duplicate = match unit.trim() {
+			"" => max_blocks
+				.replace(
+					u32::try_from(value)
+						.map_err(|_| format!("Too many blocks specified: '{constraint}'"))?,
+				)
+				.is_some(),
+			unit @ ("s" | "m" | "h" | "d" | "w") => {
+				let seconds = match unit {
+					"s" => 1,
+					"m" => 60,
+					"h" => 60 * 60,
+					"d" => 24 * 60 * 60,
+					_ => 7 * 24 * 60 * 60,
+				};
+				let seconds = value
+					.checked_mul(seconds)
+					.ok_or_else(|| format!("Too large age specified: '{constraint}'"))?;
+				max_age.replace(Duration::from_secs(seconds)).is_some()
+			},
+			unit @ ("B" | "KiB" | "MiB" | "GiB" | "TiB") => {
+				let shift = match unit {
+					"B" => 0,
+					"KiB" => 10,
+					"MiB" => 20,
+					"GiB" => 30,
+					_ => 40,
+				};
+				let bytes = value
+					.checked_mul(1 << shift)
+					.ok_or_else(|| format!("Too large size specified: '{constraint}'"))?;
+				max_bytes.replace(bytes).is_some()
+			},
+			_ => return Err(format!("Invalid pruning constraint specified: '{constraint}'")),
+		};
+
+		if duplicate {
+			return Err(format!("Pruning constraint specified more than once: '{constraint}'"))
 		}
 	}
+
+	Ok(DatabasePruningMode::Constrained { max_blocks, max_age, max_bytes })
 }
 
 impl Into<PruningMode> for DatabasePruningMode {
@@ -102,6 +244,13 @@ impl Into<PruningMode> for DatabasePruningMode {
 			DatabasePruningMode::Archive => PruningMode::ArchiveAll,
 			DatabasePruningMode::ArchiveCanonical => PruningMode::ArchiveCanonical,
 			DatabasePruningMode::Custom(n) => PruningMode::blocks_pruning(n),
+			DatabasePruningMode::Constrained { max_blocks, max_age, max_bytes } =>
+				PruningMode::Constrained(Constraints {
+					max_blocks,
+					max_age,
+					max_bytes,
+					timestamp_key: None,
+				}),
 		}
 	}
 }
@@ -112,6 +261,72 @@ impl Into<BlocksPruning> for DatabasePruningMode {
 			DatabasePruningMode::Archive => BlocksPruning::KeepAll,
 			DatabasePruningMode::ArchiveCanonical => BlocksPruning::KeepFinalized,
 			DatabasePruningMode::Custom(n) => BlocksPruning::Some(n),
+			// Age and size constraints are rejected by `PruningParams`.
+			DatabasePruningMode::Constrained { max_blocks, .. } =>
+				max_blocks.map_or(BlocksPruning::KeepFinalized, BlocksPruning::Some),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_pruning_modes() {
+		assert_eq!("archive".parse(), Ok(DatabasePruningMode::Archive));
+		assert_eq!("archive-canonical".parse(), Ok(DatabasePruningMode::ArchiveCanonical));
+		assert_eq!("256".parse(), Ok(DatabasePruningMode::Custom(256)));
+		assert_eq!(
+			"7d".parse(),
+			Ok(DatabasePruningMode::Constrained {
+				max_blocks: None,
+				max_age: Some(Duration::from_secs(7 * 24 * 60 * 60)),
+				max_bytes: None,
+			})
+		);
+		assert_eq!(
+			"1000, 12h,200GiB".parse(),
+			Ok(DatabasePruningMode::Constrained {
+				max_blocks: Some(1000),
+				max_age: Some(Duration::from_secs(12 * 60 * 60)),
+				max_bytes: Some(200 << 30),
+			})
+		);
+	}
+
+	#[test]
+	fn block_data_pruning_only_supports_block_constraints() {
+		assert_eq!(block_data_pruning("1000".parse().unwrap()).unwrap(), BlocksPruning::Some(1000));
+		assert_eq!(block_data_pruning("archive".parse().unwrap()).unwrap(), BlocksPruning::KeepAll);
+		assert!(block_data_pruning("1000,7d".parse().unwrap()).is_err());
+		assert!(block_data_pruning("200GiB".parse().unwrap()).is_err());
+	}
+
+	#[test]
+	fn state_pruning_timestamp_key_requires_age_constraint() {
+		let params = |state_pruning: &str, timestamp_key: &str| PruningParams {
+			state_pruning: Some(state_pruning.parse().unwrap()),
+			state_pruning_timestamp_key: Some(timestamp_key.into()),
+			blocks_pruning: DatabasePruningMode::ArchiveCanonical,
+			justifications_pruning: None,
+			transactions_pruning: None,
+		};
+
+		match params("1000,7d", "0x0102").state_pruning().unwrap() {
+			Some(PruningMode::Constrained(constraints)) =>
+				assert_eq!(constraints.timestamp_key, Some(vec![1, 2])),
+			mode => panic!("Unexpected pruning mode: {mode:?}"),
+		}
+		assert!(params("1000", "0x0102").state_pruning().is_err());
+		assert!(params("archive", "0x0102").state_pruning().is_err());
+		assert!(params("7d", "0xzz").state_pruning().is_err());
+	}
+
+	#[test]
+	fn rejects_invalid_constraints() {
+		for input in ["", "7x", "d", "1000,2000", "7d,1h", "-1", "5000000000", "1,,2GiB"] {
+			assert!(input.parse::<DatabasePruningMode>().is_err(), "{input}");
 		}
 	}
 }
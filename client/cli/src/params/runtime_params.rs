@@ -29,6 +29,12 @@ pub struct RuntimeParams {
 	/// Maximum number of different runtimes that can be cached.
 	#[arg(long, default_value_t = 2)]
 	pub runtime_cache_size: u8,
+
+	/// Maximum size in MiB of the compiled runtimes kept on disk and reused after a restart.
+	///
+	/// Set to `0` to disable the on-disk cache of compiled runtimes.
+	#[arg(long, value_name = "MiB", default_value_t = 1024)]
+	pub runtime_artifact_cache_size: u64,
 }
 
 fn parse_max_runtime_instances(s: &str) -> Result<usize, String> {
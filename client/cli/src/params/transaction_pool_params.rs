@@ -17,7 +17,8 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use clap::Args;
-use sc_service::config::TransactionPoolOptions;
+use sc_service::config::{TransactionPoolJournalOptions, TransactionPoolOptions};
+use std::path::PathBuf;
 
 /// Parameters used to create the pool configuration.
 #[derive(Debug, Clone, Args)]
@@ -30,6 +31,30 @@ pub struct TransactionPoolParams {
 	#[arg(long, value_name = "COUNT", default_value_t = 20480)]
 	pub pool_kbytes: usize,
 
+	/// Maximum number of ready transactions of a single sender in the transaction pool.
+	///
+	/// Senders are recognised from the nonce tags of signed transactions, so the first ready
+	/// transaction of a sender is not counted. Unlimited by default.
+	#[arg(long, value_name = "COUNT")]
+	pub pool_sender_limit: Option<usize>,
+
+	/// Maximum number of future transactions of a single sender in the transaction pool.
+	///
+	/// Unlimited by default.
+	#[arg(long, value_name = "COUNT")]
+	pub pool_sender_future_limit: Option<usize>,
+
+	/// Keep the pool transactions in a journal at the given path across node restarts.
+	///
+	/// Journalled transactions are re-validated and re-imported after startup.
+	/// Disabled by default.
+	#[arg(long, value_name = "PATH")]
+	pub pool_journal: Option<PathBuf>,
+
+	/// Maximum number of kilobytes of transactions stored in the pool journal.
+	#[arg(long, value_name = "COUNT", default_value_t = 20480)]
+	pub pool_journal_kbytes: usize,
+
 	/// How long a transaction is banned for, if it is considered invalid. Defaults to 1800s.
 	#[arg(long, value_name = "SECONDS")]
 	pub tx_ban_seconds: Option<u64>,
@@ -49,6 +74,15 @@ impl TransactionPoolParams {
 		opts.future.count = self.pool_limit / factor;
 		opts.future.total_bytes = self.pool_kbytes * 1024 / factor;
 
+		// per-sender limits
+		opts.ready_per_sender = self.pool_sender_limit;
+		opts.future_per_sender = self.pool_sender_future_limit;
+
+		opts.journal = self.pool_journal.clone().map(|path| TransactionPoolJournalOptions {
+			path,
+			max_bytes: self.pool_journal_kbytes * 1024,
+		});
+
 		opts.ban_time = if let Some(ban_seconds) = self.tx_ban_seconds {
 			std::time::Duration::from_secs(ban_seconds)
 		} else if is_dev {
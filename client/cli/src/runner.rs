@@ -249,8 +249,11 @@ mod tests {
 				keystore: sc_service::config::KeystoreConfig::InMemory,
 				database: sc_client_db::DatabaseSource::ParityDb { path: root.clone() },
 				trie_cache_maximum_size: None,
+				trie_cache_adaptive: false,
 				state_pruning: None,
 				blocks_pruning: sc_client_db::BlocksPruning::KeepAll,
+				justifications_pruning: None,
+				transactions_pruning: None,
 				chain_spec: Box::new(GenericChainSpec::from_genesis(
 					"test",
 					"test_id",
@@ -265,6 +268,7 @@ mod tests {
 				)),
 				wasm_method: Default::default(),
 				wasm_runtime_overrides: None,
+				runtime_profiler: None,
 				rpc_addr: None,
 				rpc_max_connections: Default::default(),
 				rpc_cors: None,
@@ -273,6 +277,7 @@ mod tests {
 				rpc_max_response_size: Default::default(),
 				rpc_id_provider: Default::default(),
 				rpc_max_subs_per_conn: Default::default(),
+				rpc_call_budget: Default::default(),
 				rpc_port: 9944,
 				prometheus_config: None,
 				telemetry_endpoints: None,
@@ -289,6 +294,7 @@ mod tests {
 				data_path: root,
 				informant_output_format: Default::default(),
 				runtime_cache_size: 2,
+				runtime_artifact_cache_size: 0,
 			},
 			runtime,
 			Signals::dummy(),
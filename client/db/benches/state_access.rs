@@ -119,9 +119,12 @@ fn create_backend(config: BenchmarkConfig, temp_dir: &TempDir) -> Backend<Block>
 
 	let settings = DatabaseSettings {
 		trie_cache_maximum_size,
+		trie_cache_adaptive: false,
 		state_pruning: Some(PruningMode::ArchiveAll),
 		source: DatabaseSource::ParityDb { path },
 		blocks_pruning: BlocksPruning::KeepAll,
+		justifications_pruning: None,
+		transactions_pruning: None,
 	};
 
 	Backend::new(settings, 100).expect("Creates backend")
@@ -0,0 +1,182 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use rand::{distributions::Uniform, rngs::StdRng, Rng, SeedableRng};
+use sc_client_api::{Backend as _, BlockImportOperation, NewBlockState, StateBackend};
+use sc_client_db::{Backend, BlocksPruning, DatabaseSettings, DatabaseSource, PruningMode};
+use sp_core::{
+	storage::{ChildInfo, StorageChild},
+	Encode, H256,
+};
+use sp_runtime::{
+	testing::{Block as RawBlock, ExtrinsicWrapper, Header},
+	StateVersion, Storage,
+};
+use tempfile::TempDir;
+
+type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+/// The number of child tries in the state.
+const CHILD_TRIES: usize = 64;
+/// The number of keys in every child trie.
+const CHILD_TRIE_KEYS: usize = 2_000;
+/// The number of keys changed per child trie.
+const CHANGED_KEYS: usize = 200;
+
+type Pairs = Vec<(Vec<u8>, Vec<u8>)>;
+
+fn random_pairs(rng: &mut StdRng, count: usize) -> Pairs {
+	(0..count)
+		.map(|_| {
+			let key = (&mut *rng).sample_iter(Uniform::new_inclusive(0, 255)).take(32).collect();
+			let value = (&mut *rng).sample_iter(Uniform::new_inclusive(0, 255)).take(64).collect();
+			(key, value)
+		})
+		.collect()
+}
+
+fn create_backend(trie_cache_maximum_size: Option<usize>, temp_dir: &TempDir) -> Backend<Block> {
+	let settings = DatabaseSettings {
+		trie_cache_maximum_size,
+		trie_cache_adaptive: false,
+		state_pruning: Some(PruningMode::ArchiveAll),
+		source: DatabaseSource::ParityDb { path: temp_dir.path().to_owned() },
+		blocks_pruning: BlocksPruning::KeepAll,
+		justifications_pruning: None,
+		transactions_pruning: None,
+	};
+
+	Backend::new(settings, 100).expect("Creates backend")
+}
+
+/// Writes a genesis block with [`CHILD_TRIES`] child tries and returns its hash.
+fn insert_genesis(db: &Backend<Block>, children: &[(ChildInfo, Pairs)]) -> H256 {
+	let mut op = db.begin_operation().unwrap();
+	let mut header = Header {
+		number: 0,
+		parent_hash: Default::default(),
+		state_root: Default::default(),
+		digest: Default::default(),
+		extrinsics_root: Default::default(),
+	};
+
+	let children_default = children
+		.iter()
+		.map(|(child_info, pairs)| {
+			let data = pairs.iter().cloned().collect();
+			(
+				child_info.storage_key().to_vec(),
+				StorageChild { data, child_info: child_info.clone() },
+			)
+		})
+		.collect();
+
+	header.state_root = op
+		.set_genesis_state(
+			Storage { top: Default::default(), children_default },
+			true,
+			StateVersion::V1,
+		)
+		.unwrap();
+
+	op.set_block_data(header.clone(), Some(vec![]), None, None, NewBlockState::Best)
+		.unwrap();
+
+	db.commit_operation(op).unwrap();
+
+	header.hash()
+}
+
+fn state_root_benchmarks(c: &mut Criterion) {
+	sp_tracing::try_init_simple();
+
+	let mut rng = StdRng::seed_from_u64(353893213);
+	let children = (0..CHILD_TRIES)
+		.map(|i| {
+			let child_info = ChildInfo::new_default(format!("child{}", i).as_bytes());
+			(child_info, random_pairs(&mut rng, CHILD_TRIE_KEYS))
+		})
+		.collect::<Vec<_>>();
+	// Overwrite existing keys and add as many new ones.
+	let changes = children
+		.iter()
+		.map(|(child_info, pairs)| {
+			let mut changes = random_pairs(&mut rng, CHANGED_KEYS / 2);
+			changes
+				.extend(pairs.iter().take(CHANGED_KEYS / 2).map(|(k, v)| (k.clone(), v.repeat(2))));
+			(child_info.clone(), changes)
+		})
+		.collect::<Vec<_>>();
+
+	let path = TempDir::new().expect("Creates temporary directory");
+	let block_hash = insert_genesis(&create_backend(None, &path), &children);
+
+	let mut group = c.benchmark_group("Computing the state root with many changed child tries");
+	group.sample_size(20);
+
+	let mut bench = |trie_cache_maximum_size, parallel, desc| {
+		let backend = create_backend(trie_cache_maximum_size, &path);
+
+		group.bench_function(desc, |b| {
+			b.iter_batched(
+				|| backend.state_at(block_hash).expect("Creates state"),
+				|state| {
+					let child_deltas = changes.iter().map(|(child_info, changes)| {
+						(child_info, changes.iter().map(|(k, v)| (&k[..], Some(&v[..]))))
+					});
+
+					if parallel {
+						state.full_storage_root(std::iter::empty(), child_deltas, StateVersion::V1)
+					} else {
+						// What `full_storage_root` did before computing the child roots in
+						// parallel.
+						let mut child_roots = Vec::new();
+						for (child_info, delta) in child_deltas {
+							let (root, _, _) =
+								state.child_storage_root(child_info, delta, StateVersion::V1);
+							child_roots.push((
+								child_info.prefixed_storage_key().into_inner(),
+								root.encode(),
+							));
+						}
+						state.storage_root(
+							child_roots.iter().map(|(k, v)| (&k[..], Some(&v[..]))),
+							StateVersion::V1,
+						)
+					}
+				},
+				BatchSize::SmallInput,
+			)
+		});
+	};
+
+	bench(None, false, "no cache and child roots one after another");
+	bench(None, true, "no cache and child roots in parallel");
+	bench(
+		Some(1024 * 1024 * 1024),
+		false,
+		"with trie node cache and child roots one after another",
+	);
+	bench(Some(1024 * 1024 * 1024), true, "with trie node cache and child roots in parallel");
+
+	group.finish();
+}
+
+criterion_group!(benches, state_root_benchmarks);
+criterion_main!(benches);
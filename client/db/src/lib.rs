@@ -31,24 +31,30 @@
 pub mod offchain;
 
 pub mod bench;
+pub mod maintenance;
+pub mod state_migration;
 
 mod children;
 mod parity_db;
 mod pinned_blocks_cache;
 mod record_stats_state;
+mod state_import;
 mod stats;
 #[cfg(any(feature = "rocksdb", test))]
 mod upgrade;
 mod utils;
 
 use linked_hash_map::LinkedHashMap;
-use log::{debug, trace, warn};
+use log::{debug, error, trace, warn};
 use parking_lot::{Mutex, RwLock};
 use std::{
-	collections::{HashMap, HashSet},
+	collections::{BTreeMap, HashMap, HashSet},
 	io,
 	path::{Path, PathBuf},
-	sync::Arc,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
 };
 
 use crate::{
@@ -63,7 +69,7 @@ use sc_client_api::{
 	backend::NewBlockState,
 	leaves::{FinalizationOutcome, LeafSet},
 	utils::is_descendent_of,
-	IoInfo, MemoryInfo, MemorySize, UsageInfo,
+	IoInfo, MemoryInfo, MemorySize, TrieCacheInfo, TrieCacheUsage, UsageInfo,
 };
 use sc_state_db::{IsPruned, LastCanonicalized, StateDb};
 use sp_arithmetic::traits::Saturating;
@@ -72,6 +78,8 @@ use sp_blockchain::{
 	HeaderMetadataCache, Result as ClientResult,
 };
 use sp_core::{
+	hashing::twox_128,
+	hexdisplay::HexDisplay,
 	offchain::OffchainOverlayedChange,
 	storage::{well_known_keys, ChildInfo},
 };
@@ -90,16 +98,26 @@ use sp_state_machine::{
 	OffchainChangesCollection, StateMachineStats, StorageCollection, StorageIterator, StorageKey,
 	StorageValue, UsageInfo as StateUsageInfo,
 };
-use sp_trie::{cache::SharedTrieCache, prefixed_key, MemoryDB, PrefixedMemoryDB};
+use sp_trie::{
+	cache::SharedTrieCache, empty_child_trie_root, prefixed_key, KeySpacedDBMut, LayoutV1,
+	MemoryDB, PrefixedMemoryDB,
+};
 
 // Re-export the Database trait so that one can pass an implementation of it.
-pub use sc_state_db::PruningMode;
+pub use sc_state_db::{Constraints, PruningMode};
 pub use sp_database::Database;
 
 pub use bench::BenchmarkingState;
 
+/// Sorted key/value pairs of the top trie, or of the given child trie, see
+/// [`Backend::import_state`].
+pub type StateChunk = (Option<ChildInfo>, Vec<(Vec<u8>, Vec<u8>)>);
+
 const CACHE_HEADERS: usize = 8;
 
+/// Size of the batches of trie nodes written by [`Backend::import_state`].
+const IMPORT_STATE_BATCH_BYTES: usize = 64 * 1024 * 1024;
+
 /// DB-backed patricia trie state, transaction type is an overlay of changes to commit.
 pub type DbState<B> =
 	sp_state_machine::TrieBackend<Arc<dyn sp_state_machine::Storage<HashingFor<B>>>, HashingFor<B>>;
@@ -255,6 +273,17 @@ impl<B: BlockT> StateBackend<HashingFor<B>> for RefTrackingState<B> {
 		self.state.child_storage_root(child_info, delta, state_version)
 	}
 
+	fn full_storage_root<'a>(
+		&self,
+		delta: impl Iterator<Item = (&'a [u8], Option<&'a [u8]>)>,
+		child_deltas: impl Iterator<
+			Item = (&'a ChildInfo, impl Iterator<Item = (&'a [u8], Option<&'a [u8]>)>),
+		>,
+		state_version: StateVersion,
+	) -> (B::Hash, BackendTransaction<HashingFor<B>>) {
+		self.state.full_storage_root(delta, child_deltas, state_version)
+	}
+
 	fn raw_iter(&self, args: IterArgs) -> Result<Self::RawIter, Self::Error> {
 		self.state.raw_iter(args).map(|inner| RawIter { inner })
 	}
@@ -284,6 +313,8 @@ pub struct DatabaseSettings {
 	///
 	/// If `None` is given, the cache is disabled.
 	pub trie_cache_maximum_size: Option<usize>,
+	/// Split the trie cache budget between nodes and values based on the observed workload.
+	pub trie_cache_adaptive: bool,
 	/// Requested state pruning mode.
 	pub state_pruning: Option<PruningMode>,
 	/// Where to find the database.
@@ -292,6 +323,15 @@ pub struct DatabaseSettings {
 	///
 	/// NOTE: only finalized blocks are subject for removal!
 	pub blocks_pruning: BlocksPruning,
+	/// Justifications pruning mode.
+	///
+	/// If `None` is given, justifications are pruned together with the block bodies.
+	pub justifications_pruning: Option<BlocksPruning>,
+	/// Indexed transactions pruning mode.
+	///
+	/// If `None` is given, indexed transactions are pruned together with the block bodies.
+	/// Indexed transactions are never kept for longer than the bodies of their blocks.
+	pub transactions_pruning: Option<BlocksPruning>,
 }
 
 /// Block pruning settings.
@@ -536,7 +576,8 @@ impl<Block: BlockT> BlockchainDb<Block> {
 	/// to load values for items items into the cache which have already been pinned.
 	fn insert_persisted_body_if_pinned(&self, hash: Block::Hash) -> ClientResult<()> {
 		let mut cache = self.pinned_blocks_cache.write();
-		if !cache.contains(hash) {
+		// The body may already be cached if its indexed transactions were pruned before.
+		if !cache.contains(hash) || cache.body(&hash).is_some() {
 			return Ok(())
 		}
 
@@ -826,6 +867,8 @@ pub struct BlockImportOperation<Block: BlockT> {
 	finalized_blocks: Vec<(Block::Hash, Option<Justification>)>,
 	set_head: Option<Block::Hash>,
 	commit_state: bool,
+	// The total size of the nodes of a state written by `Backend::import_state`.
+	imported_state: Option<u64>,
 	index_ops: Vec<IndexOperation>,
 }
 
@@ -1098,6 +1141,10 @@ pub struct Backend<Block: BlockT> {
 	import_lock: Arc<RwLock<()>>,
 	is_archive: bool,
 	blocks_pruning: BlocksPruning,
+	justifications_pruning: BlocksPruning,
+	transactions_pruning: BlocksPruning,
+	block_timestamp_key: Option<Vec<u8>>,
+	missing_block_timestamp_reported: AtomicBool,
 	io_stats: FrozenForDuration<(kvdb::IoStats, StateUsageInfo)>,
 	state_usage: Arc<StateUsageStats>,
 	genesis_state: RwLock<Option<Arc<DbGenesisStorage<Block>>>>,
@@ -1155,9 +1202,12 @@ impl<Block: BlockT> Backend<Block> {
 		};
 		let db_setting = DatabaseSettings {
 			trie_cache_maximum_size: Some(16 * 1024 * 1024),
+			trie_cache_adaptive: false,
 			state_pruning: Some(state_pruning),
 			source: DatabaseSource::Custom { db, require_create_flag: true },
 			blocks_pruning,
+			justifications_pruning: None,
+			transactions_pruning: None,
 		};
 
 		Self::new(db_setting, canonicalization_delay).expect("failed to create test-db")
@@ -1180,6 +1230,134 @@ impl<Block: BlockT> Backend<Block> {
 		self.storage.clone()
 	}
 
+	/// Import the state of `header` from `chunks` of sorted key/value pairs and finalize `header`
+	/// with it.
+	///
+	/// A chunk holds pairs of the top trie, or of the given child trie, the chunks of one trie
+	/// must follow each other. The pairs of the top trie don't need to include the roots of the
+	/// child tries, they are computed from the chunks of the child tries. The tries are built as
+	/// the chunks arrive and their nodes are written to the database in batches, so the state
+	/// doesn't need to fit into memory. The state is then added to the state database as its
+	/// last canonicalized block, so it is pruned like the states of the following blocks. The
+	/// database must not contain any blocks yet, the parent of `header` doesn't need to be known.
+	///
+	/// Returns an error if the root of the imported state doesn't match the state root of
+	/// `header`. The nodes written before the error are left in the database.
+	pub fn import_state(
+		&self,
+		header: Block::Header,
+		chunks: impl IntoIterator<Item = ClientResult<StateChunk>>,
+		state_version: StateVersion,
+	) -> ClientResult<()> {
+		self.import_state_in_batches(header, chunks, state_version, IMPORT_STATE_BATCH_BYTES)
+	}
+
+	fn import_state_in_batches(
+		&self,
+		header: Block::Header,
+		chunks: impl IntoIterator<Item = ClientResult<StateChunk>>,
+		state_version: StateVersion,
+		batch_bytes: usize,
+	) -> ClientResult<()> {
+		use sc_client_api::backend::{Backend as _, BlockImportOperation as _};
+
+		if self.blockchain.info().finalized_state.is_some() {
+			return Err(ClientError::Backend(
+				"Can't import a state into a database with a state".into(),
+			))
+		}
+
+		let mut writer =
+			state_import::NodeWriter::<HashingFor<Block>>::new(&*self.storage.db, batch_bytes);
+		let mut top_root = None;
+		let mut child_roots = BTreeMap::new();
+		let mut chunks = chunks.into_iter();
+		let mut next = chunks.next().transpose()?;
+		while let Some(chunk) = next {
+			let child_info = chunk.0.clone();
+			match child_info {
+				None => {
+					if top_root.is_some() {
+						return Err(ClientError::Backend(
+							"The chunks of the imported top trie are not consecutive".into(),
+						))
+					}
+					let (root, next_chunk) =
+						state_import::build_trie(&mut writer, chunk, &mut chunks, state_version)?;
+					top_root = Some(root);
+					next = next_chunk;
+				},
+				Some(child_info) => {
+					let storage_key = child_info.prefixed_storage_key().into_inner();
+					if child_roots.contains_key(&storage_key) {
+						return Err(ClientError::Backend(format!(
+							"The chunks of the imported child trie 0x{} are not consecutive",
+							HexDisplay::from(&storage_key)
+						)))
+					}
+					let mut db = KeySpacedDBMut::new(&mut writer, child_info.keyspace());
+					let (root, next_chunk) =
+						state_import::build_trie(&mut db, chunk, &mut chunks, state_version)?;
+					// The roots of empty child tries are not stored in the top trie.
+					let root = (root != empty_child_trie_root::<LayoutV1<HashingFor<Block>>>())
+						.then(|| root.encode());
+					child_roots.insert(storage_key, root);
+					next = next_chunk;
+				},
+			}
+		}
+		let top_root = match top_root {
+			Some(root) => root,
+			None =>
+				state_import::build_trie(
+					&mut writer,
+					(None, Vec::new()),
+					&mut std::iter::empty(),
+					state_version,
+				)?
+				.0,
+		};
+		writer.flush()?;
+
+		// The roots of the child tries are added to the top trie, only the changed nodes are kept
+		// in memory.
+		let state = DbStateBuilder::<Block>::new(self.storage.clone(), top_root).build();
+		let (root, mut changes) = state.storage_root(
+			child_roots.iter().map(|(key, root)| (key.as_slice(), root.as_deref())),
+			state_version,
+		);
+		for (mut key, (value, rc)) in changes.drain() {
+			self.storage.db.sanitize_key(&mut key);
+			for _ in 0..rc {
+				writer.write(key.clone(), value.clone());
+			}
+			for _ in rc..0 {
+				writer.delete(&key);
+			}
+		}
+		writer.flush()?;
+
+		if root != *header.state_root() {
+			return Err(ClientError::Backend(format!(
+				"The imported state has the root {:?}, expected {:?}",
+				root,
+				header.state_root(),
+			)))
+		}
+
+		let mut operation = self.begin_operation()?;
+		// The timestamp of a block is read from its storage changes, so it is added to them.
+		let timestamp = self.block_timestamp_key.as_ref().and_then(|key| {
+			let state = DbStateBuilder::<Block>::new(self.storage.clone(), root).build();
+			state.storage(key).ok().flatten().map(|value| (key.clone(), Some(value)))
+		});
+		operation.update_storage(timestamp.into_iter().collect(), Vec::new())?;
+		operation.set_block_data(header, None, None, None, NewBlockState::Final)?;
+		operation.commit_state = true;
+		operation.imported_state = Some(writer.size());
+		self.commit_operation(operation)
+	}
+
 	fn from_database(
 		db: Arc<dyn Database<DbHash>>,
 		canonicalization_delay: u64,
@@ -1204,6 +1382,12 @@ impl<Block: BlockT> Backend<Block> {
 
 		let state_pruning_used = state_db.pruning_mode();
 		let is_archive_pruning = state_pruning_used.is_archive();
+		// Block timestamps are only needed to enforce the maximum age of the pruning window.
+		let block_timestamp_key = match state_pruning_used {
+			PruningMode::Constrained(Constraints { max_age: Some(_), timestamp_key, .. }) =>
+				Some(timestamp_key.unwrap_or_else(default_block_timestamp_key)),
+			_ => None,
+		};
 		let blockchain = BlockchainDb::new(db.clone())?;
 
 		let storage_db =
@@ -1218,12 +1402,21 @@ impl<Block: BlockT> Backend<Block> {
 			canonicalization_delay,
 			import_lock: Default::default(),
 			is_archive: is_archive_pruning,
+			block_timestamp_key,
+			missing_block_timestamp_reported: AtomicBool::new(false),
 			io_stats: FrozenForDuration::new(std::time::Duration::from_secs(1)),
 			state_usage: Arc::new(StateUsageStats::new()),
 			blocks_pruning: config.blocks_pruning,
+			justifications_pruning: config.justifications_pruning.unwrap_or(config.blocks_pruning),
+			transactions_pruning: config.transactions_pruning.unwrap_or(config.blocks_pruning),
 			genesis_state: RwLock::new(None),
 			shared_trie_cache: config.trie_cache_maximum_size.map(|maximum_size| {
-				SharedTrieCache::new(sp_trie::cache::CacheSize::new(maximum_size))
+				let cache_size = sp_trie::cache::CacheSize::new(maximum_size);
+				if config.trie_cache_adaptive {
+					SharedTrieCache::new_adaptive(cache_size)
+				} else {
+					SharedTrieCache::new(cache_size)
+				}
 			}),
 		};
 
@@ -1361,6 +1554,7 @@ impl<Block: BlockT> Backend<Block> {
 			header,
 			hash,
 			with_state,
+			if with_state { self.block_timestamp(hash, number) } else { None },
 			current_transaction_justifications,
 		)?;
 
@@ -1375,6 +1569,41 @@ impl<Block: BlockT> Backend<Block> {
 		Ok(MetaUpdate { hash, number, is_best: false, is_finalized: true, with_state })
 	}
 
+	// reads the timestamp of a block from its state, if needed by the state pruning.
+	fn block_timestamp(&self, hash: Block::Hash, number: NumberFor<Block>) -> Option<u64> {
+		let key = self.block_timestamp_key.as_ref()?;
+		let timestamp = sc_client_api::Backend::state_at(self, hash)
+			.ok()
+			.and_then(|state| state.storage(key).ok().flatten())
+			.and_then(|value| decode_block_timestamp(&value));
+		self.check_block_timestamp(number, timestamp)
+	}
+
+	// reports once that the timestamp of a block is missing, which makes the state pruning
+	// prune its state as soon as the maximum age is enforced.
+	fn check_block_timestamp(
+		&self,
+		number: NumberFor<Block>,
+		timestamp: Option<u64>,
+	) -> Option<u64> {
+		// The genesis state has no timestamp.
+		if timestamp.is_none() &&
+			!number.is_zero() &&
+			!self.missing_block_timestamp_reported.swap(true, Ordering::Relaxed)
+		{
+			let key = self.block_timestamp_key.as_deref().unwrap_or_default();
+			error!(
+				target: "db",
+				"No timestamp found in the state of block #{} under the key 0x{}. \
+				 The maximum age of the state pruning can not be enforced, configure the storage \
+				 key of the block timestamp.",
+				number,
+				HexDisplay::from(key),
+			);
+		}
+		timestamp
+	}
+
 	// performs forced canonicalization with a delay after importing a non-finalized block.
 	fn force_delayed_canonicalize(
 		&self,
@@ -1414,11 +1643,17 @@ impl<Block: BlockT> Backend<Block> {
 			}
 
 			trace!(target: "db", "Canonicalize block #{} ({:?})", to_canonicalize, hash_to_canonicalize);
-			let commit = self.storage.state_db.canonicalize_block(&hash_to_canonicalize).map_err(
-				sp_blockchain::Error::from_state_db::<
-					sc_state_db::Error<sp_database::error::DatabaseError>,
-				>,
-			)?;
+			let timestamp =
+				self.block_timestamp(hash_to_canonicalize, to_canonicalize.saturated_into());
+			let commit = self
+				.storage
+				.state_db
+				.canonicalize_block_with_timestamp(&hash_to_canonicalize, timestamp)
+				.map_err(
+					sp_blockchain::Error::from_state_db::<
+						sc_state_db::Error<sp_database::error::DatabaseError>,
+					>,
+				)?;
 			apply_state_commit(transaction, commit);
 		}
 
@@ -1515,6 +1750,21 @@ impl<Block: BlockT> Backend<Block> {
 				}
 			}
 
+			// The state of the block is not committed yet, so its timestamp is taken from the
+			// storage changes.
+			let timestamp = match &self.block_timestamp_key {
+				Some(key) if operation.commit_state => self.check_block_timestamp(
+					number,
+					operation
+						.storage_updates
+						.iter()
+						.find(|(k, _)| k == key)
+						.and_then(|(_, value)| value.as_deref())
+						.and_then(decode_block_timestamp),
+				),
+				_ => None,
+			};
+
 			let finalized = if operation.commit_state {
 				let mut changeset: sc_state_db::ChangeSet<Vec<u8>> =
 					sc_state_db::ChangeSet::default();
@@ -1565,21 +1815,34 @@ impl<Block: BlockT> Backend<Block> {
 				}
 				self.state_usage.tally_writes(ops, bytes);
 				let number_u64 = number.saturated_into::<u64>();
-				let commit = self
-					.storage
-					.state_db
-					.insert_block(&hash, number_u64, pending_block.header.parent_hash(), changeset)
-					.map_err(|e: sc_state_db::Error<sp_database::error::DatabaseError>| {
-						sp_blockchain::Error::from_state_db(e)
-					})?;
+				let commit = match operation.imported_state {
+					// The nodes of an imported state are in the database already.
+					Some(size) => self
+						.storage
+						.state_db
+						.import_canonical_block(&hash, number_u64, timestamp, size),
+					None => self.storage.state_db.insert_block(
+						&hash,
+						number_u64,
+						pending_block.header.parent_hash(),
+						changeset,
+					),
+				}
+				.map_err(|e: sc_state_db::Error<sp_database::error::DatabaseError>| {
+					sp_blockchain::Error::from_state_db(e)
+				})?;
 				apply_state_commit(&mut transaction, commit);
-				if number <= last_finalized_num {
+				if number <= last_finalized_num && operation.imported_state.is_none() {
 					// Canonicalize in the db when re-importing existing blocks with state.
-					let commit = self.storage.state_db.canonicalize_block(&hash).map_err(
-						sp_blockchain::Error::from_state_db::<
-							sc_state_db::Error<sp_database::error::DatabaseError>,
-						>,
-					)?;
+					let commit = self
+						.storage
+						.state_db
+						.canonicalize_block_with_timestamp(&hash, timestamp)
+						.map_err(
+							sp_blockchain::Error::from_state_db::<
+								sc_state_db::Error<sp_database::error::DatabaseError>,
+							>,
+						)?;
 					apply_state_commit(&mut transaction, commit);
 					meta_updates.push(MetaUpdate {
 						hash,
@@ -1626,6 +1889,7 @@ impl<Block: BlockT> Backend<Block> {
 					header,
 					hash,
 					operation.commit_state,
+					timestamp,
 					&mut current_transaction_justifications,
 				)?;
 			} else {
@@ -1686,7 +1950,8 @@ impl<Block: BlockT> Backend<Block> {
 						);
 					}
 				} else if number > best_num + One::one() &&
-					number > One::one() && self.blockchain.header(parent_hash)?.is_none()
+					number > One::one() &&
+					self.blockchain.header(parent_hash)?.is_none()
 				{
 					let gap = (best_num + One::one(), number - One::one());
 					transaction.set(columns::META, meta_keys::BLOCK_GAP, &gap.encode());
@@ -1760,6 +2025,7 @@ impl<Block: BlockT> Backend<Block> {
 		f_header: &Block::Header,
 		f_hash: Block::Hash,
 		with_state: bool,
+		timestamp: Option<u64>,
 		current_transaction_justifications: &mut HashMap<Block::Hash, Justification>,
 	) -> ClientResult<()> {
 		let f_num = *f_header.number();
@@ -1777,11 +2043,15 @@ impl<Block: BlockT> Backend<Block> {
 		};
 
 		if requires_canonicalization && sc_client_api::Backend::have_state_at(self, f_hash, f_num) {
-			let commit = self.storage.state_db.canonicalize_block(&f_hash).map_err(
-				sp_blockchain::Error::from_state_db::<
-					sc_state_db::Error<sp_database::error::DatabaseError>,
-				>,
-			)?;
+			let commit = self
+				.storage
+				.state_db
+				.canonicalize_block_with_timestamp(&f_hash, timestamp)
+				.map_err(
+					sp_blockchain::Error::from_state_db::<
+						sc_state_db::Error<sp_database::error::DatabaseError>,
+					>,
+				)?;
 			apply_state_commit(transaction, commit);
 		}
 
@@ -1805,36 +2075,44 @@ impl<Block: BlockT> Backend<Block> {
 		displaced: &FinalizationOutcome<Block::Hash, NumberFor<Block>>,
 		current_transaction_justifications: &mut HashMap<Block::Hash, Justification>,
 	) -> ClientResult<()> {
-		match self.blocks_pruning {
-			BlocksPruning::KeepAll => {},
-			BlocksPruning::Some(blocks_pruning) => {
-				// Always keep the last finalized block
-				let keep = std::cmp::max(blocks_pruning, 1);
-				if finalized_number >= keep.into() {
-					let number = finalized_number.saturating_sub(keep.into());
-
-					// Before we prune a block, check if it is pinned
-					if let Some(hash) = self.blockchain.hash(number)? {
-						self.blockchain.insert_persisted_body_if_pinned(hash)?;
-
-						// If the block was finalized in this transaction, it will not be in the db
-						// yet.
-						if let Some(justification) =
-							current_transaction_justifications.remove(&hash)
-						{
-							self.blockchain.insert_justifications_if_pinned(hash, justification);
-						} else {
-							self.blockchain.insert_persisted_justifications_if_pinned(hash)?;
-						}
-					};
+		let body_number = pruned_block_number::<Block>(self.blocks_pruning, finalized_number);
+		if let Some(number) = body_number {
+			// Before we prune a block, check if it is pinned
+			if let Some(hash) = self.blockchain.hash(number)? {
+				self.blockchain.insert_persisted_body_if_pinned(hash)?;
+			}
+			self.prune_body(transaction, BlockId::<Block>::number(number))?;
+		}
+
+		// Indexed transactions that are kept for at least as long as the bodies are pruned
+		// together with them.
+		let transactions_number =
+			pruned_block_number::<Block>(self.transactions_pruning, finalized_number)
+				.filter(|number| body_number.map_or(true, |body_number| *number > body_number));
+		if let Some(number) = transactions_number {
+			if let Some(hash) = self.blockchain.hash(number)? {
+				self.blockchain.insert_persisted_body_if_pinned(hash)?;
+			}
+			self.prune_indexed_transactions(transaction, BlockId::<Block>::number(number))?;
+		}
 
-					self.prune_block(transaction, BlockId::<Block>::number(number))?;
+		if let Some(number) =
+			pruned_block_number::<Block>(self.justifications_pruning, finalized_number)
+		{
+			if let Some(hash) = self.blockchain.hash(number)? {
+				// If the block was finalized in this transaction, it will not be in the db
+				// yet.
+				if let Some(justification) = current_transaction_justifications.remove(&hash) {
+					self.blockchain.insert_justifications_if_pinned(hash, justification);
+				} else {
+					self.blockchain.insert_persisted_justifications_if_pinned(hash)?;
 				}
-				self.prune_displaced_branches(transaction, finalized_hash, displaced)?;
-			},
-			BlocksPruning::KeepFinalized => {
-				self.prune_displaced_branches(transaction, finalized_hash, displaced)?;
-			},
+			}
+			self.prune_justifications(transaction, BlockId::<Block>::number(number))?;
+		}
+
+		if self.blocks_pruning != BlocksPruning::KeepAll {
+			self.prune_displaced_branches(transaction, finalized_hash, displaced)?;
 		}
 		Ok(())
 	}
@@ -1868,6 +2146,16 @@ impl<Block: BlockT> Backend<Block> {
 		id: BlockId<Block>,
 	) -> ClientResult<()> {
 		debug!(target: "db", "Removing block #{}", id);
+		self.prune_body(transaction, id)?;
+		self.prune_justifications(transaction, id)
+	}
+
+	fn prune_body(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		id: BlockId<Block>,
+	) -> ClientResult<()> {
+		debug!(target: "db", "Removing body of block #{}", id);
 		utils::remove_from_db(
 			transaction,
 			&*self.storage.db,
@@ -1875,16 +2163,7 @@ impl<Block: BlockT> Backend<Block> {
 			columns::BODY,
 			id,
 		)?;
-		utils::remove_from_db(
-			transaction,
-			&*self.storage.db,
-			columns::KEY_LOOKUP,
-			columns::JUSTIFICATIONS,
-			id,
-		)?;
-		if let Some(index) =
-			read_db(&*self.storage.db, columns::KEY_LOOKUP, columns::BODY_INDEX, id)?
-		{
+		if let Some(index) = self.read_body_index(id)? {
 			utils::remove_from_db(
 				transaction,
 				&*self.storage.db,
@@ -1892,23 +2171,74 @@ impl<Block: BlockT> Backend<Block> {
 				columns::BODY_INDEX,
 				id,
 			)?;
-			match Vec::<DbExtrinsic<Block>>::decode(&mut &index[..]) {
-				Ok(index) =>
-					for ex in index {
-						if let DbExtrinsic::Indexed { hash, .. } = ex {
-							transaction.release(columns::TRANSACTION, hash);
-						}
-					},
-				Err(err) =>
-					return Err(sp_blockchain::Error::Backend(format!(
-						"Error decoding body list: {}",
-						err
-					))),
+			for ex in index {
+				if let DbExtrinsic::Indexed { hash, .. } = ex {
+					transaction.release(columns::TRANSACTION, hash);
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn prune_indexed_transactions(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		id: BlockId<Block>,
+	) -> ClientResult<()> {
+		let Some(index) = self.read_body_index(id)? else { return Ok(()) };
+		let mut has_indexed = false;
+		for ex in index {
+			if let DbExtrinsic::Indexed { hash, .. } = ex {
+				transaction.release(columns::TRANSACTION, hash);
+				has_indexed = true;
 			}
 		}
+		// The body can not be restored without its indexed transactions.
+		if has_indexed {
+			debug!(target: "db", "Removing indexed transactions of block #{}", id);
+			utils::remove_from_db(
+				transaction,
+				&*self.storage.db,
+				columns::KEY_LOOKUP,
+				columns::BODY_INDEX,
+				id,
+			)?;
+		}
 		Ok(())
 	}
 
+	fn prune_justifications(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		id: BlockId<Block>,
+	) -> ClientResult<()> {
+		utils::remove_from_db(
+			transaction,
+			&*self.storage.db,
+			columns::KEY_LOOKUP,
+			columns::JUSTIFICATIONS,
+			id,
+		)
+	}
+
+	fn read_body_index(&self, id: BlockId<Block>) -> ClientResult<Option<Vec<DbExtrinsic<Block>>>> {
+		match read_db(&*self.storage.db, columns::KEY_LOOKUP, columns::BODY_INDEX, id)? {
+			Some(index) => match Vec::<DbExtrinsic<Block>>::decode(&mut &index[..]) {
+				Ok(index) => Ok(Some(index)),
+				Err(err) =>
+					Err(sp_blockchain::Error::Backend(format!("Error decoding body list: {}", err))),
+			},
+			None => Ok(None),
+		}
+	}
+
+	// whether any kind of block data is ever pruned.
+	fn prunes_block_data(&self) -> bool {
+		[self.blocks_pruning, self.justifications_pruning, self.transactions_pruning]
+			.iter()
+			.any(|pruning| *pruning != BlocksPruning::KeepAll)
+	}
+
 	fn empty_state(&self) -> RecordStatsState<RefTrackingState<Block>, Block> {
 		let root = EmptyStorage::<Block>::new().0; // Empty trie
 		let db_state = DbStateBuilder::<Block>::new(self.storage.clone(), root)
@@ -1919,6 +2249,31 @@ impl<Block: BlockT> Backend<Block> {
 	}
 }
 
+/// Storage key of the block timestamp set by FRAME's timestamp pallet.
+fn default_block_timestamp_key() -> Vec<u8> {
+	[twox_128(b"Timestamp"), twox_128(b"Now")].concat()
+}
+
+/// Decode a block timestamp in milliseconds.
+fn decode_block_timestamp(mut value: &[u8]) -> Option<u64> {
+	u64::decode(&mut value).ok()
+}
+
+/// Number of the finalized block whose data is pruned when finalizing `finalized_number`, if any.
+fn pruned_block_number<Block: BlockT>(
+	pruning: BlocksPruning,
+	finalized_number: NumberFor<Block>,
+) -> Option<NumberFor<Block>> {
+	match pruning {
+		BlocksPruning::KeepAll | BlocksPruning::KeepFinalized => None,
+		BlocksPruning::Some(blocks_pruning) => {
+			// Always keep the last finalized block
+			let keep = std::cmp::max(blocks_pruning, 1);
+			(finalized_number >= keep.into()).then(|| finalized_number.saturating_sub(keep.into()))
+		},
+	}
+}
+
 fn apply_state_commit(
 	transaction: &mut Transaction<DbHash>,
 	commit: sc_state_db::CommitSet<Vec<u8>>,
@@ -2052,6 +2407,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 			finalized_blocks: Vec::new(),
 			set_head: None,
 			commit_state: false,
+			imported_state: None,
 			index_ops: Default::default(),
 		})
 	}
@@ -2170,6 +2526,21 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		let state_cache = MemorySize::from_bytes(
 			self.shared_trie_cache.as_ref().map_or(0, |c| c.used_memory_size()),
 		);
+		let trie_cache = self.shared_trie_cache.as_ref().map(|cache| {
+			let stats = cache.stats();
+			let usage = |stats: sp_trie::cache::CacheStats| TrieCacheUsage {
+				local_hits: stats.local_hits,
+				shared_hits: stats.shared_hits,
+				misses: stats.misses,
+				evictions: stats.evictions,
+				size: MemorySize::from_bytes(stats.used_memory),
+				budget: MemorySize::from_bytes(stats.memory_budget),
+			};
+			TrieCacheInfo {
+				node_cache: usage(stats.node_cache),
+				value_cache: usage(stats.value_cache),
+			}
+		});
 
 		Some(UsageInfo {
 			memory: MemoryInfo { state_cache, database_cache },
@@ -2186,6 +2557,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 				state_reads_cache: state_stats.cache_reads.ops,
 				state_writes_nodes: state_stats.nodes_writes.ops,
 			},
+			trie_cache,
 		})
 	}
 
@@ -2511,7 +2883,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 			)))
 		}
 
-		if self.blocks_pruning != BlocksPruning::KeepAll {
+		if self.prunes_block_data() {
 			// Only increase reference count for this hash. Value is loaded once we prune.
 			self.blockchain.bump_ref(hash);
 		}
@@ -2521,7 +2893,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 	fn unpin_block(&self, hash: <Block as BlockT>::Hash) {
 		self.storage.state_db.unpin(&hash);
 
-		if self.blocks_pruning != BlocksPruning::KeepAll {
+		if self.prunes_block_data() {
 			self.blockchain.unpin(hash);
 		}
 	}
@@ -2673,9 +3045,12 @@ pub(crate) mod tests {
 		let backend = Backend::<Block>::new(
 			DatabaseSettings {
 				trie_cache_maximum_size: Some(16 * 1024 * 1024),
+				trie_cache_adaptive: false,
 				state_pruning: Some(PruningMode::blocks_pruning(1)),
 				source: DatabaseSource::Custom { db: backing, require_create_flag: false },
 				blocks_pruning: BlocksPruning::KeepFinalized,
+				justifications_pruning: None,
+				transactions_pruning: None,
 			},
 			0,
 		)
@@ -2768,6 +3143,326 @@ pub(crate) mod tests {
 		}
 	}
 
+	#[test]
+	fn import_state_in_chunks() {
+		let child_info = ChildInfo::new_default(b"child");
+		let top = vec![(vec![1, 2, 3], vec![9; 40]), (vec![1, 3, 5], vec![2, 4, 6])];
+		let child = vec![(vec![7], vec![8])];
+
+		let mut header = Header {
+			number: 0,
+			parent_hash: Default::default(),
+			state_root: Default::default(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		header.state_root = {
+			let db = Backend::<Block>::new_test(2, 0);
+			let mut op = db.begin_operation().unwrap();
+			let mut storage = Storage {
+				top: top.iter().cloned().collect(),
+				children_default: Default::default(),
+			};
+			storage.children_default.insert(
+				child_info.storage_key().to_vec(),
+				sp_core::storage::StorageChild {
+					data: child.iter().cloned().collect(),
+					child_info: child_info.clone(),
+				},
+			);
+			op.reset_storage(storage, StateVersion::V1).unwrap()
+		};
+
+		let db = Backend::<Block>::new_test(2, 0);
+		let chunks = vec![
+			Ok((None, top[..1].to_vec())),
+			Ok((None, top[1..].to_vec())),
+			Ok((Some(child_info.clone()), child.clone())),
+		];
+		db.import_state(header.clone(), chunks, StateVersion::V1).unwrap();
+
+		assert_eq!(db.blockchain().info().finalized_hash, header.hash());
+		let state = db.state_at(header.hash()).unwrap();
+		assert_eq!(state.storage(&[1, 2, 3]).unwrap(), Some(vec![9; 40]));
+		assert_eq!(state.storage(&[1, 3, 5]).unwrap(), Some(vec![2, 4, 6]));
+		assert_eq!(state.child_storage(&child_info, &[7]).unwrap(), Some(vec![8]));
+
+		let db = Backend::<Block>::new_test(2, 0);
+		let chunks = vec![
+			Ok((None, top[..1].to_vec())),
+			Ok((Some(child_info.clone()), child.clone())),
+			Ok((None, top[1..].to_vec())),
+		];
+		assert!(db.import_state(header.clone(), chunks, StateVersion::V1).is_err());
+
+		let db = Backend::<Block>::new_test(2, 0);
+		let chunks = vec![Ok((None, top[1..].to_vec())), Ok((None, top[..1].to_vec()))];
+		assert!(db.import_state(header.clone(), chunks, StateVersion::V1).is_err());
+
+		let db = Backend::<Block>::new_test(2, 0);
+		let chunks = vec![Ok((None, top[..1].to_vec()))];
+		assert!(db.import_state(header, chunks, StateVersion::V1).is_err());
+	}
+
+	#[test]
+	fn import_state_in_batches() {
+		// Records the size of the state nodes of each commit.
+		struct BatchesDb {
+			db: Arc<dyn Database<DbHash>>,
+			batches: Mutex<Vec<usize>>,
+		}
+
+		impl Database<DbHash> for BatchesDb {
+			fn commit(&self, transaction: Transaction<DbHash>) -> sp_database::error::Result<()> {
+				let size = transaction
+					.0
+					.iter()
+					.map(|change| match change {
+						sp_database::Change::Set(columns::STATE, key, value) =>
+							key.len() + value.len(),
+						_ => 0,
+					})
+					.sum();
+				self.batches.lock().push(size);
+				self.db.commit(transaction)
+			}
+
+			fn get(&self, col: sp_database::ColumnId, key: &[u8]) -> Option<Vec<u8>> {
+				self.db.get(col, key)
+			}
+		}
+
+		let state_version = StateVersion::V1;
+		let batch_bytes = 4096;
+		let child_info = ChildInfo::new_default(b"child");
+		let chunk = |start: u32| -> Vec<_> {
+			(start..start + 20)
+				.map(|i| (i.to_be_bytes().to_vec(), vec![i as u8; 64]))
+				.collect()
+		};
+
+		let mut header = Header {
+			number: 0,
+			parent_hash: Default::default(),
+			state_root: Default::default(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		header.state_root = {
+			let db = Backend::<Block>::new_test(2, 0);
+			let mut op = db.begin_operation().unwrap();
+			let mut storage = Storage {
+				top: (0..50).flat_map(|i| chunk(i * 20)).collect(),
+				children_default: Default::default(),
+			};
+			storage.children_default.insert(
+				child_info.storage_key().to_vec(),
+				sp_core::storage::StorageChild {
+					data: (0..50).flat_map(|i| chunk(i * 20)).collect(),
+					child_info: child_info.clone(),
+				},
+			);
+			op.reset_storage(storage, state_version).unwrap()
+		};
+
+		let batches_db = Arc::new(BatchesDb {
+			db: sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS)),
+			batches: Default::default(),
+		});
+		let backend = Backend::<Block>::new(
+			DatabaseSettings {
+				trie_cache_maximum_size: None,
+				trie_cache_adaptive: false,
+				state_pruning: Some(PruningMode::blocks_pruning(2)),
+				source: DatabaseSource::Custom {
+					db: batches_db.clone(),
+					require_create_flag: true,
+				},
+				blocks_pruning: BlocksPruning::KeepAll,
+				justifications_pruning: None,
+				transactions_pruning: None,
+			},
+			0,
+		)
+		.unwrap();
+		let batches_before = batches_db.batches.lock().len();
+
+		// The chunks are only produced when asked for, the nodes of the first ones must have been
+		// written before the last one is produced.
+		let chunks = (0..100).map(|i| {
+			if i == 99 {
+				assert!(batches_db.batches.lock().len() > batches_before + 1);
+			}
+			let child_info = (i >= 50).then(|| child_info.clone());
+			Ok((child_info, chunk((i % 50) * 20)))
+		});
+		backend
+			.import_state_in_batches(header.clone(), chunks, state_version, batch_bytes)
+			.unwrap();
+
+		let batches = batches_db.batches.lock().clone();
+		assert!(batches.len() > batches_before + 10);
+		assert!(batches.iter().all(|size| *size < 2 * batch_bytes), "{batches:?}");
+		assert_eq!(backend.blockchain().info().finalized_hash, header.hash());
+		let state = backend.state_at(header.hash()).unwrap();
+		assert_eq!(state.storage(&999u32.to_be_bytes()).unwrap(), Some(vec![999u32 as u8; 64]));
+		assert_eq!(
+			state.child_storage(&child_info, &42u32.to_be_bytes()).unwrap(),
+			Some(vec![42; 64])
+		);
+	}
+
+	#[test]
+	fn import_state_checkpoint_and_prune_it() {
+		let state_version = StateVersion::V1;
+		let top = vec![(vec![1], vec![1; 40]), (vec![2], vec![2; 40])];
+		let mut checkpoint = Header {
+			number: 10,
+			parent_hash: [7; 32].into(),
+			state_root: Default::default(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		checkpoint.state_root = {
+			let db = Backend::<Block>::new_test(2, 0);
+			let mut op = db.begin_operation().unwrap();
+			let storage = Storage {
+				top: top.iter().cloned().collect(),
+				children_default: Default::default(),
+			};
+			op.reset_storage(storage, state_version).unwrap()
+		};
+
+		// given
+		let backend = Backend::<Block>::new_test(2, 0);
+		backend
+			.import_state(checkpoint.clone(), vec![Ok((None, top.clone()))], state_version)
+			.unwrap();
+
+		// when
+		let mut hashes = vec![checkpoint.hash()];
+		for number in 11..15u64 {
+			let parent_hash = *hashes.last().unwrap();
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, parent_hash).unwrap();
+			let storage = vec![(vec![1], Some(vec![number as u8; 40]))];
+			let (root, overlay) = op.old_state.storage_root(
+				storage.iter().map(|(k, v)| (k.as_slice(), v.as_deref())),
+				state_version,
+			);
+			op.update_db_storage(overlay).unwrap();
+			op.update_storage(storage, Vec::new()).unwrap();
+			let header = Header {
+				number,
+				parent_hash,
+				state_root: root.into(),
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+			hashes.push(header.hash());
+			op.set_block_data(header, Some(vec![]), None, None, NewBlockState::Final)
+				.unwrap();
+			backend.commit_operation(op).unwrap();
+		}
+
+		// then
+		for (hash, number) in hashes.iter().zip(10u64..) {
+			assert_eq!(backend.have_state_at(*hash, number), number >= 13, "#{number}");
+		}
+		let checkpoint_root =
+			sp_state_machine::Storage::get(&*backend.storage, &checkpoint.state_root, (&[], None));
+		assert_eq!(checkpoint_root.unwrap(), None);
+		let state = backend.state_at(hashes[4]).unwrap();
+		assert_eq!(state.storage(&[1]).unwrap(), Some(vec![14; 40]));
+		assert_eq!(state.storage(&[2]).unwrap(), Some(vec![2; 40]));
+	}
+
+	#[test]
+	fn state_pruning_by_age_and_size() {
+		let state_version = StateVersion::V1;
+		let new_backend = |max_age, max_bytes| {
+			let db = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+			let constraints =
+				Constraints { max_blocks: None, max_age, max_bytes, timestamp_key: None };
+			let db_setting = DatabaseSettings {
+				trie_cache_maximum_size: None,
+				trie_cache_adaptive: false,
+				state_pruning: Some(PruningMode::Constrained(constraints)),
+				source: DatabaseSource::Custom { db, require_create_flag: true },
+				blocks_pruning: BlocksPruning::KeepAll,
+				justifications_pruning: None,
+				transactions_pruning: None,
+			};
+			Backend::<Block>::new(db_setting, 0).unwrap()
+		};
+
+		// Imports finalized blocks, each setting a timestamp 4 seconds after its parent and
+		// inserting a 4 KiB value.
+		let import_blocks = |backend: &Backend<Block>| {
+			let mut hashes: Vec<H256> = Vec::new();
+			for number in 0..6u64 {
+				let mut op = backend.begin_operation().unwrap();
+				let parent_hash = hashes.last().cloned().unwrap_or_default();
+				backend.begin_state_operation(&mut op, parent_hash).unwrap();
+				let mut header = Header {
+					number,
+					parent_hash,
+					state_root: Default::default(),
+					digest: Default::default(),
+					extrinsics_root: Default::default(),
+				};
+
+				if number == 0 {
+					header.state_root =
+						op.old_state.storage_root(std::iter::empty(), state_version).0.into();
+					op.reset_storage(Default::default(), state_version).unwrap();
+				} else {
+					let storage = vec![
+						(default_block_timestamp_key(), Some((number * 4_000).encode())),
+						(number.encode(), Some(vec![number as u8; 4096])),
+					];
+					let (root, overlay) = op.old_state.storage_root(
+						storage.iter().map(|(k, v)| (k.as_slice(), v.as_deref())),
+						state_version,
+					);
+					op.update_db_storage(overlay).unwrap();
+					op.update_storage(storage, Vec::new()).unwrap();
+					header.state_root = root.into();
+				}
+
+				hashes.push(header.hash());
+				op.set_block_data(header, Some(vec![]), None, None, NewBlockState::Final)
+					.unwrap();
+				backend.commit_operation(op).unwrap();
+			}
+			hashes
+		};
+
+		// The newest block has the timestamp 20s, so the states older than 10s are pruned. The
+		// genesis state has no timestamp and is pruned as well.
+		let backend = new_backend(Some(std::time::Duration::from_secs(10)), None);
+		let hashes = import_blocks(&backend);
+		for (number, hash) in hashes.iter().enumerate() {
+			assert_eq!(backend.have_state_at(*hash, number as u64), number >= 3, "#{number}");
+		}
+		assert_eq!(
+			backend
+				.state_at(hashes[5])
+				.unwrap()
+				.storage(&default_block_timestamp_key())
+				.unwrap(),
+			Some(20_000u64.encode())
+		);
+
+		// Every block inserts more than 4 KiB of state, so only the states of the last two
+		// blocks fit into 10 KB.
+		let backend = new_backend(None, Some(10_000));
+		let hashes = import_blocks(&backend);
+		for (number, hash) in hashes.into_iter().enumerate() {
+			assert_eq!(backend.have_state_at(hash, number as u64), number >= 4, "#{number}");
+		}
+	}
+
 	#[test]
 	fn delete_only_when_negative_rc() {
 		sp_tracing::try_init_simple();
@@ -3414,6 +4109,95 @@ pub(crate) mod tests {
 		}
 	}
 
+	#[test]
+	fn prune_block_data_independently() {
+		let pruned = |pruning: BlocksPruning, finalized: usize, number: usize| match pruning {
+			BlocksPruning::Some(n) => number + std::cmp::max(n, 1) as usize <= finalized,
+			BlocksPruning::KeepAll | BlocksPruning::KeepFinalized => false,
+		};
+		let pruning_modes = vec![
+			(BlocksPruning::Some(2), None, None),
+			(BlocksPruning::Some(2), Some(BlocksPruning::KeepAll), None),
+			(BlocksPruning::Some(2), Some(BlocksPruning::Some(1)), Some(BlocksPruning::KeepAll)),
+			(
+				BlocksPruning::Some(3),
+				Some(BlocksPruning::KeepFinalized),
+				Some(BlocksPruning::Some(1)),
+			),
+			(BlocksPruning::KeepAll, Some(BlocksPruning::Some(1)), Some(BlocksPruning::Some(3))),
+			(BlocksPruning::KeepFinalized, None, Some(BlocksPruning::Some(2))),
+			(BlocksPruning::KeepFinalized, None, None),
+		];
+
+		for (blocks_pruning, justifications_pruning, transactions_pruning) in pruning_modes {
+			let backend = Backend::<Block>::new(
+				DatabaseSettings {
+					trie_cache_maximum_size: None,
+					trie_cache_adaptive: false,
+					state_pruning: Some(PruningMode::ArchiveAll),
+					source: DatabaseSource::Custom {
+						db: sp_database::as_database(kvdb_memorydb::create(
+							crate::utils::NUM_COLUMNS,
+						)),
+						require_create_flag: true,
+					},
+					blocks_pruning,
+					justifications_pruning,
+					transactions_pruning,
+				},
+				0,
+			)
+			.unwrap();
+
+			let mut blocks = Vec::new();
+			let mut indexed = Vec::new();
+			let mut prev_hash = Default::default();
+			for i in 0..6 {
+				let x = ExtrinsicWrapper::from(i).encode();
+				let x_hash = <HashingFor<Block> as sp_core::Hasher>::hash(&x[1..]);
+				let index = vec![IndexOperation::Insert {
+					extrinsic: 0,
+					hash: x_hash.as_ref().to_vec(),
+					size: (x.len() - 1) as u32,
+				}];
+				let hash = insert_block(
+					&backend,
+					i,
+					prev_hash,
+					None,
+					Default::default(),
+					vec![i.into()],
+					Some(index),
+				)
+				.unwrap();
+				blocks.push(hash);
+				indexed.push(x_hash);
+				prev_hash = hash;
+			}
+			for i in 1..6 {
+				backend
+					.finalize_block(blocks[i], Some((CONS0_ENGINE_ID, vec![i as u8])))
+					.unwrap();
+			}
+
+			let bc = backend.blockchain();
+			let justifications_pruning = justifications_pruning.unwrap_or(blocks_pruning);
+			let transactions_pruning = transactions_pruning.unwrap_or(blocks_pruning);
+			for i in 0..6 {
+				// The body can not be restored once its indexed transactions are pruned.
+				let body_pruned =
+					pruned(blocks_pruning, 5, i) || pruned(transactions_pruning, 5, i);
+				let body = (!body_pruned).then(|| vec![(i as u64).into()]);
+				assert_eq!(body, bc.body(blocks[i]).unwrap());
+				assert_eq!(!body_pruned, bc.indexed_transaction(indexed[i]).unwrap().is_some());
+
+				let justifications = (i > 0 && !pruned(justifications_pruning, 5, i))
+					.then(|| Justifications::from((CONS0_ENGINE_ID, vec![i as u8])));
+				assert_eq!(justifications, bc.justifications(blocks[i]).unwrap());
+			}
+		}
+	}
+
 	#[test]
 	fn prune_blocks_on_finalize_with_fork() {
 		sp_tracing::try_init_simple();
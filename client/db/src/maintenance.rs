@@ -0,0 +1,938 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Offline inspection and maintenance of the client database.
+//!
+//! These helpers open the database files directly, bypassing [`sp_database::Database`], and
+//! therefore must only be used while no node is running on top of the same database.
+
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use crate::{
+	columns,
+	utils::{DatabaseType, NUM_COLUMNS},
+	DatabaseSource,
+};
+use sp_blockchain::{Error as ClientError, Result as ClientResult};
+use sp_runtime::traits::Block as BlockT;
+
+/// Key in the `META` column of the target database that stores the progress of a migration.
+#[cfg(feature = "rocksdb")]
+const MIGRATION_PROGRESS: &[u8] = b"backend_migration";
+
+/// Number of entries copied per commit when migrating a database.
+#[cfg(feature = "rocksdb")]
+const MIGRATION_BATCH_SIZE: u64 = 16 * 1024;
+
+/// Cache size in MiB of the RocksDB databases opened by a migration.
+#[cfg(feature = "rocksdb")]
+const MIGRATION_CACHE_SIZE: usize = 128;
+
+/// Returns a human readable name for the given column.
+pub fn column_name(col: u32) -> &'static str {
+	match col {
+		columns::META => "meta",
+		columns::STATE => "state",
+		columns::STATE_META => "state_meta",
+		columns::KEY_LOOKUP => "key_lookup",
+		columns::HEADER => "header",
+		columns::BODY => "body",
+		columns::JUSTIFICATIONS => "justifications",
+		columns::AUX => "aux",
+		columns::OFFCHAIN => "offchain",
+		columns::TRANSACTION => "transaction",
+		columns::BODY_INDEX => "body_index",
+		_ => "unused",
+	}
+}
+
+/// Distribution of reference counts of the entries of a ref-counted column.
+///
+/// Reference counts are grouped into power of two buckets. Each bucket is keyed by its inclusive
+/// upper bound, i.e. the bucket `4` counts all entries with a reference count of `3` or `4`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RefCountDistribution(BTreeMap<u32, u64>);
+
+impl RefCountDistribution {
+	/// Record an entry with the given reference count.
+	pub fn record(&mut self, ref_count: u32) {
+		*self
+			.0
+			.entry(ref_count.checked_next_power_of_two().unwrap_or(u32::MAX))
+			.or_default() += 1;
+	}
+
+	/// Iterate over the `(upper bound, number of entries)` buckets in ascending order.
+	pub fn buckets(&self) -> impl Iterator<Item = (u32, u64)> + '_ {
+		self.0.iter().map(|(bound, count)| (*bound, *count))
+	}
+}
+
+impl fmt::Display for RefCountDistribution {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for (i, (bound, count)) in self.buckets().enumerate() {
+			if i > 0 {
+				write!(f, ", ")?;
+			}
+			if bound <= 2 {
+				write!(f, "{}: {}", bound, count)?;
+			} else {
+				write!(f, "{}-{}: {}", bound / 2 + 1, bound, count)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Statistics of a single database column.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnStats {
+	/// Index of the column.
+	pub column: u32,
+	/// Number of keys stored in the column.
+	pub keys: u64,
+	/// Total size of all keys in bytes.
+	pub key_bytes: u64,
+	/// Total size of all values in bytes.
+	pub value_bytes: u64,
+	/// Reference count distribution, if the column is reference counted by the database.
+	pub ref_counts: Option<RefCountDistribution>,
+}
+
+impl ColumnStats {
+	fn new(column: u32) -> Self {
+		Self { column, ..Default::default() }
+	}
+
+	/// Human readable name of the column.
+	pub fn name(&self) -> &'static str {
+		column_name(self.column)
+	}
+}
+
+/// Statistics of all columns of a database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseStats {
+	/// Name of the database backend the statistics were collected from.
+	pub backend: &'static str,
+	/// Per column statistics, ordered by column index.
+	pub columns: Vec<ColumnStats>,
+}
+
+/// Collect per column statistics of the database at the given source.
+///
+/// This iterates over every entry of the database and may take a long time for big databases.
+pub fn database_stats(source: &DatabaseSource) -> ClientResult<DatabaseStats> {
+	match resolve(source)? {
+		ResolvedSource::ParityDb(path) => parity_db_stats(path),
+		#[cfg(feature = "rocksdb")]
+		ResolvedSource::RocksDb(path) => rocksdb_stats(path),
+	}
+}
+
+/// Trigger a manual compaction of all columns of the database at the given source.
+///
+/// Only supported by RocksDB, ParityDb reclaims space in its background tasks.
+pub fn compact_database(source: &DatabaseSource) -> ClientResult<()> {
+	match resolve(source)? {
+		ResolvedSource::ParityDb(_) =>
+			Err(ClientError::Backend("ParityDb does not support manual compaction".into())),
+		#[cfg(feature = "rocksdb")]
+		ResolvedSource::RocksDb(path) => rocksdb_compact(path),
+	}
+}
+
+/// Copy the database at `source` into a new database at `target` that uses the other backend.
+///
+/// RocksDB databases are copied into ParityDb and ParityDb databases into RocksDB. The copy is
+/// done in batches that also record the progress in the target database, so an interrupted
+/// migration continues where it stopped when started again. Finally the states of the best and
+/// of the finalized block are walked in the new database and checked against their state roots.
+///
+/// From RocksDB every column is copied. `STATE` entries are stored without their trie prefix and
+/// with one reference per RocksDB entry, and the reference counters kept by the RocksDB adapter
+/// for the `TRANSACTION` column are turned into ParityDb references.
+///
+/// ParityDb only keeps a hash of the keys, so the keys are rebuilt from the content of the
+/// database instead. All blocks reachable from the canonical chain are copied with their lookup
+/// entries, and the `STATE` keys are rebuilt by walking the state of every block. Entries of the
+/// `AUX` column are only copied for the given `aux_keys`, the `OFFCHAIN` column is not copied.
+///
+/// Only databases using the `archive` state pruning mode can be migrated, as the state db
+/// journals of the other modes refer to backend specific keys.
+pub fn migrate_database<Block: BlockT>(
+	source: &DatabaseSource,
+	target: &Path,
+	aux_keys: &[Vec<u8>],
+) -> ClientResult<()> {
+	match resolve(source)? {
+		#[cfg(feature = "rocksdb")]
+		ResolvedSource::ParityDb(path) => {
+			copy_parity_db_to_rocksdb::<Block>(path, target, aux_keys)?;
+			verify_migrated_state::<Block>(DatabaseSource::RocksDb {
+				path: target.into(),
+				cache_size: MIGRATION_CACHE_SIZE,
+			})
+		},
+		#[cfg(not(feature = "rocksdb"))]
+		ResolvedSource::ParityDb(_) => {
+			let _ = aux_keys;
+			Err(ClientError::Backend("Migrating to RocksDB requires the `rocksdb` feature".into()))
+		},
+		#[cfg(feature = "rocksdb")]
+		ResolvedSource::RocksDb(path) => {
+			copy_rocksdb_to_parity_db(path, target)?;
+			verify_migrated_state::<Block>(DatabaseSource::ParityDb { path: target.into() })
+		},
+	}
+}
+
+#[cfg(feature = "rocksdb")]
+fn copy_rocksdb_to_parity_db(source: &Path, target: &Path) -> ClientResult<()> {
+	use crate::DbHash;
+	use codec::{Decode, Encode};
+	use kvdb::KeyValueDB;
+	use sp_database::{Database, Transaction};
+
+	let mut config = kvdb_rocksdb::DatabaseConfig::with_columns(NUM_COLUMNS);
+	config.create_if_missing = false;
+	let source = kvdb_rocksdb::Database::open(&config, source).map_err(rocksdb_err)?;
+
+	// The state db stores its pruning mode under the `mode` key of the `STATE_META` column.
+	let mode = KeyValueDB::get(&source, columns::STATE_META, b"mode").map_err(rocksdb_err)?;
+	if mode.as_deref() != Some(crate::PruningMode::ArchiveAll.id()) {
+		return Err(ClientError::Backend(
+			"Only databases using the `archive` state pruning mode can be migrated".into(),
+		))
+	}
+
+	let target = crate::parity_db::open::<DbHash>(target, DatabaseType::Full, true, false)
+		.map_err(parity_db_err)?;
+	let (first_col, mut resume_at) = match target.get(columns::META, MIGRATION_PROGRESS) {
+		Some(progress) => <(u32, u64)>::decode(&mut &progress[..]).map_err(|e| {
+			ClientError::Backend(format!("Invalid migration progress in target database: {}", e))
+		})?,
+		None if target.get(columns::META, crate::utils::meta_keys::TYPE).is_some() =>
+			return Err(ClientError::Backend("Target database is not empty".into())),
+		None => (0, 0),
+	};
+	if (first_col, resume_at) != (0, 0) {
+		log::info!(target: "db", "Resuming migration at column {}, entry {}", first_col, resume_at);
+	}
+
+	for col in first_col..NUM_COLUMNS {
+		let mut transaction = Transaction::new();
+		let mut batch = 0;
+		let mut position = 0;
+		for entry in KeyValueDB::iter(&source, col) {
+			position += 1;
+			if position <= resume_at {
+				continue
+			}
+
+			let (key, value) = entry.map_err(rocksdb_err)?;
+			match col {
+				columns::STATE => {
+					if key.len() < crate::DB_HASH_LEN {
+						return Err(ClientError::Backend(format!(
+							"Invalid state key of length {}",
+							key.len()
+						)))
+					}
+					transaction.set_from_vec(col, &key[key.len() - crate::DB_HASH_LEN..], value);
+				},
+				columns::TRANSACTION => {
+					// Reference counters are stored next to the entries, under the entry key
+					// suffixed with a zero byte.
+					if key.len() == crate::DB_HASH_LEN + 1 && key.last() == Some(&0) {
+						continue
+					}
+					let mut counter_key = key.to_vec();
+					counter_key.push(0);
+					let references = KeyValueDB::get(&source, col, &counter_key)
+						.map_err(rocksdb_err)?
+						.and_then(|counter| <[u8; 4]>::try_from(&counter[..]).ok())
+						.map_or(1, u32::from_le_bytes);
+					for _ in 0..references {
+						transaction.set(col, &key, &value);
+					}
+				},
+				_ => transaction.set_from_vec(col, &key, value),
+			}
+
+			batch += 1;
+			if batch == MIGRATION_BATCH_SIZE {
+				transaction.set_from_vec(
+					columns::META,
+					MIGRATION_PROGRESS,
+					(col, position).encode(),
+				);
+				target.commit(std::mem::take(&mut transaction))?;
+				batch = 0;
+				log::info!(
+					target: "db",
+					"Migrated {} entries of column {}",
+					position,
+					column_name(col),
+				);
+			}
+		}
+
+		transaction.set_from_vec(columns::META, MIGRATION_PROGRESS, (col + 1, 0u64).encode());
+		target.commit(transaction)?;
+		resume_at = 0;
+		log::info!(target: "db", "Migrated column {}", column_name(col));
+	}
+
+	let mut transaction = Transaction::new();
+	transaction.remove(columns::META, MIGRATION_PROGRESS);
+	target.commit(transaction)?;
+	Ok(())
+}
+
+/// Copy the ParityDb database at `source` into a new RocksDB database at `target`.
+///
+/// The blocks are copied one by one, in the order of [`for_each_block`]. Every block is
+/// committed together with the number of blocks copied so far, which is where an interrupted
+/// migration continues.
+#[cfg(feature = "rocksdb")]
+fn copy_parity_db_to_rocksdb<Block: BlockT>(
+	source: &Path,
+	target: &Path,
+	aux_keys: &[Vec<u8>],
+) -> ClientResult<()> {
+	use crate::{utils, DbExtrinsic};
+	use codec::{Decode, Encode};
+	use sc_client_api::backend::Backend as _;
+	use sp_database::{Database, Transaction};
+	use sp_runtime::traits::HashingFor;
+	use sp_state_machine::backend::AsTrieBackend;
+	use std::collections::HashMap;
+
+	let source = open_backend::<Block>(DatabaseSource::ParityDb { path: source.into() })?;
+	let source_db = source.storage.db.clone();
+
+	// The state db stores its pruning mode under the `mode` key of the `STATE_META` column.
+	let mode = source_db.get(columns::STATE_META, b"mode");
+	if mode.as_deref() != Some(crate::PruningMode::ArchiveAll.id()) {
+		return Err(ClientError::Backend(
+			"Only databases using the `archive` state pruning mode can be migrated".into(),
+		))
+	}
+
+	let mut config = kvdb_rocksdb::DatabaseConfig::with_columns(NUM_COLUMNS);
+	config.create_if_missing = true;
+	let target_db = kvdb_rocksdb::Database::open(&config, target).map_err(rocksdb_err)?;
+	crate::upgrade::update_version(target).map_err(rocksdb_err)?;
+	let target: std::sync::Arc<dyn Database<DbHash>> = sp_database::as_database(target_db);
+
+	let resume_at = match target.get(columns::META, MIGRATION_PROGRESS) {
+		Some(progress) => u64::decode(&mut &progress[..]).map_err(|e| {
+			ClientError::Backend(format!("Invalid migration progress in target database: {}", e))
+		})?,
+		None if target.get(columns::META, utils::meta_keys::TYPE).is_some() =>
+			return Err(ClientError::Backend("Target database is not empty".into())),
+		None => 0,
+	};
+	if resume_at != 0 {
+		log::info!(target: "db", "Resuming migration at block {}", resume_at);
+	}
+
+	let copy = |transaction: &mut Transaction<DbHash>, col, key: &[u8]| {
+		if let Some(value) = source_db.get(col, key) {
+			transaction.set_from_vec(col, key, value);
+		}
+	};
+
+	let mut position = 0;
+	for_each_block(source.blockchain(), |hash, number, canonical| {
+		position += 1;
+		if position <= resume_at {
+			return Ok(())
+		}
+
+		let mut transaction = Transaction::new();
+		let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
+		copy(&mut transaction, columns::KEY_LOOKUP, hash.as_ref());
+		if canonical {
+			copy(&mut transaction, columns::KEY_LOOKUP, &utils::number_index_key(number)?);
+		}
+		for col in [columns::HEADER, columns::BODY, columns::JUSTIFICATIONS, columns::BODY_INDEX] {
+			copy(&mut transaction, col, &lookup_key);
+		}
+		let mut children_key = utils::meta_keys::CHILDREN_PREFIX.to_vec();
+		hash.using_encoded(|hash| children_key.extend(hash));
+		copy(&mut transaction, columns::META, &children_key);
+
+		// Every indexed extrinsic holds one reference to its transaction. The RocksDB adapter
+		// keeps the reference counter under the entry key suffixed with a zero byte.
+		let mut references = HashMap::<DbHash, u32>::new();
+		if let Some(index) = source_db.get(columns::BODY_INDEX, &lookup_key) {
+			let index = Vec::<DbExtrinsic<Block>>::decode(&mut &index[..]).map_err(|e| {
+				ClientError::Backend(format!("Invalid body index of block {:?}: {}", hash, e))
+			})?;
+			for extrinsic in index {
+				if let DbExtrinsic::Indexed { hash, .. } = extrinsic {
+					*references.entry(hash).or_default() += 1;
+				}
+			}
+		}
+		for (hash, count) in references {
+			let Some(value) = source_db.get(columns::TRANSACTION, hash.as_ref()) else { continue };
+			let mut counter_key = hash.as_ref().to_vec();
+			counter_key.push(0);
+			let existing = target
+				.get(columns::TRANSACTION, &counter_key)
+				.and_then(|counter| <[u8; 4]>::try_from(&counter[..]).ok())
+				.map_or(0, u32::from_le_bytes);
+			if existing == 0 {
+				transaction.set_from_vec(columns::TRANSACTION, hash.as_ref(), value);
+			}
+			transaction.set(columns::TRANSACTION, &counter_key, &(existing + count).to_le_bytes());
+		}
+
+		if source.have_state_at(hash, number) {
+			// Only the block that was interrupted may have left incomplete subtrees behind.
+			let skip_copied = position != resume_at + 1;
+			let state = source.state_at(hash)?;
+			let trie = state.as_trie_backend();
+			let mut tries = vec![(*trie.root(), None)];
+			tries.extend(child_tries::<Block, _>(&state)?.into_iter().map(|(r, k)| (r, Some(k))));
+			for (root, keyspace) in tries {
+				copy_trie::<HashingFor<Block>>(
+					trie.essence(),
+					&root,
+					keyspace.as_deref(),
+					&*target,
+					&mut transaction,
+					skip_copied,
+				)?;
+			}
+		}
+
+		transaction.set_from_vec(columns::META, MIGRATION_PROGRESS, position.encode());
+		target.commit(transaction)?;
+		if position % 1024 == 0 {
+			log::info!(target: "db", "Migrated {} blocks, at block {:?}", position, number);
+		}
+		Ok(())
+	})?;
+
+	let mut transaction = Transaction::new();
+	for key in [
+		&utils::meta_keys::TYPE[..],
+		utils::meta_keys::BEST_BLOCK,
+		utils::meta_keys::FINALIZED_BLOCK,
+		utils::meta_keys::FINALIZED_STATE,
+		utils::meta_keys::BLOCK_GAP,
+		utils::meta_keys::GENESIS_HASH,
+		utils::meta_keys::LEAF_PREFIX,
+	] {
+		copy(&mut transaction, columns::META, key);
+	}
+	copy(&mut transaction, columns::STATE_META, b"mode");
+	for key in aux_keys {
+		copy(&mut transaction, columns::AUX, key);
+	}
+	transaction.remove(columns::META, MIGRATION_PROGRESS);
+	target.commit(transaction)?;
+
+	log::info!(
+		target: "db",
+		"Migrated {} blocks. The offchain storage and the auxiliary data of other keys than the \
+		 {} given ones were not migrated",
+		position,
+		aux_keys.len(),
+	);
+	Ok(())
+}
+
+/// Call `f` with the hash and number of every block of `blockchain`, and whether it is part of
+/// the canonical chain.
+///
+/// The canonical blocks are visited in ascending order, each followed by the blocks of the forks
+/// starting at it. Blocks that are only part of forks below a gap in the canonical chain are not
+/// visited.
+#[cfg(feature = "rocksdb")]
+fn for_each_block<Block: BlockT>(
+	blockchain: &crate::BlockchainDb<Block>,
+	mut f: impl FnMut(Block::Hash, sp_runtime::traits::NumberFor<Block>, bool) -> ClientResult<()>,
+) -> ClientResult<()> {
+	use sp_blockchain::{Backend as _, HeaderBackend};
+	use sp_runtime::traits::{One, Zero};
+
+	let best_number = blockchain.info().best_number;
+	let mut number = Zero::zero();
+	let mut hash = blockchain.hash(number)?;
+	while number <= best_number {
+		let next = number + One::one();
+		let next_hash = blockchain.hash(next)?;
+		if let Some(hash) = hash {
+			f(hash, number, true)?;
+
+			let mut forks = blockchain
+				.children(hash)?
+				.into_iter()
+				.filter(|child| Some(*child) != next_hash)
+				.map(|child| (child, next))
+				.collect::<Vec<_>>();
+			while let Some((hash, number)) = forks.pop() {
+				f(hash, number, false)?;
+				let next = number + One::one();
+				forks.extend(blockchain.children(hash)?.into_iter().map(|child| (child, next)));
+			}
+		}
+
+		number = next;
+		hash = next_hash;
+	}
+
+	Ok(())
+}
+
+/// Copy the nodes of the trie at `root` into the `STATE` column of the RocksDB `target`, under
+/// their prefixed keys.
+///
+/// The nodes are added to `transaction`, which is committed whenever it holds a full batch. With
+/// `skip_copied`, the nodes that are already present in `target` are skipped together with their
+/// children. Nodes are added before their children, so this is only correct if all the nodes in
+/// `target` were copied as part of a complete trie.
+#[cfg(feature = "rocksdb")]
+fn copy_trie<H: hash_db::Hasher>(
+	source: &dyn hash_db::HashDBRef<H, sp_trie::DBValue>,
+	root: &H::Out,
+	keyspace: Option<&[u8]>,
+	target: &dyn sp_database::Database<DbHash>,
+	transaction: &mut sp_database::Transaction<DbHash>,
+	skip_copied: bool,
+) -> ClientResult<()> {
+	let mut result = Ok(());
+	sp_trie::walk_trie_nodes::<H>(source, root, keyspace, |prefix, hash, data| {
+		let key = sp_trie::prefixed_key::<H>(hash, prefix);
+		if result.is_err() || (skip_copied && target.contains(columns::STATE, &key)) {
+			return false
+		}
+
+		transaction.set(columns::STATE, &key, data);
+		if transaction.0.len() as u64 >= MIGRATION_BATCH_SIZE {
+			result = target.commit(std::mem::take(transaction)).map_err(ClientError::from);
+		}
+		true
+	})
+	.map_err(|e| ClientError::Backend(format!("Incomplete state in source database: {:?}", e)))?;
+
+	result
+}
+
+/// Open the client backend of the database at `source`, using its stored pruning mode.
+#[cfg(feature = "rocksdb")]
+fn open_backend<Block: BlockT>(source: DatabaseSource) -> ClientResult<crate::Backend<Block>> {
+	use crate::{Backend, BlocksPruning, DatabaseSettings};
+
+	Backend::<Block>::new(
+		DatabaseSettings {
+			trie_cache_maximum_size: None,
+			trie_cache_adaptive: false,
+			state_pruning: None,
+			source,
+			blocks_pruning: BlocksPruning::KeepAll,
+			justifications_pruning: None,
+			transactions_pruning: None,
+		},
+		0,
+	)
+}
+
+/// The roots and keyspaces of the child tries of `state`.
+#[cfg(feature = "rocksdb")]
+fn child_tries<
+	Block: BlockT,
+	S: sp_state_machine::Backend<sp_runtime::traits::HashingFor<Block>>,
+>(
+	state: &S,
+) -> ClientResult<Vec<(Block::Hash, Vec<u8>)>> {
+	use codec::Decode;
+	use sp_core::storage::well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX;
+	use sp_state_machine::IterArgs;
+
+	let pairs = state
+		.pairs(IterArgs { prefix: Some(DEFAULT_CHILD_STORAGE_KEY_PREFIX), ..Default::default() })
+		.map_err(ClientError::Backend)?;
+
+	let mut tries = Vec::new();
+	for pair in pairs {
+		let (key, root) = pair.map_err(ClientError::Backend)?;
+		let root = Decode::decode(&mut &root[..])
+			.map_err(|e| ClientError::Backend(format!("Invalid child trie root: {}", e)))?;
+		tries.push((root, key[DEFAULT_CHILD_STORAGE_KEY_PREFIX.len()..].to_vec()));
+	}
+	Ok(tries)
+}
+
+/// Check that the states of the best and of the finalized block are complete and consistent
+/// with their state roots in the migrated database at `target`.
+#[cfg(feature = "rocksdb")]
+fn verify_migrated_state<Block: BlockT>(target: DatabaseSource) -> ClientResult<()> {
+	use sc_client_api::backend::Backend as _;
+	use sp_blockchain::HeaderBackend;
+	use sp_runtime::traits::HashingFor;
+	use sp_state_machine::backend::AsTrieBackend;
+
+	let backend = open_backend::<Block>(target)?;
+
+	let info = backend.blockchain().info();
+	let mut hashes = vec![info.best_hash];
+	if info.finalized_hash != info.best_hash {
+		hashes.push(info.finalized_hash);
+	}
+
+	for hash in hashes {
+		let state = backend.state_at(hash)?;
+		let trie = state.as_trie_backend();
+		verify_trie::<HashingFor<Block>>(trie.essence(), trie.root(), None)?;
+
+		for (root, keyspace) in child_tries::<Block, _>(&state)? {
+			verify_trie::<HashingFor<Block>>(trie.essence(), &root, Some(&keyspace[..]))?;
+		}
+
+		log::info!(target: "db", "Verified migrated state of block {:?}", hash);
+	}
+
+	Ok(())
+}
+
+/// Walk the trie at `root` and check that every node is present and matches its hash.
+#[cfg(feature = "rocksdb")]
+fn verify_trie<H: hash_db::Hasher>(
+	db: &dyn hash_db::HashDBRef<H, sp_trie::DBValue>,
+	root: &H::Out,
+	keyspace: Option<&[u8]>,
+) -> ClientResult<()> {
+	let mut corrupted = None;
+	sp_trie::walk_trie_nodes::<H>(db, root, keyspace, |_, hash, data| {
+		if H::hash(data) != *hash {
+			corrupted = Some(*hash);
+			return false
+		}
+		true
+	})
+	.map_err(|e| ClientError::Backend(format!("Incomplete migrated state: {:?}", e)))?;
+
+	match corrupted {
+		Some(hash) =>
+			Err(ClientError::Backend(format!("Corrupted trie node {:?} in migrated state", hash))),
+		None => Ok(()),
+	}
+}
+
+enum ResolvedSource<'a> {
+	ParityDb(&'a Path),
+	#[cfg(feature = "rocksdb")]
+	RocksDb(&'a Path),
+}
+
+fn resolve(source: &DatabaseSource) -> ClientResult<ResolvedSource> {
+	match source {
+		DatabaseSource::ParityDb { path } => Ok(ResolvedSource::ParityDb(path)),
+		#[cfg(feature = "rocksdb")]
+		DatabaseSource::RocksDb { path, .. } => Ok(ResolvedSource::RocksDb(path)),
+		#[cfg(feature = "rocksdb")]
+		DatabaseSource::Auto { rocksdb_path, .. } if rocksdb_path.join("CURRENT").exists() =>
+			Ok(ResolvedSource::RocksDb(rocksdb_path)),
+		DatabaseSource::Auto { paritydb_path, .. } => Ok(ResolvedSource::ParityDb(paritydb_path)),
+		DatabaseSource::Custom { .. } =>
+			Err(ClientError::Backend("Custom database implementations are not supported".into())),
+	}
+}
+
+fn parity_db_stats(path: &Path) -> ClientResult<DatabaseStats> {
+	let options = crate::parity_db::options(path, DatabaseType::Full);
+	let db = parity_db::Db::open_read_only(&options).map_err(parity_db_err)?;
+
+	let mut stats = Vec::with_capacity(NUM_COLUMNS as usize);
+	for col in 0..NUM_COLUMNS {
+		let mut col_stats = ColumnStats::new(col);
+		let mut ref_counts = RefCountDistribution::default();
+		db.iter_column_while(col as u8, |item| {
+			col_stats.keys += 1;
+			col_stats.key_bytes += item.key.len() as u64;
+			col_stats.value_bytes += item.value.len() as u64;
+			ref_counts.record(item.rc);
+			true
+		})
+		.map_err(parity_db_err)?;
+
+		if crate::parity_db::ref_counted_column(col) {
+			col_stats.ref_counts = Some(ref_counts);
+		}
+		stats.push(col_stats);
+	}
+
+	Ok(DatabaseStats { backend: "ParityDb", columns: stats })
+}
+
+fn parity_db_err(err: parity_db::Error) -> ClientError {
+	ClientError::Backend(format!("ParityDb error: {}", err))
+}
+
+#[cfg(feature = "rocksdb")]
+fn rocksdb_stats(path: &Path) -> ClientResult<DatabaseStats> {
+	use kvdb::KeyValueDB;
+
+	let mut config = kvdb_rocksdb::DatabaseConfig::with_columns(NUM_COLUMNS);
+	config.create_if_missing = false;
+	let db = kvdb_rocksdb::Database::open(&config, path).map_err(rocksdb_err)?;
+
+	let mut stats = Vec::with_capacity(NUM_COLUMNS as usize);
+	for col in 0..NUM_COLUMNS {
+		let mut col_stats = ColumnStats::new(col);
+		// The `kvdb` adapter stores the reference counter of an entry next to it, under the
+		// entry key suffixed with a zero byte. Only the transaction column has fixed size keys,
+		// state nodes are reference counted in memory by the state db and never persisted with
+		// a counter.
+		let mut ref_counts = (col == columns::TRANSACTION).then(RefCountDistribution::default);
+		for entry in KeyValueDB::iter(&db, col) {
+			let (key, value) = entry.map_err(rocksdb_err)?;
+			if let Some(ref_counts) = ref_counts.as_mut() {
+				if key.len() == crate::DB_HASH_LEN + 1 && key.last() == Some(&0) {
+					if let Ok(counter) = <[u8; 4]>::try_from(&value[..]) {
+						ref_counts.record(u32::from_le_bytes(counter));
+						continue
+					}
+				}
+			}
+			col_stats.keys += 1;
+			col_stats.key_bytes += key.len() as u64;
+			col_stats.value_bytes += value.len() as u64;
+		}
+		col_stats.ref_counts = ref_counts;
+		stats.push(col_stats);
+	}
+
+	Ok(DatabaseStats { backend: "RocksDb", columns: stats })
+}
+
+#[cfg(feature = "rocksdb")]
+fn rocksdb_compact(path: &Path) -> ClientResult<()> {
+	// `kvdb-rocksdb` names the column families `col0`, `col1`, ...
+	let names = (0..NUM_COLUMNS).map(|col| format!("col{}", col)).collect::<Vec<_>>();
+	let mut options = rocksdb::Options::default();
+	options.create_if_missing(false);
+	let db = rocksdb::DB::open_cf(&options, path, &names).map_err(rocksdb_err)?;
+
+	for name in &names {
+		let cf = db
+			.cf_handle(name)
+			.ok_or_else(|| ClientError::Backend(format!("Missing column family {}", name)))?;
+		log::info!(target: "db", "Compacting column family {}", name);
+		db.compact_range_cf(cf, None::<&[u8]>, None::<&[u8]>);
+	}
+	Ok(())
+}
+
+#[cfg(feature = "rocksdb")]
+fn rocksdb_err(err: impl fmt::Display) -> ClientError {
+	ClientError::Backend(format!("RocksDb error: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn ref_count_distribution_buckets() {
+		let mut distribution = RefCountDistribution::default();
+		for rc in [1, 1, 2, 3, 4, 5, 100] {
+			distribution.record(rc);
+		}
+
+		assert_eq!(
+			distribution.buckets().collect::<Vec<_>>(),
+			vec![(1, 2), (2, 1), (4, 2), (8, 1), (128, 1)],
+		);
+		assert_eq!(distribution.to_string(), "1: 2, 2: 1, 3-4: 2, 5-8: 1, 65-128: 1");
+	}
+
+	#[test]
+	fn parity_db_stats_work() {
+		let tmp = tempfile::tempdir().unwrap();
+		let source = DatabaseSource::ParityDb { path: tmp.path().into() };
+		let db = crate::utils::open_database::<substrate_test_runtime_client::runtime::Block>(
+			&source,
+			DatabaseType::Full,
+			true,
+		)
+		.unwrap();
+
+		let mut tx = sp_database::Transaction::new();
+		tx.set(columns::AUX, b"key", b"value");
+		tx.store(columns::STATE, crate::DbHash::repeat_byte(1), vec![1, 2, 3]);
+		tx.store(columns::STATE, crate::DbHash::repeat_byte(2), vec![4, 5]);
+		tx.reference(columns::STATE, crate::DbHash::repeat_byte(2));
+		db.commit(tx).unwrap();
+		drop(db);
+
+		let stats = database_stats(&source).unwrap();
+		assert_eq!(stats.backend, "ParityDb");
+
+		let aux = &stats.columns[columns::AUX as usize];
+		assert_eq!((aux.keys, aux.value_bytes), (1, 5));
+		assert!(aux.ref_counts.is_none());
+
+		let state = &stats.columns[columns::STATE as usize];
+		assert_eq!(state.keys, 2);
+		assert_eq!(
+			state.ref_counts.as_ref().unwrap().buckets().collect::<Vec<_>>(),
+			vec![(1, 1), (2, 1)],
+		);
+
+		assert!(compact_database(&source).is_err());
+	}
+
+	#[cfg(feature = "rocksdb")]
+	#[test]
+	fn rocksdb_to_parity_db_migration_works() {
+		use crate::{
+			tests::{insert_header, Block},
+			Backend, BlocksPruning, DatabaseSettings, PruningMode,
+		};
+		use sc_client_api::backend::Backend as _;
+		use sp_blockchain::HeaderBackend;
+		use sp_state_machine::Backend as _;
+
+		let tmp = tempfile::tempdir().unwrap();
+		let settings = |source| DatabaseSettings {
+			trie_cache_maximum_size: None,
+			trie_cache_adaptive: false,
+			state_pruning: Some(PruningMode::ArchiveAll),
+			source,
+			blocks_pruning: BlocksPruning::KeepAll,
+			justifications_pruning: None,
+			transactions_pruning: None,
+		};
+		let source = DatabaseSource::RocksDb { path: tmp.path().join("rocksdb"), cache_size: 16 };
+		let target = tmp.path().join("paritydb");
+
+		let (parent, best) = {
+			let backend = Backend::<Block>::new(settings(source.clone()), 0).unwrap();
+			let mut parent = Default::default();
+			let mut best = insert_header(&backend, 0, parent, None, Default::default());
+			for number in 1..4 {
+				parent = best;
+				best = insert_header(&backend, number, parent, None, Default::default());
+			}
+			(parent, best)
+		};
+
+		migrate_database::<Block>(&source, &target, &[]).unwrap();
+
+		let backend =
+			Backend::<Block>::new(settings(DatabaseSource::ParityDb { path: target.clone() }), 0)
+				.unwrap();
+		assert_eq!(backend.blockchain().info().best_hash, best);
+		assert_eq!(
+			backend.state_at(best).unwrap().storage(parent.as_ref()).unwrap(),
+			Some(parent.as_ref().to_vec()),
+		);
+		drop(backend);
+
+		// A finished migration is not started again.
+		assert!(migrate_database::<Block>(&source, &target, &[]).is_err());
+	}
+
+	#[cfg(feature = "rocksdb")]
+	#[test]
+	fn parity_db_to_rocksdb_migration_works() {
+		use crate::{
+			tests::{insert_block, insert_header, insert_header_no_head, Block},
+			Backend, BlocksPruning, DatabaseSettings, PruningMode,
+		};
+		use codec::Encode;
+		use sc_client_api::backend::{AuxStore, Backend as _};
+		use sp_blockchain::{Backend as _, HeaderBackend};
+		use sp_core::{Hasher, H256};
+		use sp_runtime::{testing::ExtrinsicWrapper, traits::HashingFor};
+		use sp_state_machine::{Backend as _, IndexOperation};
+
+		let tmp = tempfile::tempdir().unwrap();
+		let settings = |source| DatabaseSettings {
+			trie_cache_maximum_size: None,
+			trie_cache_adaptive: false,
+			state_pruning: Some(PruningMode::ArchiveAll),
+			source,
+			blocks_pruning: BlocksPruning::KeepAll,
+			justifications_pruning: None,
+			transactions_pruning: None,
+		};
+		let source = DatabaseSource::ParityDb { path: tmp.path().join("paritydb") };
+		let target = tmp.path().join("rocksdb");
+
+		let extrinsic = ExtrinsicWrapper::from(1u64).encode();
+		let extrinsic_hash = <HashingFor<Block> as Hasher>::hash(&extrinsic[1..]);
+		let (parent, best, fork) = {
+			let backend = Backend::<Block>::new(settings(source.clone()), 0).unwrap();
+			let genesis = insert_header(&backend, 0, Default::default(), None, Default::default());
+			let parent = insert_block(
+				&backend,
+				1,
+				genesis,
+				None,
+				Default::default(),
+				vec![1.into()],
+				Some(vec![IndexOperation::Insert {
+					extrinsic: 0,
+					hash: extrinsic_hash.as_ref().to_vec(),
+					size: (extrinsic.len() - 1) as u32,
+				}]),
+			)
+			.unwrap();
+			let fork = insert_header_no_head(&backend, 1, genesis, H256::repeat_byte(1));
+			let best = insert_header(&backend, 2, parent, None, Default::default());
+			backend.insert_aux(&[(&b"listed"[..], &b"value"[..])], &[]).unwrap();
+			backend.insert_aux(&[(&b"unlisted"[..], &b"value"[..])], &[]).unwrap();
+			(parent, best, fork)
+		};
+
+		migrate_database::<Block>(&source, &target, &[b"listed".to_vec()]).unwrap();
+
+		let backend = Backend::<Block>::new(
+			settings(DatabaseSource::RocksDb { path: target.clone(), cache_size: 16 }),
+			0,
+		)
+		.unwrap();
+		let blockchain = backend.blockchain();
+		assert_eq!(blockchain.info().best_hash, best);
+		assert_eq!(blockchain.hash(1).unwrap(), Some(parent));
+		assert_eq!(blockchain.number(fork).unwrap(), Some(1));
+		assert_eq!(
+			backend.state_at(best).unwrap().storage(parent.as_ref()).unwrap(),
+			Some(parent.as_ref().to_vec()),
+		);
+		assert_eq!(blockchain.body(parent).unwrap(), Some(vec![1.into()]));
+		assert_eq!(
+			blockchain.indexed_transaction(extrinsic_hash).unwrap(),
+			Some(extrinsic[1..].to_vec()),
+		);
+		assert_eq!(backend.get_aux(b"listed").unwrap(), Some(b"value".to_vec()));
+		assert_eq!(backend.get_aux(b"unlisted").unwrap(), None);
+		drop(backend);
+
+		// A finished migration is not started again.
+		assert!(migrate_database::<Block>(&source, &target, &[]).is_err());
+	}
+}
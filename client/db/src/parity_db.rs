@@ -33,13 +33,8 @@ fn handle_err<T>(result: parity_db::Result<T>) -> T {
 	}
 }
 
-/// Wrap parity-db database into a trait object that implements `sp_database::Database`
-pub fn open<H: Clone + AsRef<[u8]>>(
-	path: &std::path::Path,
-	db_type: DatabaseType,
-	create: bool,
-	upgrade: bool,
-) -> parity_db::Result<std::sync::Arc<dyn Database<H>>> {
+/// Database options matching the column layout used by the client for the given database type.
+pub(crate) fn options(path: &std::path::Path, db_type: DatabaseType) -> parity_db::Options {
 	let mut config = parity_db::Options::with_columns(path, NUM_COLUMNS as u8);
 
 	match db_type {
@@ -70,6 +65,18 @@ pub fn open<H: Clone + AsRef<[u8]>>(
 		},
 	}
 
+	config
+}
+
+/// Wrap parity-db database into a trait object that implements `sp_database::Database`
+pub fn open<H: Clone + AsRef<[u8]>>(
+	path: &std::path::Path,
+	db_type: DatabaseType,
+	create: bool,
+	upgrade: bool,
+) -> parity_db::Result<std::sync::Arc<dyn Database<H>>> {
+	let config = options(path, db_type);
+
 	if upgrade {
 		log::info!("Upgrading database metadata.");
 		if let Some(meta) = parity_db::Options::load_metadata(path)? {
@@ -86,7 +93,7 @@ pub fn open<H: Clone + AsRef<[u8]>>(
 	Ok(std::sync::Arc::new(DbAdapter(db)))
 }
 
-fn ref_counted_column(col: u32) -> bool {
+pub(crate) fn ref_counted_column(col: u32) -> bool {
 	col == columns::TRANSACTION || col == columns::STATE
 }
 
@@ -0,0 +1,232 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Building the tries of a state imported by [`Backend::import_state`].
+//!
+//! The tries are built from their sorted pairs as the pairs arrive, and their nodes are written
+//! to the database in batches, so an imported state is never held in memory as a whole.
+//!
+//! [`Backend::import_state`]: crate::Backend::import_state
+
+use crate::{columns, DbHash, StateChunk};
+use hash_db::{AsHashDB, HashDB, Hasher, Prefix};
+use sp_blockchain::{Error as ClientError, Result as ClientResult};
+use sp_core::{hexdisplay::HexDisplay, storage::ChildInfo};
+use sp_database::{error::DatabaseError, Database, Transaction};
+use sp_runtime::StateVersion;
+use sp_state_machine::DBValue;
+use sp_trie::{prefixed_key, LayoutV0, LayoutV1, TrieConfiguration};
+use std::marker::PhantomData;
+
+/// Writes trie nodes to the state column, committing them once `batch_bytes` are pending.
+pub(crate) struct NodeWriter<'a, H> {
+	db: &'a dyn Database<DbHash>,
+	transaction: Transaction<DbHash>,
+	pending_bytes: usize,
+	batch_bytes: usize,
+	size: u64,
+	error: Option<DatabaseError>,
+	_phantom: PhantomData<H>,
+}
+
+impl<'a, H: Hasher> NodeWriter<'a, H> {
+	pub fn new(db: &'a dyn Database<DbHash>, batch_bytes: usize) -> Self {
+		Self {
+			db,
+			transaction: Transaction::new(),
+			pending_bytes: 0,
+			batch_bytes,
+			size: 0,
+			error: None,
+			_phantom: PhantomData,
+		}
+	}
+
+	/// Total size of the written nodes.
+	pub fn size(&self) -> u64 {
+		self.size
+	}
+
+	/// Write the node `value` with the database `key`.
+	pub fn write(&mut self, key: Vec<u8>, value: DBValue) {
+		self.pending_bytes += key.len() + value.len();
+		self.size += value.len() as u64;
+		self.transaction.set_from_vec(columns::STATE, &key, value);
+		self.commit_if_full();
+	}
+
+	/// Delete the node with the database `key`.
+	pub fn delete(&mut self, key: &[u8]) {
+		self.pending_bytes += key.len();
+		self.transaction.remove(columns::STATE, key);
+		self.commit_if_full();
+	}
+
+	/// Commit the pending nodes, returns the first error of any commit.
+	pub fn flush(&mut self) -> ClientResult<()> {
+		self.commit();
+		match self.error.take() {
+			Some(e) => Err(e.into()),
+			None => Ok(()),
+		}
+	}
+
+	fn commit_if_full(&mut self) {
+		if self.pending_bytes >= self.batch_bytes {
+			self.commit();
+		}
+	}
+
+	fn commit(&mut self) {
+		let transaction = std::mem::take(&mut self.transaction);
+		self.pending_bytes = 0;
+		if self.error.is_none() {
+			self.error = self.db.commit(transaction).err();
+		}
+	}
+
+	fn database_key(&self, key: &H::Out, prefix: Prefix) -> Vec<u8> {
+		let mut key = prefixed_key::<H>(key, prefix);
+		self.db.sanitize_key(&mut key);
+		key
+	}
+}
+
+// Building a trie from its sorted pairs only inserts nodes, it never reads any.
+impl<'a, H: Hasher> HashDB<H, DBValue> for NodeWriter<'a, H> {
+	fn get(&self, _key: &H::Out, _prefix: Prefix) -> Option<DBValue> {
+		None
+	}
+
+	fn contains(&self, _key: &H::Out, _prefix: Prefix) -> bool {
+		false
+	}
+
+	fn insert(&mut self, prefix: Prefix, value: &[u8]) -> H::Out {
+		let key = H::hash(value);
+		self.emplace(key, prefix, value.to_vec());
+		key
+	}
+
+	fn emplace(&mut self, key: H::Out, prefix: Prefix, value: DBValue) {
+		let key = self.database_key(&key, prefix);
+		self.write(key, value);
+	}
+
+	fn remove(&mut self, key: &H::Out, prefix: Prefix) {
+		let key = self.database_key(key, prefix);
+		self.delete(&key);
+	}
+}
+
+impl<'a, H: Hasher> AsHashDB<H, DBValue> for NodeWriter<'a, H> {
+	fn as_hash_db(&self) -> &dyn HashDB<H, DBValue> {
+		self
+	}
+
+	fn as_hash_db_mut<'b>(&'b mut self) -> &'b mut (dyn HashDB<H, DBValue> + 'b) {
+		self
+	}
+}
+
+/// Build the trie of the pairs of `chunk` and of the chunks following it in `chunks` that belong
+/// to the same trie, inserting its nodes into `db`.
+///
+/// Returns the root of the trie and the first chunk of the next trie, if any.
+pub(crate) fn build_trie<H, DB, I>(
+	db: &mut DB,
+	chunk: StateChunk,
+	chunks: &mut I,
+	state_version: StateVersion,
+) -> ClientResult<(H::Out, Option<StateChunk>)>
+where
+	H: Hasher,
+	DB: HashDB<H, DBValue>,
+	I: Iterator<Item = ClientResult<StateChunk>>,
+{
+	let (child_info, pairs) = chunk;
+	let mut pairs = TriePairs {
+		chunks,
+		child_info,
+		pairs: pairs.into_iter(),
+		last_key: None,
+		next: None,
+		error: None,
+	};
+	let root = match state_version {
+		StateVersion::V0 => LayoutV0::<H>::trie_build(db, &mut pairs),
+		StateVersion::V1 => LayoutV1::<H>::trie_build(db, &mut pairs),
+	};
+
+	match pairs.error {
+		Some(e) => Err(e),
+		None => Ok((root, pairs.next)),
+	}
+}
+
+/// The pairs of consecutive chunks of one trie.
+///
+/// Ends at the first chunk of another trie, which is kept in `next`, or at the first error, which
+/// is kept in `error`.
+struct TriePairs<'a, I> {
+	chunks: &'a mut I,
+	child_info: Option<ChildInfo>,
+	pairs: std::vec::IntoIter<(Vec<u8>, Vec<u8>)>,
+	last_key: Option<Vec<u8>>,
+	next: Option<StateChunk>,
+	error: Option<ClientError>,
+}
+
+impl<'a, I: Iterator<Item = ClientResult<StateChunk>>> Iterator for TriePairs<'a, I> {
+	type Item = (Vec<u8>, Vec<u8>);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some((key, value)) = self.pairs.next() {
+				// The trie is only built correctly from strictly sorted keys.
+				if self.last_key.as_ref().map_or(false, |last_key| *last_key >= key) {
+					self.error = Some(ClientError::Backend(format!(
+						"The keys of the imported state are not sorted at 0x{}",
+						HexDisplay::from(&key)
+					)));
+					return None
+				}
+				self.last_key = Some(key.clone());
+				return Some((key, value))
+			}
+
+			match self.chunks.next()? {
+				Ok((child_info, pairs))
+					if storage_key(&child_info) == storage_key(&self.child_info) =>
+					self.pairs = pairs.into_iter(),
+				Ok(chunk) => {
+					self.next = Some(chunk);
+					return None
+				},
+				Err(e) => {
+					self.error = Some(e);
+					return None
+				},
+			}
+		}
+	}
+}
+
+fn storage_key(child_info: &Option<ChildInfo>) -> Option<&[u8]> {
+	child_info.as_ref().map(|child_info| child_info.storage_key())
+}
@@ -0,0 +1,484 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Offline analysis of the migration of a state from [`StateVersion::V0`] to
+//! [`StateVersion::V1`].
+//!
+//! [`state_migration_report`] rebuilds the whole state under both layouts without writing
+//! anything, which tells how far the state is from the V1 layout and how much smaller its nodes
+//! become. It can additionally replay the key order of `pallet-state-trie-migration` to predict
+//! how many blocks and how much weight the on-chain automatic migration would take.
+//!
+//! [`StateVersion::V0`]: sp_core::storage::StateVersion::V0
+//! [`StateVersion::V1`]: sp_core::storage::StateVersion::V1
+
+use std::fmt;
+
+use hash_db::Hasher;
+use sp_blockchain::{Error as ClientError, Result as ClientResult};
+use sp_core::storage::{
+	well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX, ChildInfo, StateVersion,
+};
+use sp_state_machine::{Backend as StateBackend, IterArgs};
+use sp_trie::{trie_layout_stats, LayoutV0, LayoutV1, TrieLayout, TrieLayoutStats};
+
+/// Limits of a single block of the automatic migration, as configured in
+/// `pallet-state-trie-migration`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationLimits {
+	/// Maximum number of keys migrated per block.
+	pub item: u32,
+	/// Maximum number of value bytes migrated per block.
+	pub size: u32,
+}
+
+/// Reference time, in picoseconds, charged by `pallet-state-trie-migration`.
+///
+/// The defaults are the weights of the pallet benchmarked on the reference hardware together with
+/// the RocksDb database weights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationWeights {
+	/// Weight of a database read.
+	pub db_read: u64,
+	/// Weight of a database write.
+	pub db_write: u64,
+	/// Base weight of processing the keys of one block.
+	pub base: u64,
+	/// Weight per migrated value byte.
+	pub per_byte: u64,
+}
+
+impl Default for MigrationWeights {
+	fn default() -> Self {
+		Self { db_read: 25_000_000, db_write: 100_000_000, base: 5_560_000, per_byte: 1_139 }
+	}
+}
+
+impl MigrationWeights {
+	/// Weight of a block migrating `items` keys with `size` value bytes in total.
+	///
+	/// Mirrors `dynamic_weight` of the pallet: every key is read and written once, and the
+	/// migration progress is read and written once per block.
+	pub fn block_weight(&self, items: u32, size: u32) -> u64 {
+		let read_write = self.db_read.saturating_add(self.db_write);
+		read_write
+			.saturating_mul(items as u64)
+			.saturating_add(self.base)
+			.saturating_add(self.per_byte.saturating_mul(size as u64))
+			.saturating_add(read_write)
+	}
+}
+
+/// Predicted cost of migrating the whole state with the automatic migration of
+/// `pallet-state-trie-migration`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MigrationEstimate {
+	/// Number of blocks until the migration reports that it is finished.
+	pub blocks: u64,
+	/// Number of migrated keys, top and child keys together.
+	pub items: u64,
+	/// Number of migrated value bytes.
+	pub bytes: u64,
+	/// Sum of the reference time of all blocks.
+	pub total_weight: u64,
+	/// Highest reference time of a single block.
+	pub max_block_weight: u64,
+}
+
+/// Replays the ticks of `migrate_until_exhaustion` over the keys of the state.
+///
+/// The pallet migrates the empty key before the first key of every trie, and the child keys right
+/// after the top key holding the child root. A block stops as soon as one of the limits is
+/// reached, and the tick noticing that the top trie is exhausted always needs a block of its own
+/// or the remainder of the last one.
+struct DryRun {
+	limits: MigrationLimits,
+	weights: MigrationWeights,
+	pending_start: bool,
+	items: u32,
+	size: u32,
+	estimate: MigrationEstimate,
+}
+
+impl DryRun {
+	fn new(limits: MigrationLimits, weights: MigrationWeights) -> Self {
+		Self {
+			limits,
+			weights,
+			pending_start: false,
+			items: 0,
+			size: 0,
+			estimate: Default::default(),
+		}
+	}
+
+	fn start_trie(&mut self) {
+		self.pending_start = true;
+	}
+
+	fn key(&mut self, key: &[u8], size: usize) {
+		if std::mem::take(&mut self.pending_start) {
+			if key.is_empty() {
+				self.tick(size);
+				return
+			}
+			self.tick(0);
+		}
+		self.tick(size);
+	}
+
+	fn end_trie(&mut self) {
+		if std::mem::take(&mut self.pending_start) {
+			self.tick(0);
+		}
+	}
+
+	fn tick(&mut self, size: usize) {
+		self.items += 1;
+		self.size = self.size.saturating_add(size.try_into().unwrap_or(u32::MAX));
+		if self.items >= self.limits.item || self.size >= self.limits.size {
+			self.close_block();
+		}
+	}
+
+	fn close_block(&mut self) {
+		let weight = self.weights.block_weight(self.items, self.size);
+		self.estimate.blocks += 1;
+		self.estimate.items += self.items as u64;
+		self.estimate.bytes += self.size as u64;
+		self.estimate.total_weight = self.estimate.total_weight.saturating_add(weight);
+		self.estimate.max_block_weight = self.estimate.max_block_weight.max(weight);
+		self.items = 0;
+		self.size = 0;
+	}
+
+	fn finish(mut self) -> MigrationEstimate {
+		self.close_block();
+		self.estimate
+	}
+}
+
+/// Counts the keys of the state and feeds the dry-run, if any.
+#[derive(Default)]
+struct Observer {
+	top_keys: u64,
+	child_tries: u64,
+	child_keys: u64,
+	dry_run: Option<DryRun>,
+}
+
+impl Observer {
+	fn start_trie(&mut self, child: bool) {
+		if child {
+			self.child_tries += 1;
+		}
+		if let Some(dry_run) = self.dry_run.as_mut() {
+			dry_run.start_trie();
+		}
+	}
+
+	fn key(&mut self, child: bool, key: &[u8], size: usize) {
+		if child {
+			self.child_keys += 1;
+		} else {
+			self.top_keys += 1;
+		}
+		if let Some(dry_run) = self.dry_run.as_mut() {
+			dry_run.key(key, size);
+		}
+	}
+
+	fn end_trie(&mut self) {
+		if let Some(dry_run) = self.dry_run.as_mut() {
+			dry_run.end_trie();
+		}
+	}
+}
+
+fn add_stats<H>(into: &mut TrieLayoutStats<H>, other: &TrieLayoutStats<H>) {
+	into.nodes += other.nodes;
+	into.node_bytes += other.node_bytes;
+	into.hashed_values += other.hashed_values;
+	into.hashed_value_bytes += other.hashed_value_bytes;
+}
+
+/// Compute the stats of the trie `child_info` of `state` under the layout `L`, including the
+/// stats of all child tries when computing the top trie.
+///
+/// The root values of the child tries are replaced by the roots under `L`, so the resulting top
+/// root is the root the state would have once fully stored with `L`.
+fn layout_stats<L, S>(
+	state: &S,
+	child_info: Option<&ChildInfo>,
+	mut observer: Option<&mut Observer>,
+) -> ClientResult<TrieLayoutStats<<L::Hash as Hasher>::Out>>
+where
+	L: TrieLayout,
+	S: StateBackend<L::Hash>,
+{
+	let mut args = IterArgs::default();
+	args.child_info = child_info.cloned();
+	let pairs = state.pairs(args).map_err(|e| ClientError::Backend(e.to_string()))?;
+
+	let is_child = child_info.is_some();
+	if let Some(observer) = observer.as_deref_mut() {
+		observer.start_trie(is_child);
+	}
+
+	let mut children = TrieLayoutStats::default();
+	let mut error = None;
+	let input = pairs.map_while(|pair| {
+		let (key, value) = match pair {
+			Ok(pair) => pair,
+			Err(e) => {
+				error = Some(ClientError::Backend(e.to_string()));
+				return None
+			},
+		};
+		if let Some(observer) = observer.as_deref_mut() {
+			observer.key(is_child, &key, value.len());
+		}
+
+		let Some(storage_key) =
+			key.strip_prefix(DEFAULT_CHILD_STORAGE_KEY_PREFIX).filter(|_| !is_child)
+		else {
+			return Some((key, value))
+		};
+		let child_info = ChildInfo::new_default(storage_key);
+		match layout_stats::<L, S>(state, Some(&child_info), observer.as_deref_mut()) {
+			Ok(child) => {
+				add_stats(&mut children, &child);
+				Some((key, child.root.as_ref().to_vec()))
+			},
+			Err(e) => {
+				error = Some(e);
+				None
+			},
+		}
+	});
+	let mut stats = trie_layout_stats::<L, _, _, _>(input);
+
+	if let Some(e) = error {
+		return Err(e)
+	}
+	if let Some(observer) = observer {
+		observer.end_trie();
+	}
+	add_stats(&mut stats, &children);
+	Ok(stats)
+}
+
+/// How far the state is from the V1 layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationStatus {
+	/// The state is entirely stored with the V0 layout.
+	V0,
+	/// The state is entirely stored with the V1 layout.
+	V1,
+	/// No value of the state is large enough to be affected by the layout.
+	Unaffected,
+	/// Some of the values are stored with the V1 layout.
+	Partial,
+}
+
+/// Result of [`state_migration_report`].
+#[derive(Debug, Clone)]
+pub struct StateMigrationReport<H> {
+	/// The root of the state as it is stored.
+	pub current_root: H,
+	/// The state, including all child tries, under the V0 layout.
+	pub v0: TrieLayoutStats<H>,
+	/// The state, including all child tries, under the V1 layout.
+	pub v1: TrieLayoutStats<H>,
+	/// Number of keys in the top trie.
+	pub top_keys: u64,
+	/// Number of default child tries.
+	pub child_tries: u64,
+	/// Number of keys in all child tries together.
+	pub child_keys: u64,
+	/// The predicted cost of the on-chain migration, if a dry-run was requested.
+	pub dry_run: Option<MigrationEstimate>,
+}
+
+impl<H: PartialEq> StateMigrationReport<H> {
+	/// How far the state is from the V1 layout.
+	pub fn status(&self) -> MigrationStatus {
+		match (self.current_root == self.v0.root, self.current_root == self.v1.root) {
+			(true, true) => MigrationStatus::Unaffected,
+			(true, false) => MigrationStatus::V0,
+			(false, true) => MigrationStatus::V1,
+			(false, false) => MigrationStatus::Partial,
+		}
+	}
+}
+
+impl<H: PartialEq + fmt::Debug> fmt::Display for StateMigrationReport<H> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let status = match self.status() {
+			MigrationStatus::V0 => "not migrated",
+			MigrationStatus::V1 => "fully migrated",
+			MigrationStatus::Unaffected => "not affected by the migration",
+			MigrationStatus::Partial => "partially migrated",
+		};
+		writeln!(f, "Current root: {:?} ({})", self.current_root, status)?;
+		writeln!(
+			f,
+			"Keys: {} top keys, {} child keys in {} child tries",
+			self.top_keys, self.child_keys, self.child_tries,
+		)?;
+		for (name, stats) in [("V0", &self.v0), ("V1", &self.v1)] {
+			writeln!(
+				f,
+				"{} layout: root {:?}, {} nodes of {} bytes, {} hashed values of {} bytes",
+				name,
+				stats.root,
+				stats.nodes,
+				stats.node_bytes,
+				stats.hashed_values,
+				stats.hashed_value_bytes,
+			)?;
+		}
+		writeln!(
+			f,
+			"Node bytes saved by V1: {} ({:.2}%)",
+			self.v0.node_bytes.saturating_sub(self.v1.node_bytes),
+			percent(self.v0.node_bytes.saturating_sub(self.v1.node_bytes), self.v0.node_bytes),
+		)?;
+		if let Some(estimate) = &self.dry_run {
+			writeln!(
+				f,
+				"Dry-run: {} blocks migrating {} keys and {} bytes",
+				estimate.blocks, estimate.items, estimate.bytes,
+			)?;
+			writeln!(
+				f,
+				"Dry-run weight: {} ps in total, at most {} ps per block",
+				estimate.total_weight, estimate.max_block_weight,
+			)?;
+		}
+		Ok(())
+	}
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+	if total == 0 {
+		0.0
+	} else {
+		part as f64 * 100.0 / total as f64
+	}
+}
+
+/// Rebuild `state` under the V0 and the V1 layout and compare the results.
+///
+/// When `dry_run` is given, the automatic migration of `pallet-state-trie-migration` is replayed
+/// with the given limits and weights, starting from the beginning of the state.
+///
+/// The state is iterated twice in full, so this takes a while on large states.
+pub fn state_migration_report<H, S>(
+	state: &S,
+	dry_run: Option<(MigrationLimits, MigrationWeights)>,
+) -> ClientResult<StateMigrationReport<H::Out>>
+where
+	H: Hasher,
+	H::Out: Ord,
+	S: StateBackend<H>,
+{
+	if let Some((limits, _)) = &dry_run {
+		if limits.item == 0 || limits.size == 0 {
+			return Err(ClientError::Backend("Migration limits must not be zero".into()))
+		}
+	}
+
+	let mut observer = Observer {
+		dry_run: dry_run.map(|(limits, weights)| DryRun::new(limits, weights)),
+		..Default::default()
+	};
+	let v0 = layout_stats::<LayoutV0<H>, S>(state, None, Some(&mut observer))?;
+	let v1 = layout_stats::<LayoutV1<H>, S>(state, None, None)?;
+	let (current_root, _) = state.storage_root(std::iter::empty(), StateVersion::V1);
+
+	Ok(StateMigrationReport {
+		current_root,
+		v0,
+		v1,
+		top_keys: observer.top_keys,
+		child_tries: observer.child_tries,
+		child_keys: observer.child_keys,
+		dry_run: observer.dry_run.map(DryRun::finish),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::storage::{Storage, StorageChild};
+	use sp_runtime::traits::BlakeTwo256;
+	use sp_state_machine::InMemoryBackend;
+
+	fn storage() -> Storage {
+		let child_info = ChildInfo::new_default(b"child");
+		let mut storage = Storage::default();
+		for i in 0..16u8 {
+			storage.top.insert(vec![1, i], vec![i; 64]);
+		}
+		storage.children_default.insert(
+			child_info.storage_key().to_vec(),
+			StorageChild {
+				data: (0..8u8).map(|i| (vec![i], vec![i; 48])).collect(),
+				child_info: child_info.clone(),
+			},
+		);
+		storage
+	}
+
+	#[test]
+	fn report_matches_both_layouts() {
+		let v0_state = InMemoryBackend::<BlakeTwo256>::from((storage(), StateVersion::V0));
+		let v1_state = InMemoryBackend::<BlakeTwo256>::from((storage(), StateVersion::V1));
+		let v1_root = v1_state.storage_root(std::iter::empty(), StateVersion::V1).0;
+
+		let report = state_migration_report(&v0_state, None).unwrap();
+		assert_eq!(report.status(), MigrationStatus::V0);
+		assert_eq!(report.v1.root, v1_root);
+		assert_eq!((report.top_keys, report.child_tries, report.child_keys), (17, 1, 8));
+		assert_eq!(report.v1.hashed_values, 24);
+		assert!(report.v1.node_bytes < report.v0.node_bytes);
+
+		let report = state_migration_report(&v1_state, None).unwrap();
+		assert_eq!(report.status(), MigrationStatus::V1);
+	}
+
+	#[test]
+	fn dry_run_follows_the_pallet_ticks() {
+		let state = InMemoryBackend::<BlakeTwo256>::from((storage(), StateVersion::V0));
+		let weights = MigrationWeights::default();
+		let limits = MigrationLimits { item: 10, size: u32::MAX };
+
+		let estimate = state_migration_report(&state, Some((limits, weights)))
+			.unwrap()
+			.dry_run
+			.unwrap();
+		// 17 top keys and 8 child keys, plus the empty key of both tries.
+		assert_eq!(estimate.items, 27);
+		assert_eq!(estimate.bytes, 16 * 64 + 32 + 8 * 48);
+		// The last block only holds 7 child keys and the final tick.
+		assert_eq!(estimate.blocks, 3);
+		// The first block holds the empty key and 9 top keys.
+		assert_eq!(estimate.max_block_weight, weights.block_weight(10, 9 * 64));
+	}
+}
@@ -56,6 +56,7 @@ fn initialize(
 			let config = sc_executor_wasmtime::Config {
 				allow_missing_func_imports,
 				cache_path: None,
+				profiler: None,
 				semantics: sc_executor_wasmtime::Semantics {
 					heap_alloc_strategy: DEFAULT_HEAP_ALLOC_STRATEGY,
 					instantiation_strategy,
@@ -66,6 +67,7 @@ fn initialize(
 					wasm_bulk_memory: false,
 					wasm_reference_types: false,
 					wasm_simd: false,
+					call_metering: false,
 				},
 			};
 
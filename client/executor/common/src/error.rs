@@ -99,6 +99,12 @@ pub enum Error {
 
 	#[error("Output exceeds bounds of wasm memory")]
 	OutputExceedsBounds,
+
+	#[error("Execution aborted, the call ran out of its budget")]
+	CallBudgetExhausted,
+
+	#[error("The executor was not configured to meter calls")]
+	CallMeteringUnsupported,
 }
 
 impl From<&'static str> for Error {
@@ -145,6 +151,10 @@ pub enum WasmError {
 	#[error("{0}")]
 	Instantiation(String),
 
+	/// The runtime imports host functions that are not provided.
+	#[error("The runtime is incompatible with the host functions of the node. {0}")]
+	IncompatibleHostFunctions(crate::host_functions::CompatibilityReport),
+
 	/// Other error happenend.
 	#[error("Other error happened while constructing the runtime: {0}")]
 	Other(String),
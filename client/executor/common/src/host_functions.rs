@@ -0,0 +1,264 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! A registry of the host functions provided by a node.
+//!
+//! The registry lists the host functions with their names, versions and signatures. Checking a
+//! runtime against it yields a [`CompatibilityReport`] that names every host function the runtime
+//! imports but the node does not provide, which is clearer than the error of the linker.
+
+use crate::{error::WasmError, runtime_blob::RuntimeBlob};
+use sp_wasm_interface::{HostFunctions, Signature, ValueType};
+use std::{collections::BTreeMap, fmt};
+
+/// The suffix `#[runtime_interface]` appends to the name of a host function before its version.
+const VERSION_SUFFIX: &str = "_version_";
+
+/// A host function, as provided by a node or imported by a runtime.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HostFunctionInfo {
+	/// The name of the function without its version, e.g. `ext_storage_get`.
+	pub name: String,
+	/// The version of the function, `None` if its name doesn't carry one.
+	pub version: Option<u32>,
+	/// The signature of the function.
+	pub signature: SignatureDisplay,
+}
+
+impl HostFunctionInfo {
+	/// Creates the info of the host function with the given full `name`, e.g.
+	/// `ext_storage_get_version_1`.
+	pub fn new(name: &str, signature: Signature) -> Self {
+		let (name, version) = match name.rsplit_once(VERSION_SUFFIX) {
+			Some((name, version)) => match version.parse() {
+				Ok(version) => (name, Some(version)),
+				Err(_) => (name, None),
+			},
+			None => (name, None),
+		};
+
+		Self { name: name.into(), version, signature: SignatureDisplay(signature) }
+	}
+
+	/// The name of the function as it is imported by the runtime.
+	pub fn full_name(&self) -> String {
+		match self.version {
+			Some(version) => format!("{}{}{}", self.name, VERSION_SUFFIX, version),
+			None => self.name.clone(),
+		}
+	}
+}
+
+impl fmt::Display for HostFunctionInfo {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.version {
+			Some(version) => write!(f, "{} v{}: {}", self.name, version, self.signature),
+			None => write!(f, "{}: {}", self.name, self.signature),
+		}
+	}
+}
+
+/// A [`Signature`] that is displayed like `(i32, i64) -> i64`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureDisplay(pub Signature);
+
+impl PartialOrd for SignatureDisplay {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for SignatureDisplay {
+	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+		self.to_string().cmp(&other.to_string())
+	}
+}
+
+impl fmt::Display for SignatureDisplay {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let name = |value_type: &ValueType| match value_type {
+			ValueType::I32 => "i32",
+			ValueType::I64 => "i64",
+			ValueType::F32 => "f32",
+			ValueType::F64 => "f64",
+		};
+
+		let args = self.0.args.iter().map(name).collect::<Vec<_>>();
+		write!(f, "({})", args.join(", "))?;
+		if let Some(return_value) = &self.0.return_value {
+			write!(f, " -> {}", name(return_value))?;
+		}
+		Ok(())
+	}
+}
+
+/// The host functions provided by a node, by name and version.
+#[derive(Debug, Clone, Default)]
+pub struct HostFunctionCatalog {
+	functions: BTreeMap<(String, Option<u32>), HostFunctionInfo>,
+}
+
+impl HostFunctionCatalog {
+	/// Creates the registry of the host functions `H`, e.g. `sp_io::SubstrateHostFunctions`.
+	pub fn new<H: HostFunctions>() -> Self {
+		Self::from_functions(
+			H::host_functions()
+				.into_iter()
+				.map(|function| HostFunctionInfo::new(function.name(), function.signature())),
+		)
+	}
+
+	/// Creates the registry of the given host functions.
+	pub fn from_functions(functions: impl IntoIterator<Item = HostFunctionInfo>) -> Self {
+		Self {
+			functions: functions
+				.into_iter()
+				.map(|function| ((function.name.clone(), function.version), function))
+				.collect(),
+		}
+	}
+
+	/// The host functions, ordered by name and version.
+	pub fn functions(&self) -> impl Iterator<Item = &HostFunctionInfo> {
+		self.functions.values()
+	}
+
+	/// The versions of the host function `name` that are provided.
+	pub fn versions(&self, name: &str) -> Vec<u32> {
+		self.functions
+			.range((name.to_owned(), None)..=(name.to_owned(), Some(u32::MAX)))
+			.filter_map(|((_, version), _)| *version)
+			.collect()
+	}
+
+	/// Checks that the host functions imported by the runtime `blob` are provided.
+	pub fn check(&self, blob: &RuntimeBlob) -> CompatibilityReport {
+		self.check_imports(
+			blob.imported_functions()
+				.into_iter()
+				.map(|(name, signature)| HostFunctionInfo::new(&name, signature)),
+		)
+	}
+
+	/// Returns an [`WasmError::IncompatibleHostFunctions`] error unless every host function
+	/// imported by the runtime `blob` is provided.
+	pub fn ensure_compatible(&self, blob: &RuntimeBlob) -> Result<(), WasmError> {
+		let report = self.check(blob);
+		if report.is_compatible() {
+			Ok(())
+		} else {
+			Err(WasmError::IncompatibleHostFunctions(report))
+		}
+	}
+
+	/// Checks that the host functions imported by the runtime `code`, which may be compressed,
+	/// are provided.
+	pub fn check_code(&self, code: &[u8]) -> Result<CompatibilityReport, WasmError> {
+		RuntimeBlob::uncompress_if_needed(code).map(|blob| self.check(&blob))
+	}
+
+	/// Checks that the given imported host functions are provided.
+	pub fn check_imports(
+		&self,
+		imports: impl IntoIterator<Item = HostFunctionInfo>,
+	) -> CompatibilityReport {
+		let mut report = CompatibilityReport::default();
+		for import in imports {
+			match self.functions.get(&(import.name.clone(), import.version)) {
+				Some(provided) if provided.signature == import.signature => {},
+				Some(provided) => report.mismatched.push(MismatchedHostFunction {
+					provided: provided.signature.clone(),
+					imported: import,
+				}),
+				None => report.missing.push(MissingHostFunction {
+					provided_versions: self.versions(&import.name),
+					imported: import,
+				}),
+			}
+		}
+		report.missing.sort_by(|a, b| a.imported.cmp(&b.imported));
+		report.mismatched.sort_by(|a, b| a.imported.cmp(&b.imported));
+
+		report
+	}
+}
+
+/// A host function imported by a runtime that is not provided.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingHostFunction {
+	/// The imported function.
+	pub imported: HostFunctionInfo,
+	/// The versions of the function that are provided instead.
+	pub provided_versions: Vec<u32>,
+}
+
+/// A host function imported by a runtime with a different signature than the provided one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MismatchedHostFunction {
+	/// The imported function.
+	pub imported: HostFunctionInfo,
+	/// The signature of the provided function.
+	pub provided: SignatureDisplay,
+}
+
+/// The result of checking a runtime against a [`HostFunctionCatalog`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatibilityReport {
+	/// The imported host functions that are not provided.
+	pub missing: Vec<MissingHostFunction>,
+	/// The imported host functions that are provided with a different signature.
+	pub mismatched: Vec<MismatchedHostFunction>,
+}
+
+impl CompatibilityReport {
+	/// Whether every imported host function is provided.
+	pub fn is_compatible(&self) -> bool {
+		self.missing.is_empty() && self.mismatched.is_empty()
+	}
+}
+
+impl fmt::Display for CompatibilityReport {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.is_compatible() {
+			return writeln!(f, "All host functions imported by the runtime are provided.")
+		}
+
+		if !self.missing.is_empty() {
+			writeln!(f, "The runtime imports host functions that are not provided:")?;
+			for missing in &self.missing {
+				let provided = match &missing.provided_versions[..] {
+					[] => "no version is provided".to_owned(),
+					versions => format!(
+						"provided versions: {}",
+						versions.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+					),
+				};
+				writeln!(f, "  {} ({})", missing.imported, provided)?;
+			}
+		}
+
+		if !self.mismatched.is_empty() {
+			writeln!(f, "The runtime imports host functions with a different signature:")?;
+			for mismatched in &self.mismatched {
+				writeln!(f, "  {} (provided: {})", mismatched.imported, mismatched.provided)?;
+			}
+		}
+
+		Ok(())
+	}
+}
@@ -38,6 +38,9 @@
 //!   execution deterministic, which in turn is needed by the Parachain Validation Function in
 //! Polkadot.
 //!
+//! - profiling needs to know which function is running. The instrumentation reports every function
+//!   call to the host, since the engine can't attribute time to the functions of the runtime.
+//!
 //! ## Inspection
 //!
 //! Inspection of a wasm module may be needed to extract some useful information, such as to extract
@@ -48,8 +51,10 @@
 
 mod data_segments_snapshot;
 mod globals_snapshot;
+mod profiling_hooks;
 mod runtime_blob;
 
 pub use data_segments_snapshot::DataSegmentsSnapshot;
 pub use globals_snapshot::{ExposedMutableGlobalsSet, GlobalsSnapshot, InstanceGlobals};
+pub use profiling_hooks::{PROFILER_ENTER_HOOK, PROFILER_EXIT_HOOK};
 pub use runtime_blob::RuntimeBlob;
@@ -0,0 +1,198 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Instrumentation reporting every function call of a module to the host.
+//!
+//! Every function, including the imported ones, gets a wrapper that calls [`PROFILER_ENTER_HOOK`]
+//! before and [`PROFILER_EXIT_HOOK`] after calling the wrapped function. Both hooks receive the
+//! index the function had in the original module. All calls, exports and table entries are then
+//! redirected to the wrappers.
+
+use wasm_instrument::parity_wasm::elements::{
+	External, Func, FuncBody, FunctionType, ImportCountType, ImportEntry, ImportSection,
+	Instruction, Instructions, Internal, Module, Section, Type, ValueType,
+};
+
+/// The name of the host function called when a function is entered.
+///
+/// It is imported from the `env` module and takes the index of the function as an `i32`.
+pub const PROFILER_ENTER_HOOK: &str = "sc_profiler_enter";
+
+/// The name of the host function called when a function returns.
+///
+/// It is imported from the `env` module and takes the index of the function as an `i32`.
+pub const PROFILER_EXIT_HOOK: &str = "sc_profiler_exit";
+
+/// Returns the names of all functions of `module`, indexed like the function index space.
+///
+/// Imported functions are named after the imported field, defined functions after the entries of
+/// the name section. Functions without a name are called `wasm-function[index]`.
+pub(super) fn function_names(module: &Module) -> Vec<String> {
+	let mut names: Vec<String> = module
+		.import_section()
+		.map(|section| {
+			section
+				.entries()
+				.iter()
+				.filter(|entry| matches!(entry.external(), External::Function(_)))
+				.map(|entry| entry.field().to_owned())
+				.collect()
+		})
+		.unwrap_or_default();
+	let imported = names.len();
+	let defined = module.function_section().map_or(0, |section| section.entries().len());
+	names.extend((imported..imported + defined).map(|index| format!("wasm-function[{}]", index)));
+
+	let module = match module.clone().parse_names() {
+		Ok(module) => module,
+		// The names we could parse are still useful.
+		Err((_, module)) => module,
+	};
+	let function_names = module.names_section().and_then(|section| section.functions());
+	if let Some(function_names) = function_names {
+		for (index, name) in function_names.names().iter() {
+			if let Some(slot) = names.get_mut(index as usize) {
+				*slot = format!("{:#}", rustc_demangle::demangle(name));
+			}
+		}
+	}
+
+	names
+}
+
+/// Inserts the profiling hooks into `module`.
+pub(super) fn inject(module: &mut Module) -> Result<(), &'static str> {
+	let imported = module.import_count(ImportCountType::Function) as u32;
+	let mut function_types = module
+		.import_section()
+		.map(|section| {
+			section
+				.entries()
+				.iter()
+				.filter_map(|entry| match entry.external() {
+					External::Function(type_ref) => Some(*type_ref),
+					_ => None,
+				})
+				.collect::<Vec<_>>()
+		})
+		.unwrap_or_default();
+	function_types.extend(
+		module
+			.function_section()
+			.map(|section| section.entries().iter().map(Func::type_ref).collect::<Vec<_>>())
+			.unwrap_or_default(),
+	);
+	let functions = function_types.len() as u32;
+
+	// The hooks are appended to the imported functions, shifting the defined ones by two.
+	let enter_hook = imported;
+	let exit_hook = imported + 1;
+	let shifted = |index: u32| if index < imported { index } else { index + 2 };
+	let wrapper = |index: u32| functions + 2 + index;
+
+	let types = module.type_section_mut().ok_or("the module has no type section")?.types_mut();
+	let hook_type = types
+		.iter()
+		.position(|ty| match ty {
+			Type::Function(ty) => ty.params() == [ValueType::I32] && ty.results().is_empty(),
+		})
+		.unwrap_or_else(|| {
+			types.push(Type::Function(FunctionType::new(vec![ValueType::I32], vec![])));
+			types.len() - 1
+		}) as u32;
+	let params = types
+		.iter()
+		.map(|ty| match ty {
+			Type::Function(ty) => ty.params().len() as u32,
+		})
+		.collect::<Vec<_>>();
+
+	if module.import_section().is_none() {
+		module
+			.insert_section(Section::Import(ImportSection::default()))
+			.map_err(|_| "cannot insert an import section")?;
+	}
+	let imports = module.import_section_mut().ok_or("the module has no import section")?;
+	for hook in [PROFILER_ENTER_HOOK, PROFILER_EXIT_HOOK] {
+		imports.entries_mut().push(ImportEntry::new(
+			"env".into(),
+			hook.into(),
+			External::Function(hook_type),
+		));
+	}
+
+	if let Some(code) = module.code_section_mut() {
+		for body in code.bodies_mut() {
+			for instruction in body.code_mut().elements_mut() {
+				if let Instruction::Call(index) = instruction {
+					*index = wrapper(*index);
+				}
+			}
+		}
+	}
+	if let Some(exports) = module.export_section_mut() {
+		for export in exports.entries_mut() {
+			if let Internal::Function(index) = export.internal_mut() {
+				*index = wrapper(*index);
+			}
+		}
+	}
+	if let Some(elements) = module.elements_section_mut() {
+		for segment in elements.entries_mut() {
+			for index in segment.members_mut() {
+				*index = wrapper(*index);
+			}
+		}
+	}
+	if let Some(start) = module.start_section() {
+		module.set_start_section(shifted(start));
+	}
+
+	let mut wrappers = Vec::with_capacity(function_types.len());
+	for (index, type_ref) in function_types.iter().enumerate() {
+		let index = index as u32;
+		let mut body = vec![Instruction::I32Const(index as i32), Instruction::Call(enter_hook)];
+		body.extend((0..params[*type_ref as usize]).map(Instruction::GetLocal));
+		body.extend([
+			Instruction::Call(shifted(index)),
+			Instruction::I32Const(index as i32),
+			Instruction::Call(exit_hook),
+			Instruction::End,
+		]);
+		wrappers.push(FuncBody::new(Vec::new(), Instructions::new(body)));
+	}
+	module
+		.function_section_mut()
+		.ok_or("the module has no function section")?
+		.entries_mut()
+		.extend(function_types.iter().map(|type_ref| Func::new(*type_ref)));
+	module
+		.code_section_mut()
+		.ok_or("the module has no code section")?
+		.bodies_mut()
+		.extend(wrappers);
+
+	// The function indices changed, so the names would be misleading.
+	module.sections_mut().retain(|section| match section {
+		Section::Name(_) => false,
+		Section::Custom(section) => section.name() != "name",
+		_ => true,
+	});
+
+	Ok(())
+}
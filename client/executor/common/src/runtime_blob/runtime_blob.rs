@@ -16,12 +16,14 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use super::profiling_hooks;
 use crate::{error::WasmError, wasm_runtime::HeapAllocStrategy};
+use sp_wasm_interface::{Signature, ValueType};
 use wasm_instrument::{
 	export_mutable_globals,
 	parity_wasm::elements::{
-		deserialize_buffer, serialize, DataSegment, ExportEntry, External, Internal, MemorySection,
-		MemoryType, Module, Section,
+		self, deserialize_buffer, serialize, DataSegment, ExportEntry, External, Internal,
+		MemorySection, MemoryType, Module, Section, Type,
 	},
 };
 
@@ -94,6 +96,55 @@ impl RuntimeBlob {
 		Ok(Self { raw_module: injected_module })
 	}
 
+	/// Run a pass that reports every function call to the host, for profiling.
+	///
+	/// The module will import the [`PROFILER_ENTER_HOOK`](super::PROFILER_ENTER_HOOK) and
+	/// [`PROFILER_EXIT_HOOK`](super::PROFILER_EXIT_HOOK) functions, which are called with the
+	/// index of the function in [`Self::function_names`] when it is entered and when it returns.
+	pub fn inject_profiling_hooks(&mut self) -> Result<(), WasmError> {
+		profiling_hooks::inject(&mut self.raw_module)
+			.map_err(|e| WasmError::Other(format!("cannot inject the profiling hooks: {}", e)))
+	}
+
+	/// Returns the names of all functions, imported and defined, by their index.
+	///
+	/// The names of the defined functions are taken from the name section, if there is one.
+	pub fn function_names(&self) -> Vec<String> {
+		profiling_hooks::function_names(&self.raw_module)
+	}
+
+	/// Returns the functions imported from the `env` module with their signatures.
+	pub fn imported_functions(&self) -> Vec<(String, Signature)> {
+		let types = self.raw_module.type_section().map(|ts| ts.types()).unwrap_or(&[]);
+		let value_type = |value_type: &elements::ValueType| match value_type {
+			elements::ValueType::I32 => ValueType::I32,
+			elements::ValueType::I64 => ValueType::I64,
+			elements::ValueType::F32 => ValueType::F32,
+			elements::ValueType::F64 => ValueType::F64,
+		};
+
+		self.raw_module
+			.import_section()
+			.map(|is| is.entries())
+			.unwrap_or(&[])
+			.iter()
+			.filter(|entry| entry.module() == "env")
+			.filter_map(|entry| match entry.external() {
+				External::Function(type_index) => {
+					let ty = match types.get(*type_index as usize)? {
+						Type::Function(ty) => ty,
+					};
+					let signature = Signature::new(
+						ty.params().iter().map(value_type).collect::<Vec<_>>(),
+						ty.results().first().map(value_type),
+					);
+					Some((entry.field().to_owned(), signature))
+				},
+				_ => None,
+			})
+			.collect()
+	}
+
 	/// Perform an instrumentation that makes sure that a specific function `entry_point` is
 	/// exported
 	pub fn entry_point_exists(&self, entry_point: &str) -> bool {
@@ -20,6 +20,7 @@
 
 use crate::error::Error;
 use sp_wasm_interface::Value;
+use std::time::Duration;
 
 pub use sc_allocator::AllocationStats;
 
@@ -70,6 +71,28 @@ impl<'a> From<&'a str> for InvokeMethod<'a> {
 	}
 }
 
+/// Limits on the resources a single runtime call may use.
+///
+/// Limits that are `None` are not enforced.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct CallBudget {
+	/// The maximum wall-clock time the call may run for.
+	///
+	/// Only time spent executing wasm code is interrupted, host functions always run to
+	/// completion.
+	pub max_duration: Option<Duration>,
+	/// The maximum amount of fuel the call may consume. One unit of fuel is consumed by roughly
+	/// every executed wasm instruction.
+	pub max_fuel: Option<u64>,
+}
+
+impl CallBudget {
+	/// Returns `true` if the budget doesn't limit anything.
+	pub fn is_unlimited(&self) -> bool {
+		self.max_duration.is_none() && self.max_fuel.is_none()
+	}
+}
+
 /// A trait that defines an abstract WASM runtime module.
 ///
 /// This can be implemented by an execution engine.
@@ -111,6 +134,18 @@ pub trait WasmInstance: Send {
 		self.call(method.into(), data)
 	}
 
+	/// Limit the resources of the next call on this instance to the given `budget`.
+	///
+	/// A call that runs out of its budget fails with [`Error::CallBudgetExhausted`]. Returns an
+	/// error if the instance doesn't support metering.
+	fn set_call_budget(&mut self, budget: CallBudget) -> Result<(), Error> {
+		if budget.is_unlimited() {
+			Ok(())
+		} else {
+			Err(Error::CallMeteringUnsupported)
+		}
+	}
+
 	/// Get the value from a global with the given `name`.
 	///
 	/// This method is only suitable for getting immutable globals.
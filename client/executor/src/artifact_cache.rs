@@ -0,0 +1,285 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! On-disk cache of precompiled runtimes.
+//!
+//! Compiling a runtime takes seconds, so the artifacts produced by wasmtime are kept on disk and
+//! reused across node restarts. Every artifact is addressed by the hash of the runtime code, the
+//! [`Semantics`] it was compiled with and the wasmtime engine, see [`ArtifactId`].
+//!
+//! Next to every artifact a small metadata file is stored, holding the checksum of the artifact.
+//! The checksum is verified before an artifact is loaded. The metadata file is rewritten on every
+//! load, so its modification time tracks the last use of the artifact and the least recently used
+//! artifacts are evicted first once the cache grows over its size limit.
+
+use codec::{Decode, Encode};
+use sc_executor_common::error::WasmError;
+use sc_executor_wasmtime::Semantics;
+use sp_core::{blake2_256, hexdisplay::HexDisplay};
+use std::{
+	fs, io,
+	path::{Path, PathBuf},
+	time::SystemTime,
+};
+
+/// The default maximum size of the artifact cache, in bytes.
+pub const DEFAULT_ARTIFACT_CACHE_SIZE: u64 = 1024 * 1024 * 1024;
+
+const ARTIFACT_EXTENSION: &str = "artifact";
+const META_EXTENSION: &str = "meta";
+
+/// Identifies a compiled artifact of a runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArtifactId([u8; 32]);
+
+impl ArtifactId {
+	/// Returns the id of the artifact of the code with the given hash compiled with `semantics`.
+	pub fn new(code_hash: &[u8], semantics: &Semantics) -> Result<Self, WasmError> {
+		let mut preimage = code_hash.to_vec();
+		preimage.extend(sc_executor_wasmtime::artifact_compatibility_key(semantics)?);
+		Ok(Self(blake2_256(&preimage)))
+	}
+
+	fn file_name(&self, extension: &str) -> String {
+		format!("{}.{}", HexDisplay::from(&self.0), extension)
+	}
+}
+
+/// Metadata stored next to every artifact.
+#[derive(Encode, Decode)]
+struct ArtifactMeta {
+	/// The length of the artifact in bytes.
+	len: u64,
+	/// The blake2-256 hash of the artifact.
+	checksum: [u8; 32],
+}
+
+/// A content-addressed cache of compiled runtimes in a directory.
+pub struct ArtifactCache {
+	path: PathBuf,
+	max_size: u64,
+}
+
+impl ArtifactCache {
+	/// Opens the cache in the given directory, creating it if needed.
+	///
+	/// `max_size` is the size in bytes above which the least recently used artifacts are evicted.
+	pub fn open(path: impl Into<PathBuf>, max_size: u64) -> io::Result<Self> {
+		let path = path.into();
+		fs::create_dir_all(&path)?;
+		Ok(Self { path, max_size })
+	}
+
+	/// Returns the directory of the cache.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Loads the artifact with the given id.
+	///
+	/// Returns `None` if the artifact is not cached or doesn't match its checksum. Corrupted
+	/// artifacts are removed from the cache.
+	pub fn load(&self, id: &ArtifactId) -> Option<Vec<u8>> {
+		let meta_path = self.path.join(id.file_name(META_EXTENSION));
+		let artifact_path = self.path.join(id.file_name(ARTIFACT_EXTENSION));
+
+		let meta = fs::read(&meta_path).ok()?;
+		let valid = ArtifactMeta::decode(&mut &meta[..]).ok().and_then(|meta| {
+			let artifact = fs::read(&artifact_path).ok()?;
+			(artifact.len() as u64 == meta.len && blake2_256(&artifact) == meta.checksum)
+				.then_some(artifact)
+		});
+
+		match valid {
+			Some(artifact) => {
+				// Rewriting the metadata marks the artifact as recently used.
+				if let Err(e) = fs::write(&meta_path, &meta) {
+					tracing::debug!(target: "wasm-runtime", error = %e, "Failed to touch artifact");
+				}
+				Some(artifact)
+			},
+			None => {
+				tracing::warn!(
+					target: "wasm-runtime",
+					path = %artifact_path.display(),
+					"Removing corrupted runtime artifact",
+				);
+				let _ = fs::remove_file(&meta_path);
+				let _ = fs::remove_file(&artifact_path);
+				None
+			},
+		}
+	}
+
+	/// Stores the artifact with the given id and evicts the least recently used artifacts if the
+	/// cache is over its size limit.
+	///
+	/// Returns the path of the stored artifact.
+	pub fn store(&self, id: &ArtifactId, artifact: &[u8]) -> io::Result<PathBuf> {
+		let artifact_path = self.path.join(id.file_name(ARTIFACT_EXTENSION));
+		let meta = ArtifactMeta { len: artifact.len() as u64, checksum: blake2_256(artifact) };
+
+		// The metadata is written last, so a partially written artifact is never considered valid.
+		write_atomically(&artifact_path, artifact)?;
+		write_atomically(&self.path.join(id.file_name(META_EXTENSION)), &meta.encode())?;
+
+		self.evict(id);
+		Ok(artifact_path)
+	}
+
+	/// Removes the least recently used artifacts, except `keep`, until the cache fits its limit.
+	fn evict(&self, keep: &ArtifactId) {
+		let Ok(entries) = fs::read_dir(&self.path) else { return };
+
+		let keep = keep.file_name(META_EXTENSION);
+		let mut artifacts = entries
+			.filter_map(Result::ok)
+			.filter(|entry| entry.path().extension().map_or(false, |ext| ext == META_EXTENSION))
+			.filter_map(|entry| {
+				let meta_path = entry.path();
+				let artifact_path = meta_path.with_extension(ARTIFACT_EXTENSION);
+				let used = entry.metadata().and_then(|m| m.modified()).ok()?;
+				let size = fs::metadata(&artifact_path).map_or(0, |m| m.len());
+				let pinned = entry.file_name() == keep.as_str();
+				Some((pinned, used, size, meta_path, artifact_path))
+			})
+			.collect::<Vec<(bool, SystemTime, u64, PathBuf, PathBuf)>>();
+
+		// Most recently used first, the artifact just stored always stays.
+		artifacts.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+
+		let mut total = 0u64;
+		for (pinned, _, size, meta_path, artifact_path) in artifacts {
+			total = total.saturating_add(size);
+			if total > self.max_size && !pinned {
+				tracing::debug!(
+					target: "wasm-runtime",
+					path = %artifact_path.display(),
+					"Evicting runtime artifact",
+				);
+				let _ = fs::remove_file(meta_path);
+				let _ = fs::remove_file(artifact_path);
+			}
+		}
+	}
+}
+
+/// Writes `data` to a temporary file next to `path` and renames it to `path`.
+fn write_atomically(path: &Path, data: &[u8]) -> io::Result<()> {
+	// The temporary file keeps the whole name of `path`, so an artifact and its metadata never
+	// share the same temporary file.
+	let mut tmp_name = path.file_name().unwrap_or_default().to_owned();
+	tmp_name.push(".tmp");
+	let tmp_path = path.with_file_name(tmp_name);
+	fs::write(&tmp_path, data)?;
+	fs::rename(&tmp_path, path)
+}
+
+/// Returns an error describing a failure to access the artifact cache.
+pub(crate) fn cache_error(e: io::Error) -> WasmError {
+	WasmError::Other(format!("cannot access the runtime artifact cache: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_executor_common::wasm_runtime::HeapAllocStrategy;
+	use sc_executor_wasmtime::InstantiationStrategy;
+
+	fn semantics(extra_pages: u32) -> Semantics {
+		Semantics {
+			instantiation_strategy: InstantiationStrategy::Pooling,
+			deterministic_stack_limit: None,
+			canonicalize_nans: false,
+			parallel_compilation: true,
+			heap_alloc_strategy: HeapAllocStrategy::Static { extra_pages },
+			wasm_multi_value: false,
+			wasm_bulk_memory: false,
+			wasm_reference_types: false,
+			wasm_simd: false,
+			call_metering: false,
+		}
+	}
+
+	#[test]
+	fn artifact_id_depends_on_code_and_semantics() {
+		let id = ArtifactId::new(&[1; 32], &semantics(1)).unwrap();
+
+		assert_eq!(id, ArtifactId::new(&[1; 32], &semantics(1)).unwrap());
+		assert_ne!(id, ArtifactId::new(&[2; 32], &semantics(1)).unwrap());
+		assert_ne!(id, ArtifactId::new(&[1; 32], &semantics(2)).unwrap());
+	}
+
+	#[test]
+	fn stored_artifact_is_loaded() {
+		let dir = tempfile::tempdir().unwrap();
+		let cache = ArtifactCache::open(dir.path(), DEFAULT_ARTIFACT_CACHE_SIZE).unwrap();
+		let id = ArtifactId::new(&[1; 32], &semantics(1)).unwrap();
+
+		assert_eq!(cache.load(&id), None);
+		cache.store(&id, b"artifact").unwrap();
+		assert_eq!(cache.load(&id), Some(b"artifact".to_vec()));
+	}
+
+	#[test]
+	fn artifact_and_metadata_use_distinct_temporary_files() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("id.artifact");
+
+		write_atomically(&path, b"artifact").unwrap();
+		write_atomically(&path.with_extension(META_EXTENSION), b"meta").unwrap();
+
+		assert_eq!(fs::read(&path).unwrap(), b"artifact");
+		assert_eq!(fs::read(path.with_extension(META_EXTENSION)).unwrap(), b"meta");
+		assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+	}
+
+	#[test]
+	fn corrupted_artifact_is_removed() {
+		let dir = tempfile::tempdir().unwrap();
+		let cache = ArtifactCache::open(dir.path(), DEFAULT_ARTIFACT_CACHE_SIZE).unwrap();
+		let id = ArtifactId::new(&[1; 32], &semantics(1)).unwrap();
+		let path = cache.store(&id, b"artifact").unwrap();
+
+		fs::write(&path, b"tampered").unwrap();
+
+		assert_eq!(cache.load(&id), None);
+		assert!(!path.exists());
+	}
+
+	#[test]
+	fn least_recently_used_artifact_is_evicted() {
+		let dir = tempfile::tempdir().unwrap();
+		let cache = ArtifactCache::open(dir.path(), 16).unwrap();
+		let first = ArtifactId::new(&[1; 32], &semantics(1)).unwrap();
+		let second = ArtifactId::new(&[2; 32], &semantics(1)).unwrap();
+		let third = ArtifactId::new(&[3; 32], &semantics(1)).unwrap();
+
+		cache.store(&first, b"01234567").unwrap();
+		std::thread::sleep(std::time::Duration::from_millis(10));
+		cache.store(&second, b"01234567").unwrap();
+		std::thread::sleep(std::time::Duration::from_millis(10));
+		assert!(cache.load(&first).is_some());
+		std::thread::sleep(std::time::Duration::from_millis(10));
+		cache.store(&third, b"01234567").unwrap();
+
+		assert!(cache.load(&first).is_some());
+		assert_eq!(cache.load(&second), None);
+		assert!(cache.load(&third).is_some());
+	}
+}
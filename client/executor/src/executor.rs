@@ -17,9 +17,10 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
+	artifact_cache::DEFAULT_ARTIFACT_CACHE_SIZE,
 	error::{Error, Result},
 	wasm_runtime::{RuntimeCache, WasmExecutionMethod},
-	RuntimeVersionOf,
+	RuntimeVersionOf, WasmtimeProfiler,
 };
 
 use std::{
@@ -33,10 +34,12 @@ use codec::Encode;
 use sc_executor_common::{
 	runtime_blob::RuntimeBlob,
 	wasm_runtime::{
-		AllocationStats, HeapAllocStrategy, WasmInstance, WasmModule, DEFAULT_HEAP_ALLOC_STRATEGY,
+		AllocationStats, CallBudget, HeapAllocStrategy, WasmInstance, WasmModule,
+		DEFAULT_HEAP_ALLOC_STRATEGY,
 	},
 };
 use sp_core::traits::{CallContext, CodeExecutor, Externalities, RuntimeCode};
+use sp_externalities::ExternalitiesExt;
 use sp_version::{GetNativeVersion, NativeVersion, RuntimeVersion};
 use sp_wasm_interface::{ExtendedHostFunctions, HostFunctions};
 
@@ -63,6 +66,54 @@ where
 	})
 }
 
+/// Extension limiting the resources the runtime calls made with the externalities may use.
+///
+/// Only applies to calls executed in wasm by an executor built with
+/// [`WasmExecutorBuilder::with_call_metering`], which runs them on its metered instances. Each
+/// call gets the full `budget`.
+pub struct CallBudgetExt {
+	/// The budget of each call.
+	pub budget: CallBudget,
+	/// Set once a call was aborted because it ran out of its budget.
+	pub exhausted: bool,
+}
+
+impl CallBudgetExt {
+	/// Create a new instance of `Self`.
+	pub fn new(budget: CallBudget) -> Self {
+		Self { budget, exhausted: false }
+	}
+}
+
+impl sp_externalities::Extension for CallBudgetExt {
+	fn as_mut_any(&mut self) -> &mut dyn std::any::Any {
+		self
+	}
+}
+
+/// Call `method` on `instance`, applying the [`CallBudgetExt`] registered in `ext`.
+fn call_export_with_budget(
+	instance: &mut dyn WasmInstance,
+	mut ext: &mut dyn Externalities,
+	method: &str,
+	data: &[u8],
+) -> Result<Result<Vec<u8>>> {
+	if let Some(budget) = ext.extension::<CallBudgetExt>().map(|ext| ext.budget) {
+		instance.set_call_budget(budget)?;
+	}
+
+	let mut instance = AssertUnwindSafe(instance);
+	let result = with_externalities_safe(ext, move || instance.call_export(method, data))?;
+
+	if let (Err(Error::CallBudgetExhausted), Some(budget)) =
+		(&result, ext.extension::<CallBudgetExt>())
+	{
+		budget.exhausted = true;
+	}
+
+	Ok(result)
+}
+
 /// Delegate for dispatching a CodeExecutor call.
 ///
 /// By dispatching we mean that we execute a runtime function specified by it's name.
@@ -93,6 +144,9 @@ pub struct WasmExecutorBuilder<H> {
 	cache_path: Option<PathBuf>,
 	allow_missing_host_functions: bool,
 	runtime_cache_size: u8,
+	artifact_cache_size: u64,
+	call_metering: bool,
+	profiler: Option<Arc<WasmtimeProfiler>>,
 }
 
 impl<H> WasmExecutorBuilder<H> {
@@ -108,6 +162,9 @@ impl<H> WasmExecutorBuilder<H> {
 			ignore_onchain_heap_pages: false,
 			max_runtime_instances: 2,
 			runtime_cache_size: 4,
+			artifact_cache_size: DEFAULT_ARTIFACT_CACHE_SIZE,
+			call_metering: false,
+			profiler: None,
 			allow_missing_host_functions: false,
 			cache_path: None,
 		}
@@ -164,6 +221,9 @@ impl<H> WasmExecutorBuilder<H> {
 	/// purposes of caching. This may be important in cases when there are many different modules
 	/// with the compiled execution method is used.
 	///
+	/// Compiled runtimes are stored in the `cache_path` and reused after a restart, see
+	/// [`Self::with_artifact_cache_size`].
+	///
 	/// By default there is no `cache_path` given.
 	pub fn with_cache_path(mut self, cache_path: impl Into<PathBuf>) -> Self {
 		self.cache_path = Some(cache_path.into());
@@ -193,8 +253,56 @@ impl<H> WasmExecutorBuilder<H> {
 		self
 	}
 
+	/// Create the wasm executor with the given `artifact_cache_size`.
+	///
+	/// Defines the maximum size in bytes of the compiled runtimes stored in the `cache_path`. Once
+	/// it is exceeded, the least recently used runtimes are removed.
+	///
+	/// By default this value is set to 1 GiB.
+	pub fn with_artifact_cache_size(mut self, artifact_cache_size: u64) -> Self {
+		self.artifact_cache_size = artifact_cache_size;
+		self
+	}
+
+	/// Create the wasm executor with support for call budgets.
+	///
+	/// This allows limiting the fuel and the time of the calls made with a [`CallBudgetExt`]
+	/// registered. These calls run on a separate cache of runtimes compiled with call metering,
+	/// which execute slightly slower. All other calls keep using the unmetered runtimes.
+	///
+	/// By default call metering is disabled.
+	pub fn with_call_metering(mut self, call_metering: bool) -> Self {
+		self.call_metering = call_metering;
+		self
+	}
+
+	/// Create the wasm executor with the given `profiler`.
+	///
+	/// The runtimes are instrumented to report the time spent in each of their functions to the
+	/// `profiler`. This slows down the execution considerably and disables the on-disk cache of
+	/// compiled runtimes, so it should only be used for debugging.
+	///
+	/// By default there is no profiler.
+	pub fn with_profiler(mut self, profiler: Arc<WasmtimeProfiler>) -> Self {
+		self.profiler = Some(profiler);
+		self
+	}
+
 	/// Build the configured [`WasmExecutor`].
 	pub fn build(self) -> WasmExecutor<H> {
+		let runtime_cache = |call_metering| {
+			Arc::new(RuntimeCache::new(
+				self.max_runtime_instances,
+				self.cache_path.clone(),
+				self.runtime_cache_size,
+				self.artifact_cache_size,
+				call_metering,
+				self.profiler.clone(),
+			))
+		};
+		let cache = runtime_cache(false);
+		let metered_cache = self.call_metering.then(|| runtime_cache(true));
+
 		WasmExecutor {
 			method: self.method,
 			default_offchain_heap_alloc_strategy: unwrap_heap_pages(
@@ -204,11 +312,8 @@ impl<H> WasmExecutorBuilder<H> {
 				self.onchain_heap_alloc_strategy,
 			),
 			ignore_onchain_heap_pages: self.ignore_onchain_heap_pages,
-			cache: Arc::new(RuntimeCache::new(
-				self.max_runtime_instances,
-				self.cache_path.clone(),
-				self.runtime_cache_size,
-			)),
+			cache,
+			metered_cache,
 			cache_path: self.cache_path,
 			allow_missing_host_functions: self.allow_missing_host_functions,
 			phantom: PhantomData,
@@ -229,6 +334,9 @@ pub struct WasmExecutor<H> {
 	ignore_onchain_heap_pages: bool,
 	/// WASM runtime cache.
 	cache: Arc<RuntimeCache>,
+	/// WASM runtime cache used for the calls made with a [`CallBudgetExt`], if call metering is
+	/// enabled.
+	metered_cache: Option<Arc<RuntimeCache>>,
 	/// The path to a directory which the executor can leverage for a file cache, e.g. put there
 	/// compiled artifacts.
 	cache_path: Option<PathBuf>,
@@ -245,6 +353,7 @@ impl<H> Clone for WasmExecutor<H> {
 			default_offchain_heap_alloc_strategy: self.default_offchain_heap_alloc_strategy,
 			ignore_onchain_heap_pages: self.ignore_onchain_heap_pages,
 			cache: self.cache.clone(),
+			metered_cache: self.metered_cache.clone(),
 			cache_path: self.cache_path.clone(),
 			allow_missing_host_functions: self.allow_missing_host_functions,
 			phantom: self.phantom,
@@ -295,7 +404,11 @@ where
 				max_runtime_instances,
 				cache_path.clone(),
 				runtime_cache_size,
+				DEFAULT_ARTIFACT_CACHE_SIZE,
+				false,
+				None,
 			)),
+			metered_cache: None,
 			cache_path,
 			allow_missing_host_functions: false,
 			phantom: PhantomData,
@@ -329,7 +442,7 @@ where
 	pub fn with_instance<R, F>(
 		&self,
 		runtime_code: &RuntimeCode,
-		ext: &mut dyn Externalities,
+		mut ext: &mut dyn Externalities,
 		heap_alloc_strategy: HeapAllocStrategy,
 		f: F,
 	) -> Result<R>
@@ -341,7 +454,12 @@ where
 			AssertUnwindSafe<&mut dyn Externalities>,
 		) -> Result<Result<R>>,
 	{
-		match self.cache.with_instance::<H, _, _>(
+		let cache = match &self.metered_cache {
+			Some(metered_cache) if ext.extension::<CallBudgetExt>().is_some() => metered_cache,
+			_ => &self.cache,
+		};
+
+		match cache.with_instance::<H, _, _>(
 			runtime_code,
 			ext,
 			self.method,
@@ -520,7 +638,7 @@ where
 			ext,
 			heap_alloc_strategy,
 			|_, mut instance, _onchain_version, mut ext| {
-				with_externalities_safe(&mut **ext, move || instance.call_export(method, data))
+				call_export_with_budget(&mut **instance, &mut **ext, method, data)
 			},
 		);
 
@@ -701,7 +819,7 @@ impl<D: NativeExecutionDispatch + 'static> CodeExecutor for NativeElseWasmExecut
 						);
 					}
 
-					with_externalities_safe(&mut **ext, move || instance.call_export(method, data))
+					call_export_with_budget(&mut **instance, &mut **ext, method, data)
 				}
 			},
 		);
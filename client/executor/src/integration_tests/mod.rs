@@ -22,25 +22,27 @@ mod linux;
 use assert_matches::assert_matches;
 use codec::{Decode, Encode};
 use sc_executor_common::{
-	error::Error,
+	error::{Error, WasmError},
+	host_functions::{HostFunctionCatalog, HostFunctionInfo},
 	runtime_blob::RuntimeBlob,
-	wasm_runtime::{HeapAllocStrategy, WasmModule},
+	wasm_runtime::{CallBudget, HeapAllocStrategy, WasmModule},
 };
 use sc_runtime_test::wasm_binary_unwrap;
 use sp_core::{
 	blake2_128, blake2_256, ed25519, map,
 	offchain::{testing, OffchainDbExt, OffchainWorkerExt},
 	sr25519,
-	traits::Externalities,
+	traits::{CallContext, CodeExecutor, Externalities, RuntimeCode, WrappedRuntimeCode},
 	Pair,
 };
+use sp_externalities::ExternalitiesExt;
 use sp_runtime::traits::BlakeTwo256;
 use sp_state_machine::TestExternalities as CoreTestExternalities;
 use sp_trie::{LayoutV1 as Layout, TrieConfiguration};
 use std::sync::Arc;
 use tracing_subscriber::layer::SubscriberExt;
 
-use crate::WasmExecutionMethod;
+use crate::{CallBudgetExt, WasmExecutionMethod};
 
 pub type TestExternalities = CoreTestExternalities<BlakeTwo256>;
 type HostFunctions = sp_io::SubstrateHostFunctions;
@@ -821,3 +823,91 @@ fn return_overflow(wasm_method: WasmExecutionMethod) {
 		error => panic!("unexpected error: {:?}", error),
 	}
 }
+
+test_wasm_execution!(incompatible_host_functions_are_reported);
+fn incompatible_host_functions_are_reported(wasm_method: WasmExecutionMethod) {
+	let blob = RuntimeBlob::uncompress_if_needed(wasm_binary_unwrap()).unwrap();
+	let report = HostFunctionCatalog::new::<HostFunctions>().check(&blob);
+
+	assert!(report.mismatched.is_empty());
+	assert_eq!(
+		report.missing.iter().map(|m| m.imported.full_name()).collect::<Vec<_>>(),
+		vec!["missing_external", "yet_another_missing_external"],
+	);
+
+	let error = crate::wasm_runtime::create_wasm_runtime_with_code::<HostFunctions>(
+		wasm_method,
+		HeapAllocStrategy::Static { extra_pages: 1024 },
+		blob,
+		false,
+		None,
+	)
+	.err()
+	.expect("the test runtime imports missing host functions");
+	assert_matches!(error, WasmError::IncompatibleHostFunctions(r) if r == report);
+}
+
+#[test]
+fn host_function_versions_are_reported() {
+	let signature = sp_wasm_interface::Signature::new_with_args(&[][..]);
+	let catalog = HostFunctionCatalog::from_functions([
+		HostFunctionInfo::new("ext_misc_print_version_1", signature.clone()),
+		HostFunctionInfo::new("ext_misc_print_version_2", signature.clone()),
+	]);
+
+	let report = catalog.check_imports([
+		HostFunctionInfo::new("ext_misc_print_version_2", signature.clone()),
+		HostFunctionInfo::new("ext_misc_print_version_3", signature.clone()),
+		HostFunctionInfo::new(
+			"ext_misc_print_version_1",
+			sp_wasm_interface::Signature::new_with_args(&[sp_wasm_interface::ValueType::I32][..]),
+		),
+	]);
+
+	assert_eq!(report.missing.len(), 1);
+	assert_eq!(report.missing[0].imported.version, Some(3));
+	assert_eq!(report.missing[0].provided_versions, vec![1, 2]);
+	assert_eq!(report.mismatched.len(), 1);
+	assert_eq!(report.mismatched[0].imported.full_name(), "ext_misc_print_version_1");
+	assert_eq!(report.mismatched[0].provided.to_string(), "()");
+	assert!(!report.is_compatible());
+}
+
+test_wasm_execution!(call_budget_only_applies_to_metered_calls);
+fn call_budget_only_applies_to_metered_calls(wasm_method: WasmExecutionMethod) {
+	let executor = crate::WasmExecutor::<HostFunctions>::builder()
+		.with_execution_method(wasm_method)
+		.with_call_metering(true)
+		.build();
+
+	let code_fetcher = WrappedRuntimeCode(wasm_binary_unwrap().into());
+	let runtime_code = RuntimeCode { code_fetcher: &code_fetcher, heap_pages: None, hash: vec![1] };
+	let call = |ext: &mut TestExternalities| {
+		executor
+			.call(
+				&mut ext.ext(),
+				&runtime_code,
+				"test_blake2_256",
+				&b"Hello world!".to_vec().encode(),
+				false,
+				CallContext::Offchain,
+			)
+			.0
+	};
+
+	// Calls without a budget use the unmetered instances.
+	let mut ext = TestExternalities::default();
+	assert_eq!(call(&mut ext).unwrap(), blake2_256(b"Hello world!").to_vec().encode());
+
+	let mut ext = TestExternalities::default();
+	ext.register_extension(CallBudgetExt::new(CallBudget {
+		max_fuel: Some(1),
+		..Default::default()
+	}));
+	assert_matches!(call(&mut ext), Err(Error::CallBudgetExhausted));
+	assert!(ext.ext().extension::<CallBudgetExt>().unwrap().exhausted);
+
+	// An exhausted metered call leaves the unmetered instances untouched.
+	let mut ext = TestExternalities::default();
+	assert_eq!(call(&mut ext).unwrap(), blake2_256(b"Hello world!").to_vec().encode());
+}
@@ -31,6 +31,7 @@
 #![warn(missing_docs)]
 #![recursion_limit = "128"]
 
+mod artifact_cache;
 #[macro_use]
 mod executor;
 #[cfg(test)]
@@ -39,9 +40,12 @@ mod wasm_runtime;
 
 pub use self::{
 	executor::{
-		with_externalities_safe, NativeElseWasmExecutor, NativeExecutionDispatch, WasmExecutor,
+		with_externalities_safe, CallBudgetExt, NativeElseWasmExecutor, NativeExecutionDispatch,
+		WasmExecutor,
+	},
+	wasm_runtime::{
+		artifact_cache_path, precompile_runtime, read_embedded_version, WasmExecutionMethod,
 	},
-	wasm_runtime::{read_embedded_version, WasmExecutionMethod},
 };
 pub use codec::Codec;
 #[doc(hidden)]
@@ -51,10 +55,15 @@ pub use sp_version::{NativeVersion, RuntimeVersion};
 pub use sp_wasm_interface;
 
 pub use sc_executor_common::{
-	error,
-	wasm_runtime::{HeapAllocStrategy, DEFAULT_HEAP_ALLOC_PAGES, DEFAULT_HEAP_ALLOC_STRATEGY},
+	error, host_functions,
+	runtime_blob::RuntimeBlob,
+	wasm_runtime::{
+		CallBudget, HeapAllocStrategy, DEFAULT_HEAP_ALLOC_PAGES, DEFAULT_HEAP_ALLOC_STRATEGY,
+	},
+};
+pub use sc_executor_wasmtime::{
+	InstantiationStrategy as WasmtimeInstantiationStrategy, Profiler as WasmtimeProfiler,
 };
-pub use sc_executor_wasmtime::InstantiationStrategy as WasmtimeInstantiationStrategy;
 
 /// Extracts the runtime version of a given runtime code.
 pub trait RuntimeVersionOf {
@@ -21,11 +21,16 @@
 //! The primary means of accessing the runtimes is through a cache which saves the reusable
 //! components of the runtime that are expensive to initialize.
 
-use crate::error::{Error, WasmError};
+use crate::{
+	artifact_cache::{cache_error, ArtifactCache, ArtifactId},
+	error::{Error, WasmError},
+	WasmtimeProfiler,
+};
 
 use codec::Decode;
 use parking_lot::Mutex;
 use sc_executor_common::{
+	host_functions::HostFunctionCatalog,
 	runtime_blob::RuntimeBlob,
 	wasm_runtime::{HeapAllocStrategy, WasmInstance, WasmModule},
 };
@@ -159,6 +164,9 @@ impl VersionedRuntime {
 /// request.
 ///
 /// The size of cache is configurable via the cli option `--runtime-cache-size`.
+///
+/// If a `cache_path` is given, compiled runtimes are additionally kept in an on-disk
+/// [`ArtifactCache`], so that they don't have to be compiled again after a restart.
 pub struct RuntimeCache {
 	/// A cache of runtimes along with metadata.
 	///
@@ -167,6 +175,12 @@ pub struct RuntimeCache {
 	/// The size of the instances cache for each runtime.
 	max_runtime_instances: usize,
 	cache_path: Option<PathBuf>,
+	/// The on-disk cache of compiled runtimes.
+	artifact_cache: Option<ArtifactCache>,
+	/// Whether runtimes are compiled with support for call budgets.
+	call_metering: bool,
+	/// The profiler the runtimes are instrumented for.
+	profiler: Option<Arc<WasmtimeProfiler>>,
 }
 
 impl RuntimeCache {
@@ -180,13 +194,43 @@ impl RuntimeCache {
 	///
 	/// `runtime_cache_size` specifies the number of different runtimes versions preserved in an
 	/// in-memory cache, must always be at least 1.
+	///
+	/// `artifact_cache_size` specifies the maximum size in bytes of the compiled runtimes kept in
+	/// `cache_path`.
+	///
+	/// `call_metering` compiles the runtimes with support for
+	/// [`CallBudget`](sc_executor_common::wasm_runtime::CallBudget)s.
+	///
+	/// `profiler` instruments the runtimes to report the time spent in their functions. Such
+	/// runtimes are never stored in the on-disk cache.
 	pub fn new(
 		max_runtime_instances: usize,
 		cache_path: Option<PathBuf>,
 		runtime_cache_size: u8,
+		artifact_cache_size: u64,
+		call_metering: bool,
+		profiler: Option<Arc<WasmtimeProfiler>>,
 	) -> RuntimeCache {
 		let cap = ByLength::new(runtime_cache_size.max(1) as u32);
-		RuntimeCache { runtimes: Mutex::new(LruMap::new(cap)), max_runtime_instances, cache_path }
+		let artifact_cache = cache_path.as_ref().and_then(|path| {
+			ArtifactCache::open(artifact_cache_path(path), artifact_cache_size)
+				.map_err(|e| {
+					tracing::warn!(
+						target: "wasm-runtime",
+						error = %e,
+						"Cannot open the runtime artifact cache",
+					)
+				})
+				.ok()
+		});
+		RuntimeCache {
+			runtimes: Mutex::new(LruMap::new(cap)),
+			max_runtime_instances,
+			cache_path,
+			artifact_cache,
+			call_metering,
+			profiler,
+		}
 	}
 
 	/// Prepares a WASM module instance and executes given function for it.
@@ -247,14 +291,13 @@ impl RuntimeCache {
 
 			let time = std::time::Instant::now();
 
-			let result = create_versioned_wasm_runtime::<H>(
+			let result = self.create_versioned_wasm_runtime::<H>(
 				&code,
+				code_hash,
 				ext,
 				wasm_method,
 				heap_alloc_strategy,
 				allow_missing_func_imports,
-				self.max_runtime_instances,
-				self.cache_path.as_deref(),
 			);
 
 			match result {
@@ -284,6 +327,104 @@ impl RuntimeCache {
 
 		Ok(versioned_runtime.with_instance(ext, f))
 	}
+
+	fn create_versioned_wasm_runtime<H>(
+		&self,
+		code: &[u8],
+		code_hash: &[u8],
+		ext: &mut dyn Externalities,
+		wasm_method: WasmExecutionMethod,
+		heap_alloc_strategy: HeapAllocStrategy,
+		allow_missing_func_imports: bool,
+	) -> Result<VersionedRuntime, WasmError>
+	where
+		H: HostFunctions,
+	{
+		// The incoming code may be actually compressed. We decompress it here and then work with
+		// the uncompressed code from now on.
+		let blob = sc_executor_common::runtime_blob::RuntimeBlob::uncompress_if_needed(code)?;
+
+		// Use the runtime blob to scan if there is any metadata embedded into the wasm binary
+		// pertaining to runtime version. We do it before consuming the runtime blob for creating
+		// the runtime.
+		let mut version = read_embedded_version(&blob)?;
+
+		let semantics = semantics(wasm_method, heap_alloc_strategy, self.call_metering);
+		let runtime = match (&self.artifact_cache, &self.profiler) {
+			(Some(artifact_cache), None) => create_wasm_runtime_with_artifact_cache::<H>(
+				semantics,
+				blob,
+				code_hash,
+				allow_missing_func_imports,
+				artifact_cache,
+			)?,
+			(_, profiler) => create_wasm_runtime_with_semantics::<H>(
+				semantics,
+				blob,
+				allow_missing_func_imports,
+				self.cache_path.as_deref(),
+				profiler.clone(),
+			)?,
+		};
+
+		// If the runtime blob doesn't embed the runtime version then use the legacy version query
+		// mechanism: call the runtime.
+		if version.is_none() {
+			// Call to determine runtime version.
+			let version_result = {
+				// `ext` is already implicitly handled as unwind safe, as we store it in a global
+				// variable.
+				let mut ext = AssertUnwindSafe(ext);
+
+				// The following unwind safety assertion is OK because if the method call panics,
+				// the runtime will be dropped.
+				let runtime = AssertUnwindSafe(runtime.as_ref());
+				crate::executor::with_externalities_safe(&mut **ext, move || {
+					runtime.new_instance()?.call("Core_version".into(), &[])
+				})
+				.map_err(|_| {
+					WasmError::Instantiation("panic in call to get runtime version".into())
+				})?
+			};
+
+			if let Ok(version_buf) = version_result {
+				version = Some(decode_version(&version_buf)?)
+			}
+		}
+
+		let mut instances = Vec::with_capacity(self.max_runtime_instances);
+		instances.resize_with(self.max_runtime_instances, || Mutex::new(None));
+
+		Ok(VersionedRuntime { module: runtime, version, instances })
+	}
+}
+
+/// Returns the directory of the compiled runtimes cache within the executor's `cache_path`.
+pub fn artifact_cache_path(cache_path: &Path) -> PathBuf {
+	cache_path.join("artifacts")
+}
+
+/// The wasmtime semantics runtimes are compiled with.
+fn semantics(
+	wasm_method: WasmExecutionMethod,
+	heap_alloc_strategy: HeapAllocStrategy,
+	call_metering: bool,
+) -> sc_executor_wasmtime::Semantics {
+	match wasm_method {
+		WasmExecutionMethod::Compiled { instantiation_strategy } =>
+			sc_executor_wasmtime::Semantics {
+				heap_alloc_strategy,
+				instantiation_strategy,
+				deterministic_stack_limit: None,
+				canonicalize_nans: false,
+				parallel_compilation: true,
+				wasm_multi_value: false,
+				wasm_bulk_memory: false,
+				wasm_reference_types: false,
+				wasm_simd: false,
+				call_metering,
+			},
+	}
 }
 
 /// Create a wasm runtime with the given `code`.
@@ -297,28 +438,137 @@ pub fn create_wasm_runtime_with_code<H>(
 where
 	H: HostFunctions,
 {
-	match wasm_method {
-		WasmExecutionMethod::Compiled { instantiation_strategy } =>
-			sc_executor_wasmtime::create_runtime::<H>(
-				blob,
-				sc_executor_wasmtime::Config {
-					allow_missing_func_imports,
-					cache_path: cache_path.map(ToOwned::to_owned),
-					semantics: sc_executor_wasmtime::Semantics {
-						heap_alloc_strategy,
-						instantiation_strategy,
-						deterministic_stack_limit: None,
-						canonicalize_nans: false,
-						parallel_compilation: true,
-						wasm_multi_value: false,
-						wasm_bulk_memory: false,
-						wasm_reference_types: false,
-						wasm_simd: false,
-					},
-				},
-			)
-			.map(|runtime| -> Box<dyn WasmModule> { Box::new(runtime) }),
+	create_wasm_runtime_with_semantics::<H>(
+		semantics(wasm_method, heap_alloc_strategy, false),
+		blob,
+		allow_missing_func_imports,
+		cache_path,
+		None,
+	)
+}
+
+fn create_wasm_runtime_with_semantics<H>(
+	semantics: sc_executor_wasmtime::Semantics,
+	blob: RuntimeBlob,
+	allow_missing_func_imports: bool,
+	cache_path: Option<&Path>,
+	profiler: Option<Arc<WasmtimeProfiler>>,
+) -> Result<Box<dyn WasmModule>, WasmError>
+where
+	H: HostFunctions,
+{
+	sc_executor_wasmtime::create_runtime::<H>(
+		blob,
+		sc_executor_wasmtime::Config {
+			allow_missing_func_imports,
+			cache_path: cache_path.map(ToOwned::to_owned),
+			profiler,
+			semantics,
+		},
+	)
+	.map(|runtime| -> Box<dyn WasmModule> { Box::new(runtime) })
+}
+
+/// Create a wasm runtime with the given `code`, reusing the compiled artifact from
+/// `artifact_cache` if there is one.
+///
+/// Freshly compiled runtimes are stored in the cache. Runtimes that can't be loaded from
+/// precompiled artifacts are compiled from the code as usual.
+fn create_wasm_runtime_with_artifact_cache<H>(
+	semantics: sc_executor_wasmtime::Semantics,
+	blob: RuntimeBlob,
+	code_hash: &[u8],
+	allow_missing_func_imports: bool,
+	artifact_cache: &ArtifactCache,
+) -> Result<Box<dyn WasmModule>, WasmError>
+where
+	H: HostFunctions,
+{
+	if semantics.instantiation_strategy ==
+		sc_executor_wasmtime::InstantiationStrategy::LegacyInstanceReuse
+	{
+		return create_wasm_runtime_with_semantics::<H>(
+			semantics,
+			blob,
+			allow_missing_func_imports,
+			None,
+			None,
+		)
+	}
+
+	// Precompiled runtimes are not checked by `create_runtime_from_artifact_bytes`, so the missing
+	// host functions are reported here, before anything is loaded or compiled.
+	if !allow_missing_func_imports {
+		HostFunctionCatalog::new::<H>().ensure_compatible(&blob)?;
 	}
+
+	let config = sc_executor_wasmtime::Config {
+		allow_missing_func_imports,
+		cache_path: None,
+		profiler: None,
+		semantics: semantics.clone(),
+	};
+	let id = ArtifactId::new(code_hash, &semantics)?;
+
+	if let Some(artifact) = artifact_cache.load(&id) {
+		// SAFETY: The artifact was produced by `prepare_runtime_artifact` and its checksum was
+		//         verified after it was read from the disk.
+		match unsafe {
+			sc_executor_wasmtime::create_runtime_from_artifact_bytes::<H>(&artifact, config.clone())
+		} {
+			Ok(runtime) => return Ok(Box::new(runtime)),
+			Err(e) => tracing::debug!(
+				target: "wasm-runtime",
+				error = %e,
+				"Cannot load the precompiled runtime, compiling it again",
+			),
+		}
+	}
+
+	let artifact = sc_executor_wasmtime::prepare_runtime_artifact(blob, &semantics)?;
+	if let Err(e) = artifact_cache.store(&id, &artifact) {
+		tracing::warn!(target: "wasm-runtime", error = %e, "Cannot store the compiled runtime");
+	}
+
+	// SAFETY: The artifact was just produced by `prepare_runtime_artifact`.
+	unsafe { sc_executor_wasmtime::create_runtime_from_artifact_bytes::<H>(&artifact, config) }
+		.map(|runtime| -> Box<dyn WasmModule> { Box::new(runtime) })
+}
+
+/// Compile the runtime `code` and store the result in the compiled runtimes cache of the executor
+/// using `cache_path`.
+///
+/// `code_hash` must be the hash of `code` as found in the state, i.e. the hash of the `:code`
+/// storage value. A node using the same `cache_path`, `wasm_method`, `heap_alloc_strategy` and
+/// `call_metering` will then load this runtime without compiling it. `artifact_cache_size` is the
+/// maximum size in bytes of the compiled runtimes kept in `cache_path`.
+///
+/// Returns the path of the stored artifact.
+pub fn precompile_runtime(
+	code: &[u8],
+	code_hash: &[u8],
+	wasm_method: WasmExecutionMethod,
+	heap_alloc_strategy: HeapAllocStrategy,
+	call_metering: bool,
+	cache_path: &Path,
+	artifact_cache_size: u64,
+) -> Result<PathBuf, WasmError> {
+	let semantics = semantics(wasm_method, heap_alloc_strategy, call_metering);
+	if semantics.instantiation_strategy ==
+		sc_executor_wasmtime::InstantiationStrategy::LegacyInstanceReuse
+	{
+		return Err(WasmError::Other(
+			"the legacy instance reuse instantiation strategy can't use precompiled runtimes"
+				.into(),
+		))
+	}
+
+	let blob = RuntimeBlob::uncompress_if_needed(code)?;
+	let artifact = sc_executor_wasmtime::prepare_runtime_artifact(blob, &semantics)?;
+	let id = ArtifactId::new(code_hash, &semantics)?;
+	ArtifactCache::open(artifact_cache_path(cache_path), artifact_cache_size)
+		.and_then(|cache| cache.store(&id, &artifact))
+		.map_err(cache_error)
 }
 
 fn decode_version(mut version: &[u8]) -> Result<RuntimeVersion, WasmError> {
@@ -377,64 +627,6 @@ pub fn read_embedded_version(blob: &RuntimeBlob) -> Result<Option<RuntimeVersion
 	}
 }
 
-fn create_versioned_wasm_runtime<H>(
-	code: &[u8],
-	ext: &mut dyn Externalities,
-	wasm_method: WasmExecutionMethod,
-	heap_alloc_strategy: HeapAllocStrategy,
-	allow_missing_func_imports: bool,
-	max_instances: usize,
-	cache_path: Option<&Path>,
-) -> Result<VersionedRuntime, WasmError>
-where
-	H: HostFunctions,
-{
-	// The incoming code may be actually compressed. We decompress it here and then work with
-	// the uncompressed code from now on.
-	let blob = sc_executor_common::runtime_blob::RuntimeBlob::uncompress_if_needed(code)?;
-
-	// Use the runtime blob to scan if there is any metadata embedded into the wasm binary
-	// pertaining to runtime version. We do it before consuming the runtime blob for creating the
-	// runtime.
-	let mut version = read_embedded_version(&blob)?;
-
-	let runtime = create_wasm_runtime_with_code::<H>(
-		wasm_method,
-		heap_alloc_strategy,
-		blob,
-		allow_missing_func_imports,
-		cache_path,
-	)?;
-
-	// If the runtime blob doesn't embed the runtime version then use the legacy version query
-	// mechanism: call the runtime.
-	if version.is_none() {
-		// Call to determine runtime version.
-		let version_result = {
-			// `ext` is already implicitly handled as unwind safe, as we store it in a global
-			// variable.
-			let mut ext = AssertUnwindSafe(ext);
-
-			// The following unwind safety assertion is OK because if the method call panics, the
-			// runtime will be dropped.
-			let runtime = AssertUnwindSafe(runtime.as_ref());
-			crate::executor::with_externalities_safe(&mut **ext, move || {
-				runtime.new_instance()?.call("Core_version".into(), &[])
-			})
-			.map_err(|_| WasmError::Instantiation("panic in call to get runtime version".into()))?
-		};
-
-		if let Ok(version_buf) = version_result {
-			version = Some(decode_version(&version_buf)?)
-		}
-	}
-
-	let mut instances = Vec::with_capacity(max_instances);
-	instances.resize_with(max_instances, || Mutex::new(None));
-
-	Ok(VersionedRuntime { module: runtime, version, instances })
-}
-
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -444,6 +636,35 @@ mod tests {
 	use sp_wasm_interface::HostFunctions;
 	use substrate_test_runtime::Block;
 
+	#[test]
+	fn artifact_cache_rejects_incompatible_host_functions() {
+		let dir = tempfile::tempdir().unwrap();
+		let artifact_cache =
+			ArtifactCache::open(dir.path(), crate::artifact_cache::DEFAULT_ARTIFACT_CACHE_SIZE)
+				.unwrap();
+		let code = sc_runtime_test::wasm_binary_unwrap();
+		let blob = RuntimeBlob::uncompress_if_needed(code).unwrap();
+		let semantics = semantics(
+			WasmExecutionMethod::default(),
+			HeapAllocStrategy::Static { extra_pages: 1024 },
+			false,
+		);
+
+		let error = create_wasm_runtime_with_artifact_cache::<sp_io::SubstrateHostFunctions>(
+			semantics,
+			blob,
+			&sp_core::blake2_256(code),
+			false,
+			&artifact_cache,
+		)
+		.err()
+		.expect("the test runtime imports missing host functions");
+
+		assert!(matches!(error, WasmError::IncompatibleHostFunctions(_)));
+		// Nothing was compiled, so nothing was stored.
+		assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+	}
+
 	#[derive(Encode)]
 	pub struct OldRuntimeVersion {
 		pub spec_name: RuntimeString,
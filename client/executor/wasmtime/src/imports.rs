@@ -17,17 +17,23 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{host::HostContext, runtime::StoreData};
-use sc_executor_common::error::WasmError;
-use sp_wasm_interface::{FunctionContext, HostFunctions};
+use sc_executor_common::{
+	error::WasmError,
+	runtime_blob::{PROFILER_ENTER_HOOK, PROFILER_EXIT_HOOK},
+};
+use sp_wasm_interface::{FunctionContext, HostFunctionRegistry, HostFunctions};
 use std::collections::HashMap;
-use wasmtime::{ExternType, FuncType, ImportType, Linker, Module};
+use wasmtime::{Caller, ExternType, FuncType, ImportType, Linker, Module};
 
 /// Goes over all imports of a module and prepares the given linker for instantiation of the module.
 /// Returns an error if there are imports that cannot be satisfied.
+///
+/// If `profiling` is set, the hooks of the profiling instrumentation are provided as well.
 pub(crate) fn prepare_imports<H>(
 	linker: &mut Linker<StoreData>,
 	module: &Module,
 	allow_missing_func_imports: bool,
+	profiling: bool,
 ) -> Result<(), WasmError>
 where
 	H: HostFunctions,
@@ -60,6 +66,25 @@ where
 	let mut registry = Registry { linker, pending_func_imports };
 	H::register_static(&mut registry)?;
 
+	if profiling {
+		registry.register_static(
+			PROFILER_ENTER_HOOK,
+			|mut caller: Caller<'_, StoreData>, function: u32| {
+				if let Some(profile) = caller.data_mut().profile.as_mut() {
+					profile.enter(function);
+				}
+			},
+		)?;
+		registry.register_static(
+			PROFILER_EXIT_HOOK,
+			|mut caller: Caller<'_, StoreData>, function: u32| {
+				if let Some(profile) = caller.data_mut().profile.as_mut() {
+					profile.exit(function);
+				}
+			},
+		)?;
+	}
+
 	if !registry.pending_func_imports.is_empty() {
 		if allow_missing_func_imports {
 			for (name, (import_ty, func_ty)) in registry.pending_func_imports {
@@ -69,6 +69,12 @@ impl EntryPoint {
 				dispatcher.call(&mut *store, (func, data_ptr, data_len)),
 		}
 		.map_err(|trap| {
+			if let Some(wasmtime::Trap::OutOfFuel | wasmtime::Trap::Interrupt) =
+				trap.downcast_ref::<wasmtime::Trap>()
+			{
+				return Error::CallBudgetExhausted
+			}
+
 			let host_state = store
 				.data_mut()
 				.host_state
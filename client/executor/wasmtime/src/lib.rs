@@ -30,14 +30,16 @@
 mod host;
 mod imports;
 mod instance_wrapper;
+mod profiler;
 mod runtime;
 mod util;
 
 #[cfg(test)]
 mod tests;
 
+pub use profiler::Profiler;
 pub use runtime::{
-	create_runtime, create_runtime_from_artifact, create_runtime_from_artifact_bytes,
-	prepare_runtime_artifact, Config, DeterministicStackLimit, InstantiationStrategy, Semantics,
-	WasmtimeRuntime,
+	artifact_compatibility_key, create_runtime, create_runtime_from_artifact,
+	create_runtime_from_artifact_bytes, prepare_runtime_artifact, Config, DeterministicStackLimit,
+	InstantiationStrategy, Semantics, WasmtimeRuntime,
 };
@@ -0,0 +1,132 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! An instrumenting profiler attributing the time spent in a runtime call to its functions.
+
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt::Write,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+/// Collects the time spent in the functions of the runtimes it is attached to.
+///
+/// The runtime is instrumented to report every function call, including calls to host functions,
+/// so the profile is exact, but the instrumentation itself slows the execution down considerably.
+/// The samples of all calls are accumulated until they are taken with [`Self::take_folded`].
+#[derive(Debug, Default)]
+pub struct Profiler {
+	/// The nanoseconds spent in each stack, keyed by the folded stack.
+	stacks: Mutex<BTreeMap<String, u64>>,
+}
+
+impl Profiler {
+	/// Creates a new profiler without any samples.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Takes the samples collected so far in the folded stack format.
+	///
+	/// Every line holds the names of the functions of a stack separated by `;`, followed by the
+	/// nanoseconds spent in the innermost function. The output can be rendered with `inferno` or
+	/// the `flamegraph.pl` script.
+	pub fn take_folded(&self) -> String {
+		let stacks =
+			std::mem::take(&mut *self.stacks.lock().expect("the lock is never poisoned; qed"));
+		let mut folded = String::new();
+		for (stack, nanos) in stacks {
+			let _ = writeln!(folded, "{} {}", stack, nanos);
+		}
+		folded
+	}
+
+	/// Adds the samples of a finished call, naming the functions with `function_names`.
+	pub(crate) fn record(&self, function_names: &[String], profile: CallProfile) {
+		let mut stacks = self.stacks.lock().expect("the lock is never poisoned; qed");
+		for (path, duration) in profile.finish() {
+			let stack = path
+				.iter()
+				.map(|function| {
+					function_names.get(*function as usize).map_or("<unknown>", String::as_str)
+				})
+				.collect::<Vec<_>>()
+				.join(";");
+			*stacks.entry(stack).or_default() += duration.as_nanos() as u64;
+		}
+	}
+}
+
+/// A profiler attached to a runtime.
+#[derive(Clone)]
+pub(crate) struct Profiling {
+	pub(crate) profiler: Arc<Profiler>,
+	/// The names of the functions of the runtime, by their index before the instrumentation.
+	pub(crate) function_names: Arc<[String]>,
+}
+
+struct Frame {
+	function: u32,
+	entered: Instant,
+	/// The time spent in the functions called by this one.
+	children: Duration,
+}
+
+/// The samples of a single runtime call.
+#[derive(Default)]
+pub(crate) struct CallProfile {
+	frames: Vec<Frame>,
+	/// The time spent in the innermost function of each stack.
+	samples: HashMap<Vec<u32>, Duration>,
+}
+
+impl CallProfile {
+	/// Called when the runtime enters `function`.
+	pub(crate) fn enter(&mut self, function: u32) {
+		self.frames
+			.push(Frame { function, entered: Instant::now(), children: Duration::ZERO });
+	}
+
+	/// Called when the runtime returns from `function`.
+	pub(crate) fn exit(&mut self, function: u32) {
+		if self.frames.last().map_or(false, |frame| frame.function == function) {
+			self.pop(Instant::now());
+		}
+	}
+
+	fn pop(&mut self, now: Instant) {
+		let path = self.frames.iter().map(|frame| frame.function).collect::<Vec<_>>();
+		let Some(frame) = self.frames.pop() else { return };
+
+		let total = now.saturating_duration_since(frame.entered);
+		*self.samples.entry(path).or_default() += total.saturating_sub(frame.children);
+		if let Some(parent) = self.frames.last_mut() {
+			parent.children += total;
+		}
+	}
+
+	/// Closes the frames left open by a trap and returns the samples.
+	fn finish(mut self) -> HashMap<Vec<u32>, Duration> {
+		let now = Instant::now();
+		while !self.frames.is_empty() {
+			self.pop(now);
+		}
+		self.samples
+	}
+}
@@ -21,17 +21,19 @@
 use crate::{
 	host::HostState,
 	instance_wrapper::{EntryPoint, InstanceWrapper, MemoryWrapper},
+	profiler::{CallProfile, Profiler, Profiling},
 	util::{self, replace_strategy_if_broken},
 };
 
 use sc_allocator::{AllocationStats, FreeingBumpHeapAllocator};
 use sc_executor_common::{
 	error::{Error, Result, WasmError},
+	host_functions::HostFunctionCatalog,
 	runtime_blob::{
 		self, DataSegmentsSnapshot, ExposedMutableGlobalsSet, GlobalsSnapshot, RuntimeBlob,
 	},
 	util::checked_range,
-	wasm_runtime::{HeapAllocStrategy, InvokeMethod, WasmInstance, WasmModule},
+	wasm_runtime::{CallBudget, HeapAllocStrategy, InvokeMethod, WasmInstance, WasmModule},
 };
 use sp_runtime_interface::unpack_ptr_and_len;
 use sp_wasm_interface::{HostFunctions, Pointer, Value, WordSize};
@@ -39,8 +41,9 @@ use std::{
 	path::{Path, PathBuf},
 	sync::{
 		atomic::{AtomicBool, Ordering},
-		Arc,
+		Arc, Once, Weak,
 	},
+	time::Duration,
 };
 use wasmtime::{AsContext, Engine, Memory, Table};
 
@@ -52,6 +55,8 @@ pub(crate) struct StoreData {
 	pub(crate) memory: Option<Memory>,
 	/// This will be set only if the runtime actually contains a table.
 	pub(crate) table: Option<Table>,
+	/// This will only be set when we call into a runtime instrumented for profiling.
+	pub(crate) profile: Option<CallProfile>,
 }
 
 impl StoreData {
@@ -83,6 +88,81 @@ struct InstanceCreator {
 	instance_pre: Arc<wasmtime::InstancePre<StoreData>>,
 }
 
+/// The amount of fuel given to a call that has no fuel limit.
+const UNLIMITED_FUEL: u64 = i64::MAX as u64;
+
+/// The epoch deadline of a call that has no time limit.
+///
+/// Not `u64::MAX`, since wasmtime adds the current epoch to it.
+const UNLIMITED_EPOCHS: u64 = u64::MAX / 2;
+
+/// The interval in which [`EpochTicker`] increments the epoch of the engine.
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Advances the epoch of an engine with call metering enabled.
+///
+/// The ticking thread is only spawned once a call with a time limit is made and exits once the
+/// runtime owning the ticker is dropped.
+struct EpochTicker {
+	engine: wasmtime::Engine,
+	started: Once,
+}
+
+impl EpochTicker {
+	fn new(engine: wasmtime::Engine) -> Arc<Self> {
+		Arc::new(Self { engine, started: Once::new() })
+	}
+
+	fn start(self: &Arc<Self>) {
+		self.started.call_once(|| {
+			let ticker = Arc::downgrade(self);
+			let spawned = std::thread::Builder::new()
+				.name("wasmtime-epoch-ticker".into())
+				.spawn(move || Self::run(ticker));
+			if let Err(error) = spawned {
+				log::warn!("Failed to spawn the wasmtime epoch ticker: {}", error);
+			}
+		});
+	}
+
+	fn run(ticker: Weak<Self>) {
+		loop {
+			std::thread::sleep(EPOCH_TICK);
+			match ticker.upgrade() {
+				Some(ticker) => ticker.engine.increment_epoch(),
+				None => return,
+			}
+		}
+	}
+
+	/// Configures `store` to trap once the given `budget` is exhausted.
+	fn apply(self: &Arc<Self>, store: &mut Store, budget: CallBudget) -> Result<()> {
+		let fuel = budget.max_fuel.unwrap_or(UNLIMITED_FUEL).min(UNLIMITED_FUEL);
+		let remaining = store.consume_fuel(0).map_err(metering_error)?;
+		if remaining < fuel {
+			store.add_fuel(fuel - remaining).map_err(metering_error)?;
+		} else {
+			store.consume_fuel(remaining - fuel).map_err(metering_error)?;
+		}
+
+		match budget.max_duration {
+			Some(max_duration) => {
+				self.start();
+				// The first tick can happen at any point, so round up and add one.
+				let ticks = max_duration.as_nanos() / EPOCH_TICK.as_nanos() + 2;
+				store.set_epoch_deadline(u64::try_from(ticks).unwrap_or(UNLIMITED_EPOCHS));
+			},
+			None => store.set_epoch_deadline(UNLIMITED_EPOCHS),
+		}
+
+		Ok(())
+	}
+}
+
+fn metering_error(error: wasmtime::Error) -> Error {
+	Error::Other(format!("failed to set up call metering: {:#}", error))
+}
+
 impl InstanceCreator {
 	fn instantiate(&mut self) -> Result<InstanceWrapper> {
 		InstanceWrapper::new(&self.engine, &self.instance_pre)
@@ -125,6 +205,10 @@ pub struct WasmtimeRuntime {
 	engine: wasmtime::Engine,
 	instance_pre: Arc<wasmtime::InstancePre<StoreData>>,
 	instantiation_strategy: InternalInstantiationStrategy,
+	/// Set if the runtime was compiled with [`Semantics::call_metering`].
+	epoch_ticker: Option<Arc<EpochTicker>>,
+	/// Set if the runtime was instrumented for [`Config::profiler`].
+	profiling: Option<Profiling>,
 }
 
 impl WasmModule for WasmtimeRuntime {
@@ -156,7 +240,12 @@ impl WasmModule for WasmtimeRuntime {
 			}),
 		};
 
-		Ok(Box::new(WasmtimeInstance { strategy }))
+		Ok(Box::new(WasmtimeInstance {
+			strategy,
+			epoch_ticker: self.epoch_ticker.clone(),
+			budget: None,
+			profiling: self.profiling.clone(),
+		}))
 	}
 }
 
@@ -164,6 +253,10 @@ impl WasmModule for WasmtimeRuntime {
 /// to execute the compiled code.
 pub struct WasmtimeInstance {
 	strategy: Strategy,
+	epoch_ticker: Option<Arc<EpochTicker>>,
+	/// The budget of the next call.
+	budget: Option<CallBudget>,
+	profiling: Option<Profiling>,
 }
 
 impl WasmtimeInstance {
@@ -173,6 +266,10 @@ impl WasmtimeInstance {
 		data: &[u8],
 		allocation_stats: &mut Option<AllocationStats>,
 	) -> Result<Vec<u8>> {
+		let budget = self.budget.take().unwrap_or_default();
+		let metering = self.epoch_ticker.as_ref().map(|ticker| (ticker, budget));
+		let profiling = self.profiling.as_ref();
+
 		match &mut self.strategy {
 			Strategy::LegacyInstanceReuse {
 				ref mut instance_wrapper,
@@ -192,8 +289,15 @@ impl WasmtimeInstance {
 				globals_snapshot.apply(&mut InstanceGlobals { instance: instance_wrapper });
 				let allocator = FreeingBumpHeapAllocator::new(*heap_base);
 
-				let result =
-					perform_call(data, instance_wrapper, entrypoint, allocator, allocation_stats);
+				let result = perform_call(
+					data,
+					instance_wrapper,
+					entrypoint,
+					allocator,
+					metering,
+					profiling,
+					allocation_stats,
+				);
 
 				// Signal to the OS that we are done with the linear memory and that it can be
 				// reclaimed.
@@ -207,7 +311,15 @@ impl WasmtimeInstance {
 				let entrypoint = instance_wrapper.resolve_entrypoint(method)?;
 
 				let allocator = FreeingBumpHeapAllocator::new(heap_base);
-				perform_call(data, &mut instance_wrapper, entrypoint, allocator, allocation_stats)
+				perform_call(
+					data,
+					&mut instance_wrapper,
+					entrypoint,
+					allocator,
+					metering,
+					profiling,
+					allocation_stats,
+				)
 			},
 		}
 	}
@@ -224,6 +336,15 @@ impl WasmInstance for WasmtimeInstance {
 		(result, allocation_stats)
 	}
 
+	fn set_call_budget(&mut self, budget: CallBudget) -> Result<()> {
+		if self.epoch_ticker.is_none() && !budget.is_unlimited() {
+			return Err(Error::CallMeteringUnsupported)
+		}
+
+		self.budget = Some(budget);
+		Ok(())
+	}
+
 	fn get_global_const(&mut self, name: &str) -> Result<Option<Value>> {
 		match &mut self.strategy {
 			Strategy::LegacyInstanceReuse { instance_wrapper, .. } =>
@@ -333,6 +454,11 @@ fn common_config(semantics: &Semantics) -> std::result::Result<wasmtime::Config,
 	config.wasm_threads(false);
 	config.wasm_memory64(false);
 
+	if semantics.call_metering {
+		config.consume_fuel(true);
+		config.epoch_interruption(true);
+	}
+
 	let (use_pooling, use_cow) = match semantics.instantiation_strategy {
 		InstantiationStrategy::PoolingCopyOnWrite => (true, true),
 		InstantiationStrategy::Pooling => (true, false),
@@ -516,6 +642,12 @@ pub struct Semantics {
 
 	/// Enables WASM Fixed-Width SIMD proposal
 	pub wasm_simd: bool,
+
+	/// Enables fuel and epoch based interruption of calls, allowing them to be limited with a
+	/// [`CallBudget`].
+	///
+	/// This makes the compiled code somewhat slower, even for calls without a budget.
+	pub call_metering: bool,
 }
 
 #[derive(Clone)]
@@ -529,6 +661,12 @@ pub struct Config {
 	/// A directory in which wasmtime can store its compiled artifacts cache.
 	pub cache_path: Option<PathBuf>,
 
+	/// Instruments the runtime to report the time spent in its functions to the given profiler.
+	///
+	/// Since this depends on instrumentation, it can be set only if the runtime is instantiated
+	/// using the runtime blob, e.g. using [`create_runtime`].
+	pub profiler: Option<Arc<Profiler>>,
+
 	/// Tuning of various semantics of the wasmtime executor.
 	pub semantics: Semantics,
 }
@@ -646,8 +784,27 @@ where
 	let engine = Engine::new(&wasmtime_config)
 		.map_err(|e| WasmError::Other(format!("cannot create the wasmtime engine: {:#}", e)))?;
 
+	if config.profiler.is_some() && !matches!(code_supply_mode, CodeSupplyMode::Fresh(_)) {
+		return Err(WasmError::Other("profiling is incompatible with precompiled modules".into()))
+	}
+
+	let mut profiling = None;
 	let (module, instantiation_strategy) = match code_supply_mode {
-		CodeSupplyMode::Fresh(blob) => {
+		CodeSupplyMode::Fresh(mut blob) => {
+			// Report every missing host function at once, instead of failing on the first one
+			// the linker can't resolve.
+			if !config.allow_missing_func_imports {
+				HostFunctionCatalog::new::<H>().ensure_compatible(&blob)?;
+			}
+
+			if let Some(ref profiler) = config.profiler {
+				profiling = Some(Profiling {
+					profiler: profiler.clone(),
+					function_names: blob.function_names().into(),
+				});
+				blob.inject_profiling_hooks()?;
+			}
+
 			let blob = prepare_blob_for_compilation(blob, &config.semantics)?;
 			let serialized_blob = blob.clone().serialize();
 
@@ -713,13 +870,26 @@ where
 	};
 
 	let mut linker = wasmtime::Linker::new(&engine);
-	crate::imports::prepare_imports::<H>(&mut linker, &module, config.allow_missing_func_imports)?;
+	crate::imports::prepare_imports::<H>(
+		&mut linker,
+		&module,
+		config.allow_missing_func_imports,
+		profiling.is_some(),
+	)?;
 
 	let instance_pre = linker
 		.instantiate_pre(&module)
 		.map_err(|e| WasmError::Other(format!("cannot preinstantiate module: {:#}", e)))?;
 
-	Ok(WasmtimeRuntime { engine, instance_pre: Arc::new(instance_pre), instantiation_strategy })
+	let epoch_ticker = config.semantics.call_metering.then(|| EpochTicker::new(engine.clone()));
+
+	Ok(WasmtimeRuntime {
+		engine,
+		instance_pre: Arc::new(instance_pre),
+		instantiation_strategy,
+		epoch_ticker,
+		profiling,
+	})
 }
 
 fn prepare_blob_for_compilation(
@@ -766,24 +936,96 @@ pub fn prepare_runtime_artifact(
 		.map_err(|e| WasmError::Other(format!("cannot precompile module: {:#}", e)))
 }
 
+/// Returns a key identifying the artifacts produced by [`prepare_runtime_artifact`] with the given
+/// `semantics`.
+///
+/// Two artifacts of the same code are interchangeable if and only if their keys are equal. The key
+/// covers every part of `semantics` that affects the compiled code and the artifact of an empty
+/// module, which embeds the version of wasmtime and the settings of the engine that compiled it.
+pub fn artifact_compatibility_key(
+	semantics: &Semantics,
+) -> std::result::Result<Vec<u8>, WasmError> {
+	let mut semantics = semantics.clone();
+	replace_strategy_if_broken(&mut semantics.instantiation_strategy);
+
+	let engine = Engine::new(&common_config(&semantics)?)
+		.map_err(|e| WasmError::Other(format!("cannot create the engine: {:#}", e)))?;
+	let mut key = engine
+		.precompile_module(b"\0asm\x01\0\0\0")
+		.map_err(|e| WasmError::Other(format!("cannot precompile the empty module: {:#}", e)))?;
+
+	key.push(match semantics.instantiation_strategy {
+		InstantiationStrategy::PoolingCopyOnWrite => 0,
+		InstantiationStrategy::RecreateInstanceCopyOnWrite => 1,
+		InstantiationStrategy::Pooling => 2,
+		InstantiationStrategy::RecreateInstance => 3,
+		InstantiationStrategy::LegacyInstanceReuse => 4,
+	});
+	match semantics.deterministic_stack_limit {
+		Some(DeterministicStackLimit { logical_max, native_stack_max }) => {
+			key.push(1);
+			key.extend(logical_max.to_le_bytes());
+			key.extend(native_stack_max.to_le_bytes());
+		},
+		None => key.push(0),
+	}
+	match semantics.heap_alloc_strategy {
+		HeapAllocStrategy::Static { extra_pages } => {
+			key.push(0);
+			key.extend(extra_pages.to_le_bytes());
+		},
+		HeapAllocStrategy::Dynamic { maximum_pages } => {
+			key.push(1);
+			key.extend(maximum_pages.map_or(u64::MAX, u64::from).to_le_bytes());
+		},
+	}
+	key.extend(
+		[
+			semantics.canonicalize_nans,
+			semantics.wasm_multi_value,
+			semantics.wasm_bulk_memory,
+			semantics.wasm_reference_types,
+			semantics.wasm_simd,
+			semantics.call_metering,
+		]
+		.map(u8::from),
+	);
+	Ok(key)
+}
+
 fn perform_call(
 	data: &[u8],
 	instance_wrapper: &mut InstanceWrapper,
 	entrypoint: EntryPoint,
 	mut allocator: FreeingBumpHeapAllocator,
+	metering: Option<(&Arc<EpochTicker>, CallBudget)>,
+	profiling: Option<&Profiling>,
 	allocation_stats: &mut Option<AllocationStats>,
 ) -> Result<Vec<u8>> {
 	let (data_ptr, data_len) = inject_input_data(instance_wrapper, &mut allocator, data)?;
 
+	if let Some((epoch_ticker, budget)) = metering {
+		epoch_ticker.apply(instance_wrapper.store_mut(), budget)?;
+	}
+
 	let host_state = HostState::new(allocator);
 
 	// Set the host state before calling into wasm.
 	instance_wrapper.store_mut().data_mut().host_state = Some(host_state);
+	if profiling.is_some() {
+		instance_wrapper.store_mut().data_mut().profile = Some(CallProfile::default());
+	}
 
 	let ret = entrypoint
 		.call(instance_wrapper.store_mut(), data_ptr, data_len)
 		.map(unpack_ptr_and_len);
 
+	if let Some(profiling) = profiling {
+		if let Some(profile) = instance_wrapper.store_mut().data_mut().profile.take() {
+			profiling.profiler.record(&profiling.function_names, profile);
+		}
+	}
+
 	// Reset the host state
 	let host_state = instance_wrapper.store_mut().data_mut().host_state.take().expect(
 		"the host state is always set before calling into WASM so it can't be None here; qed",
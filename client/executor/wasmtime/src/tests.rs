@@ -20,11 +20,12 @@ use codec::{Decode as _, Encode as _};
 use sc_executor_common::{
 	error::Error,
 	runtime_blob::RuntimeBlob,
-	wasm_runtime::{HeapAllocStrategy, WasmModule, DEFAULT_HEAP_ALLOC_STRATEGY},
+	wasm_runtime::{CallBudget, HeapAllocStrategy, WasmModule, DEFAULT_HEAP_ALLOC_STRATEGY},
 };
 use sc_runtime_test::wasm_binary_unwrap;
+use std::{sync::Arc, time::Duration};
 
-use crate::InstantiationStrategy;
+use crate::{InstantiationStrategy, Profiler};
 
 type HostFunctions = sp_io::SubstrateHostFunctions;
 
@@ -83,6 +84,8 @@ struct RuntimeBuilder {
 	deterministic_stack: bool,
 	heap_pages: HeapAllocStrategy,
 	precompile_runtime: bool,
+	call_metering: bool,
+	profiler: Option<Arc<Profiler>>,
 	tmpdir: Option<tempfile::TempDir>,
 }
 
@@ -95,6 +98,8 @@ impl RuntimeBuilder {
 			deterministic_stack: false,
 			heap_pages: DEFAULT_HEAP_ALLOC_STRATEGY,
 			precompile_runtime: false,
+			call_metering: false,
+			profiler: None,
 			tmpdir: None,
 		}
 	}
@@ -119,6 +124,16 @@ impl RuntimeBuilder {
 		self
 	}
 
+	fn call_metering(mut self, call_metering: bool) -> Self {
+		self.call_metering = call_metering;
+		self
+	}
+
+	fn profiler(mut self, profiler: Arc<Profiler>) -> Self {
+		self.profiler = Some(profiler);
+		self
+	}
+
 	fn heap_alloc_strategy(mut self, heap_pages: HeapAllocStrategy) -> Self {
 		self.heap_pages = heap_pages;
 		self
@@ -143,6 +158,7 @@ impl RuntimeBuilder {
 		let config = crate::Config {
 			allow_missing_func_imports: true,
 			cache_path: None,
+			profiler: self.profiler.clone(),
 			semantics: crate::Semantics {
 				instantiation_strategy: self.instantiation_strategy,
 				deterministic_stack_limit: match self.deterministic_stack {
@@ -159,6 +175,7 @@ impl RuntimeBuilder {
 				wasm_bulk_memory: false,
 				wasm_reference_types: false,
 				wasm_simd: false,
+				call_metering: self.call_metering,
 			},
 		};
 
@@ -316,6 +333,127 @@ fn test_stack_depth_reaching(instantiation_strategy: InstantiationStrategy) {
 	}
 }
 
+const INFINITE_LOOP_WAT: &str = r#"
+	(module
+	  (memory $0 1)
+	  (export "memory" (memory $0))
+	  (global (export "__heap_base") i32 (i32.const 0))
+	  (func (export "main") (param i32 i32) (result i64)
+	    (loop $continue (br $continue))
+	    (unreachable)
+	  )
+	)
+"#;
+
+test_wasm_execution!(test_call_runs_out_of_fuel);
+fn test_call_runs_out_of_fuel(instantiation_strategy: InstantiationStrategy) {
+	let mut builder = RuntimeBuilder::new(instantiation_strategy)
+		.use_wat(INFINITE_LOOP_WAT.to_string())
+		.call_metering(true);
+	let runtime = builder.build();
+	let mut instance = runtime.new_instance().expect("failed to instantiate a runtime");
+
+	instance
+		.set_call_budget(CallBudget { max_fuel: Some(1_000_000), ..Default::default() })
+		.unwrap();
+	match instance.call_export("main", &[]).unwrap_err() {
+		Error::CallBudgetExhausted => {},
+		error => panic!("unexpected error: {:?}", error),
+	}
+}
+
+test_wasm_execution!(test_call_runs_out_of_time);
+fn test_call_runs_out_of_time(instantiation_strategy: InstantiationStrategy) {
+	let mut builder = RuntimeBuilder::new(instantiation_strategy)
+		.use_wat(INFINITE_LOOP_WAT.to_string())
+		.call_metering(true);
+	let runtime = builder.build();
+	let mut instance = runtime.new_instance().expect("failed to instantiate a runtime");
+
+	instance
+		.set_call_budget(CallBudget {
+			max_duration: Some(Duration::from_millis(50)),
+			..Default::default()
+		})
+		.unwrap();
+	match instance.call_export("main", &[]).unwrap_err() {
+		Error::CallBudgetExhausted => {},
+		error => panic!("unexpected error: {:?}", error),
+	}
+}
+
+test_wasm_execution!(test_call_budget_is_reset_after_call);
+fn test_call_budget_is_reset_after_call(instantiation_strategy: InstantiationStrategy) {
+	let mut builder = RuntimeBuilder::new(instantiation_strategy).call_metering(true);
+	let runtime = builder.build();
+	let mut instance = runtime.new_instance().expect("failed to instantiate a runtime");
+
+	instance
+		.set_call_budget(CallBudget { max_fuel: Some(0), ..Default::default() })
+		.unwrap();
+	match instance.call_export("test_empty_return", &[]).unwrap_err() {
+		Error::CallBudgetExhausted => {},
+		error => panic!("unexpected error: {:?}", error),
+	}
+
+	// The budget only applies to a single call.
+	instance.call_export("test_empty_return", &[]).unwrap();
+}
+
+test_wasm_execution!(test_call_budget_requires_metering);
+fn test_call_budget_requires_metering(instantiation_strategy: InstantiationStrategy) {
+	let mut builder = RuntimeBuilder::new(instantiation_strategy);
+	let runtime = builder.build();
+	let mut instance = runtime.new_instance().expect("failed to instantiate a runtime");
+
+	instance.set_call_budget(CallBudget::default()).unwrap();
+	match instance.set_call_budget(CallBudget { max_fuel: Some(1), ..Default::default() }) {
+		Err(Error::CallMeteringUnsupported) => {},
+		result => panic!("unexpected result: {:?}", result),
+	}
+}
+
+test_wasm_execution!(test_profiler_records_call_stacks);
+fn test_profiler_records_call_stacks(instantiation_strategy: InstantiationStrategy) {
+	let profiler = Arc::new(Profiler::new());
+	let mut builder = RuntimeBuilder::new(instantiation_strategy)
+		.use_wat(
+			r#"
+			(module
+			  (import "env" "ext_misc_print_num_version_1" (func (param i64)))
+			  (memory $0 32)
+			  (export "memory" (memory $0))
+			  (global (export "__heap_base") i32 (i32.const 0))
+			  (func $callee
+			    (call 0 (i64.const 1))
+			  )
+			  (func $main (export "main") (param i32 i32) (result i64)
+			    (call $callee)
+			    (call $callee)
+			    (i64.const 0)
+			  )
+			)
+			"#
+			.to_string(),
+		)
+		.profiler(profiler.clone());
+	let runtime = builder.build();
+	let mut instance = runtime.new_instance().expect("failed to instantiate a runtime");
+
+	instance.call_export("main", &[]).unwrap();
+	instance.call_export("main", &[]).unwrap();
+
+	let folded = profiler.take_folded();
+	let stacks = folded
+		.lines()
+		.map(|line| line.rsplit_once(' ').expect("every line ends with a sample").0)
+		.collect::<Vec<_>>();
+	assert_eq!(stacks, ["main", "main;callee", "main;callee;ext_misc_print_num_version_1"]);
+
+	// The samples are taken out of the profiler.
+	assert!(profiler.take_folded().is_empty());
+}
+
 test_wasm_execution!(test_max_memory_pages_imported_memory_without_precompilation);
 fn test_max_memory_pages_imported_memory_without_precompilation(
 	instantiation_strategy: InstantiationStrategy,
@@ -472,6 +610,7 @@ fn test_instances_without_reuse_are_not_leaked() {
 		crate::Config {
 			allow_missing_func_imports: true,
 			cache_path: None,
+			profiler: None,
 			semantics: crate::Semantics {
 				instantiation_strategy: InstantiationStrategy::RecreateInstance,
 				deterministic_stack_limit: None,
@@ -482,6 +621,7 @@ fn test_instances_without_reuse_are_not_leaked() {
 				wasm_bulk_memory: false,
 				wasm_reference_types: false,
 				wasm_simd: false,
+				call_metering: false,
 			},
 		},
 	)
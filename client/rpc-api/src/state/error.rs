@@ -52,6 +52,9 @@ pub enum Error {
 	/// Call to an unsafe RPC was denied.
 	#[error(transparent)]
 	UnsafeRpcCalled(#[from] crate::policy::UnsafeRpcError),
+	/// The runtime call ran out of its budget.
+	#[error("Runtime call aborted, it exceeded the limits of the node")]
+	CallBudgetExhausted,
 }
 
 /// Base code for all state errors.
@@ -66,6 +69,9 @@ impl From<Error> for JsonRpseeError {
 			Error::InvalidCount { .. } =>
 				CallError::Custom(ErrorObject::owned(BASE_ERROR + 2, e.to_string(), None::<()>))
 					.into(),
+			Error::CallBudgetExhausted =>
+				CallError::Custom(ErrorObject::owned(BASE_ERROR + 3, e.to_string(), None::<()>))
+					.into(),
 			e => Self::to_call_error(e),
 		}
 	}
@@ -0,0 +1,194 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The archive's types returned as json compatible objects.
+
+use serde::{Deserialize, Serialize};
+
+use crate::chain_head::event::{StorageQueryType, StorageResult};
+
+/// The storage item received as parameter by the `archive_storage` method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaginatedStorageQuery<Key> {
+	/// The provided key.
+	pub key: Key,
+	/// The type of the storage query.
+	#[serde(rename = "type")]
+	pub query_type: StorageQueryType,
+	/// The key after which the iteration of descendants should resume.
+	///
+	/// This is only taken into account for the `descendantsValues` and
+	/// `descendantsHashes` query types.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[serde(default)]
+	pub pagination_start_key: Option<Key>,
+}
+
+/// The result of the `archive_call` method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MethodResult {
+	/// The runtime call succeeded.
+	Ok(MethodResultOk),
+	/// The runtime call failed.
+	Err(MethodResultErr),
+}
+
+impl MethodResult {
+	/// Constructs a successful result.
+	pub fn ok(value: impl Into<String>) -> MethodResult {
+		MethodResult::Ok(MethodResultOk { success: true, value: value.into() })
+	}
+
+	/// Constructs an error result.
+	pub fn err(error: impl Into<String>) -> MethodResult {
+		MethodResult::Err(MethodResultErr { success: false, error: error.into() })
+	}
+}
+
+/// The successful result of the `archive_call` method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodResultOk {
+	/// Always `true` for successful calls.
+	pub success: bool,
+	/// Hexadecimal-encoded output of the runtime function call.
+	pub value: String,
+}
+
+/// The error result of the `archive_call` method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodResultErr {
+	/// Always `false` for failed calls.
+	pub success: bool,
+	/// The reason of the error.
+	pub error: String,
+}
+
+/// The result of the `archive_storage` method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ArchiveStorageResult {
+	/// The storage queries were answered.
+	Ok(ArchiveStorageMethodOk),
+	/// The storage queries could not be answered.
+	Err(ArchiveStorageMethodErr),
+}
+
+impl ArchiveStorageResult {
+	/// Constructs a successful result.
+	pub fn ok(result: Vec<StorageResult>, discarded_items: usize) -> ArchiveStorageResult {
+		ArchiveStorageResult::Ok(ArchiveStorageMethodOk { result, discarded_items })
+	}
+
+	/// Constructs an error result.
+	pub fn err(error: impl Into<String>) -> ArchiveStorageResult {
+		ArchiveStorageResult::Err(ArchiveStorageMethodErr { error: error.into() })
+	}
+}
+
+/// The successful result of the `archive_storage` method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveStorageMethodOk {
+	/// The reported storage items.
+	pub result: Vec<StorageResult>,
+	/// The number of items from the back of the `archive_storage` parameters that have been
+	/// discarded.
+	pub discarded_items: usize,
+}
+
+/// The error result of the `archive_storage` method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveStorageMethodErr {
+	/// The reason of the error.
+	pub error: String,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::chain_head::event::StorageResultType;
+
+	#[test]
+	fn paginated_storage_query_serialize() {
+		let query = PaginatedStorageQuery {
+			key: "0x1",
+			query_type: StorageQueryType::DescendantsValues,
+			pagination_start_key: None,
+		};
+		let ser = serde_json::to_string(&query).unwrap();
+		let exp = r#"{"key":"0x1","type":"descendantsValues"}"#;
+		assert_eq!(ser, exp);
+
+		let query = PaginatedStorageQuery {
+			key: "0x1",
+			query_type: StorageQueryType::DescendantsHashes,
+			pagination_start_key: Some("0x2"),
+		};
+		let ser = serde_json::to_string(&query).unwrap();
+		let exp = r#"{"key":"0x1","type":"descendantsHashes","paginationStartKey":"0x2"}"#;
+		assert_eq!(ser, exp);
+
+		let dec: PaginatedStorageQuery<&str> = serde_json::from_str(exp).unwrap();
+		assert_eq!(dec, query);
+	}
+
+	#[test]
+	fn method_result_serialize() {
+		let ok = MethodResult::ok("0x1");
+		let ser = serde_json::to_string(&ok).unwrap();
+		let exp = r#"{"success":true,"value":"0x1"}"#;
+		assert_eq!(ser, exp);
+		let dec: MethodResult = serde_json::from_str(exp).unwrap();
+		assert_eq!(dec, ok);
+
+		let err = MethodResult::err("error");
+		let ser = serde_json::to_string(&err).unwrap();
+		let exp = r#"{"success":false,"error":"error"}"#;
+		assert_eq!(ser, exp);
+		let dec: MethodResult = serde_json::from_str(exp).unwrap();
+		assert_eq!(dec, err);
+	}
+
+	#[test]
+	fn archive_storage_result_serialize() {
+		let ok = ArchiveStorageResult::ok(
+			vec![StorageResult {
+				key: "0x1".into(),
+				result: StorageResultType::Value("0x2".into()),
+			}],
+			1,
+		);
+		let ser = serde_json::to_string(&ok).unwrap();
+		let exp = r#"{"result":[{"key":"0x1","value":"0x2"}],"discardedItems":1}"#;
+		assert_eq!(ser, exp);
+		let dec: ArchiveStorageResult = serde_json::from_str(exp).unwrap();
+		assert_eq!(dec, ok);
+
+		let err = ArchiveStorageResult::err("error");
+		let ser = serde_json::to_string(&err).unwrap();
+		let exp = r#"{"error":"error"}"#;
+		assert_eq!(ser, exp);
+		let dec: ArchiveStorageResult = serde_json::from_str(exp).unwrap();
+		assert_eq!(dec, err);
+	}
+}
@@ -0,0 +1,47 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Substrate archive API.
+//!
+//! The archive methods give access to the blocks, storage and runtime of any
+//! block known to the node, including finalized blocks that are no longer
+//! tracked by `chainHead_unstable_follow`.
+//!
+//! The methods are only available on nodes that keep the state of all
+//! blocks (archive pruning mode).
+//!
+//! # Note
+//!
+//! Methods are prefixed by `archive`.
+
+#[cfg(test)]
+mod tests;
+
+pub mod api;
+pub mod archive;
+pub mod error;
+pub mod event;
+
+mod archive_storage;
+
+pub use api::ArchiveApiServer;
+pub use archive::{Archive, ArchiveConfig};
+pub use event::{
+	ArchiveStorageMethodErr, ArchiveStorageMethodOk, ArchiveStorageResult, MethodResult,
+	MethodResultErr, MethodResultOk, PaginatedStorageQuery,
+};
@@ -0,0 +1,520 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::chain_head::{
+	event::{StorageQueryType, StorageResult, StorageResultType},
+	hex_string,
+};
+
+use super::*;
+
+use assert_matches::assert_matches;
+use codec::{Decode, Encode};
+use jsonrpsee::{
+	core::error::Error,
+	rpc_params,
+	types::{error::CallError, EmptyServerParams as EmptyParams},
+	RpcModule,
+};
+use sc_block_builder::BlockBuilderProvider;
+use sc_client_api::ChildInfo;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::BlockOrigin;
+use sp_core::{Blake2Hasher, Hasher};
+use std::sync::Arc;
+use substrate_test_runtime::Transfer;
+use substrate_test_runtime_client::{
+	prelude::*, runtime, Backend, BlockBuilderExt, BlocksPruning, Client, ClientBlockImportExt,
+};
+
+type Header = substrate_test_runtime_client::runtime::Header;
+type Block = substrate_test_runtime_client::runtime::Block;
+const CHAIN_GENESIS: [u8; 32] = [0; 32];
+const INVALID_HASH: [u8; 32] = [1; 32];
+const MAX_DESCENDANT_RESPONSES: usize = 5;
+const MAX_QUERIED_ITEMS: usize = 8;
+const KEY: &[u8] = b":mock";
+const VALUE: &[u8] = b"hello world";
+const CHILD_STORAGE_KEY: &[u8] = b"child";
+const CHILD_VALUE: &[u8] = b"child value";
+
+fn setup_api(
+	max_descendant_responses: usize,
+	max_queried_items: usize,
+) -> (Arc<Client<Backend>>, RpcModule<Archive<Backend, Block, Client<Backend>>>) {
+	let child_info = ChildInfo::new_default(CHILD_STORAGE_KEY);
+	// The archive methods require a backend that keeps the state of all blocks.
+	let backend = Arc::new(Backend::new_test_with_tx_storage(BlocksPruning::KeepAll, 0));
+	let builder = TestClientBuilder::with_backend(backend.clone()).add_extra_child_storage(
+		&child_info,
+		KEY.to_vec(),
+		CHILD_VALUE.to_vec(),
+	);
+	let client = Arc::new(builder.build());
+
+	let api = Archive::new(
+		client.clone(),
+		backend,
+		CHAIN_GENESIS,
+		ArchiveConfig { max_descendant_responses, max_queried_items },
+	)
+	.unwrap()
+	.into_rpc();
+
+	(client, api)
+}
+
+#[test]
+fn archive_requires_archive_node() {
+	let builder = TestClientBuilder::with_pruning_window(32);
+	let backend = builder.backend();
+	let client = Arc::new(builder.build());
+
+	let res = Archive::new(client, backend, CHAIN_GENESIS, ArchiveConfig::default());
+	assert!(matches!(res, Err(error::Error::NotArchiveNode)));
+}
+
+#[tokio::test]
+async fn archive_genesis() {
+	let (_client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	let genesis: String =
+		api.call("archive_unstable_genesisHash", EmptyParams::new()).await.unwrap();
+	assert_eq!(genesis, hex_string(&CHAIN_GENESIS));
+}
+
+#[tokio::test]
+async fn archive_body() {
+	let (mut client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	// Invalid block hash.
+	let invalid_hash = hex_string(&INVALID_HASH);
+	let res: Option<Vec<String>> = api.call("archive_unstable_body", [invalid_hash]).await.unwrap();
+	assert!(res.is_none());
+
+	// Import a new block with an extrinsic.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder
+		.push_transfer(runtime::Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 42,
+			nonce: 0,
+		})
+		.unwrap();
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	let expected_tx = hex_string(&block.extrinsics[0].encode());
+
+	let body: Vec<String> = api.call("archive_unstable_body", [block_hash]).await.unwrap();
+	assert_eq!(vec![expected_tx], body);
+}
+
+#[tokio::test]
+async fn archive_header() {
+	let (mut client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	// Invalid block hash.
+	let invalid_hash = hex_string(&INVALID_HASH);
+	let res: Option<String> = api.call("archive_unstable_header", [invalid_hash]).await.unwrap();
+	assert!(res.is_none());
+
+	// Import a new block.
+	let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	let header: String = api.call("archive_unstable_header", [block_hash]).await.unwrap();
+	let bytes = array_bytes::hex2bytes(&header).unwrap();
+	let header: Header = Decode::decode(&mut &bytes[..]).unwrap();
+	assert_eq!(header, block.header);
+}
+
+#[tokio::test]
+async fn archive_finalized_height() {
+	let (client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	let client_height = client.info().finalized_number;
+
+	let height: u64 =
+		api.call("archive_unstable_finalizedHeight", EmptyParams::new()).await.unwrap();
+
+	assert_eq!(client_height, height);
+}
+
+#[tokio::test]
+async fn archive_hash_by_height() {
+	let (mut client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	// Genesis height.
+	let hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [0]).await.unwrap();
+	assert_eq!(hashes, vec![format!("{:?}", client.genesis_hash())]);
+
+	// Block tree:
+	// finalized -> block 1 -> block 2 -> block 3
+	//                      -> block 1 -> block 4
+	//
+	//                          ^^^ h = N
+	//                                     ^^^ h =  N + 1
+	//                                                 ^^^ h = N + 2
+	let finalized_hash = client.info().finalized_hash;
+
+	let block_1 = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let block_1_hash = block_1.header.hash();
+	client.import(BlockOrigin::Own, block_1.clone()).await.unwrap();
+
+	let block_2 = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let block_2_hash = block_2.header.hash();
+	client.import(BlockOrigin::Own, block_2.clone()).await.unwrap();
+
+	let block_3 = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let block_3_hash = block_3.header.hash();
+	client.import(BlockOrigin::Own, block_3.clone()).await.unwrap();
+
+	// Import block 4 fork.
+	let mut block_builder = client.new_block_at(block_1_hash, Default::default(), false).unwrap();
+	// This push is required as otherwise block 4 has the same hash as block 2 and won't get
+	// imported
+	block_builder
+		.push_transfer(Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 41,
+			nonce: 0,
+		})
+		.unwrap();
+	let block_4 = block_builder.build().unwrap().block;
+	let block_4_hash = block_4.header.hash();
+	client.import(BlockOrigin::Own, block_4.clone()).await.unwrap();
+
+	// Check finalized height.
+	let hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [0]).await.unwrap();
+	assert_eq!(hashes, vec![format!("{:?}", finalized_hash)]);
+
+	// Test nonfinalized heights.
+	// Height N must include block 1.
+	let mut height = block_1.header.number;
+	let hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [height]).await.unwrap();
+	assert_eq!(hashes, vec![format!("{:?}", block_1_hash)]);
+
+	// Height (N + 1) must include block 2 and 4.
+	height += 1;
+	let mut hashes: Vec<String> =
+		api.call("archive_unstable_hashByHeight", [height]).await.unwrap();
+	hashes.sort();
+	let mut expected = vec![format!("{:?}", block_2_hash), format!("{:?}", block_4_hash)];
+	expected.sort();
+	assert_eq!(hashes, expected);
+
+	// Height (N + 2) must include block 3.
+	height += 1;
+	let hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [height]).await.unwrap();
+	assert_eq!(hashes, vec![format!("{:?}", block_3_hash)]);
+
+	// Height (N + 3) has no blocks.
+	height += 1;
+	let hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [height]).await.unwrap();
+	assert!(hashes.is_empty());
+}
+
+#[tokio::test]
+async fn archive_call() {
+	let (mut client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+	let invalid_hash = hex_string(&INVALID_HASH);
+
+	// Pass an invalid parameters that cannot be decode.
+	let err = api
+		.call::<_, serde_json::Value>(
+			"archive_unstable_call",
+			// 0x0 is invalid.
+			[&invalid_hash, "BabeApi_current_epoch", "0x0"],
+		)
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == 3001 && err.message().contains("Invalid parameter")
+	);
+
+	// Invalid hash.
+	let result: MethodResult = api
+		.call("archive_unstable_call", [&invalid_hash, "BabeApi_current_epoch", "0x00"])
+		.await
+		.unwrap();
+	assert_matches!(result, MethodResult::Err(_));
+
+	let block_1 = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let block_1_hash = block_1.header.hash();
+	client.import(BlockOrigin::Own, block_1.clone()).await.unwrap();
+
+	// Valid call.
+	let alice_id = AccountKeyring::Alice.to_account_id();
+	// Hex encoded scale encoded bytes representing the call parameters.
+	let call_parameters = hex_string(&alice_id.encode());
+	let result: MethodResult = api
+		.call(
+			"archive_unstable_call",
+			[&format!("{:?}", block_1_hash), "AccountNonceApi_account_nonce", &call_parameters],
+		)
+		.await
+		.unwrap();
+	let expected = MethodResult::ok("0x0000000000000000");
+	assert_eq!(result, expected);
+}
+
+#[tokio::test]
+async fn archive_storage_hashes_values() {
+	let (mut client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	let genesis_hash = format!("{:?}", client.genesis_hash());
+	let key = hex_string(&KEY);
+	let items: Vec<PaginatedStorageQuery<String>> = vec![
+		PaginatedStorageQuery {
+			key: key.clone(),
+			query_type: StorageQueryType::DescendantsHashes,
+			pagination_start_key: None,
+		},
+		PaginatedStorageQuery {
+			key: key.clone(),
+			query_type: StorageQueryType::DescendantsValues,
+			pagination_start_key: None,
+		},
+		PaginatedStorageQuery {
+			key: key.clone(),
+			query_type: StorageQueryType::Hash,
+			pagination_start_key: None,
+		},
+		PaginatedStorageQuery {
+			key: key.clone(),
+			query_type: StorageQueryType::Value,
+			pagination_start_key: None,
+		},
+	];
+
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", rpc_params![&genesis_hash, items.clone()])
+		.await
+		.unwrap();
+
+	match result {
+		ArchiveStorageResult::Ok(ArchiveStorageMethodOk { result, discarded_items }) => {
+			// Key has not been imported yet.
+			assert_eq!(result.len(), 0);
+			assert_eq!(discarded_items, 0);
+		},
+		_ => panic!("Unexpected result"),
+	};
+
+	// Import a block with the given key value pair.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(KEY.to_vec(), Some(VALUE.to_vec())).unwrap();
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	let expected_hash = format!("{:?}", Blake2Hasher::hash(&VALUE));
+	let expected_value = hex_string(&VALUE);
+
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", rpc_params![&block_hash, items])
+		.await
+		.unwrap();
+
+	match result {
+		ArchiveStorageResult::Ok(ArchiveStorageMethodOk { result, discarded_items }) => {
+			assert_eq!(result.len(), 4);
+			assert_eq!(discarded_items, 0);
+
+			assert_eq!(result[0].key, key);
+			assert_eq!(result[0].result, StorageResultType::Hash(expected_hash.clone()));
+			assert_eq!(result[1].key, key);
+			assert_eq!(result[1].result, StorageResultType::Value(expected_value.clone()));
+			assert_eq!(result[2].key, key);
+			assert_eq!(result[2].result, StorageResultType::Hash(expected_hash));
+			assert_eq!(result[3].key, key);
+			assert_eq!(result[3].result, StorageResultType::Value(expected_value));
+		},
+		_ => panic!("Unexpected result"),
+	};
+}
+
+#[tokio::test]
+async fn archive_storage_child_trie() {
+	let (client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	// Child value set in `setup_api`.
+	let child_info = hex_string(&CHILD_STORAGE_KEY);
+	let genesis_hash = format!("{:?}", client.genesis_hash());
+	let key = hex_string(&KEY);
+	let expected_hash = format!("{:?}", Blake2Hasher::hash(&CHILD_VALUE));
+	let expected_value = hex_string(&CHILD_VALUE);
+
+	let items = vec![
+		PaginatedStorageQuery {
+			key: key.clone(),
+			query_type: StorageQueryType::Hash,
+			pagination_start_key: None,
+		},
+		PaginatedStorageQuery {
+			key: key.clone(),
+			query_type: StorageQueryType::Value,
+			pagination_start_key: None,
+		},
+	];
+
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", rpc_params![&genesis_hash, items, &child_info])
+		.await
+		.unwrap();
+
+	match result {
+		ArchiveStorageResult::Ok(ArchiveStorageMethodOk { result, discarded_items }) => {
+			assert_eq!(result.len(), 2);
+			assert_eq!(discarded_items, 0);
+
+			assert_eq!(
+				result[0],
+				StorageResult { key: key.clone(), result: StorageResultType::Hash(expected_hash) }
+			);
+			assert_eq!(
+				result[1],
+				StorageResult { key, result: StorageResultType::Value(expected_value) }
+			);
+		},
+		_ => panic!("Unexpected result"),
+	};
+}
+
+#[tokio::test]
+async fn archive_storage_iterations() {
+	// 1 iteration allowed before pagination kicks in.
+	let (mut client, api) = setup_api(1, MAX_QUERIED_ITEMS);
+
+	// Import a new block with storage changes.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(b":m".to_vec(), Some(b"a".to_vec())).unwrap();
+	builder.push_storage_change(b":mo".to_vec(), Some(b"ab".to_vec())).unwrap();
+	builder.push_storage_change(b":moc".to_vec(), Some(b"abc".to_vec())).unwrap();
+	builder.push_storage_change(b":moD".to_vec(), Some(b"abcmoD".to_vec())).unwrap();
+	builder.push_storage_change(b":mock".to_vec(), Some(b"abcd".to_vec())).unwrap();
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	// Query the descendants one page at a time.
+	let query = |pagination_start_key: Option<String>| {
+		vec![PaginatedStorageQuery {
+			key: hex_string(b":m"),
+			query_type: StorageQueryType::DescendantsValues,
+			pagination_start_key,
+		}]
+	};
+
+	let expected: [(&[u8], &[u8]); 5] = [
+		(b":m", b"a"),
+		(b":mo", b"ab"),
+		(b":moD", b"abcmoD"),
+		(b":moc", b"abc"),
+		(b":mock", b"abcd"),
+	];
+
+	let mut pagination_start_key = None;
+	for (key, value) in expected {
+		let result: ArchiveStorageResult = api
+			.call("archive_unstable_storage", rpc_params![&block_hash, query(pagination_start_key)])
+			.await
+			.unwrap();
+
+		match result {
+			ArchiveStorageResult::Ok(ArchiveStorageMethodOk { result, discarded_items }) => {
+				assert_eq!(result.len(), 1);
+				assert_eq!(discarded_items, 0);
+				assert_eq!(
+					result[0],
+					StorageResult {
+						key: hex_string(&key),
+						result: StorageResultType::Value(hex_string(&value)),
+					}
+				);
+			},
+			_ => panic!("Unexpected result"),
+		};
+
+		// Continue from the last reported key.
+		pagination_start_key = Some(hex_string(&key));
+	}
+
+	// No more items to report.
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", rpc_params![&block_hash, query(pagination_start_key)])
+		.await
+		.unwrap();
+	assert_matches!(
+		result,
+		ArchiveStorageResult::Ok(ArchiveStorageMethodOk { result, discarded_items: 0 }) if result.is_empty()
+	);
+}
+
+#[tokio::test]
+async fn archive_storage_discarded_items() {
+	// One query at a time
+	let (mut client, api) = setup_api(MAX_DESCENDANT_RESPONSES, 1);
+
+	// Import a new block with storage changes.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(b":m".to_vec(), Some(b"a".to_vec())).unwrap();
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	// Valid call with storage at the key.
+	let items = vec![
+		PaginatedStorageQuery {
+			key: hex_string(b":m"),
+			query_type: StorageQueryType::Value,
+			pagination_start_key: None,
+		},
+		PaginatedStorageQuery {
+			key: hex_string(b":m"),
+			query_type: StorageQueryType::Hash,
+			pagination_start_key: None,
+		},
+		PaginatedStorageQuery {
+			key: hex_string(b":m"),
+			query_type: StorageQueryType::Hash,
+			pagination_start_key: None,
+		},
+	];
+
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", rpc_params![&block_hash, items])
+		.await
+		.unwrap();
+
+	match result {
+		ArchiveStorageResult::Ok(ArchiveStorageMethodOk { result, discarded_items }) => {
+			assert_eq!(result.len(), 1);
+			assert_eq!(discarded_items, 2);
+
+			assert_eq!(result[0].key, hex_string(b":m"));
+			assert_eq!(result[0].result, StorageResultType::Value(hex_string(b"a")));
+		},
+		_ => panic!("Unexpected result"),
+	};
+}
@@ -95,6 +95,25 @@ pub trait ChainHeadApi<Hash> {
 		child_trie: Option<String>,
 	) -> RpcResult<MethodResponse>;
 
+	/// Returns the storage entries that differ between two pinned blocks.
+	///
+	/// The descendants of the provided keys are compared between the state of `previous_hash`
+	/// and the state of `hash`. The added, modified and deleted entries are reported by
+	/// `chainHead_unstable_follow` events.
+	///
+	/// # Unstable
+	///
+	/// This method is unstable and subject to change in the future.
+	#[method(name = "chainHead_unstable_storageDiff", blocking)]
+	fn chain_head_unstable_storage_diff(
+		&self,
+		follow_subscription: String,
+		hash: Hash,
+		items: Vec<StorageQuery<String>>,
+		previous_hash: Hash,
+		child_trie: Option<String>,
+	) -> RpcResult<MethodResponse>;
+
 	/// Call into the Runtime API at a specified block's state.
 	///
 	/// # Unstable
@@ -120,8 +139,8 @@ pub trait ChainHeadApi<Hash> {
 	#[method(name = "chainHead_unstable_unpin", blocking)]
 	fn chain_head_unstable_unpin(&self, follow_subscription: String, hash: Hash) -> RpcResult<()>;
 
-	/// Resumes a storage fetch started with `chainHead_storage` after it has generated an
-	/// `operationWaitingForContinue` event.
+	/// Resumes a storage fetch started with `chainHead_storage` or `chainHead_storageDiff` after
+	/// it has generated an `operationWaitingForContinue` event.
 	///
 	/// # Unstable
 	///
@@ -133,9 +152,10 @@ pub trait ChainHeadApi<Hash> {
 		operation_id: String,
 	) -> RpcResult<()>;
 
-	/// Stops an operation started with chainHead_unstable_body, chainHead_unstable_call, or
-	/// chainHead_unstable_storage. If the operation was still in progress, this interrupts it. If
-	/// the operation was already finished, this call has no effect.
+	/// Stops an operation started with chainHead_unstable_body, chainHead_unstable_call,
+	/// chainHead_unstable_storage or chainHead_unstable_storageDiff. If the operation was still in
+	/// progress, this interrupts it. If the operation was already finished, this call has no
+	/// effect.
 	///
 	/// # Unstable
 	///
@@ -20,6 +20,7 @@
 
 use super::{
 	chain_head_storage::ChainHeadStorage,
+	chain_head_storage_diff::ChainHeadStorageDiff,
 	event::{MethodResponseStarted, OperationBodyDone, OperationCallDone},
 };
 use crate::{
@@ -42,8 +43,8 @@ use jsonrpsee::{
 };
 use log::debug;
 use sc_client_api::{
-	Backend, BlockBackend, BlockchainEvents, CallExecutor, ChildInfo, ExecutorProvider, StorageKey,
-	StorageProvider,
+	Backend, BlockBackend, BlockchainEvents, CallBudget, CallExecutor, ChildInfo, ExecutorProvider,
+	StorageKey, StorageProvider,
 };
 use sp_api::CallApiAt;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
@@ -61,9 +62,11 @@ pub struct ChainHeadConfig {
 	pub subscription_max_pinned_duration: Duration,
 	/// The maximum number of ongoing operations per subscription.
 	pub subscription_max_ongoing_operations: usize,
-	/// The maximum number of items reported by the `chainHead_storage` before
-	/// pagination is required.
+	/// The maximum number of items reported by the `chainHead_storage` and
+	/// `chainHead_storageDiff` before pagination is required.
 	pub operation_max_storage_items: usize,
+	/// The limits of the runtime calls made by `chainHead_call`.
+	pub call_budget: CallBudget,
 }
 
 /// Maximum pinned blocks across all connections.
@@ -92,6 +95,7 @@ impl Default for ChainHeadConfig {
 			subscription_max_pinned_duration: MAX_PINNED_DURATION,
 			subscription_max_ongoing_operations: MAX_ONGOING_OPERATIONS,
 			operation_max_storage_items: MAX_STORAGE_ITER_ITEMS,
+			call_budget: CallBudget::default(),
 		}
 	}
 }
@@ -111,6 +115,8 @@ pub struct ChainHead<BE: Backend<Block>, Block: BlockT, Client> {
 	/// The maximum number of items reported by the `chainHead_storage` before
 	/// pagination is required.
 	operation_max_storage_items: usize,
+	/// The limits of the runtime calls made by `chainHead_call`.
+	call_budget: CallBudget,
 	/// Phantom member to pin the block type.
 	_phantom: PhantomData<Block>,
 }
@@ -136,6 +142,7 @@ impl<BE: Backend<Block>, Block: BlockT, Client> ChainHead<BE, Block, Client> {
 				backend,
 			)),
 			operation_max_storage_items: config.operation_max_storage_items,
+			call_budget: config.call_budget,
 			genesis_hash,
 			_phantom: PhantomData,
 		}
@@ -385,6 +392,82 @@ where
 		}))
 	}
 
+	fn chain_head_unstable_storage_diff(
+		&self,
+		follow_subscription: String,
+		hash: Block::Hash,
+		items: Vec<StorageQuery<String>>,
+		previous_hash: Block::Hash,
+		child_trie: Option<String>,
+	) -> RpcResult<MethodResponse> {
+		// Gain control over parameter parsing and returned error.
+		let items = items
+			.into_iter()
+			.map(|query| {
+				if !matches!(
+					query.query_type,
+					StorageQueryType::DescendantsValues | StorageQueryType::DescendantsHashes
+				) {
+					return Err(ChainHeadRpcError::InvalidParam(
+						"Storage diff query type not supported".into(),
+					))
+				}
+
+				Ok(StorageQuery {
+					key: StorageKey(parse_hex_param(query.key)?),
+					query_type: query.query_type,
+				})
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let child_trie = child_trie
+			.map(|child_trie| parse_hex_param(child_trie))
+			.transpose()?
+			.map(ChildInfo::new_default_from_vec);
+
+		// Both blocks must remain pinned while the diff is computed.
+		let mut block_guard = match self.subscriptions.lock_blocks(
+			&follow_subscription,
+			&[hash, previous_hash],
+			items.len(),
+		) {
+			Ok(block) => block,
+			Err(SubscriptionManagementError::SubscriptionAbsent) |
+			Err(SubscriptionManagementError::ExceededLimits) => return Ok(MethodResponse::LimitReached),
+			Err(SubscriptionManagementError::BlockHashAbsent) => {
+				// Block is not part of the subscription.
+				return Err(ChainHeadRpcError::InvalidBlock.into())
+			},
+			Err(_) => return Err(ChainHeadRpcError::InvalidBlock.into()),
+		};
+
+		let mut storage_diff_client = ChainHeadStorageDiff::<BE, Block>::new(
+			self.backend.clone(),
+			self.operation_max_storage_items,
+		);
+		let operation = block_guard.operation();
+		let operation_id = operation.operation_id();
+
+		// The number of operations we are allowed to execute.
+		let num_operations = operation.num_reserved();
+		let discarded = items.len().saturating_sub(num_operations);
+		let mut items = items;
+		items.truncate(num_operations);
+
+		let fut = async move {
+			storage_diff_client
+				.generate_events(block_guard, hash, previous_hash, items, child_trie)
+				.await;
+		};
+
+		self.executor
+			.spawn_blocking("substrate-rpc-subscription", Some("rpc"), fut.boxed());
+		Ok(MethodResponse::Started(MethodResponseStarted {
+			operation_id,
+			discarded_items: Some(discarded),
+		}))
+	}
+
 	fn chain_head_unstable_call(
 		&self,
 		follow_subscription: String,
@@ -420,7 +503,13 @@ where
 		let event = self
 			.client
 			.executor()
-			.call(hash, &function, &call_parameters, CallContext::Offchain)
+			.call_with_budget(
+				hash,
+				&function,
+				&call_parameters,
+				CallContext::Offchain,
+				self.call_budget,
+			)
 			.map(|result| {
 				FollowEvent::<Block::Hash>::OperationCallDone(OperationCallDone {
 					operation_id: operation_id.clone(),
@@ -0,0 +1,239 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the `chainHead_storageDiff` method.
+
+use std::{collections::VecDeque, marker::PhantomData, sync::Arc};
+
+use sc_client_api::{Backend, ChildInfo, StateBackend, StorageKey};
+use sp_api::BlockT;
+use sp_state_machine::{backend::AsTrieBackend, TrieDiffType};
+
+use crate::{
+	chain_head::{
+		event::{OperationStorageDiffItems, StorageDiffResult, StorageDiffType, StorageResultType},
+		hex_string,
+	},
+	common::storage::{is_key_queryable, IterQueryType},
+};
+
+use super::{
+	chain_head_storage::send_error,
+	event::{OperationId, StorageQuery, StorageQueryType},
+	subscription::BlockGuard,
+	FollowEvent,
+};
+
+/// Query to iterate over the keys that differ between two blocks.
+struct QueryDiff {
+	/// The key prefix of the compared descendants.
+	query_key: StorageKey,
+	/// The key after which the iteration should continue (exclusive).
+	///
+	/// When `None`, the iteration starts from the `query_key`.
+	pagination_start_key: Option<StorageKey>,
+	/// The type of the query (either value or hash).
+	ty: IterQueryType,
+}
+
+/// The result of iterating over the keys that differ between two blocks.
+type QueryDiffResult = Result<(Vec<StorageDiffResult>, Option<QueryDiff>), String>;
+
+/// Generates the events of the `chainHead_storageDiff` method.
+pub struct ChainHeadStorageDiff<BE, Block> {
+	/// Backend of the chain.
+	backend: Arc<BE>,
+	/// Queue of operations that may require pagination.
+	iter_operations: VecDeque<QueryDiff>,
+	/// The maximum number of items reported by the `chainHead_storageDiff` before
+	/// pagination is required.
+	operation_max_storage_items: usize,
+	_phandom: PhantomData<Block>,
+}
+
+impl<BE, Block> ChainHeadStorageDiff<BE, Block> {
+	/// Constructs a new [`ChainHeadStorageDiff`].
+	pub fn new(backend: Arc<BE>, operation_max_storage_items: usize) -> Self {
+		Self {
+			backend,
+			iter_operations: VecDeque::new(),
+			operation_max_storage_items,
+			_phandom: PhantomData,
+		}
+	}
+}
+
+impl<BE, Block> ChainHeadStorageDiff<BE, Block>
+where
+	Block: BlockT + 'static,
+	BE: Backend<Block> + 'static,
+{
+	/// Iterate over at most `operation_max_storage_items` keys that differ between the state of
+	/// `previous_hash` and the state of `hash`.
+	///
+	/// The tries of both states are walked at the same time, skipping the identical sub-tries.
+	///
+	/// Returns the storage results with a potential next query to resume iteration.
+	fn query_diff_pagination(
+		&self,
+		query: QueryDiff,
+		hash: Block::Hash,
+		previous_hash: Block::Hash,
+		child_key: Option<&ChildInfo>,
+	) -> QueryDiffResult {
+		let QueryDiff { query_key, pagination_start_key, ty } = query;
+
+		let state = self.backend.state_at(hash).map_err(|err| err.to_string())?;
+		let previous_state = self.backend.state_at(previous_hash).map_err(|err| err.to_string())?;
+
+		let mut diff_iter = state.as_trie_backend().storage_diff(
+			previous_state.as_trie_backend(),
+			child_key,
+			&query_key.0,
+			pagination_start_key.as_ref().map(|key| key.0.as_slice()),
+		)?;
+
+		let mut ret = Vec::with_capacity(self.operation_max_storage_items);
+		let mut last_key = None;
+		for _ in 0..self.operation_max_storage_items {
+			let Some(entry) = diff_iter.next() else { break };
+			let entry = entry.map_err(|err| err.to_string())?;
+
+			// Deleted keys are reported with the value from the previous block.
+			let (diff_type, entry_state, entry_hash) = match entry.diff_type {
+				TrieDiffType::Added => (StorageDiffType::Added, &state, entry.hash),
+				TrieDiffType::Modified => (StorageDiffType::Modified, &state, entry.hash),
+				TrieDiffType::Deleted =>
+					(StorageDiffType::Deleted, &previous_state, entry.previous_hash),
+			};
+
+			let result = match ty {
+				IterQueryType::Value => {
+					let value = if let Some(child_key) = child_key {
+						entry_state.child_storage(child_key, &entry.key)
+					} else {
+						entry_state.storage(&entry.key)
+					}
+					.map_err(|err| err.to_string())?;
+					value.map(|value| StorageResultType::Value(hex_string(&value)))
+				},
+				IterQueryType::Hash =>
+					entry_hash.map(|hash| StorageResultType::Hash(hex_string(&hash.as_ref()))),
+			};
+
+			if let Some(result) = result {
+				ret.push(StorageDiffResult { key: hex_string(&entry.key), result, diff_type });
+			}
+
+			last_key = Some(StorageKey(entry.key));
+		}
+
+		// Save the last reported key if there are more keys to continue the iteration.
+		let maybe_next_query = match (last_key, diff_iter.next()) {
+			(Some(last_key), Some(_)) =>
+				Some(QueryDiff { query_key, pagination_start_key: Some(last_key), ty }),
+			_ => None,
+		};
+		Ok((ret, maybe_next_query))
+	}
+
+	/// Generate the block events for the `chainHead_storageDiff` method.
+	pub async fn generate_events(
+		&mut self,
+		mut block_guard: BlockGuard<Block, BE>,
+		hash: Block::Hash,
+		previous_hash: Block::Hash,
+		items: Vec<StorageQuery<StorageKey>>,
+		child_key: Option<ChildInfo>,
+	) {
+		let sender = block_guard.response_sender();
+		let operation = block_guard.operation();
+
+		if let Some(child_key) = child_key.as_ref() {
+			if !is_key_queryable(child_key.storage_key()) {
+				let _ = sender.unbounded_send(FollowEvent::<Block::Hash>::OperationStorageDone(
+					OperationId { operation_id: operation.operation_id() },
+				));
+				return
+			}
+		}
+
+		for item in items {
+			if !is_key_queryable(&item.key.0) {
+				continue
+			}
+
+			let ty = match item.query_type {
+				StorageQueryType::DescendantsValues => IterQueryType::Value,
+				StorageQueryType::DescendantsHashes => IterQueryType::Hash,
+				_ => continue,
+			};
+			self.iter_operations.push_back(QueryDiff {
+				query_key: item.key,
+				pagination_start_key: None,
+				ty,
+			});
+		}
+
+		while let Some(query) = self.iter_operations.pop_front() {
+			if operation.was_stopped() {
+				return
+			}
+
+			let result = self.query_diff_pagination(query, hash, previous_hash, child_key.as_ref());
+			let (items, maybe_next_query) = match result {
+				QueryDiffResult::Ok(result) => result,
+				QueryDiffResult::Err(error) => {
+					send_error::<Block>(&sender, operation.operation_id(), error);
+					return
+				},
+			};
+
+			if !items.is_empty() {
+				// Send back the results of the iteration produced so far.
+				let _ =
+					sender.unbounded_send(FollowEvent::<Block::Hash>::OperationStorageDiffItems(
+						OperationStorageDiffItems { operation_id: operation.operation_id(), items },
+					));
+			}
+
+			if let Some(next_query) = maybe_next_query {
+				let _ =
+					sender.unbounded_send(FollowEvent::<Block::Hash>::OperationWaitingForContinue(
+						OperationId { operation_id: operation.operation_id() },
+					));
+
+				// The operation might be continued or cancelled only after the
+				// `OperationWaitingForContinue` is generated above.
+				operation.wait_for_continue().await;
+
+				// Give a chance for the other items to advance next time.
+				self.iter_operations.push_back(next_query);
+			}
+		}
+
+		if operation.was_stopped() {
+			return
+		}
+
+		let _ =
+			sender.unbounded_send(FollowEvent::<Block::Hash>::OperationStorageDone(OperationId {
+				operation_id: operation.operation_id(),
+			}));
+	}
+}
@@ -206,6 +206,16 @@ pub struct OperationStorageItems {
 	pub items: Vec<StorageResult>,
 }
 
+/// The response of the `chainHead_storageDiff` method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationStorageDiffItems {
+	/// The operation id of the event.
+	pub operation_id: String,
+	/// The keys that differ between the two blocks.
+	pub items: Vec<StorageDiffResult>,
+}
+
 /// Indicate a problem during the operation.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -231,7 +241,9 @@ pub struct OperationError {
 /// - OperationStorageItems: Items produced by the `chianHead_storage`
 /// - OperationWaitingForContinue: Generated after OperationStorageItems and requires the user to
 ///   call `chainHead_continue`
-/// - OperationStorageDone: The `chianHead_storage` method has produced all the results
+/// - OperationStorageDiffItems: Items produced by the `chainHead_storageDiff`
+/// - OperationStorageDone: The `chianHead_storage` or `chainHead_storageDiff` method has produced
+///   all the results
 /// - OperationInaccessible: The server was unable to provide the result, retries might succeed in
 ///   the future
 /// - OperationError: The server encountered an error, retries will not succeed
@@ -258,10 +270,13 @@ pub enum FollowEvent<Hash> {
 	OperationCallDone(OperationCallDone),
 	/// Yield one or more items found in the storage.
 	OperationStorageItems(OperationStorageItems),
+	/// Yield one or more keys that differ between two blocks.
+	OperationStorageDiffItems(OperationStorageDiffItems),
 	/// Ask the user to call `chainHead_continue` to produce more events
 	/// regarding the operation id.
 	OperationWaitingForContinue(OperationId),
-	/// The responses of the `chainHead_storage` method have been produced.
+	/// The responses of the `chainHead_storage` or `chainHead_storageDiff` method have been
+	/// produced.
 	OperationStorageDone(OperationId),
 	/// The RPC server was unable to provide the response of the following operation id.
 	///
@@ -326,7 +341,36 @@ pub enum StorageResultType {
 	ClosestDescendantMerkleValue(String),
 }
 
-/// The method respose of `chainHead_body`, `chainHead_call` and `chainHead_storage`.
+/// The result of the `chainHead_storageDiff` method for a key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageDiffResult {
+	/// The hex-encoded key of the result.
+	pub key: String,
+	/// The value or the hash of the value.
+	///
+	/// Deleted keys report the value from the previous block.
+	#[serde(flatten)]
+	pub result: StorageResultType,
+	/// The way the key changed between the previous block and the block.
+	#[serde(rename = "type")]
+	pub diff_type: StorageDiffType,
+}
+
+/// The way a key changed between two blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageDiffType {
+	/// The key is present only in the block.
+	Added,
+	/// The key is present in both blocks with different values.
+	Modified,
+	/// The key is present only in the previous block.
+	Deleted,
+}
+
+/// The method respose of `chainHead_body`, `chainHead_call`, `chainHead_storage` and
+/// `chainHead_storageDiff`.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(tag = "result")]
@@ -555,6 +599,37 @@ mod tests {
 		assert_eq!(event_dec, event);
 	}
 
+	#[test]
+	fn follow_op_storage_diff_items_event() {
+		let event: FollowEvent<String> =
+			FollowEvent::OperationStorageDiffItems(OperationStorageDiffItems {
+				operation_id: "123".into(),
+				items: vec![
+					StorageDiffResult {
+						key: "0x1".into(),
+						result: StorageResultType::Value("0x123".to_string()),
+						diff_type: StorageDiffType::Added,
+					},
+					StorageDiffResult {
+						key: "0x2".into(),
+						result: StorageResultType::Hash("0x456".to_string()),
+						diff_type: StorageDiffType::Deleted,
+					},
+				],
+			});
+
+		let ser = serde_json::to_string(&event).unwrap();
+		let exp = concat!(
+			r#"{"event":"operationStorageDiffItems","operationId":"123","items":["#,
+			r#"{"key":"0x1","value":"0x123","type":"added"},"#,
+			r#"{"key":"0x2","hash":"0x456","type":"deleted"}]}"#,
+		);
+		assert_eq!(ser, exp);
+
+		let event_dec: FollowEvent<String> = serde_json::from_str(exp).unwrap();
+		assert_eq!(event_dec, event);
+	}
+
 	#[test]
 	fn follow_op_wait_event() {
 		let event: FollowEvent<String> =
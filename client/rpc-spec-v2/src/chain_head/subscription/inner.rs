@@ -480,11 +480,11 @@ impl<Block: BlockT> SubscriptionState<Block> {
 	}
 }
 
-/// Keeps a specific block pinned while the handle is alive.
-/// This object ensures that the block is not unpinned while
+/// Keeps specific blocks pinned while the handle is alive.
+/// This object ensures that the blocks are not unpinned while
 /// executing an RPC method call.
 pub struct BlockGuard<Block: BlockT, BE: Backend<Block>> {
-	hash: Block::Hash,
+	hashes: Vec<Block::Hash>,
 	with_runtime: bool,
 	response_sender: TracingUnboundedSender<FollowEvent<Block::Hash>>,
 	operation: RegisteredOperation,
@@ -495,24 +495,28 @@ pub struct BlockGuard<Block: BlockT, BE: Backend<Block>> {
 // testing.
 impl<Block: BlockT, BE: Backend<Block>> std::fmt::Debug for BlockGuard<Block, BE> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		write!(f, "BlockGuard hash {:?} with_runtime {:?}", self.hash, self.with_runtime)
+		write!(f, "BlockGuard hashes {:?} with_runtime {:?}", self.hashes, self.with_runtime)
 	}
 }
 
 impl<Block: BlockT, BE: Backend<Block>> BlockGuard<Block, BE> {
 	/// Construct a new [`BlockGuard`] .
 	fn new(
-		hash: Block::Hash,
+		hashes: Vec<Block::Hash>,
 		with_runtime: bool,
 		response_sender: TracingUnboundedSender<FollowEvent<Block::Hash>>,
 		operation: RegisteredOperation,
 		backend: Arc<BE>,
 	) -> Result<Self, SubscriptionManagementError> {
-		backend
-			.pin_block(hash)
-			.map_err(|err| SubscriptionManagementError::Custom(err.to_string()))?;
+		for (index, hash) in hashes.iter().enumerate() {
+			if let Err(err) = backend.pin_block(*hash) {
+				// Release the blocks pinned so far.
+				hashes[..index].iter().for_each(|hash| backend.unpin_block(*hash));
+				return Err(SubscriptionManagementError::Custom(err.to_string()))
+			}
+		}
 
-		Ok(Self { hash, with_runtime, response_sender, operation, backend })
+		Ok(Self { hashes, with_runtime, response_sender, operation, backend })
 	}
 
 	/// The `with_runtime` flag of the subscription.
@@ -533,7 +537,9 @@ impl<Block: BlockT, BE: Backend<Block>> BlockGuard<Block, BE> {
 
 impl<Block: BlockT, BE: Backend<Block>> Drop for BlockGuard<Block, BE> {
 	fn drop(&mut self) {
-		self.backend.unpin_block(self.hash);
+		for hash in &self.hashes {
+			self.backend.unpin_block(*hash);
+		}
 	}
 }
 
@@ -774,12 +780,21 @@ impl<Block: BlockT, BE: Backend<Block>> SubscriptionsInner<Block, BE> {
 		sub_id: &str,
 		hash: Block::Hash,
 		to_reserve: usize,
+	) -> Result<BlockGuard<Block, BE>, SubscriptionManagementError> {
+		self.lock_blocks(sub_id, &[hash], to_reserve)
+	}
+
+	pub fn lock_blocks(
+		&mut self,
+		sub_id: &str,
+		hashes: &[Block::Hash],
+		to_reserve: usize,
 	) -> Result<BlockGuard<Block, BE>, SubscriptionManagementError> {
 		let Some(sub) = self.subs.get_mut(sub_id) else {
 			return Err(SubscriptionManagementError::SubscriptionAbsent)
 		};
 
-		if !sub.contains_block(hash) {
+		if !hashes.iter().all(|hash| sub.contains_block(*hash)) {
 			return Err(SubscriptionManagementError::BlockHashAbsent)
 		}
 
@@ -789,7 +804,7 @@ impl<Block: BlockT, BE: Backend<Block>> SubscriptionsInner<Block, BE> {
 		};
 
 		BlockGuard::new(
-			hash,
+			hashes.to_vec(),
 			sub.with_runtime,
 			sub.response_sender.clone(),
 			operation,
@@ -1031,6 +1046,37 @@ mod tests {
 		assert_eq!(err, SubscriptionManagementError::BlockHashAbsent);
 	}
 
+	#[test]
+	fn subscription_lock_blocks() {
+		let (backend, mut client) = init_backend();
+
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		let hash_1 = block.header.hash();
+		futures::executor::block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		let hash_2 = block.header.hash();
+		futures::executor::block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
+
+		let mut subs =
+			SubscriptionsInner::new(10, Duration::from_secs(10), MAX_OPERATIONS_PER_SUB, backend);
+		let id = "abc".to_string();
+
+		let _stop = subs.insert_subscription(id.clone(), true).unwrap();
+		assert_eq!(subs.pin_block(&id, hash_1).unwrap(), true);
+
+		// All the blocks must be pinned by the subscription.
+		let err = subs.lock_blocks(&id, &[hash_1, hash_2], 1).unwrap_err();
+		assert_eq!(err, SubscriptionManagementError::BlockHashAbsent);
+
+		assert_eq!(subs.pin_block(&id, hash_2).unwrap(), true);
+		let _block_guard = subs.lock_blocks(&id, &[hash_1, hash_2], 1).unwrap();
+
+		// Unpin one of the blocks.
+		subs.unpin_block(&id, hash_2).unwrap();
+		let err = subs.lock_blocks(&id, &[hash_1, hash_2], 1).unwrap_err();
+		assert_eq!(err, SubscriptionManagementError::BlockHashAbsent);
+	}
+
 	#[test]
 	fn subscription_ref_count() {
 		let (backend, mut client) = init_backend();
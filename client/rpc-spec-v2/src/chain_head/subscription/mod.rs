@@ -129,6 +129,19 @@ impl<Block: BlockT, BE: Backend<Block>> SubscriptionManagement<Block, BE> {
 		inner.lock_block(sub_id, hash, to_reserve)
 	}
 
+	/// Ensure the blocks remain pinned until the return object is dropped.
+	///
+	/// Similar to [`Self::lock_block`], for operations that require more than one block.
+	pub fn lock_blocks(
+		&self,
+		sub_id: &str,
+		hashes: &[Block::Hash],
+		to_reserve: usize,
+	) -> Result<BlockGuard<Block, BE>, SubscriptionManagementError> {
+		let mut inner = self.inner.write();
+		inner.lock_blocks(sub_id, hashes, to_reserve)
+	}
+
 	/// Get the operation state.
 	pub fn get_operation(&self, sub_id: &str, operation_id: &str) -> Option<OperationState> {
 		let mut inner = self.inner.write();
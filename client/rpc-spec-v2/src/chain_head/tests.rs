@@ -17,7 +17,10 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::chain_head::{
-	event::{MethodResponse, StorageQuery, StorageQueryType, StorageResultType},
+	event::{
+		MethodResponse, StorageDiffResult, StorageDiffType, StorageQuery, StorageQueryType,
+		StorageResultType,
+	},
 	test_utils::ChainHeadMockClient,
 };
 
@@ -112,6 +115,7 @@ async fn setup_api() -> (
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -156,6 +160,7 @@ async fn follow_subscription_produces_blocks() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -218,6 +223,7 @@ async fn follow_with_runtime() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -330,6 +336,7 @@ async fn get_genesis() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -540,6 +547,7 @@ async fn call_runtime_without_flag() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -982,6 +990,208 @@ async fn get_storage_value() {
 	);
 }
 
+#[tokio::test]
+async fn get_storage_diff() {
+	let (mut client, api, mut block_sub, sub_id, block) = setup_api().await;
+	let block_1_hash = format!("{:?}", block.header.hash());
+	let invalid_hash = hex_string(&INVALID_HASH);
+	let prefix = hex_string(b":m");
+
+	// Import a block that adds keys.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(b":m".to_vec(), Some(b"a".to_vec())).unwrap();
+	builder.push_storage_change(b":mo".to_vec(), Some(b"ab".to_vec())).unwrap();
+	let block = builder.build().unwrap().block;
+	let block_2_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	// Import a block that adds, modifies and deletes keys.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(b":m".to_vec(), Some(b"b".to_vec())).unwrap();
+	builder.push_storage_change(b":mo".to_vec(), None).unwrap();
+	builder.push_storage_change(b":moc".to_vec(), Some(b"abc".to_vec())).unwrap();
+	let block = builder.build().unwrap().block;
+	let block_3_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	// Ensure the imported blocks are propagated and pinned for this subscription.
+	for _ in 0..2 {
+		assert_matches!(
+			get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+			FollowEvent::NewBlock(_)
+		);
+		assert_matches!(
+			get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+			FollowEvent::BestBlockChanged(_)
+		);
+	}
+
+	// Subscription ID is invalid.
+	let response: MethodResponse = api
+		.call(
+			"chainHead_unstable_storageDiff",
+			rpc_params![
+				"invalid_sub_id",
+				&block_3_hash,
+				vec![StorageQuery {
+					key: prefix.clone(),
+					query_type: StorageQueryType::DescendantsValues
+				}],
+				&block_2_hash
+			],
+		)
+		.await
+		.unwrap();
+	assert_matches!(response, MethodResponse::LimitReached);
+
+	// Previous block hash is invalid.
+	let err = api
+		.call::<_, serde_json::Value>(
+			"chainHead_unstable_storageDiff",
+			rpc_params![
+				&sub_id,
+				&block_3_hash,
+				vec![StorageQuery {
+					key: prefix.clone(),
+					query_type: StorageQueryType::DescendantsValues
+				}],
+				&invalid_hash
+			],
+		)
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == 2001 && err.message() == "Invalid block hash"
+	);
+
+	// Only the descendants of the keys can be compared.
+	let err = api
+		.call::<_, serde_json::Value>(
+			"chainHead_unstable_storageDiff",
+			rpc_params![
+				&sub_id,
+				&block_3_hash,
+				vec![StorageQuery { key: prefix.clone(), query_type: StorageQueryType::Value }],
+				&block_2_hash
+			],
+		)
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == 2003 && err.message().contains("Storage diff query type not supported")
+	);
+
+	// Compare the values.
+	let response: MethodResponse = api
+		.call(
+			"chainHead_unstable_storageDiff",
+			rpc_params![
+				&sub_id,
+				&block_3_hash,
+				vec![StorageQuery {
+					key: prefix.clone(),
+					query_type: StorageQueryType::DescendantsValues
+				}],
+				&block_2_hash
+			],
+		)
+		.await
+		.unwrap();
+	let operation_id = match response {
+		MethodResponse::Started(started) => started.operation_id,
+		MethodResponse::LimitReached => panic!("Expected started response"),
+	};
+
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::OperationStorageDiffItems(res) if res.operation_id == operation_id &&
+			res.items == vec![
+				StorageDiffResult {
+					key: hex_string(b":m"),
+					result: StorageResultType::Value(hex_string(b"b")),
+					diff_type: StorageDiffType::Modified,
+				},
+				StorageDiffResult {
+					key: hex_string(b":mo"),
+					result: StorageResultType::Value(hex_string(b"ab")),
+					diff_type: StorageDiffType::Deleted,
+				},
+				StorageDiffResult {
+					key: hex_string(b":moc"),
+					result: StorageResultType::Value(hex_string(b"abc")),
+					diff_type: StorageDiffType::Added,
+				},
+			]
+	);
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::OperationStorageDone(done) if done.operation_id == operation_id
+	);
+
+	// Compare the hashes of an older block with a more specific prefix.
+	let response: MethodResponse = api
+		.call(
+			"chainHead_unstable_storageDiff",
+			rpc_params![
+				&sub_id,
+				&block_1_hash,
+				vec![StorageQuery {
+					key: hex_string(b":mo"),
+					query_type: StorageQueryType::DescendantsHashes
+				}],
+				&block_2_hash
+			],
+		)
+		.await
+		.unwrap();
+	let operation_id = match response {
+		MethodResponse::Started(started) => started.operation_id,
+		MethodResponse::LimitReached => panic!("Expected started response"),
+	};
+
+	// The keys added by the previous block are reported as deleted.
+	let expected_hash = format!("{:?}", Blake2Hasher::hash(b"ab"));
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::OperationStorageDiffItems(res) if res.operation_id == operation_id &&
+			res.items == vec![StorageDiffResult {
+				key: hex_string(b":mo"),
+				result: StorageResultType::Hash(expected_hash),
+				diff_type: StorageDiffType::Deleted,
+			}]
+	);
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::OperationStorageDone(done) if done.operation_id == operation_id
+	);
+
+	// The child trie did not change.
+	let response: MethodResponse = api
+		.call(
+			"chainHead_unstable_storageDiff",
+			rpc_params![
+				&sub_id,
+				&block_3_hash,
+				vec![StorageQuery {
+					key: hex_string(&KEY),
+					query_type: StorageQueryType::DescendantsValues
+				}],
+				&block_1_hash,
+				hex_string(&CHILD_STORAGE_KEY)
+			],
+		)
+		.await
+		.unwrap();
+	let operation_id = match response {
+		MethodResponse::Started(started) => started.operation_id,
+		MethodResponse::LimitReached => panic!("Expected started response"),
+	};
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::OperationStorageDone(done) if done.operation_id == operation_id
+	);
+}
+
 #[tokio::test]
 async fn get_storage_non_queryable_key() {
 	let (mut _client, api, mut block_sub, sub_id, block) = setup_api().await;
@@ -1180,6 +1390,7 @@ async fn separate_operation_ids_for_subscriptions() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -1261,6 +1472,7 @@ async fn follow_generates_initial_blocks() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -1393,6 +1605,7 @@ async fn follow_exceeding_pinned_blocks() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -1448,6 +1661,7 @@ async fn follow_with_unpin() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -1533,6 +1747,7 @@ async fn follow_prune_best_block() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -1694,6 +1909,7 @@ async fn follow_forks_pruned_block() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -1812,6 +2028,7 @@ async fn follow_report_multiple_pruned_block() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -2021,6 +2238,7 @@ async fn pin_block_references() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -2135,6 +2353,7 @@ async fn follow_finalized_before_new_block() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -2236,6 +2455,7 @@ async fn ensure_operation_limits_works() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: 1,
 			operation_max_storage_items: MAX_PAGINATION_LIMIT,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -2334,6 +2554,7 @@ async fn check_continue_operation() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: 1,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -2493,6 +2714,7 @@ async fn stop_storage_operation() {
 			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
 			subscription_max_ongoing_operations: MAX_OPERATIONS,
 			operation_max_storage_items: 1,
+			call_budget: Default::default(),
 		},
 	)
 	.into_rpc();
@@ -23,9 +23,12 @@
 #![warn(missing_docs)]
 #![deny(unused_crate_dependencies)]
 
+pub mod archive;
 pub mod chain_head;
 pub mod chain_spec;
 pub mod transaction;
 
+mod common;
+
 /// Task executor that is being used by RPC subscriptions.
 pub type SubscriptionTaskExecutor = std::sync::Arc<dyn sp_core::traits::SpawnNamed>;
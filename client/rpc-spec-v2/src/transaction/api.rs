@@ -19,7 +19,7 @@
 //! API trait for transactions.
 
 use crate::transaction::event::TransactionEvent;
-use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
 use sp_core::Bytes;
 
 #[rpc(client, server)]
@@ -34,4 +34,31 @@ pub trait TransactionApi<Hash: Clone> {
 		item = TransactionEvent<Hash>,
 	)]
 	fn submit_and_watch(&self, bytes: Bytes);
+
+	/// Broadcast an extrinsic to the peer-to-peer network.
+	///
+	/// The extrinsic is submitted to the transaction pool and is kept there, being
+	/// propagated to peers, until it is finalized or the operation is stopped.
+	/// Extrinsics that are dropped from the pool or become invalid are resubmitted
+	/// on top of the next best block.
+	///
+	/// Returns the operation ID that can be passed to `transaction_unstable_stop`,
+	/// or `null` if the maximum number of concurrent broadcasts has been reached.
+	///
+	/// # Unstable
+	///
+	/// This method is unstable and subject to change in the future.
+	#[method(name = "transaction_unstable_broadcast", blocking)]
+	fn broadcast(&self, bytes: Bytes) -> RpcResult<Option<String>>;
+
+	/// Stop broadcasting an extrinsic previously provided to `transaction_unstable_broadcast`.
+	///
+	/// Returns an error if the operation ID is unknown, or if the broadcast has already
+	/// completed.
+	///
+	/// # Unstable
+	///
+	/// This method is unstable and subject to change in the future.
+	#[method(name = "transaction_unstable_stop", blocking)]
+	fn stop_broadcast(&self, operation_id: String) -> RpcResult<()>;
 }
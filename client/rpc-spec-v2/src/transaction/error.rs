@@ -18,9 +18,14 @@
 
 //! Transaction RPC errors.
 //!
-//! Errors are interpreted as transaction events for subscriptions.
+//! Errors are interpreted as transaction events for subscriptions, while
+//! broadcast errors are returned directly to the caller.
 
 use crate::transaction::event::{TransactionError, TransactionEvent};
+use jsonrpsee::{
+	core::Error as RpcError,
+	types::error::{CallError, ErrorObject},
+};
 use sc_transaction_pool_api::error::Error as PoolError;
 use sp_runtime::transaction_validity::InvalidTransaction;
 
@@ -98,3 +103,41 @@ impl<Hash> From<Error> for TransactionEvent<Hash> {
 		}
 	}
 }
+
+/// The transaction broadcast RPC errors.
+#[derive(Debug, thiserror::Error)]
+pub enum ErrorBroadcast {
+	/// The provided extrinsic cannot be decoded.
+	#[error("Extrinsic has invalid format: {0}")]
+	BadFormat(codec::Error),
+	/// The provided operation ID is invalid.
+	#[error("Invalid operation id")]
+	InvalidOperationID,
+}
+
+/// Extrinsic has an invalid format.
+///
+/// # Note
+///
+/// This is similar to the old `author` API error code.
+pub(crate) const BAD_FORMAT: i32 = 1001;
+/// Invalid method parameter(s), as defined by the JSON-RPC specification.
+const INVALID_PARAM_ERROR: i32 = -32602;
+
+impl From<ErrorBroadcast> for ErrorObject<'static> {
+	fn from(e: ErrorBroadcast) -> Self {
+		let msg = e.to_string();
+
+		match e {
+			ErrorBroadcast::BadFormat(_) => ErrorObject::owned(BAD_FORMAT, msg, None::<()>),
+			ErrorBroadcast::InvalidOperationID =>
+				ErrorObject::owned(INVALID_PARAM_ERROR, msg, None::<()>),
+		}
+	}
+}
+
+impl From<ErrorBroadcast> for RpcError {
+	fn from(e: ErrorBroadcast) -> Self {
+		CallError::Custom(e.into()).into()
+	}
+}
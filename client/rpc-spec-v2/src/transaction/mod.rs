@@ -19,12 +19,16 @@
 //! Substrate transaction API.
 //!
 //! The transaction methods allow submitting a transaction and subscribing to
-//! its status updates generated by the chain.
+//! its status updates generated by the chain, or broadcasting a transaction
+//! without keeping a subscription open.
 //!
 //! # Note
 //!
 //! Methods are prefixed by `transaction`.
 
+#[cfg(test)]
+mod tests;
+
 pub mod api;
 pub mod error;
 pub mod event;
@@ -35,4 +39,4 @@ pub use event::{
 	TransactionBlock, TransactionBroadcasted, TransactionDropped, TransactionError,
 	TransactionEvent,
 };
-pub use transaction::Transaction;
+pub use transaction::{Transaction, TransactionConfig};
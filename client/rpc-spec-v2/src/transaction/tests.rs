@@ -0,0 +1,257 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+use crate::chain_head::hex_string;
+use assert_matches::assert_matches;
+use codec::Encode;
+use jsonrpsee::{core::error::Error, rpc_params, types::error::CallError, RpcModule};
+use sc_block_builder::BlockBuilderProvider;
+use sc_client_api::BlockBackend;
+use sc_transaction_pool::BasicPool;
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool};
+use sp_consensus::BlockOrigin;
+use sp_core::testing::TaskExecutor;
+use std::{sync::Arc, time::Duration};
+use substrate_test_runtime_client::{
+	prelude::*, runtime::Block, Backend, Client, ClientBlockImportExt,
+};
+use substrate_test_runtime_transaction_pool::{uxt, TestApi};
+
+type Pool = BasicPool<TestApi, Block>;
+
+const MAX_CONCURRENT_BROADCASTS: usize = 4;
+
+/// Setup the transaction API with a pool validating transactions against the [`TestApi`].
+///
+/// If `genesis_known_by_pool` is false, the pool cannot validate transactions until
+/// the first block is imported.
+fn setup_api(
+	max_concurrent_broadcasts: usize,
+	genesis_known_by_pool: bool,
+) -> (Arc<TestApi>, Arc<Pool>, Arc<Client<Backend>>, RpcModule<Transaction<Pool, Client<Backend>>>)
+{
+	let client = Arc::new(TestClientBuilder::new().build());
+	let genesis_hash = client.genesis_hash();
+
+	let api = Arc::new(TestApi::empty());
+	if genesis_known_by_pool {
+		let genesis_block = client.block(genesis_hash).unwrap().unwrap().block;
+		api.add_block(genesis_block, true);
+	}
+
+	let (pool, background_task) = BasicPool::new_test(api.clone(), genesis_hash, genesis_hash);
+	tokio::spawn(background_task);
+	let pool = Arc::new(pool);
+
+	let tx_api = Transaction::new(
+		client.clone(),
+		pool.clone(),
+		Arc::new(TaskExecutor::default()),
+		TransactionConfig { max_concurrent_broadcasts },
+	)
+	.into_rpc();
+
+	(api, pool, client, tx_api)
+}
+
+/// Wait until the pool contains the given number of ready transactions.
+async fn wait_pool_ready(pool: &Pool, ready: usize) {
+	tokio::time::timeout(Duration::from_secs(60), async {
+		while pool.status().ready != ready {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		}
+	})
+	.await
+	.unwrap();
+}
+
+#[tokio::test]
+async fn tx_broadcast_enters_pool() {
+	let (_api, pool, _client, tx_api) = setup_api(MAX_CONCURRENT_BROADCASTS, true);
+
+	let xt = uxt(AccountKeyring::Alice, 0);
+	let operation_id: Option<String> = tx_api
+		.call("transaction_unstable_broadcast", rpc_params![hex_string(&xt.encode())])
+		.await
+		.unwrap();
+	let operation_id = operation_id.expect("Broadcast limit not reached; qed");
+
+	wait_pool_ready(&pool, 1).await;
+	let pool_xt = pool.ready().next().unwrap();
+	assert_eq!(pool_xt.data(), &xt);
+
+	// Stop the broadcast.
+	let _: () = tx_api
+		.call("transaction_unstable_stop", rpc_params![&operation_id])
+		.await
+		.unwrap();
+
+	// The operation is no longer known.
+	let err = tx_api
+		.call::<_, ()>("transaction_unstable_stop", rpc_params![&operation_id])
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == -32602 && err.message() == "Invalid operation id"
+	);
+}
+
+#[tokio::test]
+async fn tx_broadcast_invalid_format() {
+	let (_api, _pool, _client, tx_api) = setup_api(MAX_CONCURRENT_BROADCASTS, true);
+
+	let err = tx_api
+		.call::<_, Option<String>>("transaction_unstable_broadcast", rpc_params!["0xdeadbeef"])
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == 1001 && err.message().contains("Extrinsic has invalid format")
+	);
+}
+
+#[tokio::test]
+async fn tx_broadcast_stop_invalid_operation_id() {
+	let (_api, _pool, _client, tx_api) = setup_api(MAX_CONCURRENT_BROADCASTS, true);
+
+	let err = tx_api
+		.call::<_, ()>("transaction_unstable_stop", rpc_params!["invalid_operation_id"])
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == -32602 && err.message() == "Invalid operation id"
+	);
+}
+
+#[tokio::test]
+async fn tx_broadcast_limit_reached() {
+	let (_api, pool, _client, tx_api) = setup_api(1, true);
+
+	let xt = hex_string(&uxt(AccountKeyring::Alice, 0).encode());
+	let operation_id: Option<String> =
+		tx_api.call("transaction_unstable_broadcast", rpc_params![&xt]).await.unwrap();
+	let operation_id = operation_id.expect("Broadcast limit not reached; qed");
+	wait_pool_ready(&pool, 1).await;
+
+	// The limit of concurrent broadcasts is reached.
+	let xt = hex_string(&uxt(AccountKeyring::Bob, 0).encode());
+	let response: Option<String> =
+		tx_api.call("transaction_unstable_broadcast", rpc_params![&xt]).await.unwrap();
+	assert!(response.is_none());
+
+	// Stopping the first broadcast makes room for a new one.
+	let _: () = tx_api
+		.call("transaction_unstable_stop", rpc_params![&operation_id])
+		.await
+		.unwrap();
+
+	let response: Option<String> =
+		tx_api.call("transaction_unstable_broadcast", rpc_params![&xt]).await.unwrap();
+	assert!(response.is_some());
+	wait_pool_ready(&pool, 2).await;
+}
+
+#[tokio::test]
+async fn tx_broadcast_invalid_transaction_is_not_retried() {
+	let (api, pool, _client, tx_api) = setup_api(1, true);
+
+	let xt = uxt(AccountKeyring::Alice, 0);
+	api.add_invalid(&xt);
+	let operation_id: Option<String> = tx_api
+		.call("transaction_unstable_broadcast", rpc_params![hex_string(&xt.encode())])
+		.await
+		.unwrap();
+	let operation_id = operation_id.expect("Broadcast limit not reached; qed");
+
+	// The broadcast ends on its own, freeing its slot and its operation id.
+	let xt = hex_string(&uxt(AccountKeyring::Bob, 0).encode());
+	tokio::time::timeout(Duration::from_secs(60), async {
+		loop {
+			let response: Option<String> =
+				tx_api.call("transaction_unstable_broadcast", rpc_params![&xt]).await.unwrap();
+			if let Some(new_operation_id) = response {
+				assert_ne!(new_operation_id, operation_id);
+				break
+			}
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		}
+	})
+	.await
+	.unwrap();
+	wait_pool_ready(&pool, 1).await;
+
+	let err = tx_api
+		.call::<_, ()>("transaction_unstable_stop", rpc_params![&operation_id])
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == -32602 && err.message() == "Invalid operation id"
+	);
+}
+
+#[tokio::test]
+async fn tx_broadcast_limit_does_not_consume_operation_ids() {
+	let (_api, pool, _client, tx_api) = setup_api(1, true);
+
+	let xt = hex_string(&uxt(AccountKeyring::Alice, 0).encode());
+	let first: Option<String> =
+		tx_api.call("transaction_unstable_broadcast", rpc_params![&xt]).await.unwrap();
+	wait_pool_ready(&pool, 1).await;
+
+	let xt = hex_string(&uxt(AccountKeyring::Bob, 0).encode());
+	for _ in 0..3 {
+		let response: Option<String> =
+			tx_api.call("transaction_unstable_broadcast", rpc_params![&xt]).await.unwrap();
+		assert!(response.is_none());
+	}
+
+	let _: () = tx_api
+		.call("transaction_unstable_stop", rpc_params![first.as_ref().unwrap()])
+		.await
+		.unwrap();
+	let second: Option<String> =
+		tx_api.call("transaction_unstable_broadcast", rpc_params![&xt]).await.unwrap();
+	assert_eq!(second, Some("1".into()));
+}
+
+#[tokio::test]
+async fn tx_broadcast_resubmits_on_next_best_block() {
+	// The pool does not know the genesis block and cannot validate transactions.
+	let (api, pool, mut client, tx_api) = setup_api(MAX_CONCURRENT_BROADCASTS, false);
+
+	let xt = uxt(AccountKeyring::Alice, 0);
+	let operation_id: Option<String> = tx_api
+		.call("transaction_unstable_broadcast", rpc_params![hex_string(&xt.encode())])
+		.await
+		.unwrap();
+	assert!(operation_id.is_some());
+
+	// The transaction cannot be validated at the genesis block.
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	assert_eq!(pool.status().ready, 0);
+
+	// Import a new best block known by the pool.
+	let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	api.add_block(block.clone(), true);
+	client.import(BlockOrigin::Own, block).await.unwrap();
+
+	// The transaction is resubmitted on top of the new best block.
+	wait_pool_ready(&pool, 1).await;
+	let pool_xt = pool.ready().next().unwrap();
+	assert_eq!(pool_xt.data(), &xt);
+}
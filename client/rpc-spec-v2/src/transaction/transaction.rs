@@ -21,7 +21,7 @@
 use crate::{
 	transaction::{
 		api::TransactionApiServer,
-		error::Error,
+		error::{Error, ErrorBroadcast, BAD_FORMAT},
 		event::{
 			TransactionBlock, TransactionBroadcasted, TransactionDropped, TransactionError,
 			TransactionEvent,
@@ -30,18 +30,26 @@ use crate::{
 	SubscriptionTaskExecutor,
 };
 use jsonrpsee::{
-	core::async_trait,
+	core::{async_trait, RpcResult},
 	types::{
 		error::{CallError, ErrorObject},
 		SubscriptionResult,
 	},
 	SubscriptionSink,
 };
+use parking_lot::RwLock;
+use sc_client_api::BlockchainEvents;
 use sc_transaction_pool_api::{
 	error::IntoPoolError, BlockHash, TransactionFor, TransactionPool, TransactionSource,
 	TransactionStatus,
 };
-use std::sync::Arc;
+use std::{
+	collections::HashMap,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+};
 
 use sp_api::ProvideRuntimeApi;
 use sp_blockchain::HeaderBackend;
@@ -49,7 +57,39 @@ use sp_core::Bytes;
 use sp_runtime::{generic, traits::Block as BlockT};
 
 use codec::Decode;
-use futures::{FutureExt, StreamExt, TryFutureExt};
+use futures::{
+	future::{self, AbortHandle},
+	FutureExt, Stream, StreamExt, TryFutureExt,
+};
+
+/// The configuration of [`Transaction`].
+pub struct TransactionConfig {
+	/// The maximum number of transactions broadcasted at the same time.
+	///
+	/// # Note
+	///
+	/// The limit is shared by all the connections served by this instance. `jsonrpsee` 0.16
+	/// passes the `ConnectionId` only to its internal method callbacks, so the methods can not
+	/// tell the connections apart. Switch to a per-connection limit once `jsonrpsee` exposes the
+	/// connection to the methods.
+	pub max_concurrent_broadcasts: usize,
+}
+
+/// The maximum number of transactions broadcasted at the same time.
+/// Note: The lower limit imposed by the spec is 4.
+const MAX_CONCURRENT_BROADCASTS: usize = 16;
+
+impl Default for TransactionConfig {
+	fn default() -> Self {
+		TransactionConfig { max_concurrent_broadcasts: MAX_CONCURRENT_BROADCASTS }
+	}
+}
+
+/// The state of a transaction broadcast.
+struct BroadcastState {
+	/// Handle to abort the broadcast future.
+	handle: AbortHandle,
+}
 
 /// An API for transaction RPC calls.
 pub struct Transaction<Pool, Client> {
@@ -59,12 +99,35 @@ pub struct Transaction<Pool, Client> {
 	pool: Arc<Pool>,
 	/// Executor to spawn subscriptions.
 	executor: SubscriptionTaskExecutor,
+	/// The ongoing broadcasts indexed by their operation ID.
+	broadcast_ids: Arc<RwLock<HashMap<String, BroadcastState>>>,
+	/// The ID of the next broadcast operation.
+	next_operation_id: AtomicUsize,
+	/// The maximum number of transactions broadcasted at the same time.
+	max_concurrent_broadcasts: usize,
 }
 
 impl<Pool, Client> Transaction<Pool, Client> {
 	/// Creates a new [`Transaction`].
-	pub fn new(client: Arc<Client>, pool: Arc<Pool>, executor: SubscriptionTaskExecutor) -> Self {
-		Transaction { client, pool, executor }
+	pub fn new(
+		client: Arc<Client>,
+		pool: Arc<Pool>,
+		executor: SubscriptionTaskExecutor,
+		config: TransactionConfig,
+	) -> Self {
+		Transaction {
+			client,
+			pool,
+			executor,
+			broadcast_ids: Default::default(),
+			next_operation_id: AtomicUsize::new(0),
+			max_concurrent_broadcasts: config.max_concurrent_broadcasts,
+		}
+	}
+
+	/// Generate a unique operation ID for a broadcast.
+	fn next_operation_id(&self) -> String {
+		self.next_operation_id.fetch_add(1, Ordering::Relaxed).to_string()
 	}
 }
 
@@ -75,20 +138,18 @@ impl<Pool, Client> Transaction<Pool, Client> {
 /// some unique transactions via RPC and have them included in the pool.
 const TX_SOURCE: TransactionSource = TransactionSource::External;
 
-/// Extrinsic has an invalid format.
-///
-/// # Note
-///
-/// This is similar to the old `author` API error code.
-const BAD_FORMAT: i32 = 1001;
-
 #[async_trait]
 impl<Pool, Client> TransactionApiServer<BlockHash<Pool>> for Transaction<Pool, Client>
 where
 	Pool: TransactionPool + Sync + Send + 'static,
 	Pool::Hash: Unpin,
 	<Pool::Block as BlockT>::Hash: Unpin,
-	Client: HeaderBackend<Pool::Block> + ProvideRuntimeApi<Pool::Block> + Send + Sync + 'static,
+	Client: HeaderBackend<Pool::Block>
+		+ ProvideRuntimeApi<Pool::Block>
+		+ BlockchainEvents<Pool::Block>
+		+ Send
+		+ Sync
+		+ 'static,
 {
 	fn submit_and_watch(&self, mut sink: SubscriptionSink, xt: Bytes) -> SubscriptionResult {
 		// This is the only place where the RPC server can return an error for this
@@ -141,6 +202,118 @@ where
 		self.executor.spawn("substrate-rpc-subscription", Some("rpc"), fut.boxed());
 		Ok(())
 	}
+
+	fn broadcast(&self, xt: Bytes) -> RpcResult<Option<String>> {
+		let decoded_extrinsic =
+			TransactionFor::<Pool>::decode(&mut &xt[..]).map_err(ErrorBroadcast::BadFormat)?;
+
+		// Subscribe before submitting to not miss the best blocks imported in between.
+		let mut best_block_import_stream =
+			Box::pin(self.client.import_notification_stream().filter_map(
+				|notification| async move { notification.is_new_best.then_some(notification.hash) },
+			));
+		let best_block_hash = self.client.info().best_hash;
+		let pool = self.pool.clone();
+
+		let broadcast_transaction_fut = async move {
+			let mut at = best_block_hash;
+
+			loop {
+				let submit = pool
+					.submit_and_watch(
+						&generic::BlockId::hash(at),
+						TX_SOURCE,
+						decoded_extrinsic.clone(),
+					)
+					.await;
+
+				// Flag to determine if the transaction should be submitted again.
+				let should_resubmit = match submit {
+					Ok(mut stream) => {
+						let mut should_resubmit = false;
+						while let Some(event) = stream.next().await {
+							// The transaction left the pool, but could enter it again later.
+							// Invalid transactions are not retried.
+							if event.is_retriable() {
+								should_resubmit = true;
+								break
+							}
+
+							if event.is_final() {
+								break
+							}
+						}
+						should_resubmit
+					},
+					// The transaction was not included in the pool. Invalid and banned
+					// transactions end the broadcast.
+					Err(err) =>
+						err.into_pool_error().map(|err| err.is_retriable()).unwrap_or(false),
+				};
+
+				if !should_resubmit {
+					return
+				}
+
+				// Retry on top of the latest best block.
+				let Some(best_block_hash) =
+					last_stream_element(&mut best_block_import_stream).await
+				else {
+					return
+				};
+				at = best_block_hash;
+			}
+		};
+
+		// Keep track of the abort handle to terminate the broadcast from `transaction_stop`.
+		let (fut, handle) = future::abortable(broadcast_transaction_fut);
+
+		let operation_id = {
+			let mut broadcast_ids = self.broadcast_ids.write();
+			if broadcast_ids.len() >= self.max_concurrent_broadcasts {
+				return Ok(None)
+			}
+
+			let operation_id = self.next_operation_id();
+			broadcast_ids.insert(operation_id.clone(), BroadcastState { handle });
+			operation_id
+		};
+
+		let broadcast_ids = self.broadcast_ids.clone();
+		let drop_id = operation_id.clone();
+		let fut = fut.map(move |_| {
+			// The broadcast either completed or was aborted.
+			broadcast_ids.write().remove(&drop_id);
+		});
+
+		self.executor.spawn("substrate-rpc-subscription", Some("rpc"), fut.boxed());
+		Ok(Some(operation_id))
+	}
+
+	fn stop_broadcast(&self, operation_id: String) -> RpcResult<()> {
+		let Some(broadcast_state) = self.broadcast_ids.write().remove(&operation_id) else {
+			return Err(ErrorBroadcast::InvalidOperationID.into())
+		};
+
+		broadcast_state.handle.abort();
+		Ok(())
+	}
+}
+
+/// Returns the last element of the stream that is immediately available.
+///
+/// Waits for the next element if none is available.
+async fn last_stream_element<S>(stream: &mut S) -> Option<S::Item>
+where
+	S: Stream + Unpin,
+{
+	let mut last = stream.next().await?;
+
+	while let Some(Some(next)) = stream.next().now_or_never() {
+		last = next;
+	}
+
+	Some(last)
 }
 
 /// The transaction's state that needs to be preserved between
@@ -200,6 +373,14 @@ impl TransactionState {
 			TransactionStatus::Dropped => Some(TransactionEvent::Invalid(TransactionError {
 				error: "Extrinsic dropped from the pool due to exceeding limits".into(),
 			})),
+			TransactionStatus::Evicted => Some(TransactionEvent::Invalid(TransactionError {
+				error: "Extrinsic evicted from the pool by a higher priority extrinsic".into(),
+			})),
+			TransactionStatus::SenderLimitReached =>
+				Some(TransactionEvent::Invalid(TransactionError {
+					error: "Extrinsic dropped from the pool due to exceeding the sender limit"
+						.into(),
+				})),
 			TransactionStatus::Invalid => Some(TransactionEvent::Invalid(TransactionError {
 				error: "Extrinsic marked as invalid".into(),
 			})),
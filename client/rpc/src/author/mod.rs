@@ -26,7 +26,7 @@ use std::sync::Arc;
 use crate::SubscriptionTaskExecutor;
 
 use codec::{Decode, Encode};
-use futures::{FutureExt, TryFutureExt};
+use futures::{FutureExt, StreamExt, TryFutureExt};
 use jsonrpsee::{
 	core::{async_trait, Error as JsonRpseeError, RpcResult},
 	types::SubscriptionResult,
@@ -35,7 +35,7 @@ use jsonrpsee::{
 use sc_rpc_api::DenyUnsafe;
 use sc_transaction_pool_api::{
 	error::IntoPoolError, BlockHash, InPoolTransaction, TransactionFor, TransactionPool,
-	TransactionSource, TxHash,
+	TransactionSource, TransactionStatus, TxHash,
 };
 use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
@@ -209,10 +209,22 @@ where
 				},
 			};
 
-			sink.pipe_from_stream(stream).await;
+			sink.pipe_from_stream(stream.map(to_legacy_status)).await;
 		};
 
 		self.executor.spawn("substrate-rpc-subscription", Some("rpc"), fut.boxed());
 		Ok(())
 	}
 }
+
+/// Reports the pool statuses that are finer grained than `Dropped` as `Dropped`, which keeps
+/// the statuses sent to the subscribers of this API unchanged.
+fn to_legacy_status<Hash, BlockHash>(
+	status: TransactionStatus<Hash, BlockHash>,
+) -> TransactionStatus<Hash, BlockHash> {
+	match status {
+		TransactionStatus::Evicted | TransactionStatus::SenderLimitReached =>
+			TransactionStatus::Dropped,
+		status => status,
+	}
+}
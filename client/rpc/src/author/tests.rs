@@ -328,3 +328,18 @@ async fn author_has_key() {
 	};
 	assert!(!has_bob_ed);
 }
+
+#[test]
+fn watch_extrinsic_reports_pool_evictions_as_dropped() {
+	for status in [
+		TransactionStatus::Dropped,
+		TransactionStatus::Evicted,
+		TransactionStatus::SenderLimitReached,
+	] {
+		assert_eq!(to_legacy_status::<H256, H256>(status), TransactionStatus::Dropped);
+	}
+	assert_eq!(
+		to_legacy_status::<H256, H256>(TransactionStatus::Invalid),
+		TransactionStatus::Invalid
+	);
+}
@@ -46,7 +46,8 @@ use sp_api::{CallApiAt, Metadata, ProvideRuntimeApi};
 use self::error::Error;
 
 use sc_client_api::{
-	Backend, BlockBackend, BlockchainEvents, ExecutorProvider, ProofProvider, StorageProvider,
+	Backend, BlockBackend, BlockchainEvents, CallBudget, ExecutorProvider, ProofProvider,
+	StorageProvider,
 };
 pub use sc_rpc_api::{child_state::*, state::*};
 use sp_blockchain::{HeaderBackend, HeaderMetadata};
@@ -169,6 +170,7 @@ pub fn new_full<BE, Block: BlockT, Client>(
 	client: Arc<Client>,
 	executor: SubscriptionTaskExecutor,
 	deny_unsafe: DenyUnsafe,
+	call_budget: CallBudget,
 ) -> (State<Block, Client>, ChildState<Block, Client>)
 where
 	Block: BlockT + 'static,
@@ -189,8 +191,8 @@ where
 	Client::Api: Metadata<Block>,
 {
 	let child_backend =
-		Box::new(self::state_full::FullState::new(client.clone(), executor.clone()));
-	let backend = Box::new(self::state_full::FullState::new(client, executor));
+		Box::new(self::state_full::FullState::new(client.clone(), executor.clone(), call_budget));
+	let backend = Box::new(self::state_full::FullState::new(client, executor, call_budget));
 	(State { backend, deny_unsafe }, ChildState { backend: child_backend })
 }
 
@@ -33,8 +33,8 @@ use jsonrpsee::{
 	SubscriptionSink,
 };
 use sc_client_api::{
-	Backend, BlockBackend, BlockchainEvents, CallExecutor, ExecutorProvider, ProofProvider,
-	StorageProvider,
+	Backend, BlockBackend, BlockchainEvents, CallBudget, CallExecutor, ExecutorProvider,
+	ProofProvider, StorageProvider,
 };
 use sc_rpc_api::state::ReadProof;
 use sp_api::{CallApiAt, Metadata, ProvideRuntimeApi};
@@ -65,6 +65,7 @@ struct QueryStorageRange<Block: BlockT> {
 pub struct FullState<BE, Block: BlockT, Client> {
 	client: Arc<Client>,
 	executor: SubscriptionTaskExecutor,
+	call_budget: CallBudget,
 	_phantom: PhantomData<(BE, Block)>,
 }
 
@@ -78,8 +79,14 @@ where
 	Block: BlockT + 'static,
 {
 	/// Create new state API backend for full nodes.
-	pub fn new(client: Arc<Client>, executor: SubscriptionTaskExecutor) -> Self {
-		Self { client, executor, _phantom: PhantomData }
+	///
+	/// The runtime calls of `state_call` are limited to `call_budget`.
+	pub fn new(
+		client: Arc<Client>,
+		executor: SubscriptionTaskExecutor,
+		call_budget: CallBudget,
+	) -> Self {
+		Self { client, executor, call_budget, _phantom: PhantomData }
 	}
 
 	/// Returns given block hash or best block hash if None is passed.
@@ -198,10 +205,19 @@ where
 			.and_then(|block| {
 				self.client
 					.executor()
-					.call(block, &method, &call_data, CallContext::Offchain)
+					.call_with_budget(
+						block,
+						&method,
+						&call_data,
+						CallContext::Offchain,
+						self.call_budget,
+					)
 					.map(Into::into)
 			})
-			.map_err(client_err)
+			.map_err(|e| match e {
+				ClientError::CallBudgetExhausted => Error::CallBudgetExhausted,
+				e => client_err(e),
+			})
 	}
 
 	// TODO: This is horribly broken; either remove it, or make it streaming.
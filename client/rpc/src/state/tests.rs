@@ -57,7 +57,8 @@ async fn should_return_storage() {
 		.add_extra_storage(b":map:acc2".to_vec(), vec![1, 2, 3])
 		.build();
 	let genesis_hash = client.genesis_hash();
-	let (client, child) = new_full(Arc::new(client), test_executor(), DenyUnsafe::No);
+	let (client, child) =
+		new_full(Arc::new(client), test_executor(), DenyUnsafe::No, Default::default());
 	let key = StorageKey(KEY.to_vec());
 
 	assert_eq!(
@@ -105,7 +106,8 @@ async fn should_return_storage_entries() {
 		.add_extra_child_storage(&child_info, KEY2.to_vec(), CHILD_VALUE2.to_vec())
 		.build();
 	let genesis_hash = client.genesis_hash();
-	let (_client, child) = new_full(Arc::new(client), test_executor(), DenyUnsafe::No);
+	let (_client, child) =
+		new_full(Arc::new(client), test_executor(), DenyUnsafe::No, Default::default());
 
 	let keys = &[StorageKey(KEY1.to_vec()), StorageKey(KEY2.to_vec())];
 	assert_eq!(
@@ -136,7 +138,7 @@ async fn should_return_child_storage() {
 			.build(),
 	);
 	let genesis_hash = client.genesis_hash();
-	let (_client, child) = new_full(client, test_executor(), DenyUnsafe::No);
+	let (_client, child) = new_full(client, test_executor(), DenyUnsafe::No, Default::default());
 	let child_key = prefixed_storage_key();
 	let key = StorageKey(b"key".to_vec());
 
@@ -167,7 +169,7 @@ async fn should_return_child_storage_entries() {
 			.build(),
 	);
 	let genesis_hash = client.genesis_hash();
-	let (_client, child) = new_full(client, test_executor(), DenyUnsafe::No);
+	let (_client, child) = new_full(client, test_executor(), DenyUnsafe::No, Default::default());
 	let child_key = prefixed_storage_key();
 	let keys = vec![StorageKey(b"key1".to_vec()), StorageKey(b"key2".to_vec())];
 
@@ -198,7 +200,7 @@ async fn should_return_child_storage_entries() {
 async fn should_call_contract() {
 	let client = Arc::new(substrate_test_runtime_client::new());
 	let genesis_hash = client.genesis_hash();
-	let (client, _child) = new_full(client, test_executor(), DenyUnsafe::No);
+	let (client, _child) = new_full(client, test_executor(), DenyUnsafe::No, Default::default());
 
 	use jsonrpsee::{core::Error, types::error::CallError};
 
@@ -212,7 +214,8 @@ async fn should_call_contract() {
 async fn should_notify_about_storage_changes() {
 	let mut sub = {
 		let mut client = Arc::new(substrate_test_runtime_client::new());
-		let (api, _child) = new_full(client.clone(), test_executor(), DenyUnsafe::No);
+		let (api, _child) =
+			new_full(client.clone(), test_executor(), DenyUnsafe::No, Default::default());
 
 		let api_rpc = api.into_rpc();
 		let sub = api_rpc.subscribe("state_subscribeStorage", EmptyParams::new()).await.unwrap();
@@ -244,7 +247,8 @@ async fn should_notify_about_storage_changes() {
 async fn should_send_initial_storage_changes_and_notifications() {
 	let mut sub = {
 		let mut client = Arc::new(substrate_test_runtime_client::new());
-		let (api, _child) = new_full(client.clone(), test_executor(), DenyUnsafe::No);
+		let (api, _child) =
+			new_full(client.clone(), test_executor(), DenyUnsafe::No, Default::default());
 
 		let alice_balance_key = [
 			sp_core::hashing::twox_128(b"System"),
@@ -288,7 +292,8 @@ async fn should_send_initial_storage_changes_and_notifications() {
 #[tokio::test]
 async fn should_query_storage() {
 	async fn run_tests(mut client: Arc<TestClient>) {
-		let (api, _child) = new_full(client.clone(), test_executor(), DenyUnsafe::No);
+		let (api, _child) =
+			new_full(client.clone(), test_executor(), DenyUnsafe::No, Default::default());
 
 		let mut add_block = |index| {
 			let mut builder = client.new_block(Default::default()).unwrap();
@@ -510,7 +515,8 @@ async fn should_query_storage() {
 #[tokio::test]
 async fn should_return_runtime_version() {
 	let client = Arc::new(substrate_test_runtime_client::new());
-	let (api, _child) = new_full(client.clone(), test_executor(), DenyUnsafe::No);
+	let (api, _child) =
+		new_full(client.clone(), test_executor(), DenyUnsafe::No, Default::default());
 
 	// it is basically json-encoded substrate_test_runtime_client::runtime::VERSION
 	let result = "{\"specName\":\"test\",\"implName\":\"parity-test\",\"authoringVersion\":1,\
@@ -532,7 +538,7 @@ async fn should_return_runtime_version() {
 async fn should_notify_on_runtime_version_initially() {
 	let mut sub = {
 		let client = Arc::new(substrate_test_runtime_client::new());
-		let (api, _child) = new_full(client, test_executor(), DenyUnsafe::No);
+		let (api, _child) = new_full(client, test_executor(), DenyUnsafe::No, Default::default());
 
 		let api_rpc = api.into_rpc();
 		let sub = api_rpc
@@ -561,7 +567,7 @@ fn should_deserialize_storage_key() {
 #[tokio::test]
 async fn wildcard_storage_subscriptions_are_rpc_unsafe() {
 	let client = Arc::new(substrate_test_runtime_client::new());
-	let (api, _child) = new_full(client, test_executor(), DenyUnsafe::Yes);
+	let (api, _child) = new_full(client, test_executor(), DenyUnsafe::Yes, Default::default());
 
 	let api_rpc = api.into_rpc();
 	let err = api_rpc.subscribe("state_subscribeStorage", EmptyParams::new()).await;
@@ -571,7 +577,7 @@ async fn wildcard_storage_subscriptions_are_rpc_unsafe() {
 #[tokio::test]
 async fn concrete_storage_subscriptions_are_rpc_safe() {
 	let client = Arc::new(substrate_test_runtime_client::new());
-	let (api, _child) = new_full(client, test_executor(), DenyUnsafe::Yes);
+	let (api, _child) = new_full(client, test_executor(), DenyUnsafe::Yes, Default::default());
 	let api_rpc = api.into_rpc();
 
 	let key = StorageKey(STORAGE_KEY.to_vec());
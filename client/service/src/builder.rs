@@ -27,7 +27,7 @@ use crate::{
 };
 use futures::{channel::oneshot, future::ready, FutureExt, StreamExt};
 use jsonrpsee::RpcModule;
-use log::info;
+use log::{debug, info};
 use prometheus_endpoint::Registry;
 use sc_chain_spec::get_extension;
 use sc_client_api::{
@@ -63,7 +63,9 @@ use sc_rpc::{
 	system::SystemApiServer,
 	DenyUnsafe, SubscriptionTaskExecutor,
 };
-use sc_rpc_spec_v2::{chain_head::ChainHeadApiServer, transaction::TransactionApiServer};
+use sc_rpc_spec_v2::{
+	archive::ArchiveApiServer, chain_head::ChainHeadApiServer, transaction::TransactionApiServer,
+};
 use sc_telemetry::{telemetry, ConnectionMessage, Telemetry, TelemetryHandle, SUBSTRATE_INFO};
 use sc_transaction_pool_api::{MaintainedTransactionPool, TransactionPool};
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedSender};
@@ -75,7 +77,7 @@ use sp_consensus::block_validation::{
 use sp_core::traits::{CodeExecutor, SpawnNamed};
 use sp_keystore::KeystorePtr;
 use sp_runtime::traits::{Block as BlockT, BlockIdTo, NumberFor, Zero};
-use std::{str::FromStr, sync::Arc, time::SystemTime};
+use std::{path::PathBuf, str::FromStr, sync::Arc, time::SystemTime};
 
 /// Full client type.
 pub type TFullClient<TBl, TRtApi, TExec> =
@@ -246,13 +248,28 @@ pub fn new_wasm_executor<H: HostFunctions>(config: &Configuration) -> WasmExecut
 	let strategy = config
 		.default_heap_pages
 		.map_or(DEFAULT_HEAP_ALLOC_STRATEGY, |p| HeapAllocStrategy::Static { extra_pages: p as _ });
-	WasmExecutor::<H>::builder()
+	let mut builder = WasmExecutor::<H>::builder()
 		.with_execution_method(config.wasm_method)
 		.with_onchain_heap_alloc_strategy(strategy)
 		.with_offchain_heap_alloc_strategy(strategy)
 		.with_max_runtime_instances(config.max_runtime_instances)
 		.with_runtime_cache_size(config.runtime_cache_size)
-		.build()
+		// Only the runtime calls of the RPC are metered, on their own runtime instances.
+		.with_call_metering(!config.rpc_call_budget.is_unlimited());
+	if config.runtime_artifact_cache_size > 0 {
+		builder = builder
+			.with_cache_path(executor_cache_path(config))
+			.with_artifact_cache_size(config.runtime_artifact_cache_size);
+	}
+	if let Some(ref profiler) = config.runtime_profiler {
+		builder = builder.with_profiler(profiler.clone());
+	}
+	builder.build()
+}
+
+/// Returns the directory in which the executor keeps its caches, e.g. the compiled runtimes.
+pub fn executor_cache_path(config: &Configuration) -> PathBuf {
+	config.data_path.join("executor")
 }
 
 /// Create an instance of default DB-backend backend.
@@ -618,8 +635,12 @@ where
 
 	let (chain, state, child_state) = {
 		let chain = sc_rpc::chain::new_full(client.clone(), task_executor.clone()).into_rpc();
-		let (state, child_state) =
-			sc_rpc::state::new_full(client.clone(), task_executor.clone(), deny_unsafe);
+		let (state, child_state) = sc_rpc::state::new_full(
+			client.clone(),
+			task_executor.clone(),
+			deny_unsafe,
+			config.rpc_call_budget,
+		);
 		let state = state.into_rpc();
 		let child_state = child_state.into_rpc();
 
@@ -630,6 +651,8 @@ where
 		client.clone(),
 		transaction_pool.clone(),
 		task_executor.clone(),
+		// Defaults to sensible limits for the `Transaction`.
+		sc_rpc_spec_v2::transaction::TransactionConfig::default(),
 	)
 	.into_rpc();
 
@@ -638,11 +661,24 @@ where
 		backend.clone(),
 		task_executor.clone(),
 		client.info().genesis_hash,
-		// Defaults to sensible limits for the `ChainHead`.
-		sc_rpc_spec_v2::chain_head::ChainHeadConfig::default(),
+		sc_rpc_spec_v2::chain_head::ChainHeadConfig {
+			call_budget: config.rpc_call_budget,
+			// Defaults to sensible limits for the `ChainHead`.
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
+	// The archive methods are only exposed by nodes keeping the state of all blocks.
+	let archive_v2 = sc_rpc_spec_v2::archive::Archive::new(
+		client.clone(),
+		backend.clone(),
+		client.info().genesis_hash,
+		// Defaults to sensible limits for the `Archive`.
+		sc_rpc_spec_v2::archive::ArchiveConfig::default(),
+	)
+	.map(|archive| archive.into_rpc());
+
 	let author = sc_rpc::author::Author::new(
 		client.clone(),
 		transaction_pool,
@@ -663,6 +699,10 @@ where
 	// Part of the RPC v2 spec.
 	rpc_api.merge(transaction_v2).map_err(|e| Error::Application(e.into()))?;
 	rpc_api.merge(chain_head_v2).map_err(|e| Error::Application(e.into()))?;
+	match archive_v2 {
+		Ok(archive_v2) => rpc_api.merge(archive_v2).map_err(|e| Error::Application(e.into()))?,
+		Err(err) => debug!("Archive RPC methods are not available: {}", err),
+	}
 
 	// Part of the old RPC spec.
 	rpc_api.merge(chain).map_err(|e| Error::Application(e.into()))?;
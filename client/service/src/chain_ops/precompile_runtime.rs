@@ -0,0 +1,57 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{builder::executor_cache_path, config::Configuration, error::Error};
+use sc_executor::{HeapAllocStrategy, DEFAULT_HEAP_ALLOC_STRATEGY};
+use sp_runtime::traits::{Block as BlockT, Hash, HashingFor};
+use std::path::PathBuf;
+
+/// Compile the runtime `code` and store it in the executor cache of the node.
+///
+/// This allows to compile a runtime upgrade ahead of its enactment, so the node doesn't need to
+/// compile it when the upgrade is applied. `code` must be the exact value that will be stored in
+/// `:code` and `heap_pages` the `:heappages` value of the chain, if it is set. `call_metering`
+/// compiles the runtime used for the runtime calls of the RPC the node limits.
+///
+/// Returns the path of the compiled runtime.
+pub fn precompile_runtime<B: BlockT>(
+	config: &Configuration,
+	code: &[u8],
+	heap_pages: Option<u64>,
+	call_metering: bool,
+) -> Result<PathBuf, Error> {
+	if config.runtime_artifact_cache_size == 0 {
+		return Err(Error::Other("The cache of compiled runtimes is disabled".into()))
+	}
+
+	let code_hash = <HashingFor<B> as Hash>::hash(code);
+	let heap_alloc_strategy = heap_pages
+		.or(config.default_heap_pages)
+		.map_or(DEFAULT_HEAP_ALLOC_STRATEGY, |p| HeapAllocStrategy::Static { extra_pages: p as _ });
+
+	sc_executor::precompile_runtime(
+		code,
+		code_hash.as_ref(),
+		config.wasm_method,
+		heap_alloc_strategy,
+		call_metering,
+		&executor_cache_path(config),
+		config.runtime_artifact_cache_size,
+	)
+	.map_err(|e| Error::Other(format!("Failed to precompile the runtime: {}", e)))
+}
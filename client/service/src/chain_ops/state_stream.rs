@@ -0,0 +1,617 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Streaming export and import of the state.
+//!
+//! The state is written as a stream of [`StateRecord`]s: the header of the exported block, the
+//! key/value pairs of the top trie and of every child trie in chunks, and every so often a
+//! checkpoint proving the last exported pair against the state root of the block. Neither the
+//! export nor the import holds the state in memory as a whole, the import verifies it chunk by
+//! chunk and writes it to the database as it goes.
+
+use crate::error::Error;
+use codec::{Decode, Encode};
+use sc_client_api::{HeaderBackend, ProofProvider, StorageProvider};
+use sc_executor::RuntimeBlob;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+	hexdisplay::HexDisplay,
+	storage::{well_known_keys, ChildInfo, StorageKey},
+	Bytes,
+};
+use sp_runtime::{
+	traits::{Block as BlockT, HashingFor, Header as HeaderT},
+	StateVersion,
+};
+use sp_state_machine::{read_child_proof_check, read_proof_check};
+use sp_trie::StorageProof;
+use std::io::{self, BufRead, Read, Write};
+
+/// The magic bytes the binary format starts with.
+const BINARY_MAGIC: &[u8; 8] = b"substate";
+
+/// The version of the binary format.
+const BINARY_VERSION: u8 = 1;
+
+/// The format of a state stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateStreamFormat {
+	/// SCALE encoded records, each prefixed by its length.
+	Binary,
+	/// A JSON object per line, with hex encoded keys and values.
+	Jsonl,
+}
+
+/// A record of a state stream.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum StateRecord {
+	/// The first record, with the SCALE encoded header of the exported block.
+	Header {
+		/// The encoded header.
+		header: Vec<u8>,
+		/// The state version of the runtime of the block.
+		state_version: u8,
+	},
+	/// Key/value pairs of the top trie, or of the child trie with the given storage key, sorted
+	/// by key.
+	Pairs {
+		/// The storage key of the child trie.
+		child: Option<Vec<u8>>,
+		/// The pairs.
+		pairs: Vec<(Vec<u8>, Vec<u8>)>,
+	},
+	/// A proof of the last exported pair against the state root of the block.
+	Checkpoint {
+		/// The storage key of the child trie of the pair.
+		child: Option<Vec<u8>>,
+		/// The key of the pair.
+		key: Vec<u8>,
+		/// The nodes of the proof.
+		proof: Vec<Vec<u8>>,
+	},
+	/// The last record, with the number of exported pairs.
+	End {
+		/// The number of exported pairs.
+		pairs: u64,
+	},
+}
+
+/// The JSON representation of a [`StateRecord`].
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum JsonRecord {
+	#[serde(rename_all = "camelCase")]
+	Header {
+		header: Bytes,
+		state_version: u8,
+	},
+	Pairs {
+		child: Option<Bytes>,
+		pairs: Vec<(Bytes, Bytes)>,
+	},
+	Checkpoint {
+		child: Option<Bytes>,
+		key: Bytes,
+		proof: Vec<Bytes>,
+	},
+	End {
+		pairs: u64,
+	},
+}
+
+impl From<StateRecord> for JsonRecord {
+	fn from(record: StateRecord) -> Self {
+		match record {
+			StateRecord::Header { header, state_version } =>
+				Self::Header { header: header.into(), state_version },
+			StateRecord::Pairs { child, pairs } => Self::Pairs {
+				child: child.map(Into::into),
+				pairs: pairs.into_iter().map(|(k, v)| (k.into(), v.into())).collect(),
+			},
+			StateRecord::Checkpoint { child, key, proof } => Self::Checkpoint {
+				child: child.map(Into::into),
+				key: key.into(),
+				proof: proof.into_iter().map(Into::into).collect(),
+			},
+			StateRecord::End { pairs } => Self::End { pairs },
+		}
+	}
+}
+
+impl From<JsonRecord> for StateRecord {
+	fn from(record: JsonRecord) -> Self {
+		match record {
+			JsonRecord::Header { header, state_version } =>
+				Self::Header { header: header.0, state_version },
+			JsonRecord::Pairs { child, pairs } => Self::Pairs {
+				child: child.map(|c| c.0),
+				pairs: pairs.into_iter().map(|(k, v)| (k.0, v.0)).collect(),
+			},
+			JsonRecord::Checkpoint { child, key, proof } => Self::Checkpoint {
+				child: child.map(|c| c.0),
+				key: key.0,
+				proof: proof.into_iter().map(|node| node.0).collect(),
+			},
+			JsonRecord::End { pairs } => Self::End { pairs },
+		}
+	}
+}
+
+/// Writes [`StateRecord`]s in the given format.
+pub struct StateStreamWriter<W> {
+	output: W,
+	format: StateStreamFormat,
+}
+
+impl<W: Write> StateStreamWriter<W> {
+	/// Create a writer, writing the preamble of the format to `output`.
+	pub fn new(mut output: W, format: StateStreamFormat) -> io::Result<Self> {
+		if format == StateStreamFormat::Binary {
+			output.write_all(BINARY_MAGIC)?;
+			output.write_all(&[BINARY_VERSION])?;
+		}
+		Ok(Self { output, format })
+	}
+
+	/// Write a record.
+	pub fn write(&mut self, record: StateRecord) -> io::Result<()> {
+		match self.format {
+			StateStreamFormat::Binary => {
+				let encoded = record.encode();
+				self.output.write_all(&(encoded.len() as u32).to_le_bytes())?;
+				self.output.write_all(&encoded)
+			},
+			StateStreamFormat::Jsonl => {
+				serde_json::to_writer(&mut self.output, &JsonRecord::from(record))?;
+				self.output.write_all(b"\n")
+			},
+		}
+	}
+
+	/// Flush the output and return it.
+	pub fn finish(mut self) -> io::Result<W> {
+		self.output.flush()?;
+		Ok(self.output)
+	}
+}
+
+/// Reads [`StateRecord`]s, detecting the format of the stream.
+pub struct StateStreamReader<R> {
+	input: R,
+	format: StateStreamFormat,
+}
+
+impl<R: BufRead> StateStreamReader<R> {
+	/// Create a reader, reading the preamble of the format from `input`.
+	pub fn new(mut input: R) -> Result<Self, Error> {
+		let format = if input.fill_buf()?.starts_with(BINARY_MAGIC) {
+			let mut preamble = [0; BINARY_MAGIC.len() + 1];
+			input.read_exact(&mut preamble)?;
+			if preamble[BINARY_MAGIC.len()] != BINARY_VERSION {
+				return Err(Error::Other(format!(
+					"Unsupported version {} of the state stream",
+					preamble[BINARY_MAGIC.len()]
+				)))
+			}
+			StateStreamFormat::Binary
+		} else {
+			StateStreamFormat::Jsonl
+		};
+
+		Ok(Self { input, format })
+	}
+
+	/// The format of the stream.
+	pub fn format(&self) -> StateStreamFormat {
+		self.format
+	}
+
+	fn read(&mut self) -> Result<Option<StateRecord>, Error> {
+		match self.format {
+			StateStreamFormat::Binary => {
+				if self.input.fill_buf()?.is_empty() {
+					return Ok(None)
+				}
+				let mut len = [0; 4];
+				self.input.read_exact(&mut len)?;
+				let mut encoded = vec![0; u32::from_le_bytes(len) as usize];
+				self.input.read_exact(&mut encoded)?;
+				StateRecord::decode(&mut &encoded[..])
+					.map(Some)
+					.map_err(|e| Error::Other(format!("Invalid record: {}", e)))
+			},
+			StateStreamFormat::Jsonl => {
+				let mut line = String::new();
+				while line.trim().is_empty() {
+					line.clear();
+					if self.input.read_line(&mut line)? == 0 {
+						return Ok(None)
+					}
+				}
+				serde_json::from_str::<JsonRecord>(&line)
+					.map(|record| Some(record.into()))
+					.map_err(|e| Error::Other(format!("Invalid record: {}", e)))
+			},
+		}
+	}
+}
+
+impl<R: BufRead> Iterator for StateStreamReader<R> {
+	type Item = Result<StateRecord, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.read().transpose()
+	}
+}
+
+/// Settings of [`export_state_stream`].
+#[derive(Debug, Clone)]
+pub struct StateExportConfig {
+	/// The format to write.
+	pub format: StateStreamFormat,
+	/// The maximum number of pairs per [`StateRecord::Pairs`].
+	pub chunk_size: usize,
+	/// The number of pairs after which a [`StateRecord::Checkpoint`] is written.
+	pub checkpoint_interval: u64,
+	/// The state version of the state, read from the runtime of the block if `None`.
+	pub state_version: Option<StateVersion>,
+}
+
+impl Default for StateExportConfig {
+	fn default() -> Self {
+		Self {
+			format: StateStreamFormat::Binary,
+			chunk_size: 4096,
+			checkpoint_interval: 100_000,
+			state_version: None,
+		}
+	}
+}
+
+/// Export the state of the block `hash` to `output` as a stream of [`StateRecord`]s.
+///
+/// Returns the number of exported key/value pairs.
+pub fn export_state_stream<B, BA, C>(
+	client: &C,
+	hash: B::Hash,
+	output: impl Write,
+	config: StateExportConfig,
+) -> Result<u64, Error>
+where
+	B: BlockT,
+	BA: sc_client_api::backend::Backend<B>,
+	C: StorageProvider<B, BA> + ProofProvider<B> + HeaderBackend<B>,
+{
+	let header = client
+		.header(hash)?
+		.ok_or_else(|| Error::Other(format!("Unknown block {:?}", hash)))?;
+	let state_version = match config.state_version {
+		Some(state_version) => state_version,
+		None => state_version_at(client, hash)?,
+	};
+
+	let mut exporter = ChunkExporter {
+		client,
+		hash,
+		writer: StateStreamWriter::new(output, config.format)?,
+		chunk: Vec::with_capacity(config.chunk_size),
+		config,
+		pairs: 0,
+		since_checkpoint: 0,
+		_phantom: Default::default(),
+	};
+	exporter.writer.write(StateRecord::Header {
+		header: header.encode(),
+		state_version: state_version as u8,
+	})?;
+
+	// The child roots are not exported, the import computes them from the child tries.
+	let mut children = Vec::new();
+	for (key, value) in client.storage_pairs(hash, None, None)? {
+		match key.0.strip_prefix(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+			Some(child) => children.push(ChildInfo::new_default(child)),
+			None => exporter.push(None, key.0, value.0)?,
+		}
+	}
+	exporter.flush(None)?;
+
+	for child_info in children {
+		for key in client.child_storage_keys(hash, child_info.clone(), None, None)? {
+			if let Some(value) = client.child_storage(hash, &child_info, &key)? {
+				exporter.push(Some(&child_info), key.0, value.0)?;
+			}
+		}
+		exporter.flush(Some(&child_info))?;
+	}
+
+	let pairs = exporter.pairs;
+	exporter.writer.write(StateRecord::End { pairs })?;
+	exporter.writer.finish()?;
+
+	Ok(pairs)
+}
+
+/// Read the state version from the runtime stored in the state of the block `hash`.
+fn state_version_at<B, BA, C>(client: &C, hash: B::Hash) -> Result<StateVersion, Error>
+where
+	B: BlockT,
+	BA: sc_client_api::backend::Backend<B>,
+	C: StorageProvider<B, BA>,
+{
+	let code = client
+		.storage(hash, &StorageKey(well_known_keys::CODE.to_vec()))?
+		.ok_or_else(|| Error::Other("The state has no runtime code".into()))?;
+	let version = RuntimeBlob::uncompress_if_needed(&code.0)
+		.and_then(|blob| sc_executor::read_embedded_version(&blob))
+		.map_err(|e| Error::Other(format!("Failed to read the runtime version: {}", e)))?
+		.ok_or_else(|| {
+			Error::Other("The runtime has no embedded version, set the state version".into())
+		})?;
+
+	Ok(version.state_version())
+}
+
+/// Writes the pairs of [`export_state_stream`] in chunks.
+struct ChunkExporter<'a, B: BlockT, BA, C, W> {
+	client: &'a C,
+	hash: B::Hash,
+	writer: StateStreamWriter<W>,
+	config: StateExportConfig,
+	chunk: Vec<(Vec<u8>, Vec<u8>)>,
+	pairs: u64,
+	since_checkpoint: u64,
+	_phantom: std::marker::PhantomData<BA>,
+}
+
+impl<'a, B, BA, C, W> ChunkExporter<'a, B, BA, C, W>
+where
+	B: BlockT,
+	BA: sc_client_api::backend::Backend<B>,
+	C: StorageProvider<B, BA> + ProofProvider<B>,
+	W: Write,
+{
+	fn push(
+		&mut self,
+		child_info: Option<&ChildInfo>,
+		key: Vec<u8>,
+		value: Vec<u8>,
+	) -> Result<(), Error> {
+		self.chunk.push((key, value));
+		self.pairs += 1;
+		self.since_checkpoint += 1;
+
+		if self.chunk.len() >= self.config.chunk_size {
+			self.flush(child_info)?;
+		}
+		Ok(())
+	}
+
+	/// Write the pending pairs of the top trie, or of the given child trie.
+	fn flush(&mut self, child_info: Option<&ChildInfo>) -> Result<(), Error> {
+		let Some((last_key, _)) = self.chunk.last() else { return Ok(()) };
+		let last_key = last_key.clone();
+		let child = child_info.map(|child_info| child_info.storage_key().to_vec());
+
+		let pairs = std::mem::take(&mut self.chunk);
+		self.writer.write(StateRecord::Pairs { child: child.clone(), pairs })?;
+
+		if self.since_checkpoint >= self.config.checkpoint_interval {
+			let keys = &mut std::iter::once(&last_key[..]);
+			let proof = match child_info {
+				Some(child_info) => self.client.read_child_proof(self.hash, child_info, keys)?,
+				None => self.client.read_proof(self.hash, keys)?,
+			};
+			self.writer.write(StateRecord::Checkpoint {
+				child,
+				key: last_key,
+				proof: proof.into_iter_nodes().collect(),
+			})?;
+			self.since_checkpoint = 0;
+		}
+		Ok(())
+	}
+}
+
+/// Import the state stream in `input` into `backend`.
+///
+/// If `genesis` is set, the state becomes the genesis state of a new chain and `backend` must be
+/// empty. Otherwise the exported block is imported with its state as the new finalized block,
+/// which requires the genesis of its chain to be in `backend`.
+///
+/// The checkpoints of the stream are verified as they are read. Returns the imported header.
+pub fn import_state_stream<B: BlockT>(
+	backend: &sc_client_db::Backend<B>,
+	input: impl BufRead,
+	genesis: bool,
+) -> Result<B::Header, Error> {
+	let mut records = StateStreamReader::new(input)?;
+	let (exported, state_version) = match records.next().transpose()? {
+		Some(StateRecord::Header { header, state_version }) => (
+			B::Header::decode(&mut &header[..])
+				.map_err(|e| Error::Other(format!("Invalid header: {}", e)))?,
+			match state_version {
+				0 => StateVersion::V0,
+				1 => StateVersion::V1,
+				v => return Err(Error::Other(format!("Unknown state version {}", v))),
+			},
+		),
+		_ => return Err(Error::Other("The state stream doesn't start with a header".into())),
+	};
+
+	let header = if genesis {
+		sc_chain_spec::construct_genesis_block::<B>(*exported.state_root(), state_version)
+			.header()
+			.clone()
+	} else {
+		exported.clone()
+	};
+
+	let mut chunks = StateChunks::<B, _> {
+		records,
+		root: *exported.state_root(),
+		last: None,
+		pairs: 0,
+		finished: false,
+	};
+	backend.import_state(header.clone(), &mut chunks, state_version)?;
+
+	Ok(header)
+}
+
+/// The chunks of a state stream, verifying the checkpoints.
+struct StateChunks<B: BlockT, R> {
+	records: StateStreamReader<R>,
+	root: B::Hash,
+	last: Option<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)>,
+	pairs: u64,
+	finished: bool,
+}
+
+impl<B: BlockT, R: BufRead> StateChunks<B, R> {
+	/// Verify the checkpoint of the pair `key` against the last imported pair.
+	fn check(
+		&self,
+		child: Option<Vec<u8>>,
+		key: Vec<u8>,
+		proof: Vec<Vec<u8>>,
+	) -> Result<(), Error> {
+		let last = self.last.as_ref().filter(|(c, k, _)| *c == child && *k == key);
+		let Some((_, _, value)) = last else {
+			return Err(Error::Other("The checkpoint doesn't follow its pair".into()))
+		};
+
+		let proof = StorageProof::new(proof);
+		let proven = match &child {
+			Some(child) => read_child_proof_check::<HashingFor<B>, _>(
+				self.root,
+				proof,
+				&ChildInfo::new_default(child),
+				[&key],
+			),
+			None => read_proof_check::<HashingFor<B>, _>(self.root, proof, [&key]),
+		}
+		.map_err(|e| Error::Other(format!("Invalid checkpoint proof: {}", e)))?;
+
+		match proven.get(&key) {
+			Some(Some(proven)) if proven == value => Ok(()),
+			_ => Err(Error::Other(format!(
+				"The checkpoint doesn't match the imported value of 0x{}",
+				HexDisplay::from(&key)
+			))),
+		}
+	}
+
+	fn next_chunk(&mut self) -> Result<Option<sc_client_db::StateChunk>, Error> {
+		while let Some(record) = self.records.next().transpose()? {
+			match record {
+				StateRecord::Pairs { child, pairs } => {
+					let Some((key, value)) = pairs.last() else { continue };
+					self.last = Some((child.clone(), key.clone(), value.clone()));
+					self.pairs += pairs.len() as u64;
+					return Ok(Some((child.as_deref().map(ChildInfo::new_default), pairs)))
+				},
+				StateRecord::Checkpoint { child, key, proof } => self.check(child, key, proof)?,
+				StateRecord::End { pairs } if pairs == self.pairs => {
+					self.finished = true;
+					return Ok(None)
+				},
+				StateRecord::End { pairs } =>
+					return Err(Error::Other(format!(
+						"The state stream ends after {} pairs, but {} were read",
+						pairs, self.pairs
+					))),
+				StateRecord::Header { .. } =>
+					return Err(Error::Other("The state stream has a second header".into())),
+			}
+		}
+
+		Err(Error::Other("The state stream is incomplete".into()))
+	}
+}
+
+impl<B: BlockT, R: BufRead> Iterator for StateChunks<B, R> {
+	type Item = sp_blockchain::Result<sc_client_db::StateChunk>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.finished {
+			return None
+		}
+		self.next_chunk()
+			.map_err(|e| sp_blockchain::Error::Backend(e.to_string()))
+			.transpose()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_client_api::backend::Backend as _;
+	use sp_state_machine::Backend as _;
+	use substrate_test_runtime_client::{
+		runtime::Block, DefaultTestClientBuilderExt, TestClientBuilderExt,
+	};
+
+	fn roundtrip(format: StateStreamFormat) {
+		let client = substrate_test_runtime_client::TestClientBuilder::new().build();
+		let genesis = client.info().genesis_hash;
+
+		let mut exported = Vec::new();
+		let config = StateExportConfig {
+			format,
+			chunk_size: 2,
+			checkpoint_interval: 3,
+			..Default::default()
+		};
+		let pairs = export_state_stream(&client, genesis, &mut exported, config).unwrap();
+		assert!(pairs > 0);
+
+		let records = StateStreamReader::new(&exported[..]).unwrap();
+		assert_eq!(records.format(), format);
+		let records = records.collect::<Result<Vec<_>, _>>().unwrap();
+		assert!(records.iter().any(|r| matches!(r, StateRecord::Checkpoint { .. })));
+		assert_eq!(records.last(), Some(&StateRecord::End { pairs }));
+
+		let backend = sc_client_db::Backend::<Block>::new_test(2, 0);
+		let header = import_state_stream(&backend, &exported[..], true).unwrap();
+		assert_eq!(header.hash(), genesis);
+		let code = StorageKey(well_known_keys::CODE.to_vec());
+		assert_eq!(
+			backend.state_at(genesis).unwrap().storage(&code.0).unwrap(),
+			client.storage(genesis, &code).unwrap().map(|code| code.0),
+		);
+
+		// A stream without its end is rejected.
+		let backend = sc_client_db::Backend::<Block>::new_test(2, 0);
+		let mut truncated = StateStreamWriter::new(Vec::new(), format).unwrap();
+		records[..records.len() - 1]
+			.iter()
+			.for_each(|record| truncated.write(record.clone()).unwrap());
+		let truncated = truncated.finish().unwrap();
+		assert!(import_state_stream(&backend, &truncated[..], true).is_err());
+	}
+
+	#[test]
+	fn binary_state_stream_roundtrips() {
+		roundtrip(StateStreamFormat::Binary);
+	}
+
+	#[test]
+	fn jsonl_state_stream_roundtrips() {
+		roundtrip(StateStreamFormat::Jsonl);
+	}
+}
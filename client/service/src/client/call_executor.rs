@@ -20,7 +20,7 @@ use super::{client::ClientConfig, wasm_override::WasmOverride, wasm_substitutes:
 use sc_client_api::{
 	backend, call_executor::CallExecutor, execution_extensions::ExecutionExtensions, HeaderBackend,
 };
-use sc_executor::{RuntimeVersion, RuntimeVersionOf};
+use sc_executor::{CallBudget, CallBudgetExt, RuntimeVersion, RuntimeVersionOf};
 use sp_api::ProofRecorder;
 use sp_core::traits::{CallContext, CodeExecutor, RuntimeCode};
 use sp_externalities::Extensions;
@@ -29,7 +29,7 @@ use sp_runtime::{
 	traits::{Block as BlockT, HashingFor},
 };
 use sp_state_machine::{backend::AsTrieBackend, Ext, OverlayedChanges, StateMachine, StorageProof};
-use std::{cell::RefCell, sync::Arc};
+use std::{any::TypeId, cell::RefCell, sync::Arc};
 
 /// Call executor that executes methods locally, querying all required
 /// data from local backend.
@@ -165,6 +165,17 @@ where
 		method: &str,
 		call_data: &[u8],
 		context: CallContext,
+	) -> sp_blockchain::Result<Vec<u8>> {
+		self.call_with_budget(at_hash, method, call_data, context, CallBudget::default())
+	}
+
+	fn call_with_budget(
+		&self,
+		at_hash: Block::Hash,
+		method: &str,
+		call_data: &[u8],
+		context: CallContext,
+		budget: CallBudget,
 	) -> sp_blockchain::Result<Vec<u8>> {
 		let mut changes = OverlayedChanges::default();
 		let at_number =
@@ -178,8 +189,11 @@ where
 		let runtime_code = self.check_override(runtime_code, &state, at_hash)?.0;
 
 		let mut extensions = self.execution_extensions.extensions(at_hash, at_number);
+		if !budget.is_unlimited() {
+			extensions.register(CallBudgetExt::new(budget));
+		}
 
-		let mut sm = StateMachine::new(
+		let result = StateMachine::new(
 			&state,
 			&mut changes,
 			&self.executor,
@@ -189,9 +203,18 @@ where
 			&runtime_code,
 			context,
 		)
-		.set_parent_hash(at_hash);
+		.set_parent_hash(at_hash)
+		.execute();
+
+		let exhausted = extensions
+			.get_mut(TypeId::of::<CallBudgetExt>())
+			.and_then(|ext| ext.downcast_mut::<CallBudgetExt>())
+			.map_or(false, |ext| ext.exhausted);
+		if exhausted {
+			return Err(sp_blockchain::Error::CallBudgetExhausted)
+		}
 
-		sm.execute().map_err(Into::into)
+		result.map_err(Into::into)
 	}
 
 	fn contextual_call(
@@ -19,7 +19,9 @@
 //! Service configuration.
 
 pub use sc_client_db::{BlocksPruning, Database, DatabaseSource, PruningMode};
-pub use sc_executor::{WasmExecutionMethod, WasmtimeInstantiationStrategy};
+pub use sc_executor::{
+	CallBudget, WasmExecutionMethod, WasmtimeInstantiationStrategy, WasmtimeProfiler,
+};
 pub use sc_network::{
 	config::{
 		MultiaddrWithPeerId, NetworkConfiguration, NodeKeyConfig, NonDefaultSetConfig, ProtocolId,
@@ -34,12 +36,15 @@ pub use sc_network::{
 use prometheus_endpoint::Registry;
 use sc_chain_spec::ChainSpec;
 pub use sc_telemetry::TelemetryEndpoints;
-pub use sc_transaction_pool::Options as TransactionPoolOptions;
+pub use sc_transaction_pool::{
+	JournalOptions as TransactionPoolJournalOptions, Options as TransactionPoolOptions,
+};
 use sp_core::crypto::SecretString;
 use std::{
 	io, iter,
 	net::SocketAddr,
 	path::{Path, PathBuf},
+	sync::Arc,
 };
 use tempfile::TempDir;
 
@@ -66,12 +71,22 @@ pub struct Configuration {
 	///
 	/// If `None` is given the cache is disabled.
 	pub trie_cache_maximum_size: Option<usize>,
+	/// Split the trie cache between nodes and values based on the observed workload.
+	pub trie_cache_adaptive: bool,
 	/// State pruning settings.
 	pub state_pruning: Option<PruningMode>,
 	/// Number of blocks to keep in the db.
 	///
 	/// NOTE: only finalized blocks are subject for removal!
 	pub blocks_pruning: BlocksPruning,
+	/// Justifications pruning settings.
+	///
+	/// If `None` is given, justifications are pruned together with the block bodies.
+	pub justifications_pruning: Option<BlocksPruning>,
+	/// Indexed transactions pruning settings.
+	///
+	/// If `None` is given, indexed transactions are pruned together with the block bodies.
+	pub transactions_pruning: Option<BlocksPruning>,
 	/// Chain configuration.
 	pub chain_spec: Box<dyn ChainSpec>,
 	/// Wasm execution method.
@@ -80,6 +95,8 @@ pub struct Configuration {
 	/// over on-chain runtimes when the spec version matches. Set to `None` to
 	/// disable overrides (default).
 	pub wasm_runtime_overrides: Option<PathBuf>,
+	/// Profiler the runtimes are instrumented for. `None` if disabled (default).
+	pub runtime_profiler: Option<Arc<WasmtimeProfiler>>,
 	/// JSON-RPC server binding address.
 	pub rpc_addr: Option<SocketAddr>,
 	/// Maximum number of connections for JSON-RPC server.
@@ -98,6 +115,11 @@ pub struct Configuration {
 	pub rpc_id_provider: Option<Box<dyn crate::RpcSubscriptionIdProvider>>,
 	/// Maximum allowed subscriptions per rpc connection
 	pub rpc_max_subs_per_conn: u32,
+	/// Limits of the runtime calls made by the `state_call` and `chainHead_unstable_call` RPCs.
+	///
+	/// Unless the budget is unlimited, the executor keeps runtimes compiled with call metering
+	/// for these calls only.
+	pub rpc_call_budget: CallBudget,
 	/// JSON-RPC server default port.
 	pub rpc_port: u16,
 	/// Prometheus endpoint configuration. `None` if disabled.
@@ -137,6 +159,8 @@ pub struct Configuration {
 	pub informant_output_format: sc_informant::OutputFormat,
 	/// Maximum number of different runtime versions that can be cached.
 	pub runtime_cache_size: u8,
+	/// Maximum size in bytes of the compiled runtimes cached on disk, `0` disables the cache.
+	pub runtime_artifact_cache_size: u64,
 }
 
 /// Type for tasks spawned by the executor.
@@ -238,9 +262,12 @@ impl Configuration {
 	pub fn db_config(&self) -> sc_client_db::DatabaseSettings {
 		sc_client_db::DatabaseSettings {
 			trie_cache_maximum_size: self.trie_cache_maximum_size,
+			trie_cache_adaptive: self.trie_cache_adaptive,
 			state_pruning: self.state_pruning.clone(),
 			source: self.database.clone(),
 			blocks_pruning: self.blocks_pruning,
+			justifications_pruning: self.justifications_pruning,
+			transactions_pruning: self.transactions_pruning,
 		}
 	}
 }
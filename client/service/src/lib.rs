@@ -55,10 +55,10 @@ use sp_runtime::{
 
 pub use self::{
 	builder::{
-		build_network, new_client, new_db_backend, new_full_client, new_full_parts,
-		new_full_parts_with_genesis_builder, new_native_or_wasm_executor, new_wasm_executor,
-		spawn_tasks, BuildNetworkParams, KeystoreContainer, NetworkStarter, SpawnTasksParams,
-		TFullBackend, TFullCallExecutor, TFullClient,
+		build_network, executor_cache_path, new_client, new_db_backend, new_full_client,
+		new_full_parts, new_full_parts_with_genesis_builder, new_native_or_wasm_executor,
+		new_wasm_executor, spawn_tasks, BuildNetworkParams, KeystoreContainer, NetworkStarter,
+		SpawnTasksParams, TFullBackend, TFullCallExecutor, TFullClient,
 	},
 	client::{ClientConfig, LocalCallExecutor},
 	error::Error,
@@ -43,6 +43,9 @@ struct PrometheusMetrics {
 	// I/O
 	database_cache: Gauge<U64>,
 	state_cache: Gauge<U64>,
+	trie_cache_lookups: GaugeVec<U64>,
+	trie_cache_evictions: GaugeVec<U64>,
+	trie_cache_bytes: GaugeVec<U64>,
 }
 
 impl PrometheusMetrics {
@@ -116,6 +119,36 @@ impl PrometheusMetrics {
 				Gauge::new("substrate_state_cache_bytes", "State cache size in bytes")?,
 				registry,
 			)?,
+			trie_cache_lookups: register(
+				GaugeVec::new(
+					Opts::new(
+						"substrate_trie_cache_lookups",
+						"Number of trie cache lookups since startup, by cache and result",
+					),
+					&["cache", "result"],
+				)?,
+				registry,
+			)?,
+			trie_cache_evictions: register(
+				GaugeVec::new(
+					Opts::new(
+						"substrate_trie_cache_evictions",
+						"Number of items evicted from the shared trie cache since startup",
+					),
+					&["cache"],
+				)?,
+				registry,
+			)?,
+			trie_cache_bytes: register(
+				GaugeVec::new(
+					Opts::new(
+						"substrate_trie_cache_bytes",
+						"Used and allowed size of the shared trie cache in bytes",
+					),
+					&["cache", "kind"],
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
@@ -252,6 +285,21 @@ impl MetricsService {
 			if let Some(info) = info.usage.as_ref() {
 				metrics.database_cache.set(info.memory.database_cache.as_bytes() as u64);
 				metrics.state_cache.set(info.memory.state_cache.as_bytes() as u64);
+
+				let trie_caches = info
+					.trie_cache
+					.iter()
+					.flat_map(|c| [("node", &c.node_cache), ("value", &c.value_cache)]);
+				for (cache, usage) in trie_caches {
+					let lookups = &metrics.trie_cache_lookups;
+					lookups.with_label_values(&[cache, "local_hit"]).set(usage.local_hits);
+					lookups.with_label_values(&[cache, "shared_hit"]).set(usage.shared_hits);
+					lookups.with_label_values(&[cache, "miss"]).set(usage.misses);
+					metrics.trie_cache_evictions.with_label_values(&[cache]).set(usage.evictions);
+					let bytes = &metrics.trie_cache_bytes;
+					bytes.with_label_values(&[cache, "used"]).set(usage.size.as_bytes() as u64);
+					bytes.with_label_values(&[cache, "budget"]).set(usage.budget.as_bytes() as u64);
+				}
 			}
 		}
 
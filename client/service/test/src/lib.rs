@@ -240,11 +240,15 @@ fn node_config<
 		keystore: KeystoreConfig::Path { path: root.join("key"), password: None },
 		database: DatabaseSource::RocksDb { path: root.join("db"), cache_size: 128 },
 		trie_cache_maximum_size: Some(16 * 1024 * 1024),
+		trie_cache_adaptive: false,
 		state_pruning: Default::default(),
 		blocks_pruning: BlocksPruning::KeepFinalized,
+		justifications_pruning: None,
+		transactions_pruning: None,
 		chain_spec: Box::new((*spec).clone()),
 		wasm_method: Default::default(),
 		wasm_runtime_overrides: Default::default(),
+		runtime_profiler: None,
 		rpc_addr: Default::default(),
 		rpc_max_connections: Default::default(),
 		rpc_cors: None,
@@ -253,6 +257,7 @@ fn node_config<
 		rpc_max_response_size: Default::default(),
 		rpc_id_provider: Default::default(),
 		rpc_max_subs_per_conn: Default::default(),
+		rpc_call_budget: Default::default(),
 		rpc_port: 9944,
 		prometheus_config: None,
 		telemetry_endpoints: None,
@@ -269,6 +274,7 @@ fn node_config<
 		data_path: root,
 		informant_output_format: Default::default(),
 		runtime_cache_size: 2,
+		runtime_artifact_cache_size: 0,
 	}
 }
 
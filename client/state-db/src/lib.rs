@@ -54,6 +54,7 @@ use pruning::{HaveBlock, RefWindow};
 use std::{
 	collections::{hash_map::Entry, HashMap},
 	fmt,
+	time::Duration,
 };
 
 const LOG_TARGET: &str = "state-db";
@@ -145,6 +146,8 @@ pub enum StateDbError {
 	BlockUnavailable,
 	/// Block record is missing from the pruning window
 	BlockMissing,
+	/// Trying to import the state of a block into a state database that has blocks already.
+	NotEmpty,
 }
 
 impl<E> From<StateDbError> for Error<E> {
@@ -196,6 +199,8 @@ impl fmt::Debug for StateDbError {
 				write!(f, "Trying to get a block record from db while it is not commit to db yet")
 			},
 			Self::BlockMissing => write!(f, "Block record is missing from the pruning window"),
+			Self::NotEmpty =>
+				write!(f, "Trying to import a block state into a state database that has blocks"),
 		}
 	}
 }
@@ -219,11 +224,33 @@ pub struct CommitSet<H: Hash> {
 }
 
 /// Pruning constraints. If none are specified pruning is
+///
+/// Blocks are pruned from the window for as long as any of the given constraints is exceeded.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Constraints {
-	/// Maximum blocks. Defaults to 0 when unspecified, effectively keeping only non-canonical
-	/// states.
+	/// Maximum blocks. Defaults to 0 when no constraint is specified, effectively keeping only
+	/// non-canonical states, and to no limit when another constraint is specified.
 	pub max_blocks: Option<u32>,
+	/// Maximum age of the window, measured between the timestamps of its oldest and of its
+	/// newest block.
+	pub max_age: Option<Duration>,
+	/// Maximum size of the window in bytes, measured as the total size of the state nodes
+	/// inserted by its blocks.
+	pub max_bytes: Option<u64>,
+	/// Storage key of the timestamp of a block, in milliseconds, from which the age of the window
+	/// is measured. Only used with `max_age`. Defaults to the key of FRAME's `Timestamp::Now`.
+	pub timestamp_key: Option<Vec<u8>>,
+}
+
+impl Constraints {
+	/// Maximum number of blocks in the window, taking the other constraints into account.
+	fn max_blocks_or_default(&self) -> u64 {
+		match self.max_blocks {
+			Some(max_blocks) => max_blocks as u64,
+			None if self.max_age.is_some() || self.max_bytes.is_some() => u64::MAX,
+			None => 0,
+		}
+	}
 }
 
 /// Pruning mode.
@@ -240,7 +267,12 @@ pub enum PruningMode {
 impl PruningMode {
 	/// Create a mode that keeps given number of blocks.
 	pub fn blocks_pruning(n: u32) -> PruningMode {
-		PruningMode::Constrained(Constraints { max_blocks: Some(n) })
+		PruningMode::Constrained(Constraints {
+			max_blocks: Some(n),
+			max_age: None,
+			max_bytes: None,
+			timestamp_key: None,
+		})
 	}
 
 	/// Is this an archive (either ArchiveAll or ArchiveCanonical) pruning mode?
@@ -278,7 +310,12 @@ impl Default for PruningMode {
 
 impl Default for Constraints {
 	fn default() -> Self {
-		Self { max_blocks: Some(DEFAULT_MAX_BLOCK_CONSTRAINT) }
+		Self {
+			max_blocks: Some(DEFAULT_MAX_BLOCK_CONSTRAINT),
+			max_age: None,
+			max_bytes: None,
+			timestamp_key: None,
+		}
 	}
 }
 
@@ -317,8 +354,17 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 
 		let non_canonical: NonCanonicalOverlay<BlockHash, Key> = NonCanonicalOverlay::new(&db)?;
 		let pruning: Option<RefWindow<BlockHash, Key, D>> = match mode {
-			PruningMode::Constrained(Constraints { max_blocks }) =>
-				Some(RefWindow::new(db, max_blocks.unwrap_or(0), ref_counting)?),
+			PruningMode::Constrained(ref constraints) => {
+				// The window size is only used to size the caches of the window.
+				let window_size = constraints.max_blocks.unwrap_or(
+					if constraints.max_age.is_some() || constraints.max_bytes.is_some() {
+						DEFAULT_MAX_BLOCK_CONSTRAINT
+					} else {
+						0
+					},
+				);
+				Some(RefWindow::new(db, window_size, ref_counting)?)
+			},
 			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => None,
 		};
 
@@ -345,7 +391,11 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 		}
 	}
 
-	fn canonicalize_block(&mut self, hash: &BlockHash) -> Result<CommitSet<Key>, Error<D::Error>> {
+	fn canonicalize_block(
+		&mut self,
+		hash: &BlockHash,
+		timestamp: Option<u64>,
+	) -> Result<CommitSet<Key>, Error<D::Error>> {
 		// NOTE: it is important that the change to `LAST_CANONICAL` (emit from
 		// `non_canonical.canonicalize`) and the insert of the new pruning journal (emit from
 		// `pruning.note_canonical`) are collected into the same `CommitSet` and are committed to
@@ -359,7 +409,26 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 			commit.data.deleted.clear();
 		}
 		if let Some(ref mut pruning) = self.pruning {
-			pruning.note_canonical(hash, number, &mut commit)?;
+			pruning.note_canonical(hash, number, timestamp, &mut commit)?;
+		}
+		self.prune(&mut commit)?;
+		Ok(commit)
+	}
+
+	fn import_canonical_block(
+		&mut self,
+		hash: &BlockHash,
+		number: u64,
+		timestamp: Option<u64>,
+		size: u64,
+	) -> Result<CommitSet<Key>, Error<D::Error>> {
+		let mut commit = CommitSet::default();
+		if self.mode == PruningMode::ArchiveAll {
+			return Ok(commit)
+		}
+		self.non_canonical.import_canonical(hash, number, &mut commit)?;
+		if let Some(ref mut pruning) = self.pruning {
+			pruning.note_imported(hash, number, timestamp, size, &mut commit)?;
 		}
 		self.prune(&mut commit)?;
 		Ok(commit)
@@ -412,7 +481,7 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 			(&mut self.pruning, &self.mode)
 		{
 			loop {
-				if pruning.window_size() <= constraints.max_blocks.unwrap_or(0) as u64 {
+				if !pruning.exceeds(constraints)? {
 					break
 				}
 
@@ -592,7 +661,36 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDb<BlockHash, Key, D> {
 
 	/// Finalize a previously inserted block.
 	pub fn canonicalize_block(&self, hash: &BlockHash) -> Result<CommitSet<Key>, Error<D::Error>> {
-		self.db.write().canonicalize_block(hash)
+		self.db.write().canonicalize_block(hash, None)
+	}
+
+	/// Finalize a previously inserted block with the given timestamp in milliseconds.
+	///
+	/// The timestamp is used to enforce the [`Constraints::max_age`] of the pruning window.
+	pub fn canonicalize_block_with_timestamp(
+		&self,
+		hash: &BlockHash,
+		timestamp: Option<u64>,
+	) -> Result<CommitSet<Key>, Error<D::Error>> {
+		self.db.write().canonicalize_block(hash, timestamp)
+	}
+
+	/// Add a block whose state nodes were written to the backing database directly, e.g. when
+	/// importing the state from a snapshot, as the last canonicalized block.
+	///
+	/// Only possible while the database has no blocks yet. The nodes are not passed in, so the
+	/// state may be larger than the memory. `size` is the total size of the nodes, it counts
+	/// towards [`Constraints::max_bytes`]. Pruning the block doesn't delete any of its nodes,
+	/// they are deleted by the following blocks which replace them, like the nodes of any other
+	/// block.
+	pub fn import_canonical_block(
+		&self,
+		hash: &BlockHash,
+		number: u64,
+		timestamp: Option<u64>,
+		size: u64,
+	) -> Result<CommitSet<Key>, Error<D::Error>> {
+		self.db.write().import_canonical_block(hash, number, timestamp, size)
 	}
 
 	/// Prevents pruning of specified block and its descendants.
@@ -706,7 +804,7 @@ fn choose_pruning_mode(
 mod tests {
 	use crate::{
 		test::{make_changeset, make_db, TestDb},
-		Constraints, Error, IsPruned, PruningMode, StateDb, StateDbError,
+		Constraints, Error, IsPruned, LastCanonicalized, PruningMode, StateDb, StateDbError,
 	};
 	use sp_core::H256;
 
@@ -788,8 +886,7 @@ mod tests {
 
 	#[test]
 	fn block_record_unavailable() {
-		let (mut db, state_db) =
-			make_test_db(PruningMode::Constrained(Constraints { max_blocks: Some(1) }));
+		let (mut db, state_db) = make_test_db(PruningMode::blocks_pruning(1));
 		// import 2 blocks
 		for i in &[5, 6] {
 			db.commit(
@@ -823,13 +920,13 @@ mod tests {
 
 	#[test]
 	fn prune_window_0() {
-		let (db, _) = make_test_db(PruningMode::Constrained(Constraints { max_blocks: Some(0) }));
+		let (db, _) = make_test_db(PruningMode::blocks_pruning(0));
 		assert!(db.data_eq(&make_db(&[21, 3, 922, 94])));
 	}
 
 	#[test]
 	fn prune_window_1() {
-		let (db, sdb) = make_test_db(PruningMode::Constrained(Constraints { max_blocks: Some(1) }));
+		let (db, sdb) = make_test_db(PruningMode::blocks_pruning(1));
 		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(0), 0), IsPruned::Pruned);
 		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(1), 1), IsPruned::Pruned);
 		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(21), 2), IsPruned::Pruned);
@@ -839,7 +936,7 @@ mod tests {
 
 	#[test]
 	fn prune_window_2() {
-		let (db, sdb) = make_test_db(PruningMode::Constrained(Constraints { max_blocks: Some(2) }));
+		let (db, sdb) = make_test_db(PruningMode::blocks_pruning(2));
 		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(0), 0), IsPruned::Pruned);
 		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(1), 1), IsPruned::Pruned);
 		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(21), 2), IsPruned::NotPruned);
@@ -847,6 +944,69 @@ mod tests {
 		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
 	}
 
+	#[test]
+	fn import_canonical_block_and_prune_it() {
+		let mut db = make_db(&[91]);
+		let (init, state_db) = StateDb::<H256, H256, TestDb>::open(
+			db.clone(),
+			Some(PruningMode::blocks_pruning(1)),
+			false,
+			true,
+		)
+		.unwrap();
+		db.commit(&init);
+
+		// The nodes of the imported block are written to the database directly.
+		db.commit(
+			&state_db
+				.import_canonical_block(&H256::from_low_u64_be(10), 10, None, 64)
+				.unwrap(),
+		);
+		assert_eq!(state_db.last_canonicalized(), LastCanonicalized::Block(10));
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(10), 10), IsPruned::NotPruned);
+		assert!(matches!(
+			state_db.import_canonical_block(&H256::from_low_u64_be(20), 20, None, 0),
+			Err(Error::StateDb(StateDbError::NotEmpty))
+		));
+
+		for (number, deleted) in [(11, &[91][..]), (12, &[])] {
+			db.commit(
+				&state_db
+					.insert_block(
+						&H256::from_low_u64_be(number),
+						number,
+						&H256::from_low_u64_be(number - 1),
+						make_changeset(&[number], deleted),
+					)
+					.unwrap(),
+			);
+			db.commit(&state_db.canonicalize_block(&H256::from_low_u64_be(number)).unwrap());
+		}
+
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(10), 10), IsPruned::Pruned);
+		assert_ne!(state_db.is_pruned(&H256::from_low_u64_be(12), 12), IsPruned::Pruned);
+		assert!(db.data_eq(&make_db(&[11, 12])));
+
+		let (_, state_db) = StateDb::<H256, H256, TestDb>::open(db, None, false, false).unwrap();
+		assert_eq!(state_db.last_canonicalized(), LastCanonicalized::Block(12));
+	}
+
+	#[test]
+	fn prune_window_bytes() {
+		// Every canonicalized block inserts a single 32 byte node.
+		let constraints = Constraints {
+			max_blocks: None,
+			max_age: None,
+			max_bytes: Some(64),
+			timestamp_key: None,
+		};
+		let (db, sdb) = make_test_db(PruningMode::Constrained(constraints));
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(1), 1), IsPruned::Pruned);
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(21), 2), IsPruned::NotPruned);
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(22), 2), IsPruned::Pruned);
+		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
+	}
+
 	#[test]
 	fn detects_incompatible_mode() {
 		let mut db = make_db(&[]);
@@ -863,7 +1023,7 @@ mod tests {
 				)
 				.unwrap(),
 		);
-		let new_mode = PruningMode::Constrained(Constraints { max_blocks: Some(2) });
+		let new_mode = PruningMode::blocks_pruning(2);
 		let state_db_open_result: Result<(_, StateDb<H256, H256, TestDb>), _> =
 			StateDb::open(db.clone(), Some(new_mode), false, false);
 		assert!(state_db_open_result.is_err());
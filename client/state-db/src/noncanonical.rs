@@ -340,6 +340,28 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 		Ok(commit)
 	}
 
+	/// Make the block `hash` the last canonicalized block without inserting it, see
+	/// [`crate::StateDb::import_canonical_block`]. Fails if any block was inserted or
+	/// canonicalized before.
+	pub fn import_canonical(
+		&mut self,
+		hash: &BlockHash,
+		number: u64,
+		commit: &mut CommitSet<Key>,
+	) -> Result<(), StateDbError> {
+		if self.last_canonicalized.is_some() || !self.levels.is_empty() {
+			return Err(StateDbError::NotEmpty)
+		}
+		trace!(target: LOG_TARGET, "Importing canonical block {} {:?}", number, hash);
+		let canonicalized = (hash.clone(), number);
+		commit
+			.meta
+			.inserted
+			.push((to_meta_key(LAST_CANONICAL, &()), canonicalized.encode()));
+		self.last_canonicalized = Some(canonicalized);
+		Ok(())
+	}
+
 	fn discard_journals(
 		&self,
 		level_index: usize,
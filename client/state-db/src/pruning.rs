@@ -25,8 +25,8 @@
 //! The changes are journaled in the DB.
 
 use crate::{
-	noncanonical::LAST_CANONICAL, to_meta_key, CommitSet, Error, Hash, MetaDb, StateDbError,
-	DEFAULT_MAX_BLOCK_CONSTRAINT, LOG_TARGET,
+	noncanonical::LAST_CANONICAL, to_meta_key, CommitSet, Constraints, Error, Hash, MetaDb,
+	StateDbError, DEFAULT_MAX_BLOCK_CONSTRAINT, LOG_TARGET,
 };
 use codec::{Decode, Encode};
 use log::trace;
@@ -34,6 +34,7 @@ use std::collections::{HashMap, HashSet, VecDeque};
 
 pub(crate) const LAST_PRUNED: &[u8] = b"last_pruned";
 const PRUNING_JOURNAL: &[u8] = b"pruning_journal";
+const PRUNING_WINDOW_BYTES: &[u8] = b"pruning_window_bytes";
 
 /// See module documentation.
 pub struct RefWindow<BlockHash: Hash, Key: Hash, D: MetaDb> {
@@ -42,6 +43,10 @@ pub struct RefWindow<BlockHash: Hash, Key: Hash, D: MetaDb> {
 	queue: DeathRowQueue<BlockHash, Key, D>,
 	/// Block number that is next to be pruned.
 	base: u64,
+	/// Total size of the state nodes inserted by the blocks in the window.
+	window_bytes: u64,
+	/// Timestamp of the most recent block added to the window, if known.
+	newest_timestamp: Option<u64>,
 }
 
 /// `DeathRowQueue` used to keep track of blocks in the pruning window, there are two flavors:
@@ -88,8 +93,7 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> DeathRowQueue<BlockHash, Key, D> {
 			let journal_key = to_journal_key(block);
 			match db.get_meta(&journal_key).map_err(Error::Db)? {
 				Some(record) => {
-					let record: JournalRecord<BlockHash, Key> =
-						Decode::decode(&mut record.as_slice())?;
+					let (record, info) = decode_journal::<BlockHash, Key>(&record)?;
 					trace!(
 						target: LOG_TARGET,
 						"Pruning journal entry {} ({} inserted, {} deleted)",
@@ -97,7 +101,7 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> DeathRowQueue<BlockHash, Key, D> {
 						record.inserted.len(),
 						record.deleted.len(),
 					);
-					queue.import(base, block, record);
+					queue.import(base, block, record, info);
 				},
 				None => break,
 			}
@@ -127,7 +131,13 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> DeathRowQueue<BlockHash, Key, D> {
 	}
 
 	/// import a new block to the back of the queue
-	fn import(&mut self, base: u64, num: u64, journal_record: JournalRecord<BlockHash, Key>) {
+	fn import(
+		&mut self,
+		base: u64,
+		num: u64,
+		journal_record: JournalRecord<BlockHash, Key>,
+		info: JournalInfo,
+	) {
 		let JournalRecord { hash, inserted, deleted } = journal_record;
 		trace!(target: LOG_TARGET, "Importing {}, base={}", num, base);
 		match self {
@@ -136,7 +146,11 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> DeathRowQueue<BlockHash, Key, D> {
 				// cache.
 				if num == base + cache.len() as u64 && cache.len() < *cache_capacity {
 					trace!(target: LOG_TARGET, "Adding to DB backed cache {:?} (#{})", hash, num);
-					cache.push_back(DeathRow { hash, deleted: deleted.into_iter().collect() });
+					cache.push_back(DeathRow {
+						hash,
+						deleted: deleted.into_iter().collect(),
+						info,
+					});
 				}
 				*last = Some(num);
 			},
@@ -152,7 +166,11 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> DeathRowQueue<BlockHash, Key, D> {
 				for k in deleted.iter() {
 					death_index.insert(k.clone(), imported_block);
 				}
-				death_rows.push_back(DeathRow { hash, deleted: deleted.into_iter().collect() });
+				death_rows.push_back(DeathRow {
+					hash,
+					deleted: deleted.into_iter().collect(),
+					info,
+				});
 			},
 		}
 	}
@@ -255,8 +273,9 @@ fn load_death_row_from_db<BlockHash: Hash, Key: Hash, D: MetaDb>(
 	let journal_key = to_journal_key(block);
 	match db.get_meta(&journal_key).map_err(Error::Db)? {
 		Some(record) => {
-			let JournalRecord { hash, deleted, .. } = Decode::decode(&mut record.as_slice())?;
-			Ok(Some(DeathRow { hash, deleted: deleted.into_iter().collect() }))
+			let (JournalRecord { hash, deleted, .. }, info) =
+				decode_journal::<BlockHash, Key>(&record)?;
+			Ok(Some(DeathRow { hash, deleted: deleted.into_iter().collect(), info }))
 		},
 		None => Ok(None),
 	}
@@ -266,6 +285,7 @@ fn load_death_row_from_db<BlockHash: Hash, Key: Hash, D: MetaDb>(
 struct DeathRow<BlockHash: Hash, Key: Hash> {
 	hash: BlockHash,
 	deleted: HashSet<Key>,
+	info: JournalInfo,
 }
 
 #[derive(Encode, Decode, Default)]
@@ -275,6 +295,26 @@ struct JournalRecord<BlockHash: Hash, Key: Hash> {
 	deleted: Vec<Key>,
 }
 
+/// Additional information about a block in the pruning window.
+///
+/// It is encoded right after the [`JournalRecord`] of the block. Journals written before it was
+/// introduced end with the record and are read with the default, unknown, information.
+#[derive(Encode, Decode, Default, Clone, Copy, Debug, PartialEq, Eq)]
+struct JournalInfo {
+	/// Timestamp of the block in milliseconds, if known.
+	timestamp: Option<u64>,
+	/// Total size of the state nodes inserted by the block.
+	size: u64,
+}
+
+fn decode_journal<BlockHash: Hash, Key: Hash>(
+	mut input: &[u8],
+) -> Result<(JournalRecord<BlockHash, Key>, JournalInfo), codec::Error> {
+	let record = JournalRecord::decode(&mut input)?;
+	let info = if input.is_empty() { JournalInfo::default() } else { Decode::decode(&mut input)? };
+	Ok((record, info))
+}
+
 fn to_journal_key(block: u64) -> Vec<u8> {
 	to_meta_key(PRUNING_JOURNAL, &block)
 }
@@ -318,6 +358,20 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 				Some(buffer) => Some(<(BlockHash, u64)>::decode(&mut buffer.as_slice())?.1),
 				None => None,
 			};
+		let window_bytes =
+			match db.get_meta(&to_meta_key(PRUNING_WINDOW_BYTES, &())).map_err(Error::Db)? {
+				Some(buffer) => u64::decode(&mut buffer.as_slice())?,
+				None => 0,
+			};
+		// the last canonicalized block is the last block of the window, if it was not pruned yet
+		let newest_timestamp = match last_canonicalized_number {
+			Some(last) if last >= base =>
+				match db.get_meta(&to_journal_key(last)).map_err(Error::Db)? {
+					Some(record) => decode_journal::<BlockHash, Key>(&record)?.1.timestamp,
+					None => None,
+				},
+			_ => None,
+		};
 
 		let queue = if count_insertions {
 			// Highly scientific crafted number for deciding when to print the warning!
@@ -347,27 +401,64 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 			DeathRowQueue::new_db_backed(db, base, last, window_size)?
 		};
 
-		Ok(RefWindow { queue, base })
+		Ok(RefWindow { queue, base, window_bytes, newest_timestamp })
 	}
 
 	pub fn window_size(&self) -> u64 {
 		self.queue.len(self.base) as u64
 	}
 
+	/// Total size of the state nodes inserted by the blocks in the window.
+	pub fn window_bytes(&self) -> u64 {
+		self.window_bytes
+	}
+
 	/// Get the hash of the next pruning block
 	pub fn next_hash(&mut self) -> Result<Option<BlockHash>, Error<D::Error>> {
+		Ok(self.next_row()?.map(|row| row.hash.clone()))
+	}
+
+	/// Get the death row of the next pruning block
+	fn next_row(&mut self) -> Result<Option<&DeathRow<BlockHash, Key>>, Error<D::Error>> {
 		let res = match &mut self.queue {
 			DeathRowQueue::DbBacked { db, cache, cache_capacity, .. } => {
 				if cache.is_empty() {
 					DeathRowQueue::load_batch_from_db(db, cache, self.base, *cache_capacity)?;
 				}
-				cache.front().map(|r| r.hash.clone())
+				cache.front()
 			},
-			DeathRowQueue::Mem { death_rows, .. } => death_rows.front().map(|r| r.hash.clone()),
+			DeathRowQueue::Mem { death_rows, .. } => death_rows.front(),
 		};
 		Ok(res)
 	}
 
+	/// Check if the window exceeds any of the given constraints, i.e. if the next block should be
+	/// pruned.
+	///
+	/// The age of the window can only be checked once a block with a known timestamp was added
+	/// to it. Blocks with an unknown timestamp are then considered to be too old.
+	pub fn exceeds(&mut self, constraints: &Constraints) -> Result<bool, Error<D::Error>> {
+		if self.is_empty() {
+			return Ok(false)
+		}
+		if self.window_size() > constraints.max_blocks_or_default() {
+			return Ok(true)
+		}
+		if constraints.max_bytes.map_or(false, |max_bytes| self.window_bytes > max_bytes) {
+			return Ok(true)
+		}
+		if let (Some(max_age), Some(newest)) = (constraints.max_age, self.newest_timestamp) {
+			let max_age = max_age.as_millis().try_into().unwrap_or(u64::MAX);
+			if let Some(row) = self.next_row()? {
+				return Ok(row
+					.info
+					.timestamp
+					.map_or(true, |oldest| oldest.saturating_add(max_age) < newest))
+			}
+		}
+		Ok(false)
+	}
+
 	fn is_empty(&self) -> bool {
 		self.window_size() == 0
 	}
@@ -389,6 +480,11 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 			let index = self.base;
 			commit.data.deleted.extend(pruned.deleted.into_iter());
 			commit.meta.inserted.push((to_meta_key(LAST_PRUNED, &()), index.encode()));
+			self.window_bytes = self.window_bytes.saturating_sub(pruned.info.size);
+			commit
+				.meta
+				.inserted
+				.push((to_meta_key(PRUNING_WINDOW_BYTES, &()), self.window_bytes.encode()));
 			commit.meta.deleted.push(to_journal_key(self.base));
 			self.base += 1;
 			Ok(())
@@ -399,18 +495,16 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 	}
 
 	/// Add a change set to the window. Creates a journal record and pushes it to `commit`
+	///
+	/// `timestamp` is the timestamp of the block in milliseconds, if known.
 	pub fn note_canonical(
 		&mut self,
 		hash: &BlockHash,
 		number: u64,
+		timestamp: Option<u64>,
 		commit: &mut CommitSet<Key>,
 	) -> Result<(), Error<D::Error>> {
-		if self.base == 0 && self.is_empty() && number > 0 {
-			// assume that parent was canonicalized
-			self.base = number;
-		} else if (self.base + self.window_size()) != number {
-			return Err(Error::StateDb(StateDbError::InvalidBlockNumber))
-		}
+		self.check_next_number(number)?;
 		trace!(target: "state-db", "Adding to pruning window: {:?} ({} inserted, {} deleted)", hash, commit.data.inserted.len(), commit.data.deleted.len());
 		let inserted = if matches!(self.queue, DeathRowQueue::Mem { .. }) {
 			commit.data.inserted.iter().map(|(k, _)| k.clone()).collect()
@@ -419,10 +513,58 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 		};
 		let deleted = std::mem::take(&mut commit.data.deleted);
 		let journal_record = JournalRecord { hash: hash.clone(), inserted, deleted };
-		commit.meta.inserted.push((to_journal_key(number), journal_record.encode()));
-		self.queue.import(self.base, number, journal_record);
+		let size = commit.data.inserted.iter().map(|(_, value)| value.len() as u64).sum();
+		self.push_record(number, journal_record, JournalInfo { timestamp, size }, commit);
+		Ok(())
+	}
+
+	/// Add a block whose `size` bytes of state nodes were written to the database directly to
+	/// the window. Its journal record has no keys, so pruning it doesn't delete any nodes.
+	pub fn note_imported(
+		&mut self,
+		hash: &BlockHash,
+		number: u64,
+		timestamp: Option<u64>,
+		size: u64,
+		commit: &mut CommitSet<Key>,
+	) -> Result<(), Error<D::Error>> {
+		self.check_next_number(number)?;
+		trace!(target: "state-db", "Adding imported block to pruning window: {:?} ({} bytes)", hash, size);
+		let journal_record =
+			JournalRecord { hash: hash.clone(), inserted: Vec::new(), deleted: Vec::new() };
+		self.push_record(number, journal_record, JournalInfo { timestamp, size }, commit);
 		Ok(())
 	}
+
+	/// Check that the block `number` is the next block of the window.
+	fn check_next_number(&mut self, number: u64) -> Result<(), Error<D::Error>> {
+		if self.base == 0 && self.is_empty() && number > 0 {
+			// assume that parent was canonicalized
+			self.base = number;
+		} else if (self.base + self.window_size()) != number {
+			return Err(Error::StateDb(StateDbError::InvalidBlockNumber))
+		}
+		Ok(())
+	}
+
+	fn push_record(
+		&mut self,
+		number: u64,
+		journal_record: JournalRecord<BlockHash, Key>,
+		info: JournalInfo,
+		commit: &mut CommitSet<Key>,
+	) {
+		let mut encoded = journal_record.encode();
+		info.encode_to(&mut encoded);
+		commit.meta.inserted.push((to_journal_key(number), encoded));
+		self.window_bytes = self.window_bytes.saturating_add(info.size);
+		commit
+			.meta
+			.inserted
+			.push((to_meta_key(PRUNING_WINDOW_BYTES, &()), self.window_bytes.encode()));
+		self.newest_timestamp = info.timestamp.or(self.newest_timestamp);
+		self.queue.import(self.base, number, journal_record, info);
+	}
 }
 
 #[cfg(test)]
@@ -431,10 +573,12 @@ mod tests {
 	use crate::{
 		noncanonical::LAST_CANONICAL,
 		test::{make_commit, make_db, TestDb},
-		to_meta_key, CommitSet, Error, Hash, StateDbError, DEFAULT_MAX_BLOCK_CONSTRAINT,
+		to_meta_key, CommitSet, Constraints, Error, Hash, StateDbError,
+		DEFAULT_MAX_BLOCK_CONSTRAINT,
 	};
 	use codec::Encode;
 	use sp_core::H256;
+	use std::time::Duration;
 
 	fn check_journal(pruning: &RefWindow<H256, H256, TestDb>, db: &TestDb) {
 		let count_insertions = matches!(pruning.queue, DeathRowQueue::Mem { .. });
@@ -478,7 +622,7 @@ mod tests {
 			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
 		let mut commit = make_commit(&[4, 5], &[1, 3]);
 		let hash = H256::random();
-		pruning.note_canonical(&hash, 0, &mut commit).unwrap();
+		pruning.note_canonical(&hash, 0, None, &mut commit).unwrap();
 		db.commit(&commit);
 		assert_eq!(pruning.have_block(&hash, 0), HaveBlock::Yes);
 		assert_eq!(pruning.have_block(&hash, 0), HaveBlock::Yes);
@@ -507,10 +651,10 @@ mod tests {
 		let mut pruning: RefWindow<H256, H256, TestDb> =
 			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
 		let mut commit = make_commit(&[4], &[1]);
-		pruning.note_canonical(&H256::random(), 0, &mut commit).unwrap();
+		pruning.note_canonical(&H256::random(), 0, None, &mut commit).unwrap();
 		db.commit(&commit);
 		let mut commit = make_commit(&[5], &[2]);
-		pruning.note_canonical(&H256::random(), 1, &mut commit).unwrap();
+		pruning.note_canonical(&H256::random(), 1, None, &mut commit).unwrap();
 		db.commit(&commit);
 		assert!(db.data_eq(&make_db(&[1, 2, 3, 4, 5])));
 
@@ -533,10 +677,10 @@ mod tests {
 		let mut pruning: RefWindow<H256, H256, TestDb> =
 			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
 		let mut commit = make_commit(&[4], &[1]);
-		pruning.note_canonical(&H256::random(), 0, &mut commit).unwrap();
+		pruning.note_canonical(&H256::random(), 0, None, &mut commit).unwrap();
 		db.commit(&commit);
 		let mut commit = make_commit(&[5], &[2]);
-		pruning.note_canonical(&H256::random(), 1, &mut commit).unwrap();
+		pruning.note_canonical(&H256::random(), 1, None, &mut commit).unwrap();
 		db.commit(&commit);
 		assert!(db.data_eq(&make_db(&[1, 2, 3, 4, 5])));
 		let mut commit = CommitSet::default();
@@ -550,19 +694,133 @@ mod tests {
 		assert_eq!(pruning.base, 2);
 	}
 
+	#[test]
+	fn prune_by_window_bytes() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256, TestDb> =
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+		let constraints = Constraints {
+			max_blocks: None,
+			max_age: None,
+			max_bytes: Some(64),
+			timestamp_key: None,
+		};
+		for (number, (inserted, deleted)) in [(4, 1), (5, 2), (6, 3)].into_iter().enumerate() {
+			let mut commit = make_commit(&[inserted], &[deleted]);
+			pruning
+				.note_canonical(&H256::random(), number as u64, None, &mut commit)
+				.unwrap();
+			db.commit(&commit);
+		}
+		// Every block inserts a single 32 byte node.
+		assert_eq!(pruning.window_bytes(), 96);
+		assert!(pruning.exceeds(&constraints).unwrap());
+
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit).unwrap();
+		db.commit(&commit);
+		assert_eq!(pruning.window_bytes(), 64);
+		assert!(!pruning.exceeds(&constraints).unwrap());
+		assert!(db.data_eq(&make_db(&[2, 3, 4, 5, 6])));
+
+		// The window size is restored from the database.
+		let restored: RefWindow<H256, H256, TestDb> =
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+		assert_eq!(restored.window_bytes(), 64);
+	}
+
+	#[test]
+	fn prune_by_window_age() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256, TestDb> =
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+		let constraints = Constraints {
+			max_blocks: None,
+			max_age: Some(Duration::from_secs(10)),
+			max_bytes: None,
+			timestamp_key: None,
+		};
+		let mut commit = make_commit(&[4], &[1]);
+		pruning.note_canonical(&H256::random(), 0, Some(1_000), &mut commit).unwrap();
+		db.commit(&commit);
+		let mut commit = make_commit(&[5], &[2]);
+		pruning.note_canonical(&H256::random(), 1, Some(6_000), &mut commit).unwrap();
+		db.commit(&commit);
+		assert!(!pruning.exceeds(&constraints).unwrap());
+
+		let mut commit = make_commit(&[6], &[3]);
+		pruning.note_canonical(&H256::random(), 2, Some(12_000), &mut commit).unwrap();
+		commit
+			.meta
+			.inserted
+			.push((to_meta_key(LAST_CANONICAL, &()), (H256::random(), 2u64).encode()));
+		db.commit(&commit);
+		assert!(pruning.exceeds(&constraints).unwrap());
+
+		// The newest timestamp is restored from the database.
+		let mut pruning: RefWindow<H256, H256, TestDb> =
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+		assert!(pruning.exceeds(&constraints).unwrap());
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit).unwrap();
+		db.commit(&commit);
+		assert!(!pruning.exceeds(&constraints).unwrap());
+		assert!(db.data_eq(&make_db(&[2, 3, 4, 5, 6])));
+	}
+
+	#[test]
+	fn journal_without_info_is_loaded() {
+		let mut db = make_db(&[1, 2]);
+		let mut commit = make_commit(&[3], &[]);
+		let record = JournalRecord {
+			hash: H256::random(),
+			inserted: vec![H256::from_low_u64_be(3)],
+			deleted: vec![H256::from_low_u64_be(1)],
+		};
+		commit.meta.inserted.push((to_journal_key(0), record.encode()));
+		commit
+			.meta
+			.inserted
+			.push((to_meta_key(LAST_CANONICAL, &()), (H256::random(), 0u64).encode()));
+		db.commit(&commit);
+
+		let mut pruning: RefWindow<H256, H256, TestDb> =
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
+		assert_eq!(pruning.window_size(), 1);
+		assert_eq!(pruning.window_bytes(), 0);
+		// Blocks without a timestamp are pruned as soon as an age constraint is enforced.
+		let constraints = Constraints {
+			max_blocks: None,
+			max_age: Some(Duration::from_secs(10)),
+			max_bytes: None,
+			timestamp_key: None,
+		};
+		assert!(!pruning.exceeds(&constraints).unwrap());
+		let mut commit = make_commit(&[4], &[2]);
+		pruning.note_canonical(&H256::random(), 1, Some(1_000), &mut commit).unwrap();
+		db.commit(&commit);
+		assert!(pruning.exceeds(&constraints).unwrap());
+
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit).unwrap();
+		db.commit(&commit);
+		assert!(!pruning.exceeds(&constraints).unwrap());
+		assert!(db.data_eq(&make_db(&[2, 3, 4])));
+	}
+
 	#[test]
 	fn reinserted_survives() {
 		let mut db = make_db(&[1, 2, 3]);
 		let mut pruning: RefWindow<H256, H256, TestDb> =
 			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
 		let mut commit = make_commit(&[], &[2]);
-		pruning.note_canonical(&H256::random(), 0, &mut commit).unwrap();
+		pruning.note_canonical(&H256::random(), 0, None, &mut commit).unwrap();
 		db.commit(&commit);
 		let mut commit = make_commit(&[2], &[]);
-		pruning.note_canonical(&H256::random(), 1, &mut commit).unwrap();
+		pruning.note_canonical(&H256::random(), 1, None, &mut commit).unwrap();
 		db.commit(&commit);
 		let mut commit = make_commit(&[], &[2]);
-		pruning.note_canonical(&H256::random(), 2, &mut commit).unwrap();
+		pruning.note_canonical(&H256::random(), 2, None, &mut commit).unwrap();
 		db.commit(&commit);
 		assert!(db.data_eq(&make_db(&[1, 2, 3])));
 
@@ -588,13 +846,13 @@ mod tests {
 		let mut pruning: RefWindow<H256, H256, TestDb> =
 			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
 		let mut commit = make_commit(&[], &[2]);
-		pruning.note_canonical(&H256::random(), 0, &mut commit).unwrap();
+		pruning.note_canonical(&H256::random(), 0, None, &mut commit).unwrap();
 		db.commit(&commit);
 		let mut commit = make_commit(&[2], &[]);
-		pruning.note_canonical(&H256::random(), 1, &mut commit).unwrap();
+		pruning.note_canonical(&H256::random(), 1, None, &mut commit).unwrap();
 		db.commit(&commit);
 		let mut commit = make_commit(&[], &[2]);
-		pruning.note_canonical(&H256::random(), 2, &mut commit).unwrap();
+		pruning.note_canonical(&H256::random(), 2, None, &mut commit).unwrap();
 		db.commit(&commit);
 		assert!(db.data_eq(&make_db(&[1, 2, 3])));
 
@@ -618,13 +876,13 @@ mod tests {
 		let mut pruning: RefWindow<H256, H256, TestDb> =
 			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
 		let mut commit = make_commit(&[], &[2]);
-		pruning.note_canonical(&H256::random(), 0, &mut commit).unwrap();
+		pruning.note_canonical(&H256::random(), 0, None, &mut commit).unwrap();
 		db.commit(&commit);
 		let mut commit = make_commit(&[2], &[]);
-		pruning.note_canonical(&H256::random(), 1, &mut commit).unwrap();
+		pruning.note_canonical(&H256::random(), 1, None, &mut commit).unwrap();
 		db.commit(&commit);
 		let mut commit = make_commit(&[], &[2]);
-		pruning.note_canonical(&H256::random(), 2, &mut commit).unwrap();
+		pruning.note_canonical(&H256::random(), 2, None, &mut commit).unwrap();
 		db.commit(&commit);
 		assert!(db.data_eq(&make_db(&[1, 2, 3])));
 
@@ -720,7 +978,7 @@ mod tests {
 		// queue size and content should match
 		for i in 0..(cache_capacity + 10) {
 			let mut commit = make_commit(&[], &[]);
-			pruning.note_canonical(&(i as u64), i as u64, &mut commit).unwrap();
+			pruning.note_canonical(&(i as u64), i as u64, None, &mut commit).unwrap();
 			push_last_canonicalized(i as u64, &mut commit);
 			db.commit(&commit);
 			// blocks will fill the cache first
@@ -744,7 +1002,12 @@ mod tests {
 		// won't keep the new block in memory
 		let mut commit = CommitSet::default();
 		pruning
-			.note_canonical(&(cache_capacity as u64 + 10), cache_capacity as u64 + 10, &mut commit)
+			.note_canonical(
+				&(cache_capacity as u64 + 10),
+				cache_capacity as u64 + 10,
+				None,
+				&mut commit,
+			)
 			.unwrap();
 		assert_eq!(pruning.window_size(), cache_capacity as u64 + 11);
 		let (cache, _) = pruning.queue.get_db_backed_queue_state().unwrap();
@@ -792,7 +1055,7 @@ mod tests {
 		// import blocks
 		for i in 0..(cache_capacity as u64 * 2 + 10) {
 			let mut commit = make_commit(&[], &[]);
-			pruning.note_canonical(&i, i, &mut commit).unwrap();
+			pruning.note_canonical(&i, i, None, &mut commit).unwrap();
 			push_last_canonicalized(i as u64, &mut commit);
 			db.commit(&commit);
 		}
@@ -842,14 +1105,14 @@ mod tests {
 		// import blocks and commit to db
 		let mut commit = make_commit(&[], &[]);
 		for i in 0..(cache_capacity + 10) {
-			pruning.note_canonical(&i, i, &mut commit).unwrap();
+			pruning.note_canonical(&i, i, None, &mut commit).unwrap();
 		}
 		db.commit(&commit);
 
 		// import a block but not commit to db yet
 		let mut pending_commit = make_commit(&[], &[]);
 		let index = cache_capacity + 10;
-		pruning.note_canonical(&index, index, &mut pending_commit).unwrap();
+		pruning.note_canonical(&index, index, None, &mut pending_commit).unwrap();
 
 		let mut commit = make_commit(&[], &[]);
 		// prune blocks that had committed to db
@@ -129,10 +129,54 @@ pub enum TransactionStatus<Hash, BlockHash> {
 	Usurped(Hash),
 	/// Transaction has been dropped from the pool because of the limit.
 	Dropped,
+	/// Transaction has been dropped from the full pool to make room for a transaction
+	/// with a higher priority.
+	///
+	/// Reported as `Dropped` by the `author` RPC API.
+	Evicted,
+	/// Transaction has been dropped from the pool because its sender has too many
+	/// transactions in the pool.
+	///
+	/// Reported as `Dropped` by the `author` RPC API.
+	SenderLimitReached,
 	/// Transaction is no longer valid in the current state.
 	Invalid,
 }
 
+impl<Hash, BlockHash> TransactionStatus<Hash, BlockHash> {
+	/// Returns true if this is the last event emitted by [`TransactionStatusStream`].
+	pub fn is_final(&self) -> bool {
+		// The state must be kept in sync with the watcher `Sender` of `sc-transaction-pool`.
+		matches!(
+			self,
+			Self::Usurped(_) |
+				Self::Finalized(_) |
+				Self::FinalityTimeout(_) |
+				Self::Invalid |
+				Self::Dropped |
+				Self::Evicted |
+				Self::SenderLimitReached
+		)
+	}
+
+	/// Returns true if the transaction could be re-submitted to the pool in the future.
+	///
+	/// For example, `TransactionStatus::Dropped` is retriable, because the transaction
+	/// may enter the pool if there is space for it in the future.
+	pub fn is_retriable(&self) -> bool {
+		matches!(
+			self,
+			// The number of finality watchers has been reached.
+			Self::FinalityTimeout(_) |
+			// The transaction was dropped because of the limits of the pool.
+			// It can reenter the pool when other transactions are removed / finalized.
+			Self::Dropped |
+			Self::Evicted |
+			Self::SenderLimitReached
+		)
+	}
+}
+
 /// The stream of transaction events.
 pub type TransactionStatusStream<Hash, BlockHash> =
 	dyn Stream<Item = TransactionStatus<Hash, BlockHash>> + Send;
@@ -239,6 +283,24 @@ pub trait TransactionPool: Send + Sync {
 		>,
 	>;
 
+	/// Get an iterator for ready transactions ordered by priority, valid on top of the given
+	/// block.
+	///
+	/// Pools that keep a separate state for every fork return the transactions that are ready
+	/// exactly at `at`. By default this is the same as [`Self::ready_at`] for the block number.
+	fn ready_at_block(
+		&self,
+		at: &sp_blockchain::HashAndNumber<Self::Block>,
+	) -> Pin<
+		Box<
+			dyn Future<
+					Output = Box<dyn ReadyTransactions<Item = Arc<Self::InPoolTransaction>> + Send>,
+				> + Send,
+		>,
+	> {
+		self.ready_at(at.number)
+	}
+
 	/// Get an iterator for ready transactions ordered by priority.
 	fn ready(&self) -> Box<dyn ReadyTransactions<Item = Arc<Self::InPoolTransaction>> + Send>;
 
@@ -522,4 +584,39 @@ mod tests {
 		let event_dec: TransactionStatus<u8, u8> = serde_json::from_str(exp).unwrap();
 		assert_eq!(event_dec, TransactionStatus::Finalized((1, 0)));
 	}
+
+	#[test]
+	fn tx_status_final_and_retriable() {
+		let event: TransactionStatus<u8, u8> = TransactionStatus::Ready;
+		assert!(!event.is_final());
+		assert!(!event.is_retriable());
+
+		let event: TransactionStatus<u8, u8> = TransactionStatus::InBlock((1, 2));
+		assert!(!event.is_final());
+		assert!(!event.is_retriable());
+
+		let event: TransactionStatus<u8, u8> = TransactionStatus::Finalized((1, 2));
+		assert!(event.is_final());
+		assert!(!event.is_retriable());
+
+		let event: TransactionStatus<u8, u8> = TransactionStatus::Usurped(1);
+		assert!(event.is_final());
+		assert!(!event.is_retriable());
+
+		let event: TransactionStatus<u8, u8> = TransactionStatus::Invalid;
+		assert!(event.is_final());
+		assert!(!event.is_retriable());
+
+		let event: TransactionStatus<u8, u8> = TransactionStatus::Dropped;
+		assert!(event.is_final());
+		assert!(event.is_retriable());
+
+		let event: TransactionStatus<u8, u8> = TransactionStatus::Evicted;
+		assert!(event.is_final());
+		assert!(event.is_retriable());
+
+		let event: TransactionStatus<u8, u8> = TransactionStatus::SenderLimitReached;
+		assert!(event.is_final());
+		assert!(event.is_retriable());
+	}
 }
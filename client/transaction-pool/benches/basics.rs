@@ -131,6 +131,10 @@ impl ChainApi for TestApi {
 	) -> Result<sp_blockchain::TreeRoute<Self::Block>, Self::Error> {
 		unimplemented!()
 	}
+
+	fn best_block_hash(&self) -> Result<<Self::Block as BlockT>::Hash, Self::Error> {
+		unimplemented!()
+	}
 }
 
 fn uxt(transfer: TransferData) -> Extrinsic {
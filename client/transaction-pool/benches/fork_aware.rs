@@ -0,0 +1,87 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Compares the single-state [`BasicPool`] with the [`ForkAwareTxPool`] on the same workload.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use futures::executor::block_on;
+use sc_transaction_pool::{BasicPool, ForkAwareTxPool};
+use sc_transaction_pool_api::{ChainEvent, MaintainedTransactionPool, TransactionPool};
+use sp_blockchain::HashAndNumber;
+use sp_runtime::{generic::BlockId, transaction_validity::TransactionSource};
+use std::sync::Arc;
+use substrate_test_runtime_client::{runtime::Block, AccountKeyring::Alice};
+use substrate_test_runtime_transaction_pool::{uxt, TestApi};
+
+const BLOCKS: u64 = 4;
+const TXS_PER_BLOCK: u64 = 50;
+
+/// Submits a batch of transactions for every block, imports a block including them and reads
+/// the ready set the next block would be built from.
+fn import_blocks<P>(pool: &P, api: &TestApi)
+where
+	P: MaintainedTransactionPool<Block = Block>,
+{
+	let mut parent = api.chain().read().block_by_number[&0][0].0.header.clone();
+	for number in 1..=BLOCKS {
+		let nonces = (number - 1) * TXS_PER_BLOCK..number * TXS_PER_BLOCK;
+		let xts = nonces.map(|nonce| uxt(Alice, nonce)).collect::<Vec<_>>();
+		block_on(pool.submit_at(
+			&BlockId::Hash(parent.hash()),
+			TransactionSource::External,
+			xts.clone(),
+		))
+		.expect("submission succeeds");
+
+		let header = api.push_block_with_parent(parent.hash(), xts, true);
+		(0..TXS_PER_BLOCK).for_each(|_| api.increment_nonce(Alice.into()));
+		block_on(pool.maintain(ChainEvent::NewBestBlock { hash: header.hash(), tree_route: None }));
+
+		let at = HashAndNumber { hash: header.hash(), number };
+		block_on(pool.ready_at_block(&at)).for_each(drop);
+		parent = header;
+	}
+}
+
+fn genesis(api: &TestApi) -> HashAndNumber<Block> {
+	let hash = api.chain().read().block_by_number[&0][0].0.header.hash();
+	HashAndNumber { hash, number: 0 }
+}
+
+fn benchmark_main(c: &mut Criterion) {
+	c.bench_function("basic pool, 4 blocks of 50 tx", |b| {
+		b.iter(|| {
+			let api = Arc::new(TestApi::empty());
+			let genesis = genesis(&api).hash;
+			let (pool, _background_task) = BasicPool::new_test(api.clone(), genesis, genesis);
+			import_blocks(&pool, &api);
+		});
+	});
+
+	c.bench_function("fork-aware pool, 4 blocks of 50 tx", |b| {
+		b.iter(|| {
+			let api = Arc::new(TestApi::empty());
+			let pool = ForkAwareTxPool::new_test(api.clone(), genesis(&api));
+			import_blocks(&pool, &api);
+		});
+	});
+}
+
+criterion_group!(benches, benchmark_main);
+criterion_main!(benches);
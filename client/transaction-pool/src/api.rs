@@ -203,6 +203,10 @@ where
 	) -> Result<TreeRoute<Self::Block>, Self::Error> {
 		sp_blockchain::tree_route::<Block, Client>(&*self.client, from, to).map_err(Into::into)
 	}
+
+	fn best_block_hash(&self) -> error::Result<Block::Hash> {
+		Ok(self.client.info().best_hash)
+	}
 }
 
 /// Helper function to validate a transaction using a full chain API.
@@ -0,0 +1,227 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Transactions known to the fork-aware pool, independently of any view.
+
+use std::{
+	collections::{HashMap, HashSet},
+	sync::atomic::{AtomicU64, Ordering},
+};
+
+use parking_lot::RwLock;
+use sc_transaction_pool_api::TransactionSource;
+use sp_blockchain::HashAndNumber;
+use sp_runtime::{
+	traits::SaturatedConversion,
+	transaction_validity::{TransactionLongevity, TransactionPriority},
+};
+
+use crate::graph::{self, BlockHash, ExtrinsicFor, ExtrinsicHash, NumberFor};
+
+/// Outcome of inserting a transaction into the [`Mempool`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Insertion {
+	/// The transaction was not known before.
+	Inserted,
+	/// The transaction is already part of the mempool.
+	AlreadyKnown,
+}
+
+/// A transaction of the [`Mempool`] together with what the views learnt about it.
+struct MempoolTransaction<ChainApi: graph::ChainApi> {
+	source: TransactionSource,
+	xt: ExtrinsicFor<ChainApi>,
+	/// Order of insertion, used to evict the oldest of equal priority transactions first.
+	insertion_id: u64,
+	/// Highest priority the transaction was validated with in any view.
+	priority: Option<TransactionPriority>,
+	/// Highest block number up to which the transaction is valid in any view.
+	valid_till: Option<TransactionLongevity>,
+	/// Blocks at which the transaction was found to be stale.
+	stale_at: Vec<HashAndNumber<ChainApi::Block>>,
+}
+
+/// All transactions submitted to the pool that are neither finalized nor invalid.
+///
+/// The views are (re-)built from the mempool, so a transaction that is not valid at any of the
+/// current leaves is still kept and may become valid again after a re-org. Transactions are
+/// only dropped once they are outdated at the finalized block (see [`Mempool::remove_outdated`])
+/// or when the mempool exceeds its limit (see [`Mempool::enforce_limit`]).
+pub(super) struct Mempool<ChainApi: graph::ChainApi> {
+	transactions: RwLock<HashMap<ExtrinsicHash<ChainApi>, MempoolTransaction<ChainApi>>>,
+	next_insertion_id: AtomicU64,
+	max_count: usize,
+}
+
+impl<ChainApi: graph::ChainApi> Mempool<ChainApi> {
+	/// Create a new mempool holding at most `max_count` transactions.
+	pub(super) fn new(max_count: usize) -> Self {
+		Self { transactions: Default::default(), next_insertion_id: Default::default(), max_count }
+	}
+
+	/// Insert the given transactions.
+	///
+	/// The mempool may temporarily exceed its limit, [`Mempool::enforce_limit`] has to be called
+	/// once the priorities of the new transactions are known.
+	pub(super) fn extend(
+		&self,
+		source: TransactionSource,
+		xts: &[(ExtrinsicHash<ChainApi>, ExtrinsicFor<ChainApi>)],
+	) -> Vec<Insertion> {
+		let mut transactions = self.transactions.write();
+		xts.iter()
+			.map(|(hash, xt)| {
+				if transactions.contains_key(hash) {
+					return Insertion::AlreadyKnown
+				}
+				transactions.insert(
+					*hash,
+					MempoolTransaction {
+						source,
+						xt: xt.clone(),
+						insertion_id: self.next_insertion_id.fetch_add(1, Ordering::Relaxed),
+						priority: None,
+						valid_till: None,
+						stale_at: Vec::new(),
+					},
+				);
+				Insertion::Inserted
+			})
+			.collect()
+	}
+
+	/// Record the priority and longevity a transaction was validated with in a view.
+	pub(super) fn note_valid(
+		&self,
+		hash: &ExtrinsicHash<ChainApi>,
+		priority: TransactionPriority,
+		valid_till: TransactionLongevity,
+	) {
+		if let Some(tx) = self.transactions.write().get_mut(hash) {
+			tx.priority = tx.priority.max(Some(priority));
+			tx.valid_till = tx.valid_till.max(Some(valid_till));
+		}
+	}
+
+	/// Record that the given transactions are stale at the given block.
+	pub(super) fn note_stale(
+		&self,
+		at: &HashAndNumber<ChainApi::Block>,
+		hashes: &[ExtrinsicHash<ChainApi>],
+	) {
+		let mut transactions = self.transactions.write();
+		for hash in hashes {
+			if let Some(tx) = transactions.get_mut(hash) {
+				if !tx.stale_at.iter().any(|stale| stale.hash == at.hash) {
+					tx.stale_at.push(at.clone());
+				}
+			}
+		}
+	}
+
+	/// Remove the lowest priority transactions until the mempool is within its limit.
+	///
+	/// Transactions of unknown priority go first and, like in the views, the oldest transaction
+	/// goes first among the ones of equal priority, so a new transaction replaces an old one
+	/// instead of being rejected. Returns the hashes of the removed transactions.
+	pub(super) fn enforce_limit(&self) -> Vec<ExtrinsicHash<ChainApi>> {
+		let mut transactions = self.transactions.write();
+		let excess = transactions.len().saturating_sub(self.max_count);
+		if excess == 0 {
+			return Vec::new()
+		}
+
+		let mut by_priority = transactions
+			.iter()
+			.map(|(hash, tx)| ((tx.priority, tx.insertion_id), *hash))
+			.collect::<Vec<_>>();
+		by_priority.sort_unstable_by_key(|(key, _)| *key);
+		by_priority
+			.into_iter()
+			.take(excess)
+			.map(|(_, hash)| {
+				transactions.remove(&hash);
+				hash
+			})
+			.collect()
+	}
+
+	/// Remove the transactions that can't become valid again once `finalized_blocks` are final.
+	///
+	/// A transaction is outdated if its longevity has passed at the finalized block, or if it
+	/// was found to be stale at one of the `finalized_blocks`, since every future view descends
+	/// from them. Returns the hashes of the removed transactions.
+	pub(super) fn remove_outdated(
+		&self,
+		finalized_number: NumberFor<ChainApi>,
+		finalized_blocks: &HashSet<BlockHash<ChainApi>>,
+	) -> Vec<ExtrinsicHash<ChainApi>> {
+		let finalized = finalized_number.saturated_into::<u64>();
+		let mut removed = Vec::new();
+		self.transactions.write().retain(|hash, tx| {
+			let expired = tx.valid_till.map_or(false, |valid_till| valid_till <= finalized);
+			let stale = tx.stale_at.iter().any(|stale| finalized_blocks.contains(&stale.hash));
+			if expired || stale {
+				removed.push(*hash);
+				return false
+			}
+			// Blocks at or below the finalized one that were not finalized are on dead forks.
+			tx.stale_at.retain(|stale| stale.number > finalized_number);
+			true
+		});
+		removed
+	}
+
+	/// Returns true if the transaction is part of the mempool.
+	pub(super) fn contains(&self, hash: &ExtrinsicHash<ChainApi>) -> bool {
+		self.transactions.read().contains_key(hash)
+	}
+
+	/// Remove the given transactions, returning the hashes of the actually removed ones.
+	pub(super) fn remove(
+		&self,
+		hashes: &[ExtrinsicHash<ChainApi>],
+	) -> Vec<ExtrinsicHash<ChainApi>> {
+		let mut transactions = self.transactions.write();
+		hashes
+			.iter()
+			.filter(|hash| transactions.remove(hash).is_some())
+			.copied()
+			.collect()
+	}
+
+	/// Returns all transactions grouped by their source.
+	pub(super) fn by_source(
+		&self,
+	) -> Vec<(TransactionSource, Vec<(ExtrinsicHash<ChainApi>, ExtrinsicFor<ChainApi>)>)> {
+		let mut grouped = Vec::<(TransactionSource, Vec<_>)>::new();
+		for (hash, tx) in self.transactions.read().iter() {
+			let entry = (*hash, tx.xt.clone());
+			match grouped.iter_mut().find(|(s, _)| *s == tx.source) {
+				Some((_, xts)) => xts.push(entry),
+				None => grouped.push((tx.source, vec![entry])),
+			}
+		}
+		grouped
+	}
+
+	/// Returns the number of transactions in the mempool.
+	pub(super) fn len(&self) -> usize {
+		self.transactions.read().len()
+	}
+}
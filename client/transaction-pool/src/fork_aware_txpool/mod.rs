@@ -0,0 +1,471 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Fork-aware transaction pool.
+//!
+//! Unlike [`BasicPool`](crate::BasicPool), which keeps a single set of ready transactions and
+//! updates it on every re-org, this pool keeps a separate view for every non-finalized leaf of
+//! the chain. A view holds the transactions validated at its block, so a block author gets the
+//! exact ready set of the parent it builds on (see
+//! [`TransactionPool::ready_at_block`]).
+//!
+//! All submitted transactions are kept in a mempool until they are finalized or turn out to be
+//! invalid. A view is created by validating the whole mempool at its block, either when the block
+//! becomes the new best block or when a block author asks for it.
+
+mod mempool;
+mod view;
+mod view_store;
+
+use std::{
+	collections::{HashMap, HashSet},
+	pin::Pin,
+	sync::Arc,
+};
+
+use async_trait::async_trait;
+use futures::prelude::*;
+use parking_lot::Mutex;
+use prometheus_endpoint::Registry as PrometheusRegistry;
+use sc_transaction_pool_api::{
+	error::Error as TxPoolError, ChainEvent, ImportNotificationStream, MaintainedTransactionPool,
+	PoolFuture, PoolStatus, TransactionFor, TransactionPool, TransactionSource,
+	TransactionStatusStreamFor, TxHash,
+};
+use sp_blockchain::HashAndNumber;
+use sp_core::traits::SpawnEssentialNamed;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, NumberFor},
+};
+
+use crate::{
+	api::FullChainApi, error, graph, metrics::MetricsLink as PrometheusMetrics, PolledIterator,
+	ReadyIteratorFor, ReadyPoll, ValidatedTransaction, LOG_TARGET,
+};
+use view_store::ViewStore;
+
+/// A fork-aware transaction pool for a full node.
+pub type ForkAwareFullPool<Block, Client> = ForkAwareTxPool<FullChainApi<Client, Block>, Block>;
+
+/// Transaction pool keeping a view of the validated transactions for every fork.
+pub struct ForkAwareTxPool<ChainApi, Block>
+where
+	Block: BlockT,
+	ChainApi: graph::ChainApi<Block = Block> + 'static,
+{
+	view_store: Arc<ViewStore<ChainApi>>,
+	ready_poll: Mutex<ReadyPoll<ReadyIteratorFor<ChainApi>, Block>>,
+	metrics: PrometheusMetrics,
+}
+
+impl<ChainApi, Block> ForkAwareTxPool<ChainApi, Block>
+where
+	Block: BlockT,
+	ChainApi: graph::ChainApi<Block = Block> + 'static,
+{
+	/// Create a new fork-aware pool with the given best block.
+	pub fn new(
+		options: graph::Options,
+		is_validator: graph::IsValidator,
+		pool_api: Arc<ChainApi>,
+		prometheus: Option<&PrometheusRegistry>,
+		best_block: HashAndNumber<Block>,
+	) -> Self {
+		let metrics = PrometheusMetrics::new(prometheus);
+		let best_number = best_block.number;
+		Self {
+			view_store: Arc::new(ViewStore::new(
+				pool_api,
+				options,
+				is_validator,
+				metrics.clone(),
+				best_block,
+			)),
+			ready_poll: Mutex::new(ReadyPoll::new(best_number)),
+			metrics,
+		}
+	}
+
+	/// Create a new fork-aware pool with the given best block, for tests.
+	pub fn new_test(pool_api: Arc<ChainApi>, best_block: HashAndNumber<Block>) -> Self {
+		Self::new(Default::default(), true.into(), pool_api, None, best_block)
+	}
+
+	/// Get access to the underlying api.
+	pub fn api(&self) -> &ChainApi {
+		&self.view_store.api
+	}
+
+	/// Returns the number of views currently kept by the pool.
+	pub fn views_count(&self) -> usize {
+		self.view_store.len()
+	}
+
+	/// Switches the best view to the given block, creating the view if needed.
+	async fn handle_new_best_block(&self, hash: Block::Hash) {
+		let number = match self.view_store.api.block_id_to_number(&BlockId::Hash(hash)) {
+			Ok(Some(number)) => number,
+			Ok(None) | Err(_) => {
+				log::debug!(target: LOG_TARGET, "Unknown new best block {:?}", hash);
+				return
+			},
+		};
+		let at = HashAndNumber { hash, number };
+		let previous = self.view_store.best().at.clone();
+		if previous.hash == hash {
+			return
+		}
+
+		self.notify_enacted(&previous, &at).await;
+		let view = self.view_store.view_at(&at).await;
+
+		// Views are only kept for leaves, the parent view is superseded by the new one.
+		if let Ok(Some(header)) = self.view_store.api.block_header(hash) {
+			self.view_store.remove(header.parent_hash());
+		}
+		self.view_store.set_best(view.clone());
+		self.ready_poll.lock().trigger(number, || view.ready());
+	}
+
+	/// Notify the watchers about transactions included in the enacted and retracted blocks.
+	async fn notify_enacted(&self, from: &HashAndNumber<Block>, to: &HashAndNumber<Block>) {
+		let api = &self.view_store.api;
+		let tree_route = match api.tree_route(from.hash, to.hash) {
+			Ok(tree_route) => tree_route,
+			Err(e) => {
+				log::debug!(
+					target: LOG_TARGET,
+					"Error computing tree route from {:?} to {:?}: {}",
+					from.hash,
+					to.hash,
+					e,
+				);
+				return
+			},
+		};
+
+		for retracted in tree_route.retracted() {
+			self.view_store.listener.lock().retracted(retracted.hash);
+		}
+
+		for enacted in tree_route.enacted() {
+			let Ok(Some(body)) = api.block_body(enacted.hash).await else { continue };
+			let mut listener = self.view_store.listener.lock();
+			for xt in body {
+				let hash = api.hash_and_length(&xt).0;
+				if self.view_store.mempool.contains(&hash) {
+					listener.pruned(enacted.hash, &hash);
+				}
+			}
+		}
+	}
+
+	/// Removes the finalized and outdated transactions and the views that are not descendants of
+	/// the finalized block.
+	async fn handle_finalized(&self, hash: Block::Hash, tree_route: &[Block::Hash]) {
+		let api = &self.view_store.api;
+		for block in tree_route.iter().chain(std::iter::once(&hash)) {
+			if let Ok(Some(body)) = api.block_body(*block).await {
+				let included = body.iter().map(|xt| api.hash_and_length(xt).0).collect::<Vec<_>>();
+				self.view_store.mempool.remove(&included);
+			}
+			self.view_store.listener.lock().finalized(*block);
+		}
+
+		let Ok(Some(finalized_number)) = api.block_id_to_number(&BlockId::Hash(hash)) else {
+			return
+		};
+		let finalized =
+			tree_route.iter().chain(std::iter::once(&hash)).copied().collect::<HashSet<_>>();
+		self.view_store.remove_outdated(finalized_number, &finalized);
+
+		self.view_store.retain(|view| {
+			view.at.number >= finalized_number &&
+				api.tree_route(hash, view.at.hash)
+					.map(|route| route.retracted().is_empty())
+					.unwrap_or(false)
+		});
+	}
+}
+
+impl<ChainApi, Block> TransactionPool for ForkAwareTxPool<ChainApi, Block>
+where
+	Block: BlockT,
+	ChainApi: graph::ChainApi<Block = Block> + 'static,
+{
+	type Block = ChainApi::Block;
+	type Hash = graph::ExtrinsicHash<ChainApi>;
+	type InPoolTransaction = graph::base_pool::Transaction<TxHash<Self>, TransactionFor<Self>>;
+	type Error = ChainApi::Error;
+
+	fn submit_at(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolFuture<Vec<Result<TxHash<Self>, Self::Error>>, Self::Error> {
+		let view_store = self.view_store.clone();
+		let at = block_hash(at);
+
+		self.metrics
+			.report(|metrics| metrics.submitted_transactions.inc_by(xts.len() as u64));
+
+		async move { view_store.submit(at, source, xts).await }.boxed()
+	}
+
+	fn submit_one(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		let view_store = self.view_store.clone();
+		let at = block_hash(at);
+
+		self.metrics.report(|metrics| metrics.submitted_transactions.inc());
+
+		async move {
+			view_store
+				.submit(at, source, vec![xt])
+				.await?
+				.pop()
+				.expect("One extrinsic passed; one result returned; qed")
+		}
+		.boxed()
+	}
+
+	fn submit_and_watch(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error> {
+		let view_store = self.view_store.clone();
+		let at = block_hash(at);
+
+		self.metrics.report(|metrics| metrics.submitted_transactions.inc());
+
+		async move {
+			let hash = view_store.api.hash_and_length(&xt).0;
+			let watcher = view_store.listener.lock().create_watcher(hash);
+			view_store
+				.submit(at, source, vec![xt])
+				.await?
+				.pop()
+				.expect("One extrinsic passed; one result returned; qed")?;
+
+			Ok(watcher.into_stream().boxed())
+		}
+		.boxed()
+	}
+
+	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>> {
+		let removed = self.view_store.remove_invalid(hashes);
+		self.metrics
+			.report(|metrics| metrics.validations_invalid.inc_by(removed.len() as u64));
+		removed
+	}
+
+	fn status(&self) -> PoolStatus {
+		self.view_store.best().pool.validated_pool().status()
+	}
+
+	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>> {
+		self.view_store.import_notification_stream()
+	}
+
+	fn hash_of(&self, xt: &TransactionFor<Self>) -> TxHash<Self> {
+		self.view_store.api.hash_and_length(xt).0
+	}
+
+	fn on_broadcasted(&self, propagations: HashMap<TxHash<Self>, Vec<String>>) {
+		let mut listener = self.view_store.listener.lock();
+		for (hash, peers) in propagations {
+			listener.broadcasted(&hash, peers);
+		}
+	}
+
+	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
+		self.view_store.best().pool.validated_pool().ready_by_hash(hash)
+	}
+
+	fn ready_at(&self, at: NumberFor<Self::Block>) -> PolledIterator<ChainApi> {
+		if self.ready_poll.lock().updated_at() >= at {
+			let iterator = self.view_store.best().ready();
+			return async move { iterator }.boxed()
+		}
+
+		self.ready_poll
+			.lock()
+			.add(at)
+			.map(|received| {
+				received.unwrap_or_else(|e| {
+					log::warn!(target: LOG_TARGET, "Error receiving pending set: {:?}", e);
+					Box::new(std::iter::empty())
+				})
+			})
+			.boxed()
+	}
+
+	fn ready_at_block(&self, at: &HashAndNumber<Self::Block>) -> PolledIterator<ChainApi> {
+		if let Some(view) = self.view_store.get(&at.hash) {
+			let iterator = view.ready();
+			return async move { iterator }.boxed()
+		}
+
+		let view_store = self.view_store.clone();
+		let at = at.clone();
+		async move { view_store.view_at(&at).await.ready() }.boxed()
+	}
+
+	fn ready(&self) -> ReadyIteratorFor<ChainApi> {
+		self.view_store.best().ready()
+	}
+
+	fn futures(&self) -> Vec<Self::InPoolTransaction> {
+		let view = self.view_store.best();
+		let pool = view.pool.validated_pool().pool.read();
+		pool.futures().cloned().collect()
+	}
+}
+
+#[async_trait]
+impl<ChainApi, Block> MaintainedTransactionPool for ForkAwareTxPool<ChainApi, Block>
+where
+	Block: BlockT,
+	ChainApi: graph::ChainApi<Block = Block> + 'static,
+{
+	async fn maintain(&self, event: ChainEvent<Self::Block>) {
+		match event {
+			ChainEvent::NewBestBlock { hash, .. } => self.handle_new_best_block(hash).await,
+			ChainEvent::Finalized { hash, tree_route } =>
+				self.handle_finalized(hash, &tree_route).await,
+		}
+	}
+}
+
+impl<Block, Client> ForkAwareFullPool<Block, Client>
+where
+	Block: BlockT,
+	Client: sp_api::ProvideRuntimeApi<Block>
+		+ sc_client_api::BlockBackend<Block>
+		+ sc_client_api::blockchain::HeaderBackend<Block>
+		+ sp_runtime::traits::BlockIdTo<Block>
+		+ sc_client_api::ExecutorProvider<Block>
+		+ sc_client_api::UsageProvider<Block>
+		+ sp_blockchain::HeaderMetadata<Block, Error = sp_blockchain::Error>
+		+ Send
+		+ Sync
+		+ 'static,
+	Client::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>,
+{
+	/// Create new fork-aware transaction pool for a full node with the provided api.
+	pub fn new_full(
+		options: graph::Options,
+		is_validator: graph::IsValidator,
+		prometheus: Option<&PrometheusRegistry>,
+		spawner: impl SpawnEssentialNamed,
+		client: Arc<Client>,
+	) -> Arc<Self> {
+		let pool_api = Arc::new(FullChainApi::new(client.clone(), prometheus, &spawner));
+		let info = client.usage_info().chain;
+		Arc::new(Self::new(
+			options,
+			is_validator,
+			pool_api,
+			prometheus,
+			HashAndNumber { hash: info.best_hash, number: info.best_number },
+		))
+	}
+}
+
+impl<Block, Client> sc_transaction_pool_api::LocalTransactionPool
+	for ForkAwareFullPool<Block, Client>
+where
+	Block: BlockT,
+	Client: sp_api::ProvideRuntimeApi<Block>
+		+ sc_client_api::BlockBackend<Block>
+		+ sc_client_api::blockchain::HeaderBackend<Block>
+		+ sp_runtime::traits::BlockIdTo<Block>
+		+ sp_blockchain::HeaderMetadata<Block, Error = sp_blockchain::Error>,
+	Client: Send + Sync + 'static,
+	Client::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>,
+{
+	type Block = Block;
+	type Hash = graph::ExtrinsicHash<FullChainApi<Client, Block>>;
+	type Error = <FullChainApi<Client, Block> as graph::ChainApi>::Error;
+
+	fn submit_local(
+		&self,
+		at: Block::Hash,
+		xt: sc_transaction_pool_api::LocalTransactionFor<Self>,
+	) -> Result<Self::Hash, Self::Error> {
+		use sp_runtime::{
+			traits::SaturatedConversion, transaction_validity::TransactionValidityError,
+		};
+
+		let api = &self.view_store.api;
+		let validity = api
+			.validate_transaction_blocking(
+				&BlockId::hash(at),
+				TransactionSource::Local,
+				xt.clone(),
+			)?
+			.map_err(|e| {
+				Self::Error::Pool(match e {
+					TransactionValidityError::Invalid(i) => TxPoolError::InvalidTransaction(i),
+					TransactionValidityError::Unknown(u) => TxPoolError::UnknownTransaction(u),
+				})
+			})?;
+
+		let (hash, bytes) = api.hash_and_length(&xt);
+		let block_number = api
+			.block_id_to_number(&BlockId::hash(at))?
+			.ok_or_else(|| error::Error::BlockIdConversion(format!("{:?}", at)))?;
+
+		// The other views pick the transaction up from the mempool when they are rebuilt.
+		self.view_store.mempool.extend(TransactionSource::Local, &[(hash, xt.clone())]);
+		let view = self.view_store.get(&at).unwrap_or_else(|| self.view_store.best());
+		let validated = ValidatedTransaction::valid_at(
+			block_number.saturated_into::<u64>(),
+			hash,
+			TransactionSource::Local,
+			xt,
+			bytes,
+			validity,
+		);
+
+		let result = view.pool.validated_pool().submit(vec![validated]).remove(0);
+		for (hash, priority, valid_till) in view.validity(&[hash]) {
+			self.view_store.mempool.note_valid(&hash, priority, valid_till);
+		}
+		if self.view_store.enforce_limit(&[hash]).contains(&hash) {
+			return Err(TxPoolError::ImmediatelyDropped.into())
+		}
+		result
+	}
+}
+
+/// Returns the block hash of a [`BlockId`], if it is given by hash.
+fn block_hash<Block: BlockT>(at: &BlockId<Block>) -> Option<Block::Hash> {
+	match at {
+		BlockId::Hash(hash) => Some(*hash),
+		BlockId::Number(_) => None,
+	}
+}
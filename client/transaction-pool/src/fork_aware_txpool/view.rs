@@ -0,0 +1,146 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! A view of the transaction pool at a particular block.
+
+use std::sync::Arc;
+
+use sc_transaction_pool_api::{
+	error::{Error as TxPoolError, IntoPoolError},
+	TransactionSource,
+};
+use sp_blockchain::HashAndNumber;
+use sp_runtime::{
+	generic::BlockId,
+	transaction_validity::{InvalidTransaction, TransactionLongevity, TransactionPriority},
+};
+
+use crate::{
+	graph::{self, BlockHash, ExtrinsicFor, ExtrinsicHash, IsValidator},
+	metrics::MetricsLink,
+	ReadyIteratorFor,
+};
+
+/// Transactions validated at a single block.
+///
+/// Every view holds its own [`graph::Pool`], so the ready set of a view is exactly the set of
+/// transactions that can be included in a block built on top of `at`.
+pub(super) struct View<ChainApi: graph::ChainApi> {
+	/// The block this view was validated at.
+	pub(super) at: HashAndNumber<ChainApi::Block>,
+	/// Transactions valid at `at`.
+	pub(super) pool: graph::Pool<ChainApi>,
+}
+
+impl<ChainApi> View<ChainApi>
+where
+	ChainApi: graph::ChainApi + 'static,
+{
+	/// Create a new, empty view at the given block.
+	pub(super) fn new(
+		api: Arc<ChainApi>,
+		at: HashAndNumber<ChainApi::Block>,
+		options: graph::Options,
+		is_validator: IsValidator,
+		metrics: MetricsLink,
+	) -> Self {
+		Self { at, pool: graph::Pool::new_with_metrics(options, is_validator, api, metrics) }
+	}
+
+	/// Validate the given transactions at the view's block and import them.
+	pub(super) async fn submit_many(
+		&self,
+		source: TransactionSource,
+		xts: Vec<ExtrinsicFor<ChainApi>>,
+	) -> Result<Vec<Result<ExtrinsicHash<ChainApi>, ChainApi::Error>>, ChainApi::Error> {
+		self.pool.submit_at(&BlockId::Hash(self.at.hash), source, xts).await
+	}
+
+	/// Prune the transactions included in the view's block, validating unknown ones at `parent`.
+	pub(super) async fn prune(
+		&self,
+		parent: BlockHash<ChainApi>,
+		extrinsics: &[ExtrinsicFor<ChainApi>],
+	) -> Result<(), ChainApi::Error> {
+		self.pool
+			.prune(&BlockId::Hash(self.at.hash), &BlockId::Hash(parent), extrinsics)
+			.await
+	}
+
+	/// Returns the ready transactions of this view, ordered by priority.
+	pub(super) fn ready(&self) -> ReadyIteratorFor<ChainApi> {
+		Box::new(self.pool.validated_pool().ready())
+	}
+
+	/// Returns the priority and the block number up to which each of the given transactions is
+	/// valid in this view, skipping the ones that are not part of it.
+	pub(super) fn validity(
+		&self,
+		hashes: &[ExtrinsicHash<ChainApi>],
+	) -> Vec<(ExtrinsicHash<ChainApi>, TransactionPriority, TransactionLongevity)> {
+		self.pool
+			.validated_pool()
+			.pool
+			.read()
+			.by_hashes(hashes)
+			.into_iter()
+			.flatten()
+			.map(|tx| (tx.hash, tx.priority, tx.valid_till))
+			.collect()
+	}
+
+	/// Returns true if the transaction is part of the ready queue of this view.
+	pub(super) fn is_ready(&self, hash: &ExtrinsicHash<ChainApi>) -> bool {
+		self.pool.validated_pool().ready_by_hash(hash).is_some()
+	}
+}
+
+/// How a view rejected a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Rejection {
+	/// The transaction is stale at the view's block.
+	///
+	/// It is kept, because it may still be valid on a fork that doesn't include it yet.
+	Stale,
+	/// The transaction can't become valid on any fork.
+	Invalid,
+	/// The transaction may become valid later, e.g. its validity is unknown.
+	Other,
+}
+
+impl Rejection {
+	/// Classifies the error returned when submitting a transaction to a view.
+	///
+	/// The error is handed back, so it can still be reported to the submitter.
+	pub(super) fn of<E>(error: E) -> (Self, E)
+	where
+		E: IntoPoolError + From<TxPoolError>,
+	{
+		match error.into_pool_error() {
+			Ok(error) => {
+				let rejection = match error {
+					TxPoolError::InvalidTransaction(InvalidTransaction::Stale) => Self::Stale,
+					TxPoolError::InvalidTransaction(_) => Self::Invalid,
+					_ => Self::Other,
+				};
+				(rejection, error.into())
+			},
+			Err(error) => (Self::Other, error),
+		}
+	}
+}
@@ -0,0 +1,408 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Collection of the views kept by the fork-aware pool.
+
+use std::{
+	collections::{HashMap, HashSet},
+	sync::Arc,
+};
+
+use futures::{
+	channel::mpsc::{channel, Sender},
+	future::join_all,
+};
+use parking_lot::{Mutex, RwLock};
+use sc_transaction_pool_api::{error::Error as TxPoolError, TransactionSource};
+use sp_blockchain::HashAndNumber;
+use sp_runtime::traits::Header as _;
+
+use super::{
+	mempool::{Insertion, Mempool},
+	view::{Rejection, View},
+};
+use crate::{
+	graph::{
+		self, listener::Listener, BlockHash, EventStream, ExtrinsicFor, ExtrinsicHash, IsValidator,
+		NumberFor, TransactionFor,
+	},
+	metrics::MetricsLink,
+	LOG_TARGET,
+};
+
+/// Size of the buffer of every import notification stream.
+const IMPORT_NOTIFICATION_BUFFER_SIZE: usize = 1024;
+
+/// The views of all non-finalized leaves together with the transactions they are built from.
+pub(super) struct ViewStore<ChainApi: graph::ChainApi> {
+	pub(super) api: Arc<ChainApi>,
+	options: graph::Options,
+	is_validator: Arc<IsValidator>,
+	metrics: MetricsLink,
+	pub(super) mempool: Mempool<ChainApi>,
+	views: RwLock<HashMap<BlockHash<ChainApi>, Arc<View<ChainApi>>>>,
+	best: RwLock<Arc<View<ChainApi>>>,
+	pub(super) listener: Mutex<Listener<ExtrinsicHash<ChainApi>, ChainApi>>,
+	import_notification_sinks: Mutex<Vec<Sender<ExtrinsicHash<ChainApi>>>>,
+}
+
+impl<ChainApi> ViewStore<ChainApi>
+where
+	ChainApi: graph::ChainApi + 'static,
+{
+	/// Create a new store with an empty view at the given best block.
+	pub(super) fn new(
+		api: Arc<ChainApi>,
+		options: graph::Options,
+		is_validator: IsValidator,
+		metrics: MetricsLink,
+		best: HashAndNumber<ChainApi::Block>,
+	) -> Self {
+		let is_validator = Arc::new(is_validator);
+		let view = Arc::new(View::new(
+			api.clone(),
+			best,
+			options.clone(),
+			shared_validator(&is_validator),
+			metrics.clone(),
+		));
+		let max_count = options.ready.count.saturating_add(options.future.count);
+
+		Self {
+			api,
+			options,
+			is_validator,
+			metrics,
+			mempool: Mempool::new(max_count),
+			views: RwLock::new(HashMap::from([(view.at.hash, view.clone())])),
+			best: RwLock::new(view),
+			listener: Default::default(),
+			import_notification_sinks: Default::default(),
+		}
+	}
+
+	/// Returns the view of the current best block.
+	pub(super) fn best(&self) -> Arc<View<ChainApi>> {
+		self.best.read().clone()
+	}
+
+	/// Returns the view at the given block, if there is one.
+	pub(super) fn get(&self, at: &BlockHash<ChainApi>) -> Option<Arc<View<ChainApi>>> {
+		self.views.read().get(at).cloned()
+	}
+
+	/// Makes the given view the best one.
+	pub(super) fn set_best(&self, view: Arc<View<ChainApi>>) {
+		*self.best.write() = view;
+	}
+
+	/// Removes the view at the given block, unless it is the best one.
+	pub(super) fn remove(&self, at: &BlockHash<ChainApi>) {
+		if self.best.read().at.hash != *at {
+			self.views.write().remove(at);
+		}
+	}
+
+	/// Retains only the views for which `f` returns true (and the best view).
+	pub(super) fn retain(&self, mut f: impl FnMut(&View<ChainApi>) -> bool) {
+		let best = self.best.read().at.hash;
+		self.views.write().retain(|hash, view| *hash == best || f(view));
+	}
+
+	/// Returns the number of views.
+	pub(super) fn len(&self) -> usize {
+		self.views.read().len()
+	}
+
+	/// Returns the view at the given block, creating it if it doesn't exist yet.
+	///
+	/// A new view is created by validating all transactions of the mempool at the given block.
+	/// Transactions that turn out to be permanently invalid are removed from the mempool, the
+	/// stale ones are recorded so they can be removed once the block is finalized.
+	pub(super) async fn view_at(&self, at: &HashAndNumber<ChainApi::Block>) -> Arc<View<ChainApi>> {
+		if let Some(view) = self.get(&at.hash) {
+			return view
+		}
+
+		let view = View::new(
+			self.api.clone(),
+			at.clone(),
+			self.options.clone(),
+			shared_validator(&self.is_validator),
+			self.metrics.clone(),
+		);
+
+		let mut invalid = Vec::new();
+		let mut stale = Vec::new();
+		let mut submitted = Vec::new();
+		for (source, txs) in self.mempool.by_source() {
+			let (hashes, xts): (Vec<_>, Vec<_>) = txs.into_iter().unzip();
+			match view.submit_many(source, xts).await {
+				Ok(results) =>
+					for (hash, result) in hashes.iter().zip(results) {
+						match result.map_err(|e| Rejection::of(e).0) {
+							Err(Rejection::Invalid) => invalid.push(*hash),
+							Err(Rejection::Stale) => stale.push(*hash),
+							_ => (),
+						}
+					},
+				Err(e) => log::debug!(
+					target: LOG_TARGET,
+					"Failed to build the view at {:?}: {}",
+					at.hash,
+					e,
+				),
+			}
+			submitted.extend(hashes);
+		}
+		self.remove_from_mempool(&invalid);
+		self.mempool.note_stale(at, &stale);
+		for (hash, priority, valid_till) in view.validity(&submitted) {
+			self.mempool.note_valid(&hash, priority, valid_till);
+		}
+
+		// The runtime may still consider the transactions of the view's own block as valid at
+		// it, so they are pruned by their tags like the single-state pool does.
+		if let (Ok(Some(header)), Ok(Some(body))) =
+			(self.api.block_header(at.hash), self.api.block_body(at.hash).await)
+		{
+			if let Err(e) = view.prune(*header.parent_hash(), &body).await {
+				log::debug!(target: LOG_TARGET, "Failed to prune the view at {:?}: {}", at.hash, e);
+			}
+		}
+
+		log::debug!(
+			target: LOG_TARGET,
+			"Created view at {:?} ({:?}), {} transactions in mempool, {} views",
+			at.hash,
+			view.pool.validated_pool().status(),
+			self.mempool.len(),
+			self.len() + 1,
+		);
+
+		self.views.write().entry(at.hash).or_insert_with(|| Arc::new(view)).clone()
+	}
+
+	/// Submit transactions to the mempool and all views.
+	///
+	/// The returned results are the ones of the view at `at`, or of the best view if there is
+	/// no view at `at`. Transactions that are not valid at any of the views are not kept.
+	///
+	/// If the mempool exceeds its limit, the lowest priority transactions are evicted. A
+	/// submitted transaction that is evicted right away is reported as immediately dropped.
+	pub(super) async fn submit(
+		&self,
+		at: Option<BlockHash<ChainApi>>,
+		source: TransactionSource,
+		xts: Vec<ExtrinsicFor<ChainApi>>,
+	) -> Result<Vec<Result<ExtrinsicHash<ChainApi>, ChainApi::Error>>, ChainApi::Error> {
+		let xts = xts
+			.into_iter()
+			.map(|xt| (self.api.hash_and_length(&xt).0, xt))
+			.collect::<Vec<_>>();
+		let insertions = self.mempool.extend(source, &xts);
+		let (hashes, to_submit): (Vec<_>, Vec<_>) = xts.iter().cloned().unzip();
+
+		let reporting = at.and_then(|at| self.get(&at)).unwrap_or_else(|| self.best());
+		let mut views = self.views.read().values().cloned().collect::<Vec<_>>();
+		if !views.iter().any(|view| view.at.hash == reporting.at.hash) {
+			views.push(reporting.clone());
+		}
+
+		let results =
+			join_all(views.iter().map(|view| view.submit_many(source, to_submit.clone()))).await;
+
+		let mut valid_anywhere = HashSet::new();
+		let mut reported = None;
+		for (view, result) in views.iter().zip(results) {
+			let result = result.map(|results| {
+				let mut stale = Vec::new();
+				let results = hashes
+					.iter()
+					.zip(results)
+					.map(|(hash, result)| {
+						result.map_err(|e| {
+							let (rejection, e) = Rejection::of(e);
+							if rejection == Rejection::Stale {
+								stale.push(*hash);
+							}
+							e
+						})
+					})
+					.collect::<Vec<_>>();
+				self.mempool.note_stale(&view.at, &stale);
+				results
+			});
+			if let Ok(ref results) = result {
+				valid_anywhere.extend(results.iter().filter_map(|r| r.as_ref().ok().copied()));
+			}
+			for (hash, priority, valid_till) in view.validity(&hashes) {
+				self.mempool.note_valid(&hash, priority, valid_till);
+			}
+			if view.at.hash == reporting.at.hash {
+				reported = Some(result);
+			}
+		}
+
+		// Don't keep transactions that no view accepted.
+		let rejected = xts
+			.iter()
+			.zip(&insertions)
+			.filter(|((hash, _), insertion)| {
+				**insertion == Insertion::Inserted && !valid_anywhere.contains(hash)
+			})
+			.map(|((hash, _), _)| *hash)
+			.collect::<Vec<_>>();
+		self.mempool.remove(&rejected);
+
+		let evicted = self.enforce_limit(&hashes);
+
+		let reported = reported.expect("The reporting view is always submitted to; qed")?;
+		let results = hashes
+			.iter()
+			.zip(insertions)
+			.zip(reported)
+			.map(|((hash, insertion), result)| {
+				if insertion == Insertion::Inserted && evicted.contains(hash) {
+					Err(TxPoolError::ImmediatelyDropped.into())
+				} else {
+					result
+				}
+			})
+			.collect::<Vec<_>>();
+
+		let mut listener = self.listener.lock();
+		for hash in results.iter().filter_map(|r| r.as_ref().ok()) {
+			if reporting.is_ready(hash) {
+				listener.ready(hash, None);
+				self.import_notification_sinks.lock().retain_mut(|sink| {
+					match sink.try_send(*hash) {
+						Ok(()) => true,
+						Err(e) =>
+							if e.is_full() {
+								log::warn!(
+									target: LOG_TARGET,
+									"[{:?}] Trying to notify an import but the channel is full",
+									hash,
+								);
+								true
+							} else {
+								false
+							},
+					}
+				});
+			} else {
+				listener.future(hash);
+			}
+		}
+
+		Ok(results)
+	}
+
+	/// Remove the given transactions from the mempool and all views.
+	///
+	/// Returns the transactions removed from the best view.
+	pub(super) fn remove_invalid(
+		&self,
+		hashes: &[ExtrinsicHash<ChainApi>],
+	) -> Vec<TransactionFor<ChainApi>> {
+		let best = self.best.read().at.hash;
+		let mut removed = Vec::new();
+		for (at, view) in self.views.read().iter() {
+			let view_removed = view.pool.validated_pool().remove_invalid(hashes);
+			if *at == best {
+				removed = view_removed;
+			}
+		}
+		self.remove_from_mempool(hashes);
+		removed
+	}
+
+	/// Evict the lowest priority transactions from the mempool and all views until the mempool
+	/// is within its limit.
+	///
+	/// The watchers of the evicted transactions are notified, except for the ones of
+	/// `submitted`, which are expected to be reported to the submitter. Returns all evicted
+	/// transactions.
+	pub(super) fn enforce_limit(
+		&self,
+		submitted: &[ExtrinsicHash<ChainApi>],
+	) -> Vec<ExtrinsicHash<ChainApi>> {
+		let evicted = self.mempool.enforce_limit();
+		if evicted.is_empty() {
+			return evicted
+		}
+
+		for view in self.views.read().values() {
+			view.pool.validated_pool().remove_invalid(&evicted);
+		}
+		let mut listener = self.listener.lock();
+		for hash in evicted.iter().filter(|hash| !submitted.contains(hash)) {
+			listener.evicted(hash);
+		}
+		evicted
+	}
+
+	/// Remove the transactions that are outdated once the given blocks are finalized from the
+	/// mempool and all views, notifying their watchers.
+	pub(super) fn remove_outdated(
+		&self,
+		finalized_number: NumberFor<ChainApi>,
+		finalized: &HashSet<BlockHash<ChainApi>>,
+	) {
+		let outdated = self.mempool.remove_outdated(finalized_number, finalized);
+		if outdated.is_empty() {
+			return
+		}
+
+		log::debug!(
+			target: LOG_TARGET,
+			"Removing {} outdated transactions from the mempool",
+			outdated.len(),
+		);
+		for view in self.views.read().values() {
+			view.pool.validated_pool().remove_invalid(&outdated);
+		}
+		let mut listener = self.listener.lock();
+		for hash in &outdated {
+			listener.invalid(hash);
+		}
+	}
+
+	/// Remove the given transactions from the mempool, notifying their watchers.
+	fn remove_from_mempool(&self, hashes: &[ExtrinsicHash<ChainApi>]) {
+		let removed = self.mempool.remove(hashes);
+		let mut listener = self.listener.lock();
+		for hash in &removed {
+			listener.invalid(hash);
+		}
+	}
+
+	/// Returns a stream of transactions that became ready in the best view.
+	pub(super) fn import_notification_stream(&self) -> EventStream<ExtrinsicHash<ChainApi>> {
+		let (sink, stream) = channel(IMPORT_NOTIFICATION_BUFFER_SIZE);
+		self.import_notification_sinks.lock().push(sink);
+		stream
+	}
+}
+
+/// Create an [`IsValidator`] for a view, sharing the closure of the pool.
+fn shared_validator(is_validator: &Arc<IsValidator>) -> IsValidator {
+	let is_validator = is_validator.clone();
+	IsValidator::from(Box::new(move || is_validator.is_validator()) as Box<_>)
+}
@@ -20,7 +20,12 @@
 //!
 //! For a more full-featured pool, have a look at the `pool` module.
 
-use std::{cmp::Ordering, collections::HashSet, fmt, hash, sync::Arc};
+use std::{
+	cmp::Ordering,
+	collections::{HashMap, HashSet},
+	fmt, hash,
+	sync::Arc,
+};
 
 use crate::LOG_TARGET;
 use log::{debug, trace, warn};
@@ -83,6 +88,31 @@ pub struct PruneStatus<Hash, Ex> {
 	pub pruned: Vec<Arc<Transaction<Hash, Ex>>>,
 }
 
+/// Returns the encoded account of a transaction whose tags are the ones of FRAME's `CheckNonce`.
+///
+/// `CheckNonce` provides `(who, nonce)` and requires `(who, nonce - 1)`, with the nonce encoded
+/// as a little endian `u32` or `u64`. Only a transaction with exactly one tag of each kind that
+/// follows this layout is recognised, so the first transaction of an account, which requires no
+/// tag, has no known sender.
+pub fn nonce_tag_sender(requires: &[Tag], provides: &[Tag]) -> Option<Vec<u8>> {
+	let ([required], [provided]) = (requires, provides) else { return None };
+	if required.len() != provided.len() {
+		return None
+	}
+
+	[4, 8].into_iter().filter(|width| provided.len() > *width).find_map(|width| {
+		let split = provided.len() - width;
+		let nonce = |tag: &[u8]| {
+			let mut nonce = [0u8; 8];
+			nonce[..width].copy_from_slice(&tag[split..]);
+			u64::from_le_bytes(nonce)
+		};
+		(required[..split] == provided[..split] &&
+			nonce(required).checked_add(1) == Some(nonce(provided)))
+		.then(|| provided[..split].to_vec())
+	})
+}
+
 /// Immutable transaction
 #[derive(PartialEq, Eq, Clone)]
 pub struct Transaction<Hash, Extrinsic> {
@@ -104,6 +134,11 @@ pub struct Transaction<Hash, Extrinsic> {
 	pub propagate: bool,
 	/// Source of that transaction.
 	pub source: Source,
+	/// Encoded identity of the account that sent the transaction, if known.
+	///
+	/// Set by the pool from [`ChainApi::transaction_sender`](super::ChainApi::transaction_sender)
+	/// when the transaction is imported.
+	pub sender: Option<Vec<u8>>,
 }
 
 impl<Hash, Extrinsic> AsRef<Extrinsic> for Transaction<Hash, Extrinsic> {
@@ -162,8 +197,62 @@ impl<Hash: Clone, Extrinsic: Clone> Transaction<Hash, Extrinsic> {
 			requires: self.requires.clone(),
 			provides: self.provides.clone(),
 			propagate: self.propagate,
+			sender: self.sender.clone(),
+		}
+	}
+}
+
+/// Transactions of every known sender in a queue.
+#[derive(Debug)]
+pub struct Senders<Hash> {
+	/// Transactions of every sender, in the order they were added to the queue.
+	transactions: HashMap<Vec<u8>, Vec<Hash>>,
+}
+
+impl<Hash> Default for Senders<Hash> {
+	fn default() -> Self {
+		Self { transactions: Default::default() }
+	}
+}
+
+impl<Hash: Clone + PartialEq> Senders<Hash> {
+	/// Adds a transaction to the transactions of its sender, if known.
+	pub fn insert<Ex>(&mut self, tx: &Transaction<Hash, Ex>) {
+		if let Some(sender) = &tx.sender {
+			self.transactions.entry(sender.clone()).or_default().push(tx.hash.clone());
 		}
 	}
+
+	/// Removes a transaction from the transactions of its sender.
+	pub fn remove<Ex>(&mut self, tx: &Transaction<Hash, Ex>) {
+		let Some(sender) = &tx.sender else { return };
+		if let Some(transactions) = self.transactions.get_mut(sender) {
+			transactions.retain(|hash| *hash != tx.hash);
+			if transactions.is_empty() {
+				self.transactions.remove(sender);
+			}
+		}
+	}
+
+	/// Removes all transactions.
+	pub fn clear(&mut self) {
+		self.transactions.clear();
+	}
+
+	/// Returns the number of transactions of the given sender.
+	pub fn count(&self, sender: &[u8]) -> usize {
+		self.transactions.get(sender).map_or(0, Vec::len)
+	}
+
+	/// Returns the most recently added transaction of every sender with more than `limit`
+	/// transactions.
+	pub fn newest_over_limit(&self, limit: usize) -> Vec<Hash> {
+		self.transactions
+			.values()
+			.filter(|transactions| transactions.len() > limit)
+			.filter_map(|transactions| transactions.last().cloned())
+			.collect()
+	}
 }
 
 impl<Hash, Extrinsic> fmt::Debug for Transaction<Hash, Extrinsic>
@@ -425,11 +514,16 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		}
 
 		while future.is_exceeded(self.future.len(), self.future.bytes()) {
-			// find the worst transaction
+			// find the worst transaction, preferring to keep higher priority and newer ones
 			let worst = self.future.fold(|worst, current| match worst {
 				None => Some(current.clone()),
-				Some(ref tx) if tx.imported_at > current.imported_at => Some(current.clone()),
-				other => other,
+				Some(ref tx) => match tx.transaction.priority.cmp(&current.transaction.priority) {
+					Ordering::Less => worst,
+					Ordering::Equal if tx.imported_at > current.imported_at =>
+						Some(current.clone()),
+					Ordering::Equal => worst,
+					Ordering::Greater => Some(current.clone()),
+				},
 			});
 
 			if let Some(worst) = worst {
@@ -442,6 +536,41 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		removed
 	}
 
+	/// Makes sure that no sender has more transactions in either queue than the given limits.
+	///
+	/// Senders are identified by [`Transaction::sender`]. The most recent transactions of a
+	/// sender that exceeds a limit are removed (together with their dependencies) first.
+	/// Returns all removed transactions.
+	pub fn enforce_sender_limits(
+		&mut self,
+		ready: Option<usize>,
+		future: Option<usize>,
+	) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let mut removed = vec![];
+
+		if let Some(limit) = ready {
+			loop {
+				let to_remove = self.ready.senders().newest_over_limit(limit);
+				if to_remove.is_empty() {
+					break
+				}
+				removed.append(&mut self.remove_subtree(&to_remove));
+			}
+		}
+
+		if let Some(limit) = future {
+			loop {
+				let to_remove = self.future.senders().newest_over_limit(limit);
+				if to_remove.is_empty() {
+					break
+				}
+				removed.append(&mut self.remove_subtree(&to_remove));
+			}
+		}
+
+		removed
+	}
+
 	/// Removes all transactions represented by the hashes and all other transactions
 	/// that depend on them.
 	///
@@ -549,6 +678,7 @@ mod tests {
 		provides: vec![],
 		propagate: true,
 		source: Source::External,
+		sender: None,
 	};
 
 	#[test]
@@ -1042,4 +1172,182 @@ source: TransactionSource::External, requires: [03, 02], provides: [04], data: [
 		assert_eq!(pool.reject_future_transactions, true);
 		assert_eq!(pool.future.len(), 1);
 	}
+
+	#[test]
+	fn should_recognise_nonce_tags() {
+		let tag = |who: u8, nonce: u32| {
+			let mut tag = vec![who; 32];
+			tag.extend(nonce.to_le_bytes());
+			tag
+		};
+		let long_tag = |who: u8, nonce: u64| {
+			let mut tag = vec![who; 32];
+			tag.extend(nonce.to_le_bytes());
+			tag
+		};
+
+		assert_eq!(nonce_tag_sender(&[tag(7, 255)], &[tag(7, 256)]), Some(vec![7; 32]));
+		assert_eq!(nonce_tag_sender(&[long_tag(7, 0)], &[long_tag(7, 1)]), Some(vec![7; 32]));
+		// the first transaction of an account requires no tag
+		assert_eq!(nonce_tag_sender(&[], &[tag(7, 0)]), None);
+		// the accounts differ
+		assert_eq!(nonce_tag_sender(&[tag(7, 0)], &[tag(8, 1)]), None);
+		// the nonces are not consecutive
+		assert_eq!(nonce_tag_sender(&[tag(7, 0)], &[tag(7, 2)]), None);
+		assert_eq!(nonce_tag_sender(&[tag(7, 1)], &[tag(7, 0)]), None);
+		// more than one tag
+		assert_eq!(nonce_tag_sender(&[tag(7, 0)], &[tag(7, 1), tag(7, 2)]), None);
+	}
+
+	#[test]
+	fn should_not_limit_unsigned_transactions_with_tags_of_the_same_length() {
+		// given
+		let mut pool = pool();
+		let tag = |who: u8, nonce: u32| {
+			let mut tag = vec![who; 32];
+			tag.extend(nonce.to_le_bytes());
+			tag
+		};
+		// A signed transaction, followed by unsigned transactions whose tags have the same
+		// length and share a prefix.
+		for (hash, requires, provides) in [
+			(1u64, vec![tag(7, 0)], tag(7, 1)),
+			(2, vec![], tag(9, 0)),
+			(3, vec![], tag(9, 1)),
+			(4, vec![], tag(9, 2)),
+			(5, vec![tag(9, 0)], tag(9, 5)),
+		] {
+			let sender = nonce_tag_sender(&requires, &[provides.clone()]);
+			pool.import(Transaction {
+				data: vec![hash as u8],
+				hash,
+				requires,
+				provides: vec![provides],
+				sender,
+				..DEFAULT_TX.clone()
+			})
+			.unwrap();
+		}
+		assert_eq!(pool.ready.len(), 4);
+		assert_eq!(pool.future.len(), 1);
+
+		// when
+		let removed = pool.enforce_sender_limits(Some(1), Some(1));
+
+		// then
+		let sender = |hash| pool.by_hashes(&[hash])[0].as_ref().unwrap().sender.clone();
+		assert_eq!(sender(1), Some(vec![7; 32]));
+		for hash in 2..=5 {
+			assert_eq!(sender(hash), None);
+		}
+		assert!(removed.is_empty());
+		assert_eq!(pool.ready.len(), 4);
+		assert_eq!(pool.future.len(), 1);
+	}
+
+	#[test]
+	fn should_track_transactions_of_every_sender() {
+		// given
+		let mut pool = pool();
+		pool.import(Transaction {
+			hash: 1,
+			provides: vec![vec![1]],
+			sender: Some(vec![7]),
+			..DEFAULT_TX.clone()
+		})
+		.unwrap();
+		pool.import(Transaction {
+			hash: 2,
+			requires: vec![vec![1]],
+			provides: vec![vec![2]],
+			sender: Some(vec![7]),
+			..DEFAULT_TX.clone()
+		})
+		.unwrap();
+		pool.import(Transaction { hash: 3, provides: vec![vec![3]], ..DEFAULT_TX.clone() })
+			.unwrap();
+		assert_eq!(pool.ready.senders().count(&[7]), 2);
+
+		// when
+		pool.remove_subtree(&[1, 3]);
+
+		// then
+		assert_eq!(pool.ready.senders().count(&[7]), 0);
+	}
+
+	#[test]
+	fn should_enforce_sender_limits() {
+		// given
+		let mut pool = pool();
+		pool.import(Transaction {
+			data: vec![1u8],
+			hash: 1,
+			provides: vec![vec![1, 0]],
+			sender: Some(vec![1]),
+			..DEFAULT_TX.clone()
+		})
+		.unwrap();
+		for nonce in 1..4u8 {
+			pool.import(Transaction {
+				data: vec![1u8, nonce],
+				hash: 1 + nonce as u64,
+				requires: vec![vec![1, nonce - 1]],
+				provides: vec![vec![1, nonce]],
+				sender: Some(vec![1]),
+				..DEFAULT_TX.clone()
+			})
+			.unwrap();
+		}
+		for nonce in 1..3u8 {
+			pool.import(Transaction {
+				data: vec![2u8, nonce],
+				hash: 10 + nonce as u64,
+				requires: vec![vec![2, nonce - 1]],
+				provides: vec![vec![2, nonce]],
+				sender: Some(vec![2]),
+				..DEFAULT_TX.clone()
+			})
+			.unwrap();
+		}
+		assert_eq!(pool.ready.len(), 4);
+		assert_eq!(pool.future.len(), 2);
+
+		// when
+		let removed = pool.enforce_sender_limits(Some(2), Some(1));
+
+		// then
+		let removed = removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>();
+		assert_eq!(removed.len(), 2);
+		assert!(removed.contains(&4));
+		assert_eq!(pool.ready.len(), 3);
+		assert_eq!(pool.future.len(), 1);
+		assert_eq!(pool.ready.senders().count(&[1]), 2);
+		assert_eq!(pool.future.senders().count(&[2]), 1);
+	}
+
+	#[test]
+	fn should_evict_lowest_priority_future_transactions() {
+		// given
+		let mut pool = pool();
+		for (hash, priority) in [(1u64, 5u64), (2, 1), (3, 5)] {
+			pool.import(Transaction {
+				data: vec![hash as u8],
+				hash,
+				priority,
+				requires: vec![vec![hash as u8]],
+				..DEFAULT_TX.clone()
+			})
+			.unwrap();
+		}
+		assert_eq!(pool.future.len(), 3);
+
+		// when
+		let limit = Limit { count: 2, total_bytes: 1000 };
+		let removed = pool.enforce_limits(&limit, &limit);
+
+		// then
+		assert_eq!(removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2]);
+		assert!(pool.future.contains(&1));
+		assert!(pool.future.contains(&3));
+	}
 }
@@ -96,6 +96,18 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 		})
 	}
 
+	/// Transaction was evicted from the full pool in favour of a higher priority one.
+	pub fn evicted(&mut self, tx: &H) {
+		trace!(target: LOG_TARGET, "[{:?}] Evicted", tx);
+		self.fire(tx, |watcher| watcher.evicted());
+	}
+
+	/// Transaction was dropped from the pool because its sender has too many transactions.
+	pub fn sender_limit_reached(&mut self, tx: &H) {
+		trace!(target: LOG_TARGET, "[{:?}] Dropped (sender limit reached)", tx);
+		self.fire(tx, |watcher| watcher.sender_limit_reached());
+	}
+
 	/// Transaction was removed as invalid.
 	pub fn invalid(&mut self, tx: &H) {
 		debug!(target: LOG_TARGET, "[{:?}] Extrinsic invalid", tx);
@@ -27,7 +27,7 @@
 #![warn(unused_extern_crates)]
 
 mod future;
-mod listener;
+pub(crate) mod listener;
 mod pool;
 mod ready;
 mod rotator;
@@ -18,7 +18,7 @@
 
 use std::{collections::HashMap, sync::Arc, time::Duration};
 
-use crate::LOG_TARGET;
+use crate::{journal::JournalOptions, metrics::MetricsLink, LOG_TARGET};
 use futures::{channel::mpsc::Receiver, Future};
 use sc_transaction_pool_api::error;
 use sp_blockchain::TreeRoute;
@@ -106,6 +106,21 @@ pub trait ChainApi: Send + Sync {
 		from: <Self::Block as BlockT>::Hash,
 		to: <Self::Block as BlockT>::Hash,
 	) -> Result<TreeRoute<Self::Block>, Self::Error>;
+
+	/// Returns the hash of the best block of the chain.
+	fn best_block_hash(&self) -> Result<<Self::Block as BlockT>::Hash, Self::Error>;
+
+	/// Returns the encoded identity of the account that sent the validated transaction.
+	///
+	/// The per-sender limits of [`Options`] only apply to transactions with a known sender. By
+	/// default the sender is only known for the tags of FRAME's `CheckNonce`, see
+	/// [`base::nonce_tag_sender`].
+	fn transaction_sender(
+		&self,
+		tx: &base::Transaction<ExtrinsicHash<Self>, ExtrinsicFor<Self>>,
+	) -> Option<Vec<u8>> {
+		base::nonce_tag_sender(&tx.requires, &tx.provides)
+	}
 }
 
 /// Pool configuration options.
@@ -115,10 +130,20 @@ pub struct Options {
 	pub ready: base::Limit,
 	/// Future queue limits.
 	pub future: base::Limit,
+	/// Maximal number of ready transactions of a single sender (`None` for unlimited).
+	///
+	/// Senders are reported by [`ChainApi::transaction_sender`].
+	pub ready_per_sender: Option<usize>,
+	/// Maximal number of future transactions of a single sender (`None` for unlimited).
+	///
+	/// Senders are reported by [`ChainApi::transaction_sender`].
+	pub future_per_sender: Option<usize>,
 	/// Reject future transactions.
 	pub reject_future_transactions: bool,
 	/// How long the extrinsic is banned for.
 	pub ban_time: Duration,
+	/// On-disk journal that keeps the transactions across restarts (disabled if `None`).
+	pub journal: Option<JournalOptions>,
 }
 
 impl Default for Options {
@@ -126,8 +151,11 @@ impl Default for Options {
 		Self {
 			ready: base::Limit { count: 8192, total_bytes: 20 * 1024 * 1024 },
 			future: base::Limit { count: 512, total_bytes: 1 * 1024 * 1024 },
+			ready_per_sender: None,
+			future_per_sender: None,
 			reject_future_transactions: false,
 			ban_time: Duration::from_secs(60 * 30),
+			journal: None,
 		}
 	}
 }
@@ -151,6 +179,23 @@ impl<B: ChainApi> Pool<B> {
 		Self { validated_pool: Arc::new(ValidatedPool::new(options, is_validator, api)) }
 	}
 
+	/// Create a new transaction pool reporting to the given metrics.
+	pub(crate) fn new_with_metrics(
+		options: Options,
+		is_validator: IsValidator,
+		api: Arc<B>,
+		metrics: MetricsLink,
+	) -> Self {
+		Self {
+			validated_pool: Arc::new(ValidatedPool::new_with_metrics(
+				options,
+				is_validator,
+				api,
+				metrics,
+			)),
+		}
+	}
+
 	/// Imports a bunch of unverified extrinsics to the pool
 	pub async fn submit_at(
 		&self,
@@ -1008,9 +1053,10 @@ mod tests {
 				assert_eq!(pool.validated_pool().status().ready, 2);
 
 				// then
+				// the transfer made room for a higher priority transaction
 				let mut stream = futures::executor::block_on_stream(watcher.into_stream());
 				assert_eq!(stream.next(), Some(TransactionStatus::Ready));
-				assert_eq!(stream.next(), Some(TransactionStatus::Dropped));
+				assert_eq!(stream.next(), Some(TransactionStatus::Evicted));
 			}
 		}
 
@@ -127,6 +127,7 @@ mod tests {
 			provides: vec![],
 			propagate: true,
 			source: TransactionSource::External,
+			sender: None,
 		};
 
 		(hash, tx)
@@ -193,6 +194,7 @@ mod tests {
 				provides: vec![],
 				propagate: true,
 				source: TransactionSource::External,
+				sender: None,
 			}
 		}
 
@@ -22,7 +22,7 @@ use std::{
 	sync::Arc,
 };
 
-use crate::LOG_TARGET;
+use crate::{metrics::MetricsLink, LOG_TARGET};
 use futures::channel::mpsc::{channel, Sender};
 use parking_lot::{Mutex, RwLock};
 use sc_transaction_pool_api::{error, PoolStatus, ReadyTransactions};
@@ -77,6 +77,8 @@ impl<Hash, Ex, Error> ValidatedTransaction<Hash, Ex, Error> {
 			provides: validity.provides,
 			propagate: validity.propagate,
 			valid_till: at.saturated_into::<u64>().saturating_add(validity.longevity),
+			// set by the pool on import
+			sender: None,
 		})
 	}
 }
@@ -100,6 +102,13 @@ impl From<Box<dyn Fn() -> bool + Send + Sync>> for IsValidator {
 	}
 }
 
+impl IsValidator {
+	/// Returns true if the local node is currently a validator.
+	pub(crate) fn is_validator(&self) -> bool {
+		(self.0)()
+	}
+}
+
 /// Pool that deals with validated transactions.
 pub struct ValidatedPool<B: ChainApi> {
 	api: Arc<B>,
@@ -109,11 +118,22 @@ pub struct ValidatedPool<B: ChainApi> {
 	pub(crate) pool: RwLock<base::BasePool<ExtrinsicHash<B>, ExtrinsicFor<B>>>,
 	import_notification_sinks: Mutex<Vec<Sender<ExtrinsicHash<B>>>>,
 	rotator: PoolRotator<ExtrinsicHash<B>>,
+	metrics: MetricsLink,
 }
 
 impl<B: ChainApi> ValidatedPool<B> {
 	/// Create a new transaction pool.
 	pub fn new(options: Options, is_validator: IsValidator, api: Arc<B>) -> Self {
+		Self::new_with_metrics(options, is_validator, api, Default::default())
+	}
+
+	/// Create a new transaction pool reporting to the given metrics.
+	pub(crate) fn new_with_metrics(
+		options: Options,
+		is_validator: IsValidator,
+		api: Arc<B>,
+		metrics: MetricsLink,
+	) -> Self {
 		let base_pool = base::BasePool::new(options.reject_future_transactions);
 		let ban_time = options.ban_time;
 		Self {
@@ -124,6 +144,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 			pool: RwLock::new(base_pool),
 			import_notification_sinks: Default::default(),
 			rotator: PoolRotator::new(ban_time),
+			metrics,
 		}
 	}
 
@@ -167,11 +188,10 @@ impl<B: ChainApi> ValidatedPool<B> {
 			.collect::<Vec<_>>();
 
 		// only enforce limits if there is at least one imported transaction
-		let removed = if results.iter().any(|res| res.is_ok()) {
-			self.enforce_limits()
-		} else {
-			Default::default()
-		};
+		let imported =
+			results.iter().filter_map(|res| res.as_ref().ok().copied()).collect::<Vec<_>>();
+		let removed =
+			if !imported.is_empty() { self.enforce_limits(&imported) } else { Default::default() };
 
 		results
 			.into_iter()
@@ -186,11 +206,13 @@ impl<B: ChainApi> ValidatedPool<B> {
 	/// Submit single pre-validated transaction to the pool.
 	fn submit_one(&self, tx: ValidatedTransactionFor<B>) -> Result<ExtrinsicHash<B>, B::Error> {
 		match tx {
-			ValidatedTransaction::Valid(tx) => {
+			ValidatedTransaction::Valid(mut tx) => {
 				if !tx.propagate && !(self.is_validator.0)() {
 					return Err(error::Error::Unactionable.into())
 				}
 
+				tx.sender = self.api.transaction_sender(&tx);
+
 				let imported = self.pool.write().import(tx)?;
 
 				if let base::Imported::Ready { ref hash, .. } = imported {
@@ -226,50 +248,91 @@ impl<B: ChainApi> ValidatedPool<B> {
 		}
 	}
 
-	fn enforce_limits(&self) -> HashSet<ExtrinsicHash<B>> {
+	fn enforce_limits(&self, imported: &[ExtrinsicHash<B>]) -> HashSet<ExtrinsicHash<B>> {
 		let status = self.pool.read().status();
 		let ready_limit = &self.options.ready;
 		let future_limit = &self.options.future;
+		let ready_per_sender = self.options.ready_per_sender;
+		let future_per_sender = self.options.future_per_sender;
 
 		log::debug!(target: LOG_TARGET, "Pool Status: {:?}", status);
-		if ready_limit.is_exceeded(status.ready, status.ready_bytes) ||
-			future_limit.is_exceeded(status.future, status.future_bytes)
-		{
-			log::debug!(
-				target: LOG_TARGET,
-				"Enforcing limits ({}/{}kB ready, {}/{}kB future",
-				ready_limit.count,
-				ready_limit.total_bytes / 1024,
-				future_limit.count,
-				future_limit.total_bytes / 1024,
-			);
+		let exceeded = ready_limit.is_exceeded(status.ready, status.ready_bytes) ||
+			future_limit.is_exceeded(status.future, status.future_bytes);
+		if !exceeded && ready_per_sender.is_none() && future_per_sender.is_none() {
+			return Default::default()
+		}
 
-			// clean up the pool
-			let removed = {
-				let mut pool = self.pool.write();
-				let removed = pool
+		// clean up the pool
+		let (sender_limited, evicted, dropped) = {
+			let mut pool = self.pool.write();
+			let sender_limited = pool
+				.enforce_sender_limits(ready_per_sender, future_per_sender)
+				.into_iter()
+				.map(|x| x.hash)
+				.collect::<Vec<_>>();
+
+			let (evicted, dropped) = if exceeded {
+				log::debug!(
+					target: LOG_TARGET,
+					"Enforcing limits ({}/{}kB ready, {}/{}kB future",
+					ready_limit.count,
+					ready_limit.total_bytes / 1024,
+					future_limit.count,
+					future_limit.total_bytes / 1024,
+				);
+
+				// Transactions making room for a better one that was just imported are evicted,
+				// all the others are simply dropped because the pool is full.
+				let best_imported =
+					pool.by_hashes(imported).into_iter().flatten().map(|tx| tx.priority).max();
+				let (evicted, dropped): (Vec<_>, Vec<_>) = pool
 					.enforce_limits(ready_limit, future_limit)
 					.into_iter()
-					.map(|x| x.hash)
-					.collect::<HashSet<_>>();
-				// ban all removed transactions
-				self.rotator.ban(&Instant::now(), removed.iter().copied());
-				removed
+					.partition(|tx| best_imported.map_or(false, |best| tx.priority < best));
+				(
+					evicted.into_iter().map(|x| x.hash).collect::<Vec<_>>(),
+					dropped.into_iter().map(|x| x.hash).collect::<Vec<_>>(),
+				)
+			} else {
+				Default::default()
 			};
-			if !removed.is_empty() {
-				log::debug!(target: LOG_TARGET, "Enforcing limits: {} dropped", removed.len());
-			}
 
-			// run notifications
-			let mut listener = self.listener.write();
-			for h in &removed {
-				listener.dropped(h, None);
-			}
+			// ban all removed transactions
+			self.rotator.ban(
+				&Instant::now(),
+				sender_limited.iter().chain(&evicted).chain(&dropped).copied(),
+			);
+			(sender_limited, evicted, dropped)
+		};
+		if !sender_limited.is_empty() || !evicted.is_empty() || !dropped.is_empty() {
+			log::debug!(
+				target: LOG_TARGET,
+				"Enforcing limits: {} dropped, {} evicted, {} over sender limit",
+				dropped.len(),
+				evicted.len(),
+				sender_limited.len(),
+			);
+		}
 
-			removed
-		} else {
-			Default::default()
+		self.metrics.report(|metrics| {
+			metrics.dropped_transactions.inc_by(dropped.len() as u64);
+			metrics.evicted_transactions.inc_by(evicted.len() as u64);
+			metrics.sender_limited_transactions.inc_by(sender_limited.len() as u64);
+		});
+
+		// run notifications
+		let mut listener = self.listener.write();
+		for h in &sender_limited {
+			listener.sender_limit_reached(h);
 		}
+		for h in &evicted {
+			listener.evicted(h);
+		}
+		for h in &dropped {
+			listener.dropped(h, None);
+		}
+
+		sender_limited.into_iter().chain(evicted).chain(dropped).collect()
 	}
 
 	/// Import a single extrinsic and starts to watch their progress in the pool.
@@ -364,37 +427,40 @@ impl<B: ChainApi> ValidatedPool<B> {
 				let mut final_statuses = HashMap::new();
 				for (hash, tx_to_resubmit) in txs_to_resubmit {
 					match tx_to_resubmit {
-						ValidatedTransaction::Valid(tx) => match pool.import(tx) {
-							Ok(imported) => match imported {
-								base::Imported::Ready { promoted, failed, removed, .. } => {
-									final_statuses.insert(hash, Status::Ready);
-									for hash in promoted {
+						ValidatedTransaction::Valid(mut tx) => {
+							tx.sender = self.api.transaction_sender(&tx);
+							match pool.import(tx) {
+								Ok(imported) => match imported {
+									base::Imported::Ready { promoted, failed, removed, .. } => {
 										final_statuses.insert(hash, Status::Ready);
-									}
-									for hash in failed {
-										final_statuses.insert(hash, Status::Failed);
-									}
-									for tx in removed {
-										final_statuses.insert(tx.hash, Status::Dropped);
-									}
+										for hash in promoted {
+											final_statuses.insert(hash, Status::Ready);
+										}
+										for hash in failed {
+											final_statuses.insert(hash, Status::Failed);
+										}
+										for tx in removed {
+											final_statuses.insert(tx.hash, Status::Dropped);
+										}
+									},
+									base::Imported::Future { .. } => {
+										final_statuses.insert(hash, Status::Future);
+									},
 								},
-								base::Imported::Future { .. } => {
-									final_statuses.insert(hash, Status::Future);
+								Err(err) => {
+									// we do not want to fail if single transaction import has
+									// failed nor we do want to propagate this error,
+									// because it could tx unknown to caller => let's just
+									// notify listeners (and issue debug message)
+									log::warn!(
+										target: LOG_TARGET,
+										"[{:?}] Removing invalid transaction from update: {}",
+										hash,
+										err,
+									);
+									final_statuses.insert(hash, Status::Failed);
 								},
-							},
-							Err(err) => {
-								// we do not want to fail if single transaction import has failed
-								// nor we do want to propagate this error, because it could tx
-								// unknown to caller => let's just notify listeners (and issue debug
-								// message)
-								log::warn!(
-									target: LOG_TARGET,
-									"[{:?}] Removing invalid transaction from update: {}",
-									hash,
-									err,
-								);
-								final_statuses.insert(hash, Status::Failed);
-							},
+							}
 						},
 						ValidatedTransaction::Invalid(_, _) |
 						ValidatedTransaction::Unknown(_, _) => {
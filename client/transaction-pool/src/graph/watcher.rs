@@ -118,6 +118,18 @@ impl<H: Clone, BH: Clone> Sender<H, BH> {
 		self.is_finalized = true;
 	}
 
+	/// Transaction has been evicted from the full pool by a higher priority transaction.
+	pub fn evicted(&mut self) {
+		self.send(TransactionStatus::Evicted);
+		self.is_finalized = true;
+	}
+
+	/// Transaction has been dropped from the pool because of the per-sender limit.
+	pub fn sender_limit_reached(&mut self) {
+		self.send(TransactionStatus::SenderLimitReached);
+		self.is_finalized = true;
+	}
+
 	/// The extrinsic has been broadcast to the given peers.
 	pub fn broadcast(&mut self, peers: Vec<String>) {
 		self.send(TransactionStatus::Broadcast(peers))
@@ -0,0 +1,225 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! On-disk journal of the pool transactions.
+//!
+//! The journal allows the pool to survive node restarts. The transactions are stored as opaque
+//! encoded extrinsics together with their source, so that entries that can't be decoded after a
+//! runtime upgrade are simply skipped. Restored transactions go through the usual validation
+//! before they enter the pool again.
+
+use std::{
+	fs,
+	io::{self, Write},
+	path::PathBuf,
+	sync::atomic::{AtomicBool, Ordering},
+	time::{Duration, Instant},
+};
+
+use codec::{Decode, Encode};
+use parking_lot::Mutex;
+use sp_runtime::transaction_validity::TransactionSource;
+
+use crate::LOG_TARGET;
+
+/// How often the journal is written while the node is running.
+const STORE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Version of the journal format.
+const JOURNAL_VERSION: u8 = 1;
+
+/// Transaction pool journal configuration.
+#[derive(Debug, Clone)]
+pub struct JournalOptions {
+	/// Path of the journal file.
+	pub path: PathBuf,
+	/// Maximal size of all transactions stored in the journal.
+	pub max_bytes: usize,
+}
+
+/// A single journal entry.
+#[derive(Debug, PartialEq, Encode, Decode)]
+pub(crate) struct JournalEntry {
+	/// Source the transaction was originally submitted from.
+	pub source: TransactionSource,
+	/// The encoded extrinsic.
+	pub data: Vec<u8>,
+}
+
+/// Transaction pool journal.
+pub(crate) struct Journal {
+	options: JournalOptions,
+	restored: AtomicBool,
+	last_stored: Mutex<Instant>,
+}
+
+impl Journal {
+	/// Create a new journal with the given options.
+	pub fn new(options: JournalOptions) -> Self {
+		Self { options, restored: AtomicBool::new(false), last_stored: Mutex::new(Instant::now()) }
+	}
+
+	/// Returns the journalled transactions if they haven't been restored yet.
+	///
+	/// The journal is only restored once, all subsequent calls return nothing.
+	pub fn restore(&self) -> Vec<JournalEntry> {
+		if self.restored.swap(true, Ordering::SeqCst) {
+			return Vec::new()
+		}
+
+		match self.load() {
+			Ok(entries) => entries,
+			Err(e) => {
+				log::warn!(
+					target: LOG_TARGET,
+					"Failed to load transaction pool journal from {}: {}",
+					self.options.path.display(),
+					e,
+				);
+				Vec::new()
+			},
+		}
+	}
+
+	/// Returns true if the journal has already been restored.
+	pub fn is_restored(&self) -> bool {
+		self.restored.load(Ordering::SeqCst)
+	}
+
+	/// Returns true if the journal should be written again.
+	pub fn should_store(&self) -> bool {
+		self.last_stored.lock().elapsed() >= STORE_INTERVAL
+	}
+
+	/// Write the given transactions to the journal, replacing the previous content.
+	///
+	/// Transactions are stored in the given order until the configured size limit is reached.
+	/// Nothing is written before the journal has been restored, so that a node stopped right
+	/// after startup doesn't lose the previously journalled transactions.
+	pub fn store(&self, entries: impl IntoIterator<Item = JournalEntry>) {
+		if !self.restored.load(Ordering::SeqCst) {
+			return
+		}
+		*self.last_stored.lock() = Instant::now();
+
+		let mut bytes = 0;
+		let entries = entries
+			.into_iter()
+			.take_while(|entry| {
+				bytes += entry.data.len();
+				bytes <= self.options.max_bytes
+			})
+			.collect::<Vec<_>>();
+
+		match self.write(&entries) {
+			Ok(()) => log::debug!(
+				target: LOG_TARGET,
+				"Stored {} transactions in the journal",
+				entries.len(),
+			),
+			Err(e) => log::warn!(
+				target: LOG_TARGET,
+				"Failed to store transaction pool journal at {}: {}",
+				self.options.path.display(),
+				e,
+			),
+		}
+	}
+
+	fn load(&self) -> io::Result<Vec<JournalEntry>> {
+		let data = match fs::read(&self.options.path) {
+			Ok(data) => data,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(e) => return Err(e),
+		};
+
+		let (version, entries) = <(u8, Vec<JournalEntry>)>::decode(&mut &data[..])
+			.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+		if version != JOURNAL_VERSION {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidData,
+				format!("unsupported journal version {}", version),
+			))
+		}
+
+		Ok(entries)
+	}
+
+	fn write(&self, entries: &[JournalEntry]) -> io::Result<()> {
+		if let Some(parent) = self.options.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		// Write to a temporary file first, so that a crash can't leave a truncated journal.
+		let tmp_path = self.options.path.with_extension("tmp");
+		let mut file = fs::File::create(&tmp_path)?;
+		file.write_all(&(JOURNAL_VERSION, entries).encode())?;
+		file.sync_all()?;
+		fs::rename(tmp_path, &self.options.path)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entry(data: Vec<u8>) -> JournalEntry {
+		JournalEntry { source: TransactionSource::External, data }
+	}
+
+	#[test]
+	fn journal_roundtrip() {
+		let dir = tempfile::tempdir().unwrap();
+		let options = JournalOptions { path: dir.path().join("txpool.journal"), max_bytes: 1024 };
+
+		let journal = Journal::new(options.clone());
+		assert!(journal.restore().is_empty());
+		journal.store(vec![entry(vec![1, 2, 3]), entry(vec![4])]);
+
+		let journal = Journal::new(options);
+		assert_eq!(journal.restore(), vec![entry(vec![1, 2, 3]), entry(vec![4])]);
+		// only restored once
+		assert!(journal.restore().is_empty());
+	}
+
+	#[test]
+	fn journal_is_not_overwritten_before_restore() {
+		let dir = tempfile::tempdir().unwrap();
+		let options = JournalOptions { path: dir.path().join("txpool.journal"), max_bytes: 1024 };
+
+		let journal = Journal::new(options.clone());
+		journal.restore();
+		journal.store(vec![entry(vec![1])]);
+
+		Journal::new(options.clone()).store(vec![]);
+
+		assert_eq!(Journal::new(options).restore(), vec![entry(vec![1])]);
+	}
+
+	#[test]
+	fn journal_respects_size_limit() {
+		let dir = tempfile::tempdir().unwrap();
+		let options = JournalOptions { path: dir.path().join("txpool.journal"), max_bytes: 5 };
+
+		let journal = Journal::new(options.clone());
+		assert!(journal.restore().is_empty());
+		journal.store(vec![entry(vec![1, 2, 3]), entry(vec![4, 5]), entry(vec![6])]);
+
+		assert_eq!(Journal::new(options).restore(), vec![entry(vec![1, 2, 3]), entry(vec![4, 5])]);
+	}
+}
@@ -25,7 +25,9 @@
 mod api;
 mod enactment_state;
 pub mod error;
+mod fork_aware_txpool;
 mod graph;
+mod journal;
 mod metrics;
 mod revalidation;
 #[cfg(test)]
@@ -33,7 +35,9 @@ mod tests;
 
 pub use crate::api::FullChainApi;
 use async_trait::async_trait;
+use codec::{Decode, Encode};
 use enactment_state::{EnactmentAction, EnactmentState};
+pub use fork_aware_txpool::{ForkAwareFullPool, ForkAwareTxPool};
 use futures::{
 	channel::oneshot,
 	future::{self, ready},
@@ -42,6 +46,7 @@ use futures::{
 pub use graph::{
 	base_pool::Limit as PoolLimit, ChainApi, Options, Pool, Transaction, ValidatedTransaction,
 };
+pub use journal::JournalOptions;
 use parking_lot::Mutex;
 use std::{
 	collections::{HashMap, HashSet},
@@ -62,7 +67,10 @@ use sp_runtime::{
 };
 use std::time::Instant;
 
-use crate::metrics::MetricsLink as PrometheusMetrics;
+use crate::{
+	journal::{Journal, JournalEntry},
+	metrics::MetricsLink as PrometheusMetrics,
+};
 use prometheus_endpoint::Registry as PrometheusRegistry;
 
 use sp_blockchain::{HashAndNumber, TreeRoute};
@@ -93,6 +101,7 @@ where
 	ready_poll: Arc<Mutex<ReadyPoll<ReadyIteratorFor<PoolApi>, Block>>>,
 	metrics: PrometheusMetrics,
 	enactment_state: Arc<Mutex<EnactmentState<Block>>>,
+	journal: Option<Journal>,
 }
 
 struct ReadyPoll<T, Block: BlockT> {
@@ -180,6 +189,7 @@ where
 					best_block_hash,
 					finalized_hash,
 				))),
+				journal: None,
 			},
 			background_task,
 		)
@@ -198,7 +208,14 @@ where
 		best_block_hash: Block::Hash,
 		finalized_hash: Block::Hash,
 	) -> Self {
-		let pool = Arc::new(graph::Pool::new(options, is_validator, pool_api.clone()));
+		let metrics = PrometheusMetrics::new(prometheus);
+		let journal = options.journal.clone().map(Journal::new);
+		let pool = Arc::new(graph::Pool::new_with_metrics(
+			options,
+			is_validator,
+			pool_api.clone(),
+			metrics.clone(),
+		));
 		let (revalidation_queue, background_task) = match revalidation_type {
 			RevalidationType::Light =>
 				(revalidation::RevalidationQueue::new(pool_api.clone(), pool.clone()), None),
@@ -223,12 +240,65 @@ where
 				RevalidationType::Full => RevalidationStrategy::Always,
 			})),
 			ready_poll: Arc::new(Mutex::new(ReadyPoll::new(best_block_number))),
-			metrics: PrometheusMetrics::new(prometheus),
+			metrics,
 			enactment_state: Arc::new(Mutex::new(EnactmentState::new(
 				best_block_hash,
 				finalized_hash,
 			))),
+			journal,
+		}
+	}
+
+	/// Re-imports the journalled transactions at the best block of the chain.
+	///
+	/// Does nothing if the journal is disabled or was already restored.
+	async fn restore_journal(&self) {
+		let Some(journal) = &self.journal else { return };
+		if journal.is_restored() {
+			return
+		}
+
+		let at = match self.api.best_block_hash() {
+			Ok(at) => at,
+			Err(e) => {
+				log::warn!(target: LOG_TARGET, "Failed to restore journalled transactions: {}", e);
+				return
+			},
+		};
+		let entries = journal.restore();
+		if entries.is_empty() {
+			return
+		}
+
+		let total = entries.len();
+		let mut by_source = Vec::<(TransactionSource, Vec<graph::ExtrinsicFor<PoolApi>>)>::new();
+		for JournalEntry { source, data } in entries {
+			let Ok(xt) = graph::ExtrinsicFor::<PoolApi>::decode(&mut &data[..]) else { continue };
+			match by_source.iter_mut().find(|(s, _)| *s == source) {
+				Some((_, xts)) => xts.push(xt),
+				None => by_source.push((source, vec![xt])),
+			}
+		}
+
+		let mut imported = 0;
+		for (source, xts) in by_source {
+			match self.pool.submit_at(&BlockId::Hash(at), source, xts).await {
+				Ok(results) => imported += results.iter().filter(|r| r.is_ok()).count(),
+				Err(e) => log::warn!(
+					target: LOG_TARGET,
+					"Failed to restore journalled transactions at {:?}: {}",
+					at,
+					e,
+				),
+			}
 		}
+
+		log::info!(
+			target: LOG_TARGET,
+			"Restored {} of {} transactions from the journal",
+			imported,
+			total,
+		);
 	}
 
 	/// Gets shared reference to the underlying pool.
@@ -242,6 +312,43 @@ where
 	}
 }
 
+impl<PoolApi, Block> BasicPool<PoolApi, Block>
+where
+	Block: BlockT,
+	PoolApi: graph::ChainApi<Block = Block>,
+{
+	/// Writes the ready and future transactions to the journal, if it is enabled.
+	///
+	/// Ready transactions are stored first in the order of their priority, so that the
+	/// best ones are kept when the journal size limit is reached.
+	fn store_journal(&self) {
+		let Some(journal) = &self.journal else { return };
+		let entries = {
+			let pool = self.pool.validated_pool().pool.read();
+			let ready = pool
+				.ready()
+				.map(|tx| JournalEntry { source: tx.source, data: tx.data.encode() })
+				.collect::<Vec<_>>();
+			let future = pool
+				.futures()
+				.map(|tx| JournalEntry { source: tx.source, data: tx.data.encode() })
+				.collect::<Vec<_>>();
+			ready.into_iter().chain(future)
+		};
+		journal.store(entries);
+	}
+}
+
+impl<PoolApi, Block> Drop for BasicPool<PoolApi, Block>
+where
+	Block: BlockT,
+	PoolApi: graph::ChainApi<Block = Block>,
+{
+	fn drop(&mut self) {
+		self.store_journal();
+	}
+}
+
 impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 where
 	Block: BlockT,
@@ -731,6 +838,10 @@ where
 	PoolApi: 'static + graph::ChainApi<Block = Block>,
 {
 	async fn maintain(&self, event: ChainEvent<Self::Block>) {
+		// The transactions of the previous run are restored on the first maintenance, even if
+		// the event itself is skipped.
+		self.restore_journal().await;
+
 		let prev_finalized_block = self.enactment_state.lock().recent_finalized_block();
 		let compute_tree_route = |from, to| -> Result<TreeRoute<Block>, String> {
 			match self.api.tree_route(from, to) {
@@ -778,6 +889,10 @@ where
 				}
 			}
 		}
+
+		if self.journal.as_ref().map_or(false, |journal| journal.should_store()) {
+			self.store_journal();
+		}
 	}
 }
 
@@ -49,6 +49,9 @@ pub struct Metrics {
 	pub validations_invalid: Counter<U64>,
 	pub block_transactions_pruned: Counter<U64>,
 	pub block_transactions_resubmitted: Counter<U64>,
+	pub dropped_transactions: Counter<U64>,
+	pub evicted_transactions: Counter<U64>,
+	pub sender_limited_transactions: Counter<U64>,
 }
 
 impl Metrics {
@@ -82,6 +85,27 @@ impl Metrics {
 				)?,
 				registry,
 			)?,
+			dropped_transactions: register(
+				Counter::new(
+					"substrate_sub_txpool_dropped_transactions",
+					"Total number of transactions that were dropped because the pool was full",
+				)?,
+				registry,
+			)?,
+			evicted_transactions: register(
+				Counter::new(
+					"substrate_sub_txpool_evicted_transactions",
+					"Total number of transactions that were evicted by higher priority transactions",
+				)?,
+				registry,
+			)?,
+			sender_limited_transactions: register(
+				Counter::new(
+					"substrate_sub_txpool_sender_limited_transactions",
+					"Total number of transactions that were dropped because of the per-sender limit",
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
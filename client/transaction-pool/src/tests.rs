@@ -193,6 +193,10 @@ impl ChainApi for TestApi {
 	) -> Result<TreeRoute<Self::Block>, Self::Error> {
 		unimplemented!()
 	}
+
+	fn best_block_hash(&self) -> Result<<Self::Block as BlockT>::Hash, Self::Error> {
+		unimplemented!()
+	}
 }
 
 pub(crate) fn uxt(transfer: Transfer) -> Extrinsic {
@@ -0,0 +1,225 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Tests for the fork-aware transaction pool.
+
+use futures::executor::{block_on, block_on_stream};
+use sc_transaction_pool::{ForkAwareTxPool, Options, PoolLimit};
+use sc_transaction_pool_api::{
+	ChainEvent, InPoolTransaction, MaintainedTransactionPool, TransactionPool, TransactionStatus,
+};
+use sp_blockchain::HashAndNumber;
+use sp_runtime::{generic::BlockId, transaction_validity::TransactionSource};
+use std::sync::Arc;
+use substrate_test_runtime_client::{
+	runtime::{Block, Extrinsic, Hash, Header, TransferData},
+	AccountKeyring::*,
+};
+use substrate_test_runtime_transaction_pool::{uxt, TestApi};
+
+const SOURCE: TransactionSource = TransactionSource::External;
+
+fn genesis_hash(api: &TestApi) -> Hash {
+	api.chain()
+		.read()
+		.block_by_number
+		.get(&0)
+		.map(|blocks| blocks[0].0.header.hash())
+		.expect("there is block 0. qed")
+}
+
+fn create_pool() -> (ForkAwareTxPool<TestApi, Block>, Arc<TestApi>) {
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let genesis = HashAndNumber { hash: genesis_hash(&api), number: 0 };
+	(ForkAwareTxPool::new_test(api.clone(), genesis), api)
+}
+
+/// Create a pool whose mempool holds at most `count` transactions, all of them ready.
+fn create_pool_with_limit(count: usize) -> (ForkAwareTxPool<TestApi, Block>, Arc<TestApi>) {
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let genesis = HashAndNumber { hash: genesis_hash(&api), number: 0 };
+	let options = Options {
+		ready: PoolLimit { count, total_bytes: usize::MAX },
+		future: PoolLimit { count: 0, total_bytes: usize::MAX },
+		..Default::default()
+	};
+	(ForkAwareTxPool::new(options, true.into(), api.clone(), None, genesis), api)
+}
+
+fn new_best_block_event(header: &Header) -> ChainEvent<Block> {
+	ChainEvent::NewBestBlock { hash: header.hash(), tree_route: None }
+}
+
+fn ready_nonces<T>(ready: impl Iterator<Item = Arc<T>>) -> Vec<u64>
+where
+	T: InPoolTransaction<Transaction = Extrinsic>,
+{
+	ready
+		.map(|tx| TransferData::try_from(tx.data()).expect("only transfers are submitted").nonce)
+		.collect()
+}
+
+#[test]
+fn submitted_transactions_are_ready_at_best_block() {
+	// given
+	let (pool, api) = create_pool();
+	let at = BlockId::Hash(genesis_hash(&api));
+
+	// when
+	block_on(pool.submit_at(&at, SOURCE, vec![uxt(Alice, 209), uxt(Alice, 210)])).unwrap();
+	block_on(pool.submit_one(&at, SOURCE, uxt(Alice, 212))).unwrap();
+
+	// then
+	assert_eq!(pool.status().ready, 2);
+	assert_eq!(pool.status().future, 1);
+	assert_eq!(ready_nonces(pool.ready()), vec![209, 210]);
+}
+
+#[test]
+fn new_best_block_replaces_parent_view() {
+	// given
+	let (pool, api) = create_pool();
+	let at = BlockId::Hash(genesis_hash(&api));
+	block_on(pool.submit_at(&at, SOURCE, vec![uxt(Alice, 209), uxt(Alice, 210)])).unwrap();
+
+	// when
+	let header = api.push_block(1, vec![uxt(Alice, 209)], true);
+	api.increment_nonce(Alice.into());
+	block_on(pool.maintain(new_best_block_event(&header)));
+
+	// then
+	assert_eq!(pool.views_count(), 1);
+	assert_eq!(ready_nonces(pool.ready()), vec![210]);
+	let ready_at = block_on(pool.ready_at(1));
+	assert_eq!(ready_nonces(ready_at), vec![210]);
+}
+
+#[test]
+fn ready_at_block_creates_view_for_fork() {
+	// given
+	let (pool, api) = create_pool();
+	let genesis = genesis_hash(&api);
+	block_on(pool.submit_one(&BlockId::Hash(genesis), SOURCE, uxt(Alice, 209))).unwrap();
+	let best = api.push_block_with_parent(genesis, vec![], true);
+	block_on(pool.maintain(new_best_block_event(&best)));
+	let fork = api.push_block_with_parent(genesis, vec![], false);
+
+	// when
+	let ready = block_on(pool.ready_at_block(&HashAndNumber { hash: fork.hash(), number: 1 }));
+
+	// then
+	assert_eq!(ready_nonces(ready), vec![209]);
+	assert_eq!(pool.views_count(), 2);
+}
+
+#[test]
+fn finalization_keeps_only_descendant_views() {
+	// given
+	let (pool, api) = create_pool();
+	let genesis = genesis_hash(&api);
+	let fork = api.push_block_with_parent(genesis, vec![], false);
+	let best = api.push_block_with_parent(genesis, vec![], true);
+	block_on(pool.ready_at_block(&HashAndNumber { hash: fork.hash(), number: 1 }));
+	block_on(pool.maintain(new_best_block_event(&best)));
+	assert_eq!(pool.views_count(), 2);
+
+	// when
+	block_on(
+		pool.maintain(ChainEvent::Finalized { hash: best.hash(), tree_route: Arc::from(vec![]) }),
+	);
+
+	// then
+	assert_eq!(pool.views_count(), 1);
+}
+
+#[test]
+fn watcher_reports_in_block_and_finalized() {
+	// given
+	let (pool, api) = create_pool();
+	let at = BlockId::Hash(genesis_hash(&api));
+	let xt = uxt(Alice, 209);
+	let watcher = block_on(pool.submit_and_watch(&at, SOURCE, xt.clone())).unwrap();
+
+	// when
+	let header = api.push_block(1, vec![xt], true);
+	api.increment_nonce(Alice.into());
+	block_on(pool.maintain(new_best_block_event(&header)));
+	block_on(
+		pool.maintain(ChainEvent::Finalized { hash: header.hash(), tree_route: Arc::from(vec![]) }),
+	);
+
+	// then
+	let mut stream = block_on_stream(watcher);
+	assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+	assert_eq!(stream.next(), Some(TransactionStatus::InBlock((header.hash(), 0))));
+	assert_eq!(stream.next(), Some(TransactionStatus::Finalized((header.hash(), 0))));
+	assert_eq!(stream.next(), None);
+	assert_eq!(pool.status().ready, 0);
+}
+
+#[test]
+fn full_pool_evicts_lowest_priority_transaction() {
+	// given
+	let (pool, api) = create_pool_with_limit(2);
+	let at = BlockId::Hash(genesis_hash(&api));
+	api.increment_nonce(Charlie.into());
+	api.set_valid_modifier(Box::new(|v| v.priority = 5));
+	block_on(pool.submit_one(&at, SOURCE, uxt(Bob, 0))).unwrap();
+	api.set_valid_modifier(Box::new(|v| v.priority = 1));
+	let watcher = block_on(pool.submit_and_watch(&at, SOURCE, uxt(Alice, 209))).unwrap();
+
+	// when
+	api.set_valid_modifier(Box::new(|v| v.priority = 10));
+	let result = block_on(pool.submit_one(&at, SOURCE, uxt(Charlie, 1)));
+
+	// then
+	assert!(result.is_ok());
+	assert_eq!(pool.status().ready, 2);
+	assert_eq!(ready_nonces(pool.ready()), vec![1, 0]);
+	let mut stream = block_on_stream(watcher);
+	assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+	assert_eq!(stream.next(), Some(TransactionStatus::Evicted));
+	assert_eq!(stream.next(), None);
+}
+
+#[test]
+fn expired_transactions_are_removed_on_finalization() {
+	// given
+	let (pool, api) = create_pool_with_limit(2);
+	let at = BlockId::Hash(genesis_hash(&api));
+	api.set_valid_modifier(Box::new(|v| {
+		v.priority = 10;
+		v.longevity = 2;
+	}));
+	block_on(pool.submit_at(&at, SOURCE, vec![uxt(Alice, 209), uxt(Alice, 210)])).unwrap();
+	api.set_valid_modifier(Box::new(|v| v.priority = 1));
+	assert!(block_on(pool.submit_one(&at, SOURCE, uxt(Bob, 0))).is_err());
+
+	// when
+	let block1 = api.push_block(1, vec![], true);
+	let block2 = api.push_block(2, vec![], true);
+	block_on(pool.maintain(ChainEvent::Finalized {
+		hash: block2.hash(),
+		tree_route: Arc::from(vec![block1.hash()]),
+	}));
+
+	// then
+	assert_eq!(pool.status().ready, 0);
+	block_on(pool.submit_one(&at, SOURCE, uxt(Charlie, 0))).unwrap();
+	assert_eq!(ready_nonces(pool.ready()), vec![0]);
+}
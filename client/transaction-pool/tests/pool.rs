@@ -28,7 +28,7 @@ use sc_block_builder::BlockBuilderProvider;
 use sc_client_api::client::BlockchainEvents;
 use sc_transaction_pool::*;
 use sc_transaction_pool_api::{
-	ChainEvent, MaintainedTransactionPool, TransactionPool, TransactionStatus,
+	ChainEvent, InPoolTransaction, MaintainedTransactionPool, TransactionPool, TransactionStatus,
 };
 use sp_blockchain::HeaderBackend;
 use sp_consensus::BlockOrigin;
@@ -507,6 +507,62 @@ fn finalization() {
 	assert_eq!(stream.next(), None);
 }
 
+#[test]
+fn should_restore_journal_at_best_block_after_restart() {
+	let dir = tempfile::tempdir().unwrap();
+	let options = Options {
+		journal: Some(JournalOptions {
+			path: dir.path().join("txpool.journal"),
+			max_bytes: 1024 * 1024,
+		}),
+		..Default::default()
+	};
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let genesis = api.chain().read().block_by_number.get(&0).unwrap()[0].0.header().hash();
+	let journaled_pool = |best_number, best_hash| {
+		BasicPool::with_revalidation_type(
+			options.clone(),
+			true.into(),
+			api.clone(),
+			None,
+			RevalidationType::Light,
+			sp_core::testing::TaskExecutor::new(),
+			best_number,
+			best_hash,
+			genesis,
+		)
+	};
+	let xt1 = uxt(Alice, 209);
+	let xt2 = uxt(Alice, 210);
+
+	// given
+	let header = api.push_block(1, vec![], true);
+	let pool = journaled_pool(0, genesis);
+	block_on(pool.maintain(block_event(header.clone())));
+	block_on(pool.submit_one(&BlockId::hash(header.hash()), SOURCE, xt1.clone())).unwrap();
+	block_on(pool.submit_one(&BlockId::hash(header.hash()), SOURCE, xt2.clone())).unwrap();
+	assert_eq!(pool.status().ready, 2);
+	// stopping the node stores the journal
+	drop(pool);
+
+	// the chain moves on while the node is stopped
+	let best = api.push_block(2, vec![], true);
+	let validation_requests = api.validation_requests().len();
+
+	// when
+	let pool = journaled_pool(2, best.hash());
+	assert_eq!(pool.status().ready, 0);
+	let event = ChainEvent::Finalized { hash: header.hash(), tree_route: Arc::from(vec![]) };
+	block_on(pool.maintain(event));
+
+	// then
+	// the transactions were validated again and imported at the best block, not at the
+	// finalized one
+	assert_eq!(api.validation_requests()[validation_requests..], [xt1, xt2]);
+	assert_eq!(pool.status().ready, 2);
+	assert!(pool.ready().all(|tx| *tx.longevity() == 2 + 64));
+}
+
 #[test]
 fn fork_aware_finalization() {
 	sp_tracing::try_init_simple();
@@ -119,6 +119,7 @@
 use codec::{Codec, Encode};
 use frame_support::{
 	dispatch::{DispatchClass, DispatchInfo, GetDispatchInfo, PostDispatchInfo},
+	migrations::MultiStepMigrator,
 	pallet_prelude::InvalidTransaction,
 	traits::{
 		EnsureInherentsAreFirst, ExecuteBlock, OffchainWorker, OnFinalize, OnIdle, OnInitialize,
@@ -138,6 +139,8 @@ use sp_runtime::{
 };
 use sp_std::{marker::PhantomData, prelude::*};
 
+#[cfg(feature = "try-runtime")]
+use frame_support::migrations::MultiStepStatus;
 #[cfg(feature = "try-runtime")]
 use log;
 #[cfg(feature = "try-runtime")]
@@ -146,6 +149,11 @@ use sp_runtime::TryRuntimeError;
 #[allow(dead_code)]
 const LOG_TARGET: &str = "runtime::executive";
 
+/// The maximum number of blocks that `try_runtime_upgrade` steps the multi-block migrations for
+/// before giving up.
+#[cfg(feature = "try-runtime")]
+const MAX_TRY_RUNTIME_MIGRATION_STEPS: u32 = 10_000;
+
 pub type CheckedOf<E, C> = <E as Checkable<C>>::Checked;
 pub type CallOf<E, C> = <CheckedOf<E, C> as Applyable>::Call;
 pub type OriginOf<E, C> = <CallOf<E, C> as Dispatchable>::RuntimeOrigin;
@@ -161,6 +169,8 @@ pub type OriginOf<E, C> = <CallOf<E, C> as Dispatchable>::RuntimeOrigin;
 ///   used to call hooks e.g. `on_initialize`.
 /// - `OnRuntimeUpgrade`: Custom logic that should be called after a runtime upgrade. Modules are
 ///   already called by `AllPalletsWithSystem`. It will be called before all modules will be called.
+/// - `MultiStepMigrator`: Drives the multi-block migrations of the runtime. It is stepped at the
+///   start of every block, after `on_initialize`.
 pub struct Executive<
 	System,
 	Block,
@@ -168,6 +178,7 @@ pub struct Executive<
 	UnsignedValidator,
 	AllPalletsWithSystem,
 	OnRuntimeUpgrade = (),
+	MultiStepMigrator = (),
 >(
 	PhantomData<(
 		System,
@@ -176,6 +187,7 @@ pub struct Executive<
 		UnsignedValidator,
 		AllPalletsWithSystem,
 		OnRuntimeUpgrade,
+		MultiStepMigrator,
 	)>,
 );
 
@@ -193,8 +205,17 @@ impl<
 			+ OnFinalize<BlockNumberFor<System>>
 			+ OffchainWorker<BlockNumberFor<System>>,
 		COnRuntimeUpgrade: OnRuntimeUpgrade,
+		CMultiStepMigrator: MultiStepMigrator,
 	> ExecuteBlock<Block>
-	for Executive<System, Block, Context, UnsignedValidator, AllPalletsWithSystem, COnRuntimeUpgrade>
+	for Executive<
+		System,
+		Block,
+		Context,
+		UnsignedValidator,
+		AllPalletsWithSystem,
+		COnRuntimeUpgrade,
+		CMultiStepMigrator,
+	>
 where
 	Block::Extrinsic: Checkable<Context> + Codec,
 	CheckedOf<Block::Extrinsic, Context>: Applyable + GetDispatchInfo,
@@ -211,6 +232,7 @@ where
 			UnsignedValidator,
 			AllPalletsWithSystem,
 			COnRuntimeUpgrade,
+			CMultiStepMigrator,
 		>::execute_block(block);
 	}
 }
@@ -231,7 +253,17 @@ impl<
 			+ OffchainWorker<BlockNumberFor<System>>
 			+ frame_support::traits::TryState<BlockNumberFor<System>>,
 		COnRuntimeUpgrade: OnRuntimeUpgrade,
-	> Executive<System, Block, Context, UnsignedValidator, AllPalletsWithSystem, COnRuntimeUpgrade>
+		CMultiStepMigrator: MultiStepMigrator,
+	>
+	Executive<
+		System,
+		Block,
+		Context,
+		UnsignedValidator,
+		AllPalletsWithSystem,
+		COnRuntimeUpgrade,
+		CMultiStepMigrator,
+	>
 where
 	Block::Extrinsic: Checkable<Context> + Codec,
 	CheckedOf<Block::Extrinsic, Context>: Applyable + GetDispatchInfo,
@@ -352,7 +384,9 @@ where
 	/// Execute all `OnRuntimeUpgrade` of this runtime, including the pre and post migration checks.
 	///
 	/// Runs the try-state code both before and after the migration function if `checks` is set to
-	/// `true`. Also, if set to `true`, it runs the `pre_upgrade` and `post_upgrade` hooks.
+	/// `true`. Also, if set to `true`, it runs the `pre_upgrade` and `post_upgrade` hooks and
+	/// steps the multi-block migrations until they are finished. Their weight is not included in
+	/// the returned weight, since it is spread over many blocks.
 	pub fn try_runtime_upgrade(
 		checks: frame_try_runtime::UpgradeCheckSelect,
 	) -> Result<Weight, TryRuntimeError> {
@@ -371,6 +405,10 @@ where
 				checks.pre_and_post(),
 			)?;
 
+		if checks.pre_and_post() {
+			Self::try_multi_step_migrations()?;
+		}
+
 		if checks.try_state() {
 			let _guard = frame_support::StorageNoopGuard::default();
 			<AllPalletsWithSystem as frame_support::traits::TryState<
@@ -383,6 +421,40 @@ where
 
 		Ok(weight)
 	}
+
+	/// Step the multi-block migrations until they are no longer ongoing.
+	///
+	/// Fails if they are still ongoing after [`MAX_TRY_RUNTIME_MIGRATION_STEPS`] steps.
+	fn try_multi_step_migrations() -> Result<(), TryRuntimeError> {
+		let mut steps = 0u32;
+		let mut weight = Weight::zero();
+		while CMultiStepMigrator::status() == MultiStepStatus::Ongoing {
+			if steps >= MAX_TRY_RUNTIME_MIGRATION_STEPS {
+				log::error!(
+					target: LOG_TARGET,
+					"try-runtime: multi-block migrations did not finish within {} blocks",
+					MAX_TRY_RUNTIME_MIGRATION_STEPS,
+				);
+				return Err("Multi-block migrations did not finish".into())
+			}
+			weight = weight.saturating_add(CMultiStepMigrator::step());
+			steps += 1;
+		}
+
+		if CMultiStepMigrator::status() == MultiStepStatus::Stuck {
+			log::error!(target: LOG_TARGET, "try-runtime: multi-block migrations are stuck");
+			return Err("Multi-block migrations are stuck".into())
+		}
+		if steps > 0 {
+			log::info!(
+				target: LOG_TARGET,
+				"try-runtime: multi-block migrations finished after {} blocks and {:?}",
+				steps,
+				weight,
+			);
+		}
+		Ok(())
+	}
 }
 
 impl<
@@ -399,7 +471,17 @@ impl<
 			+ OnFinalize<BlockNumberFor<System>>
 			+ OffchainWorker<BlockNumberFor<System>>,
 		COnRuntimeUpgrade: OnRuntimeUpgrade,
-	> Executive<System, Block, Context, UnsignedValidator, AllPalletsWithSystem, COnRuntimeUpgrade>
+		CMultiStepMigrator: MultiStepMigrator,
+	>
+	Executive<
+		System,
+		Block,
+		Context,
+		UnsignedValidator,
+		AllPalletsWithSystem,
+		COnRuntimeUpgrade,
+		CMultiStepMigrator,
+	>
 where
 	Block::Extrinsic: Checkable<Context> + Codec,
 	CheckedOf<Block::Extrinsic, Context>: Applyable + GetDispatchInfo,
@@ -449,6 +531,7 @@ where
 		weight = weight.saturating_add(<AllPalletsWithSystem as OnInitialize<
 			BlockNumberFor<System>,
 		>>::on_initialize(*block_number));
+		weight = weight.saturating_add(CMultiStepMigrator::step());
 		weight = weight.saturating_add(
 			<System::BlockWeights as frame_support::traits::Get<_>>::get().base_block,
 		);
@@ -872,6 +955,7 @@ mod tests {
 		type RuntimeOrigin = RuntimeOrigin;
 		type Nonce = u64;
 		type RuntimeCall = RuntimeCall;
+		type RuntimeTask = RuntimeTask;
 		type Hash = sp_core::H256;
 		type Hashing = BlakeTwo256;
 		type AccountId = u64;
@@ -954,6 +1038,27 @@ mod tests {
 		}
 	}
 
+	parameter_types! {
+		pub static MultiStepStatusTestValue: frame_support::migrations::MultiStepStatus =
+			frame_support::migrations::MultiStepStatus::Idle;
+		pub static MultiStepSteps: u32 = 0;
+	}
+
+	struct MockedMultiStepMigrator;
+	impl MultiStepMigrator for MockedMultiStepMigrator {
+		fn status() -> frame_support::migrations::MultiStepStatus {
+			MultiStepStatusTestValue::get()
+		}
+
+		fn step() -> Weight {
+			if !Self::ongoing() {
+				return Weight::zero()
+			}
+			MultiStepSteps::mutate(|steps| *steps += 1);
+			Weight::from_parts(30, 0)
+		}
+	}
+
 	type Executive = super::Executive<
 		Runtime,
 		Block<TestXt>,
@@ -961,6 +1066,7 @@ mod tests {
 		Runtime,
 		AllPalletsWithSystem,
 		CustomOnRuntimeUpgrade,
+		MockedMultiStepMigrator,
 	>;
 
 	fn extra(nonce: u64, fee: Balance) -> SignedExtra {
@@ -1505,8 +1611,39 @@ mod tests {
 				frame_system::Pallet::<Runtime>::block_weight().total(),
 				custom_runtime_upgrade_weight +
 					runtime_upgrade_weight +
-					on_initialize_weight + base_block_weight,
+					on_initialize_weight +
+					base_block_weight,
+			);
+		});
+	}
+
+	#[test]
+	fn multi_step_migrations_are_stepped_in_initialize_block() {
+		new_test_ext(1).execute_with(|| {
+			MultiStepStatusTestValue::set(frame_support::migrations::MultiStepStatus::Ongoing);
+			MultiStepSteps::set(0);
+
+			for block_number in 1..=2 {
+				Executive::initialize_block(&Header::new(
+					block_number,
+					H256::default(),
+					H256::default(),
+					[69u8; 32].into(),
+					Digest::default(),
+				));
+			}
+			assert_eq!(MultiStepSteps::get(), 2);
+
+			let on_initialize_weight =
+				<AllPalletsWithSystem as OnInitialize<u64>>::on_initialize(2);
+			let base_block_weight =
+				<Runtime as frame_system::Config>::BlockWeights::get().base_block;
+			assert_eq!(
+				frame_system::Pallet::<Runtime>::block_weight().total(),
+				on_initialize_weight + base_block_weight + Weight::from_parts(30, 0),
 			);
+
+			MultiStepStatusTestValue::set(frame_support::migrations::MultiStepStatus::Idle);
 		});
 	}
 
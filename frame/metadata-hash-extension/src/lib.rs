@@ -0,0 +1,178 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Metadata Hash Extension
+//!
+//! The [`CheckMetadataHash`] signed extension lets the signer of a transaction commit to the
+//! metadata of the runtime. The metadata hash is the hash of the merkleized metadata as
+//! calculated by `frame-merkleized-metadata` as specified by RFC-0078. Offline signers, like
+//! hardware wallets, can then decode a transaction with only a small proof of the metadata instead
+//! of the full metadata and are sure that the proof belongs to the runtime the transaction is
+//! executed in.
+//!
+//! The signer chooses the [`Mode`] as part of the transaction. With [`Mode::Enabled`] the
+//! metadata hash becomes part of the signed payload, so the signature is only valid if the
+//! signer used the same metadata hash as the runtime.
+//!
+//! ## Integration
+//!
+//! The runtime learns about its own metadata hash at compile time through the
+//! `RUNTIME_METADATA_HASH` environment variable. `substrate-wasm-builder` sets it when the
+//! `metadata-hash` feature is enabled and the build is configured with
+//! `WasmBuilder::enable_metadata_hash`. A runtime built without the metadata hash rejects
+//! transactions that enable the check, as it can not produce the expected signed payload.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_system::Config;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{DispatchInfoOf, SignedExtension},
+	transaction_validity::{TransactionValidityError, UnknownTransaction},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Whether the metadata hash check is enabled for a transaction.
+#[derive(Decode, Encode, PartialEq, Eq, Debug, TypeInfo, Clone, Copy)]
+pub enum Mode {
+	/// The metadata hash is not checked. `None` is part of the signed payload.
+	Disabled,
+	/// The metadata hash of the runtime, as `Some(hash)`, is part of the signed payload.
+	Enabled,
+}
+
+/// Where the runtime takes its metadata hash from.
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+enum MetadataHash {
+	/// Read it from the `RUNTIME_METADATA_HASH` environment variable at compile time.
+	#[default]
+	FetchFromEnv,
+	/// Use the given hash.
+	Custom([u8; 32]),
+}
+
+const RUNTIME_METADATA_HASH: Option<&str> = option_env!("RUNTIME_METADATA_HASH");
+
+impl MetadataHash {
+	fn hash(&self) -> Option<[u8; 32]> {
+		match self {
+			Self::FetchFromEnv => RUNTIME_METADATA_HASH.and_then(hex_str_to_bytes),
+			Self::Custom(hash) => Some(*hash),
+		}
+	}
+}
+
+/// Decode a hex encoded hash, with or without `0x` prefix.
+fn hex_str_to_bytes(hash: &str) -> Option<[u8; 32]> {
+	let hash = hash.strip_prefix("0x").unwrap_or(hash).as_bytes();
+	if hash.len() != 64 {
+		return None
+	}
+
+	let nibble = |c: u8| match c {
+		b'0'..=b'9' => Some(c - b'0'),
+		b'a'..=b'f' => Some(c - b'a' + 10),
+		b'A'..=b'F' => Some(c - b'A' + 10),
+		_ => None,
+	};
+
+	let mut bytes = [0u8; 32];
+	for (byte, pair) in bytes.iter_mut().zip(hash.chunks(2)) {
+		*byte = nibble(pair[0])? << 4 | nibble(pair[1])?;
+	}
+
+	Some(bytes)
+}
+
+/// Include the metadata hash of the runtime into the signed payload.
+///
+/// # Transaction Validity
+///
+/// The extension itself only carries the [`Mode`]. With [`Mode::Enabled`] the runtime includes
+/// its metadata hash into the data the signature is checked against, so a transaction signed
+/// with a different metadata hash has a bad signature. If the runtime was built without a
+/// metadata hash, transactions that enable the check are rejected.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckMetadataHash<T> {
+	_phantom: sp_std::marker::PhantomData<T>,
+	mode: Mode,
+	#[codec(skip)]
+	metadata_hash: MetadataHash,
+}
+
+impl<T> CheckMetadataHash<T> {
+	/// Create new `SignedExtension` to check the metadata hash.
+	pub fn new(enable: bool) -> Self {
+		Self {
+			_phantom: sp_std::marker::PhantomData,
+			mode: if enable { Mode::Enabled } else { Mode::Disabled },
+			metadata_hash: MetadataHash::FetchFromEnv,
+		}
+	}
+
+	/// Create an enabled `SignedExtension` that uses the given `metadata_hash`.
+	///
+	/// Meant for tests and for signing transactions outside of the runtime.
+	pub fn new_with_custom_hash(metadata_hash: [u8; 32]) -> Self {
+		Self { metadata_hash: MetadataHash::Custom(metadata_hash), ..Self::new(true) }
+	}
+}
+
+impl<T> sp_std::fmt::Debug for CheckMetadataHash<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "CheckMetadataHash {{ mode: {:?} }}", self.mode)
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckMetadataHash<T> {
+	type AccountId = T::AccountId;
+	type Call = <T as Config>::RuntimeCall;
+	type AdditionalSigned = Option<[u8; 32]>;
+	type Pre = ();
+	const IDENTIFIER: &'static str = "CheckMetadataHash";
+
+	fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
+		match self.mode {
+			Mode::Disabled => Ok(None),
+			Mode::Enabled => self
+				.metadata_hash
+				.hash()
+				.map(Some)
+				.ok_or(UnknownTransaction::CannotLookup.into()),
+		}
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		self.validate(who, call, info, len).map(|_| ())
+	}
+}
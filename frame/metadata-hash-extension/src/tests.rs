@@ -0,0 +1,130 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::*;
+use frame_merkleized_metadata::{ExtraInfo, MerkleizedMetadata};
+use frame_support::derive_impl;
+use sp_runtime::{
+	generic,
+	testing::TestSignature,
+	traits::{ConstU32, Extrinsic as _},
+};
+
+type Extrinsic =
+	generic::UncheckedExtrinsic<u64, RuntimeCall, TestSignature, CheckMetadataHash<Test>>;
+type Block = generic::Block<generic::Header<u64, sp_runtime::traits::BlakeTwo256>, Extrinsic>;
+
+frame_support::construct_runtime!(
+	pub enum Test
+	{
+		System: frame_system,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+	type BlockHashCount = ConstU32<250>;
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type RuntimeEvent = RuntimeEvent;
+	type BaseCallFilter = frame_support::traits::Everything;
+	type PalletInfo = PalletInfo;
+	type OnSetCode = ();
+}
+
+fn merkleized_metadata() -> MerkleizedMetadata {
+	let extra_info = ExtraInfo {
+		spec_version: 1,
+		spec_name: "test".into(),
+		base58_prefix: 42,
+		decimals: 10,
+		token_symbol: "UNIT".into(),
+	};
+
+	MerkleizedMetadata::from_metadata_ir(Test::metadata_ir(), extra_info).unwrap()
+}
+
+#[test]
+fn disabled_check_signs_no_hash() {
+	let extension = CheckMetadataHash::<Test>::new(false);
+
+	assert_eq!(extension.additional_signed(), Ok(None));
+	assert_eq!(extension.encode(), Mode::Disabled.encode());
+}
+
+#[test]
+fn enabled_check_signs_the_hash() {
+	let extension = CheckMetadataHash::<Test>::new_with_custom_hash([5; 32]);
+
+	assert_eq!(extension.additional_signed(), Ok(Some([5; 32])));
+	// Only the mode is part of the extrinsic.
+	assert_eq!(extension.encode(), Mode::Enabled.encode());
+	assert_eq!(
+		CheckMetadataHash::<Test>::decode(&mut &extension.encode()[..]).unwrap().mode,
+		Mode::Enabled,
+	);
+}
+
+#[test]
+fn enabled_check_without_hash_is_rejected() {
+	let extension = CheckMetadataHash::<Test> {
+		_phantom: Default::default(),
+		mode: Mode::Enabled,
+		metadata_hash: MetadataHash::FetchFromEnv,
+	};
+
+	if RUNTIME_METADATA_HASH.is_none() {
+		assert_eq!(extension.additional_signed(), Err(UnknownTransaction::CannotLookup.into()));
+	}
+}
+
+#[test]
+fn hex_str_to_bytes_works() {
+	let hex = "0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20";
+	let expected: [u8; 32] = core::array::from_fn(|i| i as u8 + 1);
+
+	assert_eq!(hex_str_to_bytes(hex), Some(expected));
+	assert_eq!(hex_str_to_bytes(&format!("0x{hex}")), Some(expected));
+	assert_eq!(hex_str_to_bytes(&hex.to_uppercase()), Some(expected));
+	assert_eq!(hex_str_to_bytes(&hex[2..]), None);
+	assert_eq!(hex_str_to_bytes(&hex.replace('a', "g")), None);
+}
+
+#[test]
+fn signed_transaction_can_be_decoded_with_a_proof() {
+	let metadata = merkleized_metadata();
+	let metadata_hash = metadata.hash();
+
+	let extension = CheckMetadataHash::<Test>::new_with_custom_hash(metadata_hash);
+	let additional_signed = extension.additional_signed().unwrap();
+	let call = RuntimeCall::System(frame_system::Call::remark { remark: vec![1, 2, 3] });
+	let xt = Extrinsic::new(call, Some((1, TestSignature(1, vec![]), extension))).unwrap();
+
+	let proof = metadata
+		.generate_proof(&xt.encode(), Some(&additional_signed.encode()))
+		.unwrap();
+	assert!(proof.verify(&metadata_hash));
+	assert!(proof
+		.extrinsic_metadata
+		.signed_extensions
+		.iter()
+		.any(|e| e.identifier == CheckMetadataHash::<Test>::IDENTIFIER));
+
+	// A different metadata hash doesn't match the proof.
+	assert!(!proof.verify(&[0; 32]));
+}
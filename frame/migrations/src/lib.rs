@@ -0,0 +1,434 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Migrations Pallet
+//!
+//! Executes [`SteppedMigration`]s over as many blocks as they need.
+//!
+//! After every runtime upgrade, the pallet starts with the first migration of
+//! [`Config::Migrations`] and calls its steps at the start of each block until all migrations are
+//! done. `frame-executive` drives the pallet through its [`MultiStepMigrator`] implementation,
+//! which has to be passed as the last generic parameter of `Executive`.
+//!
+//! Migrations that completed once are recorded in [`Historic`] by their identifier and skipped by
+//! later upgrades. A migration that fails, exceeds its maximum number of steps, or cannot make
+//! progress with [`Config::MaxServiceWeight`] leaves the pallet stuck, and only
+//! [`Pallet::force_set_cursor`] can resume or abort the migrations.
+//!
+//! While migrations are ongoing or stuck, the pallet can pause all calls but
+//! [`Config::ExemptCalls`] by being part of the `BaseCallFilter` of the runtime:
+//!
+//! ```ignore
+//! impl frame_system::Config for Runtime {
+//! 	type BaseCallFilter = InsideBoth<Everything, Migrations>;
+//! 	// ...
+//! }
+//! ```
+//!
+//! The filter also applies to inherents, which must therefore be part of
+//! [`Config::ExemptCalls`]. Filtered transactions can still be included in blocks; they fail with
+//! `CallFiltered`.
+//!
+//! The progress can be queried with the [`MigrationsApi`] runtime API.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![deny(rustdoc::broken_intra_doc_links)]
+
+mod mock;
+mod tests;
+
+use frame_support::{
+	migrations::{MultiStepMigrator, MultiStepStatus, SteppedMigrationError, SteppedMigrations},
+	pallet_prelude::*,
+	traits::Contains,
+	weights::WeightMeter,
+};
+use frame_system::pallet_prelude::*;
+use sp_runtime::traits::Saturating;
+use sp_std::prelude::*;
+
+pub use pallet::*;
+
+const LOG_TARGET: &str = "runtime::migrations";
+
+/// The encoded cursor of a migration.
+pub type RawCursorOf<T> = BoundedVec<u8, <T as Config>::CursorMaxLen>;
+
+/// The encoded identifier of a migration.
+pub type IdentifierOf<T> = BoundedVec<u8, <T as Config>::IdentifierMaxLen>;
+
+/// The [`MigrationCursor`] stored by the pallet.
+pub type CursorOf<T> = MigrationCursor<RawCursorOf<T>, BlockNumberFor<T>>;
+
+/// The progress of the migrations of the last runtime upgrade.
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum MigrationCursor<Cursor, BlockNumber> {
+	/// A migration is running.
+	Active(ActiveCursor<Cursor, BlockNumber>),
+	/// A migration failed and the remaining ones are not run.
+	Stuck,
+}
+
+/// The position within the running migration.
+#[derive(Encode, Decode, TypeInfo, MaxEncodedLen, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ActiveCursor<Cursor, BlockNumber> {
+	/// Index of the migration in [`Config::Migrations`].
+	pub index: u32,
+	/// The cursor returned by the last step of the migration, `None` before the first step.
+	pub inner_cursor: Option<Cursor>,
+	/// Number of steps the migration took so far.
+	pub steps: u32,
+	/// The block in which the migration started.
+	pub started_at: BlockNumber,
+}
+
+impl<Cursor, BlockNumber> ActiveCursor<Cursor, BlockNumber> {
+	fn advance(&mut self, now: BlockNumber) {
+		self.index.saturating_inc();
+		self.inner_cursor = None;
+		self.steps = 0;
+		self.started_at = now;
+	}
+}
+
+/// The status of the migrations, as returned by [`MigrationsApi::status`].
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct MigrationsStatus {
+	/// Whether migrations are running or stuck.
+	pub status: MultiStepStatus,
+	/// Index of the running or failed migration, if any.
+	pub index: Option<u32>,
+	/// Number of steps the running migration took so far.
+	pub steps: u32,
+	/// Number of migrations in [`Config::Migrations`].
+	pub total: u32,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The migrations to run after a runtime upgrade, in order.
+		type Migrations: SteppedMigrations;
+
+		/// The maximum encoded length of the cursor of a migration.
+		#[pallet::constant]
+		type CursorMaxLen: Get<u32>;
+
+		/// The maximum encoded length of the identifier of a migration.
+		#[pallet::constant]
+		type IdentifierMaxLen: Get<u32>;
+
+		/// The maximum weight spent on migrations in a single block.
+		#[pallet::constant]
+		type MaxServiceWeight: Get<Weight>;
+
+		/// The calls that can be dispatched while migrations are ongoing or stuck.
+		type ExemptCalls: Contains<<Self as frame_system::Config>::RuntimeCall>;
+	}
+
+	/// The progress of the migrations, if any are running or stuck.
+	#[pallet::storage]
+	pub type Cursor<T: Config> = StorageValue<_, CursorOf<T>, OptionQuery>;
+
+	/// The identifiers of the migrations that completed.
+	#[pallet::storage]
+	pub type Historic<T: Config> = StorageMap<_, Twox64Concat, IdentifierOf<T>, (), OptionQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The migrations of a runtime upgrade started.
+		UpgradeStarted { migrations: u32 },
+		/// All migrations of the runtime upgrade completed.
+		UpgradeCompleted,
+		/// A migration failed; the remaining migrations are not run until the cursor is forced.
+		UpgradeFailed,
+		/// A migration was skipped since it already completed before.
+		MigrationSkipped { index: u32 },
+		/// A migration made progress.
+		MigrationAdvanced { index: u32, took: BlockNumberFor<T> },
+		/// A migration completed.
+		MigrationCompleted { index: u32, took: BlockNumberFor<T> },
+		/// A migration failed.
+		MigrationFailed { index: u32, took: BlockNumberFor<T> },
+		/// Some entries of [`Historic`] were removed.
+		HistoricCleared { count: u32 },
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_runtime_upgrade() -> Weight {
+			Self::onboard_migrations()
+		}
+
+		fn integrity_test() {
+			assert!(
+				T::Migrations::cursor_max_encoded_len() <= T::CursorMaxLen::get() as usize,
+				"A migration cursor does not fit into `CursorMaxLen`",
+			);
+			assert!(
+				T::Migrations::identifier_max_encoded_len() <= T::IdentifierMaxLen::get() as usize,
+				"A migration identifier does not fit into `IdentifierMaxLen`",
+			);
+			assert!(
+				T::MaxServiceWeight::get().all_gt(Self::overhead_weight()),
+				"`MaxServiceWeight` is too small to make any progress",
+			);
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Set or clear the cursor of the migrations.
+		///
+		/// Can be used to resume stuck migrations, to skip a migration or to abort the migrations
+		/// altogether. Must be called by root.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn force_set_cursor(
+			origin: OriginFor<T>,
+			cursor: Option<CursorOf<T>>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+
+			Cursor::<T>::set(cursor);
+			Ok(())
+		}
+
+		/// Remove the given identifiers from [`Historic`], so that these migrations run again with
+		/// the next runtime upgrade. Must be called by root.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::DbWeight::get().writes(identifiers.len() as u64))]
+		pub fn clear_historic(
+			origin: OriginFor<T>,
+			identifiers: Vec<IdentifierOf<T>>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+
+			for id in &identifiers {
+				Historic::<T>::remove(id);
+			}
+			Self::deposit_event(Event::HistoricCleared { count: identifiers.len() as u32 });
+			Ok(())
+		}
+	}
+}
+
+/// Outcome of a single step of [`Pallet::exec_migration`].
+enum StepOutcome {
+	/// Continue with the next step in the same block.
+	Continue,
+	/// Continue with the next step in the next block.
+	Yield,
+	/// All migrations completed.
+	Done,
+	/// A migration failed.
+	Stuck,
+}
+
+impl<T: Config> Pallet<T> {
+	/// The status of the migrations.
+	pub fn migrations_status() -> MigrationsStatus {
+		let total = T::Migrations::len();
+		match Cursor::<T>::get() {
+			None =>
+				MigrationsStatus { status: MultiStepStatus::Idle, index: None, steps: 0, total },
+			Some(MigrationCursor::Active(cursor)) => MigrationsStatus {
+				status: MultiStepStatus::Ongoing,
+				index: Some(cursor.index),
+				steps: cursor.steps,
+				total,
+			},
+			Some(MigrationCursor::Stuck) =>
+				MigrationsStatus { status: MultiStepStatus::Stuck, index: None, steps: 0, total },
+		}
+	}
+
+	/// Weight of reading and writing the cursor and the historic entry of a migration.
+	fn bookkeeping_weight() -> Weight {
+		T::DbWeight::get().reads_writes(1, 1)
+	}
+
+	/// Weight of [`MultiStepMigrator::step`] besides the steps of the migrations.
+	fn overhead_weight() -> Weight {
+		T::DbWeight::get().reads(1).saturating_add(Self::bookkeeping_weight())
+	}
+
+	/// Start the migrations of a runtime upgrade.
+	fn onboard_migrations() -> Weight {
+		if let Some(cursor) = Cursor::<T>::get() {
+			if matches!(cursor, MigrationCursor::Active(_)) {
+				// The new runtime may have a different list of migrations, so the index of the
+				// running one cannot be trusted anymore.
+				log::error!(target: LOG_TARGET, "Runtime upgrade while migrations are ongoing");
+				Cursor::<T>::put(MigrationCursor::Stuck);
+				Self::deposit_event(Event::UpgradeFailed);
+			}
+			return T::DbWeight::get().reads_writes(1, 1)
+		}
+
+		let migrations = T::Migrations::len();
+		if migrations == 0 {
+			return T::DbWeight::get().reads(1)
+		}
+
+		log::info!(target: LOG_TARGET, "Starting {} multi-block migrations", migrations);
+		Cursor::<T>::put(MigrationCursor::Active(ActiveCursor {
+			index: 0,
+			inner_cursor: None,
+			steps: 0,
+			started_at: frame_system::Pallet::<T>::block_number(),
+		}));
+		Self::deposit_event(Event::UpgradeStarted { migrations });
+		T::DbWeight::get().reads_writes(1, 1)
+	}
+
+	/// Run the next step of the migration at `cursor`.
+	fn exec_migration(
+		cursor: &mut ActiveCursor<RawCursorOf<T>, BlockNumberFor<T>>,
+		meter: &mut WeightMeter,
+	) -> StepOutcome {
+		let Some(id) = T::Migrations::nth_id(cursor.index) else { return StepOutcome::Done };
+		if meter.try_consume(Self::bookkeeping_weight()).is_err() {
+			return StepOutcome::Yield
+		}
+
+		let now = frame_system::Pallet::<T>::block_number();
+		let took = now.saturating_sub(cursor.started_at);
+		let index = cursor.index;
+		let Ok(id) = IdentifierOf::<T>::try_from(id) else {
+			log::error!(target: LOG_TARGET, "Identifier of migration {} is too long", index);
+			Self::deposit_event(Event::MigrationFailed { index, took });
+			return StepOutcome::Stuck
+		};
+
+		if cursor.inner_cursor.is_none() && Historic::<T>::contains_key(&id) {
+			Self::deposit_event(Event::MigrationSkipped { index });
+			cursor.advance(now);
+			return StepOutcome::Continue
+		}
+
+		let inner_cursor = cursor.inner_cursor.clone().map(BoundedVec::into_inner);
+		let result = T::Migrations::nth_transactional_step(index, inner_cursor, meter);
+		let max_steps = T::Migrations::nth_max_steps(index).flatten();
+		match result {
+			Some(Ok(Some(next))) => {
+				cursor.steps.saturating_inc();
+				if max_steps.map_or(false, |max| cursor.steps >= max) {
+					log::error!(target: LOG_TARGET, "Migration {} exceeded its steps", index);
+				} else if let Ok(next) = RawCursorOf::<T>::try_from(next) {
+					cursor.inner_cursor = Some(next);
+					Self::deposit_event(Event::MigrationAdvanced { index, took });
+					return StepOutcome::Continue
+				} else {
+					log::error!(target: LOG_TARGET, "Cursor of migration {} is too long", index);
+				}
+			},
+			Some(Ok(None)) => {
+				Historic::<T>::insert(&id, ());
+				Self::deposit_event(Event::MigrationCompleted { index, took });
+				cursor.advance(now);
+				return StepOutcome::Continue
+			},
+			Some(Err(SteppedMigrationError::InsufficientWeight { required })) => {
+				let available = T::MaxServiceWeight::get().saturating_sub(Self::overhead_weight());
+				if required.all_lte(available) {
+					return StepOutcome::Yield
+				}
+				log::error!(
+					target: LOG_TARGET,
+					"Migration {} needs more than `MaxServiceWeight`: {:?}",
+					index,
+					required,
+				);
+			},
+			Some(Err(e)) => {
+				log::error!(target: LOG_TARGET, "Migration {} failed: {:?}", index, e);
+			},
+			None => {
+				frame_support::defensive!("The migration exists since it has an identifier");
+			},
+		}
+
+		Self::deposit_event(Event::MigrationFailed { index, took });
+		StepOutcome::Stuck
+	}
+}
+
+impl<T: Config> MultiStepMigrator for Pallet<T> {
+	fn status() -> MultiStepStatus {
+		match Cursor::<T>::get() {
+			None => MultiStepStatus::Idle,
+			Some(MigrationCursor::Active(_)) => MultiStepStatus::Ongoing,
+			Some(MigrationCursor::Stuck) => MultiStepStatus::Stuck,
+		}
+	}
+
+	fn step() -> Weight {
+		let mut meter = WeightMeter::with_limit(T::MaxServiceWeight::get());
+		meter.consume(T::DbWeight::get().reads(1));
+
+		let Some(MigrationCursor::Active(mut cursor)) = Cursor::<T>::get() else {
+			return meter.consumed()
+		};
+
+		loop {
+			match Self::exec_migration(&mut cursor, &mut meter) {
+				StepOutcome::Continue => continue,
+				StepOutcome::Yield => {
+					Cursor::<T>::put(MigrationCursor::Active(cursor));
+				},
+				StepOutcome::Done => {
+					log::info!(target: LOG_TARGET, "All multi-block migrations completed");
+					Cursor::<T>::kill();
+					Self::deposit_event(Event::UpgradeCompleted);
+				},
+				StepOutcome::Stuck => {
+					Cursor::<T>::put(MigrationCursor::Stuck);
+					Self::deposit_event(Event::UpgradeFailed);
+				},
+			}
+			break
+		}
+
+		meter.consumed()
+	}
+}
+
+impl<T: Config> Contains<<T as frame_system::Config>::RuntimeCall> for Pallet<T> {
+	/// Return whether the call is allowed to be dispatched.
+	fn contains(call: &<T as frame_system::Config>::RuntimeCall) -> bool {
+		!Self::ongoing() || T::ExemptCalls::contains(call)
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// Query the progress of the multi-block migrations.
+	pub trait MigrationsApi {
+		/// The status of the migrations.
+		fn status() -> MigrationsStatus;
+	}
+}
@@ -0,0 +1,141 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test utilities for the migrations pallet.
+
+#![cfg(test)]
+
+use super::*;
+use crate as pallet_migrations;
+
+use frame_support::{
+	derive_impl,
+	migrations::SteppedMigration,
+	parameter_types,
+	traits::{ConstU32, OnRuntimeUpgrade},
+};
+use sp_runtime::BuildStorage;
+
+type Block = frame_system::mocking::MockBlockU32<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test
+	{
+		System: frame_system,
+		Migrations: pallet_migrations,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+	type BlockHashCount = ConstU32<250>;
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type RuntimeEvent = RuntimeEvent;
+	type BaseCallFilter = Migrations;
+	type PalletInfo = PalletInfo;
+	type OnSetCode = ();
+}
+
+/// Weight consumed by every step of a [`MockedMigration`].
+pub const STEP_WEIGHT: Weight = Weight::from_parts(10, 0);
+
+parameter_types! {
+	/// Enough for two steps per block.
+	pub const MaxServiceWeight: Weight = Weight::from_parts(25, u64::MAX);
+	/// Identifiers of the [`MockedMigration`]s that fail in their next step.
+	pub static FailingMigrations: Vec<u8> = vec![];
+}
+
+/// A migration that completes after `STEPS` steps.
+///
+/// Every step records the number of steps taken so far under the key `[ID]`.
+pub struct MockedMigration<const ID: u8, const STEPS: u32>;
+
+impl<const ID: u8, const STEPS: u32> SteppedMigration for MockedMigration<ID, STEPS> {
+	type Cursor = u32;
+	type Identifier = [u8; 2];
+
+	fn id() -> Self::Identifier {
+		[b'm', ID]
+	}
+
+	fn step(
+		cursor: Option<Self::Cursor>,
+		meter: &mut WeightMeter,
+	) -> Result<Option<Self::Cursor>, SteppedMigrationError> {
+		if FailingMigrations::get().contains(&ID) {
+			return Err(SteppedMigrationError::Failed)
+		}
+		if meter.try_consume(STEP_WEIGHT).is_err() {
+			return Err(SteppedMigrationError::InsufficientWeight { required: STEP_WEIGHT })
+		}
+
+		let steps = cursor.unwrap_or_default() + 1;
+		frame_support::storage::unhashed::put(&[ID], &steps);
+		Ok((steps < STEPS).then_some(steps))
+	}
+}
+
+impl Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Migrations = (MockedMigration<0, 3>, MockedMigration<1, 1>);
+	type CursorMaxLen = ConstU32<16>;
+	type IdentifierMaxLen = ConstU32<16>;
+	type MaxServiceWeight = MaxServiceWeight;
+	type ExemptCalls = IsRemarkWithEvent;
+}
+
+/// Exempts `System::remark_with_event` from the call filter.
+pub struct IsRemarkWithEvent;
+impl Contains<RuntimeCall> for IsRemarkWithEvent {
+	fn contains(call: &RuntimeCall) -> bool {
+		matches!(call, RuntimeCall::System(frame_system::Call::remark_with_event { .. }))
+	}
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Simulate a runtime upgrade, starting the migrations.
+pub fn upgrade_runtime() {
+	<AllPalletsWithSystem as OnRuntimeUpgrade>::on_runtime_upgrade();
+}
+
+/// Go to the next block and step the migrations like `frame-executive` would.
+pub fn next_block() -> Weight {
+	System::set_block_number(System::block_number() + 1);
+	<Migrations as MultiStepMigrator>::step()
+}
+
+/// The events of the pallet since the last call, in order.
+pub fn take_events() -> Vec<Event<Test>> {
+	let events = System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			RuntimeEvent::Migrations(event) => Some(event),
+			_ => None,
+		})
+		.collect();
+	System::reset_events();
+	events
+}
@@ -0,0 +1,204 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the migrations pallet.
+
+#![cfg(test)]
+
+use super::*;
+use crate::mock::*;
+
+use frame_support::{assert_ok, storage::unhashed};
+use sp_runtime::traits::Dispatchable;
+
+fn remark() -> RuntimeCall {
+	RuntimeCall::System(frame_system::Call::remark { remark: vec![] })
+}
+
+fn remark_with_event() -> RuntimeCall {
+	RuntimeCall::System(frame_system::Call::remark_with_event { remark: vec![] })
+}
+
+/// Run the migrations of the mock until they are completed.
+fn run_migrations() {
+	upgrade_runtime();
+	while Migrations::ongoing() {
+		next_block();
+	}
+	take_events();
+}
+
+#[test]
+fn migrations_run_over_multiple_blocks() {
+	new_test_ext().execute_with(|| {
+		upgrade_runtime();
+		assert_eq!(take_events(), vec![Event::UpgradeStarted { migrations: 2 }]);
+		assert_eq!(
+			Migrations::migrations_status(),
+			MigrationsStatus {
+				status: MultiStepStatus::Ongoing,
+				index: Some(0),
+				steps: 0,
+				total: 2
+			},
+		);
+
+		// Only two steps fit into a block.
+		assert_eq!(next_block(), STEP_WEIGHT * 2);
+		assert_eq!(
+			take_events(),
+			vec![
+				Event::MigrationAdvanced { index: 0, took: 1 },
+				Event::MigrationAdvanced { index: 0, took: 1 },
+			]
+		);
+		assert_eq!(unhashed::get::<u32>(&[0]), Some(2));
+		assert_eq!(Migrations::migrations_status().steps, 2);
+
+		assert_eq!(next_block(), STEP_WEIGHT * 2);
+		assert_eq!(
+			take_events(),
+			vec![
+				Event::MigrationCompleted { index: 0, took: 2 },
+				Event::MigrationCompleted { index: 1, took: 0 },
+				Event::UpgradeCompleted,
+			]
+		);
+		assert_eq!(unhashed::get::<u32>(&[0]), Some(3));
+		assert_eq!(unhashed::get::<u32>(&[1]), Some(1));
+		assert_eq!(Migrations::status(), MultiStepStatus::Idle);
+		assert!(Historic::<Test>::contains_key(IdentifierOf::<Test>::truncate_from(vec![b'm', 0])));
+
+		// Nothing left to do.
+		assert_eq!(next_block(), Weight::zero());
+		assert!(take_events().is_empty());
+	});
+}
+
+#[test]
+fn completed_migrations_are_skipped() {
+	new_test_ext().execute_with(|| {
+		run_migrations();
+
+		upgrade_runtime();
+		next_block();
+		assert_eq!(
+			take_events(),
+			vec![
+				Event::UpgradeStarted { migrations: 2 },
+				Event::MigrationSkipped { index: 0 },
+				Event::MigrationSkipped { index: 1 },
+				Event::UpgradeCompleted,
+			]
+		);
+
+		assert_ok!(Migrations::clear_historic(
+			RuntimeOrigin::root(),
+			vec![IdentifierOf::<Test>::truncate_from(vec![b'm', 1])],
+		));
+		upgrade_runtime();
+		next_block();
+		assert_eq!(
+			take_events(),
+			vec![
+				Event::HistoricCleared { count: 1 },
+				Event::UpgradeStarted { migrations: 2 },
+				Event::MigrationSkipped { index: 0 },
+				Event::MigrationCompleted { index: 1, took: 0 },
+				Event::UpgradeCompleted,
+			]
+		);
+	});
+}
+
+#[test]
+fn calls_are_paused_while_migrations_are_ongoing() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(remark().dispatch(RuntimeOrigin::signed(1)));
+
+		upgrade_runtime();
+		assert!(remark().dispatch(RuntimeOrigin::signed(1)).is_err());
+		assert_ok!(remark_with_event().dispatch(RuntimeOrigin::signed(1)));
+
+		while Migrations::ongoing() {
+			next_block();
+		}
+		assert_ok!(remark().dispatch(RuntimeOrigin::signed(1)));
+	});
+}
+
+#[test]
+fn failed_migration_gets_stuck_until_forced() {
+	new_test_ext().execute_with(|| {
+		FailingMigrations::set(vec![1]);
+		upgrade_runtime();
+		next_block();
+		next_block();
+		assert_eq!(
+			take_events(),
+			vec![
+				Event::UpgradeStarted { migrations: 2 },
+				Event::MigrationAdvanced { index: 0, took: 1 },
+				Event::MigrationAdvanced { index: 0, took: 1 },
+				Event::MigrationCompleted { index: 0, took: 2 },
+				Event::MigrationFailed { index: 1, took: 0 },
+				Event::UpgradeFailed,
+			]
+		);
+		assert_eq!(Migrations::status(), MultiStepStatus::Stuck);
+		// The failed step was reverted.
+		assert_eq!(unhashed::get::<u32>(&[1]), None);
+
+		// Stuck migrations do not make progress and keep calls paused.
+		assert_eq!(next_block(), Weight::zero());
+		assert!(remark().dispatch(RuntimeOrigin::signed(1)).is_err());
+
+		// Resume at the failed migration.
+		FailingMigrations::set(vec![]);
+		assert_ok!(Migrations::force_set_cursor(
+			RuntimeOrigin::root(),
+			Some(MigrationCursor::Active(ActiveCursor {
+				index: 1,
+				inner_cursor: None,
+				steps: 0,
+				started_at: System::block_number(),
+			})),
+		));
+		next_block();
+		assert_eq!(
+			take_events(),
+			vec![Event::MigrationCompleted { index: 1, took: 1 }, Event::UpgradeCompleted]
+		);
+		assert_ok!(remark().dispatch(RuntimeOrigin::signed(1)));
+	});
+}
+
+#[test]
+fn upgrade_during_migrations_gets_stuck() {
+	new_test_ext().execute_with(|| {
+		upgrade_runtime();
+		next_block();
+		take_events();
+
+		upgrade_runtime();
+		assert_eq!(take_events(), vec![Event::UpgradeFailed]);
+		assert_eq!(Migrations::status(), MultiStepStatus::Stuck);
+
+		assert_ok!(Migrations::force_set_cursor(RuntimeOrigin::root(), None));
+		assert_eq!(Migrations::status(), MultiStepStatus::Idle);
+	});
+}
@@ -0,0 +1,132 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! # Parameters Pallet
+//!
+//! Stores the values of the dynamic parameters of a runtime, so that they can be changed without
+//! a runtime upgrade.
+//!
+//! The parameters are declared with the [`frame_support::dynamic_params`] macro, which generates
+//! a key type per parameter implementing [`Get`](frame_support::traits::Get) and reading its
+//! value from this pallet, and the aggregated [`Config::RuntimeParameters`] type.
+//!
+//! Each parameter can be set, or reset to its default, with [`Pallet::set_parameter`] by the
+//! [`Config::AdminOrigin`] of its key, e.g. a different origin for each group of parameters.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![deny(rustdoc::broken_intra_doc_links)]
+
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+use frame_support::traits::{
+	dynamic_params::{AggregatedKeyValue, Key, RuntimeParameterStore},
+	EnsureOriginWithArg,
+};
+
+pub use pallet::*;
+pub use weights::WeightInfo;
+
+/// The aggregated key type of the parameters of the runtime.
+pub type KeyOf<T> = <<T as Config>::RuntimeParameters as AggregatedKeyValue>::Key;
+
+/// The aggregated value type of the parameters of the runtime.
+pub type ValueOf<T> = <<T as Config>::RuntimeParameters as AggregatedKeyValue>::Value;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The parameters of the runtime, usually `RuntimeParameters` as generated by
+		/// [`frame_support::dynamic_params`].
+		type RuntimeParameters: AggregatedKeyValue;
+
+		/// The origin which may set the parameter with the given key.
+		type AdminOrigin: EnsureOriginWithArg<Self::RuntimeOrigin, KeyOf<Self>>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A parameter was set, or reset to its default if `new_value` is `None`.
+		Updated {
+			/// The key of the parameter.
+			key: KeyOf<T>,
+			/// The value of the parameter before the update.
+			old_value: Option<ValueOf<T>>,
+			/// The value of the parameter after the update.
+			new_value: Option<ValueOf<T>>,
+		},
+	}
+
+	/// The values of the parameters which are set, by key.
+	#[pallet::storage]
+	pub type Parameters<T: Config> =
+		StorageMap<_, Blake2_128Concat, KeyOf<T>, ValueOf<T>, OptionQuery>;
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Set the value of a parameter, or reset it to its default if the value is `None`.
+		///
+		/// The origin must be the [`Config::AdminOrigin`] of the key of the parameter.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::set_parameter())]
+		pub fn set_parameter(
+			origin: OriginFor<T>,
+			key_value: T::RuntimeParameters,
+		) -> DispatchResult {
+			let (key, new_value) = key_value.into_parts();
+			T::AdminOrigin::ensure_origin(origin, &key)?;
+
+			let old_value =
+				Parameters::<T>::mutate(&key, |value| core::mem::replace(value, new_value.clone()));
+			Self::deposit_event(Event::Updated { key, old_value, new_value });
+
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> RuntimeParameterStore for Pallet<T> {
+	type AggregatedKeyValue = T::RuntimeParameters;
+
+	fn get<K>(key: K) -> Option<K::Value>
+	where
+		K: Key + Into<<Self::AggregatedKeyValue as AggregatedKeyValue>::Key>,
+		<Self::AggregatedKeyValue as AggregatedKeyValue>::Value: TryInto<K::WrappedValue>,
+	{
+		let key: KeyOf<T> = key.into();
+		let value = Parameters::<T>::get(key)?;
+		TryInto::<K::WrappedValue>::try_into(value).ok().map(Into::into)
+	}
+}
@@ -0,0 +1,114 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test utilities for the parameters pallet.
+
+use crate as pallet_parameters;
+
+use frame_support::{
+	derive_impl,
+	traits::{ConstU32, EnsureOriginWithArg},
+};
+use sp_runtime::BuildStorage;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test
+	{
+		System: frame_system,
+		Parameters: pallet_parameters,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+	type BlockHashCount = ConstU32<250>;
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type RuntimeEvent = RuntimeEvent;
+	type BaseCallFilter = frame_support::traits::Everything;
+	type PalletInfo = PalletInfo;
+	type OnSetCode = ();
+}
+
+#[frame_support::dynamic_params(RuntimeParameters, pallet_parameters::Pallet::<Test>)]
+pub mod dynamic_params {
+	use super::*;
+
+	/// Parameters only root can set.
+	#[codec(index = 3)]
+	pub mod pallet1 {
+		/// The first parameter.
+		#[codec(index = 0)]
+		pub static Key1: u64 = 0;
+		#[codec(index = 1)]
+		pub static Key2: u32 = 1;
+		#[codec(index = 2)]
+		pub static Key3: u128 = 2;
+	}
+
+	/// Parameters root and account `1` can set.
+	#[codec(index = 1)]
+	pub mod pallet2 {
+		#[codec(index = 2)]
+		pub static Key1: u64 = 0;
+		#[codec(index = 1)]
+		pub static Key2: u32 = 2;
+	}
+}
+
+pub use dynamic_params::{RuntimeParameters, RuntimeParametersKey, RuntimeParametersValue};
+
+/// Allows root to set all the parameters and account `1` to set those of `pallet2`.
+pub struct AdminOrigin;
+
+impl EnsureOriginWithArg<RuntimeOrigin, RuntimeParametersKey> for AdminOrigin {
+	type Success = ();
+
+	fn try_origin(
+		origin: RuntimeOrigin,
+		key: &RuntimeParametersKey,
+	) -> Result<Self::Success, RuntimeOrigin> {
+		let raw: Result<frame_system::RawOrigin<u64>, RuntimeOrigin> = origin.clone().into();
+		match (raw, key) {
+			(Ok(frame_system::RawOrigin::Root), _) => Ok(()),
+			(Ok(frame_system::RawOrigin::Signed(1)), RuntimeParametersKey::Pallet2(_)) => Ok(()),
+			_ => Err(origin),
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn try_successful_origin(_key: &RuntimeParametersKey) -> Result<RuntimeOrigin, ()> {
+		Ok(RuntimeOrigin::root())
+	}
+}
+
+impl pallet_parameters::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeParameters = RuntimeParameters;
+	type AdminOrigin = AdminOrigin;
+	type WeightInfo = ();
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
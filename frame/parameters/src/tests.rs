@@ -0,0 +1,134 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the parameters pallet.
+
+use crate::{
+	mock::{dynamic_params::*, *},
+	Event, Parameters as ParametersStorage,
+};
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{
+		dynamic_params::{AggregatedKeyValue, RuntimeParameterStore},
+		Get,
+	},
+};
+use sp_runtime::DispatchError;
+
+fn key1(value: Option<u64>) -> RuntimeParameters {
+	RuntimeParameters::Pallet1(pallet1::Parameters::Key1(pallet1::Key1, value))
+}
+
+fn pallet2_key2(value: Option<u32>) -> RuntimeParameters {
+	RuntimeParameters::Pallet2(pallet2::Parameters::Key2(pallet2::Key2, value))
+}
+
+#[test]
+fn set_parameter_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(pallet1::Key1::get(), 0);
+		assert_eq!(Parameters::get(pallet1::Key1), None);
+
+		assert_ok!(Parameters::set_parameter(RuntimeOrigin::root(), key1(Some(123))));
+
+		assert_eq!(pallet1::Key1::get(), 123);
+		assert_eq!(Parameters::get(pallet1::Key1), Some(123));
+		// Other parameters are not affected.
+		assert_eq!(pallet1::Key2::get(), 1);
+		assert_eq!(pallet2::Key1::get(), 0);
+		System::assert_last_event(
+			Event::<Test>::Updated {
+				key: RuntimeParametersKey::Pallet1(pallet1::ParametersKey::Key1(pallet1::Key1)),
+				old_value: None,
+				new_value: Some(RuntimeParametersValue::Pallet1(pallet1::ParametersValue::Key1(
+					123,
+				))),
+			}
+			.into(),
+		);
+
+		assert_ok!(Parameters::set_parameter(RuntimeOrigin::root(), key1(Some(456))));
+		assert_eq!(pallet1::Key1::get(), 456);
+		System::assert_last_event(
+			Event::<Test>::Updated {
+				key: pallet1::Key1.into(),
+				old_value: Some(RuntimeParametersValue::Pallet1(pallet1::ParametersValue::Key1(
+					123,
+				))),
+				new_value: Some(RuntimeParametersValue::Pallet1(pallet1::ParametersValue::Key1(
+					456,
+				))),
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn reset_parameter_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Parameters::set_parameter(RuntimeOrigin::root(), pallet2_key2(Some(7))));
+		assert_eq!(pallet2::Key2::get(), 7);
+
+		assert_ok!(Parameters::set_parameter(RuntimeOrigin::root(), pallet2_key2(None)));
+
+		assert_eq!(pallet2::Key2::get(), 2);
+		assert_eq!(ParametersStorage::<Test>::iter().count(), 0);
+		System::assert_last_event(
+			Event::<Test>::Updated {
+				key: pallet2::Key2.into(),
+				old_value: Some(RuntimeParametersValue::Pallet2(pallet2::ParametersValue::Key2(7))),
+				new_value: None,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn set_parameter_checks_the_origin_of_the_key() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Parameters::set_parameter(RuntimeOrigin::signed(1), key1(Some(1))),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Parameters::set_parameter(RuntimeOrigin::signed(2), pallet2_key2(Some(1))),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Parameters::set_parameter(RuntimeOrigin::none(), pallet2_key2(Some(1))),
+			DispatchError::BadOrigin
+		);
+
+		assert_ok!(Parameters::set_parameter(RuntimeOrigin::signed(1), pallet2_key2(Some(1))));
+		assert_eq!(pallet2::Key2::get(), 1);
+	});
+}
+
+#[test]
+fn aggregated_types_are_consistent() {
+	let (key, value) = key1(Some(5)).into_parts();
+	assert_eq!(key, RuntimeParametersKey::from(pallet1::Key1));
+	assert_eq!(value.clone().map(pallet1::Key1Value::try_from), Some(Ok(pallet1::Key1Value(5))));
+	// The value of another parameter, even of the same type, does not convert.
+	assert_eq!(value.map(pallet2::Key1Value::try_from), Some(Err(())));
+
+	// Keys of the same name in different modules are distinct.
+	assert_ne!(RuntimeParametersKey::from(pallet1::Key1), pallet2::Key1.into());
+}
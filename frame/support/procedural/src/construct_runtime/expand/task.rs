@@ -0,0 +1,142 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License
+
+use crate::construct_runtime::Pallet;
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use std::str::FromStr;
+
+/// Expands aggregate `RuntimeTask` enum.
+pub fn expand_outer_task(
+	runtime: &Ident,
+	pallet_decls: &[Pallet],
+	scrate: &TokenStream,
+) -> TokenStream {
+	let mut from_impls = Vec::new();
+	let mut task_variants = Vec::new();
+	let mut variant_names = Vec::new();
+	let mut task_paths = Vec::new();
+	let mut pallet_attrs = Vec::new();
+	for decl in pallet_decls {
+		if decl.find_part("Task").is_none() {
+			continue
+		}
+
+		let variant_name = &decl.name;
+		let path = &decl.path;
+		let index = decl.index;
+		let instance = decl.instance.as_ref().map(|instance| quote!(, #path::#instance));
+		let task_type = quote!(#path::Task<#runtime #instance>);
+		let attr = decl.cfg_pattern.iter().fold(TokenStream::new(), |acc, pattern| {
+			let attr = TokenStream::from_str(&format!("#[cfg({})]", pattern.original()))
+				.expect("was successfully parsed before; qed");
+			quote! {
+				#acc
+				#attr
+			}
+		});
+
+		from_impls.push(quote! {
+			#attr
+			impl From<#task_type> for RuntimeTask {
+				fn from(task: #task_type) -> Self {
+					RuntimeTask::#variant_name(task)
+				}
+			}
+		});
+		task_variants.push(quote! {
+			#attr
+			#[codec(index = #index)]
+			#variant_name(#task_type),
+		});
+		variant_names.push(variant_name);
+		task_paths.push(task_type);
+		pallet_attrs.push(attr);
+	}
+
+	quote! {
+		/// An aggregation of all `Task` enums across all pallets included in the current runtime.
+		#[derive(
+			Clone, Eq, PartialEq,
+			#scrate::__private::codec::Encode,
+			#scrate::__private::codec::Decode,
+			#scrate::__private::scale_info::TypeInfo,
+			#scrate::__private::RuntimeDebug,
+		)]
+		pub enum RuntimeTask {
+			#( #task_variants )*
+		}
+
+		impl #scrate::traits::Task for RuntimeTask {
+			type Enumeration = #scrate::__private::sp_std::boxed::Box<
+				dyn Iterator<Item = RuntimeTask>
+			>;
+
+			fn iter() -> Self::Enumeration {
+				let iter: Self::Enumeration =
+					#scrate::__private::sp_std::boxed::Box::new(#scrate::__private::sp_std::iter::empty());
+				#(
+					#pallet_attrs
+					let iter: Self::Enumeration = #scrate::__private::sp_std::boxed::Box::new(
+						iter.chain(
+							<#task_paths as #scrate::traits::Task>::iter().map(RuntimeTask::from)
+						)
+					);
+				)*
+				iter
+			}
+
+			fn is_valid(&self) -> bool {
+				match *self {
+					#(
+						#pallet_attrs
+						RuntimeTask::#variant_names(ref task) => #scrate::traits::Task::is_valid(task),
+					)*
+				}
+			}
+
+			fn run(&self) -> Result<(), #scrate::sp_runtime::DispatchError> {
+				match *self {
+					#(
+						#pallet_attrs
+						RuntimeTask::#variant_names(ref task) => #scrate::traits::Task::run(task),
+					)*
+				}
+			}
+
+			fn weight(&self) -> #scrate::weights::Weight {
+				match *self {
+					#(
+						#pallet_attrs
+						RuntimeTask::#variant_names(ref task) => #scrate::traits::Task::weight(task),
+					)*
+				}
+			}
+
+			fn task_index(&self) -> u32 {
+				match *self {
+					#(
+						#pallet_attrs
+						RuntimeTask::#variant_names(ref task) => #scrate::traits::Task::task_index(task),
+					)*
+				}
+			}
+		}
+
+		#( #from_impls )*
+	}
+}
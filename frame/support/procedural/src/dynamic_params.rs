@@ -0,0 +1,389 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Implementation of the `dynamic_params` attribute macro.
+
+use frame_support_procedural_tools::generate_crate_access_2018;
+use inflector::Inflector;
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+	parse::{Parse, ParseStream},
+	parse_quote,
+	spanned::Spanned,
+	Attribute, Error, Expr, Ident, Item, ItemMod, Result, Token, Type, Visibility,
+};
+
+mod keyword {
+	syn::custom_keyword!(index);
+}
+
+/// The arguments of the attribute: `$aggregate_name, $store`.
+struct Args {
+	name: Ident,
+	_comma: Token![,],
+	store: Type,
+}
+
+impl Parse for Args {
+	fn parse(input: ParseStream<'_>) -> Result<Self> {
+		Ok(Self { name: input.parse()?, _comma: input.parse()?, store: input.parse()? })
+	}
+}
+
+/// The content of a `#[codec(index = $index)]` attribute.
+struct CodecIndex(u8);
+
+impl Parse for CodecIndex {
+	fn parse(input: ParseStream<'_>) -> Result<Self> {
+		input.parse::<keyword::index>()?;
+		input.parse::<Token![=]>()?;
+		let index = input.parse::<syn::LitInt>()?;
+		if !index.suffix().is_empty() {
+			return Err(Error::new(index.span(), "Number literal must not have a suffix"))
+		}
+		Ok(Self(index.base10_parse()?))
+	}
+}
+
+/// A parameter, declared as `$vis static $Name: $Type = $default;`.
+struct Param {
+	docs: Vec<Attribute>,
+	vis: Visibility,
+	ident: Ident,
+	ty: Type,
+	default: Expr,
+	index: u8,
+}
+
+/// A group of parameters, declared as a module.
+struct Group {
+	docs: Vec<Attribute>,
+	ident: Ident,
+	index: u8,
+	params: Vec<Param>,
+}
+
+impl Group {
+	/// The name of the variant of the aggregated types for this group.
+	fn variant(&self) -> Ident {
+		format_ident!("{}", self.ident.to_string().to_pascal_case())
+	}
+}
+
+/// Removes the `#[codec(index = ..)]` attribute from `attrs` and returns its index.
+fn take_codec_index(attrs: &mut Vec<Attribute>, span: Span) -> Result<u8> {
+	let Some(position) = attrs.iter().position(|attr| attr.path().is_ident("codec")) else {
+		let msg = "Invalid dynamic_params, missing `#[codec(index = ..)]`";
+		return Err(Error::new(span, msg))
+	};
+	Ok(attrs.remove(position).parse_args::<CodecIndex>()?.0)
+}
+
+fn docs(attrs: &[Attribute]) -> Vec<Attribute> {
+	attrs.iter().filter(|attr| attr.path().is_ident("doc")).cloned().collect()
+}
+
+fn derives(crate_: &TokenStream) -> TokenStream {
+	quote! {
+		#[derive(
+			Clone, PartialEq, Eq,
+			#crate_::__private::codec::Encode,
+			#crate_::__private::codec::Decode,
+			#crate_::__private::codec::MaxEncodedLen,
+			#crate_::__private::scale_info::TypeInfo,
+			#crate_::__private::RuntimeDebug,
+		)]
+	}
+}
+
+pub fn dynamic_params(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
+	let Args { name, store, .. } = syn::parse2(attr)?;
+	let mut module: ItemMod = syn::parse2(item)?;
+	let crate_ = generate_crate_access_2018("frame-support")?;
+
+	let module_span = module.span();
+	let Some((_, items)) = module.content.as_mut() else {
+		let msg = "Invalid dynamic_params, expected an inline module";
+		return Err(Error::new(module_span, msg))
+	};
+
+	let mut groups = Vec::new();
+	for item in items.iter_mut() {
+		let Item::Mod(group_mod) = item else { continue };
+		let group = parse_group(group_mod)?;
+		let expanded = expand_group(&crate_, &store, &group);
+
+		let group_items = &mut group_mod.content.as_mut().expect("checked by `parse_group`").1;
+		group_items.insert(
+			0,
+			parse_quote!(
+				#[allow(unused_imports)]
+				use super::*;
+			),
+		);
+		group_items.push(Item::Verbatim(expanded));
+		groups.push(group);
+	}
+
+	if groups.is_empty() {
+		let msg = "Invalid dynamic_params, expected at least one module of parameters";
+		return Err(Error::new(module_span, msg))
+	}
+
+	items.push(Item::Verbatim(expand_aggregate(&crate_, &name, &groups)));
+	Ok(quote!(#module))
+}
+
+/// Parses a group of parameters, removing the parameters from the module.
+fn parse_group(group_mod: &mut ItemMod) -> Result<Group> {
+	let index = take_codec_index(&mut group_mod.attrs, group_mod.ident.span())?;
+	let group_span = group_mod.span();
+	let Some((_, items)) = group_mod.content.as_mut() else {
+		let msg = "Invalid dynamic_params, expected an inline module of parameters";
+		return Err(Error::new(group_span, msg))
+	};
+
+	let mut params = Vec::new();
+	let mut others = Vec::new();
+	for item in items.drain(..) {
+		let Item::Static(mut item) = item else {
+			others.push(item);
+			continue
+		};
+		if let syn::StaticMutability::Mut(mutability) = item.mutability {
+			let msg = "Invalid dynamic_params, parameters cannot be `mut`";
+			return Err(Error::new(mutability.span(), msg))
+		}
+		let index = take_codec_index(&mut item.attrs, item.ident.span())?;
+		if let Some(attr) = item.attrs.iter().find(|attr| !attr.path().is_ident("doc")) {
+			let msg = "Invalid dynamic_params, only doc attributes are supported on parameters";
+			return Err(Error::new(attr.span(), msg))
+		}
+		params.push(Param {
+			docs: item.attrs,
+			vis: item.vis,
+			ident: item.ident,
+			ty: *item.ty,
+			default: *item.expr,
+			index,
+		});
+	}
+	*items = others;
+
+	if params.is_empty() {
+		let msg = "Invalid dynamic_params, expected at least one `static` parameter";
+		return Err(Error::new(group_span, msg))
+	}
+
+	Ok(Group { docs: docs(&group_mod.attrs), ident: group_mod.ident.clone(), index, params })
+}
+
+/// Expands the key types of the parameters of a group, and the types aggregating them.
+fn expand_group(crate_: &TokenStream, store: &Type, group: &Group) -> TokenStream {
+	let derives = derives(crate_);
+	let docs = group.params.iter().map(|param| &param.docs).collect::<Vec<_>>();
+	let vis = group.params.iter().map(|param| &param.vis);
+	let key = group.params.iter().map(|param| &param.ident).collect::<Vec<_>>();
+	let wrapper = key.iter().map(|key| format_ident!("{}Value", key)).collect::<Vec<_>>();
+	let wrapper_doc = key.iter().map(|key| format!("The value of [`{}`].", key));
+	let ty = group.params.iter().map(|param| &param.ty).collect::<Vec<_>>();
+	let default = group.params.iter().map(|param| &param.default);
+	let index = group.params.iter().map(|param| param.index).collect::<Vec<_>>();
+	let (first_key, first_default) = (&key[0], &group.params[0].default);
+
+	quote! {
+		#(
+			#( #docs )*
+			#derives
+			#vis struct #key;
+
+			#[doc = #wrapper_doc]
+			#derives
+			#vis struct #wrapper(pub #ty);
+
+			impl #crate_::traits::dynamic_params::Key for #key {
+				type Value = #ty;
+				type WrappedValue = #wrapper;
+			}
+
+			impl From<#wrapper> for #ty {
+				fn from(value: #wrapper) -> Self {
+					value.0
+				}
+			}
+
+			impl #crate_::traits::Get<#ty> for #key {
+				fn get() -> #ty {
+					<#store as #crate_::traits::dynamic_params::RuntimeParameterStore>::get(#key)
+						.unwrap_or_else(|| #default)
+				}
+			}
+		)*
+
+		/// A parameter of this module together with its new value.
+		#derives
+		pub enum Parameters {
+			#(
+				#( #docs )*
+				#[codec(index = #index)]
+				#key(#key, Option<#ty>),
+			)*
+		}
+
+		/// A key of the parameters of this module.
+		#derives
+		pub enum ParametersKey {
+			#(
+				#( #docs )*
+				#[codec(index = #index)]
+				#key(#key),
+			)*
+		}
+
+		/// A value of the parameters of this module.
+		#derives
+		pub enum ParametersValue {
+			#(
+				#( #docs )*
+				#[codec(index = #index)]
+				#key(#ty),
+			)*
+		}
+
+		impl #crate_::traits::dynamic_params::AggregatedKeyValue for Parameters {
+			type Key = ParametersKey;
+			type Value = ParametersValue;
+
+			fn into_parts(self) -> (Self::Key, Option<Self::Value>) {
+				match self {
+					#(
+						Self::#key(key, value) =>
+							(ParametersKey::#key(key), value.map(ParametersValue::#key)),
+					)*
+				}
+			}
+		}
+
+		#[cfg(feature = "runtime-benchmarks")]
+		impl Default for Parameters {
+			fn default() -> Self {
+				Self::#first_key(#first_key, Some(#first_default))
+			}
+		}
+	}
+}
+
+/// Expands the types aggregating all the groups, and the conversions between them and the key
+/// and value types of each parameter.
+fn expand_aggregate(crate_: &TokenStream, name: &Ident, groups: &[Group]) -> TokenStream {
+	let derives = derives(crate_);
+	let key_name = format_ident!("{}Key", name);
+	let value_name = format_ident!("{}Value", name);
+	let docs = groups.iter().map(|group| &group.docs).collect::<Vec<_>>();
+	let group = groups.iter().map(|group| &group.ident).collect::<Vec<_>>();
+	let variant = groups.iter().map(Group::variant).collect::<Vec<_>>();
+	let index = groups.iter().map(|group| group.index).collect::<Vec<_>>();
+	let first_variant = &variant[0];
+
+	let conversions = groups.iter().flat_map(|group| {
+		let (group_ident, variant) = (&group.ident, group.variant());
+		group.params.iter().map(move |param| {
+			let key = &param.ident;
+			let wrapper = format_ident!("{}Value", key);
+			quote! {
+				impl From<#group_ident::#key> for #key_name {
+					fn from(key: #group_ident::#key) -> Self {
+						Self::#variant(#group_ident::ParametersKey::#key(key))
+					}
+				}
+
+				impl core::convert::TryFrom<#value_name> for #group_ident::#wrapper {
+					type Error = ();
+
+					fn try_from(value: #value_name) -> Result<Self, Self::Error> {
+						#[allow(unreachable_patterns)]
+						match value {
+							#value_name::#variant(#group_ident::ParametersValue::#key(value)) =>
+								Ok(Self(value)),
+							_ => Err(()),
+						}
+					}
+				}
+			}
+		})
+	});
+
+	quote! {
+		/// A dynamic parameter of the runtime together with its new value.
+		#derives
+		pub enum #name {
+			#(
+				#( #docs )*
+				#[codec(index = #index)]
+				#variant(#group::Parameters),
+			)*
+		}
+
+		/// A key of the dynamic parameters of the runtime.
+		#derives
+		pub enum #key_name {
+			#(
+				#( #docs )*
+				#[codec(index = #index)]
+				#variant(#group::ParametersKey),
+			)*
+		}
+
+		/// A value of the dynamic parameters of the runtime.
+		#derives
+		pub enum #value_name {
+			#(
+				#( #docs )*
+				#[codec(index = #index)]
+				#variant(#group::ParametersValue),
+			)*
+		}
+
+		impl #crate_::traits::dynamic_params::AggregatedKeyValue for #name {
+			type Key = #key_name;
+			type Value = #value_name;
+
+			fn into_parts(self) -> (Self::Key, Option<Self::Value>) {
+				match self {
+					#(
+						Self::#variant(parameters) => {
+							let (key, value) =
+								#crate_::traits::dynamic_params::AggregatedKeyValue::into_parts(
+									parameters,
+								);
+							(#key_name::#variant(key), value.map(#value_name::#variant))
+						},
+					)*
+				}
+			}
+		}
+
+		#[cfg(feature = "runtime-benchmarks")]
+		impl Default for #name {
+			fn default() -> Self {
+				Self::#first_variant(Default::default())
+			}
+		}
+
+		#( #conversions )*
+	}
+}
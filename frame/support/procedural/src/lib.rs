@@ -24,6 +24,7 @@ mod construct_runtime;
 mod crate_version;
 mod derive_impl;
 mod dummy_part_checker;
+mod dynamic_params;
 mod key_prefix;
 mod match_and_insert;
 mod no_bound;
@@ -139,6 +140,7 @@ fn counter_prefix(prefix: &str) -> String {
 ///     storage with `GenesisConfig`
 ///   - `Inherent` - If the pallet provides/can check inherents.
 ///   - `ValidateUnsigned` - If the pallet validates unsigned extrinsics.
+///   - `Task` - If the pallet declares tasks with `#[pallet::tasks]`.
 ///
 ///   It is important to list these parts here to export them correctly in the metadata or to make
 /// the pallet usable in the runtime.
@@ -553,6 +555,16 @@ pub fn storage_alias(attributes: TokenStream, input: TokenStream) -> TokenStream
 		.into()
 }
 
+/// Declare dynamic runtime parameters.
+///
+/// See `frame_support::dynamic_params` for the full documentation.
+#[proc_macro_attribute]
+pub fn dynamic_params(attrs: TokenStream, input: TokenStream) -> TokenStream {
+	dynamic_params::dynamic_params(attrs.into(), input.into())
+		.unwrap_or_else(|r| r.into_compile_error())
+		.into()
+}
+
 /// This attribute can be used to derive a full implementation of a trait based on a local partial
 /// impl and an external impl containing defaults that can be overriden in the local impl.
 ///
@@ -877,11 +889,12 @@ pub fn inject_runtime_type(_: TokenStream, tokens: TokenStream) -> TokenStream {
 	if item.ident != "RuntimeCall" &&
 		item.ident != "RuntimeEvent" &&
 		item.ident != "RuntimeOrigin" &&
+		item.ident != "RuntimeTask" &&
 		item.ident != "PalletInfo"
 	{
 		return syn::Error::new_spanned(
 			item,
-			"`#[inject_runtime_type]` can only be attached to `RuntimeCall`, `RuntimeEvent`, `RuntimeOrigin` or `PalletInfo`",
+			"`#[inject_runtime_type]` can only be attached to `RuntimeCall`, `RuntimeEvent`, `RuntimeOrigin`, `RuntimeTask` or `PalletInfo`",
 		)
 		.to_compile_error()
 		.into();
@@ -1160,6 +1173,103 @@ pub fn extra_constants(_: TokenStream, _: TokenStream) -> TokenStream {
 	pallet_macro_stub()
 }
 
+/// Allows you to define view functions, i.e. read-only queries of the pallet state that can be
+/// called from outside the runtime.
+///
+/// Item must be defined as:
+///
+/// ```ignore
+/// #[pallet::view_functions]
+/// impl<T: Config> Pallet<T> where $optional_where_clause {
+/// 	/// $some_doc
+/// 	$vis fn $fn_name($arg: $arg_type, ...) -> $some_return_type {
+/// 		...
+/// 	}
+/// 	...
+/// }
+/// ```
+/// I.e. a regular rust `impl` block with some optional where clause and functions with 0
+/// generics, no `self` receiver, and some return type. Arguments and return type must implement
+/// `Encode`, `Decode` and `TypeInfo`.
+///
+/// ## Macro expansion
+///
+/// For each function the macro generates a struct named after the function, e.g.
+/// `GetValueViewFunction` for `get_value`, holding its arguments and implementing
+/// `frame_support::view_functions::ViewFunction`. The functions are added to the pallet metadata,
+/// and `construct_runtime!` makes all of them callable through the
+/// `frame_support::view_functions::runtime_api::RuntimeViewFunction` runtime API.
+///
+/// V14 metadata has no place for view functions. V15 metadata lists them in the custom entry
+/// `sp_metadata_ir::VIEW_FUNCTIONS_CUSTOM_KEY`.
+#[proc_macro_attribute]
+pub fn view_functions(_: TokenStream, _: TokenStream) -> TokenStream {
+	pallet_macro_stub()
+}
+
+/// Allows you to define tasks, i.e. units of work that anybody can trigger once their condition
+/// holds, without a dedicated dispatchable.
+///
+/// Item must be defined as:
+///
+/// ```ignore
+/// #[pallet::tasks]
+/// impl<T: Config> Pallet<T> where $optional_where_clause {
+/// 	/// $some_doc
+/// 	#[pallet::task_index($index)]
+/// 	#[pallet::task_list($candidates)]
+/// 	#[pallet::task_condition(|$arg, ...| $condition)]
+/// 	#[pallet::task_weight($weight)]
+/// 	$vis fn $fn_name($arg: $arg_type, ...) -> DispatchResult {
+/// 		...
+/// 	}
+/// 	...
+/// }
+/// ```
+/// I.e. a regular rust `impl` block with some optional where clause and functions with 0
+/// generics and no `self` receiver, each carrying the four attributes below.
+///
+/// ## Macro expansion
+///
+/// The macro generates an enum `Task` with a variant per function, holding its arguments, and
+/// implements `frame_support::traits::Task` on it. `construct_runtime!` aggregates the `Task`
+/// enums of all pallets into `RuntimeTask`, which is run by `frame_system::Call::do_task`.
+#[proc_macro_attribute]
+pub fn tasks(_: TokenStream, _: TokenStream) -> TokenStream {
+	pallet_macro_stub()
+}
+
+/// Each task inside `#[pallet::tasks]` must have the `#[pallet::task_index($index)]` attribute,
+/// where `$index` is a unique `u8` identifying the task in the encoded `Task` enum.
+#[proc_macro_attribute]
+pub fn task_index(_: TokenStream, _: TokenStream) -> TokenStream {
+	pallet_macro_stub()
+}
+
+/// Each task inside `#[pallet::tasks]` must have the `#[pallet::task_list($candidates)]`
+/// attribute. `$candidates` is an expression evaluating to an `IntoIterator` of the candidate
+/// arguments of the task: the argument itself if the task has one, a tuple of them otherwise.
+#[proc_macro_attribute]
+pub fn task_list(_: TokenStream, _: TokenStream) -> TokenStream {
+	pallet_macro_stub()
+}
+
+/// Each task inside `#[pallet::tasks]` must have the `#[pallet::task_condition($condition)]`
+/// attribute. `$condition` is a closure taking a reference to each argument of the task and
+/// returning whether the task can currently run with them.
+#[proc_macro_attribute]
+pub fn task_condition(_: TokenStream, _: TokenStream) -> TokenStream {
+	pallet_macro_stub()
+}
+
+/// Each task inside `#[pallet::tasks]` must have the `#[pallet::task_weight($weight)]`
+/// attribute. `$weight` is an expression evaluating to the `Weight` of the task, in which the
+/// arguments of the task are available by reference.
+#[proc_macro_attribute]
+pub fn task_weight(_: TokenStream, _: TokenStream) -> TokenStream {
+	pallet_macro_stub()
+}
+
 /// The `#[pallet::error]` attribute allows you to define an error enum that will be returned
 /// from the dispatchable when an error occurs. The information for this error type is then
 /// stored in metadata.
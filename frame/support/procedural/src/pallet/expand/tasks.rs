@@ -0,0 +1,158 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::pallet::Def;
+
+///
+/// * Generate enum `Task` with a variant per task.
+/// * Implement `frame_support::traits::Task` on it.
+pub fn expand_tasks(def: &mut Def) -> proc_macro2::TokenStream {
+	let Some(tasks) = def.tasks.as_ref() else { return Default::default() };
+
+	let frame_support = &def.frame_support;
+	let span = tasks.attr_span;
+	let type_impl_gen = &def.type_impl_generics(span);
+	let type_decl_bounded_gen = &def.type_decl_bounded_generics(span);
+	let type_use_gen = &def.type_use_generics(span);
+	let pallet_ident = &def.pallet_struct.pallet;
+	let where_clause = super::merge_where_clauses(&[&def.config.where_clause, &tasks.where_clause]);
+
+	let fn_name = tasks.tasks.iter().map(|task| &task.ident).collect::<Vec<_>>();
+	let task_index = tasks.tasks.iter().map(|task| task.task_index).collect::<Vec<_>>();
+	let list = tasks.tasks.iter().map(|task| &task.list);
+	let condition = tasks.tasks.iter().map(|task| &task.condition);
+	let weight = tasks.tasks.iter().map(|task| &task.weight);
+	let args_name = tasks
+		.tasks
+		.iter()
+		.map(|task| task.args.iter().map(|(name, _)| name).collect::<Vec<_>>())
+		.collect::<Vec<_>>();
+	let args_type = tasks
+		.tasks
+		.iter()
+		.map(|task| task.args.iter().map(|(_, type_)| type_).collect::<Vec<_>>());
+
+	// The pattern matching an item of `task_list`: the argument itself for a single argument, a
+	// tuple of the arguments otherwise.
+	let list_item_pattern = args_name.iter().map(|args_name| match &args_name[..] {
+		[] => quote::quote!(_),
+		[arg] => quote::quote!(#arg),
+		args => quote::quote!(( #( #args ),* )),
+	});
+
+	let fn_doc = fn_name.iter().map(|fn_name| {
+		if def.dev_mode {
+			format!("See `Pallet::{}`.", fn_name)
+		} else {
+			format!("See [`Pallet::{}`].", fn_name)
+		}
+	});
+
+	let capture_docs = if cfg!(feature = "no-metadata-docs") { "never" } else { "always" };
+
+	quote::quote_spanned!(span =>
+		/// Contains a variant per task that this pallet has.
+		#[derive(
+			#frame_support::RuntimeDebugNoBound,
+			#frame_support::CloneNoBound,
+			#frame_support::EqNoBound,
+			#frame_support::PartialEqNoBound,
+			#frame_support::__private::codec::Encode,
+			#frame_support::__private::codec::Decode,
+			#frame_support::__private::scale_info::TypeInfo,
+		)]
+		#[codec(encode_bound())]
+		#[codec(decode_bound())]
+		#[scale_info(skip_type_params(#type_use_gen), capture_docs = #capture_docs)]
+		#[allow(non_camel_case_types)]
+		pub enum Task<#type_decl_bounded_gen> #where_clause {
+			#[doc(hidden)]
+			#[codec(skip)]
+			__Ignore(
+				#frame_support::__private::sp_std::marker::PhantomData<(#type_use_gen,)>,
+				#frame_support::Never,
+			),
+			#(
+				#[doc = #fn_doc]
+				#[codec(index = #task_index)]
+				#fn_name {
+					#(
+						#[allow(missing_docs)]
+						#args_name: #args_type
+					),*
+				},
+			)*
+		}
+
+		impl<#type_impl_gen> #frame_support::traits::Task for Task<#type_use_gen> #where_clause {
+			type Enumeration = #frame_support::__private::sp_std::boxed::Box<
+				dyn Iterator<Item = Self>
+			>;
+
+			fn iter() -> Self::Enumeration {
+				#frame_support::__private::sp_std::boxed::Box::new(
+					#frame_support::__private::sp_std::iter::empty()
+					#(
+						.chain(
+							#frame_support::__private::sp_std::iter::IntoIterator::into_iter(#list)
+								.map(|#list_item_pattern| Self::#fn_name { #( #args_name ),* })
+						)
+					)*
+				)
+			}
+
+			fn is_valid(&self) -> bool {
+				match self {
+					#(
+						Self::#fn_name { #( #args_name ),* } => (#condition)( #( #args_name ),* ),
+					)*
+					Self::__Ignore(_, _) => unreachable!("__Ignore cannot be used"),
+				}
+			}
+
+			fn run(&self) -> Result<(), #frame_support::sp_runtime::DispatchError> {
+				match self.clone() {
+					#(
+						Self::#fn_name { #( #args_name ),* } =>
+							<#pallet_ident<#type_use_gen>>::#fn_name( #( #args_name ),* )
+								.map_err(Into::into),
+					)*
+					Self::__Ignore(_, _) => unreachable!("__Ignore cannot be used"),
+				}
+			}
+
+			fn weight(&self) -> #frame_support::weights::Weight {
+				match self {
+					#(
+						Self::#fn_name { #( #args_name ),* } => {
+							let _ = ( #( #args_name, )* );
+							#weight
+						},
+					)*
+					Self::__Ignore(_, _) => unreachable!("__Ignore cannot be used"),
+				}
+			}
+
+			fn task_index(&self) -> u32 {
+				match self {
+					#( Self::#fn_name { .. } => #task_index as u32, )*
+					Self::__Ignore(_, _) => unreachable!("__Ignore cannot be used"),
+				}
+			}
+		}
+	)
+}
@@ -55,6 +55,8 @@ pub fn expand_tt_default_parts(def: &mut Def) -> proc_macro2::TokenStream {
 	let validate_unsigned_part =
 		def.validate_unsigned.as_ref().map(|_| quote::quote!(ValidateUnsigned,));
 
+	let task_part = def.tasks.as_ref().map(|_| quote::quote!(Task,));
+
 	let freeze_reason_part = def
 		.composites
 		.iter()
@@ -101,7 +103,7 @@ pub fn expand_tt_default_parts(def: &mut Def) -> proc_macro2::TokenStream {
 					tokens = [{
 						expanded::{
 							Pallet, #call_part #storage_part #event_part #error_part #origin_part #config_part
-							#inherent_part #validate_unsigned_part #freeze_reason_part
+							#inherent_part #validate_unsigned_part #task_part #freeze_reason_part
 							#hold_reason_part #lock_id_part #slash_reason_part
 						}
 					}]
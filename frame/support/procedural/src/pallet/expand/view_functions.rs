@@ -0,0 +1,181 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::pallet::{parse::view_functions::ViewFunctionDef, Def};
+use inflector::Inflector;
+use proc_macro2::{Span, TokenStream};
+use quote::format_ident;
+
+/// * Generate a struct holding the arguments of each view function, implementing `ViewFunction`.
+/// * Impl `ViewFunctionIdPrefix` and `DispatchViewFunction` for pallet.
+/// * Impl fn `pallet_view_functions_metadata` for pallet.
+pub fn expand_view_functions(def: &mut Def) -> TokenStream {
+	let frame_support = &def.frame_support;
+	let span = Span::call_site();
+	let type_impl_gen = &def.type_impl_generics(span);
+	let type_decl_bounded_gen = &def.type_decl_bounded_generics(span);
+	let type_use_gen = &def.type_use_generics(span);
+	let pallet_ident = &def.pallet_struct.pallet;
+
+	let mut where_clauses = vec![&def.config.where_clause];
+	where_clauses.extend(def.view_functions.iter().map(|d| &d.where_clause));
+	let where_clause = super::merge_where_clauses(&where_clauses);
+
+	let view_functions =
+		def.view_functions.iter().flat_map(|d| &d.view_functions).collect::<Vec<_>>();
+	let struct_idents = view_functions.iter().map(|f| struct_ident(f)).collect::<Vec<_>>();
+
+	let structs = view_functions.iter().zip(&struct_idents).map(|(view_fn, struct_ident)| {
+		let fn_ident = &view_fn.ident;
+		let arg_idents = view_fn.args.iter().map(|(ident, _)| ident).collect::<Vec<_>>();
+		let arg_types = view_fn.args.iter().map(|(_, ty)| ty);
+		let return_type = &view_fn.return_type;
+		let signature = syn::LitByteStr::new(view_fn.signature.as_bytes(), span);
+		let doc = format!("The arguments of the [`Pallet::{}`] view function.", fn_ident);
+
+		quote::quote!(
+			#[doc = #doc]
+			#[derive(#frame_support::__private::codec::Encode, #frame_support::__private::codec::Decode)]
+			pub struct #struct_ident<#type_decl_bounded_gen> #where_clause {
+				#( pub #arg_idents: #arg_types, )*
+				#[codec(skip)]
+				_marker: #frame_support::__private::sp_std::marker::PhantomData<(#type_use_gen,)>,
+			}
+
+			impl<#type_impl_gen> #struct_ident<#type_use_gen> #where_clause {
+				/// Create the arguments of the view function.
+				pub fn new(#( #arg_idents: #arg_types, )*) -> Self {
+					Self { #( #arg_idents, )* _marker: Default::default() }
+				}
+			}
+
+			impl<#type_impl_gen> #frame_support::view_functions::ViewFunctionIdSuffix
+				for #struct_ident<#type_use_gen> #where_clause
+			{
+				const SUFFIX: [u8; 16] =
+					#frame_support::__private::sp_core_hashing_proc_macro::twox_128!(#signature);
+			}
+
+			impl<#type_impl_gen> #frame_support::view_functions::ViewFunction
+				for #struct_ident<#type_use_gen> #where_clause
+			{
+				fn id() -> #frame_support::view_functions::ViewFunctionId {
+					#frame_support::view_functions::ViewFunctionId {
+						prefix: <#pallet_ident<#type_use_gen> as
+							#frame_support::view_functions::ViewFunctionIdPrefix>::prefix(),
+						suffix: <Self as #frame_support::view_functions::ViewFunctionIdSuffix>::SUFFIX,
+					}
+				}
+
+				type ReturnType = #return_type;
+
+				fn invoke(self) -> Self::ReturnType {
+					let Self { #( #arg_idents, )* .. } = self;
+					<#pallet_ident<#type_use_gen>>::#fn_ident(#( #arg_idents, )*)
+				}
+			}
+		)
+	});
+
+	let view_functions_metadata =
+		view_functions.iter().zip(&struct_idents).map(|(view_fn, struct_ident)| {
+			let name = view_fn.ident.to_string();
+			let inputs = view_fn.args.iter().map(|(ident, ty)| {
+				let name = ident.to_string();
+				quote::quote!(
+					#frame_support::__private::metadata_ir::PalletViewFunctionParamMetadataIR {
+						name: #name,
+						ty: #frame_support::__private::scale_info::meta_type::<#ty>(),
+					}
+				)
+			});
+			let return_type = &view_fn.return_type;
+
+			let no_docs = vec![];
+			let doc = if cfg!(feature = "no-metadata-docs") { &no_docs } else { &view_fn.doc };
+
+			quote::quote!({
+				#frame_support::__private::metadata_ir::PalletViewFunctionMetadataIR {
+					name: #name,
+					id: <#struct_ident<#type_use_gen> as
+						#frame_support::view_functions::ViewFunction>::id().into(),
+					inputs: #frame_support::__private::sp_std::vec![ #( #inputs ),* ],
+					output: #frame_support::__private::scale_info::meta_type::<#return_type>(),
+					docs: #frame_support::__private::sp_std::vec![ #( #doc ),* ],
+				}
+			})
+		});
+
+	// The arguments are unused when the pallet has no view functions.
+	let (input, output) = if view_functions.is_empty() {
+		(format_ident!("_input"), format_ident!("_output"))
+	} else {
+		(format_ident!("input"), format_ident!("output"))
+	};
+
+	quote::quote!(
+		#( #structs )*
+
+		impl<#type_impl_gen> #frame_support::view_functions::ViewFunctionIdPrefix
+			for #pallet_ident<#type_use_gen> #where_clause
+		{
+			fn prefix() -> [u8; 16] {
+				#frame_support::__private::sp_io::hashing::twox_128(
+					<Self as #frame_support::traits::PalletInfoAccess>::name().as_bytes(),
+				)
+			}
+		}
+
+		impl<#type_impl_gen> #frame_support::view_functions::DispatchViewFunction
+			for #pallet_ident<#type_use_gen> #where_clause
+		{
+			fn dispatch_view_function<O: #frame_support::__private::codec::Output>(
+				id: &#frame_support::view_functions::ViewFunctionId,
+				#input: &mut &[u8],
+				#output: &mut O,
+			) -> Result<(), #frame_support::view_functions::ViewFunctionDispatchError> {
+				#(
+					if id.suffix ==
+						<#struct_idents<#type_use_gen> as
+							#frame_support::view_functions::ViewFunctionIdSuffix>::SUFFIX
+					{
+						return <#struct_idents<#type_use_gen> as
+							#frame_support::view_functions::ViewFunction>::execute(input, output)
+					}
+				)*
+				Err(#frame_support::view_functions::ViewFunctionDispatchError::NotFound(id.clone()))
+			}
+		}
+
+		impl<#type_impl_gen> #pallet_ident<#type_use_gen> #where_clause {
+			#[doc(hidden)]
+			pub fn pallet_view_functions_metadata()
+				-> #frame_support::__private::sp_std::vec::Vec<
+					#frame_support::__private::metadata_ir::PalletViewFunctionMetadataIR
+				>
+			{
+				#frame_support::__private::sp_std::vec![ #( #view_functions_metadata ),* ]
+			}
+		}
+	)
+}
+
+/// The name of the struct holding the arguments of `view_fn`, e.g. `GetValueViewFunction` for
+/// `fn get_value`.
+fn struct_ident(view_fn: &ViewFunctionDef) -> syn::Ident {
+	format_ident!("{}ViewFunction", view_fn.ident.to_string().to_pascal_case())
+}
@@ -0,0 +1,239 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::helper;
+use frame_support_procedural_tools::get_doc_literals;
+use std::collections::HashMap;
+use syn::spanned::Spanned;
+
+/// List of additional token to be used for parsing.
+mod keyword {
+	syn::custom_keyword!(pallet);
+	syn::custom_keyword!(task_index);
+	syn::custom_keyword!(task_list);
+	syn::custom_keyword!(task_condition);
+	syn::custom_keyword!(task_weight);
+}
+
+/// Definition of tasks typically `impl<T: Config> Pallet<T> { ... }`
+pub struct TasksDef {
+	/// The where_clause used.
+	pub where_clause: Option<syn::WhereClause>,
+	/// A set of usage of instance, must be check for consistency with trait.
+	pub instances: Vec<helper::InstanceUsage>,
+	/// The index of tasks item in pallet module.
+	pub index: usize,
+	/// The span of the `pallet::tasks` attribute.
+	pub attr_span: proc_macro2::Span,
+	/// The tasks defined.
+	pub tasks: Vec<TaskDef>,
+}
+
+/// Input definition for a task in pallet.
+pub struct TaskDef {
+	/// Name of the function
+	pub ident: syn::Ident,
+	/// The arguments of the function, with their names and types
+	pub args: Vec<(syn::Ident, syn::Type)>,
+	/// The index of the task, from `#[pallet::task_index(..)]`
+	pub task_index: u8,
+	/// The candidate items, from `#[pallet::task_list(..)]`
+	pub list: syn::Expr,
+	/// The validity condition, from `#[pallet::task_condition(..)]`
+	pub condition: syn::Expr,
+	/// The weight, from `#[pallet::task_weight(..)]`
+	pub weight: syn::Expr,
+	/// The doc associated
+	pub doc: Vec<syn::Expr>,
+}
+
+/// Attributes for functions in tasks impl block.
+pub enum TaskAttr {
+	/// Parse for `#[pallet::task_index(expr)]`
+	Index(proc_macro2::Span, u8),
+	/// Parse for `#[pallet::task_list(expr)]`
+	List(proc_macro2::Span, syn::Expr),
+	/// Parse for `#[pallet::task_condition(expr)]`
+	Condition(proc_macro2::Span, syn::Expr),
+	/// Parse for `#[pallet::task_weight(expr)]`
+	Weight(proc_macro2::Span, syn::Expr),
+}
+
+impl TaskAttr {
+	fn span(&self) -> proc_macro2::Span {
+		match self {
+			Self::Index(span, _) |
+			Self::List(span, _) |
+			Self::Condition(span, _) |
+			Self::Weight(span, _) => *span,
+		}
+	}
+}
+
+impl syn::parse::Parse for TaskAttr {
+	fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+		input.parse::<syn::Token![#]>()?;
+		let content;
+		syn::bracketed!(content in input);
+		content.parse::<keyword::pallet>()?;
+		content.parse::<syn::Token![::]>()?;
+
+		let lookahead = content.lookahead1();
+		if lookahead.peek(keyword::task_index) {
+			let span = content.parse::<keyword::task_index>()?.span();
+			let index_content;
+			syn::parenthesized!(index_content in content);
+			let index = index_content.parse::<syn::LitInt>()?;
+			if !index.suffix().is_empty() {
+				let msg = "Number literal must not have a suffix";
+				return Err(syn::Error::new(index.span(), msg))
+			}
+			Ok(TaskAttr::Index(span, index.base10_parse()?))
+		} else if lookahead.peek(keyword::task_list) {
+			let span = content.parse::<keyword::task_list>()?.span();
+			let list_content;
+			syn::parenthesized!(list_content in content);
+			Ok(TaskAttr::List(span, list_content.parse()?))
+		} else if lookahead.peek(keyword::task_condition) {
+			let span = content.parse::<keyword::task_condition>()?.span();
+			let condition_content;
+			syn::parenthesized!(condition_content in content);
+			Ok(TaskAttr::Condition(span, condition_content.parse()?))
+		} else if lookahead.peek(keyword::task_weight) {
+			let span = content.parse::<keyword::task_weight>()?.span();
+			let weight_content;
+			syn::parenthesized!(weight_content in content);
+			Ok(TaskAttr::Weight(span, weight_content.parse()?))
+		} else {
+			Err(lookahead.error())
+		}
+	}
+}
+
+impl TasksDef {
+	pub fn try_from(
+		attr_span: proc_macro2::Span,
+		index: usize,
+		item: &mut syn::Item,
+	) -> syn::Result<Self> {
+		let item = if let syn::Item::Impl(item) = item {
+			item
+		} else {
+			return Err(syn::Error::new(item.span(), "Invalid pallet::tasks, expected item impl"))
+		};
+
+		let instances = vec![
+			helper::check_impl_gen(&item.generics, item.impl_token.span())?,
+			helper::check_pallet_struct_usage(&item.self_ty)?,
+		];
+
+		if let Some((_, _, for_)) = item.trait_ {
+			let msg = "Invalid pallet::tasks, expected no trait ident as in \
+				`impl<..> Pallet<..> { .. }`";
+			return Err(syn::Error::new(for_.span(), msg))
+		}
+
+		let mut tasks = vec![];
+		let mut indices = HashMap::new();
+		for impl_item in &mut item.items {
+			let method = if let syn::ImplItem::Fn(method) = impl_item {
+				method
+			} else {
+				let msg = "Invalid pallet::tasks, only method accepted";
+				return Err(syn::Error::new(impl_item.span(), msg))
+			};
+
+			if !method.sig.generics.params.is_empty() {
+				let msg = "Invalid pallet::tasks, method must have 0 generics";
+				return Err(syn::Error::new(method.sig.generics.params[0].span(), msg))
+			}
+
+			if method.sig.generics.where_clause.is_some() {
+				let msg = "Invalid pallet::tasks, method must have no where clause";
+				return Err(syn::Error::new(method.sig.generics.where_clause.span(), msg))
+			}
+
+			let mut args = vec![];
+			for input in &method.sig.inputs {
+				let syn::FnArg::Typed(arg) = input else {
+					let msg = "Invalid pallet::tasks, method must not take `self`";
+					return Err(syn::Error::new(input.span(), msg))
+				};
+				let syn::Pat::Ident(ref pat) = *arg.pat else {
+					let msg = "Invalid pallet::tasks, argument must be an identifier";
+					return Err(syn::Error::new(arg.pat.span(), msg))
+				};
+				args.push((pat.ident.clone(), *arg.ty.clone()));
+			}
+
+			let mut task_index = None;
+			let mut list = None;
+			let mut condition = None;
+			let mut weight = None;
+			for attr in helper::take_item_pallet_attrs::<TaskAttr>(method)? {
+				let span = attr.span();
+				let duplicate = match attr {
+					TaskAttr::Index(_, i) => task_index.replace(i).is_some(),
+					TaskAttr::List(_, e) => list.replace(e).is_some(),
+					TaskAttr::Condition(_, e) => condition.replace(e).is_some(),
+					TaskAttr::Weight(_, e) => weight.replace(e).is_some(),
+				};
+				if duplicate {
+					let msg = "Invalid pallet::tasks, duplicated attribute";
+					return Err(syn::Error::new(span, msg))
+				}
+			}
+
+			let missing = |name: &str| {
+				let msg = format!("Invalid pallet::tasks, missing `#[pallet::{}(..)]`", name);
+				syn::Error::new(method.sig.span(), msg)
+			};
+			let task_index = task_index.ok_or_else(|| missing("task_index"))?;
+			let list = list.ok_or_else(|| missing("task_list"))?;
+			let condition = condition.ok_or_else(|| missing("task_condition"))?;
+			let weight = weight.ok_or_else(|| missing("task_weight"))?;
+
+			if let Some(used_fn) = indices.insert(task_index, method.sig.ident.clone()) {
+				let msg = format!(
+					"Task indices are conflicting: Both functions {} and {} are at index {}",
+					used_fn, method.sig.ident, task_index,
+				);
+				let mut err = syn::Error::new(used_fn.span(), &msg);
+				err.combine(syn::Error::new(method.sig.ident.span(), msg));
+				return Err(err)
+			}
+
+			tasks.push(TaskDef {
+				ident: method.sig.ident.clone(),
+				args,
+				task_index,
+				list,
+				condition,
+				weight,
+				doc: get_doc_literals(&method.attrs),
+			});
+		}
+
+		Ok(Self {
+			index,
+			instances,
+			attr_span,
+			where_clause: item.generics.where_clause.clone(),
+			tasks,
+		})
+	}
+}
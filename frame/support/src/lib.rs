@@ -69,6 +69,7 @@ pub mod storage;
 #[cfg(test)]
 mod tests;
 pub mod traits;
+pub mod view_functions;
 pub mod weights;
 #[doc(hidden)]
 pub mod unsigned {
@@ -169,6 +170,41 @@ pub use frame_support_procedural::storage_alias;
 
 pub use frame_support_procedural::derive_impl;
 
+/// Declare dynamic runtime parameters, i.e. parameters whose value is kept in a store such as
+/// `pallet-parameters` and can be changed without a runtime upgrade.
+///
+/// The macro is applied to a module containing a module per group of parameters. Each
+/// parameter is declared as a `static` item whose initializer is its default value:
+///
+/// ```ignore
+/// #[frame_support::dynamic_params(RuntimeParameters, pallet_parameters::Pallet::<Runtime>)]
+/// pub mod dynamic_params {
+/// 	use super::*;
+///
+/// 	/// Parameters of the storage deposits.
+/// 	#[codec(index = 0)]
+/// 	pub mod storage {
+/// 		/// The base deposit of storing some data.
+/// 		#[codec(index = 0)]
+/// 		pub static BaseDeposit: Balance = 1 * DOLLARS;
+/// 	}
+/// }
+/// ```
+///
+/// The first argument is the name of the generated aggregated types, the second the
+/// [`RuntimeParameterStore`](traits::dynamic_params::RuntimeParameterStore) the values are
+/// read from. Paths are resolved from within each group module, which imports its parent with
+/// `use super::*`.
+///
+/// For each parameter, e.g. `BaseDeposit`, the macro generates a key type `BaseDeposit`
+/// implementing [`Get`](traits::Get), which returns the value in the store or the default. For
+/// each group, it generates the `Parameters`, `ParametersKey` and `ParametersValue` enums with
+/// a variant per parameter, and it aggregates all the groups into `RuntimeParameters`,
+/// `RuntimeParametersKey` and `RuntimeParametersValue`. `RuntimeParameters` implements
+/// [`AggregatedKeyValue`](traits::dynamic_params::AggregatedKeyValue) and is meant to be the
+/// `RuntimeParameters` of `pallet-parameters`, which exposes the keys in the metadata.
+pub use frame_support_procedural::dynamic_params;
+
 /// Create new implementations of the [`Get`](crate::traits::Get) trait.
 ///
 /// The so-called parameter type can be created in four different ways:
@@ -900,6 +936,8 @@ pub mod pallet_prelude {
 /// * [`pallet::compact`](#palletcompact-some_arg-some_type)
 /// * [`pallet::call_index($idx)`](#palletcall_indexidx)
 /// * [`pallet::extra_constants`](#extra-constants-palletextra_constants-optional)
+/// * [`pallet::view_functions`](#view-functions-palletview_functions-optional)
+/// * [`pallet::tasks`](#tasks-pallettasks-optional)
 /// * [`pallet::error`](#error-palleterror-optional)
 /// * [`pallet::event`](#event-palletevent-optional)
 /// * [`pallet::generate_deposit($visibility fn
@@ -1230,6 +1268,82 @@ pub mod pallet_prelude {
 ///
 /// Also see: [`pallet::extra_constants`](`frame_support::pallet_macros::extra_constants`)
 ///
+/// # View functions: `#[pallet::view_functions]` (optional)
+///
+/// Allows you to define read-only queries of the pallet state which can be called from outside
+/// the runtime, without a dedicated runtime API.
+///
+/// Item must be defined as:
+///
+/// ```ignore
+/// #[pallet::view_functions]
+/// impl<T: Config> Pallet<T> where $optional_where_clause {
+/// 	/// $some_doc
+/// 	$vis fn $fn_name($arg: $arg_type, ...) -> $some_return_type {
+/// 		...
+/// 	}
+/// 	...
+/// }
+/// ```
+/// I.e. a regular rust `impl` block with some optional where clause and functions with 0
+/// generics, no `self` receiver, and some return type.
+///
+/// ## Macro expansion
+///
+/// Each function gets a [`ViewFunctionId`](view_functions::ViewFunctionId) made of the hash of
+/// the pallet name and the hash of the function signature, and is added to the pallet
+/// metadata. `construct_runtime!` implements `Runtime::execute_view_function`, dispatching a
+/// query to the right pallet, which the runtime exposes through the
+/// [`RuntimeViewFunction`](view_functions::runtime_api::RuntimeViewFunction) runtime API.
+///
+/// Also see: [`pallet::view_functions`](`frame_support::pallet_macros::view_functions`)
+///
+/// # Tasks: `#[pallet::tasks]` (optional)
+///
+/// Allows you to define units of work which anybody can run once their condition holds, e.g.
+/// cleaning up stale storage, without writing a dispatchable for each of them.
+///
+/// Item must be defined as:
+///
+/// ```ignore
+/// #[pallet::tasks]
+/// impl<T: Config> Pallet<T> where $optional_where_clause {
+/// 	/// $some_doc
+/// 	#[pallet::task_index($index)]
+/// 	#[pallet::task_list($candidates)]
+/// 	#[pallet::task_condition(|$arg, ...| $condition)]
+/// 	#[pallet::task_weight($weight)]
+/// 	$vis fn $fn_name($arg: $arg_type, ...) -> DispatchResult {
+/// 		...
+/// 	}
+/// 	...
+/// }
+/// ```
+/// I.e. a regular rust `impl` block with some optional where clause and functions with 0
+/// generics and no `self` receiver. For each function:
+/// * `$index` is a unique `u8` identifying the task,
+/// * `$candidates` is an `IntoIterator` of the arguments to try: the argument itself if there
+///   is one, a tuple of them otherwise,
+/// * `$condition` is a closure taking a reference to each argument, telling whether the task
+///   can run,
+/// * `$weight` is the `Weight` of the task; the arguments are available by reference.
+///
+/// ## Macro expansion
+///
+/// The macro generates an enum `Task` with a variant per function and implements
+/// [`traits::Task`] on it. `construct_runtime!` aggregates them into a `RuntimeTask` enum,
+/// which is the `RuntimeTask` of `frame_system::Config`.
+///
+/// Any valid task can then be run through the `frame_system::Call::do_task` dispatchable,
+/// including as an unsigned transaction, e.g. submitted from an offchain worker with
+/// `frame_system::offchain::submit_valid_tasks`. A pallet can also run its own tasks from
+/// `on_idle` with `frame_system::Pallet::run_tasks`.
+///
+/// Since anyone can submit a valid task for free, `$condition` must only hold if the task will
+/// run successfully.
+///
+/// Also see: [`pallet::tasks`](`frame_support::pallet_macros::tasks`)
+///
 /// # Error: `#[pallet::error]` (optional)
 ///
 /// The `#[pallet::error]` attribute allows you to define an error enum that will be returned
@@ -2190,7 +2304,8 @@ pub mod pallet_macros {
 		disable_frame_system_supertrait_check, error, event, extra_constants, generate_deposit,
 		generate_store, genesis_build, genesis_config, getter, hooks, import_section, inherent,
 		no_default, no_default_bounds, origin, pallet_section, storage, storage_prefix,
-		storage_version, type_value, unbounded, validate_unsigned, weight, whitelist_storage,
+		storage_version, task_condition, task_index, task_list, task_weight, tasks, type_value,
+		unbounded, validate_unsigned, view_functions, weight, whitelist_storage,
 	};
 }
 
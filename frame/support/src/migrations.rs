@@ -16,13 +16,17 @@
 // limitations under the License.
 
 use crate::{
+	storage::{transactional::with_transaction, TransactionOutcome},
 	traits::{GetStorageVersion, NoStorageVersionSet, PalletInfoAccess, StorageVersion},
-	weights::{RuntimeDbWeight, Weight},
+	weights::{RuntimeDbWeight, Weight, WeightMeter},
 };
+use codec::{Decode, Encode, FullCodec, MaxEncodedLen};
 use impl_trait_for_tuples::impl_for_tuples;
+use scale_info::TypeInfo;
 use sp_core::Get;
 use sp_io::{hashing::twox_128, storage::clear_prefix, KillStorageResult};
-use sp_std::marker::PhantomData;
+use sp_runtime::DispatchError;
+use sp_std::{marker::PhantomData, vec::Vec};
 
 /// Handles storage migration pallet versioning.
 ///
@@ -286,10 +290,8 @@ pub fn migrate_from_pallet_version_to_storage_version<
 /// WARNING: `RemovePallet` has no guard rails preventing it from bricking the chain if the
 /// operation of removing storage for the given pallet would exceed the block weight limit.
 ///
-/// If your pallet has too many keys to be removed in a single block, it is advised to wait for
-/// a multi-block scheduler currently under development which will allow for removal of storage
-/// items (and performing other heavy migrations) over multiple blocks
-/// (see <https://github.com/paritytech/substrate/issues/13690>).
+/// If your pallet has too many keys to be removed in a single block, remove them with a
+/// [`SteppedMigration`] instead.
 pub struct RemovePallet<P: Get<&'static str>, DbWeight: Get<RuntimeDbWeight>>(
 	PhantomData<(P, DbWeight)>,
 );
@@ -344,3 +346,262 @@ impl<P: Get<&'static str>, DbWeight: Get<RuntimeDbWeight>> frame_support::traits
 		Ok(())
 	}
 }
+
+/// A migration that is executed in steps, spread over multiple blocks.
+///
+/// Each step does a bounded amount of work and returns the cursor to continue from in the next
+/// step. The cursor is kept in storage between blocks by a [`MultiStepMigrator`], such as
+/// `pallet-migrations`.
+pub trait SteppedMigration {
+	/// The cursor carried from one step to the next.
+	type Cursor: FullCodec + MaxEncodedLen;
+
+	/// The identifier of the migration.
+	type Identifier: FullCodec + MaxEncodedLen;
+
+	/// The unique identifier of this migration.
+	///
+	/// A migrator does not run a migration again once it completed under this identifier.
+	fn id() -> Self::Identifier;
+
+	/// The maximum number of steps the migration may take.
+	///
+	/// A migration that takes more steps is treated as failed. `None` means no limit.
+	fn max_steps() -> Option<u32> {
+		None
+	}
+
+	/// Do the next step of the migration.
+	///
+	/// `cursor` is `None` for the first step. Must not consume more than the weight left in
+	/// `meter`, and return [`SteppedMigrationError::InsufficientWeight`] if that is not enough for
+	/// any progress. Returns the cursor for the next step, or `None` once the migration is done.
+	fn step(
+		cursor: Option<Self::Cursor>,
+		meter: &mut WeightMeter,
+	) -> Result<Option<Self::Cursor>, SteppedMigrationError>;
+
+	/// Same as [`Self::step`], but reverts all storage changes of the step if it fails.
+	fn transactional_step(
+		cursor: Option<Self::Cursor>,
+		meter: &mut WeightMeter,
+	) -> Result<Option<Self::Cursor>, SteppedMigrationError> {
+		with_transaction(|| match Self::step(cursor, meter) {
+			Ok(cursor) => TransactionOutcome::Commit(Ok(cursor)),
+			Err(e) => TransactionOutcome::Rollback(Err(e)),
+		})
+	}
+}
+
+/// Error of a step of a [`SteppedMigration`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, TypeInfo, MaxEncodedLen)]
+pub enum SteppedMigrationError {
+	/// The remaining weight is not enough to make any progress.
+	///
+	/// The step is retried in the next block. If even the weight of a whole block is not enough,
+	/// the migration fails.
+	InsufficientWeight {
+		/// The weight needed to make progress.
+		required: Weight,
+	},
+	/// The cursor could not be decoded.
+	InvalidCursor,
+	/// The migration failed and cannot continue.
+	Failed,
+}
+
+impl From<DispatchError> for SteppedMigrationError {
+	fn from(_: DispatchError) -> Self {
+		Self::Failed
+	}
+}
+
+/// An ordered list of [`SteppedMigration`]s, addressed by index.
+///
+/// Implemented for every [`SteppedMigration`] and for tuples of lists. Cursors and identifiers are
+/// passed in their encoded form so that the migrations of a list can have different types.
+pub trait SteppedMigrations {
+	/// The number of migrations in the list.
+	fn len() -> u32;
+
+	/// The encoded identifier of the `n`th migration.
+	fn nth_id(n: u32) -> Option<Vec<u8>>;
+
+	/// The maximum number of steps of the `n`th migration.
+	fn nth_max_steps(n: u32) -> Option<Option<u32>>;
+
+	/// Do a [`SteppedMigration::transactional_step`] of the `n`th migration.
+	fn nth_transactional_step(
+		n: u32,
+		cursor: Option<Vec<u8>>,
+		meter: &mut WeightMeter,
+	) -> Option<Result<Option<Vec<u8>>, SteppedMigrationError>>;
+
+	/// The maximum encoded length of the cursors of all migrations.
+	fn cursor_max_encoded_len() -> usize;
+
+	/// The maximum encoded length of the identifiers of all migrations.
+	fn identifier_max_encoded_len() -> usize;
+}
+
+impl<T: SteppedMigration> SteppedMigrations for T {
+	fn len() -> u32 {
+		1
+	}
+
+	fn nth_id(n: u32) -> Option<Vec<u8>> {
+		(n == 0).then(|| T::id().encode())
+	}
+
+	fn nth_max_steps(n: u32) -> Option<Option<u32>> {
+		(n == 0).then(T::max_steps)
+	}
+
+	fn nth_transactional_step(
+		n: u32,
+		cursor: Option<Vec<u8>>,
+		meter: &mut WeightMeter,
+	) -> Option<Result<Option<Vec<u8>>, SteppedMigrationError>> {
+		if n != 0 {
+			return None
+		}
+		let cursor = match cursor.map(|c| T::Cursor::decode(&mut &c[..])).transpose() {
+			Ok(cursor) => cursor,
+			Err(_) => return Some(Err(SteppedMigrationError::InvalidCursor)),
+		};
+		Some(T::transactional_step(cursor, meter).map(|cursor| cursor.map(|c| c.encode())))
+	}
+
+	fn cursor_max_encoded_len() -> usize {
+		T::Cursor::max_encoded_len()
+	}
+
+	fn identifier_max_encoded_len() -> usize {
+		T::Identifier::max_encoded_len()
+	}
+}
+
+#[impl_for_tuples(1, 30)]
+#[allow(unused_assignments)]
+impl SteppedMigrations for Tuple {
+	fn len() -> u32 {
+		for_tuples!( #( Tuple::len() )+* )
+	}
+
+	fn nth_id(n: u32) -> Option<Vec<u8>> {
+		let mut offset = 0;
+		for_tuples!( #(
+			if n < offset + Tuple::len() {
+				return Tuple::nth_id(n - offset)
+			}
+			offset += Tuple::len();
+		)* );
+		None
+	}
+
+	fn nth_max_steps(n: u32) -> Option<Option<u32>> {
+		let mut offset = 0;
+		for_tuples!( #(
+			if n < offset + Tuple::len() {
+				return Tuple::nth_max_steps(n - offset)
+			}
+			offset += Tuple::len();
+		)* );
+		None
+	}
+
+	fn nth_transactional_step(
+		n: u32,
+		cursor: Option<Vec<u8>>,
+		meter: &mut WeightMeter,
+	) -> Option<Result<Option<Vec<u8>>, SteppedMigrationError>> {
+		let mut offset = 0;
+		for_tuples!( #(
+			if n < offset + Tuple::len() {
+				return Tuple::nth_transactional_step(n - offset, cursor, meter)
+			}
+			offset += Tuple::len();
+		)* );
+		None
+	}
+
+	fn cursor_max_encoded_len() -> usize {
+		let mut max = 0;
+		for_tuples!( #( max = max.max(Tuple::cursor_max_encoded_len()); )* );
+		max
+	}
+
+	fn identifier_max_encoded_len() -> usize {
+		let mut max = 0;
+		for_tuples!( #( max = max.max(Tuple::identifier_max_encoded_len()); )* );
+		max
+	}
+}
+
+impl SteppedMigrations for () {
+	fn len() -> u32 {
+		0
+	}
+
+	fn nth_id(_: u32) -> Option<Vec<u8>> {
+		None
+	}
+
+	fn nth_max_steps(_: u32) -> Option<Option<u32>> {
+		None
+	}
+
+	fn nth_transactional_step(
+		_: u32,
+		_: Option<Vec<u8>>,
+		_: &mut WeightMeter,
+	) -> Option<Result<Option<Vec<u8>>, SteppedMigrationError>> {
+		None
+	}
+
+	fn cursor_max_encoded_len() -> usize {
+		0
+	}
+
+	fn identifier_max_encoded_len() -> usize {
+		0
+	}
+}
+
+/// The status of the multi-block migrations of a runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, TypeInfo, MaxEncodedLen)]
+pub enum MultiStepStatus {
+	/// No migration is running.
+	Idle,
+	/// Migrations are running and will continue in the next block.
+	Ongoing,
+	/// A migration failed and the remaining ones cannot run until governance intervenes.
+	Stuck,
+}
+
+/// Drives [`SteppedMigrations`] across blocks.
+///
+/// `frame-executive` calls [`Self::step`] at the start of every block.
+pub trait MultiStepMigrator {
+	/// The current status of the migrations.
+	fn status() -> MultiStepStatus;
+
+	/// Whether the migrations are ongoing or stuck, in which case user transactions must stay
+	/// paused.
+	fn ongoing() -> bool {
+		Self::status() != MultiStepStatus::Idle
+	}
+
+	/// Make progress on the ongoing migrations and return the consumed weight.
+	fn step() -> Weight;
+}
+
+impl MultiStepMigrator for () {
+	fn status() -> MultiStepStatus {
+		MultiStepStatus::Idle
+	}
+
+	fn step() -> Weight {
+		Weight::zero()
+	}
+}
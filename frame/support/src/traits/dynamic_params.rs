@@ -0,0 +1,69 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Traits for dynamic runtime parameters, i.e. parameters whose value is kept in storage and can
+//! be changed without a runtime upgrade.
+//!
+//! The types implementing them are usually generated by the
+//! [`dynamic_params`](crate::dynamic_params) macro.
+
+use crate::Parameter;
+use codec::MaxEncodedLen;
+use sp_runtime::traits::Member;
+
+/// The key of a single dynamic parameter.
+pub trait Key {
+	/// The type of the value of the parameter.
+	type Value;
+
+	/// The value of the parameter, as it can be extracted from an aggregated value.
+	type WrappedValue: Into<Self::Value>;
+}
+
+/// A set of dynamic parameters, aggregated into a single key-value type.
+///
+/// Each variant sets, or resets to its default when the value is `None`, one parameter.
+pub trait AggregatedKeyValue: Parameter + Member {
+	/// The aggregated key type.
+	type Key: Parameter + Member + MaxEncodedLen;
+
+	/// The aggregated value type.
+	type Value: Parameter + Member + MaxEncodedLen;
+
+	/// Splits into the key and the new value of the parameter.
+	fn into_parts(self) -> (Self::Key, Option<Self::Value>);
+}
+
+impl AggregatedKeyValue for () {
+	type Key = ();
+	type Value = ();
+
+	fn into_parts(self) -> (Self::Key, Option<Self::Value>) {
+		((), None)
+	}
+}
+
+/// A store of dynamic parameters, e.g. `pallet-parameters`.
+pub trait RuntimeParameterStore {
+	/// The parameters held by the store.
+	type AggregatedKeyValue: AggregatedKeyValue;
+
+	/// Returns the value of the parameter `key`, or `None` if it is not set in the store.
+	fn get<K>(key: K) -> Option<K::Value>
+	where
+		K: Key + Into<<Self::AggregatedKeyValue as AggregatedKeyValue>::Key>,
+		<Self::AggregatedKeyValue as AggregatedKeyValue>::Value: TryInto<K::WrappedValue>;
+}
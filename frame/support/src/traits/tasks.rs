@@ -0,0 +1,57 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contains the [`Task`] trait, which defines a general-purpose way for defining and executing
+//! service work, and supporting types.
+
+use codec::FullCodec;
+use scale_info::TypeInfo;
+use sp_runtime::DispatchError;
+use sp_std::fmt::Debug;
+use sp_weights::Weight;
+
+/// A general-purpose trait which defines a type of service work (i.e., work to be performed by an
+/// off-chain worker) including methods for enumerating, validating, indexing, and running
+/// tasks of this type.
+///
+/// Tasks are declared in a pallet with `#[pallet::tasks]` and aggregated by
+/// `construct_runtime!` into a `RuntimeTask` type, which `frame-system` can dispatch with its
+/// `do_task` call.
+pub trait Task: Sized + FullCodec + TypeInfo + Clone + Debug + PartialEq + Eq {
+	/// An [`Iterator`] over the candidate tasks of this type.
+	type Enumeration: Iterator<Item = Self>;
+
+	/// Enumerates the candidate tasks of this type. Only the [valid](Task::is_valid) ones can be
+	/// run.
+	fn iter() -> Self::Enumeration;
+
+	/// Checks if a particular instance of this `Task` variant is a valid piece of work.
+	///
+	/// Anyone can submit a valid task as a free unsigned transaction, so a task must only be
+	/// valid if [`run`](Task::run) will succeed.
+	fn is_valid(&self) -> bool;
+
+	/// Performs the work for this particular `Task` variant.
+	fn run(&self) -> Result<(), DispatchError>;
+
+	/// Returns the weight of executing this `Task`.
+	fn weight(&self) -> Weight;
+
+	/// A unique value representing this `Task` within the pallet declaring it. Analogous to
+	/// `call_index`, but for tasks.
+	fn task_index(&self) -> u32;
+}
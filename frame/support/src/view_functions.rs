@@ -0,0 +1,143 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Support for pallet view functions.
+//!
+//! View functions are read-only queries declared by a pallet in a `#[pallet::view_functions]`
+//! block. `construct_runtime!` aggregates them so that all of them can be executed through the
+//! single [`runtime_api::RuntimeViewFunction`] runtime API, identified by a [`ViewFunctionId`].
+
+use codec::{Decode, DecodeAll, Encode, MaxEncodedLen, Output};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// The identifier of a view function.
+///
+/// The `prefix` identifies the pallet in the runtime and the `suffix` identifies the function in
+/// the pallet.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
+pub struct ViewFunctionId {
+	/// The `twox_128` hash of the pallet name.
+	pub prefix: [u8; 16],
+	/// The `twox_128` hash of the function signature.
+	pub suffix: [u8; 16],
+}
+
+impl From<ViewFunctionId> for [u8; 32] {
+	fn from(value: ViewFunctionId) -> Self {
+		let mut output = [0u8; 32];
+		output[..16].copy_from_slice(&value.prefix);
+		output[16..].copy_from_slice(&value.suffix);
+		output
+	}
+}
+
+/// Error returned when dispatching a view function.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, TypeInfo, RuntimeDebug)]
+pub enum ViewFunctionDispatchError {
+	/// View functions are not implemented for this runtime.
+	NotImplemented,
+	/// No view function with the given id was found.
+	NotFound(ViewFunctionId),
+	/// The input could not be decoded into the arguments of the view function.
+	Codec,
+}
+
+impl From<codec::Error> for ViewFunctionDispatchError {
+	fn from(_: codec::Error) -> Self {
+		Self::Codec
+	}
+}
+
+/// Implemented by pallets to provide the prefix of their view function ids.
+pub trait ViewFunctionIdPrefix {
+	/// The prefix shared by all view functions of the pallet.
+	fn prefix() -> [u8; 16];
+}
+
+/// Implemented by every view function to provide the suffix of its id.
+pub trait ViewFunctionIdSuffix {
+	/// The suffix identifying the view function inside its pallet.
+	const SUFFIX: [u8; 16];
+}
+
+/// A view function, i.e. the arguments of a query together with the function answering it.
+///
+/// The type implementing this trait holds the decoded arguments of the query.
+pub trait ViewFunction: DecodeAll {
+	/// The id of this view function.
+	fn id() -> ViewFunctionId;
+
+	/// The type returned by the view function.
+	type ReturnType: Encode;
+
+	/// Execute the view function with the arguments held by `self`.
+	fn invoke(self) -> Self::ReturnType;
+
+	/// Decode the arguments from `input`, execute the view function and encode the result into
+	/// `output`.
+	fn execute<O: Output>(
+		input: &mut &[u8],
+		output: &mut O,
+	) -> Result<(), ViewFunctionDispatchError> {
+		let view_function = Self::decode_all(input)?;
+		view_function.invoke().encode_to(output);
+		Ok(())
+	}
+}
+
+/// Dispatch a view function to its implementation given its id.
+///
+/// Implemented by every pallet for its own view functions, and by the runtime for the view
+/// functions of all its pallets.
+pub trait DispatchViewFunction {
+	/// Execute the view function identified by `id` with the encoded arguments in `input`, and
+	/// encode the result into `output`.
+	fn dispatch_view_function<O: Output>(
+		id: &ViewFunctionId,
+		input: &mut &[u8],
+		output: &mut O,
+	) -> Result<(), ViewFunctionDispatchError>;
+}
+
+impl DispatchViewFunction for () {
+	fn dispatch_view_function<O: Output>(
+		_id: &ViewFunctionId,
+		_input: &mut &[u8],
+		_output: &mut O,
+	) -> Result<(), ViewFunctionDispatchError> {
+		Err(ViewFunctionDispatchError::NotImplemented)
+	}
+}
+
+/// Runtime API for view functions.
+pub mod runtime_api {
+	use super::*;
+
+	sp_api::decl_runtime_apis! {
+		/// Runtime API for executing the view functions of the pallets of the runtime.
+		pub trait RuntimeViewFunction {
+			/// Execute the view function identified by `id` with the SCALE encoded arguments in
+			/// `input`, returning the SCALE encoded result.
+			fn execute_view_function(
+				id: ViewFunctionId,
+				input: Vec<u8>,
+			) -> Result<Vec<u8>, ViewFunctionDispatchError>;
+		}
+	}
+}
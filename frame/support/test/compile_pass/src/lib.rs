@@ -67,6 +67,7 @@ impl frame_system::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type PalletInfo = PalletInfo;
 	type RuntimeCall = RuntimeCall;
+	type RuntimeTask = RuntimeTask;
 	type DbWeight = ();
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
@@ -0,0 +1,290 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use frame_support::{
+	assert_noop, assert_ok, derive_impl,
+	dispatch::GetDispatchInfo,
+	traits::{ConstU32, Task},
+	weights::Weight,
+};
+use sp_io::TestExternalities;
+use sp_runtime::{
+	traits::{Dispatchable, ValidateUnsigned},
+	transaction_validity::{InvalidTransaction, TransactionSource},
+};
+
+#[frame_support::pallet(dev_mode)]
+pub mod pallet {
+	use frame_support::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {}
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::storage]
+	pub type Numbers<T: Config> = StorageMap<_, Twox64Concat, u32, u32, OptionQuery>;
+
+	#[pallet::storage]
+	pub type Pairs<T: Config> = StorageMap<_, Twox64Concat, u32, u32, OptionQuery>;
+
+	#[pallet::storage]
+	pub type Total<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// Zero cannot be added.
+		ZeroNumber,
+	}
+
+	#[pallet::tasks]
+	impl<T: Config> Pallet<T> {
+		/// Add a number into the total and remove it.
+		#[pallet::task_index(0)]
+		#[pallet::task_list(Numbers::<T>::iter_keys())]
+		#[pallet::task_condition(|i: &u32| Numbers::<T>::contains_key(i))]
+		#[pallet::task_weight(Weight::from_parts(10, 0))]
+		pub fn add_number_into_total(i: u32) -> DispatchResult {
+			let number = Numbers::<T>::take(i).ok_or(DispatchError::Other("no number"))?;
+			Total::<T>::mutate(|total| *total += number);
+			ensure!(number != 0, Error::<T>::ZeroNumber);
+			Ok(())
+		}
+
+		/// Remove a pair whose value matches.
+		#[pallet::task_index(1)]
+		#[pallet::task_list(Pairs::<T>::iter())]
+		#[pallet::task_condition(|a: &u32, b: &u32| Pairs::<T>::get(a) == Some(*b))]
+		#[pallet::task_weight(Weight::from_parts(u64::from(*a + *b), 0))]
+		pub fn clear_pair(a: u32, b: u32) -> DispatchResult {
+			ensure!(Pairs::<T>::take(a) == Some(b), DispatchError::Other("pair changed"));
+			Ok(())
+		}
+	}
+}
+
+pub type Header = sp_runtime::generic::Header<u32, sp_runtime::traits::BlakeTwo256>;
+pub type Block = sp_runtime::generic::Block<Header, UncheckedExtrinsic>;
+pub type UncheckedExtrinsic = sp_runtime::generic::UncheckedExtrinsic<u32, RuntimeCall, (), ()>;
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Runtime {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type Block = Block;
+	type BlockHashCount = ConstU32<10>;
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type RuntimeEvent = RuntimeEvent;
+	type PalletInfo = PalletInfo;
+	type OnSetCode = ();
+}
+
+impl pallet::Config for Runtime {}
+
+frame_support::construct_runtime!(
+	pub struct Runtime {
+		System: frame_system,
+		TasksPallet: pallet,
+	}
+);
+
+fn new_test_ext() -> TestExternalities {
+	let mut ext = TestExternalities::default();
+	ext.execute_with(|| frame_system::Pallet::<Runtime>::set_block_number(1));
+	ext
+}
+
+fn task_events() -> Vec<frame_system::Event<Runtime>> {
+	frame_system::Pallet::<Runtime>::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			RuntimeEvent::System(event) => Some(event),
+			_ => None,
+		})
+		.collect()
+}
+
+fn do_task(task: impl Into<RuntimeTask>) -> RuntimeCall {
+	RuntimeCall::System(frame_system::Call::do_task { task: task.into() })
+}
+
+#[test]
+fn tasks_enumeration_and_validity() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(pallet::Task::<Runtime>::iter().count(), 0);
+
+		pallet::Numbers::<Runtime>::insert(1, 5);
+		pallet::Pairs::<Runtime>::insert(2, 3);
+
+		let tasks = RuntimeTask::iter().collect::<Vec<_>>();
+		assert_eq!(
+			tasks,
+			vec![
+				pallet::Task::<Runtime>::add_number_into_total { i: 1 }.into(),
+				pallet::Task::<Runtime>::clear_pair { a: 2, b: 3 }.into(),
+			]
+		);
+		assert!(tasks.iter().all(|task| task.is_valid()));
+		assert_eq!(tasks.iter().map(Task::task_index).collect::<Vec<_>>(), vec![0, 1]);
+		assert_eq!(tasks[1].weight(), Weight::from_parts(5, 0));
+
+		assert!(!pallet::Task::<Runtime>::add_number_into_total { i: 2 }.is_valid());
+		assert!(!pallet::Task::<Runtime>::clear_pair { a: 2, b: 4 }.is_valid());
+	});
+}
+
+#[test]
+fn do_task_runs_valid_task() {
+	new_test_ext().execute_with(|| {
+		pallet::Numbers::<Runtime>::insert(1, 5);
+		let task = pallet::Task::<Runtime>::add_number_into_total { i: 1 };
+
+		let call = do_task(task.clone());
+		assert_eq!(call.get_dispatch_info().weight, Weight::from_parts(10, 0));
+		assert_ok!(call.dispatch(RuntimeOrigin::none()));
+
+		assert_eq!(pallet::Total::<Runtime>::get(), 5);
+		assert!(!pallet::Numbers::<Runtime>::contains_key(1));
+		assert_eq!(
+			task_events(),
+			vec![
+				frame_system::Event::TaskStarted { task: task.clone().into() },
+				frame_system::Event::TaskCompleted { task: task.into() },
+			]
+		);
+	});
+}
+
+#[test]
+fn do_task_rejects_invalid_task() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			do_task(pallet::Task::<Runtime>::add_number_into_total { i: 1 })
+				.dispatch(RuntimeOrigin::none()),
+			frame_system::Error::<Runtime>::InvalidTask,
+		);
+	});
+}
+
+#[test]
+fn do_task_reverts_and_reports_failed_task() {
+	new_test_ext().execute_with(|| {
+		// given
+		pallet::Numbers::<Runtime>::insert(1, 0);
+		let task = pallet::Task::<Runtime>::add_number_into_total { i: 1 };
+
+		// when
+		assert_ok!(do_task(task.clone()).dispatch(RuntimeOrigin::none()));
+
+		// then
+		assert_eq!(pallet::Numbers::<Runtime>::get(1), Some(0));
+		assert_eq!(pallet::Total::<Runtime>::get(), 0);
+		assert_eq!(
+			task_events(),
+			vec![
+				frame_system::Event::TaskStarted { task: task.clone().into() },
+				frame_system::Event::TaskFailed {
+					task: task.clone().into(),
+					err: pallet::Error::<Runtime>::ZeroNumber.into(),
+				},
+			]
+		);
+
+		// The task is still valid, but can not be submitted again in this block.
+		assert!(task.is_valid());
+		assert_eq!(
+			frame_system::Pallet::<Runtime>::validate_unsigned(
+				TransactionSource::External,
+				&frame_system::Call::do_task { task: task.clone().into() },
+			),
+			Err(InvalidTransaction::Stale.into())
+		);
+
+		frame_system::Pallet::<Runtime>::initialize(&2, &Default::default(), &Default::default());
+		assert_ok!(frame_system::Pallet::<Runtime>::validate_unsigned(
+			TransactionSource::External,
+			&frame_system::Call::do_task { task: task.into() },
+		));
+	});
+}
+
+#[test]
+fn run_tasks_respects_the_weight_limit() {
+	new_test_ext().execute_with(|| {
+		let run_tasks = frame_system::Pallet::<Runtime>::run_tasks::<pallet::Task<Runtime>>;
+		for i in 1..=3 {
+			pallet::Numbers::<Runtime>::insert(i, i);
+		}
+
+		// The test runtime has no database weight, so only task weights count.
+		assert_eq!(run_tasks(Weight::from_parts(25, 0)), Weight::from_parts(20, 0));
+		assert_eq!(pallet::Numbers::<Runtime>::iter().count(), 1);
+
+		assert_eq!(run_tasks(Weight::MAX), Weight::from_parts(10, 0));
+		assert_eq!(pallet::Total::<Runtime>::get(), 6);
+		assert_eq!(run_tasks(Weight::MAX), Weight::zero());
+
+		// A failed task is reverted and reported.
+		pallet::Numbers::<Runtime>::insert(4, 0);
+		assert_eq!(run_tasks(Weight::MAX), Weight::from_parts(10, 0));
+		assert_eq!(pallet::Numbers::<Runtime>::get(4), Some(0));
+		assert_eq!(pallet::Total::<Runtime>::get(), 6);
+		let failed = task_events()
+			.into_iter()
+			.filter(|event| matches!(event, frame_system::Event::TaskFailed { .. }))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			failed,
+			vec![frame_system::Event::TaskFailed {
+				task: pallet::Task::<Runtime>::add_number_into_total { i: 4 }.into(),
+				err: pallet::Error::<Runtime>::ZeroNumber.into(),
+			}]
+		);
+	});
+}
+
+#[test]
+fn do_task_validate_unsigned() {
+	new_test_ext().execute_with(|| {
+		let validate = |task: pallet::Task<Runtime>| {
+			frame_system::Pallet::<Runtime>::validate_unsigned(
+				TransactionSource::External,
+				&frame_system::Call::do_task { task: task.into() },
+			)
+		};
+
+		assert_eq!(
+			validate(pallet::Task::<Runtime>::add_number_into_total { i: 1 }),
+			Err(InvalidTransaction::Call.into())
+		);
+
+		pallet::Numbers::<Runtime>::insert(1, 5);
+		let valid = validate(pallet::Task::<Runtime>::add_number_into_total { i: 1 })
+			.expect("task is valid");
+		assert_eq!(valid.provides.len(), 1);
+		assert_eq!(valid.priority, 0);
+		assert_eq!(valid.longevity, 64);
+
+		assert_eq!(
+			frame_system::Pallet::<Runtime>::validate_unsigned(
+				TransactionSource::External,
+				&frame_system::Call::remark { remark: vec![] },
+			),
+			Err(InvalidTransaction::Call.into())
+		);
+	});
+}
@@ -0,0 +1,265 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use codec::{Decode, Encode};
+use frame_support::{
+	derive_impl,
+	traits::ConstU32,
+	view_functions::{ViewFunction, ViewFunctionDispatchError, ViewFunctionId},
+};
+use sp_io::TestExternalities;
+use sp_metadata_ir::{
+	PalletViewFunctionMetadataIR, PalletViewFunctionsMetadataV15, VIEW_FUNCTIONS_CUSTOM_KEY,
+};
+
+#[frame_support::pallet(dev_mode)]
+pub mod pallet {
+	use frame_support::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {}
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::storage]
+	pub type SomeValue<T: Config> = StorageValue<_, u32>;
+
+	#[pallet::storage]
+	pub type SomeMap<T: Config> = StorageMap<_, Twox64Concat, u32, u32, OptionQuery>;
+
+	#[pallet::view_functions]
+	impl<T: Config> Pallet<T>
+	where
+		T::AccountId: From<u64>,
+	{
+		/// Query value no args.
+		pub fn get_value() -> Option<u32> {
+			SomeValue::<T>::get()
+		}
+
+		/// Query value with args.
+		pub fn get_value_with_arg(key: u32) -> Option<u32> {
+			SomeMap::<T>::get(key)
+		}
+
+		/// Query the sum of two values.
+		pub fn get_sum(first: u32, second: u32) -> Option<u32> {
+			let first = SomeMap::<T>::get(first)?;
+			let second = SomeMap::<T>::get(second)?;
+			first.checked_add(second)
+		}
+	}
+}
+
+#[frame_support::pallet(dev_mode)]
+pub mod pallet2 {
+	use frame_support::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config<I: 'static = ()>: frame_system::Config {}
+
+	#[pallet::pallet]
+	pub struct Pallet<T, I = ()>(PhantomData<(T, I)>);
+
+	#[pallet::storage]
+	pub type SomeValue<T: Config<I>, I: 'static = ()> = StorageValue<_, u32>;
+
+	#[pallet::view_functions]
+	impl<T: Config<I>, I: 'static> Pallet<T, I> {
+		/// Query value of this instance.
+		pub fn get_value() -> Option<u32> {
+			SomeValue::<T, I>::get()
+		}
+	}
+}
+
+pub type Header = sp_runtime::generic::Header<u32, sp_runtime::traits::BlakeTwo256>;
+pub type Block = sp_runtime::generic::Block<Header, UncheckedExtrinsic>;
+pub type UncheckedExtrinsic = sp_runtime::generic::UncheckedExtrinsic<u32, RuntimeCall, (), ()>;
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Runtime {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type Block = Block;
+	type BlockHashCount = ConstU32<10>;
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type RuntimeEvent = RuntimeEvent;
+	type PalletInfo = PalletInfo;
+	type OnSetCode = ();
+}
+
+impl pallet::Config for Runtime {}
+impl pallet2::Config for Runtime {}
+impl pallet2::Config<pallet2::Instance1> for Runtime {}
+
+frame_support::construct_runtime!(
+	pub struct Runtime {
+		System: frame_system,
+		ViewFunctionsPallet: pallet,
+		ViewFunctionsInstance: pallet2,
+		ViewFunctionsInstance1: pallet2<Instance1>,
+	}
+);
+
+fn execute<V: ViewFunction + Encode>(query: V) -> Result<V::ReturnType, ViewFunctionDispatchError>
+where
+	V::ReturnType: Decode,
+{
+	let output = Runtime::execute_view_function(V::id(), query.encode())?;
+	Ok(V::ReturnType::decode(&mut &output[..]).expect("output is encoded by the runtime"))
+}
+
+#[test]
+fn view_function_works() {
+	TestExternalities::default().execute_with(|| {
+		assert_eq!(execute(pallet::GetValueViewFunction::<Runtime>::new()), Ok(None));
+		assert_eq!(execute(pallet::GetValueWithArgViewFunction::<Runtime>::new(1)), Ok(None));
+
+		pallet::SomeValue::<Runtime>::put(123);
+		pallet::SomeMap::<Runtime>::insert(1, 5);
+		pallet::SomeMap::<Runtime>::insert(2, 7);
+
+		assert_eq!(execute(pallet::GetValueViewFunction::<Runtime>::new()), Ok(Some(123)));
+		assert_eq!(execute(pallet::GetValueWithArgViewFunction::<Runtime>::new(1)), Ok(Some(5)));
+		assert_eq!(execute(pallet::GetSumViewFunction::<Runtime>::new(1, 2)), Ok(Some(12)));
+		assert_eq!(execute(pallet::GetSumViewFunction::<Runtime>::new(1, 3)), Ok(None));
+	});
+}
+
+#[test]
+fn view_function_dispatches_to_the_right_instance() {
+	TestExternalities::default().execute_with(|| {
+		pallet2::SomeValue::<Runtime>::put(1);
+		pallet2::SomeValue::<Runtime, pallet2::Instance1>::put(2);
+
+		assert_eq!(execute(pallet2::GetValueViewFunction::<Runtime>::new()), Ok(Some(1)));
+		assert_eq!(
+			execute(pallet2::GetValueViewFunction::<Runtime, pallet2::Instance1>::new()),
+			Ok(Some(2))
+		);
+		assert_ne!(
+			pallet2::GetValueViewFunction::<Runtime>::id(),
+			pallet2::GetValueViewFunction::<Runtime, pallet2::Instance1>::id(),
+		);
+	});
+}
+
+#[test]
+fn view_function_dispatch_errors() {
+	TestExternalities::default().execute_with(|| {
+		let id = pallet::GetValueWithArgViewFunction::<Runtime>::id();
+
+		// Arguments that cannot be decoded, or are not fully consumed.
+		assert_eq!(
+			Runtime::execute_view_function(id.clone(), vec![1]),
+			Err(ViewFunctionDispatchError::Codec)
+		);
+		assert_eq!(
+			Runtime::execute_view_function(id.clone(), (1u32, 2u32).encode()),
+			Err(ViewFunctionDispatchError::Codec)
+		);
+
+		// Unknown function of a known pallet.
+		let unknown = ViewFunctionId { prefix: id.prefix, suffix: [0; 16] };
+		assert_eq!(
+			Runtime::execute_view_function(unknown.clone(), vec![]),
+			Err(ViewFunctionDispatchError::NotFound(unknown))
+		);
+
+		// Unknown pallet.
+		let unknown = ViewFunctionId { prefix: [0; 16], suffix: id.suffix };
+		assert_eq!(
+			Runtime::execute_view_function(unknown.clone(), vec![]),
+			Err(ViewFunctionDispatchError::NotFound(unknown))
+		);
+	});
+}
+
+#[test]
+fn view_functions_metadata() {
+	let metadata = Runtime::metadata_ir();
+	let view_functions = |name: &str| -> Vec<PalletViewFunctionMetadataIR> {
+		metadata
+			.pallets
+			.iter()
+			.find(|pallet| pallet.name == name)
+			.expect("pallet is part of the runtime")
+			.view_functions
+			.clone()
+	};
+
+	assert!(view_functions("System").is_empty());
+
+	let pallet = view_functions("ViewFunctionsPallet");
+	assert_eq!(
+		pallet.iter().map(|f| f.name).collect::<Vec<_>>(),
+		vec!["get_value", "get_value_with_arg", "get_sum"],
+	);
+	assert_eq!(pallet[0].id, <[u8; 32]>::from(pallet::GetValueViewFunction::<Runtime>::id()));
+	assert!(pallet[0].inputs.is_empty());
+	assert_eq!(pallet[0].output, scale_info::meta_type::<Option<u32>>());
+	assert_eq!(
+		pallet[2].inputs.iter().map(|input| input.name).collect::<Vec<_>>(),
+		vec!["first", "second"],
+	);
+	assert_eq!(pallet[2].docs, vec![" Query the sum of two values."]);
+
+	let instance = view_functions("ViewFunctionsInstance");
+	let instance1 = view_functions("ViewFunctionsInstance1");
+	assert_eq!(instance.len(), 1);
+	assert_eq!(instance1.len(), 1);
+	assert_ne!(instance[0].id, instance1[0].id);
+}
+
+#[test]
+fn view_functions_metadata_v15() {
+	use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
+	use scale_info::TypeDef;
+
+	let bytes = Runtime::metadata_at_version(15).expect("V15 is supported");
+	let metadata = match RuntimeMetadataPrefixed::decode(&mut &bytes[..]).unwrap().1 {
+		RuntimeMetadata::V15(metadata) => metadata,
+		_ => panic!("metadata version 15 was requested"),
+	};
+
+	let custom = &metadata.custom.map[VIEW_FUNCTIONS_CUSTOM_KEY];
+	let ty = metadata.types.resolve(custom.ty.id).expect("type is registered");
+	assert!(matches!(ty.type_def, TypeDef::Sequence(_)));
+
+	let pallets = Vec::<PalletViewFunctionsMetadataV15>::decode(&mut &custom.value[..]).unwrap();
+	assert_eq!(
+		pallets.iter().map(|pallet| pallet.pallet.as_str()).collect::<Vec<_>>(),
+		vec!["ViewFunctionsPallet", "ViewFunctionsInstance", "ViewFunctionsInstance1"],
+	);
+
+	let get_sum = &pallets[0].view_functions[2];
+	assert_eq!(get_sum.name, "get_sum");
+	assert_eq!(get_sum.id, <[u8; 32]>::from(pallet::GetSumViewFunction::<Runtime>::id()));
+	assert_eq!(get_sum.docs, vec![" Query the sum of two values."]);
+	assert_eq!(
+		get_sum.inputs.iter().map(|input| input.name.as_str()).collect::<Vec<_>>(),
+		vec!["first", "second"],
+	);
+	let resolve = |id| metadata.types.resolve(id).expect("type is registered");
+	assert_eq!(
+		resolve(get_sum.inputs[0].ty).type_def,
+		TypeDef::Primitive(scale_info::TypeDefPrimitive::U32)
+	);
+	assert_eq!(resolve(get_sum.output).path.segments, vec!["Option".to_string()]);
+}
@@ -96,13 +96,14 @@ use frame_support::{
 	storage::{self, StorageStreamIter},
 	traits::{
 		ConstU32, Contains, EnsureOrigin, EnsureOriginWithArg, Get, HandleLifetime,
-		OnKilledAccount, OnNewAccount, OriginTrait, PalletInfo, SortedMembers, StoredMap, TypedGet,
+		OnKilledAccount, OnNewAccount, OriginTrait, PalletInfo, SortedMembers, StoredMap, Task,
+		TypedGet,
 	},
 	Parameter,
 };
 use scale_info::TypeInfo;
 use sp_core::storage::well_known_keys;
-use sp_weights::{RuntimeDbWeight, Weight};
+use sp_weights::{RuntimeDbWeight, Weight, WeightMeter};
 
 #[cfg(any(feature = "std", test))]
 use sp_io::TestExternalities;
@@ -239,6 +240,8 @@ pub mod pallet {
 			#[inject_runtime_type]
 			type RuntimeCall = ();
 			#[inject_runtime_type]
+			type RuntimeTask = ();
+			#[inject_runtime_type]
 			type PalletInfo = ();
 			type BaseCallFilter = frame_support::traits::Everything;
 			type BlockHashCount = frame_support::traits::ConstU64<10>;
@@ -293,6 +296,10 @@ pub mod pallet {
 			+ Debug
 			+ From<Call<Self>>;
 
+		/// The aggregated `RuntimeTask` type.
+		#[pallet::no_default_bounds]
+		type RuntimeTask: Task;
+
 		/// This stores the number of previous transactions associated with a sender account.
 		type Nonce: Parameter
 			+ Member
@@ -520,6 +527,31 @@ pub mod pallet {
 			Self::deposit_event(Event::Remarked { sender: who, hash });
 			Ok(().into())
 		}
+
+		/// Run a valid task declared with `#[pallet::tasks]` by a pallet of the runtime.
+		///
+		/// Can be executed by every `origin`, including as an unsigned transaction. Fails if the
+		/// task is not valid. Otherwise the task runs in its own storage layer and the call
+		/// succeeds, with the outcome reported by a `TaskCompleted` or `TaskFailed` event. A task
+		/// that failed can not be submitted again as an unsigned transaction in the same block.
+		#[pallet::call_index(8)]
+		#[pallet::weight(task.weight())]
+		pub fn do_task(_origin: OriginFor<T>, task: T::RuntimeTask) -> DispatchResultWithPostInfo {
+			if !task.is_valid() {
+				return Err(Error::<T>::InvalidTask.into())
+			}
+
+			Self::deposit_event(Event::TaskStarted { task: task.clone() });
+			match storage::with_storage_layer(|| task.run()) {
+				Ok(()) => Self::deposit_event(Event::TaskCompleted { task }),
+				Err(err) => {
+					FailedTasks::<T>::append(T::Hashing::hash_of(&task));
+					Self::deposit_event(Event::TaskFailed { task, err });
+				},
+			}
+
+			Ok(().into())
+		}
 	}
 
 	/// Event for the System pallet.
@@ -537,6 +569,12 @@ pub mod pallet {
 		KilledAccount { account: T::AccountId },
 		/// On on-chain remark happened.
 		Remarked { sender: T::AccountId, hash: T::Hash },
+		/// A task has started.
+		TaskStarted { task: T::RuntimeTask },
+		/// A task has finished.
+		TaskCompleted { task: T::RuntimeTask },
+		/// A task failed.
+		TaskFailed { task: T::RuntimeTask, err: DispatchError },
 	}
 
 	/// Error for the System pallet
@@ -558,6 +596,8 @@ pub mod pallet {
 		NonZeroRefCount,
 		/// The origin filter prevent the call to be dispatched.
 		CallFiltered,
+		/// The specified task is not valid.
+		InvalidTask,
 	}
 
 	/// Exposed trait-generic origin type.
@@ -585,6 +625,11 @@ pub mod pallet {
 	#[pallet::getter(fn block_weight)]
 	pub(super) type BlockWeight<T: Config> = StorageValue<_, ConsumedWeight, ValueQuery>;
 
+	/// Hashes of the tasks that failed in [`Call::do_task`] in the current block.
+	#[pallet::storage]
+	#[pallet::unbounded]
+	pub(super) type FailedTasks<T: Config> = StorageValue<_, Vec<T::Hash>, ValueQuery>;
+
 	/// Total length (in bytes) for all extrinsics put together, for the current block.
 	#[pallet::storage]
 	pub(super) type AllExtrinsicsLen<T: Config> = StorageValue<_, u32>;
@@ -695,8 +740,39 @@ pub mod pallet {
 			sp_io::storage::set(well_known_keys::EXTRINSIC_INDEX, &0u32.encode());
 		}
 	}
+
+	/// Accepts [`Call::do_task`] as an unsigned transaction for any valid task that did not
+	/// already fail in the current block.
+	///
+	/// This is part of the default parts of `frame-system`, so every runtime with at least one
+	/// pallet declaring `#[pallet::tasks]` accepts these transactions from anyone, for free.
+	/// Pallets must therefore only declare a task valid if running it will succeed.
+	#[pallet::validate_unsigned]
+	impl<T: Config> sp_runtime::traits::ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let Call::do_task { task } = call else { return InvalidTransaction::Call.into() };
+			if !task.is_valid() {
+				return InvalidTransaction::Call.into()
+			}
+			let hash = T::Hashing::hash_of(task);
+			if FailedTasks::<T>::get().contains(&hash) {
+				return InvalidTransaction::Stale.into()
+			}
+
+			ValidTransaction::with_tag_prefix("DoTask")
+				.and_provides(hash)
+				.longevity(TASK_LONGEVITY)
+				.propagate(true)
+				.build()
+		}
+	}
 }
 
+/// The number of blocks an unsigned [`Call::do_task`] transaction stays valid for.
+const TASK_LONGEVITY: sp_runtime::transaction_validity::TransactionLongevity = 64;
+
 pub type Key = Vec<u8>;
 pub type KeyValue = (Vec<u8>, Vec<u8>);
 
@@ -1386,6 +1462,7 @@ impl<T: Config> Pallet<T> {
 
 		// Remove previous block data from storage
 		BlockWeight::<T>::kill();
+		FailedTasks::<T>::kill();
 	}
 
 	/// Remove temporary "environment" entries in storage, compute the storage root and return the
@@ -1689,6 +1766,38 @@ impl<T: Config> Pallet<T> {
 			}
 		}
 	}
+
+	/// Run the valid tasks of type `Tk`, in the order given by [`Task::iter`], until the next
+	/// one does not fit in `limit`. Meant to be called from a pallet's `on_idle` hook.
+	///
+	/// Each task runs in its own storage layer and deposits the same events as
+	/// [`Call::do_task`]. Checking a candidate is accounted as one database read. Returns the
+	/// weight consumed.
+	pub fn run_tasks<Tk>(limit: Weight) -> Weight
+	where
+		Tk: Task + Into<T::RuntimeTask>,
+	{
+		let mut meter = WeightMeter::with_limit(limit);
+		for task in Tk::iter() {
+			if meter.try_consume(T::DbWeight::get().reads(1)).is_err() {
+				break
+			}
+			if !task.is_valid() {
+				continue
+			}
+			if meter.try_consume(task.weight()).is_err() {
+				break
+			}
+
+			let runtime_task: T::RuntimeTask = task.clone().into();
+			Self::deposit_event(Event::TaskStarted { task: runtime_task.clone() });
+			match storage::with_storage_layer(|| task.run()) {
+				Ok(()) => Self::deposit_event(Event::TaskCompleted { task: runtime_task }),
+				Err(err) => Self::deposit_event(Event::TaskFailed { task: runtime_task, err }),
+			}
+		}
+		meter.consumed()
+	}
 }
 
 /// Returns a 32 byte datum which is guaranteed to be universally unique. `entropy` is provided
@@ -57,6 +57,7 @@
 #![warn(missing_docs)]
 
 use codec::Encode;
+use frame_support::traits::Task;
 use sp_runtime::{
 	app_crypto::RuntimeAppPublic,
 	traits::{Extrinsic as ExtrinsicT, IdentifyAccount, One},
@@ -100,6 +101,26 @@ where
 	}
 }
 
+/// Submit an unsigned [`do_task`](crate::Call::do_task) transaction for each of the first `max`
+/// valid tasks of type `Tk`. Meant to be called from a pallet's offchain worker.
+///
+/// Returns the number of transactions that were successfully submitted.
+pub fn submit_valid_tasks<T, Tk>(max: usize) -> usize
+where
+	T: crate::Config + SendTransactionTypes<crate::Call<T>>,
+	Tk: Task + Into<T::RuntimeTask>,
+{
+	let mut submitted = 0;
+	for task in Tk::iter().filter(|task| task.is_valid()).take(max) {
+		let call = crate::Call::<T>::do_task { task: task.into() };
+		if SubmitTransaction::<T, crate::Call<T>>::submit_unsigned_transaction(call.into()).is_ok()
+		{
+			submitted += 1;
+		}
+	}
+	submitted
+}
+
 /// Provides an implementation for signing transaction payloads.
 ///
 /// Keys used for signing are defined when instantiating the signer object.
@@ -47,6 +47,7 @@ impl frame_system::Config for Test {
 	type BlockLength = ();
 	type BlockWeights = ();
 	type RuntimeCall = RuntimeCall;
+	type RuntimeTask = RuntimeTask;
 	type DbWeight = ();
 	type RuntimeEvent = RuntimeEvent;
 	type Hash = H256;
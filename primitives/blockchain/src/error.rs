@@ -75,6 +75,9 @@ pub enum Error {
 	#[error("Execution failed: {0}")]
 	Execution(Box<dyn sp_state_machine::Error>),
 
+	#[error("Execution aborted, the call ran out of its budget")]
+	CallBudgetExhausted,
+
 	#[error("Blockchain")]
 	Blockchain(#[source] Box<Error>),
 
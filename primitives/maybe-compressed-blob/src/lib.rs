@@ -98,6 +98,13 @@ pub fn compress(blob: &[u8], bomb_limit: usize) -> Option<Vec<u8>> {
 	Some(buf)
 }
 
+/// The version of the zstd library used by [`compress`].
+///
+/// The compressed blob can differ between versions, which matters for reproducible builds.
+pub fn zstd_version() -> &'static str {
+	zstd::zstd_safe::version_string()
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -30,6 +30,10 @@ pub use types::*;
 
 mod v14;
 mod v15;
+pub use v15::{
+	PalletViewFunctionsMetadataV15, ViewFunctionMetadataV15, ViewFunctionParamMetadataV15,
+	VIEW_FUNCTIONS_CUSTOM_KEY,
+};
 
 /// Metadata V14.
 const V14: u32 = 14;
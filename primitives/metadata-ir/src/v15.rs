@@ -20,28 +20,144 @@
 use crate::OuterEnumsIR;
 
 use super::types::{
-	ExtrinsicMetadataIR, MetadataIR, PalletMetadataIR, RuntimeApiMetadataIR,
-	RuntimeApiMethodMetadataIR, RuntimeApiMethodParamMetadataIR, SignedExtensionMetadataIR,
+	ExtrinsicMetadataIR, MetadataIR, PalletMetadataIR, PalletViewFunctionMetadataIR,
+	PalletViewFunctionParamMetadataIR, RuntimeApiMetadataIR, RuntimeApiMethodMetadataIR,
+	RuntimeApiMethodParamMetadataIR, SignedExtensionMetadataIR,
 };
 
+use codec::{Decode, Encode};
 use frame_metadata::v15::{
-	CustomMetadata, ExtrinsicMetadata, OuterEnums, PalletMetadata, RuntimeApiMetadata,
-	RuntimeApiMethodMetadata, RuntimeApiMethodParamMetadata, RuntimeMetadataV15,
-	SignedExtensionMetadata,
+	CustomMetadata, CustomValueMetadata, ExtrinsicMetadata, OuterEnums, PalletMetadata,
+	RuntimeApiMetadata, RuntimeApiMethodMetadata, RuntimeApiMethodParamMetadata,
+	RuntimeMetadataV15, SignedExtensionMetadata,
 };
+use scale_info::{
+	form::PortableForm,
+	meta_type,
+	prelude::{string::String, vec::Vec},
+	IntoPortable, Registry, TypeInfo,
+};
+use sp_std::collections::btree_map::BTreeMap;
+
+/// The key of the custom metadata entry holding the view functions of the pallets.
+///
+/// V15 has no field for view functions, so they are emitted as a custom value of type
+/// `Vec<PalletViewFunctionsMetadataV15>`. Only pallets with view functions are listed, and the
+/// entry is omitted if there are none.
+pub const VIEW_FUNCTIONS_CUSTOM_KEY: &str = "view_functions";
+
+/// The view functions of a pallet, as found in the [`VIEW_FUNCTIONS_CUSTOM_KEY`] custom entry.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, TypeInfo, Debug)]
+pub struct PalletViewFunctionsMetadataV15 {
+	/// Name of the pallet.
+	pub pallet: String,
+	/// View functions of the pallet.
+	pub view_functions: Vec<ViewFunctionMetadataV15>,
+}
+
+/// Metadata about a view function, as found in the [`VIEW_FUNCTIONS_CUSTOM_KEY`] custom entry.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, TypeInfo, Debug)]
+pub struct ViewFunctionMetadataV15 {
+	/// Name of the view function.
+	pub name: String,
+	/// Id of the view function, i.e. its prefix followed by its suffix.
+	pub id: [u8; 32],
+	/// Arguments of the view function.
+	pub inputs: Vec<ViewFunctionParamMetadataV15>,
+	/// Id of the returned type in the type registry of the metadata.
+	#[codec(compact)]
+	pub output: u32,
+	/// Documentation of the view function.
+	pub docs: Vec<String>,
+}
+
+/// Metadata of a view function argument, as found in the [`VIEW_FUNCTIONS_CUSTOM_KEY`] custom
+/// entry.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, TypeInfo, Debug)]
+pub struct ViewFunctionParamMetadataV15 {
+	/// Name of the argument.
+	pub name: String,
+	/// Id of the type of the argument in the type registry of the metadata.
+	#[codec(compact)]
+	pub ty: u32,
+}
 
 impl From<MetadataIR> for RuntimeMetadataV15 {
 	fn from(ir: MetadataIR) -> Self {
-		RuntimeMetadataV15::new(
-			ir.pallets.into_iter().map(Into::into).collect(),
-			ir.extrinsic.into(),
-			ir.ty,
-			ir.apis.into_iter().map(Into::into).collect(),
-			ir.outer_enums.into(),
-			// Substrate does not collect yet the custom metadata fields.
-			// This allows us to extend the V15 easily.
-			CustomMetadata { map: Default::default() },
-		)
+		let view_functions = ir
+			.pallets
+			.iter()
+			.filter(|pallet| !pallet.view_functions.is_empty())
+			.map(|pallet| (pallet.name, pallet.view_functions.clone()))
+			.collect::<Vec<_>>();
+
+		// Same as `RuntimeMetadataV15::new`, but the registry is also needed for the types of
+		// the view functions, which are registered last so the other type ids do not change.
+		let mut registry = Registry::new();
+		let pallets = registry.map_into_portable(ir.pallets.into_iter().map(PalletMetadata::from));
+		let extrinsic = ExtrinsicMetadata::from(ir.extrinsic).into_portable(&mut registry);
+		let ty = registry.register_type(&ir.ty);
+		let apis = registry.map_into_portable(ir.apis.into_iter().map(RuntimeApiMetadata::from));
+		let outer_enums = OuterEnums::from(ir.outer_enums).into_portable(&mut registry);
+		let custom = custom_metadata(view_functions, &mut registry);
+
+		RuntimeMetadataV15 {
+			types: registry.into(),
+			pallets,
+			extrinsic,
+			ty,
+			apis,
+			outer_enums,
+			custom,
+		}
+	}
+}
+
+/// Build the custom metadata, which holds the view functions of the given pallets.
+fn custom_metadata(
+	view_functions: Vec<(&'static str, Vec<PalletViewFunctionMetadataIR>)>,
+	registry: &mut Registry,
+) -> CustomMetadata<PortableForm> {
+	let mut map = BTreeMap::new();
+	if !view_functions.is_empty() {
+		let value = view_functions
+			.into_iter()
+			.map(|(pallet, view_functions)| PalletViewFunctionsMetadataV15 {
+				pallet: pallet.into(),
+				view_functions: registry
+					.map_into_portable(view_functions)
+					.into_iter()
+					.map(Into::into)
+					.collect(),
+			})
+			.collect::<Vec<_>>();
+		map.insert(
+			VIEW_FUNCTIONS_CUSTOM_KEY.into(),
+			CustomValueMetadata {
+				ty: registry.register_type(&meta_type::<Vec<PalletViewFunctionsMetadataV15>>()),
+				value: value.encode(),
+			},
+		);
+	}
+
+	CustomMetadata { map }
+}
+
+impl From<PalletViewFunctionMetadataIR<PortableForm>> for ViewFunctionMetadataV15 {
+	fn from(ir: PalletViewFunctionMetadataIR<PortableForm>) -> Self {
+		ViewFunctionMetadataV15 {
+			name: ir.name,
+			id: ir.id,
+			inputs: ir.inputs.into_iter().map(Into::into).collect(),
+			output: ir.output.id,
+			docs: ir.docs,
+		}
+	}
+}
+
+impl From<PalletViewFunctionParamMetadataIR<PortableForm>> for ViewFunctionParamMetadataV15 {
+	fn from(ir: PalletViewFunctionParamMetadataIR<PortableForm>) -> Self {
+		ViewFunctionParamMetadataV15 { name: ir.name, ty: ir.ty.id }
 	}
 }
 
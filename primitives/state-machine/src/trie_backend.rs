@@ -27,6 +27,8 @@ use crate::{
 
 use codec::Codec;
 #[cfg(feature = "std")]
+use codec::Encode;
+#[cfg(feature = "std")]
 use hash_db::HashDB;
 use hash_db::Hasher;
 use sp_core::storage::{ChildInfo, StateVersion};
@@ -35,7 +37,7 @@ use sp_trie::PrefixedMemoryDB;
 use sp_trie::{
 	cache::{LocalTrieCache, TrieCache},
 	recorder::Recorder,
-	MemoryDB, StorageProof,
+	MemoryDB, StorageProof, TrieDiffIterator,
 };
 #[cfg(not(feature = "std"))]
 use sp_trie::{Error, NodeCodec};
@@ -362,6 +364,24 @@ where
 	pub fn extract_proof(mut self) -> Option<StorageProof> {
 		self.essence.recorder.take().map(|r| r.drain_storage_proof())
 	}
+
+	/// Returns an iterator over the keys that differ between the `previous` state and this
+	/// state.
+	///
+	/// See [`TrieBackendEssence::storage_diff`] for more details.
+	#[cfg(feature = "std")]
+	pub fn storage_diff<'a>(
+		&'a self,
+		previous: &'a Self,
+		child_info: Option<&'a ChildInfo>,
+		prefix: &[u8],
+		start_at: Option<&[u8]>,
+	) -> Result<TrieDiffIterator<'a, H>, crate::DefaultError>
+	where
+		H::Out: Ord,
+	{
+		self.essence.storage_diff(&previous.essence, child_info, prefix, start_at)
+	}
 }
 
 impl<S: TrieBackendStorage<H>, H: Hasher, C: TrieCacheProvider<H>> sp_std::fmt::Debug
@@ -477,6 +497,43 @@ where
 		self.essence.child_storage_root(child_info, delta, state_version)
 	}
 
+	#[cfg(feature = "std")]
+	fn full_storage_root<'a>(
+		&self,
+		delta: impl Iterator<Item = (&'a [u8], Option<&'a [u8]>)>,
+		child_deltas: impl Iterator<
+			Item = (&'a ChildInfo, impl Iterator<Item = (&'a [u8], Option<&'a [u8]>)>),
+		>,
+		state_version: StateVersion,
+	) -> (H::Out, PrefixedMemoryDB<H>)
+	where
+		H::Out: Ord + Encode,
+	{
+		// Same as the default implementation, except that the child roots are computed in
+		// parallel when there are enough of them. The root of the top trie is still computed on
+		// this thread once all child roots are known, as it includes them.
+		let child_deltas = child_deltas
+			.map(|(child_info, delta)| (child_info, delta.collect::<Vec<_>>()))
+			.collect::<Vec<_>>();
+
+		let mut txs = PrefixedMemoryDB::default();
+		let mut child_roots = Vec::with_capacity(child_deltas.len());
+		let roots = self.essence.child_storage_roots(&child_deltas, state_version);
+		for ((child_info, _), (child_root, empty, child_txs)) in child_deltas.iter().zip(roots) {
+			txs.consolidate(child_txs);
+			let prefixed_storage_key = child_info.prefixed_storage_key().into_inner();
+			child_roots.push((prefixed_storage_key, (!empty).then(|| child_root.encode())));
+		}
+
+		let (root, parent_txs) = self.storage_root(
+			delta.chain(child_roots.iter().map(|(k, v)| (&k[..], v.as_deref()))),
+			state_version,
+		);
+		txs.consolidate(parent_txs);
+
+		(root, txs)
+	}
+
 	fn register_overlay_stats(&self, _stats: &crate::stats::StateMachineStats) {}
 
 	fn usage_info(&self) -> crate::UsageInfo {
@@ -529,7 +586,7 @@ pub mod tests {
 	use sp_trie::{
 		cache::{CacheSize, SharedTrieCache},
 		trie_types::{TrieDBBuilder, TrieDBMutBuilderV0, TrieDBMutBuilderV1},
-		KeySpacedDBMut, PrefixedMemoryDB, Trie, TrieCache, TrieMut,
+		KeySpacedDBMut, PrefixedMemoryDB, Trie, TrieCache, TrieDiffType, TrieMut,
 	};
 	use std::iter;
 	use trie_db::NodeCodec;
@@ -969,6 +1026,64 @@ pub mod tests {
 		);
 	}
 
+	parameterized_test!(
+		full_storage_root_with_many_child_tries_works,
+		full_storage_root_with_many_child_tries_works_inner
+	);
+	fn full_storage_root_with_many_child_tries_works_inner(
+		state_version: StateVersion,
+		cache: Option<Cache>,
+		recorder: Option<Recorder>,
+	) {
+		let mut child_infos = vec![ChildInfo::new_default(CHILD_KEY_1)];
+		child_infos.extend((0..8u8).map(|i| ChildInfo::new_default(&[b'c', i])));
+		let values = (0..16u8).map(|i| (vec![i; 8], vec![i; 40])).collect::<Vec<_>>();
+		let child_delta = |child_info: &ChildInfo| {
+			let delta = values.iter().map(|(k, v)| (&k[..], Some(&v[..])));
+			if child_info.storage_key() == CHILD_KEY_1 {
+				// Empties the existing child trie.
+				vec![(&b"value3"[..], None), (&b"value4"[..], None)]
+			} else {
+				delta.collect()
+			}
+		};
+		let delta = vec![(&b"new-key"[..], Some(&b"new-value"[..]))];
+
+		let trie = test_trie(state_version, cache, recorder);
+		let (root, mut tx) = trie.full_storage_root(
+			delta.iter().cloned(),
+			child_infos.iter().map(|info| (info, child_delta(info).into_iter())),
+			state_version,
+		);
+
+		let fresh = test_trie(state_version, None, None);
+		let mut expected_tx = PrefixedMemoryDB::<BlakeTwo256>::default();
+		let mut child_roots = Vec::new();
+		for child_info in &child_infos {
+			let (child_root, empty, child_tx) = fresh.child_storage_root(
+				child_info,
+				child_delta(child_info).into_iter(),
+				state_version,
+			);
+			expected_tx.consolidate(child_tx);
+			child_roots.push((
+				child_info.prefixed_storage_key().into_inner(),
+				(!empty).then(|| child_root.encode()),
+			));
+		}
+		let (expected_root, parent_tx) = fresh.storage_root(
+			delta
+				.iter()
+				.cloned()
+				.chain(child_roots.iter().map(|(k, v)| (&k[..], v.as_deref()))),
+			state_version,
+		);
+		expected_tx.consolidate(parent_tx);
+
+		assert_eq!(root, expected_root);
+		assert_eq!(tx.drain(), expected_tx.drain());
+	}
+
 	parameterized_test!(
 		keys_with_empty_prefix_returns_all_keys,
 		keys_with_empty_prefix_returns_all_keys_inner
@@ -1028,6 +1143,55 @@ pub mod tests {
 		assert!(!backend.extract_proof().unwrap().is_empty());
 	}
 
+	parameterized_test!(storage_diff_works, storage_diff_works_inner);
+	fn storage_diff_works_inner(
+		state_version: StateVersion,
+		cache: Option<Cache>,
+		recorder: Option<Recorder>,
+	) {
+		let child_info = ChildInfo::new_default(CHILD_KEY_1);
+		let previous = test_trie(state_version, cache, recorder);
+		let (root, transaction) = previous.full_storage_root(
+			[
+				(&b"key"[..], None),
+				(&b"value1"[..], Some(&[43][..])),
+				(&b"value5"[..], Some(&[5][..])),
+			]
+			.into_iter(),
+			iter::once((&child_info, iter::once((&b"value3"[..], None)))),
+			state_version,
+		);
+		let (mut mdb, _) = test_db(state_version);
+		mdb.consolidate(transaction);
+		let current = TrieBackendBuilder::new(mdb, root).build();
+
+		let diff = |child_info: Option<&ChildInfo>, prefix: &[u8], start_at: Option<&[u8]>| {
+			current
+				.storage_diff(&previous, child_info, prefix, start_at)
+				.unwrap()
+				.map(|entry| entry.map(|entry| (entry.key, entry.diff_type)).unwrap())
+				.collect::<Vec<_>>()
+		};
+
+		assert_eq!(
+			diff(None, &[], None),
+			vec![
+				(child_info.prefixed_storage_key().into_inner(), TrieDiffType::Modified),
+				(b"key".to_vec(), TrieDiffType::Deleted),
+				(b"value1".to_vec(), TrieDiffType::Modified),
+				(b"value5".to_vec(), TrieDiffType::Added),
+			]
+		);
+		assert_eq!(
+			diff(None, b"value", Some(b"value1")),
+			vec![(b"value5".to_vec(), TrieDiffType::Added)]
+		);
+		assert_eq!(
+			diff(Some(&child_info), &[], None),
+			vec![(b"value3".to_vec(), TrieDiffType::Deleted)]
+		);
+	}
+
 	#[test]
 	fn proof_is_invalid_when_does_not_contains_root() {
 		let result = create_proof_check_backend::<BlakeTwo256>(
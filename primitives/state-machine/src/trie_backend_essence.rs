@@ -29,8 +29,6 @@ use hash_db::{self, AsHashDB, HashDB, HashDBRef, Hasher, Prefix};
 use parking_lot::RwLock;
 use sp_core::storage::{ChildInfo, ChildType, StateVersion};
 use sp_std::{boxed::Box, marker::PhantomData, vec::Vec};
-#[cfg(feature = "std")]
-use sp_trie::recorder::Recorder;
 use sp_trie::{
 	child_delta_trie_root, delta_trie_root, empty_child_trie_root, read_child_trie_hash,
 	read_child_trie_value, read_trie_value,
@@ -39,7 +37,15 @@ use sp_trie::{
 	TrieRecorder,
 };
 #[cfg(feature = "std")]
-use std::{collections::HashMap, sync::Arc};
+use sp_trie::{recorder::Recorder, TrieDiffIterator};
+#[cfg(feature = "std")]
+use std::{
+	collections::HashMap,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+};
 // In this module, we only use layout for read operation and empty root,
 // where V1 and V0 are equivalent.
 use sp_trie::LayoutV1 as Layout;
@@ -56,6 +62,14 @@ macro_rules! format {
 
 type Result<V> = sp_std::result::Result<V, crate::DefaultError>;
 
+/// The number of child tries from which on [`TrieBackendEssence::child_storage_roots`] computes
+/// their roots in parallel.
+///
+/// Below this the threads cost more than they save, also because they have to go without the trie
+/// node cache.
+#[cfg(feature = "std")]
+pub const PARALLEL_CHILD_ROOTS_THRESHOLD: usize = 4;
+
 /// Patricia trie-based storage trait.
 pub trait Storage<H: Hasher>: Send + Sync {
 	/// Get a trie node.
@@ -361,6 +375,47 @@ impl<S: TrieBackendStorage<H>, H: Hasher, C: TrieCacheProvider<H>> TrieBackendEs
 		result
 	}
 
+	/// Like [`Self::with_recorder_and_cache_for_storage_root`], but only uses the trie node cache
+	/// if `use_node_cache` is set.
+	///
+	/// The trie node cache can only be used by one trie operation at a time, so operations that
+	/// run in parallel go without it.
+	#[cfg(feature = "std")]
+	fn with_recorder_and_optional_cache_for_storage_root<R>(
+		&self,
+		storage_root: H::Out,
+		use_node_cache: bool,
+		callback: impl FnOnce(
+			Option<&mut dyn TrieRecorder<H::Out>>,
+			Option<&mut dyn TrieCache<NodeCodec<H>>>,
+		) -> (Option<H::Out>, R),
+	) -> R {
+		if use_node_cache {
+			return self.with_recorder_and_cache_for_storage_root(Some(storage_root), callback)
+		}
+
+		let mut recorder = self.recorder.as_ref().map(|r| r.as_trie_recorder(storage_root));
+		let recorder = match recorder.as_mut() {
+			Some(recorder) => Some(recorder as &mut dyn TrieRecorder<H::Out>),
+			None => None,
+		};
+
+		callback(recorder, None).1
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn with_recorder_and_optional_cache_for_storage_root<R>(
+		&self,
+		storage_root: H::Out,
+		_use_node_cache: bool,
+		callback: impl FnOnce(
+			Option<&mut dyn TrieRecorder<H::Out>>,
+			Option<&mut dyn TrieCache<NodeCodec<H>>>,
+		) -> (Option<H::Out>, R),
+	) -> R {
+		self.with_recorder_and_cache_for_storage_root(Some(storage_root), callback)
+	}
+
 	#[cfg(not(feature = "std"))]
 	fn with_recorder_and_cache_for_storage_root<R>(
 		&self,
@@ -503,6 +558,39 @@ where
 		})
 	}
 
+	/// Returns an iterator over the keys that differ between the `previous` state and this
+	/// state, either in the main trie or in the given child trie.
+	///
+	/// Only the keys that start with `prefix` and that are strictly greater than `start_at` are
+	/// reported. Sub-tries that are identical in both states are skipped without being read.
+	#[cfg(feature = "std")]
+	pub fn storage_diff<'a>(
+		&'a self,
+		previous: &'a Self,
+		child_info: Option<&'a ChildInfo>,
+		prefix: &[u8],
+		start_at: Option<&[u8]>,
+	) -> Result<TrieDiffIterator<'a, H>> {
+		let (previous_root, root) = match child_info {
+			Some(child_info) => (
+				previous
+					.child_root(child_info)?
+					.unwrap_or_else(empty_child_trie_root::<Layout<H>>),
+				self.child_root(child_info)?.unwrap_or_else(empty_child_trie_root::<Layout<H>>),
+			),
+			None => (previous.root, self.root),
+		};
+
+		let iter = TrieDiffIterator::new(previous, previous_root, self, root)
+			.with_prefix(prefix)
+			.with_start_at(start_at);
+
+		Ok(match child_info {
+			Some(child_info) => iter.with_keyspace(child_info.keyspace()),
+			None => iter,
+		})
+	}
+
 	/// Returns the hash value
 	pub fn storage_hash(&self, key: &[u8]) -> Result<Option<H::Out>> {
 		let map_e = |e| format!("Trie lookup error: {}", e);
@@ -655,6 +743,86 @@ where
 		child_info: &ChildInfo,
 		delta: impl Iterator<Item = (&'a [u8], Option<&'a [u8]>)>,
 		state_version: StateVersion,
+	) -> (H::Out, bool, PrefixedMemoryDB<H>) {
+		self.child_storage_root_inner(child_info, delta, state_version, true)
+	}
+
+	/// Returns the child storage roots for all the given child tries, in the given order.
+	///
+	/// When there are at least [`PARALLEL_CHILD_ROOTS_THRESHOLD`] child tries, they are spread
+	/// over all available cores. The result is the same as calling [`Self::child_storage_root`]
+	/// for each of them. While recording a proof the roots are always computed one after another,
+	/// as the recorder is locked for the whole computation of a root.
+	///
+	/// Only whole child tries are computed in parallel, the subtrees of a single trie are not.
+	/// A block changing mostly the top trie, or a single child trie, gains nothing from this.
+	#[cfg(feature = "std")]
+	pub fn child_storage_roots<'a>(
+		&self,
+		child_deltas: &[(&ChildInfo, Vec<(&'a [u8], Option<&'a [u8]>)>)],
+		state_version: StateVersion,
+	) -> Vec<(H::Out, bool, PrefixedMemoryDB<H>)> {
+		let threads = std::thread::available_parallelism()
+			.map_or(1, |n| n.get())
+			.min(child_deltas.len());
+
+		if child_deltas.len() < PARALLEL_CHILD_ROOTS_THRESHOLD ||
+			threads < 2 ||
+			self.recorder.is_some()
+		{
+			return child_deltas
+				.iter()
+				.map(|(child_info, delta)| {
+					self.child_storage_root(child_info, delta.iter().copied(), state_version)
+				})
+				.collect()
+		}
+
+		// The child tries are handed out one by one, so that a single big child trie doesn't
+		// keep a thread busy while the others idle.
+		let next = AtomicUsize::new(0);
+		let mut roots = std::thread::scope(|scope| {
+			let workers = (0..threads)
+				.map(|_| {
+					scope.spawn(|| {
+						let mut roots = Vec::new();
+						loop {
+							let index = next.fetch_add(1, Ordering::Relaxed);
+							let Some((child_info, delta)) = child_deltas.get(index) else {
+								break roots
+							};
+							roots.push((
+								index,
+								self.child_storage_root_inner(
+									child_info,
+									delta.iter().copied(),
+									state_version,
+									false,
+								),
+							));
+						}
+					})
+				})
+				.collect::<Vec<_>>();
+
+			workers
+				.into_iter()
+				.flat_map(|worker| {
+					worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+				})
+				.collect::<Vec<_>>()
+		});
+
+		roots.sort_unstable_by_key(|(index, _)| *index);
+		roots.into_iter().map(|(_, root)| root).collect()
+	}
+
+	fn child_storage_root_inner<'a>(
+		&self,
+		child_info: &ChildInfo,
+		delta: impl Iterator<Item = (&'a [u8], Option<&'a [u8]>)>,
+		state_version: StateVersion,
+		use_node_cache: bool,
 	) -> (H::Out, bool, PrefixedMemoryDB<H>) {
 		let default_root = match child_info.child_type() {
 			ChildType::ParentKeyId => empty_child_trie_root::<sp_trie::LayoutV1<H>>(),
@@ -669,8 +837,10 @@ where
 			},
 		};
 
-		let new_child_root =
-			self.with_recorder_and_cache_for_storage_root(Some(child_root), |recorder, cache| {
+		let new_child_root = self.with_recorder_and_optional_cache_for_storage_root(
+			child_root,
+			use_node_cache,
+			|recorder, cache| {
 				let mut eph = Ephemeral::new(self.backend_storage(), &mut write_overlay);
 				match match state_version {
 					StateVersion::V0 =>
@@ -698,7 +868,8 @@ where
 						(None, child_root)
 					},
 				}
-			});
+			},
+		);
 
 		let is_default = new_child_root == default_root;
 
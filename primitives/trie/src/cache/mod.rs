@@ -99,6 +99,21 @@ const LOCAL_NODE_CACHE_MAX_HEAP_SIZE: usize = 8 * 1024 * 1024;
 /// Same as [`LOCAL_NODE_CACHE_MAX_HEAP_SIZE`].
 const LOCAL_VALUE_CACHE_MAX_HEAP_SIZE: usize = 2 * 1024 * 1024;
 
+/// The number of local caches that need to be merged into an adaptive shared cache before it
+/// reconsiders how its budget is split.
+const ADAPTIVE_REBALANCE_INTERVAL: u32 = 64;
+
+/// The part of the heap budget (in percent) an adaptive shared cache moves in one go.
+const ADAPTIVE_STEP_PERCENT: usize = 5;
+
+/// The bounds (in percent) of the heap budget an adaptive shared cache gives to the node cache.
+///
+/// Values are only cached next to the nodes that contain them, so the node cache is never
+/// allowed to become smaller than the value cache.
+const ADAPTIVE_MIN_NODE_PERCENT: usize = 50;
+/// See [`ADAPTIVE_MIN_NODE_PERCENT`].
+const ADAPTIVE_MAX_NODE_PERCENT: usize = 95;
+
 /// The size of the shared cache.
 #[derive(Debug, Clone, Copy)]
 pub struct CacheSize(usize);
@@ -115,6 +130,32 @@ impl CacheSize {
 	}
 }
 
+/// Counters of one of the caches held by a [`SharedTrieCache`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+	/// Number of lookups served by a [`LocalTrieCache`].
+	pub local_hits: u64,
+	/// Number of lookups served by the shared cache.
+	pub shared_hits: u64,
+	/// Number of lookups that were found in neither cache.
+	pub misses: u64,
+	/// Number of items evicted from the shared cache to stay within its budget.
+	pub evictions: u64,
+	/// Memory currently used by the shared cache, in bytes.
+	pub used_memory: usize,
+	/// Memory the shared cache is allowed to use, in bytes.
+	pub memory_budget: usize,
+}
+
+/// Statistics of a [`SharedTrieCache`], see [`SharedTrieCache::stats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TrieCacheStats {
+	/// Statistics of the node cache.
+	pub node_cache: CacheStats,
+	/// Statistics of the value cache.
+	pub value_cache: CacheStats,
+}
+
 /// A limiter for the local node cache. This makes sure the local cache doesn't grow too big.
 #[derive(Default)]
 pub struct LocalNodeCacheLimiter {
@@ -420,6 +461,8 @@ impl<H: Hasher> Drop for LocalTrieCache<H> {
 			self.value_cache.get_mut().drain(),
 			self.shared_value_cache_access.get_mut().drain().map(|(key, ())| key),
 		);
+
+		shared_inner.record_stats(&self.stats);
 	}
 }
 
@@ -950,6 +993,34 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn cache_stats_are_recorded() {
+		let (db, root) = create_trie();
+
+		let shared_cache = Cache::new(CACHE_SIZE);
+		assert_eq!(shared_cache.stats().node_cache.misses, 0);
+
+		for _ in 0..2 {
+			let local_cache = shared_cache.local_cache();
+			let mut cache = local_cache.as_trie_db_cache(root);
+			let trie = TrieDBBuilder::<Layout>::new(&db, &root).with_cache(&mut cache).build();
+			assert_eq!(TEST_DATA[0].1.to_vec(), trie.get(TEST_DATA[0].0).unwrap().unwrap());
+			assert_eq!(TEST_DATA[0].1.to_vec(), trie.get(TEST_DATA[0].0).unwrap().unwrap());
+		}
+
+		let stats = shared_cache.stats();
+		// The first local cache had to walk the trie, the second one found the value in the
+		// shared cache and didn't need to touch any node.
+		assert_eq!(stats.value_cache.misses, 1);
+		assert_eq!(stats.value_cache.shared_hits, 1);
+		assert_eq!(stats.value_cache.local_hits, 2);
+		assert!(stats.node_cache.misses > 0);
+		assert_eq!(stats.node_cache.shared_hits, 0);
+		assert_eq!(stats.node_cache.evictions, 0);
+		assert!(stats.node_cache.used_memory > 0);
+		assert!(stats.node_cache.memory_budget + stats.value_cache.memory_budget <= CACHE_SIZE_RAW);
+	}
+
 	#[test]
 	fn cache_respects_bounds() {
 		let (mut db, root) = create_trie();
@@ -17,7 +17,7 @@
 
 ///! Provides the [`SharedNodeCache`], the [`SharedValueCache`] and the [`SharedTrieCache`]
 ///! that combines both caches and is exported to the outside.
-use super::{CacheSize, NodeCached};
+use super::{CacheSize, CacheStats, NodeCached, TrieCacheStats, TrieHitStats};
 use hash_db::Hasher;
 use hashbrown::{hash_set::Entry as SetEntry, HashSet};
 use nohash_hasher::BuildNoHashHasher;
@@ -25,7 +25,7 @@ use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
 use schnellru::LruMap;
 use std::{
 	hash::{BuildHasher, Hasher as _},
-	sync::Arc,
+	sync::{atomic::Ordering, Arc},
 };
 use trie_db::{node::NodeOwned, CachedValue};
 
@@ -56,6 +56,9 @@ pub struct SharedNodeCacheLimiter {
 	///
 	/// Reset on every update.
 	max_items_evicted: usize,
+
+	/// The number of elements that got evicted over the lifetime of the cache.
+	total_items_evicted: u64,
 }
 
 impl<H> schnellru::Limiter<H, NodeOwned<H>> for SharedNodeCacheLimiter
@@ -121,6 +124,7 @@ where
 	fn on_removed(&mut self, _: &mut H, node: &mut NodeOwned<H>) {
 		self.heap_size -= node.size_in_bytes() - std::mem::size_of::<NodeOwned<H>>();
 		self.items_evicted += 1;
+		self.total_items_evicted += 1;
 	}
 
 	#[inline]
@@ -155,6 +159,9 @@ pub struct SharedValueCacheLimiter {
 	///
 	/// Reset on every update.
 	max_items_evicted: usize,
+
+	/// The number of elements that got evicted over the lifetime of the cache.
+	total_items_evicted: u64,
 }
 
 impl<H> schnellru::Limiter<ValueCacheKey<H>, CachedValue<H>> for SharedValueCacheLimiter
@@ -220,6 +227,7 @@ where
 			self.known_storage_keys.remove(&key.storage_key);
 		}
 		self.items_evicted += 1;
+		self.total_items_evicted += 1;
 	}
 
 	#[inline]
@@ -259,6 +267,7 @@ impl<H: AsRef<[u8]> + Eq + std::hash::Hash> SharedNodeCache<H> {
 				heap_size: 0,
 				items_evicted: 0,
 				max_items_evicted: 0, // Will be set during `update`.
+				total_items_evicted: 0,
 			}),
 		}
 	}
@@ -314,6 +323,21 @@ impl<H: AsRef<[u8]> + Eq + std::hash::Hash> SharedNodeCache<H> {
 	fn reset(&mut self) {
 		self.lru.clear();
 	}
+
+	/// Change the heap budget, evicting the oldest nodes until the cache fits into it.
+	fn set_max_heap_size(&mut self, max_heap_size: usize) {
+		self.lru.limiter_mut().max_heap_size = max_heap_size;
+		while self.lru.limiter().heap_size > max_heap_size && self.lru.pop_oldest().is_some() {}
+	}
+
+	/// Returns the used memory and the memory budget of this cache in bytes.
+	fn memory(&self) -> (usize, usize) {
+		let limiter = self.lru.limiter();
+		(
+			self.lru.memory_usage() + limiter.heap_size,
+			limiter.max_inline_size + limiter.max_heap_size,
+		)
+	}
 }
 
 /// The hash of [`ValueCacheKey`].
@@ -490,6 +514,7 @@ impl<H: Eq + std::hash::Hash + Clone + Copy + AsRef<[u8]>> SharedValueCache<H> {
 					known_storage_keys: Default::default(),
 					items_evicted: 0,
 					max_items_evicted: 0, // Will be set during `update`.
+					total_items_evicted: 0,
 				},
 				Default::default(),
 			),
@@ -561,12 +586,132 @@ impl<H: Eq + std::hash::Hash + Clone + Copy + AsRef<[u8]>> SharedValueCache<H> {
 	fn reset(&mut self) {
 		self.lru.clear();
 	}
+
+	/// Change the heap budget, evicting the oldest values until the cache fits into it.
+	fn set_max_heap_size(&mut self, max_heap_size: usize) {
+		self.lru.limiter_mut().max_heap_size = max_heap_size;
+		while self.lru.limiter().heap_size > max_heap_size && self.lru.pop_oldest().is_some() {}
+	}
+
+	/// Returns the used memory and the memory budget of this cache in bytes.
+	fn memory(&self) -> (usize, usize) {
+		let limiter = self.lru.limiter();
+		(
+			self.lru.memory_usage() + limiter.heap_size,
+			limiter.max_inline_size + limiter.max_heap_size,
+		)
+	}
+}
+
+/// Lookup counters accumulated from all [`LocalTrieCache`](super::LocalTrieCache)s that were
+/// merged back into the shared cache.
+#[derive(Default, Clone, Copy)]
+struct LookupCounters {
+	local_hits: u64,
+	shared_hits: u64,
+	shared_fetch_attempts: u64,
+}
+
+impl LookupCounters {
+	fn add(&mut self, stats: &super::HitStats) {
+		self.local_hits += stats.local_hits.load(Ordering::Relaxed);
+		self.shared_hits += stats.shared_hits.load(Ordering::Relaxed);
+		self.shared_fetch_attempts += stats.shared_fetch_attempts.load(Ordering::Relaxed);
+	}
+
+	fn misses(&self) -> u64 {
+		self.shared_fetch_attempts.saturating_sub(self.shared_hits)
+	}
+
+	fn since(&self, earlier: &Self) -> Self {
+		Self {
+			local_hits: self.local_hits - earlier.local_hits,
+			shared_hits: self.shared_hits - earlier.shared_hits,
+			shared_fetch_attempts: self.shared_fetch_attempts - earlier.shared_fetch_attempts,
+		}
+	}
+
+	fn to_stats(&self, evictions: u64, (used_memory, memory_budget): (usize, usize)) -> CacheStats {
+		CacheStats {
+			local_hits: self.local_hits,
+			shared_hits: self.shared_hits,
+			misses: self.misses(),
+			evictions,
+			used_memory,
+			memory_budget,
+		}
+	}
+
+	/// The share of shared cache lookups that missed, in parts per million.
+	fn miss_rate(&self) -> u64 {
+		if self.shared_fetch_attempts == 0 {
+			0
+		} else {
+			self.misses() * 1_000_000 / self.shared_fetch_attempts
+		}
+	}
+}
+
+/// What a single cache went through since the last rebalancing.
+#[derive(Default, Clone, Copy)]
+struct Window {
+	lookups: LookupCounters,
+	evictions: u64,
+}
+
+/// State of the adaptive sizing of a [`SharedTrieCache`].
+///
+/// The inline budget of both maps is fixed when the cache is created, as the maps can not
+/// shrink their allocation. Only the heap budget is moved between the node and the value cache.
+struct AdaptiveSizing {
+	/// The heap budget shared by both caches, in bytes.
+	heap_budget: usize,
+	/// The part of `heap_budget` given to the node cache, in percent.
+	node_percent: usize,
+	/// Number of local caches merged since the last rebalancing.
+	merges: u32,
+	/// Snapshot of the node cache counters at the last rebalancing.
+	node_snapshot: Window,
+	/// Snapshot of the value cache counters at the last rebalancing.
+	value_snapshot: Window,
+}
+
+impl AdaptiveSizing {
+	/// Returns the new node cache share if the budget should be moved, given what both caches
+	/// went through since the last rebalancing.
+	///
+	/// The budget is moved towards the cache with the higher miss rate, but only if that cache
+	/// had to evict items; a cache that still has room does not benefit from more memory.
+	fn next_node_percent(&self, node: &Window, value: &Window) -> Option<usize> {
+		let node_rate = node.lookups.miss_rate();
+		let value_rate = value.lookups.miss_rate();
+
+		let next = if node.evictions > 0 && node_rate > value_rate {
+			self.node_percent + super::ADAPTIVE_STEP_PERCENT
+		} else if value.evictions > 0 && value_rate > node_rate {
+			self.node_percent.saturating_sub(super::ADAPTIVE_STEP_PERCENT)
+		} else {
+			return None
+		};
+
+		let next = next.clamp(super::ADAPTIVE_MIN_NODE_PERCENT, super::ADAPTIVE_MAX_NODE_PERCENT);
+		(next != self.node_percent).then_some(next)
+	}
+
+	/// Returns the heap budget of the node and the value cache.
+	fn heap_budgets(&self) -> (usize, usize) {
+		let node = self.heap_budget / 100 * self.node_percent;
+		(node, self.heap_budget - node)
+	}
 }
 
 /// The inner of [`SharedTrieCache`].
 pub(super) struct SharedTrieCacheInner<H: Hasher> {
 	node_cache: SharedNodeCache<H::Out>,
 	value_cache: SharedValueCache<H::Out>,
+	node_lookups: LookupCounters,
+	value_lookups: LookupCounters,
+	adaptive: Option<AdaptiveSizing>,
 }
 
 impl<H: Hasher> SharedTrieCacheInner<H> {
@@ -591,6 +736,73 @@ impl<H: Hasher> SharedTrieCacheInner<H> {
 	pub(super) fn node_cache_mut(&mut self) -> &mut SharedNodeCache<H::Out> {
 		&mut self.node_cache
 	}
+
+	/// Account the lookups of a local cache that is merged back into this cache.
+	///
+	/// In adaptive mode this also moves the heap budget between the caches every
+	/// [`ADAPTIVE_REBALANCE_INTERVAL`](super::ADAPTIVE_REBALANCE_INTERVAL) merges.
+	pub(super) fn record_stats(&mut self, stats: &TrieHitStats) {
+		self.node_lookups.add(&stats.node_cache);
+		self.value_lookups.add(&stats.value_cache);
+
+		let node = Window {
+			lookups: self.node_lookups,
+			evictions: self.node_cache.lru.limiter().total_items_evicted,
+		};
+		let value = Window {
+			lookups: self.value_lookups,
+			evictions: self.value_cache.lru.limiter().total_items_evicted,
+		};
+
+		let Some(adaptive) = &mut self.adaptive else { return };
+
+		adaptive.merges += 1;
+		if adaptive.merges < super::ADAPTIVE_REBALANCE_INTERVAL {
+			return
+		}
+		adaptive.merges = 0;
+
+		let node_window = Window {
+			lookups: node.lookups.since(&adaptive.node_snapshot.lookups),
+			evictions: node.evictions - adaptive.node_snapshot.evictions,
+		};
+		let value_window = Window {
+			lookups: value.lookups.since(&adaptive.value_snapshot.lookups),
+			evictions: value.evictions - adaptive.value_snapshot.evictions,
+		};
+
+		let next = adaptive.next_node_percent(&node_window, &value_window);
+		adaptive.node_snapshot = node;
+		adaptive.value_snapshot = value;
+
+		let Some(node_percent) = next else { return };
+		adaptive.node_percent = node_percent;
+		let (node_heap_budget, value_heap_budget) = adaptive.heap_budgets();
+
+		tracing::debug!(
+			target: super::LOG_TARGET,
+			"Rebalanced the shared trie cache: {}% of the heap budget for nodes (node_cache_max_heap_size = {}, value_cache_max_heap_size = {})",
+			node_percent,
+			node_heap_budget,
+			value_heap_budget,
+		);
+
+		self.node_cache.set_max_heap_size(node_heap_budget);
+		self.value_cache.set_max_heap_size(value_heap_budget);
+	}
+
+	fn stats(&self) -> TrieCacheStats {
+		TrieCacheStats {
+			node_cache: self.node_lookups.to_stats(
+				self.node_cache.lru.limiter().total_items_evicted,
+				self.node_cache.memory(),
+			),
+			value_cache: self.value_lookups.to_stats(
+				self.value_cache.lru.limiter().total_items_evicted,
+				self.value_cache.memory(),
+			),
+		}
+	}
 }
 
 /// The shared trie cache.
@@ -613,6 +825,19 @@ impl<H: Hasher> Clone for SharedTrieCache<H> {
 impl<H: Hasher> SharedTrieCache<H> {
 	/// Create a new [`SharedTrieCache`].
 	pub fn new(cache_size: CacheSize) -> Self {
+		Self::with_sizing(cache_size, false)
+	}
+
+	/// Create a new [`SharedTrieCache`] that moves its memory budget between the node and the
+	/// value cache depending on the observed workload.
+	///
+	/// It starts with the same split as [`Self::new`]. See [`Self::stats`] for the counters the
+	/// decision is based on.
+	pub fn new_adaptive(cache_size: CacheSize) -> Self {
+		Self::with_sizing(cache_size, true)
+	}
+
+	fn with_sizing(cache_size: CacheSize, adaptive: bool) -> Self {
 		let total_budget = cache_size.0;
 
 		// Split our memory budget between the two types of caches.
@@ -639,7 +864,8 @@ impl<H: Hasher> SharedTrieCache<H> {
 
 		tracing::debug!(
 			target: super::LOG_TARGET,
-			"Configured a shared trie cache with a budget of ~{} bytes (node_cache_max_inline_size = {}, node_cache_max_heap_size = {}, value_cache_max_inline_size = {}, value_cache_max_heap_size = {})",
+			"Configured a{} shared trie cache with a budget of ~{} bytes (node_cache_max_inline_size = {}, node_cache_max_heap_size = {}, value_cache_max_inline_size = {}, value_cache_max_heap_size = {})",
+			if adaptive { "n adaptive" } else { "" },
 			total_budget,
 			node_cache_max_inline_size,
 			node_cache_max_heap_size,
@@ -647,6 +873,18 @@ impl<H: Hasher> SharedTrieCache<H> {
 			value_cache_max_heap_size,
 		);
 
+		let adaptive = adaptive.then(|| {
+			let heap_budget = node_cache_max_heap_size.saturating_add(value_cache_max_heap_size);
+			AdaptiveSizing {
+				heap_budget,
+				node_percent: (node_cache_max_heap_size as f64 / heap_budget.max(1) as f64 * 100.0)
+					as usize,
+				merges: 0,
+				node_snapshot: Default::default(),
+				value_snapshot: Default::default(),
+			}
+		});
+
 		Self {
 			inner: Arc::new(RwLock::new(SharedTrieCacheInner {
 				node_cache: SharedNodeCache::new(
@@ -657,6 +895,9 @@ impl<H: Hasher> SharedTrieCache<H> {
 					value_cache_max_inline_size,
 					value_cache_max_heap_size,
 				),
+				node_lookups: Default::default(),
+				value_lookups: Default::default(),
+				adaptive,
 			})),
 		}
 	}
@@ -715,6 +956,14 @@ impl<H: Hasher> SharedTrieCache<H> {
 		node_cache_size + value_cache_size
 	}
 
+	/// Returns the hit, miss and eviction counters of the node and the value cache.
+	///
+	/// Lookups are only accounted once the [`LocalTrieCache`](super::LocalTrieCache) that did
+	/// them is merged back into this cache.
+	pub fn stats(&self) -> TrieCacheStats {
+		self.inner.read().stats()
+	}
+
 	/// Reset the node cache.
 	pub fn reset_node_cache(&self) {
 		self.inner.write().node_cache.reset();
@@ -753,6 +1002,39 @@ mod tests {
 	use super::*;
 	use sp_core::H256 as Hash;
 
+	#[test]
+	fn adaptive_sizing_follows_misses() {
+		let window = |attempts, hits, evictions| Window {
+			lookups: LookupCounters {
+				local_hits: 0,
+				shared_hits: hits,
+				shared_fetch_attempts: attempts,
+			},
+			evictions,
+		};
+		let sizing = AdaptiveSizing {
+			heap_budget: 1000,
+			node_percent: 80,
+			merges: 0,
+			node_snapshot: Default::default(),
+			value_snapshot: Default::default(),
+		};
+
+		// The value cache misses more and is full, so it gets more memory.
+		assert_eq!(sizing.next_node_percent(&window(10, 9, 1), &window(10, 2, 5)), Some(75));
+		// The value cache misses more but still has room.
+		assert_eq!(sizing.next_node_percent(&window(10, 9, 1), &window(10, 2, 0)), None);
+		// The node cache misses more and is full.
+		assert_eq!(sizing.next_node_percent(&window(10, 2, 5), &window(10, 9, 1)), Some(85));
+		// Nothing happened.
+		assert_eq!(sizing.next_node_percent(&window(0, 0, 0), &window(0, 0, 0)), None);
+
+		// The node cache never gets less than the value cache.
+		let sizing = AdaptiveSizing { node_percent: 50, ..sizing };
+		assert_eq!(sizing.next_node_percent(&window(10, 9, 1), &window(10, 2, 5)), None);
+		assert_eq!(sizing.heap_budgets(), (500, 500));
+	}
+
 	#[test]
 	fn shared_value_cache_works() {
 		let mut cache = SharedValueCache::<sp_core::H256>::new(usize::MAX, 10 * 10);
@@ -0,0 +1,126 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compute the node sizes of a trie under a given layout without storing it.
+
+use hash_db::{Hasher, Prefix};
+use sp_std::vec::Vec;
+use trie_db::{
+	trie_visit, ChildReference, NodeCodec as _, ProcessEncodedNode, TrieHash, TrieLayout,
+};
+
+/// The root and the storage footprint of a trie, as computed by [`trie_layout_stats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TrieLayoutStats<H> {
+	/// The root of the trie.
+	pub root: H,
+	/// Number of nodes that are stored in the database, i.e. that are not inlined.
+	pub nodes: u64,
+	/// Sum of the encoded sizes of those nodes.
+	pub node_bytes: u64,
+	/// Number of values that are stored outside of their node.
+	pub hashed_values: u64,
+	/// Sum of the sizes of those values.
+	pub hashed_value_bytes: u64,
+}
+
+impl<H> TrieLayoutStats<H> {
+	/// The number of bytes the trie takes in the database.
+	pub fn total_bytes(&self) -> u64 {
+		self.node_bytes + self.hashed_value_bytes
+	}
+}
+
+struct StatsCollector<L: TrieLayout> {
+	stats: TrieLayoutStats<TrieHash<L>>,
+}
+
+impl<L: TrieLayout> ProcessEncodedNode<TrieHash<L>> for StatsCollector<L> {
+	fn process(
+		&mut self,
+		_: Prefix,
+		encoded_node: Vec<u8>,
+		is_root: bool,
+	) -> ChildReference<TrieHash<L>> {
+		let len = encoded_node.len();
+		if !is_root && len < <L::Hash as Hasher>::LENGTH {
+			let mut inline = TrieHash::<L>::default();
+			inline.as_mut()[..len].copy_from_slice(&encoded_node);
+			return ChildReference::Inline(inline, len)
+		}
+
+		let hash = <L::Hash as Hasher>::hash(&encoded_node);
+		self.stats.nodes += 1;
+		self.stats.node_bytes += len as u64;
+		if is_root {
+			self.stats.root = hash;
+		}
+		ChildReference::Hash(hash)
+	}
+
+	fn process_inner_hashed_value(&mut self, _: Prefix, value: &[u8]) -> TrieHash<L> {
+		self.stats.hashed_values += 1;
+		self.stats.hashed_value_bytes += value.len() as u64;
+		<L::Hash as Hasher>::hash(value)
+	}
+}
+
+/// Compute the root and the storage footprint of the trie containing `input` under the layout
+/// `L`.
+///
+/// The trie is built node by node from `input`, which must be sorted by key, and nothing is kept
+/// in memory besides the nodes on the path to the current key.
+pub fn trie_layout_stats<L, I, A, B>(input: I) -> TrieLayoutStats<TrieHash<L>>
+where
+	L: TrieLayout,
+	I: IntoIterator<Item = (A, B)>,
+	A: AsRef<[u8]> + Ord,
+	B: AsRef<[u8]>,
+{
+	let mut collector = StatsCollector::<L> {
+		stats: TrieLayoutStats { root: L::Codec::hashed_null_node(), ..Default::default() },
+	};
+	trie_visit::<L, _, _, _, _>(input, &mut collector);
+	collector.stats
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{LayoutV0, LayoutV1};
+	use sp_core::Blake2Hasher;
+	use trie_db::TrieConfiguration;
+
+	#[test]
+	fn stats_match_the_layout() {
+		let input = (0..64u8)
+			.map(|i| (vec![i; 4], if i % 2 == 0 { vec![i; 8] } else { vec![i; 64] }))
+			.collect::<Vec<_>>();
+
+		let v0 = trie_layout_stats::<LayoutV0<Blake2Hasher>, _, _, _>(input.clone());
+		let v1 = trie_layout_stats::<LayoutV1<Blake2Hasher>, _, _, _>(input.clone());
+
+		assert_eq!(v0.root, LayoutV0::<Blake2Hasher>::trie_root(input.clone()));
+		assert_eq!(v1.root, LayoutV1::<Blake2Hasher>::trie_root(input));
+		assert_eq!(v0.hashed_values, 0);
+		// Only the values above the inline threshold are hashed.
+		assert_eq!(v1.hashed_values, 32);
+		assert_eq!(v1.hashed_value_bytes, 32 * 64);
+		assert_eq!(v0.nodes, v1.nodes);
+		assert!(v1.node_bytes < v0.node_bytes);
+	}
+}
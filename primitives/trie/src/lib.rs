@@ -22,12 +22,17 @@
 #[cfg(feature = "std")]
 pub mod cache;
 mod error;
+mod layout_stats;
 mod node_codec;
 mod node_header;
 #[cfg(feature = "std")]
 pub mod recorder;
 mod storage_proof;
 mod trie_codec;
+#[cfg(feature = "std")]
+mod trie_diff;
+#[cfg(feature = "std")]
+mod trie_nodes;
 mod trie_stream;
 
 /// Our `NodeCodec`-specific error.
@@ -35,6 +40,8 @@ pub use error::Error;
 /// Various re-exports from the `hash-db` crate.
 pub use hash_db::{HashDB as HashDBT, EMPTY_PREFIX};
 use hash_db::{Hasher, Prefix};
+/// Compute the storage footprint of a trie under a given layout.
+pub use layout_stats::{trie_layout_stats, TrieLayoutStats};
 /// Various re-exports from the `memory-db` crate.
 pub use memory_db::{prefixed_key, HashKey, KeyFunction, PrefixedKey};
 /// The Substrate format implementation of `NodeCodec`.
@@ -53,6 +60,12 @@ pub use trie_db::{
 	CError, DBValue, Query, Recorder, Trie, TrieCache, TrieConfiguration, TrieDBIterator,
 	TrieDBKeyIterator, TrieDBRawIterator, TrieLayout, TrieMut, TrieRecorder,
 };
+/// Iterate over the differences between two tries.
+#[cfg(feature = "std")]
+pub use trie_diff::{TrieDiffEntry, TrieDiffIterator, TrieDiffType};
+/// Walk the database entries of a trie.
+#[cfg(feature = "std")]
+pub use trie_nodes::walk_trie_nodes;
 /// The Substrate format implementation of `TrieStream`.
 pub use trie_stream::TrieStream;
 
@@ -0,0 +1,540 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Iterate over the keys that differ between two tries.
+//!
+//! Both tries are walked at the same time, node by node. Sub-tries that are referenced by the
+//! same hash on both sides are identical and are never loaded from the database, so the cost of
+//! the diff depends on the size of the changes and not on the size of the tries.
+
+use crate::{Error, NodeCodec};
+use hash_db::{HashDBRef, Hasher};
+use std::sync::Arc;
+use trie_db::{
+	nibble_ops::NIBBLE_LENGTH,
+	node::{NodeHandlePlan, NodePlan, ValuePlan},
+	DBValue, NodeCodec as NodeCodecT, TrieError,
+};
+
+/// Result of the [`TrieDiffIterator`].
+pub type Result<T, H> = trie_db::Result<T, H, Error<H>>;
+
+/// The way a key changed between the previous and the current trie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrieDiffType {
+	/// The key is only present in the current trie.
+	Added,
+	/// The key is present in both tries with different values.
+	Modified,
+	/// The key is only present in the previous trie.
+	Deleted,
+}
+
+/// A key that differs between the previous and the current trie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrieDiffEntry<H> {
+	/// The key that changed.
+	pub key: Vec<u8>,
+	/// The way the key changed.
+	pub diff_type: TrieDiffType,
+	/// The hash of the value in the previous trie, if any.
+	pub previous_hash: Option<H>,
+	/// The hash of the value in the current trie, if any.
+	pub hash: Option<H>,
+}
+
+/// Reference to a child node.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) enum NodeRef<H> {
+	/// The node is stored in the database under this hash.
+	Hash(H),
+	/// The encoded node is stored inline in its parent.
+	Inline(Vec<u8>),
+}
+
+/// A decoded trie node.
+pub(crate) struct DecodedNode<H> {
+	/// The hash of the node, `None` for inline nodes.
+	pub(crate) hash: Option<H>,
+	/// The nibbles of the partial key.
+	pub(crate) partial: Vec<u8>,
+	/// The hash of the value stored at this node.
+	pub(crate) value_hash: Option<H>,
+	/// Whether the value is stored in a separate database entry under `value_hash`.
+	pub(crate) value_node: bool,
+	/// The children of the node.
+	pub(crate) children: [Option<NodeRef<H>>; NIBBLE_LENGTH],
+}
+
+/// Position of the walk in one of the tries.
+#[derive(Clone)]
+enum Cursor<H> {
+	/// There is nothing below this position.
+	Empty,
+	/// A node starts at this position and was not loaded yet.
+	Unloaded(NodeRef<H>),
+	/// A node that was loaded and whose partial key was consumed up to the given nibble.
+	Loaded(Arc<DecodedNode<H>>, usize),
+}
+
+impl<H: PartialEq> Cursor<H> {
+	/// Returns `true` if both cursors are guaranteed to point to identical sub-tries.
+	fn is_same(&self, other: &Self) -> bool {
+		match (self, other) {
+			(Cursor::Empty, Cursor::Empty) => true,
+			(Cursor::Unloaded(a), Cursor::Unloaded(b)) => a == b,
+			(Cursor::Loaded(a, a_consumed), Cursor::Loaded(b, b_consumed)) =>
+				a.hash.is_some() && a.hash == b.hash && a_consumed == b_consumed,
+			_ => false,
+		}
+	}
+}
+
+/// One of the two tries walked by the [`TrieDiffIterator`].
+struct Side<'a, H: Hasher> {
+	db: &'a dyn HashDBRef<H, DBValue>,
+	keyspace: Option<&'a [u8]>,
+}
+
+impl<'a, H: Hasher> Side<'a, H> {
+	/// Load the node at the given position of the walk.
+	fn load(&self, node: &NodeRef<H::Out>, path: &[u8]) -> Result<DecodedNode<H::Out>, H::Out> {
+		match node {
+			NodeRef::Hash(hash) => {
+				let (mut prefix, padding) = nibbles_to_prefix(path);
+				if let Some(keyspace) = self.keyspace {
+					prefix.splice(0..0, keyspace.iter().copied());
+				}
+
+				let data = self
+					.db
+					.get(hash, (&prefix, padding))
+					.ok_or_else(|| Box::new(TrieError::IncompleteDatabase(*hash)))?;
+				decode_node::<H>(Some(*hash), &data)
+			},
+			NodeRef::Inline(data) => decode_node::<H>(None, data),
+		}
+	}
+
+	/// Returns the value hash at the cursor position and the cursors of the children.
+	fn expand(
+		&self,
+		cursor: Cursor<H::Out>,
+		path: &[u8],
+	) -> Result<(Option<H::Out>, [Cursor<H::Out>; NIBBLE_LENGTH]), H::Out> {
+		let mut children: [Cursor<H::Out>; NIBBLE_LENGTH] = Default::default();
+
+		let (node, consumed) = match cursor {
+			Cursor::Empty => return Ok((None, children)),
+			Cursor::Unloaded(node) => (Arc::new(self.load(&node, path)?), 0),
+			Cursor::Loaded(node, consumed) => (node, consumed),
+		};
+
+		// The position is in the middle of the partial key of the node.
+		if let Some(nibble) = node.partial.get(consumed) {
+			children[*nibble as usize] = Cursor::Loaded(node.clone(), consumed + 1);
+			return Ok((None, children))
+		}
+
+		for (cursor, child) in children.iter_mut().zip(node.children.iter()) {
+			if let Some(child) = child {
+				*cursor = Cursor::Unloaded(child.clone());
+			}
+		}
+		Ok((node.value_hash, children))
+	}
+}
+
+impl<H> Default for Cursor<H> {
+	fn default() -> Self {
+		Cursor::Empty
+	}
+}
+
+/// Iterator over the keys that differ between two tries, in lexicographic order.
+///
+/// Only the nodes of the sub-tries that differ are read from the databases. The values are not
+/// read either, the iterator reports the hashes of the values.
+pub struct TrieDiffIterator<'a, H: Hasher> {
+	previous: Side<'a, H>,
+	current: Side<'a, H>,
+	/// The nibbles of the prefix that all the reported keys must start with.
+	prefix: Vec<u8>,
+	/// The nibbles of the key after which the keys are reported.
+	start_at: Option<Vec<u8>>,
+	/// The positions that remain to be visited, the next one is at the end.
+	stack: Vec<(Vec<u8>, Cursor<H::Out>, Cursor<H::Out>)>,
+}
+
+impl<'a, H: Hasher> TrieDiffIterator<'a, H> {
+	/// Create a new iterator over the keys that differ between the trie at `previous_root` and
+	/// the trie at `root`.
+	pub fn new(
+		previous_db: &'a dyn HashDBRef<H, DBValue>,
+		previous_root: H::Out,
+		db: &'a dyn HashDBRef<H, DBValue>,
+		root: H::Out,
+	) -> Self {
+		Self {
+			previous: Side { db: previous_db, keyspace: None },
+			current: Side { db, keyspace: None },
+			prefix: Vec::new(),
+			start_at: None,
+			stack: vec![(
+				Vec::new(),
+				Cursor::Unloaded(NodeRef::Hash(previous_root)),
+				Cursor::Unloaded(NodeRef::Hash(root)),
+			)],
+		}
+	}
+
+	/// Prefix the database keys of the nodes of both tries with the given keyspace.
+	///
+	/// This is required to walk child tries.
+	pub fn with_keyspace(mut self, keyspace: &'a [u8]) -> Self {
+		self.previous.keyspace = Some(keyspace);
+		self.current.keyspace = Some(keyspace);
+		self
+	}
+
+	/// Only report the keys that start with the given prefix.
+	pub fn with_prefix(mut self, prefix: &[u8]) -> Self {
+		self.prefix = bytes_to_nibbles(prefix);
+		self
+	}
+
+	/// Only report the keys that are strictly greater than the given key.
+	pub fn with_start_at(mut self, start_at: Option<&[u8]>) -> Self {
+		self.start_at = start_at.map(bytes_to_nibbles);
+		self
+	}
+
+	/// Visit the next position of the walk and return the difference found at this position.
+	fn visit_next(&mut self) -> Result<Option<TrieDiffEntry<H::Out>>, H::Out> {
+		let Some((path, previous, current)) = self.stack.pop() else { return Ok(None) };
+
+		// Identical sub-tries do not need to be loaded.
+		if previous.is_same(&current) {
+			return Ok(None)
+		}
+
+		let report_value = match self.start_at.as_ref() {
+			// The position is on the way to the start key, only keys after it are reported.
+			Some(start_at) if start_at.starts_with(&path) => false,
+			// The whole sub-trie is located before the start key.
+			Some(start_at) if path < *start_at => return Ok(None),
+			_ => path.len() >= self.prefix.len() && path.len() % 2 == 0,
+		};
+
+		let (previous_hash, previous_children) = self.previous.expand(previous, &path)?;
+		let (hash, children) = self.current.expand(current, &path)?;
+
+		let prefix_nibble = self.prefix.get(path.len()).copied();
+		for (nibble, (previous, current)) in
+			previous_children.into_iter().zip(children.into_iter()).enumerate().rev()
+		{
+			// Only walk towards the prefix until it is reached.
+			if prefix_nibble.map_or(false, |prefix_nibble| prefix_nibble as usize != nibble) {
+				continue
+			}
+			if matches!((&previous, &current), (Cursor::Empty, Cursor::Empty)) {
+				continue
+			}
+
+			let mut child_path = path.clone();
+			child_path.push(nibble as u8);
+			self.stack.push((child_path, previous, current));
+		}
+
+		if !report_value {
+			return Ok(None)
+		}
+
+		let diff_type = match (previous_hash, hash) {
+			(None, Some(_)) => TrieDiffType::Added,
+			(Some(_), None) => TrieDiffType::Deleted,
+			(Some(previous_hash), Some(hash)) if previous_hash != hash => TrieDiffType::Modified,
+			_ => return Ok(None),
+		};
+
+		Ok(Some(TrieDiffEntry { key: nibbles_to_prefix(&path).0, diff_type, previous_hash, hash }))
+	}
+}
+
+impl<'a, H: Hasher> Iterator for TrieDiffIterator<'a, H> {
+	type Item = Result<TrieDiffEntry<H::Out>, H::Out>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		while !self.stack.is_empty() {
+			match self.visit_next() {
+				Ok(Some(entry)) => return Some(Ok(entry)),
+				Ok(None) => continue,
+				Err(error) => {
+					// The walk cannot continue after an error.
+					self.stack.clear();
+					return Some(Err(error))
+				},
+			}
+		}
+
+		None
+	}
+}
+
+/// Decode the given encoded node.
+pub(crate) fn decode_node<H: Hasher>(
+	hash: Option<H::Out>,
+	data: &[u8],
+) -> Result<DecodedNode<H::Out>, H::Out> {
+	let decode_error = |error| Box::new(TrieError::DecoderError(hash.unwrap_or_default(), error));
+
+	let (partial, value, children) = match NodeCodec::<H>::decode_plan(data)
+		.map_err(decode_error)?
+	{
+		NodePlan::Empty => (None, None, Default::default()),
+		NodePlan::Leaf { partial, value } => (Some(partial), Some(value), Default::default()),
+		NodePlan::Branch { value, children } => (None, value, children),
+		NodePlan::NibbledBranch { partial, value, children } => (Some(partial), value, children),
+		// Substrate tries do not use extension nodes.
+		NodePlan::Extension { .. } => return Err(decode_error(Error::BadFormat)),
+	};
+
+	let partial = partial
+		.map(|partial| {
+			let partial = partial.build(data);
+			(0..partial.len()).map(|i| partial.at(i)).collect()
+		})
+		.unwrap_or_default();
+
+	let value_node = matches!(value, Some(ValuePlan::Node(_)));
+	let value_hash = value.map(|value| match value {
+		ValuePlan::Inline(range) => H::hash(&data[range]),
+		ValuePlan::Node(range) => decode_hash::<H>(&data[range]),
+	});
+
+	let children = children.map(|child| {
+		child.map(|child| match child {
+			NodeHandlePlan::Hash(range) => NodeRef::Hash(decode_hash::<H>(&data[range])),
+			NodeHandlePlan::Inline(range) => NodeRef::Inline(data[range].to_vec()),
+		})
+	});
+
+	Ok(DecodedNode { hash, partial, value_hash, value_node, children })
+}
+
+/// Decode a hash stored in a node.
+fn decode_hash<H: Hasher>(data: &[u8]) -> H::Out {
+	let mut hash = H::Out::default();
+	hash.as_mut().copy_from_slice(data);
+	hash
+}
+
+/// Split the given bytes into nibbles.
+fn bytes_to_nibbles(bytes: &[u8]) -> Vec<u8> {
+	bytes.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect()
+}
+
+/// Convert the given nibbles into a database key prefix.
+///
+/// Returns the packed full bytes and the last nibble, left aligned, for odd lengths.
+pub(crate) fn nibbles_to_prefix(nibbles: &[u8]) -> (Vec<u8>, Option<u8>) {
+	let packed = nibbles.chunks_exact(2).map(|pair| (pair[0] << 4) | pair[1]).collect();
+	let padding = (nibbles.len() % 2 == 1).then(|| nibbles[nibbles.len() - 1] << 4);
+	(packed, padding)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{LayoutV0, LayoutV1, PrefixedMemoryDB, TrieDBMutBuilder, TrieLayout, TrieMut};
+	use sp_core::Blake2Hasher;
+
+	type Hash = <Blake2Hasher as Hasher>::Out;
+
+	/// Insert the given entries into a trie and return its root.
+	fn build_trie<L: TrieLayout<Hash = Blake2Hasher>>(
+		db: &mut PrefixedMemoryDB<Blake2Hasher>,
+		entries: &[(&[u8], &[u8])],
+	) -> Hash {
+		let mut root = Default::default();
+		{
+			let mut trie = TrieDBMutBuilder::<L>::new(db, &mut root).build();
+			for (key, value) in entries {
+				trie.insert(key, value).unwrap();
+			}
+		}
+		root
+	}
+
+	/// Apply the given changes to an existing trie and return the new root.
+	fn update_trie<L: TrieLayout<Hash = Blake2Hasher>>(
+		db: &mut PrefixedMemoryDB<Blake2Hasher>,
+		mut root: Hash,
+		changes: &[(&[u8], Option<&[u8]>)],
+	) -> Hash {
+		{
+			let mut trie = TrieDBMutBuilder::<L>::from_existing(db, &mut root).build();
+			for (key, value) in changes {
+				match value {
+					Some(value) => trie.insert(key, value).unwrap(),
+					None => trie.remove(key).unwrap(),
+				};
+			}
+		}
+		root
+	}
+
+	fn diff(
+		db: &PrefixedMemoryDB<Blake2Hasher>,
+		previous_root: Hash,
+		root: Hash,
+		prefix: &[u8],
+		start_at: Option<&[u8]>,
+	) -> Vec<(Vec<u8>, TrieDiffType)> {
+		TrieDiffIterator::<Blake2Hasher>::new(db, previous_root, db, root)
+			.with_prefix(prefix)
+			.with_start_at(start_at)
+			.map(|entry| entry.map(|entry| (entry.key, entry.diff_type)).unwrap())
+			.collect()
+	}
+
+	const ENTRIES: [(&[u8], &[u8]); 6] = [
+		(b"alpha", b"1"),
+		(b"alphabet", b"2"),
+		(b"beta", b"3"),
+		(b"gamma", b"4"),
+		(b"gammas", &[5; 64]),
+		(b"zeta", b"6"),
+	];
+
+	#[test]
+	fn identical_tries_have_no_diff() {
+		let mut db = PrefixedMemoryDB::default();
+		let root = build_trie::<LayoutV1<_>>(&mut db, &ENTRIES);
+
+		assert!(diff(&db, root, root, &[], None).is_empty());
+	}
+
+	#[test]
+	fn diff_reports_added_modified_and_deleted_keys() {
+		let mut db = PrefixedMemoryDB::default();
+		let previous_root = build_trie::<LayoutV1<_>>(&mut db, &ENTRIES);
+		let root = update_trie::<LayoutV1<_>>(
+			&mut db,
+			previous_root,
+			&[
+				(b"alpha", None),
+				(b"alphabets", Some(b"7")),
+				(b"beta", Some(b"8")),
+				(b"gammas", Some(&[9; 64])),
+				(b"a", Some(b"10")),
+			],
+		);
+
+		assert_eq!(
+			diff(&db, previous_root, root, &[], None),
+			vec![
+				(b"a".to_vec(), TrieDiffType::Added),
+				(b"alpha".to_vec(), TrieDiffType::Deleted),
+				(b"alphabets".to_vec(), TrieDiffType::Added),
+				(b"beta".to_vec(), TrieDiffType::Modified),
+				(b"gammas".to_vec(), TrieDiffType::Modified),
+			]
+		);
+
+		// The reverse diff swaps the added and deleted keys.
+		assert_eq!(
+			diff(&db, root, previous_root, &[], None),
+			vec![
+				(b"a".to_vec(), TrieDiffType::Deleted),
+				(b"alpha".to_vec(), TrieDiffType::Added),
+				(b"alphabets".to_vec(), TrieDiffType::Deleted),
+				(b"beta".to_vec(), TrieDiffType::Modified),
+				(b"gammas".to_vec(), TrieDiffType::Modified),
+			]
+		);
+	}
+
+	#[test]
+	fn diff_with_prefix_and_start_at() {
+		let mut db = PrefixedMemoryDB::default();
+		let previous_root = build_trie::<LayoutV1<_>>(&mut db, &ENTRIES);
+		let root = update_trie::<LayoutV1<_>>(
+			&mut db,
+			previous_root,
+			&[
+				(b"alpha", Some(b"7")),
+				(b"alphabet", None),
+				(b"alphas", Some(b"8")),
+				(b"beta", None),
+			],
+		);
+
+		assert_eq!(
+			diff(&db, previous_root, root, b"alpha", None),
+			vec![
+				(b"alpha".to_vec(), TrieDiffType::Modified),
+				(b"alphabet".to_vec(), TrieDiffType::Deleted),
+				(b"alphas".to_vec(), TrieDiffType::Added),
+			]
+		);
+		assert_eq!(
+			diff(&db, previous_root, root, b"alphab", None),
+			vec![(b"alphabet".to_vec(), TrieDiffType::Deleted)]
+		);
+		assert_eq!(
+			diff(&db, previous_root, root, b"alpha", Some(b"alpha")),
+			vec![
+				(b"alphabet".to_vec(), TrieDiffType::Deleted),
+				(b"alphas".to_vec(), TrieDiffType::Added),
+			]
+		);
+		assert_eq!(
+			diff(&db, previous_root, root, &[], Some(b"alphabet")),
+			vec![
+				(b"alphas".to_vec(), TrieDiffType::Added),
+				(b"beta".to_vec(), TrieDiffType::Deleted)
+			]
+		);
+		assert!(diff(&db, previous_root, root, b"gamma", None).is_empty());
+	}
+
+	#[test]
+	fn diff_between_state_versions_ignores_value_encoding() {
+		let mut db = PrefixedMemoryDB::default();
+		let previous_root = build_trie::<LayoutV0<_>>(&mut db, &ENTRIES);
+		let root = build_trie::<LayoutV1<_>>(&mut db, &ENTRIES);
+		assert_ne!(previous_root, root);
+
+		assert!(diff(&db, previous_root, root, &[], None).is_empty());
+	}
+
+	#[test]
+	fn missing_node_is_reported() {
+		let mut db = PrefixedMemoryDB::default();
+		let previous_root = build_trie::<LayoutV1<_>>(&mut db, &ENTRIES);
+		let root = update_trie::<LayoutV1<_>>(&mut db, previous_root, &[(b"beta", None)]);
+
+		let empty_db = PrefixedMemoryDB::<Blake2Hasher>::default();
+		let mut iter = TrieDiffIterator::<Blake2Hasher>::new(&db, previous_root, &empty_db, root);
+		assert!(matches!(
+			iter.next(),
+			Some(Err(error)) if matches!(*error, TrieError::IncompleteDatabase(hash) if hash == root)
+		));
+		assert!(iter.next().is_none());
+	}
+}
@@ -0,0 +1,154 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Walk the nodes of a trie as they are stored in the database.
+
+use crate::{
+	trie_diff::{decode_node, nibbles_to_prefix, NodeRef, Result},
+	NodeCodec,
+};
+use hash_db::{HashDBRef, Hasher, Prefix};
+use trie_db::{DBValue, NodeCodec as NodeCodecT, TrieError};
+
+/// Walk all the database entries of the trie at `root`, depth first.
+///
+/// `visit` is called with the database prefix, the hash and the encoded data of every node and
+/// of every value that is stored outside of its node. When it returns `false` for a node, the
+/// children of that node are skipped. Inline nodes are not stored in the database and are not
+/// visited, but their children are.
+///
+/// The `keyspace` is prepended to every prefix, as done for child tries.
+pub fn walk_trie_nodes<H: Hasher>(
+	db: &dyn HashDBRef<H, DBValue>,
+	root: &H::Out,
+	keyspace: Option<&[u8]>,
+	mut visit: impl FnMut(Prefix, &H::Out, &[u8]) -> bool,
+) -> Result<(), H::Out> {
+	if *root == <NodeCodec<H> as NodeCodecT>::hashed_null_node() {
+		return Ok(())
+	}
+
+	let db_prefix = |path: &[u8]| {
+		let (mut prefix, padding) = nibbles_to_prefix(path);
+		if let Some(keyspace) = keyspace {
+			prefix.splice(0..0, keyspace.iter().copied());
+		}
+		(prefix, padding)
+	};
+	let get = |hash: &H::Out, prefix: &(Vec<u8>, Option<u8>)| {
+		db.get(hash, (&prefix.0, prefix.1))
+			.ok_or_else(|| Box::new(TrieError::IncompleteDatabase(*hash)))
+	};
+
+	let mut stack = vec![(Vec::new(), NodeRef::Hash(*root))];
+	while let Some((mut path, node)) = stack.pop() {
+		let node = match node {
+			NodeRef::Hash(hash) => {
+				let prefix = db_prefix(&path);
+				let data = get(&hash, &prefix)?;
+				if !visit((&prefix.0, prefix.1), &hash, &data) {
+					continue
+				}
+				decode_node::<H>(Some(hash), &data)?
+			},
+			NodeRef::Inline(data) => decode_node::<H>(None, &data)?,
+		};
+
+		path.extend_from_slice(&node.partial);
+		if let (true, Some(value_hash)) = (node.value_node, node.value_hash) {
+			// Values are stored under the full key of the node holding them.
+			let prefix = db_prefix(&path);
+			let data = get(&value_hash, &prefix)?;
+			visit((&prefix.0, prefix.1), &value_hash, &data);
+		}
+
+		for (nibble, child) in node.children.into_iter().enumerate().rev() {
+			if let Some(child) = child {
+				let mut child_path = path.clone();
+				child_path.push(nibble as u8);
+				stack.push((child_path, child));
+			}
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{prefixed_key, LayoutV1, PrefixedMemoryDB, TrieDBMutBuilder, TrieMut};
+	use sp_core::Blake2Hasher;
+	use std::collections::BTreeSet;
+
+	fn build_trie(db: &mut PrefixedMemoryDB<Blake2Hasher>) -> <Blake2Hasher as Hasher>::Out {
+		let mut root = Default::default();
+		{
+			let mut trie = TrieDBMutBuilder::<LayoutV1<Blake2Hasher>>::new(db, &mut root).build();
+			for (key, value) in [
+				(&b"alpha"[..], &b"1"[..]),
+				(b"alphabet", b"2"),
+				(b"beta", &[3; 64]),
+				(b"gamma", b"4"),
+			] {
+				trie.insert(key, value).unwrap();
+			}
+		}
+		root
+	}
+
+	#[test]
+	fn walk_visits_all_stored_nodes() {
+		let mut db = PrefixedMemoryDB::default();
+		let root = build_trie(&mut db);
+
+		let mut visited = BTreeSet::new();
+		walk_trie_nodes::<Blake2Hasher>(&db, &root, None, |prefix, hash, _| {
+			visited.insert(prefixed_key::<Blake2Hasher>(hash, prefix));
+			true
+		})
+		.unwrap();
+
+		let stored = db
+			.drain()
+			.into_iter()
+			.filter(|(_, (_, rc))| *rc > 0)
+			.map(|(key, _)| key)
+			.collect::<BTreeSet<_>>();
+		assert_eq!(visited, stored);
+	}
+
+	#[test]
+	fn walk_skips_children_and_reports_missing_nodes() {
+		let mut db = PrefixedMemoryDB::default();
+		let root = build_trie(&mut db);
+
+		let mut count = 0;
+		walk_trie_nodes::<Blake2Hasher>(&db, &root, None, |_, _, _| {
+			count += 1;
+			false
+		})
+		.unwrap();
+		assert_eq!(count, 1);
+
+		let empty = PrefixedMemoryDB::<Blake2Hasher>::default();
+		assert!(matches!(
+			*walk_trie_nodes::<Blake2Hasher>(&empty, &root, None, |_, _, _| true).unwrap_err(),
+			TrieError::IncompleteDatabase(hash) if hash == root
+		));
+	}
+}
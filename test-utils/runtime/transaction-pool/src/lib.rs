@@ -353,6 +353,17 @@ impl sc_transaction_pool::ChainApi for TestApi {
 	) -> Result<TreeRoute<Self::Block>, Self::Error> {
 		sp_blockchain::tree_route::<Block, TestApi>(self, from, to).map_err(Into::into)
 	}
+
+	fn best_block_hash(&self) -> Result<<Self::Block as BlockT>::Hash, Self::Error> {
+		Ok(self
+			.chain
+			.read()
+			.block_by_number
+			.values()
+			.rev()
+			.find_map(|blocks| blocks.iter().find(|b| b.1.is_best()).map(|b| b.0.header.hash()))
+			.expect("the genesis block is the best block if no other block is; qed"))
+	}
 }
 
 impl sp_blockchain::HeaderMetadata<Block> for TestApi {
@@ -21,12 +21,13 @@
 use sc_block_builder::{BlockBuilderApi, BlockBuilderProvider};
 use sc_cli::{CliConfiguration, ImportParams, Result, SharedParams};
 use sc_client_api::{Backend as ClientBackend, BlockBackend, StorageProvider, UsageProvider};
+use sc_executor::WasmtimeProfiler;
 use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
 use sp_runtime::{traits::Block as BlockT, OpaqueExtrinsic};
 
 use clap::Parser;
-use std::{fmt::Debug, sync::Arc};
+use std::{fmt::Debug, fs, path::PathBuf, sync::Arc};
 
 use super::bench::{Benchmark, BenchmarkParams};
 
@@ -74,6 +75,18 @@ pub struct BlockCmd {
 	/// This should only be used for performance analysis and not for final results.
 	#[arg(long)]
 	pub enable_trie_cache: bool,
+
+	/// Profile the runtime and write the time spent in each of its functions to this file.
+	///
+	/// The profile is written in the folded stack format, which can be rendered as a flamegraph
+	/// with `inferno-flamegraph` or `flamegraph.pl`. The runtime is considerably slower while
+	/// profiled, so the weight comparison is meaningless in this mode.
+	#[arg(long, value_name = "PATH")]
+	pub profile: Option<PathBuf>,
+
+	/// The profiler used when `profile` is set.
+	#[arg(skip)]
+	profiler: Arc<WasmtimeProfiler>,
 }
 
 impl BlockCmd {
@@ -93,7 +106,13 @@ impl BlockCmd {
 		C::Api: ApiExt<Block> + BlockBuilderApi<Block>,
 	{
 		// Put everything in the benchmark type to have the generic types handy.
-		Benchmark::new(client, self.params.clone()).run()
+		Benchmark::new(client, self.params.clone()).run()?;
+
+		if let Some(path) = &self.profile {
+			fs::write(path, self.profiler.take_folded())?;
+			log::info!("Wrote the runtime profile to {:?}", path);
+		}
+		Ok(())
 	}
 }
 
@@ -114,4 +133,8 @@ impl CliConfiguration for BlockCmd {
 			Ok(None)
 		}
 	}
+
+	fn runtime_profiler(&self) -> Option<Arc<WasmtimeProfiler>> {
+		self.profile.as_ref().map(|_| self.profiler.clone())
+	}
 }
@@ -0,0 +1,176 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Walking SCALE encoded data along the type registry to find the leaves required to decode it.
+
+use crate::Error;
+use codec::{Compact, Decode};
+use scale_info::{form::PortableForm, PortableRegistry, TypeDef, TypeDefPrimitive};
+use std::collections::BTreeSet;
+
+/// Identifies a leaf by its type id and for enums by the variant index.
+pub(crate) type LeafKey = (u32, Option<u32>);
+
+/// Collects the leaves that are touched while decoding, by the type ids of the registry.
+pub(crate) struct Collector<'a> {
+	registry: &'a PortableRegistry,
+	pub(crate) used: BTreeSet<LeafKey>,
+}
+
+impl<'a> Collector<'a> {
+	pub(crate) fn new(registry: &'a PortableRegistry) -> Self {
+		Self { registry, used: Default::default() }
+	}
+
+	/// Decode a value of type `id` from `data`, remembering every leaf on the way.
+	pub(crate) fn visit(&mut self, id: u32, data: &mut &[u8]) -> Result<(), Error> {
+		let ty = self.registry.resolve(id).ok_or(Error::UnknownType(id))?;
+
+		match &ty.type_def {
+			TypeDef::Composite(composite) => {
+				if !composite.fields.is_empty() {
+					self.used.insert((id, None));
+				}
+				for field in &composite.fields {
+					self.visit(field.ty.id, data)?;
+				}
+			},
+			TypeDef::Variant(variant) => {
+				let index = u8::decode(data)?;
+				let variant = variant
+					.variants
+					.iter()
+					.find(|v| v.index == index)
+					.ok_or(Error::UnknownVariant { type_id: id, index })?;
+
+				self.used.insert((id, Some(index.into())));
+				for field in &variant.fields {
+					self.visit(field.ty.id, data)?;
+				}
+			},
+			TypeDef::Sequence(sequence) => {
+				self.used.insert((id, None));
+				let len = Compact::<u32>::decode(data)?.0;
+				for _ in 0..len {
+					self.visit(sequence.type_param.id, data)?;
+				}
+			},
+			TypeDef::Array(array) => {
+				self.used.insert((id, None));
+				for _ in 0..array.len {
+					self.visit(array.type_param.id, data)?;
+				}
+			},
+			TypeDef::Tuple(tuple) => {
+				if !tuple.fields.is_empty() {
+					self.used.insert((id, None));
+				}
+				for field in &tuple.fields {
+					self.visit(field.id, data)?;
+				}
+			},
+			TypeDef::Primitive(primitive) => skip_primitive(primitive, data)?,
+			TypeDef::Compact(_) => skip_compact(data)?,
+			TypeDef::BitSequence(bit_sequence) => {
+				self.used.insert((id, None));
+
+				let store_size = bit_store_size(self.registry, bit_sequence.bit_store_type.id)?;
+
+				let bits = Compact::<u32>::decode(data)?.0 as usize;
+				let store_bits = store_size * 8;
+				take(data, bits.div_ceil(store_bits) * store_size)?;
+			},
+		}
+
+		Ok(())
+	}
+}
+
+/// The size of a fixed size primitive.
+fn primitive_size(primitive: &TypeDefPrimitive) -> Option<usize> {
+	Some(match primitive {
+		TypeDefPrimitive::Bool | TypeDefPrimitive::U8 | TypeDefPrimitive::I8 => 1,
+		TypeDefPrimitive::U16 | TypeDefPrimitive::I16 => 2,
+		TypeDefPrimitive::Char | TypeDefPrimitive::U32 | TypeDefPrimitive::I32 => 4,
+		TypeDefPrimitive::U64 | TypeDefPrimitive::I64 => 8,
+		TypeDefPrimitive::U128 | TypeDefPrimitive::I128 => 16,
+		TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => 32,
+		TypeDefPrimitive::Str => return None,
+	})
+}
+
+/// The size in bytes of the store type of a bit sequence.
+pub(crate) fn bit_store_size(registry: &PortableRegistry, id: u32) -> Result<usize, Error> {
+	match registry.resolve(id).map(|ty| &ty.type_def) {
+		Some(TypeDef::Primitive(TypeDefPrimitive::U8)) => Ok(1),
+		Some(TypeDef::Primitive(TypeDefPrimitive::U16)) => Ok(2),
+		Some(TypeDef::Primitive(TypeDefPrimitive::U32)) => Ok(4),
+		Some(TypeDef::Primitive(TypeDefPrimitive::U64)) => Ok(8),
+		_ => Err(Error::UnsupportedBitStore(id)),
+	}
+}
+
+fn take(data: &mut &[u8], len: usize) -> Result<(), Error> {
+	if data.len() < len {
+		return Err(Error::Decode("Not enough data".into()))
+	}
+
+	*data = &data[len..];
+	Ok(())
+}
+
+fn skip_primitive(primitive: &TypeDefPrimitive, data: &mut &[u8]) -> Result<(), Error> {
+	match primitive_size(primitive) {
+		Some(size) => take(data, size),
+		None => {
+			let len = Compact::<u32>::decode(data)?.0;
+			take(data, len as usize)
+		},
+	}
+}
+
+/// Skip a compact encoded integer of any width.
+fn skip_compact(data: &mut &[u8]) -> Result<(), Error> {
+	let first = *data.first().ok_or_else(|| Error::Decode("Not enough data".into()))?;
+
+	let len = match first & 0b11 {
+		0b00 => 1,
+		0b01 => 2,
+		0b10 => 4,
+		_ => 1 + (first >> 2) as usize + 4,
+	};
+
+	take(data, len)
+}
+
+/// Returns the primitive a `Compact` wraps, looking through single field composites like
+/// `Perbill`.
+pub(crate) fn compact_inner(
+	registry: &PortableRegistry,
+	id: u32,
+) -> Result<Option<TypeDefPrimitive>, Error> {
+	let ty: &scale_info::Type<PortableForm> = registry.resolve(id).ok_or(Error::UnknownType(id))?;
+
+	match &ty.type_def {
+		TypeDef::Primitive(primitive) => Ok(Some(primitive.clone())),
+		TypeDef::Composite(composite) if composite.fields.len() == 1 =>
+			compact_inner(registry, composite.fields[0].ty.id),
+		TypeDef::Composite(composite) if composite.fields.is_empty() => Ok(None),
+		TypeDef::Tuple(tuple) if tuple.fields.is_empty() => Ok(None),
+		_ => Err(Error::UnsupportedCompact(id)),
+	}
+}
@@ -0,0 +1,687 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merkleized runtime metadata, as specified by
+//! [RFC-0078](https://polkadot-fellows.github.io/RFCs/approved/0078-merkleized-metadata.html).
+//!
+//! The types of the runtime metadata that are required to decode an extrinsic are split into the
+//! leaves of a binary merkle tree. The root of this tree, together with the information needed
+//! to decode an extrinsic and some chain specific [`ExtraInfo`], forms the [`MetadataDigest`].
+//! Its hash is the metadata hash that the `CheckMetadataHash` signed extension includes in the
+//! signed payload. All hashes are blake3 hashes.
+//!
+//! A device with limited resources, like a hardware wallet, doesn't need the full metadata to
+//! decode an extrinsic. [`MerkleizedMetadata::generate_proof`] collects only the leaves that
+//! are required to decode the given extrinsic and [`Proof::verify`] checks them against the
+//! metadata hash.
+//!
+//! ```ignore
+//! let metadata = MerkleizedMetadata::new(metadata, extra_info)?;
+//! let proof = metadata.generate_proof(&extrinsic, Some(&additional_signed))?;
+//!
+//! assert!(proof.verify(&metadata.hash()));
+//! ```
+
+use codec::{Compact, Decode, Encode};
+use frame_metadata::{v15::RuntimeMetadataV15, RuntimeMetadata, RuntimeMetadataPrefixed};
+use scale_info::{
+	form::PortableForm, PortableRegistry, TypeDef as RegistryTypeDef, TypeDefPrimitive,
+};
+use sp_metadata_ir::MetadataIR;
+use std::collections::{BTreeMap, BTreeSet};
+
+mod decode;
+mod merkle;
+mod types;
+
+use decode::{Collector, LeafKey};
+pub use types::*;
+
+/// Errors of this crate.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	#[error("Only metadata V15 is supported")]
+	UnsupportedMetadataVersion,
+	#[error("Type `{0}` is not part of the registry")]
+	UnknownType(u32),
+	#[error("Type `{0}` can not be compact encoded")]
+	UnsupportedCompact(u32),
+	#[error("Type `{0}` is not a valid store of a bit sequence")]
+	UnsupportedBitStore(u32),
+	#[error("Type `{0}` is not a valid order of a bit sequence")]
+	UnsupportedBitOrder(u32),
+	#[error("Type `{type_id}` has no variant with index `{index}`")]
+	UnknownVariant { type_id: u32, index: u8 },
+	#[error("Extrinsic version `{0}` is not supported")]
+	UnsupportedExtrinsicVersion(u8),
+	#[error("Trailing bytes after decoding the extrinsic")]
+	TrailingBytes,
+	#[error("Failed to decode: {0}")]
+	Decode(String),
+}
+
+impl From<codec::Error> for Error {
+	fn from(error: codec::Error) -> Self {
+		Self::Decode(error.to_string())
+	}
+}
+
+/// Decode SCALE encoded [`RuntimeMetadataPrefixed`], as returned by the
+/// `Metadata_metadata_at_version` runtime api, into metadata V15.
+pub fn decode_metadata(mut metadata: &[u8]) -> Result<RuntimeMetadataV15, Error> {
+	match RuntimeMetadataPrefixed::decode(&mut metadata)?.1 {
+		RuntimeMetadata::V15(metadata) => Ok(metadata),
+		_ => Err(Error::UnsupportedMetadataVersion),
+	}
+}
+
+/// Returns the `SS58Prefix` constant of the `System` pallet.
+pub fn ss58_prefix(metadata: &RuntimeMetadataV15) -> Option<u16> {
+	metadata
+		.pallets
+		.iter()
+		.find(|p| p.name == "System")?
+		.constants
+		.iter()
+		.find(|c| c.name == "SS58Prefix")
+		.and_then(|c| u16::decode(&mut &c.value[..]).ok())
+}
+
+/// The merkleized form of the runtime metadata.
+pub struct MerkleizedMetadata {
+	registry: PortableRegistry,
+	extrinsic: frame_metadata::v15::ExtrinsicMetadata<PortableForm>,
+	extrinsic_metadata: ExtrinsicMetadata,
+	extra_info: ExtraInfo,
+	/// The id in the type information of each type with leaves, by its id in the registry.
+	type_ids: BTreeMap<u32, u32>,
+	leaves: Vec<Type>,
+	leaf_hashes: Vec<Hash>,
+	leaf_indices: BTreeMap<LeafKey, usize>,
+}
+
+impl MerkleizedMetadata {
+	/// Merkleize the given `metadata`.
+	pub fn new(metadata: RuntimeMetadataV15, extra_info: ExtraInfo) -> Result<Self, Error> {
+		let registry = metadata.types;
+		let extrinsic = metadata.extrinsic;
+
+		// Only the types reachable from the extrinsic are part of the type information. The ones
+		// with leaves are numbered in the order of their id in the registry.
+		let mut accessible = BTreeSet::new();
+		let roots = [extrinsic.address_ty.id, extrinsic.call_ty.id, extrinsic.signature_ty.id]
+			.into_iter()
+			.chain(
+				extrinsic
+					.signed_extensions
+					.iter()
+					.flat_map(|e| [e.ty.id, e.additional_signed.id]),
+			);
+		for id in roots {
+			collect_accessible_types(&registry, id, &mut accessible)?;
+		}
+		let type_ids: BTreeMap<u32, u32> = accessible
+			.into_iter()
+			.filter(|id| registry.resolve(*id).is_some_and(has_leaves))
+			.zip(0..)
+			.collect();
+
+		let context = TypeContext { registry: &registry, type_ids: &type_ids };
+		let extrinsic_metadata = ExtrinsicMetadata {
+			version: extrinsic.version,
+			address_ty: context.type_ref(extrinsic.address_ty.id)?,
+			call_ty: context.type_ref(extrinsic.call_ty.id)?,
+			signature_ty: context.type_ref(extrinsic.signature_ty.id)?,
+			signed_extensions: extrinsic
+				.signed_extensions
+				.iter()
+				.map(|e| {
+					Ok(SignedExtensionMetadata {
+						identifier: e.identifier.clone(),
+						included_in_extrinsic: context.type_ref(e.ty.id)?,
+						included_in_signed_data: context.type_ref(e.additional_signed.id)?,
+					})
+				})
+				.collect::<Result<_, Error>>()?,
+		};
+
+		// Ordered by type id and for enums by variant index.
+		let mut leaves = Vec::new();
+		for (id, type_id) in &type_ids {
+			leaves.extend(context.leaves_of(*id, *type_id)?);
+		}
+
+		let leaf_hashes = leaves.iter().map(Type::hash).collect();
+		let leaf_indices = leaves.iter().enumerate().map(|(i, leaf)| (leaf_key(leaf), i)).collect();
+
+		Ok(Self {
+			registry,
+			extrinsic,
+			extrinsic_metadata,
+			extra_info,
+			type_ids,
+			leaves,
+			leaf_hashes,
+			leaf_indices,
+		})
+	}
+
+	/// Merkleize the metadata in its intermediate representation.
+	pub fn from_metadata_ir(metadata: MetadataIR, extra_info: ExtraInfo) -> Result<Self, Error> {
+		match sp_metadata_ir::into_latest(metadata).1 {
+			RuntimeMetadata::V15(metadata) => Self::new(metadata, extra_info),
+			_ => Err(Error::UnsupportedMetadataVersion),
+		}
+	}
+
+	/// The leaves of the type information tree.
+	pub fn leaves(&self) -> &[Type] {
+		&self.leaves
+	}
+
+	/// The digest of this metadata.
+	pub fn digest(&self) -> MetadataDigest {
+		MetadataDigest::new_v1(
+			merkle::root(&self.leaf_hashes),
+			&self.extrinsic_metadata,
+			self.extra_info.clone(),
+		)
+	}
+
+	/// The metadata hash.
+	pub fn hash(&self) -> Hash {
+		self.digest().hash()
+	}
+
+	/// Generate the proof for decoding `extrinsic`.
+	///
+	/// `extrinsic` is the encoded extrinsic including its length prefix. Pass the data that is
+	/// only part of the signed payload as `additional_signed` to also include the types needed
+	/// to decode it.
+	pub fn generate_proof(
+		&self,
+		extrinsic: &[u8],
+		additional_signed: Option<&[u8]>,
+	) -> Result<Proof, Error> {
+		let mut collector = Collector::new(&self.registry);
+		let mut data = extrinsic;
+
+		Compact::<u32>::decode(&mut data)?;
+		let version = u8::decode(&mut data)?;
+		if version & 0b0111_1111 != self.extrinsic.version {
+			return Err(Error::UnsupportedExtrinsicVersion(version & 0b0111_1111))
+		}
+
+		if version & 0b1000_0000 != 0 {
+			collector.visit(self.extrinsic.address_ty.id, &mut data)?;
+			collector.visit(self.extrinsic.signature_ty.id, &mut data)?;
+			for extension in &self.extrinsic.signed_extensions {
+				collector.visit(extension.ty.id, &mut data)?;
+			}
+		}
+		collector.visit(self.extrinsic.call_ty.id, &mut data)?;
+
+		if !data.is_empty() {
+			return Err(Error::TrailingBytes)
+		}
+
+		if let Some(mut data) = additional_signed {
+			for extension in &self.extrinsic.signed_extensions {
+				collector.visit(extension.additional_signed.id, &mut data)?;
+			}
+
+			if !data.is_empty() {
+				return Err(Error::TrailingBytes)
+			}
+		}
+
+		let mut node_indices = collector
+			.used
+			.iter()
+			.map(|(id, variant)| {
+				self.type_ids
+					.get(id)
+					.and_then(|type_id| self.leaf_indices.get(&(*type_id, *variant)))
+					.map(|i| merkle::leaf_node_index(self.leaves.len(), *i))
+					.ok_or(Error::UnknownType(*id))
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		merkle::sort_left_to_right(&mut node_indices);
+
+		let first_leaf = merkle::leaf_node_index(self.leaves.len(), 0);
+		Ok(Proof {
+			nodes: merkle::proof(&self.leaf_hashes, &node_indices),
+			leaves: node_indices.iter().map(|i| self.leaves[*i - first_leaf].clone()).collect(),
+			leaf_indices: node_indices.into_iter().map(|i| i as u32).collect(),
+			extrinsic_metadata: self.extrinsic_metadata.clone(),
+			extra_info: self.extra_info.clone(),
+		})
+	}
+}
+
+/// The proof that a set of leaves is part of the merkleized metadata.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+	/// The leaves required to decode the extrinsic, from left to right in the tree.
+	pub leaves: Vec<Type>,
+	/// The index of the node of each of the `leaves` in the tree.
+	pub leaf_indices: Vec<u32>,
+	/// The hashes of the nodes required to calculate the root, in the order a depth-first, left
+	/// to right traversal of the tree visits them.
+	pub nodes: Vec<Hash>,
+	pub extrinsic_metadata: ExtrinsicMetadata,
+	pub extra_info: ExtraInfo,
+}
+
+impl Proof {
+	/// Check the proof against the expected `metadata_hash`.
+	pub fn verify(&self, metadata_hash: &Hash) -> bool {
+		if self.leaves.len() != self.leaf_indices.len() {
+			return false
+		}
+
+		let known: Vec<_> = self
+			.leaf_indices
+			.iter()
+			.zip(&self.leaves)
+			.map(|(index, leaf)| (*index as usize, leaf.hash()))
+			.collect();
+
+		let Some(root) = merkle::root_from_proof(&known, &self.nodes) else { return false };
+
+		let digest =
+			MetadataDigest::new_v1(root, &self.extrinsic_metadata, self.extra_info.clone());
+		digest.hash() == *metadata_hash
+	}
+}
+
+fn leaf_key(leaf: &Type) -> LeafKey {
+	match &leaf.type_def {
+		TypeDef::Enumeration(variant) => (leaf.type_id.0, Some(variant.index.0)),
+		_ => (leaf.type_id.0, None),
+	}
+}
+
+/// Collect the ids of all types that are needed to decode a value of type `id`.
+///
+/// The inner types of compacts and bit sequences are not needed, as they are inlined.
+fn collect_accessible_types(
+	registry: &PortableRegistry,
+	id: u32,
+	accessible: &mut BTreeSet<u32>,
+) -> Result<(), Error> {
+	if !accessible.insert(id) {
+		return Ok(())
+	}
+
+	let ty = registry.resolve(id).ok_or(Error::UnknownType(id))?;
+	let inner: Vec<u32> = match &ty.type_def {
+		RegistryTypeDef::Composite(composite) => composite.fields.iter().map(|f| f.ty.id).collect(),
+		RegistryTypeDef::Variant(variant) =>
+			variant.variants.iter().flat_map(|v| v.fields.iter().map(|f| f.ty.id)).collect(),
+		RegistryTypeDef::Sequence(sequence) => vec![sequence.type_param.id],
+		RegistryTypeDef::Array(array) => vec![array.type_param.id],
+		RegistryTypeDef::Tuple(tuple) => tuple.fields.iter().map(|f| f.id).collect(),
+		RegistryTypeDef::Primitive(_) |
+		RegistryTypeDef::Compact(_) |
+		RegistryTypeDef::BitSequence(_) => Vec::new(),
+	};
+
+	inner
+		.into_iter()
+		.try_for_each(|id| collect_accessible_types(registry, id, accessible))
+}
+
+/// Whether the type has leaves, otherwise it is always inlined into a [`TypeRef`].
+fn has_leaves(ty: &scale_info::Type<PortableForm>) -> bool {
+	match &ty.type_def {
+		RegistryTypeDef::Primitive(_) | RegistryTypeDef::Compact(_) => false,
+		RegistryTypeDef::Variant(variant) => !variant.variants.is_empty(),
+		RegistryTypeDef::Composite(composite) => !composite.fields.is_empty(),
+		RegistryTypeDef::Tuple(tuple) => !tuple.fields.is_empty(),
+		_ => true,
+	}
+}
+
+fn primitive_ref(primitive: &TypeDefPrimitive) -> TypeRef {
+	match primitive {
+		TypeDefPrimitive::Bool => TypeRef::Bool,
+		TypeDefPrimitive::Char => TypeRef::Char,
+		TypeDefPrimitive::Str => TypeRef::Str,
+		TypeDefPrimitive::U8 => TypeRef::U8,
+		TypeDefPrimitive::U16 => TypeRef::U16,
+		TypeDefPrimitive::U32 => TypeRef::U32,
+		TypeDefPrimitive::U64 => TypeRef::U64,
+		TypeDefPrimitive::U128 => TypeRef::U128,
+		TypeDefPrimitive::U256 => TypeRef::U256,
+		TypeDefPrimitive::I8 => TypeRef::I8,
+		TypeDefPrimitive::I16 => TypeRef::I16,
+		TypeDefPrimitive::I32 => TypeRef::I32,
+		TypeDefPrimitive::I64 => TypeRef::I64,
+		TypeDefPrimitive::I128 => TypeRef::I128,
+		TypeDefPrimitive::I256 => TypeRef::I256,
+	}
+}
+
+/// Converts the types of the registry into the types of the type information.
+#[derive(Clone, Copy)]
+struct TypeContext<'a> {
+	registry: &'a PortableRegistry,
+	type_ids: &'a BTreeMap<u32, u32>,
+}
+
+impl<'a> TypeContext<'a> {
+	fn resolve(&self, id: u32) -> Result<&'a scale_info::Type<PortableForm>, Error> {
+		self.registry.resolve(id).ok_or(Error::UnknownType(id))
+	}
+
+	fn type_ref(&self, id: u32) -> Result<TypeRef, Error> {
+		let ty = self.resolve(id)?;
+
+		Ok(match &ty.type_def {
+			RegistryTypeDef::Primitive(primitive) => primitive_ref(primitive),
+			RegistryTypeDef::Compact(compact) =>
+				match decode::compact_inner(self.registry, compact.type_param.id)? {
+					Some(TypeDefPrimitive::U8) => TypeRef::CompactU8,
+					Some(TypeDefPrimitive::U16) => TypeRef::CompactU16,
+					Some(TypeDefPrimitive::U32) => TypeRef::CompactU32,
+					Some(TypeDefPrimitive::U64) => TypeRef::CompactU64,
+					Some(TypeDefPrimitive::U128) => TypeRef::CompactU128,
+					Some(TypeDefPrimitive::U256) => TypeRef::CompactU256,
+					None => TypeRef::Void,
+					Some(_) => return Err(Error::UnsupportedCompact(id)),
+				},
+			_ if !has_leaves(ty) => TypeRef::Void,
+			_ => TypeRef::ById(Compact(*self.type_ids.get(&id).ok_or(Error::UnknownType(id))?)),
+		})
+	}
+
+	fn fields(&self, fields: &[scale_info::Field<PortableForm>]) -> Result<Vec<Field>, Error> {
+		fields
+			.iter()
+			.map(|f| {
+				Ok(Field {
+					name: f.name.clone(),
+					ty: self.type_ref(f.ty.id)?,
+					type_name: f.type_name.clone(),
+				})
+			})
+			.collect()
+	}
+
+	/// The leaves of the type `id`, which has the id `type_id` in the type information.
+	fn leaves_of(&self, id: u32, type_id: u32) -> Result<Vec<Type>, Error> {
+		let ty = self.resolve(id)?;
+		let leaf =
+			|type_def| Type { path: ty.path.segments.clone(), type_def, type_id: Compact(type_id) };
+
+		Ok(match &ty.type_def {
+			RegistryTypeDef::Primitive(_) | RegistryTypeDef::Compact(_) => Vec::new(),
+			RegistryTypeDef::Variant(variant) => {
+				let mut variants = variant.variants.iter().collect::<Vec<_>>();
+				variants.sort_by_key(|v| v.index);
+
+				variants
+					.into_iter()
+					.map(|v| {
+						Ok(leaf(TypeDef::Enumeration(EnumerationVariant {
+							name: v.name.clone(),
+							fields: self.fields(&v.fields)?,
+							index: Compact(v.index.into()),
+						})))
+					})
+					.collect::<Result<_, Error>>()?
+			},
+			RegistryTypeDef::Composite(composite) =>
+				vec![leaf(TypeDef::Composite(self.fields(&composite.fields)?))],
+			RegistryTypeDef::Sequence(sequence) =>
+				vec![leaf(TypeDef::Sequence(self.type_ref(sequence.type_param.id)?))],
+			RegistryTypeDef::Array(array) => vec![leaf(TypeDef::Array {
+				len: array.len,
+				type_param: self.type_ref(array.type_param.id)?,
+			})],
+			RegistryTypeDef::Tuple(tuple) => vec![leaf(TypeDef::Tuple(
+				tuple.fields.iter().map(|f| self.type_ref(f.id)).collect::<Result<_, _>>()?,
+			))],
+			RegistryTypeDef::BitSequence(bit_sequence) => {
+				let order_id = bit_sequence.bit_order_type.id;
+				let order = self.resolve(order_id)?.path.segments.last().map(String::as_str);
+				let least_significant_bit_first = match order {
+					Some("Lsb0") => true,
+					Some("Msb0") => false,
+					_ => return Err(Error::UnsupportedBitOrder(order_id)),
+				};
+
+				vec![leaf(TypeDef::BitSequence {
+					num_bytes: decode::bit_store_size(
+						self.registry,
+						bit_sequence.bit_store_type.id,
+					)? as u8,
+					least_significant_bit_first,
+				})]
+			},
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use scale_info::{meta_type, TypeInfo};
+	use sp_metadata_ir::{ExtrinsicMetadataIR, OuterEnumsIR, SignedExtensionMetadataIR};
+
+	#[derive(Encode, TypeInfo)]
+	enum Address {
+		Id([u8; 32]),
+		Index(#[codec(compact)] u32),
+	}
+
+	#[derive(Encode, TypeInfo)]
+	enum Call {
+		Transfer {
+			to: Address,
+			#[codec(compact)]
+			amount: u128,
+		},
+		Remark(Vec<u8>),
+		Batch(Vec<Call>),
+	}
+
+	#[derive(Encode, TypeInfo)]
+	struct CheckNonce(#[codec(compact)] u32);
+
+	fn metadata() -> MerkleizedMetadata {
+		let ir = MetadataIR {
+			pallets: vec![],
+			extrinsic: ExtrinsicMetadataIR {
+				ty: meta_type::<()>(),
+				version: 4,
+				address_ty: meta_type::<Address>(),
+				call_ty: meta_type::<Call>(),
+				signature_ty: meta_type::<[u8; 64]>(),
+				extra_ty: meta_type::<(CheckNonce,)>(),
+				signed_extensions: vec![SignedExtensionMetadataIR {
+					identifier: "CheckNonce",
+					ty: meta_type::<CheckNonce>(),
+					additional_signed: meta_type::<[u8; 32]>(),
+				}],
+			},
+			ty: meta_type::<Option<u64>>(),
+			apis: vec![],
+			outer_enums: OuterEnumsIR {
+				call_enum_ty: meta_type::<Call>(),
+				event_enum_ty: meta_type::<()>(),
+				error_enum_ty: meta_type::<()>(),
+			},
+		};
+
+		let extra_info = ExtraInfo {
+			spec_version: 1,
+			spec_name: "test".into(),
+			base58_prefix: 42,
+			decimals: 12,
+			token_symbol: "UNIT".into(),
+		};
+
+		MerkleizedMetadata::from_metadata_ir(ir, extra_info).unwrap()
+	}
+
+	fn signed_extrinsic(call: Call) -> Vec<u8> {
+		let mut body = vec![0b1000_0100];
+		Address::Index(7).encode_to(&mut body);
+		[1u8; 64].encode_to(&mut body);
+		CheckNonce(3).encode_to(&mut body);
+		call.encode_to(&mut body);
+		body.encode()
+	}
+
+	fn proven_leaves(proof: &Proof) -> Vec<(Vec<String>, Option<String>)> {
+		proof
+			.leaves
+			.iter()
+			.map(|leaf| {
+				let variant = match &leaf.type_def {
+					TypeDef::Enumeration(variant) => Some(variant.name.clone()),
+					_ => None,
+				};
+				(leaf.path.clone(), variant)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn proof_contains_only_required_leaves() {
+		let metadata = metadata();
+		let extrinsic = signed_extrinsic(Call::Remark(vec![1, 2, 3]));
+
+		let proof = metadata.generate_proof(&extrinsic, Some(&[0u8; 32][..])).unwrap();
+		let leaves = proven_leaves(&proof);
+
+		let path =
+			|name: &str| vec!["frame_merkleized_metadata".into(), "tests".into(), name.into()];
+		assert!(leaves.contains(&(path("Address"), Some("Index".into()))));
+		assert!(leaves.contains(&(path("Call"), Some("Remark".into()))));
+		assert!(leaves.contains(&(path("CheckNonce"), None)));
+		assert!(!leaves.contains(&(path("Address"), Some("Id".into()))));
+		assert!(!leaves.contains(&(path("Call"), Some("Transfer".into()))));
+		assert!(!leaves.iter().any(|(p, _)| *p == vec!["Option".to_string()]));
+		assert!(proof.leaves.len() < metadata.leaves().len());
+
+		assert!(proof.verify(&metadata.hash()));
+	}
+
+	#[test]
+	fn nested_calls_are_followed() {
+		let metadata = metadata();
+		let call = Call::Batch(vec![Call::Transfer { to: Address::Id([2; 32]), amount: 10 }]);
+		let extrinsic = signed_extrinsic(call);
+
+		let proof = metadata.generate_proof(&extrinsic, None).unwrap();
+		let variants: Vec<_> = proven_leaves(&proof).into_iter().filter_map(|l| l.1).collect();
+
+		assert!(variants.contains(&"Batch".to_string()));
+		assert!(variants.contains(&"Transfer".to_string()));
+		assert!(variants.contains(&"Id".to_string()));
+		assert!(variants.contains(&"Index".to_string()));
+		assert!(proof.verify(&metadata.hash()));
+	}
+
+	#[test]
+	fn unsigned_extrinsic_works() {
+		let metadata = metadata();
+		let mut body = vec![0b0000_0100];
+		Call::Remark(vec![]).encode_to(&mut body);
+
+		let proof = metadata.generate_proof(&body.encode(), None).unwrap();
+		assert!(proof.verify(&metadata.hash()));
+	}
+
+	#[test]
+	fn invalid_extrinsics_are_rejected() {
+		let metadata = metadata();
+
+		let mut extrinsic = signed_extrinsic(Call::Remark(vec![]));
+		extrinsic.push(0);
+		assert!(matches!(metadata.generate_proof(&extrinsic, None), Err(Error::TrailingBytes)));
+
+		let mut body = vec![0b0000_0011];
+		Call::Remark(vec![]).encode_to(&mut body);
+		assert!(matches!(
+			metadata.generate_proof(&body.encode(), None),
+			Err(Error::UnsupportedExtrinsicVersion(3))
+		));
+
+		let body = vec![0b0000_0100u8, 9];
+		assert!(matches!(
+			metadata.generate_proof(&body.encode(), None),
+			Err(Error::UnknownVariant { index: 9, .. })
+		));
+	}
+
+	#[test]
+	fn tampered_proofs_are_rejected() {
+		let metadata = metadata();
+		let extrinsic = signed_extrinsic(Call::Remark(vec![1]));
+		let proof = metadata.generate_proof(&extrinsic, None).unwrap();
+		let hash = metadata.hash();
+
+		let mut tampered = proof.clone();
+		tampered.extra_info.decimals = 10;
+		assert!(!tampered.verify(&hash));
+
+		let mut tampered = proof.clone();
+		if let TypeDef::Enumeration(variant) = &mut tampered.leaves[0].type_def {
+			variant.name = "Other".into();
+		} else {
+			tampered.leaves[0].path.push("Other".into());
+		}
+		assert!(!tampered.verify(&hash));
+
+		let mut tampered = proof.clone();
+		tampered.leaf_indices[0] += 1;
+		assert!(!tampered.verify(&hash));
+
+		let mut tampered = proof;
+		tampered.nodes.pop();
+		assert!(!tampered.verify(&hash));
+	}
+
+	#[test]
+	fn hash_matches_the_rfc_test_vector() {
+		let fixture = include_str!("../fixtures/acala_metadata_v15");
+		let metadata = Option::<Vec<u8>>::decode(
+			&mut &array_bytes::hex2bytes(fixture.trim_end()).unwrap()[..],
+		)
+		.unwrap()
+		.unwrap();
+
+		let extra_info = ExtraInfo {
+			spec_version: 1,
+			spec_name: "nice".into(),
+			base58_prefix: 1,
+			decimals: 1,
+			token_symbol: "lol".into(),
+		};
+		let metadata =
+			MerkleizedMetadata::new(decode_metadata(&metadata).unwrap(), extra_info).unwrap();
+
+		assert_eq!(
+			array_bytes::bytes2hex("0x", metadata.hash()),
+			"0xbd64dee496517c5288c47014fe0f57c2e12e42a7d627caeafa95e9f992e7e774",
+		);
+	}
+}
@@ -0,0 +1,199 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The merkle tree over the type information leaves, as specified by RFC-0078.
+//!
+//! The tree is a complete binary tree stored as an array of `2n - 1` nodes. Node `i` has the
+//! children `2i + 1` and `2i + 2`, and the `n` leaves are the last `n` nodes, in order. A node is
+//! the hash of its left child followed by its right child.
+//!
+//! A proof consists of the proven leaves with their node index, ordered from left to right in
+//! the tree, and the hashes of the nodes that can not be calculated from them, in the order a
+//! depth-first, left to right traversal from the root visits them.
+
+use crate::types::{blake3_256, Hash};
+
+fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+	let mut data = [0u8; 64];
+	data[..32].copy_from_slice(left);
+	data[32..].copy_from_slice(right);
+	blake3_256(&data)
+}
+
+/// Returns whether node `descendant` is part of the subtree below node `node`.
+fn is_descendant(node: usize, mut descendant: usize) -> bool {
+	while descendant > node {
+		descendant = (descendant - 1) / 2;
+	}
+	descendant == node
+}
+
+/// All the nodes of the tree over `leaves`.
+fn nodes(leaves: &[Hash]) -> Vec<Hash> {
+	if leaves.is_empty() {
+		return Vec::new()
+	}
+
+	let inner = leaves.len() - 1;
+	let mut nodes = vec![Hash::default(); inner];
+	nodes.extend_from_slice(leaves);
+	for i in (0..inner).rev() {
+		nodes[i] = hash_pair(&nodes[2 * i + 1], &nodes[2 * i + 2]);
+	}
+	nodes
+}
+
+/// Calculate the root of the tree over `leaves`.
+pub(crate) fn root(leaves: &[Hash]) -> Hash {
+	nodes(leaves).first().copied().unwrap_or_default()
+}
+
+/// The node index of the leaf at `index`, in a tree with `leaf_count` leaves.
+pub(crate) fn leaf_node_index(leaf_count: usize, index: usize) -> usize {
+	leaf_count - 1 + index
+}
+
+/// Sort node indices from left to right in the tree.
+///
+/// The leaves on the lowest level are the left most ones.
+pub(crate) fn sort_left_to_right(node_indices: &mut [usize]) {
+	node_indices.sort_by_key(|i| (usize::MAX - (i + 1).ilog2() as usize, *i));
+}
+
+/// Collect the hashes of the nodes required to calculate the root from the leaves at the given
+/// `node_indices`, which must be sorted from left to right.
+pub(crate) fn proof(leaves: &[Hash], node_indices: &[usize]) -> Vec<Hash> {
+	fn collect(nodes: &[Hash], node: usize, node_indices: &[usize], proof: &mut Vec<Hash>) {
+		if node_indices.contains(&node) {
+			return
+		}
+
+		if !node_indices.iter().any(|i| is_descendant(node, *i)) {
+			proof.push(nodes[node]);
+			return
+		}
+
+		collect(nodes, 2 * node + 1, node_indices, proof);
+		collect(nodes, 2 * node + 2, node_indices, proof);
+	}
+
+	let nodes = nodes(leaves);
+	let mut proof = Vec::new();
+	if !nodes.is_empty() {
+		collect(&nodes, 0, node_indices, &mut proof);
+	}
+	proof
+}
+
+/// Calculate the root from the known `leaves`, given as node index and hash from left to right
+/// in the tree, and the proof `nodes`.
+///
+/// Returns `None` if the proof doesn't fit the leaves.
+pub(crate) fn root_from_proof(mut leaves: &[(usize, Hash)], mut nodes: &[Hash]) -> Option<Hash> {
+	fn hash_of(node: usize, leaves: &mut &[(usize, Hash)], nodes: &mut &[Hash]) -> Option<Hash> {
+		if let Some(((index, hash), rest)) = leaves.split_first() {
+			if *index == node {
+				*leaves = rest;
+				return Some(*hash)
+			}
+
+			if is_descendant(node, *index) {
+				let left = hash_of(2 * node + 1, leaves, nodes)?;
+				let right = hash_of(2 * node + 2, leaves, nodes)?;
+				return Some(hash_pair(&left, &right))
+			}
+		}
+
+		let (hash, rest) = nodes.split_first()?;
+		*nodes = rest;
+		Some(*hash)
+	}
+
+	let root = hash_of(0, &mut leaves, &mut nodes)?;
+	(leaves.is_empty() && nodes.is_empty()).then_some(root)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn leaves(count: u8) -> Vec<Hash> {
+		(0..count).map(|i| blake3_256(&[i])).collect()
+	}
+
+	fn known_leaves(leaves: &[Hash], indices: &[usize]) -> (Vec<usize>, Vec<(usize, Hash)>) {
+		let mut node_indices: Vec<_> =
+			indices.iter().map(|i| leaf_node_index(leaves.len(), *i)).collect();
+		sort_left_to_right(&mut node_indices);
+
+		let known = node_indices
+			.iter()
+			.map(|node| (*node, leaves[*node + 1 - leaves.len()]))
+			.collect();
+		(node_indices, known)
+	}
+
+	#[test]
+	fn leaves_on_the_lowest_level_are_left_most() {
+		let leaves = leaves(3);
+
+		// Leaf 0 is a child of the root, leaves 1 and 2 are one level below.
+		assert_eq!(root(&leaves), hash_pair(&hash_pair(&leaves[1], &leaves[2]), &leaves[0]),);
+		assert_eq!(root(&leaves[..1]), leaves[0]);
+		assert_eq!(root(&[]), Hash::default());
+	}
+
+	#[test]
+	fn proofs_reproduce_the_root() {
+		for count in 1..=9 {
+			let leaves = leaves(count);
+			let root = root(&leaves);
+
+			// Every single leaf, the first and the last leaf and all leaves.
+			let mut sets: Vec<Vec<usize>> = (0..count as usize).map(|i| vec![i]).collect();
+			if count > 1 {
+				sets.push(vec![0, count as usize - 1]);
+			}
+			sets.push((0..count as usize).collect());
+
+			for indices in sets {
+				let (node_indices, known) = known_leaves(&leaves, &indices);
+				let nodes = proof(&leaves, &node_indices);
+
+				assert_eq!(
+					root_from_proof(&known, &nodes),
+					Some(root),
+					"{count} leaves, indices {indices:?}",
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn proof_with_wrong_leaf_is_rejected() {
+		let leaves = leaves(5);
+		let (node_indices, mut known) = known_leaves(&leaves, &[1, 3]);
+		let nodes = proof(&leaves, &node_indices);
+		assert_eq!(root_from_proof(&known, &nodes), Some(root(&leaves)));
+
+		assert_eq!(root_from_proof(&known, &nodes[1..]), None);
+		assert_ne!(root_from_proof(&known[..1], &nodes), Some(root(&leaves)));
+
+		known[1].1 = leaves[4];
+		assert_ne!(root_from_proof(&known, &nodes), Some(root(&leaves)));
+	}
+}
@@ -0,0 +1,189 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The types that make up the merkleized metadata, as specified by RFC-0078.
+//!
+//! Every type that is required to decode an extrinsic and can not be inlined into a [`TypeRef`]
+//! becomes one leaf of the type information tree, enums become one leaf per variant.
+
+use codec::{Compact, Decode, Encode};
+
+/// A blake3 hash.
+pub type Hash = [u8; 32];
+
+pub(crate) fn blake3_256(data: &[u8]) -> Hash {
+	blake3::hash(data).into()
+}
+
+/// A reference to a type.
+///
+/// Primitives and compact encoded primitives are referenced directly, types without any data
+/// like empty structs as [`TypeRef::Void`] and everything else by its type id.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum TypeRef {
+	Bool,
+	Char,
+	Str,
+	U8,
+	U16,
+	U32,
+	U64,
+	U128,
+	U256,
+	I8,
+	I16,
+	I32,
+	I64,
+	I128,
+	I256,
+	CompactU8,
+	CompactU16,
+	CompactU32,
+	CompactU64,
+	CompactU128,
+	CompactU256,
+	/// A type without any encoded data, like an empty struct or an enum without variants.
+	Void,
+	/// A reference to the type with the given id.
+	ById(Compact<u32>),
+}
+
+/// A field of a composite or of an enum variant.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+	pub name: Option<String>,
+	pub ty: TypeRef,
+	pub type_name: Option<String>,
+}
+
+/// One variant of an enum.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct EnumerationVariant {
+	pub name: String,
+	pub fields: Vec<Field>,
+	pub index: Compact<u32>,
+}
+
+/// The definition of a type.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum TypeDef {
+	Composite(Vec<Field>),
+	Enumeration(EnumerationVariant),
+	Sequence(TypeRef),
+	Array { len: u32, type_param: TypeRef },
+	Tuple(Vec<TypeRef>),
+	BitSequence { num_bytes: u8, least_significant_bit_first: bool },
+}
+
+/// A leaf of the type information tree.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Type {
+	/// The path of the type, e.g. `["sp_runtime", "MultiAddress"]`.
+	pub path: Vec<String>,
+	pub type_def: TypeDef,
+	/// The id of the type in the type information, not in the type registry of the metadata.
+	pub type_id: Compact<u32>,
+}
+
+impl Type {
+	/// The hash of this leaf in the type information tree.
+	pub fn hash(&self) -> Hash {
+		blake3_256(&self.encode())
+	}
+}
+
+/// Metadata of a signed extension.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SignedExtensionMetadata {
+	pub identifier: String,
+	/// The type of the data that is part of the extrinsic.
+	pub included_in_extrinsic: TypeRef,
+	/// The type of the data that is only part of the signed payload.
+	pub included_in_signed_data: TypeRef,
+}
+
+/// The information required to decode an extrinsic.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ExtrinsicMetadata {
+	pub version: u8,
+	pub address_ty: TypeRef,
+	pub call_ty: TypeRef,
+	pub signature_ty: TypeRef,
+	pub signed_extensions: Vec<SignedExtensionMetadata>,
+}
+
+impl ExtrinsicMetadata {
+	/// The hash of the extrinsic metadata as part of the [`MetadataDigest`].
+	pub fn hash(&self) -> Hash {
+		blake3_256(&self.encode())
+	}
+}
+
+/// Chain specific information that is not part of the metadata, but required to display an
+/// extrinsic to the user.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ExtraInfo {
+	pub spec_version: u32,
+	pub spec_name: String,
+	pub base58_prefix: u16,
+	pub decimals: u8,
+	pub token_symbol: String,
+}
+
+/// The digest of the merkleized metadata.
+///
+/// The hash of the encoded digest is the metadata hash checked by the `CheckMetadataHash`
+/// signed extension.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum MetadataDigest {
+	#[codec(index = 0)]
+	Disabled,
+	#[codec(index = 1)]
+	V1 {
+		types_tree_root: Hash,
+		extrinsic_metadata_hash: Hash,
+		spec_version: u32,
+		spec_name: String,
+		base58_prefix: u16,
+		decimals: u8,
+		token_symbol: String,
+	},
+}
+
+impl MetadataDigest {
+	/// Create the `V1` digest.
+	pub fn new_v1(
+		types_tree_root: Hash,
+		extrinsic_metadata: &ExtrinsicMetadata,
+		extra_info: ExtraInfo,
+	) -> Self {
+		Self::V1 {
+			types_tree_root,
+			extrinsic_metadata_hash: extrinsic_metadata.hash(),
+			spec_version: extra_info.spec_version,
+			spec_name: extra_info.spec_name,
+			base58_prefix: extra_info.base58_prefix,
+			decimals: extra_info.decimals,
+			token_symbol: extra_info.token_symbol,
+		}
+	}
+
+	/// The metadata hash.
+	pub fn hash(&self) -> Hash {
+		blake3_256(&self.encode())
+	}
+}
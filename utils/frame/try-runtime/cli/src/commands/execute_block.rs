@@ -16,17 +16,17 @@
 // limitations under the License.
 
 use crate::{
-	build_executor, full_extensions, rpc_err_handler, state_machine_call_with_proof, LiveState,
-	SharedParams, State, LOG_TARGET,
+	build_executor_with_profiler, full_extensions, rpc_err_handler, state_machine_call_with_proof,
+	LiveState, SharedParams, State, LOG_TARGET,
 };
 use parity_scale_codec::Encode;
-use sc_executor::sp_wasm_interface::HostFunctions;
+use sc_executor::{sp_wasm_interface::HostFunctions, WasmtimeProfiler};
 use sp_rpc::{list::ListOrValue, number::NumberOrHex};
 use sp_runtime::{
 	generic::SignedBlock,
 	traits::{Block as BlockT, Header as HeaderT, NumberFor},
 };
-use std::{fmt::Debug, str::FromStr};
+use std::{fmt::Debug, path::PathBuf, str::FromStr, sync::Arc};
 use substrate_rpc_client::{ws_client, ChainApi};
 
 /// Configurations of the [`crate::Command::ExecuteBlock`].
@@ -60,6 +60,14 @@ pub struct ExecuteBlockCmd {
 	)]
 	pub block_ws_uri: Option<String>,
 
+	/// Profile the execution of the block and write the time spent in each function of the
+	/// runtime to this file.
+	///
+	/// The profile is written in the folded stack format, which can be rendered as a flamegraph
+	/// with `inferno-flamegraph` or `flamegraph.pl`.
+	#[arg(long, value_name = "PATH")]
+	pub profile: Option<PathBuf>,
+
 	/// The state type to use.
 	#[command(subcommand)]
 	pub state: State,
@@ -96,7 +104,8 @@ where
 	<NumberFor<Block> as TryInto<u64>>::Error: Debug,
 	HostFns: HostFunctions,
 {
-	let executor = build_executor::<HostFns>(&shared);
+	let profiler = command.profile.as_ref().map(|_| Arc::new(WasmtimeProfiler::new()));
+	let executor = build_executor_with_profiler::<HostFns>(&shared, profiler.clone());
 	let ext = command.state.into_ext::<Block, HostFns>(&shared, &executor, None, true).await?;
 
 	// get the block number associated with this block.
@@ -126,6 +135,11 @@ where
 	let signature_check = false;
 	let payload = (block.clone(), state_root_check, signature_check, command.try_state).encode();
 
+	// Only the execution of the block should show up in the profile.
+	if let Some(profiler) = &profiler {
+		let _ = profiler.take_folded();
+	}
+
 	let _ = state_machine_call_with_proof::<Block, HostFns>(
 		&ext,
 		&executor,
@@ -135,6 +149,11 @@ where
 		shared.export_proof,
 	)?;
 
+	if let (Some(profiler), Some(path)) = (profiler, command.profile) {
+		std::fs::write(&path, profiler.take_folded())?;
+		log::info!(target: LOG_TARGET, "wrote the runtime profile to {:?}", path);
+	}
+
 	Ok(())
 }
 
@@ -35,7 +35,8 @@ use sc_cli::{
 	DEFAULT_WASM_EXECUTION_METHOD,
 };
 use sc_executor::{
-	sp_wasm_interface::HostFunctions, HeapAllocStrategy, WasmExecutor, DEFAULT_HEAP_ALLOC_STRATEGY,
+	sp_wasm_interface::HostFunctions, HeapAllocStrategy, WasmExecutor, WasmtimeProfiler,
+	DEFAULT_HEAP_ALLOC_STRATEGY,
 };
 use sp_core::{
 	hexdisplay::HexDisplay,
@@ -58,7 +59,7 @@ use sp_state_machine::{
 	CompactProof, OverlayedChanges, StateMachine, TestExternalities, TrieBackendBuilder,
 };
 use sp_version::StateVersion;
-use std::{fmt::Debug, path::PathBuf, str::FromStr};
+use std::{fmt::Debug, path::PathBuf, str::FromStr, sync::Arc};
 
 pub mod block_building_info;
 pub mod commands;
@@ -497,18 +498,29 @@ pub(crate) fn full_extensions<H: HostFunctions>(wasm_executor: WasmExecutor<H>)
 
 /// Build wasm executor by default config.
 pub(crate) fn build_executor<H: HostFunctions>(shared: &SharedParams) -> WasmExecutor<H> {
+	build_executor_with_profiler(shared, None)
+}
+
+/// Build the executor, instrumenting the runtimes for the given `profiler`.
+pub(crate) fn build_executor_with_profiler<H: HostFunctions>(
+	shared: &SharedParams,
+	profiler: Option<Arc<WasmtimeProfiler>>,
+) -> WasmExecutor<H> {
 	let heap_pages = shared
 		.heap_pages
 		.map_or(DEFAULT_HEAP_ALLOC_STRATEGY, |p| HeapAllocStrategy::Static { extra_pages: p as _ });
 
-	WasmExecutor::builder()
+	let mut builder = WasmExecutor::builder()
 		.with_execution_method(execution_method_from_cli(
 			shared.wasm_method,
 			shared.wasmtime_instantiation_strategy,
 		))
 		.with_onchain_heap_alloc_strategy(heap_pages)
-		.with_offchain_heap_alloc_strategy(heap_pages)
-		.build()
+		.with_offchain_heap_alloc_strategy(heap_pages);
+	if let Some(profiler) = profiler {
+		builder = builder.with_profiler(profiler);
+	}
+	builder.build()
 }
 
 /// Ensure that the given `ext` is compiled with `try-runtime`
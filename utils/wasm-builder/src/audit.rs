@@ -0,0 +1,392 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Audit of a built runtime blob.
+//!
+//! [`RuntimeAudit`] reports the size of a runtime per crate and per function, together with the
+//! host functions it imports. [`RuntimeDiff`] compares two runtimes and flags the host functions
+//! that are newly required by the second one, which nodes built for the first one may not provide.
+
+use parity_wasm::elements::{deserialize_buffer, External, Module};
+use sp_maybe_compressed_blob::CODE_BLOB_BOMB_LIMIT;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fmt,
+};
+
+/// The crate functions are attributed to if their name can't be attributed to one.
+const UNKNOWN_CRATE: &str = "[unknown]";
+
+/// A host function imported by a runtime, e.g. `ext_storage_get_version_1`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HostFunction {
+	/// The name of the function without the version, e.g. `ext_storage_get`.
+	pub name: String,
+	/// The version of the function, if the import has one.
+	pub version: Option<u32>,
+}
+
+impl HostFunction {
+	/// Parses the name of an imported host function.
+	pub fn parse(import: &str) -> Self {
+		match import
+			.rsplit_once("_version_")
+			.map(|(name, version)| (name, version.parse::<u32>()))
+		{
+			Some((name, Ok(version))) => Self { name: name.into(), version: Some(version) },
+			_ => Self { name: import.into(), version: None },
+		}
+	}
+}
+
+impl fmt::Display for HostFunction {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.version {
+			Some(version) => write!(f, "{}_version_{}", self.name, version),
+			None => write!(f, "{}", self.name),
+		}
+	}
+}
+
+/// The size of a function of a runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSize {
+	/// The demangled name of the function.
+	pub name: String,
+	/// The crate the function is attributed to.
+	pub krate: String,
+	/// The size of the code of the function in bytes.
+	pub size: usize,
+}
+
+/// The contents of a runtime blob.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeAudit {
+	/// The size of the blob as given, which may be compressed.
+	pub blob_size: usize,
+	/// The size of the uncompressed Wasm binary.
+	pub wasm_size: usize,
+	/// The size of the code of all functions.
+	pub code_size: usize,
+	/// The size of the data segments.
+	pub data_size: usize,
+	/// The functions of the runtime, the largest first.
+	pub functions: Vec<FunctionSize>,
+	/// The host functions imported by the runtime.
+	pub host_functions: BTreeSet<HostFunction>,
+}
+
+impl RuntimeAudit {
+	/// Audits the given runtime blob, which may be compressed.
+	pub fn from_blob(blob: &[u8]) -> Result<Self, String> {
+		let wasm = sp_maybe_compressed_blob::decompress(blob, CODE_BLOB_BOMB_LIMIT)
+			.map_err(|e| format!("Failed to decompress the runtime: {}", e))?;
+		let module = deserialize_buffer::<Module>(&wasm)
+			.map_err(|e| format!("Failed to deserialize the runtime: {}", e))?;
+
+		let imported_functions = module
+			.import_section()
+			.map(|section| {
+				section
+					.entries()
+					.iter()
+					.filter(|entry| matches!(entry.external(), External::Function(_)))
+					.map(|entry| entry.field().to_owned())
+					.collect::<Vec<_>>()
+			})
+			.unwrap_or_default();
+		let host_functions =
+			imported_functions.iter().map(|name| HostFunction::parse(name)).collect();
+
+		let module = match module.parse_names() {
+			Ok(module) => module,
+			Err((_, module)) => module,
+		};
+		let names = module.names_section().and_then(|section| section.functions());
+
+		let mut functions = Vec::new();
+		for (index, body) in
+			module.code_section().map_or(&[][..], |s| s.bodies()).iter().enumerate()
+		{
+			let index = (imported_functions.len() + index) as u32;
+			let size = parity_wasm::serialize(body.clone())
+				.map_err(|e| format!("Failed to serialize function {}: {}", index, e))?
+				.len();
+			let name = match names.and_then(|names| names.names().get(index)) {
+				Some(name) => format!("{:#}", rustc_demangle::demangle(name)),
+				None => format!("wasm-function[{}]", index),
+			};
+			functions.push(FunctionSize { krate: crate_of(&name).into(), name, size });
+		}
+		functions.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+
+		let data_size = module
+			.data_section()
+			.map_or(0, |section| section.entries().iter().map(|s| s.value().len()).sum());
+
+		Ok(Self {
+			blob_size: blob.len(),
+			wasm_size: wasm.len(),
+			code_size: functions.iter().map(|f| f.size).sum(),
+			data_size,
+			functions,
+			host_functions,
+		})
+	}
+
+	/// Returns the size of the code of each crate, the largest first.
+	pub fn crate_sizes(&self) -> Vec<(String, usize)> {
+		let mut sizes = BTreeMap::<&str, usize>::new();
+		for function in &self.functions {
+			*sizes.entry(function.krate.as_str()).or_default() += function.size;
+		}
+
+		let mut sizes = sizes
+			.into_iter()
+			.map(|(krate, size)| (krate.to_owned(), size))
+			.collect::<Vec<_>>();
+		sizes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+		sizes
+	}
+
+	/// Compares this runtime to the `new` one.
+	pub fn diff(&self, new: &RuntimeAudit) -> RuntimeDiff {
+		let old_crates = self.crate_sizes().into_iter().collect::<BTreeMap<_, _>>();
+		let new_crates = new.crate_sizes().into_iter().collect::<BTreeMap<_, _>>();
+		let mut crate_sizes = old_crates
+			.keys()
+			.chain(new_crates.keys())
+			.collect::<BTreeSet<_>>()
+			.into_iter()
+			.map(|krate| {
+				let old = old_crates.get(krate).copied().unwrap_or_default();
+				let new = new_crates.get(krate).copied().unwrap_or_default();
+				(krate.clone(), old, new)
+			})
+			.filter(|(_, old, new)| old != new)
+			.collect::<Vec<_>>();
+		crate_sizes.sort_by_key(|(_, old, new)| std::cmp::Reverse(old.abs_diff(*new)));
+
+		RuntimeDiff {
+			old_blob_size: self.blob_size,
+			new_blob_size: new.blob_size,
+			old_wasm_size: self.wasm_size,
+			new_wasm_size: new.wasm_size,
+			crate_sizes,
+			added_host_functions: new
+				.host_functions
+				.difference(&self.host_functions)
+				.cloned()
+				.collect(),
+			removed_host_functions: self
+				.host_functions
+				.difference(&new.host_functions)
+				.cloned()
+				.collect(),
+		}
+	}
+
+	/// Writes the report, listing at most `max_functions` of the largest functions.
+	pub fn report(&self, max_functions: usize) -> String {
+		let mut report = String::new();
+		let _ = self.write_report(&mut report, max_functions);
+		report
+	}
+
+	fn write_report(&self, out: &mut impl fmt::Write, max_functions: usize) -> fmt::Result {
+		writeln!(out, "Blob size: {} bytes", self.blob_size)?;
+		writeln!(out, "Wasm size: {} bytes", self.wasm_size)?;
+		writeln!(out, "Code size: {} bytes", self.code_size)?;
+		writeln!(out, "Data size: {} bytes", self.data_size)?;
+
+		writeln!(out, "\nSize per crate:")?;
+		for (krate, size) in self.crate_sizes() {
+			writeln!(out, "{:>10} {}", size, krate)?;
+		}
+
+		writeln!(out, "\nLargest functions:")?;
+		for function in self.functions.iter().take(max_functions) {
+			writeln!(out, "{:>10} {}", function.size, function.name)?;
+		}
+
+		writeln!(out, "\nHost functions:")?;
+		for host_function in &self.host_functions {
+			writeln!(out, "  {}", host_function)?;
+		}
+		Ok(())
+	}
+}
+
+/// The differences between two runtimes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeDiff {
+	/// The size of the old blob.
+	pub old_blob_size: usize,
+	/// The size of the new blob.
+	pub new_blob_size: usize,
+	/// The size of the old uncompressed Wasm binary.
+	pub old_wasm_size: usize,
+	/// The size of the new uncompressed Wasm binary.
+	pub new_wasm_size: usize,
+	/// The crates whose code size changed with their old and new size, the largest change first.
+	pub crate_sizes: Vec<(String, usize, usize)>,
+	/// The host functions imported by the new runtime, but not by the old one.
+	///
+	/// Nodes that are only able to run the old runtime may not provide them.
+	pub added_host_functions: Vec<HostFunction>,
+	/// The host functions imported by the old runtime, but not by the new one.
+	pub removed_host_functions: Vec<HostFunction>,
+}
+
+impl RuntimeDiff {
+	/// Returns `true` if the new runtime requires host functions the old one did not.
+	pub fn requires_new_host_functions(&self) -> bool {
+		!self.added_host_functions.is_empty()
+	}
+}
+
+impl fmt::Display for RuntimeDiff {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "Blob size: {} -> {} bytes", self.old_blob_size, self.new_blob_size)?;
+		writeln!(f, "Wasm size: {} -> {} bytes", self.old_wasm_size, self.new_wasm_size)?;
+
+		if !self.crate_sizes.is_empty() {
+			writeln!(f, "\nSize changes per crate:")?;
+			for (krate, old, new) in &self.crate_sizes {
+				let sign = if new > old { '+' } else { '-' };
+				writeln!(f, "{}{:>9} {} ({} -> {})", sign, old.abs_diff(*new), krate, old, new)?;
+			}
+		}
+
+		if !self.added_host_functions.is_empty() {
+			writeln!(f, "\nNewly required host functions:")?;
+			for host_function in &self.added_host_functions {
+				writeln!(f, "  {}", host_function)?;
+			}
+		}
+
+		if !self.removed_host_functions.is_empty() {
+			writeln!(f, "\nNo longer required host functions:")?;
+			for host_function in &self.removed_host_functions {
+				writeln!(f, "  {}", host_function)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Returns the crate a function with the given demangled `name` belongs to.
+///
+/// Trait implementations are attributed to the crate of the type they are implemented for, or to
+/// the crate of the trait if the type is not a path, e.g. for primitive types.
+fn crate_of(name: &str) -> &str {
+	let Some(name) = name.strip_prefix('<') else { return leading_crate(name) };
+	let ty = name.trim_start_matches(|c| matches!(c, '&' | '*' | '[' | '(' | ' '));
+	let ty = ["mut ", "const ", "dyn "]
+		.iter()
+		.fold(ty, |ty, prefix| ty.strip_prefix(prefix).unwrap_or(ty));
+
+	match leading_crate(ty) {
+		UNKNOWN_CRATE => name
+			.split_once(" as ")
+			.map_or(UNKNOWN_CRATE, |(_, trait_path)| leading_crate(trait_path)),
+		krate => krate,
+	}
+}
+
+/// Returns the first segment of `path`, if it is followed by further segments.
+fn leading_crate(path: &str) -> &str {
+	let end = path.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(path.len());
+	if end > 0 && path[end..].starts_with("::") {
+		&path[..end]
+	} else {
+		UNKNOWN_CRATE
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn host_functions_are_parsed() {
+		let parsed = HostFunction::parse("ext_storage_get_version_1");
+		assert_eq!(parsed, HostFunction { name: "ext_storage_get".into(), version: Some(1) });
+		assert_eq!(parsed.to_string(), "ext_storage_get_version_1");
+
+		let parsed = HostFunction::parse("ext_custom_function");
+		assert_eq!(parsed, HostFunction { name: "ext_custom_function".into(), version: None });
+	}
+
+	#[test]
+	fn functions_are_attributed_to_crates() {
+		assert_eq!(crate_of("sp_io::storage::get"), "sp_io");
+		assert_eq!(crate_of("core::ptr::drop_in_place<alloc::vec::Vec<u8>>"), "core");
+		assert_eq!(crate_of("<sp_core::crypto::AccountId32 as core::fmt::Debug>::fmt"), "sp_core");
+		assert_eq!(
+			crate_of("<&mut frame_system::Call<T> as core::fmt::Debug>::fmt"),
+			"frame_system"
+		);
+		assert_eq!(
+			crate_of("<u32 as parity_scale_codec::codec::Encode>::encode"),
+			"parity_scale_codec"
+		);
+		assert_eq!(crate_of("memcpy"), UNKNOWN_CRATE);
+		assert_eq!(crate_of("wasm-function[12]"), UNKNOWN_CRATE);
+	}
+
+	#[test]
+	fn diff_flags_new_host_functions() {
+		let audit = |functions: &[(&str, usize)], host_functions: &[&str]| RuntimeAudit {
+			blob_size: 0,
+			wasm_size: 0,
+			code_size: functions.iter().map(|(_, size)| size).sum(),
+			data_size: 0,
+			functions: functions
+				.iter()
+				.map(|(name, size)| FunctionSize {
+					name: name.to_string(),
+					krate: crate_of(name).into(),
+					size: *size,
+				})
+				.collect(),
+			host_functions: host_functions.iter().map(|name| HostFunction::parse(name)).collect(),
+		};
+		let old = audit(
+			&[("sp_io::storage::get", 10), ("pallet_balances::transfer", 20)],
+			&["ext_storage_get_version_1", "ext_misc_print_num_version_1"],
+		);
+		let new = audit(
+			&[("sp_io::storage::get", 10), ("pallet_balances::transfer", 25)],
+			&["ext_storage_get_version_1", "ext_storage_get_version_2"],
+		);
+
+		let diff = old.diff(&new);
+		assert!(diff.requires_new_host_functions());
+		assert_eq!(
+			diff.added_host_functions,
+			vec![HostFunction::parse("ext_storage_get_version_2")]
+		);
+		assert_eq!(
+			diff.removed_host_functions,
+			vec![HostFunction::parse("ext_misc_print_num_version_1")]
+		);
+		assert_eq!(diff.crate_sizes, vec![("pallet_balances".into(), 20, 25)]);
+
+		assert!(!old.diff(&old).requires_new_host_functions());
+	}
+}
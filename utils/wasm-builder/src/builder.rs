@@ -15,6 +15,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::reproducible::ReproducibleBuild;
+
 use std::{
 	env,
 	path::{Path, PathBuf},
@@ -49,6 +51,9 @@ impl WasmBuilderSelectProject {
 			project_cargo_toml: get_manifest_dir().join("Cargo.toml"),
 			features_to_enable: Vec::new(),
 			disable_runtime_version_section_check: false,
+			reproducible_toolchain: None,
+			#[cfg(feature = "metadata-hash")]
+			enable_metadata_hash: None,
 		}
 	}
 
@@ -65,6 +70,9 @@ impl WasmBuilderSelectProject {
 				project_cargo_toml: path,
 				features_to_enable: Vec::new(),
 				disable_runtime_version_section_check: false,
+				reproducible_toolchain: None,
+				#[cfg(feature = "metadata-hash")]
+				enable_metadata_hash: None,
 			})
 		} else {
 			Err("Project path must point to the `Cargo.toml` of the project")
@@ -97,6 +105,11 @@ pub struct WasmBuilder {
 	features_to_enable: Vec<String>,
 	/// Should the builder not check that the `runtime_version` section exists in the wasm binary?
 	disable_runtime_version_section_check: bool,
+	/// The toolchain of a reproducible build.
+	reproducible_toolchain: Option<String>,
+	/// The native token, if the metadata hash should be generated.
+	#[cfg(feature = "metadata-hash")]
+	enable_metadata_hash: Option<crate::metadata_hash::TokenInfo>,
 }
 
 impl WasmBuilder {
@@ -158,6 +171,26 @@ impl WasmBuilder {
 		self
 	}
 
+	/// Build the wasm binary reproducibly with the given `toolchain`, e.g. `1.74.0`.
+	///
+	/// See the [crate documentation](crate#reproducible-builds) for what this entails. The
+	/// toolchain can be overridden with the `WASM_BUILD_REPRODUCIBLE` environment variable.
+	pub fn enable_reproducible_build(mut self, toolchain: impl Into<String>) -> Self {
+		self.reproducible_toolchain = Some(toolchain.into());
+		self
+	}
+
+	/// Generate the metadata hash and pass it to the build of the runtime.
+	///
+	/// `token_symbol` and `decimals` describe the native token of the chain and are part of the
+	/// hash. See the [crate documentation](crate#metadata-hash) for how the hash is generated.
+	#[cfg(feature = "metadata-hash")]
+	pub fn enable_metadata_hash(mut self, token_symbol: impl Into<String>, decimals: u8) -> Self {
+		self.enable_metadata_hash =
+			Some(crate::metadata_hash::TokenInfo { symbol: token_symbol.into(), decimals });
+		self
+	}
+
 	/// Build the WASM binary.
 	pub fn build(self) {
 		let out_dir = PathBuf::from(env::var("OUT_DIR").expect("`OUT_DIR` is set by cargo!"));
@@ -181,6 +214,9 @@ impl WasmBuilder {
 			self.features_to_enable,
 			self.file_name,
 			!self.disable_runtime_version_section_check,
+			ReproducibleBuild::requested(self.reproducible_toolchain),
+			#[cfg(feature = "metadata-hash")]
+			self.enable_metadata_hash,
 		);
 
 		// As last step we need to generate our `rerun-if-changed` stuff. If a build fails, we don't
@@ -226,6 +262,7 @@ fn generate_rerun_if_changed_instructions() {
 	// Make sure that the `build.rs` is called again if one of the following env variables changes.
 	println!("cargo:rerun-if-env-changed={}", crate::SKIP_BUILD_ENV);
 	println!("cargo:rerun-if-env-changed={}", crate::FORCE_WASM_BUILD_ENV);
+	println!("cargo:rerun-if-env-changed={}", crate::WASM_BUILD_REPRODUCIBLE_ENV);
 	println!("cargo:rerun-if-env-changed={}", generate_crate_skip_build_env_name());
 }
 
@@ -247,6 +284,10 @@ fn generate_rerun_if_changed_instructions() {
 ///
 /// `check_for_runtime_version_section` - Should the wasm binary be checked for the
 /// `runtime_version` section?
+///
+/// `reproducible` - The settings of a reproducible build, if one was requested.
+///
+/// `metadata_hash` - The native token, if the metadata hash should be generated.
 fn build_project(
 	file_name: PathBuf,
 	project_cargo_toml: PathBuf,
@@ -254,8 +295,12 @@ fn build_project(
 	features_to_enable: Vec<String>,
 	wasm_binary_name: Option<String>,
 	check_for_runtime_version_section: bool,
+	reproducible: Option<ReproducibleBuild>,
+	#[cfg(feature = "metadata-hash")] metadata_hash: Option<crate::metadata_hash::TokenInfo>,
 ) {
-	let cargo_cmd = match crate::prerequisites::check() {
+	let cargo_cmd = match crate::prerequisites::check(
+		reproducible.as_ref().map(ReproducibleBuild::toolchain),
+	) {
 		Ok(cmd) => cmd,
 		Err(err_msg) => {
 			eprintln!("{}", err_msg);
@@ -270,6 +315,9 @@ fn build_project(
 		features_to_enable,
 		wasm_binary_name,
 		check_for_runtime_version_section,
+		reproducible,
+		#[cfg(feature = "metadata-hash")]
+		metadata_hash,
 	);
 
 	let (wasm_binary, wasm_binary_bloaty) = if let Some(wasm_binary) = wasm_binary {
@@ -89,11 +89,58 @@
 //!   actual workspace.
 //! - `CARGO_NET_OFFLINE` - If `true`, `--offline` will be passed to all processes launched to
 //!   prevent network access. Useful in offline environments.
+//! - `WASM_BUILD_REPRODUCIBLE` - Build the Wasm binary reproducibly with the given toolchain, e.g.
+//!   `1.74.0`. See [reproducible builds](#reproducible-builds).
 //!
 //! Each project can be skipped individually by using the environment variable
 //! `SKIP_PROJECT_NAME_WASM_BUILD`. Where `PROJECT_NAME` needs to be replaced by the name of the
 //! cargo project, e.g. `kitchensink-runtime` will be `NODE_RUNTIME`.
 //!
+//! ## Reproducible builds
+//!
+//! By default the Wasm binary is built with whatever toolchain is found and it embeds the absolute
+//! paths of the machine it is built on, so the binaries built by different machines differ. A
+//! reproducible build, enabled by [`WasmBuilder::enable_reproducible_build`] or the
+//! `WASM_BUILD_REPRODUCIBLE` environment variable:
+//!
+//! - builds with the given toolchain, which needs to be installed with `rustup`,
+//! - remaps the absolute paths of the workspace and the cargo home,
+//! - compiles every crate in a single codegen unit,
+//! - keeps only the function names and the `runtime_version` and `runtime_apis` custom sections of
+//!   the compact binary, in a fixed order.
+//!
+//! Next to the binary a `.build-manifest.toml` is written, which records the toolchain, the
+//! features and the hashes of the inputs and of the binary. It is also copied to the
+//! `WASM_TARGET_DIRECTORY`. Given the manifest, anyone can build the binary again from within the
+//! workspace and check that it matches a code hash, e.g. the one proposed in a runtime upgrade:
+//!
+//! ```sh
+//! substrate-wasm-builder verify kitchensink_runtime.build-manifest.toml 0x1234...
+//! ```
+//!
+//! ## Auditing a runtime
+//!
+//! [`RuntimeAudit`] reports the size of a built runtime per crate and per function, and the host
+//! functions it imports. Comparing the audits of two runtimes with [`RuntimeAudit::diff`] flags the
+//! host functions that are newly required by the second one. The same is available from the
+//! command line:
+//!
+//! ```sh
+//! substrate-wasm-builder audit runtime.compact.compressed.wasm
+//! substrate-wasm-builder diff old.compact.compressed.wasm new.compact.compressed.wasm
+//! ```
+//!
+//! `diff` exits with an error if the new runtime requires host functions the old one did not, as
+//! nodes that only support the old runtime may not provide them.
+//!
+//! ## Metadata hash
+//!
+//! The `CheckMetadataHash` signed extension of `frame-metadata-hash-extension` needs the hash of
+//! the merkleized metadata of the runtime at compile time. With the `metadata-hash` feature of
+//! this crate enabled, [`WasmBuilder::enable_metadata_hash`] builds the runtime once, fetches its
+//! metadata by executing it, calculates the hash and builds the runtime a second time with the
+//! hash passed in the `RUNTIME_METADATA_HASH` environment variable.
+//!
 //! ## Prerequisites:
 //!
 //! Wasm builder requires the following prerequisites for building the Wasm binary:
@@ -117,12 +164,18 @@ use std::{
 };
 use version::Version;
 
+mod audit;
 mod builder;
+#[cfg(feature = "metadata-hash")]
+mod metadata_hash;
 mod prerequisites;
+mod reproducible;
 mod version;
 mod wasm_project;
 
+pub use audit::{FunctionSize, HostFunction, RuntimeAudit, RuntimeDiff};
 pub use builder::{WasmBuilder, WasmBuilderSelectProject};
+pub use reproducible::{verify_build, BuildManifest};
 
 /// Environment variable that tells us to skip building the wasm binary.
 const SKIP_BUILD_ENV: &str = "SKIP_WASM_BUILD";
@@ -158,6 +211,14 @@ const FORCE_WASM_BUILD_ENV: &str = "FORCE_WASM_BUILD";
 /// Environment variable that hints the workspace we are building.
 const WASM_BUILD_WORKSPACE_HINT: &str = "WASM_BUILD_WORKSPACE_HINT";
 
+/// Environment variable that enables a reproducible build with the given toolchain.
+const WASM_BUILD_REPRODUCIBLE_ENV: &str = "WASM_BUILD_REPRODUCIBLE";
+
+/// Environment variable that passes the metadata hash to the wasm build.
+///
+/// Read by the `CheckMetadataHash` signed extension at compile time.
+const METADATA_HASH_ENV: &str = "RUNTIME_METADATA_HASH";
+
 /// Write to the given `file` if the `content` is different.
 fn write_file_if_changed(file: impl AsRef<Path>, content: impl AsRef<str>) {
 	if fs::read_to_string(file.as_ref()).ok().as_deref() != Some(content.as_ref()) {
@@ -179,7 +240,13 @@ fn copy_file_if_changed(src: PathBuf, dst: PathBuf) {
 }
 
 /// Get a cargo command that should be used to invoke the compilation.
-fn get_cargo_command() -> CargoCommand {
+///
+/// A `pinned_toolchain` is always used, regardless of the environment.
+fn get_cargo_command(pinned_toolchain: Option<&str>) -> CargoCommand {
+	if let Some(toolchain) = pinned_toolchain {
+		return CargoCommand::new_with_args("rustup", &["run", toolchain, "cargo"])
+	}
+
 	let env_cargo =
 		CargoCommand::new(&env::var("CARGO").expect("`CARGO` env variable is always set by cargo"));
 	let default_cargo = CargoCommand::new("cargo");
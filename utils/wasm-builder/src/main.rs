@@ -0,0 +1,105 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Command line interface of the runtime audit, see [`substrate_wasm_builder::RuntimeAudit`], and
+//! of the verification of reproducible builds, see [`substrate_wasm_builder::verify_build`].
+
+use clap::Parser;
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	process,
+};
+use substrate_wasm_builder::{verify_build, BuildManifest, RuntimeAudit};
+
+/// Audit runtimes and verify reproducible runtime builds.
+#[derive(Debug, Parser)]
+#[command(name = "substrate-wasm-builder")]
+enum Command {
+	/// Report the size per crate and function and the imported host functions of a runtime.
+	Audit {
+		/// The runtime to audit.
+		runtime: PathBuf,
+
+		/// The number of the biggest functions to list.
+		#[arg(long, default_value_t = 20)]
+		functions: usize,
+	},
+
+	/// Compare two runtimes.
+	///
+	/// Fails if the new runtime requires host functions the old one did not.
+	Diff {
+		/// The runtime to compare against.
+		old_runtime: PathBuf,
+
+		/// The runtime to compare.
+		new_runtime: PathBuf,
+	},
+
+	/// Rebuild the runtime of a reproducible build and check that it has the given code hash.
+	Verify {
+		/// The build manifest written by the reproducible build.
+		build_manifest: PathBuf,
+
+		/// The expected code hash of the rebuilt runtime.
+		code_hash: String,
+	},
+}
+
+fn main() {
+	let result = match Command::parse() {
+		Command::Audit { runtime, functions } => audit(&runtime, functions),
+		Command::Diff { old_runtime, new_runtime } => diff(&old_runtime, &new_runtime),
+		Command::Verify { build_manifest, code_hash } => verify(&build_manifest, &code_hash),
+	};
+
+	if let Err(e) = result {
+		eprintln!("{}", e);
+		process::exit(1)
+	}
+}
+
+fn load(path: &Path) -> Result<RuntimeAudit, String> {
+	let blob = fs::read(path).map_err(|e| format!("Failed to read `{}`: {}", path.display(), e))?;
+	RuntimeAudit::from_blob(&blob).map_err(|e| format!("`{}`: {}", path.display(), e))
+}
+
+fn audit(path: &Path, functions: usize) -> Result<(), String> {
+	print!("{}", load(path)?.report(functions));
+	Ok(())
+}
+
+fn diff(old: &Path, new: &Path) -> Result<(), String> {
+	let diff = load(old)?.diff(&load(new)?);
+	print!("{}", diff);
+
+	if diff.requires_new_host_functions() {
+		Err("The new runtime requires host functions that nodes supporting the old one may not \
+			 provide."
+			.into())
+	} else {
+		Ok(())
+	}
+}
+
+fn verify(manifest: &Path, code_hash: &str) -> Result<(), String> {
+	let manifest = BuildManifest::read(manifest)?;
+	verify_build(&manifest, code_hash)?;
+	println!("The rebuilt `{}` runtime has the code hash {}.", manifest.package, code_hash);
+	Ok(())
+}
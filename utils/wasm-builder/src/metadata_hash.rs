@@ -0,0 +1,74 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generating the metadata hash of a runtime, see [`WasmBuilder::enable_metadata_hash`].
+//!
+//! [`WasmBuilder::enable_metadata_hash`]: crate::WasmBuilder::enable_metadata_hash
+
+use codec::{Decode, Encode};
+use frame_merkleized_metadata::{ExtraInfo, MerkleizedMetadata};
+use sc_executor::{RuntimeBlob, RuntimeVersion, WasmExecutor};
+use sp_state_machine::BasicExternalities;
+use std::{fs, path::Path};
+
+/// The information about the native token that is part of the metadata hash.
+pub(crate) struct TokenInfo {
+	pub(crate) symbol: String,
+	pub(crate) decimals: u8,
+}
+
+/// Generate the metadata hash of the runtime at `wasm`, hex encoded with `0x` prefix.
+///
+/// The metadata and the runtime version are fetched by calling into the runtime.
+pub(crate) fn generate_metadata_hash(wasm: &Path, token: &TokenInfo) -> String {
+	let wasm = fs::read(wasm).expect("The wasm binary was just built; qed");
+	let runtime_blob =
+		RuntimeBlob::uncompress_if_needed(&wasm).expect("The wasm binary is a valid runtime");
+
+	let executor = WasmExecutor::<sp_io::SubstrateHostFunctions>::builder()
+		.with_allow_missing_host_functions(true)
+		.build();
+	let mut ext = BasicExternalities::default();
+	let mut call = |method: &str, data: &[u8]| {
+		executor
+			.uncached_call(runtime_blob.clone(), &mut ext, true, method, data)
+			.unwrap_or_else(|e| panic!("Calling `{method}` of the runtime failed: {e}"))
+	};
+
+	let version = RuntimeVersion::decode(&mut &call("Core_version", &[])[..])
+		.expect("`Core_version` returns a `RuntimeVersion`");
+	let metadata =
+		Option::<Vec<u8>>::decode(&mut &call("Metadata_metadata_at_version", &15u32.encode())[..])
+			.expect("`Metadata_metadata_at_version` returns an optional metadata")
+			.expect("The metadata hash requires a runtime that supports metadata V15");
+	let metadata = frame_merkleized_metadata::decode_metadata(&metadata)
+		.unwrap_or_else(|e| panic!("Failed to decode the metadata: {e}"));
+
+	let extra_info = ExtraInfo {
+		spec_version: version.spec_version,
+		spec_name: version.spec_name.to_string(),
+		base58_prefix: frame_merkleized_metadata::ss58_prefix(&metadata).unwrap_or(42),
+		decimals: token.decimals,
+		token_symbol: token.symbol.clone(),
+	};
+
+	let hash = MerkleizedMetadata::new(metadata, extra_info)
+		.unwrap_or_else(|e| panic!("Failed to merkleize the metadata: {e}"))
+		.hash();
+
+	format!("0x{}", hash.iter().map(|b| format!("{b:02x}")).collect::<String>())
+}
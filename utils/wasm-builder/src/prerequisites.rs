@@ -34,8 +34,16 @@ fn print_error_message(message: &str) -> String {
 /// Checks that all prerequisites are installed.
 ///
 /// Returns the versioned cargo command on success.
-pub(crate) fn check() -> Result<CargoCommandVersioned, String> {
-	let cargo_command = crate::get_cargo_command();
+pub(crate) fn check(pinned_toolchain: Option<&str>) -> Result<CargoCommandVersioned, String> {
+	let cargo_command = crate::get_cargo_command(pinned_toolchain);
+
+	if let (Some(toolchain), None) = (pinned_toolchain, cargo_command.version()) {
+		return Err(print_error_message(&format!(
+			"Cannot compile the WASM runtime: the pinned toolchain `{}` is not installed!\n\
+			 Install it with `rustup toolchain install {} --target wasm32-unknown-unknown`.",
+			toolchain, toolchain,
+		)))
+	}
 
 	if !cargo_command.supports_substrate_wasm_env() {
 		return Err(print_error_message(
@@ -0,0 +1,338 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reproducible builds of the wasm binary.
+//!
+//! A reproducible build pins the toolchain, remaps the absolute paths that would otherwise end up
+//! in the binary and normalises the custom sections of the compact binary. It writes a
+//! [`BuildManifest`] next to the binary, which [`verify_build`] uses to build the binary again.
+
+use parity_wasm::elements::{deserialize_buffer, serialize_to_file, Module, Section};
+use std::{
+	env, fs,
+	path::{Path, PathBuf},
+	process::{self, Command},
+};
+use toml::value::Table;
+
+/// The custom sections kept by [`ReproducibleBuild::normalize`].
+const KEPT_CUSTOM_SECTIONS: [&str; 3] = ["name", "runtime_apis", "runtime_version"];
+
+/// The settings of a reproducible build.
+pub(crate) struct ReproducibleBuild {
+	/// The pinned toolchain, e.g. `1.74.0`.
+	toolchain: String,
+}
+
+impl ReproducibleBuild {
+	/// Returns the settings of a reproducible build if one was requested.
+	///
+	/// The toolchain set in [`crate::WASM_BUILD_REPRODUCIBLE_ENV`] takes precedence over the
+	/// `toolchain` configured in the `build.rs`.
+	pub(crate) fn requested(toolchain: Option<String>) -> Option<Self> {
+		env::var(crate::WASM_BUILD_REPRODUCIBLE_ENV)
+			.ok()
+			.filter(|toolchain| !toolchain.is_empty())
+			.or(toolchain)
+			.map(|toolchain| Self { toolchain })
+	}
+
+	/// The pinned toolchain.
+	pub(crate) fn toolchain(&self) -> &str {
+		&self.toolchain
+	}
+
+	/// Configures the cargo command building the wasm project.
+	///
+	/// Absolute paths are remapped, as they end up in panic messages, and the code is generated in
+	/// a single unit.
+	pub(crate) fn configure(
+		&self,
+		build_cmd: &mut Command,
+		rustflags: &mut String,
+		workspace_root: &Path,
+		wasm_workspace: &Path,
+	) {
+		// The last matching prefix wins, so the more specific paths come last.
+		let remaps = [
+			(cargo_home(), "/cargo"),
+			(Some(workspace_root.to_path_buf()), "/workspace"),
+			(Some(wasm_workspace.to_path_buf()), "/wbuild"),
+		];
+		for (from, to) in remaps.iter().filter_map(|(from, to)| Some((from.as_ref()?, to))) {
+			rustflags.push_str(&format!(" --remap-path-prefix={}={}", from.display(), to));
+		}
+
+		for profile in ["RELEASE", "PRODUCTION"] {
+			build_cmd.env(format!("CARGO_PROFILE_{}_CODEGEN_UNITS", profile), "1");
+		}
+		build_cmd.env_remove("CARGO_BUILD_RUSTC_WRAPPER").env_remove("RUSTC_WRAPPER");
+	}
+
+	/// Normalises the custom sections of the compact binary at `wasm`.
+	///
+	/// Only the function names and the sections read by the node are kept, in a fixed order at the
+	/// end of the binary. The `producers` section for example records the toolchain and is
+	/// therefore dropped.
+	pub(crate) fn normalize(&self, wasm: &Path) {
+		let blob = fs::read(wasm).expect("The compact wasm binary was just written; qed");
+		let mut module: Module =
+			deserialize_buffer(&blob).expect("The compact wasm binary is valid; qed");
+
+		let mut kept = Vec::new();
+		module.sections_mut().retain(|section| match section {
+			Section::Custom(custom) => {
+				if KEPT_CUSTOM_SECTIONS.contains(&custom.name()) {
+					kept.push(custom.clone());
+				}
+				false
+			},
+			_ => true,
+		});
+		kept.sort_by(|a, b| a.name().cmp(b.name()));
+
+		if let Some(duplicate) = kept.windows(2).find(|pair| pair[0].name() == pair[1].name()) {
+			// We use println! + exit instead of a panic in order to have a cleaner output.
+			println!(
+				"Found the `{}` wasm section multiple times, which makes the build ambiguous. \
+				 Ensure that only the runtime crate embeds it.",
+				duplicate[0].name(),
+			);
+			process::exit(1);
+		}
+
+		module.sections_mut().extend(kept.into_iter().map(Section::Custom));
+		serialize_to_file(wasm, module).expect("Writing the normalised wasm binary can not fail");
+	}
+}
+
+/// Returns the directory of the cargo registry and git checkouts.
+fn cargo_home() -> Option<PathBuf> {
+	env::var_os("CARGO_HOME")
+		.map(PathBuf::from)
+		.or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")))
+}
+
+/// The record of a reproducible build, written next to the wasm binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildManifest {
+	/// The name of the built crate.
+	pub package: String,
+	/// The pinned toolchain.
+	pub toolchain: String,
+	/// The full version of `rustc`.
+	pub rustc_version: String,
+	/// The cargo profile the wasm binary was built with.
+	pub profile: String,
+	/// The features enabled for the wasm build.
+	pub features: Vec<String>,
+	/// The compression applied to the binary.
+	pub compression: String,
+	/// The hashes of the inputs of the build, i.e. the `Cargo.lock` and the sources of every
+	/// local crate the binary depends on, by name.
+	pub inputs: Vec<(String, String)>,
+	/// The BLAKE2-256 hash of the binary, which is the hash of the on-chain `:code`.
+	pub code_hash: String,
+}
+
+impl BuildManifest {
+	/// Reads a manifest written by a reproducible build.
+	pub fn read(path: &Path) -> Result<Self, String> {
+		let content = fs::read_to_string(path)
+			.map_err(|e| format!("Failed to read `{}`: {}", path.display(), e))?;
+		let table: Table = toml::from_str(&content)
+			.map_err(|e| format!("`{}` is not a valid manifest: {}", path.display(), e))?;
+
+		let string = |key: &str| {
+			table
+				.get(key)
+				.and_then(|value| value.as_str())
+				.map(ToOwned::to_owned)
+				.ok_or_else(|| format!("`{}` is missing `{}`", path.display(), key))
+		};
+		let features = table
+			.get("features")
+			.and_then(|value| value.as_array())
+			.map(|features| {
+				features.iter().filter_map(|f| f.as_str()).map(ToOwned::to_owned).collect()
+			})
+			.unwrap_or_default();
+		let inputs = table
+			.get("inputs")
+			.and_then(|value| value.as_table())
+			.map(|inputs| {
+				inputs
+					.iter()
+					.filter_map(|(name, hash)| Some((name.clone(), hash.as_str()?.to_owned())))
+					.collect()
+			})
+			.unwrap_or_default();
+
+		Ok(Self {
+			package: string("package")?,
+			toolchain: string("toolchain")?,
+			rustc_version: string("rustc-version")?,
+			profile: string("profile")?,
+			features,
+			compression: string("compression")?,
+			inputs,
+			code_hash: string("code-hash")?,
+		})
+	}
+
+	/// Writes the manifest to `path`.
+	pub(crate) fn write(&self, path: &Path) {
+		let mut table = Table::new();
+		table.insert("package".into(), self.package.clone().into());
+		table.insert("toolchain".into(), self.toolchain.clone().into());
+		table.insert("rustc-version".into(), self.rustc_version.clone().into());
+		table.insert("profile".into(), self.profile.clone().into());
+		table.insert("features".into(), self.features.clone().into());
+		table.insert("compression".into(), self.compression.clone().into());
+		table.insert("code-hash".into(), self.code_hash.clone().into());
+		table.insert(
+			"inputs".into(),
+			self.inputs
+				.iter()
+				.map(|(name, hash)| (name.clone(), hash.clone().into()))
+				.collect::<Table>()
+				.into(),
+		);
+
+		crate::write_file_if_changed(
+			path,
+			toml::to_string_pretty(&table).expect("The build manifest is valid toml; qed"),
+		);
+	}
+
+	/// The name of the binary copied to the `WASM_TARGET_DIRECTORY`.
+	fn target_file_name(&self) -> String {
+		format!("{}.wasm", self.package.replace('-', "_"))
+	}
+}
+
+/// Returns the hex encoded BLAKE2-256 hash of `data`.
+pub(crate) fn hash(data: &[u8]) -> String {
+	let hash = sp_core_hashing::blake2_256(data);
+	format!("0x{}", hash.iter().map(|byte| format!("{:02x}", byte)).collect::<String>())
+}
+
+/// Returns the hash of the given source `files`, which must be sorted, relative to `root`.
+pub(crate) fn hash_sources(root: &Path, files: &[PathBuf]) -> String {
+	let mut data = Vec::new();
+	for file in files {
+		let relative = file.strip_prefix(root).unwrap_or(file);
+		data.extend(relative.display().to_string().as_bytes());
+		data.push(0);
+		data.extend(hash(&fs::read(file).unwrap_or_default()).as_bytes());
+	}
+	hash(&data)
+}
+
+/// Builds the crate of `manifest` again and checks that its binary has the given `code_hash`.
+///
+/// Must be called from within the workspace of the crate. The crate is built in release mode with
+/// the wasm binary built reproducibly as recorded in the manifest.
+pub fn verify_build(manifest: &BuildManifest, code_hash: &str) -> Result<(), String> {
+	let target_dir = tempfile::tempdir()
+		.map_err(|e| format!("Failed to create a temporary directory: {}", e))?;
+
+	// `runtime-wasm` is only enabled by us for the wasm build.
+	let features = manifest
+		.features
+		.iter()
+		.filter(|feature| *feature != "runtime-wasm")
+		.cloned()
+		.collect::<Vec<_>>();
+	let mut build_cmd = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+	build_cmd
+		.args(["build", "--locked", "--release", "--package", &manifest.package])
+		.env(crate::WASM_BUILD_REPRODUCIBLE_ENV, &manifest.toolchain)
+		.env(crate::WASM_BUILD_TYPE_ENV, &manifest.profile)
+		.env(crate::WASM_TARGET_DIRECTORY, target_dir.path())
+		.env(crate::FORCE_WASM_BUILD_ENV, format!("{:?}", std::time::SystemTime::now()))
+		.env_remove(crate::SKIP_BUILD_ENV);
+	if !features.is_empty() {
+		build_cmd.arg("--features").arg(features.join(","));
+	}
+
+	println!("Executing build command: {:?}", build_cmd);
+	match build_cmd.status() {
+		Ok(status) if status.success() => {},
+		Ok(status) => return Err(format!("The build failed: {}", status)),
+		Err(e) => return Err(format!("Failed to run cargo: {}", e)),
+	}
+
+	let blob_path = target_dir.path().join(manifest.target_file_name());
+	let blob = fs::read(&blob_path)
+		.map_err(|e| format!("Failed to read `{}`: {}", blob_path.display(), e))?;
+	let built_hash = hash(&blob);
+
+	if built_hash.eq_ignore_ascii_case(&normalize_hash(code_hash)) {
+		Ok(())
+	} else {
+		Err(format!(
+			"The rebuilt binary has the code hash {}, expected {} (recorded in the manifest: {})",
+			built_hash, code_hash, manifest.code_hash,
+		))
+	}
+}
+
+/// Adds the `0x` prefix to `hash` if it is missing.
+fn normalize_hash(hash: &str) -> String {
+	if hash.starts_with("0x") {
+		hash.to_owned()
+	} else {
+		format!("0x{}", hash)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn build_manifest_roundtrips() {
+		let manifest = BuildManifest {
+			package: "kitchensink-runtime".into(),
+			toolchain: "1.74.0".into(),
+			rustc_version: "rustc 1.74.0 (79e9716c9 2023-11-13)".into(),
+			profile: "production".into(),
+			features: vec!["runtime-wasm".into()],
+			compression: "zstd 1.5.5, level 3".into(),
+			inputs: vec![
+				("Cargo.lock".into(), hash(b"lock")),
+				("kitchensink-runtime".into(), hash(b"sources")),
+			],
+			code_hash: hash(b"code"),
+		};
+
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("manifest.toml");
+		manifest.write(&path);
+
+		assert_eq!(BuildManifest::read(&path).unwrap(), manifest);
+		assert_eq!(manifest.target_file_name(), "kitchensink_runtime.wasm");
+	}
+
+	#[test]
+	fn hashes_are_normalized() {
+		assert_eq!(normalize_hash("abcd"), "0xabcd");
+		assert_eq!(normalize_hash("0xabcd"), "0xabcd");
+		assert_eq!(hash(b"").len(), 66);
+	}
+}
@@ -15,7 +15,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{write_file_if_changed, CargoCommandVersioned, OFFLINE};
+use crate::{
+	reproducible::{self, BuildManifest, ReproducibleBuild},
+	write_file_if_changed, CargoCommandVersioned, OFFLINE,
+};
 
 use build_helper::rerun_if_changed;
 use cargo_metadata::{CargoOpt, Metadata, MetadataCommand};
@@ -118,13 +121,15 @@ pub(crate) fn create_and_compile(
 	features_to_enable: Vec<String>,
 	wasm_binary_name: Option<String>,
 	check_for_runtime_version_section: bool,
+	reproducible: Option<ReproducibleBuild>,
+	#[cfg(feature = "metadata-hash")] metadata_hash: Option<crate::metadata_hash::TokenInfo>,
 ) -> (Option<WasmBinary>, WasmBinaryBloaty) {
 	let wasm_workspace_root = get_wasm_workspace_root();
 	let wasm_workspace = wasm_workspace_root.join("wbuild");
 
 	let crate_metadata = crate_metadata(project_cargo_toml);
 
-	let project = create_project(
+	let (project, enabled_features) = create_project(
 		project_cargo_toml,
 		&wasm_workspace,
 		&crate_metadata,
@@ -132,9 +137,30 @@ pub(crate) fn create_and_compile(
 		features_to_enable,
 	);
 
-	let profile = build_project(&project, default_rustflags, cargo_cmd);
-	let (wasm_binary, wasm_binary_compressed, bloaty) =
-		compact_wasm_file(&project, profile, project_cargo_toml, wasm_binary_name);
+	let reproducible = reproducible.as_ref().map(|reproducible| {
+		(reproducible, crate_metadata.workspace_root.as_std_path(), wasm_workspace.as_path())
+	});
+	let profile = build_project(&project, default_rustflags, &cargo_cmd, reproducible, None);
+
+	// The first build only exists to fetch the metadata, the second one embeds its hash.
+	#[cfg(feature = "metadata-hash")]
+	let profile = match metadata_hash {
+		Some(token) => {
+			let hash = crate::metadata_hash::generate_metadata_hash(
+				&built_wasm_path(&project, &profile, project_cargo_toml),
+				&token,
+			);
+			build_project(&project, default_rustflags, &cargo_cmd, reproducible, Some(&hash))
+		},
+		None => profile,
+	};
+	let (wasm_binary, wasm_binary_compressed, bloaty) = compact_wasm_file(
+		&project,
+		&profile,
+		project_cargo_toml,
+		wasm_binary_name.clone(),
+		reproducible.map(|(reproducible, ..)| reproducible),
+	);
 
 	if check_for_runtime_version_section {
 		ensure_runtime_version_wasm_section_exists(bloaty.wasm_binary_bloaty_path());
@@ -150,6 +176,31 @@ pub(crate) fn create_and_compile(
 
 	let final_wasm_binary = wasm_binary_compressed.or(wasm_binary);
 
+	if let (Some((reproducible, ..)), Some(final_wasm_binary)) =
+		(reproducible, final_wasm_binary.as_ref())
+	{
+		let manifest = BuildManifest {
+			package: get_crate_name(project_cargo_toml),
+			toolchain: reproducible.toolchain().into(),
+			rustc_version: cargo_cmd.rustc_version().into(),
+			profile: profile.directory().into(),
+			features: enabled_features,
+			compression: format!("zstd {}, level 3", sp_maybe_compressed_blob::zstd_version()),
+			inputs: build_inputs(project_cargo_toml, &project, &wasm_workspace),
+			code_hash: reproducible::hash(
+				&fs::read(final_wasm_binary.wasm_binary_path())
+					.expect("The wasm binary was just written; qed"),
+			),
+		};
+		let out_name = wasm_binary_name.unwrap_or_else(|| get_wasm_binary_name(project_cargo_toml));
+		let manifest_path = project.join(format!("{}.build-manifest.toml", out_name));
+		manifest.write(&manifest_path);
+		copy_to_target_directory(
+			&manifest_path,
+			&format!("{}.build-manifest.toml", get_wasm_binary_name(project_cargo_toml)),
+		);
+	}
+
 	generate_rerun_if_changed_instructions(
 		project_cargo_toml,
 		&project,
@@ -465,9 +516,10 @@ fn project_enabled_features(
 			// We don't want to enable the `std`/`default` feature for the wasm build and
 			// we need to check if the feature is enabled by checking the env variable.
 			*f != "std" &&
-				*f != "default" && env::var(format!("CARGO_FEATURE_{}", feature_env))
-				.map(|v| v == "1")
-				.unwrap_or_default()
+				*f != "default" &&
+				env::var(format!("CARGO_FEATURE_{}", feature_env))
+					.map(|v| v == "1")
+					.unwrap_or_default()
 		})
 		.map(|d| d.0.clone())
 		.collect::<Vec<_>>();
@@ -491,14 +543,14 @@ fn has_runtime_wasm_feature_declared(
 ///
 /// # Returns
 ///
-/// The path to the created wasm project.
+/// The path to the created wasm project and the sorted features enabled for it.
 fn create_project(
 	project_cargo_toml: &Path,
 	wasm_workspace: &Path,
 	crate_metadata: &Metadata,
 	workspace_root_path: &Path,
 	features_to_enable: Vec<String>,
-) -> PathBuf {
+) -> (PathBuf, Vec<String>) {
 	let crate_name = get_crate_name(project_cargo_toml);
 	let crate_path = project_cargo_toml.parent().expect("Parent path exists; qed");
 	let wasm_binary = get_wasm_binary_name(project_cargo_toml);
@@ -516,6 +568,8 @@ fn create_project(
 
 	let mut enabled_features = enabled_features.into_iter().collect::<HashSet<_>>();
 	enabled_features.extend(features_to_enable.into_iter());
+	let mut enabled_features = enabled_features.into_iter().collect::<Vec<_>>();
+	enabled_features.sort();
 
 	create_project_cargo_toml(
 		&wasm_project_folder,
@@ -523,7 +577,7 @@ fn create_project(
 		&crate_name,
 		crate_path,
 		&wasm_binary,
-		enabled_features.into_iter(),
+		enabled_features.iter().cloned(),
 	);
 
 	write_file_if_changed(
@@ -536,7 +590,7 @@ fn create_project(
 		crate::copy_file_if_changed(crate_lock_file, wasm_project_folder.join("Cargo.lock"));
 	}
 
-	wasm_project_folder
+	(wasm_project_folder, enabled_features)
 }
 
 /// The cargo profile that is used to build the wasm project.
@@ -652,20 +706,31 @@ fn offline_build() -> bool {
 }
 
 /// Build the project to create the WASM binary.
+///
+/// `reproducible` - The settings of a reproducible build together with the workspace root and the
+/// wasm workspace, whose paths are remapped.
+///
+/// `metadata_hash` - The metadata hash that is passed to the build.
 fn build_project(
 	project: &Path,
 	default_rustflags: &str,
-	cargo_cmd: CargoCommandVersioned,
+	cargo_cmd: &CargoCommandVersioned,
+	reproducible: Option<(&ReproducibleBuild, &Path, &Path)>,
+	metadata_hash: Option<&str>,
 ) -> Profile {
 	let manifest_path = project.join("Cargo.toml");
 	let mut build_cmd = cargo_cmd.command();
 
-	let rustflags = format!(
+	let mut rustflags = format!(
 		"-C target-cpu=mvp -C target-feature=-sign-ext -C link-arg=--export-table {} {}",
 		default_rustflags,
 		env::var(crate::WASM_BUILD_RUSTFLAGS_ENV).unwrap_or_default(),
 	);
 
+	if let Some((reproducible, workspace_root, wasm_workspace)) = reproducible {
+		reproducible.configure(&mut build_cmd, &mut rustflags, workspace_root, wasm_workspace);
+	}
+
 	build_cmd
 		.args(&["rustc", "--target=wasm32-unknown-unknown"])
 		.arg(format!("--manifest-path={}", manifest_path.display()))
@@ -681,6 +746,10 @@ fn build_project(
 		// We don't want to call ourselves recursively
 		.env(crate::SKIP_BUILD_ENV, "");
 
+	if let Some(hash) = metadata_hash {
+		build_cmd.env(crate::METADATA_HASH_ENV, hash);
+	}
+
 	if super::color_output_enabled() {
 		build_cmd.arg("--color=always");
 	}
@@ -704,19 +773,26 @@ fn build_project(
 	}
 }
 
+/// The path of the WASM binary as written by the compiler.
+fn built_wasm_path(project: &Path, profile: &Profile, cargo_manifest: &Path) -> PathBuf {
+	project
+		.join("target/wasm32-unknown-unknown")
+		.join(profile.directory())
+		.join(format!("{}.wasm", get_wasm_binary_name(cargo_manifest)))
+}
+
 /// Compact the WASM binary using `wasm-gc` and compress it using zstd.
+///
+/// The compact binary is normalised before compressing it when building reproducibly.
 fn compact_wasm_file(
 	project: &Path,
-	profile: Profile,
+	profile: &Profile,
 	cargo_manifest: &Path,
 	out_name: Option<String>,
+	reproducible: Option<&ReproducibleBuild>,
 ) -> (Option<WasmBinary>, Option<WasmBinary>, WasmBinaryBloaty) {
-	let default_out_name = get_wasm_binary_name(cargo_manifest);
-	let out_name = out_name.unwrap_or_else(|| default_out_name.clone());
-	let in_path = project
-		.join("target/wasm32-unknown-unknown")
-		.join(profile.directory())
-		.join(format!("{}.wasm", default_out_name));
+	let out_name = out_name.unwrap_or_else(|| get_wasm_binary_name(cargo_manifest));
+	let in_path = built_wasm_path(project, profile, cargo_manifest);
 
 	let (wasm_compact_path, wasm_compact_compressed_path) = if profile.wants_compact() {
 		let wasm_compact_path = project.join(format!("{}.compact.wasm", out_name,));
@@ -727,6 +803,10 @@ fn compact_wasm_file(
 			.run(&in_path, &wasm_compact_path)
 			.expect("Failed to compact generated WASM binary.");
 
+		if let Some(reproducible) = reproducible {
+			reproducible.normalize(&wasm_compact_path);
+		}
+
 		let wasm_compact_compressed_path =
 			project.join(format!("{}.compact.compressed.wasm", out_name));
 		if compress_wasm(&wasm_compact_path, &wasm_compact_compressed_path) {
@@ -830,6 +910,65 @@ fn generate_rerun_if_changed_instructions(
 		.exec()
 		.expect("`cargo metadata` can not fail!");
 
+	// Make sure that if any file/folder of a dependency change, we need to rerun the `build.rs`
+	dependency_packages(&metadata, cargo_manifest, wasm_workspace)
+		.iter()
+		.for_each(package_rerun_if_changed);
+
+	compressed_or_compact_wasm.map(|w| rerun_if_changed(w.wasm_binary_path()));
+	rerun_if_changed(bloaty_wasm.wasm_binary_bloaty_path());
+
+	// Register our env variables
+	println!("cargo:rerun-if-env-changed={}", crate::SKIP_BUILD_ENV);
+	println!("cargo:rerun-if-env-changed={}", crate::WASM_BUILD_TYPE_ENV);
+	println!("cargo:rerun-if-env-changed={}", crate::WASM_BUILD_RUSTFLAGS_ENV);
+	println!("cargo:rerun-if-env-changed={}", crate::WASM_TARGET_DIRECTORY);
+	println!("cargo:rerun-if-env-changed={}", crate::WASM_BUILD_TOOLCHAIN);
+	println!("cargo:rerun-if-env-changed={}", crate::WASM_BUILD_REPRODUCIBLE_ENV);
+}
+
+/// Returns the hashes of the inputs of the build for the [`BuildManifest`].
+///
+/// These are the `Cargo.lock` and the sources of every local crate the wasm binary depends on.
+fn build_inputs(
+	cargo_manifest: &Path,
+	project_folder: &Path,
+	wasm_workspace: &Path,
+) -> Vec<(String, String)> {
+	let metadata = create_metadata_command(project_folder.join("Cargo.toml"))
+		.exec()
+		.expect("`cargo metadata` can not fail!");
+	let workspace_root = metadata.workspace_root.as_std_path();
+
+	let mut inputs = find_cargo_lock(cargo_manifest)
+		.and_then(|cargo_lock| fs::read(cargo_lock).ok())
+		.map(|cargo_lock| ("Cargo.lock".to_owned(), reproducible::hash(&cargo_lock)))
+		.into_iter()
+		.collect::<Vec<_>>();
+
+	// Crates from a registry or a git repository are pinned by the `Cargo.lock`.
+	let mut packages = dependency_packages(&metadata, cargo_manifest, wasm_workspace)
+		.into_iter()
+		.filter(|package| package.source.is_none())
+		.map(|package| {
+			let mut files =
+				package_files(&package).filter(|file| file.is_file()).collect::<Vec<_>>();
+			files.sort();
+			(package.name.clone(), reproducible::hash_sources(workspace_root, &files))
+		})
+		.collect::<Vec<_>>();
+	packages.sort();
+	inputs.extend(packages);
+
+	inputs
+}
+
+/// Returns the crate at `cargo_manifest` and all the packages it depends on.
+fn dependency_packages<'a>(
+	metadata: &'a Metadata,
+	cargo_manifest: &Path,
+	wasm_workspace: &Path,
+) -> HashSet<DeduplicatePackage<'a>> {
 	let package = metadata
 		.packages
 		.iter()
@@ -865,30 +1004,24 @@ fn generate_rerun_if_changed_instructions(
 		}
 	}
 
-	// Make sure that if any file/folder of a dependency change, we need to rerun the `build.rs`
-	packages.iter().for_each(package_rerun_if_changed);
-
-	compressed_or_compact_wasm.map(|w| rerun_if_changed(w.wasm_binary_path()));
-	rerun_if_changed(bloaty_wasm.wasm_binary_bloaty_path());
-
-	// Register our env variables
-	println!("cargo:rerun-if-env-changed={}", crate::SKIP_BUILD_ENV);
-	println!("cargo:rerun-if-env-changed={}", crate::WASM_BUILD_TYPE_ENV);
-	println!("cargo:rerun-if-env-changed={}", crate::WASM_BUILD_RUSTFLAGS_ENV);
-	println!("cargo:rerun-if-env-changed={}", crate::WASM_TARGET_DIRECTORY);
-	println!("cargo:rerun-if-env-changed={}", crate::WASM_BUILD_TOOLCHAIN);
+	packages
 }
 
 /// Track files and paths related to the given package to rerun `build.rs` on any relevant change.
 fn package_rerun_if_changed(package: &DeduplicatePackage) {
-	let mut manifest_path = package.manifest_path.clone();
+	package_files(package).for_each(rerun_if_changed);
+}
+
+/// Returns the source files and directories of the given package.
+fn package_files(package: &cargo_metadata::Package) -> impl Iterator<Item = PathBuf> {
+	let mut manifest_path = package.manifest_path.clone().into_std_path_buf();
 	if manifest_path.ends_with("Cargo.toml") {
 		manifest_path.pop();
 	}
 
-	WalkDir::new(&manifest_path)
+	WalkDir::new(manifest_path.clone())
 		.into_iter()
-		.filter_entry(|p| {
+		.filter_entry(move |p| {
 			// Ignore this entry if it is a directory that contains a `Cargo.toml` that is not the
 			// `Cargo.toml` related to the current package. This is done to ignore sub-crates of a
 			// crate. If such a sub-crate is a dependency, it will be processed independently
@@ -899,12 +1032,20 @@ fn package_rerun_if_changed(package: &DeduplicatePackage) {
 		.filter(|p| {
 			p.is_dir() || p.extension().map(|e| e == "rs" || e == "toml").unwrap_or_default()
 		})
-		.for_each(rerun_if_changed);
 }
 
 /// Copy the WASM binary to the target directory set in `WASM_TARGET_DIRECTORY` environment
 /// variable. If the variable is not set, this is a no-op.
 fn copy_wasm_to_target_directory(cargo_manifest: &Path, wasm_binary: &WasmBinary) {
+	copy_to_target_directory(
+		wasm_binary.wasm_binary_path(),
+		&format!("{}.wasm", get_wasm_binary_name(cargo_manifest)),
+	);
+}
+
+/// Copy the file at `path` as `file_name` to the target directory set in `WASM_TARGET_DIRECTORY`
+/// environment variable. If the variable is not set, this is a no-op.
+fn copy_to_target_directory(path: &Path, file_name: &str) {
 	let target_dir = match env::var(crate::WASM_TARGET_DIRECTORY) {
 		Ok(path) => PathBuf::from(path),
 		Err(_) => return,
@@ -922,9 +1063,6 @@ fn copy_wasm_to_target_directory(cargo_manifest: &Path, wasm_binary: &WasmBinary
 
 	fs::create_dir_all(&target_dir).expect("Creates `WASM_TARGET_DIRECTORY`.");
 
-	fs::copy(
-		wasm_binary.wasm_binary_path(),
-		target_dir.join(format!("{}.wasm", get_wasm_binary_name(cargo_manifest))),
-	)
-	.expect("Copies WASM binary to `WASM_TARGET_DIRECTORY`.");
+	fs::copy(path, target_dir.join(file_name))
+		.expect("Copies WASM binary to `WASM_TARGET_DIRECTORY`.");
 }